@@ -0,0 +1,517 @@
+// src/address.rs
+//! Monero addresses in their base58 string form.
+//!
+//! A standard address is `varint(prefix) || spend_pub || view_pub` followed
+//! by the first four bytes of its Keccak-256, all in Monero's block base58
+//! ([`crate::encoding::base58`]). The prefix encodes both the [`Network`]
+//! and the address kind; mainnet standard addresses start with `4`.
+//! Integrated addresses use their own prefix and append an 8-byte
+//! [`PaymentId`] after the keys, so a receiver can tell payments apart
+//! without handing out a new address each time. Subaddresses (starting with
+//! `8` on mainnet) carry keys derived per `(major, minor)` index instead.
+use std::{fmt, str::FromStr};
+
+use curve25519_dalek::{EdwardsPoint, edwards::CompressedEdwardsY};
+use rand::{RngCore, rngs::OsRng};
+
+use crate::crypto::{
+    hash::keccak256,
+    keys::{KeyDerivation, WalletKeys},
+};
+use crate::encoding::{
+    base58::{self, Base58Error},
+    varint,
+};
+use crate::network::Network;
+
+const CHECKSUM_LEN: usize = 4;
+/// Domain byte hashed after the derivation for the payment ID keystream
+const ENCRYPTED_PAYMENT_ID_TAIL: u8 = 0x8d;
+
+/// Why an address string was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressError {
+    /// Not valid Monero base58
+    Base58(Base58Error),
+    /// Decoded to the wrong number of bytes for its prefix
+    InvalidLength(usize),
+    /// Last four bytes don't match the Keccak checksum (typo or truncation)
+    InvalidChecksum,
+    /// Prefix belongs to no known network, or not the one asked for
+    WrongNetwork(u64),
+    /// A public key isn't a valid curve point
+    InvalidKey,
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::Base58(e) => write!(f, "invalid address encoding: {e}"),
+            AddressError::InvalidLength(n) => write!(f, "invalid address length: {n} bytes"),
+            AddressError::InvalidChecksum => write!(f, "address checksum mismatch"),
+            AddressError::WrongNetwork(p) => write!(f, "unexpected address prefix {p}"),
+            AddressError::InvalidKey => write!(f, "address contains an invalid public key"),
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+impl From<Base58Error> for AddressError {
+    fn from(e: Base58Error) -> Self {
+        AddressError::Base58(e)
+    }
+}
+
+/// Short payment ID carried by integrated addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PaymentId(pub [u8; 8]);
+
+impl PaymentId {
+    /// Fresh ID from the OS RNG; 64 bits makes collisions between a
+    /// receiver's customers negligible
+    pub fn random() -> Self {
+        let mut id = [0u8; 8];
+        OsRng.fill_bytes(&mut id);
+        Self(id)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 8] {
+        &self.0
+    }
+
+    /// Encrypt for a transaction's tx_extra nonce
+    ///
+    /// XORs the ID with the first 8 bytes of `Keccak256(derivation || 0x8d)`.
+    /// The sender uses `8·r·A` for the recipient's view key `A`; the
+    /// recipient recomputes the same derivation as `8·a·R`.
+    pub fn encrypt(&self, derivation: &KeyDerivation) -> [u8; 8] {
+        let mut data = derivation.as_bytes().to_vec();
+        data.push(ENCRYPTED_PAYMENT_ID_TAIL);
+        let keystream = keccak256(&data);
+        std::array::from_fn(|i| self.0[i] ^ keystream.as_bytes()[i])
+    }
+
+    /// Inverse of [`PaymentId::encrypt`]
+    ///
+    /// Any 8 bytes decrypt to some ID; a wrong derivation just yields
+    /// garbage, so callers match the result against IDs they handed out.
+    pub fn decrypt(encrypted: &[u8; 8], derivation: &KeyDerivation) -> Self {
+        Self(PaymentId(*encrypted).encrypt(derivation))
+    }
+}
+
+impl fmt::Display for PaymentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+/// Parse from 16 hex characters
+impl FromStr for PaymentId {
+    type Err = hex::FromHexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut id = [0u8; 8];
+        hex::decode_to_slice(s, &mut id)?;
+        Ok(Self(id))
+    }
+}
+
+/// Which form an address takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressKind {
+    Standard,
+    Integrated(PaymentId),
+    Subaddress,
+}
+
+impl AddressKind {
+    fn prefix(&self, network: Network) -> u64 {
+        match self {
+            AddressKind::Standard => network.address_prefix(),
+            AddressKind::Integrated(_) => network.integrated_prefix(),
+            AddressKind::Subaddress => network.subaddress_prefix(),
+        }
+    }
+}
+
+/// Network and payment ID length a prefix stands for
+fn lookup_prefix(prefix: u64) -> Option<(Network, usize)> {
+    Network::ALL.into_iter().find_map(|network| {
+        if prefix == network.address_prefix() || prefix == network.subaddress_prefix() {
+            Some((network, 0))
+        } else if prefix == network.integrated_prefix() {
+            Some((network, 8))
+        } else {
+            None
+        }
+    })
+}
+
+/// Public spend and view keys a payment is sent to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Address {
+    network: Network,
+    kind: AddressKind,
+    spend: EdwardsPoint,
+    view: EdwardsPoint,
+}
+
+impl Address {
+    /// Standard address for the given public keys
+    pub fn new(network: Network, spend: EdwardsPoint, view: EdwardsPoint) -> Self {
+        Self {
+            network,
+            kind: AddressKind::Standard,
+            spend,
+            view,
+        }
+    }
+
+    /// Primary address of a wallet
+    pub fn from_keys(keys: &WalletKeys, network: Network) -> Self {
+        Self::new(network, *keys.spend_public(), *keys.view_public())
+    }
+
+    /// Subaddress `(major, minor)` of a wallet
+    ///
+    /// Index `(0, 0)` is the wallet's primary standard address, as in the
+    /// reference wallet.
+    pub fn subaddress(keys: &WalletKeys, network: Network, major: u32, minor: u32) -> Self {
+        if major == 0 && minor == 0 {
+            return Self::from_keys(keys, network);
+        }
+        Self {
+            network,
+            kind: AddressKind::Subaddress,
+            spend: keys.subaddress_spend_public(major, minor),
+            view: keys.subaddress_view_public(major, minor),
+        }
+    }
+
+    /// The same keys with `payment_id` attached; None for a subaddress,
+    /// whose keys don't form a standard or integrated address
+    pub fn integrated(&self, payment_id: PaymentId) -> Option<Self> {
+        match self.kind {
+            AddressKind::Subaddress => None,
+            _ => Some(Self {
+                kind: AddressKind::Integrated(payment_id),
+                ..*self
+            }),
+        }
+    }
+
+    /// The same keys without a payment ID; None for a subaddress, whose
+    /// keys don't form a standard address
+    pub fn standard(&self) -> Option<Self> {
+        match self.kind {
+            AddressKind::Subaddress => None,
+            _ => Some(Self::new(self.network, self.spend, self.view)),
+        }
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn kind(&self) -> AddressKind {
+        self.kind
+    }
+
+    /// Payment ID of an integrated address
+    pub fn payment_id(&self) -> Option<PaymentId> {
+        match self.kind {
+            AddressKind::Integrated(id) => Some(id),
+            AddressKind::Standard | AddressKind::Subaddress => None,
+        }
+    }
+
+    pub fn spend_public(&self) -> &EdwardsPoint {
+        &self.spend
+    }
+
+    pub fn view_public(&self) -> &EdwardsPoint {
+        &self.view
+    }
+
+    /// Raw `prefix || spend || view [|| payment id] || checksum` bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(1 + 64 + 8 + CHECKSUM_LEN);
+        varint::write(&mut data, self.kind.prefix(self.network));
+        data.extend_from_slice(self.spend.compress().as_bytes());
+        data.extend_from_slice(self.view.compress().as_bytes());
+        if let AddressKind::Integrated(id) = &self.kind {
+            data.extend_from_slice(id.as_bytes());
+        }
+        let checksum = keccak256(&data);
+        data.extend_from_slice(&checksum.as_bytes()[..CHECKSUM_LEN]);
+        data
+    }
+
+    /// Parse raw bytes as produced by [`Address::to_bytes`]
+    pub fn from_bytes(data: &[u8]) -> Result<Self, AddressError> {
+        let (prefix, used) =
+            varint::read(data).map_err(|_| AddressError::InvalidLength(data.len()))?;
+        let known = lookup_prefix(prefix);
+        let expected = used + 64 + known.map_or(0, |(_, len)| len) + CHECKSUM_LEN;
+        if (known.is_some() && data.len() != expected) || data.len() < CHECKSUM_LEN {
+            return Err(AddressError::InvalidLength(data.len()));
+        }
+        let (body, checksum) = data.split_at(data.len() - CHECKSUM_LEN);
+        if keccak256(body).as_bytes()[..CHECKSUM_LEN] != *checksum {
+            return Err(AddressError::InvalidChecksum);
+        }
+        // only trust the prefix once the checksum says it isn't a typo
+        let Some((network, _)) = known else {
+            return Err(AddressError::WrongNetwork(prefix));
+        };
+
+        let key = |at: usize| {
+            CompressedEdwardsY(body[at..at + 32].try_into().unwrap())
+                .decompress()
+                .ok_or(AddressError::InvalidKey)
+        };
+        let kind = if prefix == network.integrated_prefix() {
+            AddressKind::Integrated(PaymentId(body[used + 64..].try_into().unwrap()))
+        } else if prefix == network.subaddress_prefix() {
+            AddressKind::Subaddress
+        } else {
+            AddressKind::Standard
+        };
+        Ok(Self {
+            network,
+            kind,
+            spend: key(used)?,
+            view: key(used + 32)?,
+        })
+    }
+}
+
+impl Address {
+    /// Parse `s`, rejecting addresses of any network but `network`
+    ///
+    /// Plain [`FromStr`] accepts all three networks; wallets should use this
+    /// so a testnet address can't be pasted into a mainnet send.
+    pub fn parse_for(s: &str, network: Network) -> Result<Self, AddressError> {
+        let address: Self = s.parse()?;
+        if address.network != network {
+            return Err(AddressError::WrongNetwork(
+                address.kind.prefix(address.network),
+            ));
+        }
+        Ok(address)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&base58::encode(&self.to_bytes()))
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bytes(&base58::decode(s)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mnemonic::monero::{Language, to_wallet_keys};
+
+    // Wallet used by Monero's tests/functional_tests/transfer.py
+    const SEED: &str = "velvet lymph giddy number token physics poetry unquoted nibs useful \
+                        sabotage limits benches lifestyle eden nitrogen anvil fewest avoid \
+                        batch vials washing fences goat unquoted";
+    const ADDRESS: &str = "42ey1afDFnn4886T7196doS9GPMzexD9gXpsZJDwVjeRVdFCSoHnv7KPbBeGpzJBzHRCAs9\
+                           UxqeoyFQMYbqSWYTfJJQAWDm";
+
+    #[test]
+    fn test_reference_address() {
+        let keys = to_wallet_keys(SEED, Language::English).unwrap();
+        let address = Address::from_keys(&keys, Network::Mainnet);
+        assert_eq!(address.to_string(), ADDRESS);
+        assert_eq!(ADDRESS.parse::<Address>().unwrap(), address);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let bytes = base58::decode(ADDRESS).unwrap();
+
+        let mut typo = bytes.clone();
+        typo[10] ^= 1;
+        let typo = base58::encode(&typo);
+        assert_eq!(typo.parse::<Address>(), Err(AddressError::InvalidChecksum));
+
+        // a correctly checksummed address for an unknown prefix
+        let mut unknown = bytes[..65].to_vec();
+        unknown[0] = 17;
+        let checksum = keccak256(&unknown);
+        unknown.extend_from_slice(&checksum.as_bytes()[..4]);
+        assert_eq!(
+            base58::encode(&unknown).parse::<Address>(),
+            Err(AddressError::WrongNetwork(17))
+        );
+
+        assert_eq!(
+            base58::encode(&bytes[..60]).parse::<Address>(),
+            Err(AddressError::InvalidLength(60))
+        );
+        assert_eq!(
+            ADDRESS[..92].parse::<Address>(),
+            Err(AddressError::Base58(Base58Error::InvalidLength(4)))
+        );
+        assert!(matches!(
+            ADDRESS.replace('4', "0").parse::<Address>(),
+            Err(AddressError::Base58(Base58Error::InvalidCharacter('0')))
+        ));
+    }
+
+    #[test]
+    fn test_integrated_address() {
+        let address: Address = ADDRESS.parse().unwrap();
+        let id: PaymentId = "0123456789abcdef".parse().unwrap();
+        let integrated = address.integrated(id).unwrap();
+        let text = integrated.to_string();
+        assert_eq!(text.len(), 106);
+        assert!(text.starts_with('4'));
+        assert_ne!(text, ADDRESS);
+
+        let parsed: Address = text.parse().unwrap();
+        assert_eq!(parsed.kind(), AddressKind::Integrated(id));
+        assert_eq!(parsed.payment_id().unwrap().to_string(), "0123456789abcdef");
+        assert_eq!(parsed.spend_public(), address.spend_public());
+        assert_eq!(parsed.standard().unwrap().to_string(), ADDRESS);
+        assert_eq!(address.payment_id(), None);
+        assert_eq!(address.standard(), Some(address));
+        let other: PaymentId = "fedcba9876543210".parse().unwrap();
+        assert_eq!(
+            integrated.integrated(other).unwrap().payment_id(),
+            Some(other)
+        );
+
+        assert_ne!(PaymentId::random(), PaymentId::random());
+
+        // integrated prefix with a standard-length body
+        let mut short = base58::decode(ADDRESS).unwrap()[..65].to_vec();
+        short[0] = Network::Mainnet.integrated_prefix() as u8;
+        let checksum = keccak256(&short);
+        short.extend_from_slice(&checksum.as_bytes()[..4]);
+        assert_eq!(
+            Address::from_bytes(&short),
+            Err(AddressError::InvalidLength(69))
+        );
+    }
+
+    #[test]
+    fn test_encrypted_payment_id() {
+        use crate::crypto::keys::generate_key_derivation;
+        use curve25519_dalek::{Scalar, constants::ED25519_BASEPOINT_POINT};
+
+        let keys = to_wallet_keys(SEED, Language::English).unwrap();
+        let tx_secret = Scalar::from(12345u64);
+        let tx_public = tx_secret * ED25519_BASEPOINT_POINT;
+        let sender = generate_key_derivation(keys.view_public(), &tx_secret);
+        let receiver = generate_key_derivation(&tx_public, keys.view_secret());
+        assert_eq!(sender, receiver);
+
+        let id: PaymentId = "0123456789abcdef".parse().unwrap();
+        let encrypted = id.encrypt(&sender);
+        let mut data = sender.as_bytes().to_vec();
+        data.push(0x8d);
+        let keystream = keccak256(&data);
+        assert_eq!(encrypted[0], 0x01 ^ keystream.as_bytes()[0]);
+        assert_eq!(PaymentId::decrypt(&encrypted, &receiver), id);
+
+        let other = generate_key_derivation(&tx_public, &Scalar::from(7u64));
+        assert_ne!(PaymentId::decrypt(&encrypted, &other), id);
+    }
+
+    #[test]
+    fn test_subaddress() {
+        let keys = to_wallet_keys(SEED, Language::English).unwrap();
+        assert_eq!(
+            Address::subaddress(&keys, Network::Mainnet, 0, 0).to_string(),
+            ADDRESS
+        );
+
+        let sub = Address::subaddress(&keys, Network::Mainnet, 0, 1);
+        let text = sub.to_string();
+        assert!(text.starts_with('8'));
+        assert_eq!(text.len(), 95);
+        let parsed: Address = text.parse().unwrap();
+        assert_eq!(parsed.kind(), AddressKind::Subaddress);
+        assert_eq!(parsed, sub);
+        assert_eq!(*parsed.spend_public(), keys.subaddress_spend_public(0, 1));
+        assert_eq!(*parsed.view_public(), keys.subaddress_view_public(0, 1));
+        assert_eq!(parsed.payment_id(), None);
+        assert_ne!(Address::subaddress(&keys, Network::Mainnet, 1, 0), sub);
+        // a subaddress's keys under another prefix would be unspendable
+        assert_eq!(sub.integrated(PaymentId([7; 8])), None);
+        assert_eq!(sub.standard(), None);
+
+        let integrated = Address::from_keys(&keys, Network::Mainnet)
+            .integrated(PaymentId([7; 8]))
+            .unwrap();
+        let kinds: Vec<AddressKind> = [ADDRESS.to_string(), integrated.to_string(), text]
+            .iter()
+            .map(|s| s.parse::<Address>().unwrap().kind())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                AddressKind::Standard,
+                AddressKind::Integrated(PaymentId([7; 8])),
+                AddressKind::Subaddress
+            ]
+        );
+    }
+
+    #[test]
+    fn test_networks() {
+        let keys = to_wallet_keys(SEED, Language::English).unwrap();
+        let mainnet: Address = ADDRESS.parse().unwrap();
+        assert_eq!(mainnet.network(), Network::Mainnet);
+
+        let testnet = Address::from_keys(&keys, Network::Testnet);
+        let stagenet = Address::from_keys(&keys, Network::Stagenet);
+        let testnet_text = testnet.to_string();
+        assert!(testnet_text.starts_with('9') || testnet_text.starts_with('A'));
+        assert!(stagenet.to_string().starts_with('5'));
+        assert!(
+            Address::subaddress(&keys, Network::Stagenet, 0, 1)
+                .to_string()
+                .starts_with('7')
+        );
+
+        for address in [
+            testnet,
+            stagenet,
+            testnet.integrated(PaymentId([1; 8])).unwrap(),
+            Address::subaddress(&keys, Network::Testnet, 2, 3),
+        ] {
+            let parsed: Address = address.to_string().parse().unwrap();
+            assert_eq!(parsed, address);
+            if let Some(standard) = parsed.standard() {
+                assert_eq!(standard.network(), address.network());
+            }
+        }
+
+        assert_eq!(
+            Address::parse_for(&testnet_text, Network::Testnet),
+            Ok(testnet)
+        );
+        assert_eq!(
+            Address::parse_for(&testnet_text, Network::Mainnet),
+            Err(AddressError::WrongNetwork(53))
+        );
+        assert_eq!(
+            Address::parse_for(ADDRESS, Network::Stagenet),
+            Err(AddressError::WrongNetwork(18))
+        );
+    }
+}
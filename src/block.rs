@@ -0,0 +1,334 @@
+// src/block.rs
+//! Blocks in their consensus binary form.
+//!
+//! A block is a header, the miner transaction in full and the hashes of the
+//! other transactions it includes. The header is
+//! `major || minor || timestamp || prev_id || nonce`, the versions and
+//! timestamp varints and the nonce a little-endian u32. What gets hashed,
+//! for the block id and proof of work alike, is the hashing blob: the
+//! header with the transactions replaced by their Merkle root and count.
+use crate::crypto::hash::{Hash, Hash256, keccak256, tree_hash};
+#[cfg(feature = "cryptonight")]
+use crate::crypto::pow::cryptonight::{CryptoNightError, Variant, cn_slow_hash};
+#[cfg(feature = "randomx")]
+use crate::crypto::pow::randomx::RandomX;
+use crate::encoding::varint;
+use crate::network::Network;
+use crate::tx::{Reader, Transaction, TxError};
+
+/// Block 202612 was accepted under an id hashed over a wrong Merkle root,
+/// from a since-fixed `tree_hash` bug. Hashing it right gives the first
+/// id; the chain links to the second.
+const BLOCK_202612_ID: [u8; 32] = [
+    0x42, 0x6d, 0x16, 0xcf, 0xf0, 0x4c, 0x71, 0xf8, 0xb1, 0x63, 0x40, 0xb7, 0x22, 0xdc, 0x40, 0x10,
+    0xa2, 0xdd, 0x38, 0x31, 0xc2, 0x20, 0x41, 0x43, 0x1f, 0x77, 0x25, 0x47, 0xba, 0x6e, 0x33, 0x1a,
+];
+const BLOCK_202612_CHAIN_ID: [u8; 32] = [
+    0xbb, 0xd6, 0x04, 0xd2, 0xba, 0x11, 0xba, 0x27, 0x93, 0x5e, 0x00, 0x6e, 0xd3, 0x9c, 0x9b, 0xfd,
+    0xd9, 0x9b, 0x76, 0xbf, 0x4a, 0x50, 0x65, 0x4b, 0xc1, 0xe1, 0xe6, 0x12, 0x17, 0x96, 0x26, 0x98,
+];
+
+/// Miner transaction of the mainnet and testnet genesis blocks
+const GENESIS_TX: &str = "013c01ff0001ffffffffffff03029b2e4c0281c0b02e7c53291a94d1d0cbff8883f8024f\
+    5142ee494ffbbd08807121017767aafcde9be00dcfd098715ebcc7f3ef1ad1ad1e5eb05f4b3b72c3e4bb0d2f";
+/// Miner transaction of the stagenet genesis block
+const STAGENET_GENESIS_TX: &str = "013c01ff0001ffffffffffff0302df5d56da0c7d643ddd1ce61901c7bdc5fb\
+    1738bfe39fbe69c28a3a7032729c0f2101168d0c4ca86fb55a4cf6a36d31431be1c53a3bd7411bb24e8832410289\
+    fa6f3b";
+
+/// Id of `network`'s genesis block, which every chain must start from
+pub fn genesis_id(network: Network) -> Hash256 {
+    Hash(match network {
+        Network::Mainnet => MAINNET_ID,
+        Network::Testnet => TESTNET_ID,
+        Network::Stagenet => STAGENET_ID,
+    })
+}
+
+const MAINNET_ID: [u8; 32] = [
+    0x41, 0x80, 0x15, 0xbb, 0x9a, 0xe9, 0x82, 0xa1, 0x97, 0x5d, 0xa7, 0xd7, 0x92, 0x77, 0xc2, 0x70,
+    0x57, 0x27, 0xa5, 0x68, 0x94, 0xba, 0x0f, 0xb2, 0x46, 0xad, 0xaa, 0xbb, 0x1f, 0x46, 0x32, 0xe3,
+];
+const TESTNET_ID: [u8; 32] = [
+    0x48, 0xca, 0x7c, 0xd3, 0xc8, 0xde, 0x5b, 0x6a, 0x4d, 0x53, 0xd2, 0x86, 0x1f, 0xbd, 0xae, 0xdc,
+    0xa1, 0x41, 0x55, 0x35, 0x59, 0xf9, 0xbe, 0x95, 0x20, 0x06, 0x80, 0x53, 0xcd, 0xa8, 0x43, 0x0b,
+];
+const STAGENET_ID: [u8; 32] = [
+    0x76, 0xee, 0x3c, 0xc9, 0x86, 0x46, 0x29, 0x22, 0x06, 0xcd, 0x3e, 0x86, 0xf7, 0x4d, 0x88, 0xb4,
+    0xdc, 0xc1, 0xd9, 0x37, 0x08, 0x86, 0x45, 0xe9, 0xb0, 0xcb, 0xca, 0x84, 0xb7, 0xce, 0x74, 0xeb,
+];
+
+/// Everything before the transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockHeader {
+    /// Hard-fork version the block was made under
+    pub major_version: u8,
+    /// Highest hard-fork version the miner supports
+    pub minor_version: u8,
+    /// Unix time the miner claims
+    pub timestamp: u64,
+    /// Id of the previous block, zero for the genesis block
+    pub prev_id: Hash256,
+    pub nonce: u32,
+}
+
+impl BlockHeader {
+    /// Append the consensus encoding to `out`
+    pub fn serialize(&self, out: &mut Vec<u8>) {
+        varint::write(out, self.major_version as u64);
+        varint::write(out, self.minor_version as u64);
+        varint::write(out, self.timestamp);
+        out.extend_from_slice(self.prev_id.as_bytes());
+        out.extend_from_slice(&self.nonce.to_le_bytes());
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.serialize(&mut out);
+        out
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, TxError> {
+        // versions are u8 in monerod, but varint-encoded
+        let version = |r: &mut Reader<'_>| {
+            let v = r.varint()?;
+            u8::try_from(v).map_err(|_| TxError::TooLarge(v))
+        };
+        Ok(Self {
+            major_version: version(r)?,
+            minor_version: version(r)?,
+            timestamp: r.varint()?,
+            prev_id: Hash(r.array()?),
+            nonce: u32::from_le_bytes(r.array()?),
+        })
+    }
+}
+
+/// A header, its miner transaction and the ids of the rest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub header: BlockHeader,
+    pub miner_tx: Transaction,
+    pub tx_hashes: Vec<Hash256>,
+}
+
+impl Block {
+    /// The first block of `network`, hard-coded like monerod's: version
+    /// 1, timestamp 0 and a nonce of 10000, 10001 or 10002
+    pub fn genesis(network: Network) -> Self {
+        let (miner_tx, nonce) = match network {
+            Network::Mainnet => (GENESIS_TX, 10000),
+            Network::Testnet => (GENESIS_TX, 10001),
+            Network::Stagenet => (STAGENET_GENESIS_TX, 10002),
+        };
+        Self {
+            header: BlockHeader {
+                major_version: 1,
+                minor_version: 0,
+                timestamp: 0,
+                prev_id: Hash([0; 32]),
+                nonce,
+            },
+            miner_tx: Transaction::from_hex(miner_tx).expect("genesis transaction parses"),
+            tx_hashes: Vec::new(),
+        }
+    }
+
+    /// Append the consensus encoding to `out`
+    pub fn serialize(&self, out: &mut Vec<u8>) {
+        self.header.serialize(out);
+        self.miner_tx.serialize(out);
+        varint::write(out, self.tx_hashes.len() as u64);
+        for hash in &self.tx_hashes {
+            out.extend_from_slice(hash.as_bytes());
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.serialize(&mut out);
+        out
+    }
+
+    /// Parse a block blob, as `get_block` returns it. Errors are those of
+    /// transaction parsing.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, TxError> {
+        let mut r = Reader::new(data);
+        let header = BlockHeader::read(&mut r)?;
+        let miner_tx = Transaction::read(&mut r)?;
+        let tx_hashes = (0..r.count(32)?)
+            .map(|_| Ok(Hash(r.array()?)))
+            .collect::<Result<_, TxError>>()?;
+        r.finish()?;
+        Ok(Self {
+            header,
+            miner_tx,
+            tx_hashes,
+        })
+    }
+
+    /// Merkle root of the miner transaction's id and `tx_hashes`
+    pub fn tree_root(&self) -> Hash256 {
+        let mut hashes = Vec::with_capacity(1 + self.tx_hashes.len());
+        hashes.push(self.miner_tx.hash());
+        hashes.extend_from_slice(&self.tx_hashes);
+        tree_hash(&hashes)
+    }
+
+    /// `header || tree_root || varint(tx count)`, the miner transaction
+    /// counted
+    pub fn hashing_blob(&self) -> Vec<u8> {
+        let mut out = self.header.to_bytes();
+        out.extend_from_slice(self.tree_root().as_bytes());
+        varint::write(&mut out, 1 + self.tx_hashes.len() as u64);
+        out
+    }
+
+    /// Block id: `Keccak256` of the hashing blob, prefixed with its length
+    /// as monerod hashes any blob
+    pub fn hash(&self) -> Hash256 {
+        let blob = self.hashing_blob();
+        let mut data = Vec::with_capacity(blob.len() + 1);
+        varint::write(&mut data, blob.len() as u64);
+        data.extend_from_slice(&blob);
+        match keccak256(&data) {
+            id if id.0 == BLOCK_202612_ID => Hash(BLOCK_202612_CHAIN_ID),
+            id => id,
+        }
+    }
+
+    /// Proof-of-work hash since v12, with `vm` keyed by the seed block
+    /// [`randomx_seed_height`](crate::crypto::pow::randomx_seed_height)
+    /// names
+    #[cfg(feature = "randomx")]
+    pub fn pow_hash(&self, vm: &mut RandomX) -> Hash256 {
+        vm.hash(&self.hashing_blob())
+    }
+
+    /// Proof-of-work hash up to v9, for historical blocks. CryptoNight-R
+    /// blocks (v10 and v11) are an error, as are blocks since v12, which
+    /// [`Self::pow_hash`] hashes with RandomX.
+    #[cfg(feature = "cryptonight")]
+    pub fn cryptonight_hash(&self) -> Result<Hash256, CryptoNightError> {
+        let version = self.header.major_version;
+        let variant = Variant::for_major_version(version)
+            .ok_or(CryptoNightError::UnsupportedVersion(version))?;
+        cn_slow_hash(&self.hashing_blob(), variant)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Stagenet genesis block: a v1 miner transaction and nothing else
+    pub(crate) const STAGENET_GENESIS: &str = "0100000000000000000000000000000000000000000000000000\
+        00000000000000000012270000013c01ff0001ffffffffffff0302df5d56da0c7d643ddd1ce61901c7bdc5fb17\
+        38bfe39fbe69c28a3a7032729c0f2101168d0c4ca86fb55a4cf6a36d31431be1c53a3bd7411bb24e8832410289\
+        fa6f3b00";
+
+    /// A v12 block holding only its v2 miner transaction, and its hashing
+    /// blob as monerod computes it
+    const V12_BLOCK: &str = "0c0c94debaf805beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749\
+        c5276b6200000000029b892201ffdf882201b699d4c8b1ec020223df524af2a2ef5f870adb6e1ceb03a475c39f\
+        8b9ef76aa50b46ddd2a18349402b012839bfa19b7524ec7488917714c216ca254b38ed0424ca65ae828a7c006a\
+        eaf10208f5316a7f6b99cca60000";
+    const V12_HASHING_BLOB: &str = "0c0c94debaf805beb3489c722a285c092a32e7c6893abfc7d069699c8326fc\
+        3445a749c5276b6200000000602d0d4710e2c2d38da0cce097accdf5dc18b1d34323880c1aae90ab8f6be6e201";
+
+    #[test]
+    fn test_genesis_blob() {
+        let blob = hex::decode(STAGENET_GENESIS).unwrap();
+        let block = Block::from_bytes(&blob).unwrap();
+        assert_eq!(block.header.major_version, 1);
+        assert_eq!(block.header.minor_version, 0);
+        assert_eq!(block.header.timestamp, 0);
+        assert_eq!(block.header.prev_id, Hash([0; 32]));
+        assert_eq!(block.header.nonce, 10002);
+        assert!(block.miner_tx.prefix.is_coinbase());
+        assert!(block.tx_hashes.is_empty());
+        assert_eq!(block.to_bytes(), blob);
+
+        // a lone miner transaction is its own Merkle root
+        let blob = block.hashing_blob();
+        assert_eq!(blob.len(), 3 + 32 + 4 + 32 + 1);
+        assert_eq!(blob[..39], block.header.to_bytes()[..]);
+        assert_eq!(blob[39..71], *block.miner_tx.hash().as_bytes());
+        assert_eq!(blob[71], 1);
+        assert_eq!(
+            hex::encode(block.hash().as_bytes()),
+            "76ee3cc98646292206cd3e86f74d88b4dcc1d937088645e9b0cbca84b7ce74eb"
+        );
+    }
+
+    #[test]
+    fn test_genesis() {
+        for network in Network::ALL {
+            let block = Block::genesis(network);
+            assert!(block.miner_tx.prefix.is_coinbase());
+            assert_eq!(block.miner_tx.prefix.unlock_time, 60);
+            assert_eq!(block.miner_tx.prefix.vout[0].amount, (1 << 44) - 1);
+            assert_eq!(Block::from_bytes(&block.to_bytes()), Ok(block));
+        }
+        let stagenet = Block::genesis(Network::Stagenet);
+        assert_eq!(hex::encode(stagenet.to_bytes()), STAGENET_GENESIS);
+        assert_eq!(stagenet.hash(), genesis_id(Network::Stagenet));
+
+        // mainnet and testnet share a miner transaction, by this id
+        let miner_tx = "c88ce9783b4f11190d7b9c17a69c1c52200f9faaee8e98dd07e6811175177139";
+        for network in [Network::Mainnet, Network::Testnet] {
+            let mut blob = Block::genesis(network).header.to_bytes();
+            blob.extend_from_slice(&hex::decode(miner_tx).unwrap());
+            blob.push(1);
+            let mut data = vec![blob.len() as u8];
+            data.extend_from_slice(&blob);
+            assert_eq!(keccak256(&data), genesis_id(network));
+        }
+    }
+
+    #[test]
+    fn test_v12_block() {
+        let blob = hex::decode(V12_BLOCK).unwrap();
+        let block = Block::from_bytes(&blob).unwrap();
+        assert_eq!(block.header.major_version, 12);
+        assert_eq!(block.miner_tx.prefix.version, 2);
+        assert_eq!(block.to_bytes(), blob);
+        assert_eq!(hex::encode(block.hashing_blob()), V12_HASHING_BLOB);
+    }
+
+    #[cfg(feature = "cryptonight")]
+    #[test]
+    fn test_cryptonight_versions() {
+        let mut block = Block::from_bytes(&hex::decode(V12_BLOCK).unwrap()).unwrap();
+        assert_eq!(
+            block.cryptonight_hash(),
+            Err(CryptoNightError::UnsupportedVersion(12))
+        );
+        block.header.major_version = 10;
+        assert_eq!(
+            block.cryptonight_hash(),
+            Err(CryptoNightError::UnsupportedVersion(10))
+        );
+    }
+
+    #[test]
+    fn test_tx_hashes() {
+        let mut block = Block::from_bytes(&hex::decode(STAGENET_GENESIS).unwrap()).unwrap();
+        block.tx_hashes = vec![Hash([1; 32]), Hash([2; 32])];
+        let blob = block.to_bytes();
+        assert_eq!(Block::from_bytes(&blob), Ok(block.clone()));
+        assert_eq!(
+            block.tree_root(),
+            tree_hash(&[block.miner_tx.hash(), Hash([1; 32]), Hash([2; 32])])
+        );
+        assert_eq!(*block.hashing_blob().last().unwrap(), 3);
+
+        assert_eq!(
+            Block::from_bytes(&blob[..blob.len() - 1]),
+            Err(TxError::TooLarge(2))
+        );
+        let mut trailing = blob;
+        trailing.push(0);
+        assert_eq!(Block::from_bytes(&trailing), Err(TxError::TrailingBytes(1)));
+    }
+}
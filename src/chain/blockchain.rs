@@ -0,0 +1,707 @@
+// src/chain/blockchain.rs
+//! An in-memory chain from genesis up, with its side chains.
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{BLOCK_FUTURE_TIME_LIMIT, BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW, ChainError, Checkpoints};
+use crate::block::{Block, genesis_id};
+use crate::consensus::HardFork;
+use crate::consensus::difficulty::{DIFFICULTY_BLOCKS_COUNT, next_difficulty, target_seconds};
+use crate::consensus::hardfork::check_transaction;
+use crate::consensus::reward::reward;
+use crate::consensus::weight::{
+    LONG_TERM_BLOCK_WEIGHT_WINDOW, REWARD_BLOCKS_WINDOW, effective_median, long_term_median,
+    long_term_weight,
+};
+use crate::crypto::hash::Hash256;
+use crate::crypto::pow::{check_hash, randomx_seed_height};
+use crate::network::Network;
+use crate::tx::{Transaction, TxIn};
+
+/// A block with what the chain knows about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainEntry {
+    pub block: Block,
+    pub id: Hash256,
+    pub height: u64,
+    /// Difficulty the block was mined at
+    pub difficulty: u128,
+    /// Sum of the difficulties up to and including this block
+    pub cumulative_difficulty: u128,
+    /// Coins emitted up to and including this block, fees not counted
+    pub generated_coins: u64,
+    /// Weight of the miner transaction and the block's transactions
+    pub weight: u64,
+    /// [`Self::weight`] clamped around the long-term median before it
+    pub long_term_weight: u64,
+}
+
+/// What adding a block did to the chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainEvent {
+    /// The block is the new top
+    Extended(ChainEntry),
+    /// The block went on a side chain still lighter than the main one
+    SideChain(ChainEntry),
+    /// The block's side chain became the main one. Everything from
+    /// `fork_height` up was rolled back, then `attached` applied, both
+    /// lowest first. The `detached` blocks are now a side chain.
+    Reorg {
+        fork_height: u64,
+        detached: Vec<ChainEntry>,
+        attached: Vec<ChainEntry>,
+    },
+}
+
+/// Computes blocks' proof-of-work hashes for [`Blockchain::add_block`]
+pub trait PowHasher {
+    /// Proof-of-work hash of `block` at `height`. `seed_id` is the id of
+    /// the block keying RandomX for it, on the block's own chain.
+    fn pow_hash(&mut self, block: &Block, height: u64, seed_id: &Hash256) -> Hash256;
+}
+
+/// Blocks from genesis to the top, checked as they are added, and the
+/// side chains off them
+#[derive(Debug, Clone)]
+pub struct Blockchain {
+    network: Network,
+    entries: Vec<ChainEntry>,
+    heights: HashMap<Hash256, u64>,
+    side: HashMap<Hash256, ChainEntry>,
+    checkpoints: Checkpoints,
+    hard_forks: HardFork,
+}
+
+impl Blockchain {
+    /// A chain holding only the genesis block of `network`, with the
+    /// checkpoints and hard fork schedule compiled in for it
+    pub fn new(network: Network) -> Self {
+        let id = genesis_id(network);
+        let block = Block::genesis(network);
+        let weight = block.miner_tx.weight() as u64;
+        let genesis = ChainEntry {
+            generated_coins: block.miner_tx.prefix.vout.iter().map(|o| o.amount).sum(),
+            weight,
+            long_term_weight: weight,
+            block,
+            id,
+            height: 0,
+            difficulty: 1,
+            cumulative_difficulty: 1,
+        };
+        Self {
+            network,
+            entries: vec![genesis],
+            heights: HashMap::from([(id, 0)]),
+            side: HashMap::new(),
+            checkpoints: Checkpoints::for_network(network),
+            hard_forks: HardFork::for_network(network),
+        }
+    }
+
+    /// Enforce `checkpoints` on the blocks added from now on
+    pub fn with_checkpoints(mut self, checkpoints: Checkpoints) -> Self {
+        self.checkpoints = checkpoints;
+        self
+    }
+
+    pub fn checkpoints(&self) -> &Checkpoints {
+        &self.checkpoints
+    }
+
+    /// Follow `hard_forks` for the blocks added from now on
+    pub fn with_hard_forks(mut self, hard_forks: HardFork) -> Self {
+        self.hard_forks = hard_forks;
+        self
+    }
+
+    pub fn hard_forks(&self) -> &HardFork {
+        &self.hard_forks
+    }
+
+    /// Version the next block must have
+    pub fn next_version(&self) -> u8 {
+        self.hard_forks.version_at(self.height())
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Number of blocks, genesis included: the height of the next block
+    pub fn height(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    pub fn top(&self) -> &ChainEntry {
+        self.entries.last().expect("chain has a genesis block")
+    }
+
+    pub fn top_id(&self) -> Hash256 {
+        self.top().id
+    }
+
+    pub fn cumulative_difficulty(&self) -> u128 {
+        self.top().cumulative_difficulty
+    }
+
+    pub fn entry(&self, height: u64) -> Option<&ChainEntry> {
+        self.entries.get(usize::try_from(height).ok()?)
+    }
+
+    pub fn block(&self, height: u64) -> Option<&Block> {
+        self.entry(height).map(|e| &e.block)
+    }
+
+    pub fn block_by_hash(&self, id: &Hash256) -> Option<&Block> {
+        self.block(self.height_of(id)?)
+    }
+
+    pub fn height_of(&self, id: &Hash256) -> Option<u64> {
+        self.heights.get(id).copied()
+    }
+
+    pub fn contains(&self, id: &Hash256) -> bool {
+        self.heights.contains_key(id)
+    }
+
+    /// A block on a side chain, by id
+    pub fn side_block(&self, id: &Hash256) -> Option<&ChainEntry> {
+        self.side.get(id)
+    }
+
+    /// Number of blocks on side chains
+    pub fn num_side_blocks(&self) -> usize {
+        self.side.len()
+    }
+
+    /// Difficulty the next block must meet, under hard fork `major_version`
+    pub fn next_difficulty(&self, major_version: u8) -> u128 {
+        let window = self.tail(self.entries.len(), &[], DIFFICULTY_BLOCKS_COUNT);
+        difficulty_after(&window, major_version)
+    }
+
+    /// Median weight the next block's reward penalty is charged against,
+    /// under hard fork `major_version`. Blocks may weigh twice this.
+    pub fn next_median_weight(&self, major_version: u8) -> u64 {
+        median_weight_after(self, self.entries.len(), &[], major_version)
+    }
+
+    /// Median timestamp of the last [`BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW`]
+    /// blocks, None while there are fewer
+    pub fn median_timestamp(&self) -> Option<u64> {
+        let window = self.tail(self.entries.len(), &[], BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW);
+        median_timestamp(&window)
+    }
+
+    /// Check `block` and its transactions `txs`, in order, against the
+    /// chain it extends and add it, switching to its side chain if that
+    /// became the heaviest. Its proof of work, hashed by `pow`, must meet
+    /// the difficulty of the chain it is on, side chains included.
+    pub fn add_block(
+        &mut self,
+        block: Block,
+        txs: &[Transaction],
+        pow: &mut impl PowHasher,
+    ) -> Result<ChainEvent, ChainError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.add_block_at(block, txs, pow, now)
+    }
+
+    /// [`Self::add_block`] with the local clock at `now`
+    pub fn add_block_at(
+        &mut self,
+        block: Block,
+        txs: &[Transaction],
+        pow: &mut impl PowHasher,
+        now: u64,
+    ) -> Result<ChainEvent, ChainError> {
+        let id = block.hash();
+        if self.contains(&id) || self.side.contains_key(&id) {
+            return Err(ChainError::Duplicate(id));
+        }
+        let prev_id = block.header.prev_id;
+        let (fork, branch) = self.branch(&prev_id).ok_or(ChainError::Orphan(prev_id))?;
+        let height = (fork + branch.len()) as u64;
+        if !self.checkpoints.check(height, &id) {
+            return Err(ChainError::CheckpointMismatch(height));
+        }
+        if fork < self.entries.len() && !self.checkpoints.allows_side_block(self.height(), height) {
+            return Err(ChainError::BelowCheckpoint(height));
+        }
+        let timestamp = block.header.timestamp;
+        if timestamp > now.saturating_add(BLOCK_FUTURE_TIME_LIMIT) {
+            return Err(ChainError::TimestampInFuture(timestamp));
+        }
+        let window = self.tail(fork, &branch, BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW);
+        if let Some(median) = median_timestamp(&window)
+            && timestamp < median
+        {
+            return Err(ChainError::TimestampTooOld { timestamp, median });
+        }
+        let version = self.hard_forks.version_at(height);
+        if block.header.major_version != version {
+            return Err(ChainError::WrongVersion {
+                version: block.header.major_version,
+                expected: version,
+            });
+        }
+        match block.miner_tx.prefix.vin.as_slice() {
+            [TxIn::Gen(input)] if input.height == height => {}
+            _ => return Err(ChainError::InvalidMinerTx),
+        }
+        if check_transaction(&block.miner_tx, version).is_err() {
+            return Err(ChainError::InvalidMinerTx);
+        }
+        if !txs
+            .iter()
+            .map(Transaction::hash)
+            .eq(block.tx_hashes.iter().copied())
+        {
+            return Err(ChainError::TransactionMismatch);
+        }
+        for tx in txs {
+            check_transaction(tx, version)
+                .map_err(|e| ChainError::InvalidTransaction(tx.hash(), e))?;
+        }
+
+        let weight = txs
+            .iter()
+            .chain([&block.miner_tx])
+            .map(|tx| tx.weight() as u64)
+            .sum();
+        let median = median_weight_after(self, fork, &branch, version);
+        let window = self.tail(fork, &branch, DIFFICULTY_BLOCKS_COUNT);
+        let difficulty = difficulty_after(&window, version);
+        let parent = window.last().expect("parent is in the window");
+        let emitted = reward(parent.generated_coins, weight, median, version).ok_or(
+            ChainError::TooHeavy {
+                weight,
+                limit: 2 * median,
+            },
+        )?;
+        let window = self.tail(fork, &branch, LONG_TERM_BLOCK_WEIGHT_WINDOW);
+        let long_term: Vec<_> = window.iter().map(|e| e.long_term_weight).collect();
+        let seed_height = randomx_seed_height(height) as usize;
+        let seed_id = match seed_height.checked_sub(fork) {
+            Some(i) => branch[i].id,
+            None => self.entries[seed_height].id,
+        };
+        if !check_hash(&pow.pow_hash(&block, height, &seed_id), difficulty) {
+            return Err(ChainError::InsufficientWork(height));
+        }
+        let entry = ChainEntry {
+            block,
+            id,
+            height,
+            difficulty,
+            cumulative_difficulty: parent.cumulative_difficulty + difficulty,
+            generated_coins: parent.generated_coins.saturating_add(emitted),
+            weight,
+            long_term_weight: long_term_weight(weight, long_term_median(&long_term), version),
+        };
+        let branch: Vec<_> = branch.iter().map(|e| e.id).collect();
+
+        if fork == self.entries.len() {
+            self.heights.insert(id, height);
+            self.entries.push(entry.clone());
+            return Ok(ChainEvent::Extended(entry));
+        }
+        if entry.cumulative_difficulty <= self.cumulative_difficulty() {
+            self.side.insert(id, entry.clone());
+            return Ok(ChainEvent::SideChain(entry));
+        }
+
+        let detached = self.entries.split_off(fork);
+        for e in &detached {
+            self.heights.remove(&e.id);
+            self.side.insert(e.id, e.clone());
+        }
+        let mut attached: Vec<_> = branch
+            .iter()
+            .filter_map(|id| self.side.remove(id))
+            .collect();
+        attached.push(entry);
+        for e in &attached {
+            self.heights.insert(e.id, e.height);
+            self.entries.push(e.clone());
+        }
+        Ok(ChainEvent::Reorg {
+            fork_height: fork as u64,
+            detached,
+            attached,
+        })
+    }
+
+    /// Side chain blocks from the main chain up to `id`, and the height of
+    /// the first; no blocks if `id` is on the main chain
+    fn branch(&self, id: &Hash256) -> Option<(usize, Vec<&ChainEntry>)> {
+        let mut branch = Vec::new();
+        let mut id = *id;
+        loop {
+            if let Some(height) = self.height_of(&id) {
+                branch.reverse();
+                return Some((height as usize + 1, branch));
+            }
+            let entry = self.side.get(&id)?;
+            branch.push(entry);
+            id = entry.block.header.prev_id;
+        }
+    }
+
+    /// The last `n` blocks of the main chain up to `fork` followed by
+    /// `branch`
+    fn tail<'a>(&'a self, fork: usize, branch: &[&'a ChainEntry], n: usize) -> Vec<&'a ChainEntry> {
+        let from_branch = branch.len().min(n);
+        let start = fork.saturating_sub(n - from_branch);
+        self.entries[start..fork]
+            .iter()
+            .chain(branch[branch.len() - from_branch..].iter().copied())
+            .collect()
+    }
+}
+
+fn difficulty_after(window: &[&ChainEntry], major_version: u8) -> u128 {
+    let timestamps: Vec<_> = window.iter().map(|e| e.block.header.timestamp).collect();
+    let cumulative: Vec<_> = window.iter().map(|e| e.cumulative_difficulty).collect();
+    next_difficulty(&timestamps, &cumulative, target_seconds(major_version))
+}
+
+/// Effective median weight for a block after the main chain up to `fork`
+/// and `branch`
+fn median_weight_after(
+    chain: &Blockchain,
+    fork: usize,
+    branch: &[&ChainEntry],
+    major_version: u8,
+) -> u64 {
+    let window = chain.tail(fork, branch, REWARD_BLOCKS_WINDOW);
+    let weights: Vec<_> = window.iter().map(|e| e.weight).collect();
+    let window = chain.tail(fork, branch, LONG_TERM_BLOCK_WEIGHT_WINDOW);
+    let long_term: Vec<_> = window.iter().map(|e| e.long_term_weight).collect();
+    effective_median(&weights, long_term_median(&long_term), major_version)
+}
+
+fn median_timestamp(window: &[&ChainEntry]) -> Option<u64> {
+    if window.len() < BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW {
+        return None;
+    }
+    let mut timestamps: Vec<_> = window.iter().map(|e| e.block.header.timestamp).collect();
+    timestamps.sort_unstable();
+    let mid = timestamps.len() / 2;
+    Some(match timestamps.len().is_multiple_of(2) {
+        true => (timestamps[mid - 1] + timestamps[mid]) / 2,
+        false => timestamps[mid],
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::consensus::reward::base_reward;
+    use crate::crypto::hash::Hash;
+    use crate::tx::{TxInGen, TxOut};
+
+    /// Hashes every block to zero, which meets any difficulty
+    pub(crate) struct AnyWork;
+
+    impl PowHasher for AnyWork {
+        fn pow_hash(&mut self, _: &Block, _: u64, _: &Hash256) -> Hash256 {
+            Hash([0; 32])
+        }
+    }
+
+    /// Hashes every block to the largest hash, which meets difficulty 1
+    /// only
+    struct NoWork;
+
+    impl PowHasher for NoWork {
+        fn pow_hash(&mut self, _: &Block, _: u64, _: &Hash256) -> Hash256 {
+            Hash([0xff; 32])
+        }
+    }
+
+    /// A v16 block on top of `parent`, `spacing` seconds after it
+    pub(crate) fn child(parent: &ChainEntry, spacing: u64) -> Block {
+        let mut block = parent.block.clone();
+        block.header.major_version = 16;
+        block.header.minor_version = 16;
+        block.header.prev_id = parent.id;
+        block.header.timestamp += spacing;
+        let miner_tx = &mut block.miner_tx.prefix;
+        miner_tx.version = 2;
+        miner_tx.vin = vec![TxIn::Gen(TxInGen {
+            height: parent.height + 1,
+        })];
+        for output in &mut miner_tx.vout {
+            *output = TxOut::to_tagged_key(output.amount, *output.key(), 0);
+        }
+        block
+    }
+
+    /// A block on top of `chain`, `spacing` seconds after it
+    pub(crate) fn next_block(chain: &Blockchain, spacing: u64) -> Block {
+        child(chain.top(), spacing)
+    }
+
+    /// A stagenet chain of `count` blocks after genesis, two minutes apart,
+    /// at v16 from height 1
+    pub(crate) fn chain_of(count: u64) -> Blockchain {
+        let hard_forks = HardFork::new(&[(1, 0), (16, 1)]).unwrap();
+        let mut chain = Blockchain::new(Network::Stagenet).with_hard_forks(hard_forks);
+        for _ in 0..count {
+            let block = next_block(&chain, 120);
+            chain
+                .add_block_at(block, &[], &mut AnyWork, u64::MAX)
+                .unwrap();
+        }
+        chain
+    }
+
+    #[test]
+    fn test_queries() {
+        let chain = chain_of(5);
+        assert_eq!(chain.height(), 6);
+        assert_eq!(chain.block(0), Some(&Block::genesis(Network::Stagenet)));
+        assert_eq!(chain.height_of(&genesis_id(Network::Stagenet)), Some(0));
+        let top = chain.top();
+        assert_eq!(top.height, 5);
+        assert_eq!(top.block.header.timestamp, 600);
+        assert_eq!(chain.block_by_hash(&top.id), Some(&top.block));
+        assert_eq!(chain.block(6), None);
+        // one block at difficulty 1, then 120 * 1 / 120 each
+        assert_eq!(chain.cumulative_difficulty(), 6);
+        let genesis_reward = chain.entry(0).unwrap().generated_coins;
+        assert_eq!(genesis_reward, 17_592_186_044_415);
+        let second = base_reward(genesis_reward, 16);
+        assert_eq!(
+            chain.entry(2).unwrap().generated_coins,
+            genesis_reward + second + base_reward(genesis_reward + second, 16)
+        );
+    }
+
+    #[test]
+    fn test_rejects() {
+        let mut chain = chain_of(BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW as u64);
+        let block = next_block(&chain, 120);
+
+        let mut orphan = block.clone();
+        orphan.header.prev_id = Hash([7; 32]);
+        assert_eq!(
+            chain.add_block_at(orphan, &[], &mut AnyWork, u64::MAX),
+            Err(ChainError::Orphan(Hash([7; 32])))
+        );
+
+        // blocks 1..=60 are at 120..=7200, median 3660
+        let mut old = block.clone();
+        old.header.timestamp = 3659;
+        assert_eq!(
+            chain.add_block_at(old, &[], &mut AnyWork, u64::MAX),
+            Err(ChainError::TimestampTooOld {
+                timestamp: 3659,
+                median: 3660
+            })
+        );
+        let now = block.header.timestamp - BLOCK_FUTURE_TIME_LIMIT - 1;
+        assert_eq!(
+            chain.add_block_at(block.clone(), &[], &mut AnyWork, now),
+            Err(ChainError::TimestampInFuture(block.header.timestamp))
+        );
+
+        let mut wrong_version = block.clone();
+        wrong_version.header.major_version = 15;
+        assert_eq!(
+            chain.add_block_at(wrong_version, &[], &mut AnyWork, u64::MAX),
+            Err(ChainError::WrongVersion {
+                version: 15,
+                expected: 16
+            })
+        );
+        let mut v1_miner_tx = block.clone();
+        v1_miner_tx.miner_tx.prefix.version = 1;
+        assert_eq!(
+            chain.add_block_at(v1_miner_tx, &[], &mut AnyWork, u64::MAX),
+            Err(ChainError::InvalidMinerTx)
+        );
+
+        let mut wrong_height = block.clone();
+        wrong_height.miner_tx.prefix.vin = vec![TxIn::Gen(TxInGen { height: 7 })];
+        assert_eq!(
+            chain.add_block_at(wrong_height, &[], &mut AnyWork, u64::MAX),
+            Err(ChainError::InvalidMinerTx)
+        );
+
+        let event = chain
+            .add_block_at(block.clone(), &[], &mut AnyWork, u64::MAX)
+            .unwrap();
+        assert_eq!(&event, &ChainEvent::Extended(chain.top().clone()));
+        assert!(matches!(
+            chain.add_block_at(block, &[], &mut AnyWork, u64::MAX),
+            Err(ChainError::Duplicate(_))
+        ));
+    }
+
+    #[test]
+    fn test_weights() {
+        let mut chain = chain_of(3);
+        let top = chain.top().clone();
+        assert_eq!(top.weight, top.block.miner_tx.weight() as u64);
+        // raised to the long-term median / 1.4
+        assert_eq!(top.long_term_weight, 214_285);
+        assert_eq!(chain.next_median_weight(16), 300_000);
+        assert_eq!(chain.next_median_weight(1), 20_000);
+
+        let mut tx = top.block.miner_tx.clone();
+        tx.prefix.extra = vec![0; 700_000];
+        let mut block = next_block(&chain, 120);
+        block.tx_hashes = vec![tx.hash()];
+        assert_eq!(
+            chain.add_block_at(block.clone(), &[], &mut AnyWork, u64::MAX),
+            Err(ChainError::TransactionMismatch)
+        );
+        let weight = block.miner_tx.weight() as u64 + tx.weight() as u64;
+        assert_eq!(
+            chain.add_block_at(
+                block.clone(),
+                std::slice::from_ref(&tx),
+                &mut AnyWork,
+                u64::MAX
+            ),
+            Err(ChainError::TooHeavy {
+                weight,
+                limit: 600_000
+            })
+        );
+
+        // over the median the reward is penalised
+        tx.prefix.extra.truncate(400_000);
+        block.tx_hashes = vec![tx.hash()];
+        chain
+            .add_block_at(block, &[tx], &mut AnyWork, u64::MAX)
+            .unwrap();
+        let entry = chain.top();
+        let emitted = entry.generated_coins - top.generated_coins;
+        assert!(emitted < base_reward(top.generated_coins, 16));
+        assert_eq!(
+            reward(top.generated_coins, entry.weight, 300_000, 16),
+            Some(emitted)
+        );
+        assert_eq!(entry.long_term_weight, entry.weight);
+    }
+
+    #[test]
+    fn test_reorg() {
+        let mut chain = chain_of(3);
+        let main: Vec<_> = (0..4).map(|h| chain.entry(h).unwrap().clone()).collect();
+
+        // a side chain off block 1, each block of difficulty 1 like the
+        // main chain's
+        let mut parent = main[1].clone();
+        let mut side = Vec::new();
+        for _ in 0..2 {
+            let mut block = child(&parent, 120);
+            block.header.nonce = 7;
+            let ChainEvent::SideChain(entry) = chain
+                .add_block_at(block, &[], &mut AnyWork, u64::MAX)
+                .unwrap()
+            else {
+                panic!("not heavier than the main chain");
+            };
+            assert_eq!(chain.side_block(&entry.id), Some(&entry));
+            side.push(entry.clone());
+            parent = entry;
+        }
+        assert_eq!(chain.top_id(), main[3].id);
+        assert_eq!(chain.num_side_blocks(), 2);
+
+        let event = chain
+            .add_block_at(child(&parent, 120), &[], &mut AnyWork, u64::MAX)
+            .unwrap();
+        let top = chain.top().clone();
+        side.push(top.clone());
+        assert_eq!(
+            event,
+            ChainEvent::Reorg {
+                fork_height: 2,
+                detached: main[2..].to_vec(),
+                attached: side.clone(),
+            }
+        );
+        assert_eq!(top.height, 4);
+        assert_eq!(chain.cumulative_difficulty(), 5);
+        assert_eq!(chain.height_of(&side[0].id), Some(2));
+        assert_eq!(chain.height_of(&main[3].id), None);
+        assert_eq!(chain.side_block(&main[3].id), Some(&main[3]));
+        assert_eq!(chain.num_side_blocks(), 2);
+
+        // the old main chain catching up is only a side chain again
+        let event = chain.add_block_at(child(&main[3], 120), &[], &mut AnyWork, u64::MAX);
+        assert!(matches!(event, Ok(ChainEvent::SideChain(_))));
+        assert_eq!(chain.top_id(), top.id);
+    }
+
+    #[test]
+    fn test_side_chain_work() {
+        let mut chain = chain_of(3);
+        let fork = chain.entry(1).unwrap().clone();
+
+        // a side block a second after its parent raises its chain's
+        // difficulty to 2, past the main chain's 1
+        let mut block = child(&fork, 1);
+        block.header.nonce = 7;
+        let event = chain.add_block_at(block, &[], &mut NoWork, u64::MAX);
+        let Ok(ChainEvent::SideChain(parent)) = event else {
+            panic!("side block at difficulty 1");
+        };
+        // the next one would outweigh the main chain, but not without
+        // the work
+        let block = child(&parent, 1);
+        assert_eq!(
+            chain.add_block_at(block.clone(), &[], &mut NoWork, u64::MAX),
+            Err(ChainError::InsufficientWork(3))
+        );
+        assert_eq!(chain.top_id(), chain.entry(3).unwrap().id);
+        let event = chain.add_block_at(block, &[], &mut AnyWork, u64::MAX);
+        let Ok(ChainEvent::Reorg { attached, .. }) = event else {
+            panic!("no reorg");
+        };
+        assert_eq!(attached[1].difficulty, 2);
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let chain = chain_of(3);
+        let (b2, b3) = (
+            chain.entry(2).unwrap().clone(),
+            chain.entry(3).unwrap().clone(),
+        );
+        let mut points = Checkpoints::for_network(Network::Stagenet);
+        points.add(2, b2.id).unwrap();
+        let mut chain = chain.with_checkpoints(points);
+
+        let mut side = child(chain.entry(1).unwrap(), 120);
+        side.header.nonce = 7;
+        assert_eq!(
+            chain.add_block_at(side, &[], &mut AnyWork, u64::MAX),
+            Err(ChainError::CheckpointMismatch(2))
+        );
+        // forks above the checkpoint are fine, at or below it are not
+        let mut side = child(chain.entry(0).unwrap(), 120);
+        side.header.nonce = 7;
+        assert_eq!(
+            chain.add_block_at(side, &[], &mut AnyWork, u64::MAX),
+            Err(ChainError::BelowCheckpoint(1))
+        );
+        let mut side = child(&b2, 120);
+        side.header.nonce = 7;
+        let event = chain.add_block_at(side, &[], &mut AnyWork, u64::MAX);
+        assert!(matches!(event, Ok(ChainEvent::SideChain(_))));
+        assert!(
+            chain
+                .add_block_at(child(&b3, 120), &[], &mut AnyWork, u64::MAX)
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,344 @@
+// src/chain/checkpoints.rs
+//! Block ids the chain must have at given heights.
+//!
+//! A checkpoint pins the main chain: a block at its height with another id
+//! is rejected, and so is any side chain forking at or below it, however
+//! heavy. Each network's genesis id is compiled in; more come from a file
+//! in monerod's `checkpoints.json` format,
+//! `{"hashlines": [{"height": 1, "hash": "<hex>"}, ...]}`.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::block::genesis_id;
+use crate::crypto::hash::{Hash, Hash256};
+use crate::network::Network;
+
+/// Why checkpoints couldn't be added
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointError {
+    /// Malformed JSON, at this byte offset
+    InvalidJson(usize),
+    /// Hash at this height that isn't 64 hex digits
+    InvalidHash(u64),
+    /// Two different ids for this height
+    Conflict(u64),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::InvalidJson(pos) => write!(f, "invalid JSON at offset {pos}"),
+            CheckpointError::InvalidHash(h) => write!(f, "invalid checkpoint hash at height {h}"),
+            CheckpointError::Conflict(h) => write!(f, "conflicting checkpoints at height {h}"),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+/// Block ids by height
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checkpoints {
+    points: BTreeMap<u64, Hash256>,
+}
+
+impl Checkpoints {
+    /// No checkpoints at all
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The checkpoints compiled in for `network`
+    pub fn for_network(network: Network) -> Self {
+        Self {
+            points: BTreeMap::from([(0, genesis_id(network))]),
+        }
+    }
+
+    /// Pin `height` to `id`; adding the same one twice is fine
+    pub fn add(&mut self, height: u64, id: Hash256) -> Result<(), CheckpointError> {
+        match self.points.insert(height, id) {
+            Some(old) if old != id => {
+                self.points.insert(height, old);
+                Err(CheckpointError::Conflict(height))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Add the checkpoints of a `checkpoints.json` file. None are added if
+    /// one is invalid or conflicts.
+    pub fn add_json(&mut self, json: &str) -> Result<(), CheckpointError> {
+        let root = Parser::new(json).document()?;
+        let lines = match root.get("hashlines") {
+            Some(Json::Array(lines)) => lines.as_slice(),
+            _ => return Err(CheckpointError::InvalidJson(0)),
+        };
+        let mut points = self.clone();
+        for line in lines {
+            let (Some(Json::Number(height)), Some(Json::String(hash))) =
+                (line.get("height"), line.get("hash"))
+            else {
+                return Err(CheckpointError::InvalidJson(0));
+            };
+            let id = hex::decode(hash)
+                .ok()
+                .and_then(|b| b.try_into().ok())
+                .ok_or(CheckpointError::InvalidHash(*height))?;
+            points.add(*height, Hash(id))?;
+        }
+        *self = points;
+        Ok(())
+    }
+
+    pub fn get(&self, height: u64) -> Option<&Hash256> {
+        self.points.get(&height)
+    }
+
+    /// Whether a block `id` at `height` agrees with the checkpoints
+    pub fn check(&self, height: u64, id: &Hash256) -> bool {
+        self.points.get(&height).is_none_or(|point| point == id)
+    }
+
+    /// Height of the last checkpoint
+    pub fn max_height(&self) -> u64 {
+        self.points.keys().next_back().copied().unwrap_or(0)
+    }
+
+    /// Whether a side chain block at `height` may be accepted by a chain
+    /// of `chain_height` blocks: only above the last checkpoint it has
+    /// reached
+    pub fn allows_side_block(&self, chain_height: u64, height: u64) -> bool {
+        match self.points.range(..=chain_height).next_back() {
+            Some((&checkpoint, _)) => checkpoint < height,
+            None => height > 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+/// The JSON a checkpoint file uses: no floats, negatives or `\u` escapes
+#[derive(Debug, PartialEq)]
+enum Json {
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+    /// `true`, `false` or `null`
+    Literal,
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(json: &'a str) -> Self {
+        Self {
+            data: json.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn error(&self) -> CheckpointError {
+        CheckpointError::InvalidJson(self.pos)
+    }
+
+    fn document(mut self) -> Result<Json, CheckpointError> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        match self.pos == self.data.len() {
+            true => Ok(value),
+            false => Err(self.error()),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.data.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Next non-blank byte, consumed
+    fn next(&mut self) -> Result<u8, CheckpointError> {
+        self.skip_whitespace();
+        let byte = *self.data.get(self.pos).ok_or(self.error())?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), CheckpointError> {
+        match self.next()? == byte {
+            true => Ok(()),
+            false => Err(CheckpointError::InvalidJson(self.pos - 1)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, CheckpointError> {
+        match self.next()? {
+            b'{' => self
+                .list(b'}', |p| {
+                    let key = p.string()?;
+                    p.expect(b':')?;
+                    Ok((key, p.value()?))
+                })
+                .map(Json::Object),
+            b'[' => self.list(b']', Self::value).map(Json::Array),
+            b'"' => {
+                self.pos -= 1;
+                self.string().map(Json::String)
+            }
+            b'0'..=b'9' => {
+                let start = self.pos - 1;
+                while self.data.get(self.pos).is_some_and(u8::is_ascii_digit) {
+                    self.pos += 1;
+                }
+                let digits = std::str::from_utf8(&self.data[start..self.pos]).unwrap();
+                digits.parse().map(Json::Number).map_err(|_| self.error())
+            }
+            _ => {
+                let start = self.pos - 1;
+                for word in [&b"true"[..], b"false", b"null"] {
+                    if self.data[start..].starts_with(word) {
+                        self.pos = start + word.len();
+                        return Ok(Json::Literal);
+                    }
+                }
+                Err(CheckpointError::InvalidJson(start))
+            }
+        }
+    }
+
+    /// Comma-separated items up to `close`, the opening bracket consumed
+    fn list<T>(
+        &mut self,
+        close: u8,
+        mut item: impl FnMut(&mut Self) -> Result<T, CheckpointError>,
+    ) -> Result<Vec<T>, CheckpointError> {
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.data.get(self.pos) == Some(&close) {
+            self.pos += 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            match self.next()? {
+                b',' => continue,
+                b if b == close => return Ok(items),
+                _ => return Err(CheckpointError::InvalidJson(self.pos - 1)),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, CheckpointError> {
+        self.expect(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            let byte = *self.data.get(self.pos).ok_or(self.error())?;
+            self.pos += 1;
+            match byte {
+                b'"' => return String::from_utf8(out).map_err(|_| self.error()),
+                b'\\' => {
+                    let escaped = match self.data.get(self.pos) {
+                        Some(b'"') => b'"',
+                        Some(b'\\') => b'\\',
+                        Some(b'/') => b'/',
+                        Some(b'n') => b'\n',
+                        Some(b't') => b'\t',
+                        _ => return Err(self.error()),
+                    };
+                    self.pos += 1;
+                    out.push(escaped);
+                }
+                _ => out.push(byte),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID_1: &str = "771fbcd656ec1464d3a02ead5e18644030007a0fc664c0a964d30922821a8148";
+
+    #[test]
+    fn test_checkpoints() {
+        let mut points = Checkpoints::for_network(Network::Mainnet);
+        assert!(points.check(0, &genesis_id(Network::Mainnet)));
+        assert!(!points.check(0, &genesis_id(Network::Stagenet)));
+        assert!(points.check(5, &Hash([1; 32])));
+
+        points.add(100, Hash([1; 32])).unwrap();
+        points.add(100, Hash([1; 32])).unwrap();
+        assert_eq!(
+            points.add(100, Hash([2; 32])),
+            Err(CheckpointError::Conflict(100))
+        );
+        assert_eq!(points.get(100), Some(&Hash([1; 32])));
+        assert_eq!(points.max_height(), 100);
+
+        // below the checkpoint the chain has reached, nothing may fork
+        assert!(points.allows_side_block(50, 1));
+        assert!(!points.allows_side_block(50, 0));
+        assert!(!points.allows_side_block(150, 100));
+        assert!(points.allows_side_block(150, 101));
+        assert!(!Checkpoints::new().allows_side_block(10, 0));
+    }
+
+    #[test]
+    fn test_json() {
+        let json = format!(
+            r#"{{
+                "hashlines": [
+                    {{"hash": "{ID_1}", "height": 1}},
+                    {{ "height" : 10, "hash" : "{}", "note": [true, null, "a\"b"] }}
+                ]
+            }}"#,
+            "ab".repeat(32)
+        );
+        let mut points = Checkpoints::new();
+        points.add_json(&json).unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points.get(1).map(|id| hex::encode(id.0)), Some(ID_1.into()));
+        assert_eq!(points.get(10), Some(&Hash([0xab; 32])));
+        points.add_json(r#"{"hashlines": []}"#).unwrap();
+
+        let conflict = format!(
+            r#"{{"hashlines": [{{"height": 2, "hash": "{ID_1}"}}, {{"height": 10, "hash": "{ID_1}"}}]}}"#
+        );
+        assert_eq!(
+            points.add_json(&conflict),
+            Err(CheckpointError::Conflict(10))
+        );
+        assert_eq!(points.get(2), None);
+        assert_eq!(
+            points.add_json(r#"{"hashlines": [{"height": 3, "hash": "abc"}]}"#),
+            Err(CheckpointError::InvalidHash(3))
+        );
+        assert_eq!(
+            points.add_json(r#"{"hashlines": [}"#),
+            Err(CheckpointError::InvalidJson(15))
+        );
+        assert!(points.add_json(r#"{"hashlines": []} x"#).is_err());
+        assert!(points.add_json(r#"[]"#).is_err());
+    }
+}
@@ -0,0 +1,407 @@
+// src/chain/file_store.rs
+//! [`ChainStore`] in an append-only file.
+//!
+//! Each batch is one frame: `u32 payload length || 4 bytes of Keccak256 of
+//! the payload || payload`, the payload its writes one after another, each
+//! a tag byte and its fields. Opening replays the file into a
+//! [`MemoryStore`]; a frame cut short by a crash is dropped, and a whole
+//! frame that fails its checksum is corruption. Writes are synced before
+//! they show. A pruned store writes transactions without their prunable
+//! data.
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+
+use super::blockchain::ChainEntry;
+use super::store::{ChainStore, MemoryStore, Op, StoreError, WriteBatch};
+use crate::block::Block;
+use crate::crypto::cn::KeyImage;
+use crate::crypto::hash::{Hash, Hash256, keccak256};
+use crate::encoding::varint;
+use crate::tx::decoys::CandidateOutput;
+use crate::tx::rct::RingMember;
+use crate::tx::{PrunedTransaction, Reader, Transaction, TxError};
+
+const TAG_BLOCK: u8 = 1;
+const TAG_TRANSACTION: u8 = 2;
+const TAG_OUTPUT: u8 = 3;
+const TAG_KEY_IMAGE: u8 = 4;
+const TAG_POP_BLOCKS: u8 = 5;
+const TAG_PRUNED_TRANSACTION: u8 = 6;
+const FRAME_HEADER_SIZE: usize = 8;
+
+/// [`ChainStore`] kept in a file and loaded into memory when opened
+#[derive(Debug)]
+pub struct FileStore {
+    file: File,
+    /// Bytes of whole frames in the file
+    len: u64,
+    memory: MemoryStore,
+}
+
+impl FileStore {
+    /// Open the store at `path`, creating it if it doesn't exist
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Self::open_with(path, MemoryStore::new())
+    }
+
+    /// [`Self::open`] as a pruned store. Transactions written unpruned
+    /// before are pruned as they load.
+    pub fn open_pruned(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Self::open_with(path, MemoryStore::pruned())
+    }
+
+    fn open_with(path: impl AsRef<Path>, mut memory: MemoryStore) -> Result<Self, StoreError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        let mut pos = 0;
+        while data.len() - pos >= FRAME_HEADER_SIZE {
+            let len = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+            let Some(payload) = data.get(pos + FRAME_HEADER_SIZE..pos + FRAME_HEADER_SIZE + len)
+            else {
+                break;
+            };
+            let corrupt = StoreError::Corrupt(pos as u64);
+            if keccak256(payload).0[..4] != data[pos + 4..pos + FRAME_HEADER_SIZE] {
+                return Err(corrupt);
+            }
+            let batch = decode_batch(payload).map_err(|_| corrupt)?;
+            memory.check(&batch).map_err(|_| corrupt)?;
+            memory.apply(batch);
+            pos += FRAME_HEADER_SIZE + len;
+        }
+        // drop a frame torn by a crash
+        if pos < data.len() {
+            file.set_len(pos as u64)?;
+        }
+        Ok(Self {
+            file,
+            len: pos as u64,
+            memory,
+        })
+    }
+}
+
+impl ChainStore for FileStore {
+    fn write(&mut self, batch: WriteBatch) -> Result<(), StoreError> {
+        self.memory.check(&batch)?;
+        let payload = encode_batch(&batch, self.memory.is_pruned());
+        let mut frame = Vec::with_capacity(FRAME_HEADER_SIZE + payload.len());
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&keccak256(&payload).0[..4]);
+        frame.extend_from_slice(&payload);
+
+        let written = self
+            .file
+            .seek(SeekFrom::Start(self.len))
+            .and_then(|_| self.file.write_all(&frame))
+            .and_then(|_| self.file.sync_data());
+        if let Err(e) = written {
+            // best effort: a torn frame is dropped on the next open anyway
+            let _ = self.file.set_len(self.len);
+            return Err(e.into());
+        }
+        self.len += frame.len() as u64;
+        self.memory.apply(batch);
+        Ok(())
+    }
+
+    fn height(&self) -> u64 {
+        self.memory.height()
+    }
+
+    fn block(&self, height: u64) -> Option<ChainEntry> {
+        self.memory.block(height)
+    }
+
+    fn block_height(&self, id: &Hash256) -> Option<u64> {
+        self.memory.block_height(id)
+    }
+
+    fn transaction(&self, hash: &Hash256) -> Option<Transaction> {
+        self.memory.transaction(hash)
+    }
+
+    fn pruned_transaction(&self, hash: &Hash256) -> Option<PrunedTransaction> {
+        self.memory.pruned_transaction(hash)
+    }
+
+    fn is_pruned(&self) -> bool {
+        self.memory.is_pruned()
+    }
+
+    fn num_outputs(&self) -> u64 {
+        self.memory.num_outputs()
+    }
+
+    fn output(&self, global_index: u64) -> Option<CandidateOutput> {
+        self.memory.output(global_index)
+    }
+
+    fn is_spent(&self, key_image: &KeyImage) -> bool {
+        self.memory.is_spent(key_image)
+    }
+}
+
+/// Payload of `batch`, with its transactions pruned if `pruned`
+fn encode_batch(batch: &WriteBatch, pruned: bool) -> Vec<u8> {
+    let mut out = Vec::new();
+    for op in &batch.ops {
+        match op {
+            Op::Block(entry) => {
+                out.push(TAG_BLOCK);
+                encode_entry(&mut out, entry);
+            }
+            // transactions with nothing to prune, like coinbases, stay whole
+            Op::Transaction(tx) if pruned && !tx.prunable_bytes().is_empty() => {
+                encode_pruned(&mut out, &tx.hash(), &tx.prune())
+            }
+            Op::Transaction(tx) => {
+                out.push(TAG_TRANSACTION);
+                tx.serialize(&mut out);
+            }
+            Op::PrunedTransaction(hash, tx) => encode_pruned(&mut out, hash, tx),
+            Op::Output(output) => {
+                out.push(TAG_OUTPUT);
+                encode_output(&mut out, output);
+            }
+            Op::KeyImage(key_image, height) => {
+                out.push(TAG_KEY_IMAGE);
+                out.extend_from_slice(key_image.as_bytes());
+                varint::write(&mut out, *height);
+            }
+            Op::PopBlocks(height) => {
+                out.push(TAG_POP_BLOCKS);
+                varint::write(&mut out, *height);
+            }
+        }
+    }
+    out
+}
+
+fn encode_pruned(out: &mut Vec<u8>, hash: &Hash256, tx: &PrunedTransaction) {
+    out.push(TAG_PRUNED_TRANSACTION);
+    out.extend_from_slice(hash.as_bytes());
+    out.extend_from_slice(tx.prunable_hash.as_bytes());
+    tx.serialize(out);
+}
+
+fn decode_batch(payload: &[u8]) -> Result<WriteBatch, TxError> {
+    let mut r = Reader::new(payload);
+    let mut batch = WriteBatch::new();
+    while r.remaining() > 0 {
+        let tail = r.remaining();
+        let op = match r.u8()? {
+            TAG_BLOCK => Op::Block(decode_entry(&mut r)?),
+            TAG_TRANSACTION => Op::Transaction(Transaction::read(&mut r)?),
+            TAG_OUTPUT => Op::Output(decode_output(&mut r)?),
+            TAG_KEY_IMAGE => Op::KeyImage(KeyImage(r.array()?), r.varint()?),
+            TAG_POP_BLOCKS => Op::PopBlocks(r.varint()?),
+            TAG_PRUNED_TRANSACTION => {
+                let hash = Hash(r.array()?);
+                let prunable_hash = Hash(r.array()?);
+                Op::PrunedTransaction(hash, PrunedTransaction::read(&mut r, prunable_hash)?)
+            }
+            _ => return Err(TxError::TrailingBytes(tail)),
+        };
+        batch.ops.push(op);
+    }
+    Ok(batch)
+}
+
+/// A block entry's record: its chain fields, then the block blob
+pub(super) fn encode_entry(out: &mut Vec<u8>, entry: &ChainEntry) {
+    varint::write(out, entry.height);
+    out.extend_from_slice(entry.id.as_bytes());
+    out.extend_from_slice(&entry.difficulty.to_le_bytes());
+    out.extend_from_slice(&entry.cumulative_difficulty.to_le_bytes());
+    varint::write(out, entry.generated_coins);
+    varint::write(out, entry.weight);
+    varint::write(out, entry.long_term_weight);
+    let blob = entry.block.to_bytes();
+    varint::write(out, blob.len() as u64);
+    out.extend_from_slice(&blob);
+}
+
+pub(super) fn decode_entry(r: &mut Reader) -> Result<ChainEntry, TxError> {
+    let height = r.varint()?;
+    let id = Hash(r.array()?);
+    let difficulty = u128::from_le_bytes(r.array()?);
+    let cumulative_difficulty = u128::from_le_bytes(r.array()?);
+    let generated_coins = r.varint()?;
+    let weight = r.varint()?;
+    let long_term_weight = r.varint()?;
+    let len = r.count(1)?;
+    Ok(ChainEntry {
+        block: Block::from_bytes(r.take(len)?)?,
+        id,
+        height,
+        difficulty,
+        cumulative_difficulty,
+        generated_coins,
+        weight,
+        long_term_weight,
+    })
+}
+
+pub(super) fn encode_output(out: &mut Vec<u8>, output: &CandidateOutput) {
+    varint::write(out, output.member.global_index);
+    out.extend_from_slice(output.member.key.compress().as_bytes());
+    out.extend_from_slice(output.member.commitment.compress().as_bytes());
+    varint::write(out, output.height);
+    varint::write(out, output.unlock_time);
+    out.push(output.coinbase as u8);
+}
+
+pub(super) fn decode_output(r: &mut Reader) -> Result<CandidateOutput, TxError> {
+    let global_index = r.varint()?;
+    let key = point(r)?;
+    let commitment = point(r)?;
+    Ok(CandidateOutput {
+        member: RingMember {
+            global_index,
+            key,
+            commitment,
+        },
+        height: r.varint()?,
+        unlock_time: r.varint()?,
+        coinbase: r.u8()? != 0,
+    })
+}
+
+fn point(r: &mut Reader) -> Result<curve25519_dalek::EdwardsPoint, TxError> {
+    CompressedEdwardsY(r.array()?)
+        .decompress()
+        .ok_or(TxError::InvalidPoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::chain::store::tests::{batches, check_contents, check_pop, output};
+    use crate::tx::prefix::tests::RCT_TX_V2;
+
+    /// A fresh path under the temp directory, removed on drop
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let file = format!("monero-rust-{name}-{}", std::process::id());
+            let path = std::env::temp_dir().join(file);
+            let _ = std::fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_reopen() {
+        let path = TempPath::new("reopen");
+        let mut store = FileStore::open(&path.0).unwrap();
+        for batch in batches() {
+            store.write(batch).unwrap();
+        }
+        let out_of_order = WriteBatch::new().put_output(output(9));
+        assert!(store.write(out_of_order).is_err());
+        drop(store);
+
+        let mut store = FileStore::open(&path.0).unwrap();
+        check_contents(&store);
+        check_pop(&mut store);
+        drop(store);
+        let mut reopened = FileStore::open(&path.0).unwrap();
+        let mut memory = MemoryStore::new();
+        for batch in batches() {
+            memory.write(batch).unwrap();
+        }
+        check_pop(&mut memory);
+        assert!(reopened.blocks_from(0).eq(memory.blocks_from(0)));
+        assert_eq!(reopened.num_outputs(), 5);
+        assert!(!reopened.is_spent(&KeyImage([2; 32])));
+        reopened
+            .write(WriteBatch::new().put_output(output(5)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_pruned() {
+        let tx = Transaction::from_hex(RCT_TX_V2).unwrap();
+        let hash = tx.hash();
+        let (full, pruned) = (TempPath::new("full"), TempPath::new("pruned"));
+        let mut full_store = FileStore::open(&full.0).unwrap();
+        let mut pruned_store = FileStore::open_pruned(&pruned.0).unwrap();
+        for store in [&mut full_store, &mut pruned_store] {
+            for batch in batches() {
+                store.write(batch).unwrap();
+            }
+            store
+                .write(WriteBatch::new().put_transaction(tx.clone()))
+                .unwrap();
+        }
+        assert_eq!(
+            full_store.len - pruned_store.len,
+            tx.prunable_bytes().len() as u64 - 64
+        );
+        drop((full_store, pruned_store));
+
+        let store = FileStore::open_pruned(&pruned.0).unwrap();
+        check_contents(&store);
+        assert_eq!(store.transaction(&hash), None);
+        assert_eq!(store.pruned_transaction(&hash), Some(tx.prune()));
+        // a full store pruned on opening
+        let store = FileStore::open_pruned(&full.0).unwrap();
+        assert_eq!(store.transaction(&hash), None);
+        let store = FileStore::open(&full.0).unwrap();
+        assert_eq!(store.transaction(&hash), Some(tx));
+    }
+
+    #[test]
+    fn test_torn_and_corrupt() {
+        let path = TempPath::new("torn");
+        let mut store = FileStore::open(&path.0).unwrap();
+        for batch in batches() {
+            store.write(batch).unwrap();
+        }
+        let len = store.len;
+        store
+            .write(WriteBatch::new().put_output(output(8)))
+            .unwrap();
+        drop(store);
+
+        // the last frame cut short is dropped
+        let file = OpenOptions::new().write(true).open(&path.0).unwrap();
+        file.set_len(len + 10).unwrap();
+        let mut store = FileStore::open(&path.0).unwrap();
+        check_contents(&store);
+        assert_eq!(std::fs::metadata(&path.0).unwrap().len(), len);
+        store
+            .write(WriteBatch::new().put_output(output(8)))
+            .unwrap();
+        assert_eq!(FileStore::open(&path.0).unwrap().num_outputs(), 9);
+
+        // a flipped byte inside a whole frame is reported
+        let mut data = std::fs::read(&path.0).unwrap();
+        data[20] ^= 1;
+        std::fs::write(&path.0, data).unwrap();
+        assert_eq!(
+            FileStore::open(&path.0).unwrap_err(),
+            StoreError::Corrupt(0)
+        );
+    }
+}
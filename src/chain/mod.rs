@@ -0,0 +1,100 @@
+// src/chain/mod.rs
+//! The block chain, as a node keeps it.
+//!
+//! Blocks are checked against the chain they extend: they must link to a
+//! known block, have the version scheduled for their height, carry a sane
+//! timestamp and a miner transaction for their height, follow that
+//! version's transaction rules and weigh at most twice the median of
+//! recent blocks. Blocks off the main chain are kept on side chains, and a
+//! side chain that overtakes the main one in cumulative difficulty
+//! replaces it. Proof of work is hashed by the caller, who has the hash
+//! function for the block's version, and checked against the difficulty
+//! of the block's own chain, so side chains can't win a reorg without it.
+use std::fmt;
+
+use crate::consensus::RuleError;
+use crate::crypto::hash::Hash256;
+
+pub mod blockchain;
+pub mod checkpoints;
+pub mod file_store;
+#[cfg(feature = "sled")]
+pub mod sled_store;
+pub mod store;
+pub mod template;
+
+pub use blockchain::{Blockchain, ChainEntry, ChainEvent, PowHasher};
+pub use checkpoints::{CheckpointError, Checkpoints};
+pub use file_store::FileStore;
+#[cfg(feature = "sled")]
+pub use sled_store::SledStore;
+pub use store::{Blocks, ChainStore, MemoryStore, StoreError, WriteBatch};
+pub use template::{BlockTemplate, TemplateError, create_block_template};
+
+/// Timestamps a block's is checked against, by median
+pub const BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW: usize = 60;
+/// How far ahead of the local clock a block's timestamp may be
+pub const BLOCK_FUTURE_TIME_LIMIT: u64 = 2 * 60 * 60;
+
+/// Why a block wasn't added to the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainError {
+    /// Block already in the chain
+    Duplicate(Hash256),
+    /// Block whose `prev_id` is neither in the chain nor a side chain
+    Orphan(Hash256),
+    /// Timestamp below the median of the last
+    /// [`BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW`] blocks'
+    TimestampTooOld { timestamp: u64, median: u64 },
+    /// Timestamp more than [`BLOCK_FUTURE_TIME_LIMIT`] ahead
+    TimestampInFuture(u64),
+    /// Miner transaction without a single gen input for the block's height
+    InvalidMinerTx,
+    /// Block at a checkpointed height with another id
+    CheckpointMismatch(u64),
+    /// Side chain block at or below a checkpoint the chain has passed
+    BelowCheckpoint(u64),
+    /// Major version other than the one scheduled for the block's height
+    WrongVersion { version: u8, expected: u8 },
+    /// Transactions given that aren't the block's, in its order
+    TransactionMismatch,
+    /// Transaction breaking the rules of the block's version
+    InvalidTransaction(Hash256, RuleError),
+    /// Block over twice the median weight of recent blocks
+    TooHeavy { weight: u64, limit: u64 },
+    /// Proof of work short of the block's difficulty, at this height
+    InsufficientWork(u64),
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::Duplicate(id) => write!(f, "block {} already known", hex::encode(id.0)),
+            ChainError::Orphan(prev) => write!(f, "unknown parent {}", hex::encode(prev.0)),
+            ChainError::TimestampTooOld { timestamp, median } => {
+                write!(f, "timestamp {timestamp} below recent median {median}")
+            }
+            ChainError::TimestampInFuture(t) => write!(f, "timestamp {t} too far in the future"),
+            ChainError::InvalidMinerTx => write!(f, "invalid miner transaction"),
+            ChainError::CheckpointMismatch(h) => write!(f, "block at {h} fails its checkpoint"),
+            ChainError::BelowCheckpoint(h) => {
+                write!(f, "side chain block at {h} is below a checkpoint")
+            }
+            ChainError::WrongVersion { version, expected } => {
+                write!(f, "block version {version}, expected {expected}")
+            }
+            ChainError::InvalidTransaction(hash, e) => {
+                write!(f, "transaction {}: {e}", hex::encode(hash.0))
+            }
+            ChainError::TransactionMismatch => write!(f, "transactions don't match the block"),
+            ChainError::TooHeavy { weight, limit } => {
+                write!(f, "block weight {weight} over the limit of {limit}")
+            }
+            ChainError::InsufficientWork(h) => {
+                write!(f, "block at {h} doesn't meet its difficulty")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
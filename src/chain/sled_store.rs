@@ -0,0 +1,435 @@
+// src/chain/sled_store.rs
+//! [`ChainStore`] in a sled database.
+//!
+//! Records live in one tree, each kind under its own prefix byte: blocks
+//! by big-endian height, block heights by id, transactions and their
+//! prunable data by hash, outputs by big-endian global index and key
+//! images with their spending height. Key images are also listed under
+//! the height spending them, so popping blocks finds them with a range
+//! scan. A batch is staged over the tree, written as one sled batch and
+//! flushed before it shows. A pruned store writes transactions without
+//! their prunable data, and hides the prunable data of any written before.
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+use super::blockchain::ChainEntry;
+use super::file_store::{decode_entry, decode_output, encode_entry, encode_output};
+use super::store::{ChainStore, Op, StoreError, WriteBatch, check_batch};
+use crate::crypto::cn::KeyImage;
+use crate::crypto::hash::{Hash, Hash256};
+use crate::tx::decoys::CandidateOutput;
+use crate::tx::{PrunedTransaction, Reader, Transaction};
+
+const PREFIX_BLOCK: u8 = b'b';
+const PREFIX_BLOCK_HEIGHT: u8 = b'i';
+const PREFIX_TRANSACTION: u8 = b't';
+const PREFIX_PRUNABLE: u8 = b'p';
+const PREFIX_OUTPUT: u8 = b'o';
+const PREFIX_KEY_IMAGE: u8 = b'k';
+/// Key images by spending height: `height || key image`, no value
+const PREFIX_SPENT_AT: u8 = b's';
+
+/// [`ChainStore`] kept in a sled database
+#[derive(Debug)]
+pub struct SledStore {
+    db: sled::Db,
+    height: u64,
+    num_outputs: u64,
+    pruned: bool,
+}
+
+impl From<sled::Error> for StoreError {
+    fn from(e: sled::Error) -> Self {
+        io::Error::from(e).into()
+    }
+}
+
+impl SledStore {
+    /// Open the database at `path`, creating it if it doesn't exist
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Self::open_with(sled::open(path)?, false)
+    }
+
+    /// [`Self::open`] as a pruned store. Transactions written unpruned
+    /// before keep their prunable data on disk but no longer show it.
+    pub fn open_pruned(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Self::open_with(sled::open(path)?, true)
+    }
+
+    fn open_with(db: sled::Db, pruned: bool) -> Result<Self, StoreError> {
+        // one past the last index under `prefix`
+        let next = |prefix: u8| -> Result<u64, StoreError> {
+            match db.scan_prefix([prefix]).next_back().transpose()? {
+                Some((key, _)) => Ok(index(&key)? + 1),
+                None => Ok(0),
+            }
+        };
+        Ok(Self {
+            height: next(PREFIX_BLOCK)?,
+            num_outputs: next(PREFIX_OUTPUT)?,
+            db,
+            pruned,
+        })
+    }
+
+    /// Stage the writes of `batch`, already checked, with the height and
+    /// output count they leave
+    fn stage(&self, batch: WriteBatch) -> Result<(Staged<'_>, u64, u64), StoreError> {
+        let mut staged = Staged::new(&self.db);
+        let (mut height, mut num_outputs) = (self.height, self.num_outputs);
+        for op in batch.ops {
+            match op {
+                Op::Block(entry) => {
+                    let mut value = Vec::new();
+                    encode_entry(&mut value, &entry);
+                    staged.insert(key(PREFIX_BLOCK, &entry.height.to_be_bytes()), value);
+                    staged.insert(
+                        key(PREFIX_BLOCK_HEIGHT, entry.id.as_bytes()),
+                        entry.height.to_be_bytes().to_vec(),
+                    );
+                    height += 1;
+                }
+                Op::Transaction(tx) => {
+                    let hash = tx.hash();
+                    let prunable = tx.prunable_bytes();
+                    // nothing to gain from dropping nothing
+                    if !self.pruned || prunable.is_empty() {
+                        staged.insert(key(PREFIX_PRUNABLE, hash.as_bytes()), prunable);
+                    }
+                    staged.insert_pruned(&hash, &tx.prune());
+                }
+                Op::PrunedTransaction(hash, tx) => staged.insert_pruned(&hash, &tx),
+                Op::Output(output) => {
+                    let mut value = Vec::new();
+                    encode_output(&mut value, &output);
+                    let index = output.member.global_index.to_be_bytes();
+                    staged.insert(key(PREFIX_OUTPUT, &index), value);
+                    num_outputs += 1;
+                }
+                Op::KeyImage(key_image, at) => {
+                    let at = at.to_be_bytes();
+                    staged.insert(key(PREFIX_KEY_IMAGE, key_image.as_bytes()), at.to_vec());
+                    let mut spent_at = key(PREFIX_SPENT_AT, &at);
+                    spent_at.extend_from_slice(key_image.as_bytes());
+                    staged.insert(spent_at, Vec::new());
+                }
+                Op::PopBlocks(to) => {
+                    num_outputs = staged.pop_blocks(to, height, num_outputs)?;
+                    height = to;
+                }
+            }
+        }
+        Ok((staged, height, num_outputs))
+    }
+}
+
+impl ChainStore for SledStore {
+    fn write(&mut self, batch: WriteBatch) -> Result<(), StoreError> {
+        let staged = Staged::new(&self.db);
+        check_batch(&batch, self.height, self.num_outputs, |h| {
+            staged.outputs_below(h, self.num_outputs)
+        })?;
+        let (staged, height, num_outputs) = self.stage(batch)?;
+        self.db.apply_batch(staged.into_batch())?;
+        self.db.flush()?;
+        self.height = height;
+        self.num_outputs = num_outputs;
+        Ok(())
+    }
+
+    fn height(&self) -> u64 {
+        self.height
+    }
+
+    fn block(&self, height: u64) -> Option<ChainEntry> {
+        let value = self
+            .db
+            .get(key(PREFIX_BLOCK, &height.to_be_bytes()))
+            .ok()??;
+        decode_entry(&mut Reader::new(&value)).ok()
+    }
+
+    fn block_height(&self, id: &Hash256) -> Option<u64> {
+        let value = self
+            .db
+            .get(key(PREFIX_BLOCK_HEIGHT, id.as_bytes()))
+            .ok()??;
+        Some(u64::from_be_bytes(value.as_ref().try_into().ok()?))
+    }
+
+    fn transaction(&self, hash: &Hash256) -> Option<Transaction> {
+        let prunable = self.db.get(key(PREFIX_PRUNABLE, hash.as_bytes())).ok()??;
+        if self.pruned && !prunable.is_empty() {
+            return None;
+        }
+        self.pruned_transaction(hash)?.restore(&prunable).ok()
+    }
+
+    fn pruned_transaction(&self, hash: &Hash256) -> Option<PrunedTransaction> {
+        let value = self
+            .db
+            .get(key(PREFIX_TRANSACTION, hash.as_bytes()))
+            .ok()??;
+        let prunable_hash = Hash(value.get(..32)?.try_into().ok()?);
+        PrunedTransaction::from_blob(&value[32..], prunable_hash).ok()
+    }
+
+    fn is_pruned(&self) -> bool {
+        self.pruned
+    }
+
+    fn num_outputs(&self) -> u64 {
+        self.num_outputs
+    }
+
+    fn output(&self, global_index: u64) -> Option<CandidateOutput> {
+        let value = self
+            .db
+            .get(key(PREFIX_OUTPUT, &global_index.to_be_bytes()))
+            .ok()??;
+        decode_output(&mut Reader::new(&value)).ok()
+    }
+
+    fn is_spent(&self, key_image: &KeyImage) -> bool {
+        self.db
+            .contains_key(key(PREFIX_KEY_IMAGE, key_image.as_bytes()))
+            .unwrap_or(false)
+    }
+}
+
+/// Writes over the tree not yet applied, read back before the tree's
+/// contents; `None` removes a key
+struct Staged<'a> {
+    tree: &'a sled::Tree,
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<'a> Staged<'a> {
+    fn new(tree: &'a sled::Tree) -> Self {
+        Self {
+            tree,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        match self.writes.get(key) {
+            Some(value) => Ok(value.clone()),
+            None => Ok(self.tree.get(key)?.map(|v| v.to_vec())),
+        }
+    }
+
+    fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.writes.insert(key, Some(value));
+    }
+
+    fn remove(&mut self, key: Vec<u8>) {
+        self.writes.insert(key, None);
+    }
+
+    fn insert_pruned(&mut self, hash: &Hash256, tx: &PrunedTransaction) {
+        let mut value = tx.prunable_hash.as_bytes().to_vec();
+        tx.serialize(&mut value);
+        self.insert(key(PREFIX_TRANSACTION, hash.as_bytes()), value);
+    }
+
+    /// Keys from `start` on that share its prefix byte, in order
+    fn keys_from(&self, start: &[u8]) -> Result<Vec<Vec<u8>>, StoreError> {
+        let prefix = start[0];
+        let mut keys = BTreeMap::new();
+        for item in self.tree.range(start..) {
+            let (key, _) = item?;
+            if key[0] != prefix {
+                break;
+            }
+            keys.insert(key.to_vec(), ());
+        }
+        for (key, value) in self.writes.range(start.to_vec()..) {
+            if key[0] != prefix {
+                break;
+            }
+            match value {
+                Some(_) => keys.insert(key.clone(), ()),
+                None => keys.remove(key),
+            };
+        }
+        Ok(keys.into_keys().collect())
+    }
+
+    /// Of the first `num_outputs` outputs, those created below `height`;
+    /// they come in height order
+    fn outputs_below(&self, height: u64, num_outputs: u64) -> Result<u64, StoreError> {
+        let (mut lo, mut hi) = (0, num_outputs);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let value = self
+                .get(&key(PREFIX_OUTPUT, &mid.to_be_bytes()))?
+                .ok_or(StoreError::Io(io::ErrorKind::InvalidData))?;
+            let output = decode_output(&mut Reader::new(&value))
+                .map_err(|_| StoreError::Io(io::ErrorKind::InvalidData))?;
+            match output.height < height {
+                true => lo = mid + 1,
+                false => hi = mid,
+            }
+        }
+        Ok(lo)
+    }
+
+    /// Remove the blocks from `to` up to `height`, with their
+    /// transactions, outputs and key images; the outputs left
+    fn pop_blocks(&mut self, to: u64, height: u64, num_outputs: u64) -> Result<u64, StoreError> {
+        let corrupt = StoreError::Io(io::ErrorKind::InvalidData);
+        for h in to..height {
+            let block_key = key(PREFIX_BLOCK, &h.to_be_bytes());
+            let value = self.get(&block_key)?.ok_or(corrupt)?;
+            let entry = decode_entry(&mut Reader::new(&value)).map_err(|_| corrupt)?;
+            self.remove(block_key);
+            self.remove(key(PREFIX_BLOCK_HEIGHT, entry.id.as_bytes()));
+            let miner_tx = entry.block.miner_tx.hash();
+            for hash in entry.block.tx_hashes.iter().chain([&miner_tx]) {
+                self.remove(key(PREFIX_TRANSACTION, hash.as_bytes()));
+                self.remove(key(PREFIX_PRUNABLE, hash.as_bytes()));
+            }
+        }
+        let kept = self.outputs_below(to, num_outputs)?;
+        for i in kept..num_outputs {
+            self.remove(key(PREFIX_OUTPUT, &i.to_be_bytes()));
+        }
+        for spent_at in self.keys_from(&key(PREFIX_SPENT_AT, &to.to_be_bytes()))? {
+            self.remove(key(PREFIX_KEY_IMAGE, &spent_at[9..]));
+            self.remove(spent_at);
+        }
+        Ok(kept)
+    }
+
+    fn into_batch(self) -> sled::Batch {
+        let mut batch = sled::Batch::default();
+        for (key, value) in self.writes {
+            match value {
+                Some(value) => batch.insert(key, value),
+                None => batch.remove(key),
+            }
+        }
+        batch
+    }
+}
+
+fn key(prefix: u8, id: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + id.len());
+    key.push(prefix);
+    key.extend_from_slice(id);
+    key
+}
+
+/// Big-endian height or global index of a block or output key
+fn index(key: &[u8]) -> Result<u64, StoreError> {
+    key.get(1..9)
+        .and_then(|b| b.try_into().ok())
+        .map(u64::from_be_bytes)
+        .ok_or(StoreError::Io(io::ErrorKind::InvalidData))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::chain::store::MemoryStore;
+    use crate::chain::store::tests::{batches, check_contents, check_pop, output};
+    use crate::tx::prefix::tests::RCT_TX_V2;
+
+    /// A fresh directory path under the temp directory, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = format!("monero-rust-sled-{name}-{}", std::process::id());
+            let path = std::env::temp_dir().join(dir);
+            let _ = std::fs::remove_dir_all(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_reopen() {
+        let dir = TempDir::new("reopen");
+        let mut store = SledStore::open(&dir.0).unwrap();
+        for batch in batches() {
+            store.write(batch).unwrap();
+        }
+        let out_of_order = WriteBatch::new().put_output(output(9));
+        assert!(store.write(out_of_order).is_err());
+        drop(store);
+
+        let mut store = SledStore::open(&dir.0).unwrap();
+        check_contents(&store);
+        check_pop(&mut store);
+        drop(store);
+        let mut reopened = SledStore::open(&dir.0).unwrap();
+        let mut memory = MemoryStore::new();
+        for batch in batches() {
+            memory.write(batch).unwrap();
+        }
+        check_pop(&mut memory);
+        assert!(reopened.blocks_from(0).eq(memory.blocks_from(0)));
+        assert_eq!(reopened.num_outputs(), 5);
+        assert!(!reopened.is_spent(&KeyImage([2; 32])));
+        reopened
+            .write(WriteBatch::new().put_output(output(5)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_pruned() {
+        let tx = Transaction::from_hex(RCT_TX_V2).unwrap();
+        let hash = tx.hash();
+        let (full, pruned) = (TempDir::new("full"), TempDir::new("pruned"));
+        for (dir, pruned) in [(&full, false), (&pruned, true)] {
+            let mut store = match pruned {
+                true => SledStore::open_pruned(&dir.0).unwrap(),
+                false => SledStore::open(&dir.0).unwrap(),
+            };
+            for batch in batches() {
+                store.write(batch).unwrap();
+            }
+            store
+                .write(WriteBatch::new().put_transaction(tx.clone()))
+                .unwrap();
+        }
+
+        let store = SledStore::open_pruned(&pruned.0).unwrap();
+        check_contents(&store);
+        assert_eq!(store.transaction(&hash), None);
+        assert_eq!(store.pruned_transaction(&hash), Some(tx.prune()));
+        // a full store opened pruned
+        let store = SledStore::open_pruned(&full.0).unwrap();
+        assert_eq!(store.transaction(&hash), None);
+        drop(store);
+        let store = SledStore::open(&full.0).unwrap();
+        assert_eq!(store.transaction(&hash), Some(tx));
+    }
+
+    #[test]
+    fn test_pop_in_batch() {
+        // key images and outputs added earlier in the same batch go too
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let mut store = SledStore::open_with(db, false).unwrap();
+        let mut all = WriteBatch::new();
+        for batch in batches() {
+            all.ops.extend(batch.ops);
+        }
+        store.write(all.pop_blocks(2)).unwrap();
+        assert_eq!(store.height(), 2);
+        assert_eq!(store.num_outputs(), 4);
+        assert!(store.is_spent(&KeyImage([1; 32])));
+        assert!(!store.is_spent(&KeyImage([2; 32])));
+        assert_eq!(store.block(2), None);
+    }
+}
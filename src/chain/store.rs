@@ -0,0 +1,499 @@
+// src/chain/store.rs
+//! Where a node keeps the chain.
+//!
+//! A [`ChainStore`] holds blocks by height, transactions by hash, outputs
+//! by global index and the key images already spent. Writes come in
+//! [`WriteBatch`]es, applied whole or not at all, so a block never lands
+//! without its transactions and a reorg never stops halfway.
+//!
+//! A pruned store keeps transactions without their prunable data: the
+//! range proofs and ring signatures a node only needs to check them once.
+//! That is most of a transaction, so about two thirds of the chain.
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+use super::blockchain::ChainEntry;
+use crate::crypto::cn::KeyImage;
+use crate::crypto::hash::Hash256;
+use crate::tx::decoys::CandidateOutput;
+use crate::tx::{PrunedTransaction, Transaction};
+
+/// Why a store couldn't be read or written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreError {
+    Io(io::ErrorKind),
+    /// Stored data that doesn't decode, at this byte offset
+    Corrupt(u64),
+    /// Block or output not next in line: its height or global index, and
+    /// the one expected
+    OutOfOrder {
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io(kind) => write!(f, "I/O error: {kind}"),
+            StoreError::Corrupt(offset) => write!(f, "corrupt record at offset {offset}"),
+            StoreError::OutOfOrder { expected, found } => {
+                write!(f, "expected entry {expected}, got {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> Self {
+        StoreError::Io(e.kind())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Op {
+    Block(ChainEntry),
+    Transaction(Transaction),
+    PrunedTransaction(Hash256, PrunedTransaction),
+    Output(CandidateOutput),
+    KeyImage(KeyImage, u64),
+    PopBlocks(u64),
+}
+
+/// Writes to apply together
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteBatch {
+    pub(crate) ops: Vec<Op>,
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a block; it must be at the store's height
+    pub fn put_block(mut self, entry: ChainEntry) -> Self {
+        self.ops.push(Op::Block(entry));
+        self
+    }
+
+    pub fn put_transaction(mut self, tx: Transaction) -> Self {
+        self.ops.push(Op::Transaction(tx));
+        self
+    }
+
+    /// Add a transaction already pruned, with its id
+    pub fn put_pruned_transaction(mut self, hash: Hash256, tx: PrunedTransaction) -> Self {
+        self.ops.push(Op::PrunedTransaction(hash, tx));
+        self
+    }
+
+    /// Append an output; its global index must be the store's output count
+    pub fn put_output(mut self, output: CandidateOutput) -> Self {
+        self.ops.push(Op::Output(output));
+        self
+    }
+
+    /// Mark a key image spent in the block at `height`
+    pub fn put_key_image(mut self, key_image: KeyImage, height: u64) -> Self {
+        self.ops.push(Op::KeyImage(key_image, height));
+        self
+    }
+
+    /// Roll back the blocks from `height` up, with their transactions,
+    /// outputs and key images, as a reorg does
+    pub fn pop_blocks(mut self, height: u64) -> Self {
+        self.ops.push(Op::PopBlocks(height));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+/// Blocks, transactions, outputs and key images of one chain
+pub trait ChainStore {
+    /// Apply every write in `batch`, or none if one is out of order or
+    /// pops blocks above the top
+    fn write(&mut self, batch: WriteBatch) -> Result<(), StoreError>;
+
+    /// Number of blocks stored
+    fn height(&self) -> u64;
+
+    fn block(&self, height: u64) -> Option<ChainEntry>;
+
+    fn block_height(&self, id: &Hash256) -> Option<u64>;
+
+    /// A transaction, if its prunable data is kept
+    fn transaction(&self, hash: &Hash256) -> Option<Transaction>;
+
+    /// A transaction without its prunable data, whether or not it is kept
+    fn pruned_transaction(&self, hash: &Hash256) -> Option<PrunedTransaction>;
+
+    /// Whether prunable data is dropped as transactions are written
+    fn is_pruned(&self) -> bool;
+
+    /// Number of outputs stored
+    fn num_outputs(&self) -> u64;
+
+    fn output(&self, global_index: u64) -> Option<CandidateOutput>;
+
+    fn is_spent(&self, key_image: &KeyImage) -> bool;
+
+    /// Blocks from `height` to the top, in order
+    fn blocks_from(&self, height: u64) -> Blocks<'_, Self>
+    where
+        Self: Sized,
+    {
+        Blocks {
+            store: self,
+            height,
+        }
+    }
+}
+
+/// Iterator over stored blocks by height, from [`ChainStore::blocks_from`]
+pub struct Blocks<'a, S> {
+    store: &'a S,
+    height: u64,
+}
+
+impl<S: ChainStore> Iterator for Blocks<'_, S> {
+    type Item = ChainEntry;
+
+    fn next(&mut self) -> Option<ChainEntry> {
+        let entry = self.store.block(self.height)?;
+        self.height += 1;
+        Some(entry)
+    }
+}
+
+/// Ensure every write of `batch` would apply to a store of `height`
+/// blocks and `outputs` outputs, `outputs_below(h)` of them created below
+/// height `h`
+pub(crate) fn check_batch(
+    batch: &WriteBatch,
+    mut height: u64,
+    mut outputs: u64,
+    outputs_below: impl Fn(u64) -> Result<u64, StoreError>,
+) -> Result<(), StoreError> {
+    // lowest height popped, and heights of the outputs added since
+    let (mut popped, mut added) = (u64::MAX, Vec::new());
+    for op in &batch.ops {
+        let (next, found) = match op {
+            Op::Block(entry) => (&mut height, entry.height),
+            Op::Output(output) => {
+                added.push(output.height);
+                (&mut outputs, output.member.global_index)
+            }
+            Op::PopBlocks(h) if *h > height => {
+                return Err(StoreError::OutOfOrder {
+                    expected: height,
+                    found: *h,
+                });
+            }
+            Op::PopBlocks(h) => {
+                height = *h;
+                popped = popped.min(*h);
+                added.retain(|&a| a < *h);
+                outputs = outputs_below(popped)? + added.len() as u64;
+                continue;
+            }
+            _ => continue,
+        };
+        if found != *next {
+            return Err(StoreError::OutOfOrder {
+                expected: *next,
+                found,
+            });
+        }
+        *next += 1;
+    }
+    Ok(())
+}
+
+/// [`ChainStore`] in memory, gone with the process
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    blocks: Vec<ChainEntry>,
+    heights: HashMap<Hash256, u64>,
+    transactions: HashMap<Hash256, PrunedTransaction>,
+    /// Prunable data of the transactions that kept theirs
+    prunable: HashMap<Hash256, Vec<u8>>,
+    pruned: bool,
+    outputs: Vec<CandidateOutput>,
+    /// Spent key images, with the height of the block spending them
+    key_images: HashMap<KeyImage, u64>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A store dropping the prunable data of the transactions written
+    pub fn pruned() -> Self {
+        Self {
+            pruned: true,
+            ..Self::default()
+        }
+    }
+
+    /// Ensure every write of `batch` would apply
+    pub(crate) fn check(&self, batch: &WriteBatch) -> Result<(), StoreError> {
+        check_batch(batch, self.height(), self.num_outputs(), |h| {
+            Ok(self.outputs_below(h))
+        })
+    }
+
+    /// Apply `batch`, already checked
+    pub(crate) fn apply(&mut self, batch: WriteBatch) {
+        for op in batch.ops {
+            match op {
+                Op::Block(entry) => {
+                    self.heights.insert(entry.id, entry.height);
+                    self.blocks.push(entry);
+                }
+                Op::Transaction(tx) => {
+                    let hash = tx.hash();
+                    let prunable = tx.prunable_bytes();
+                    // nothing to gain from dropping nothing
+                    if !self.pruned || prunable.is_empty() {
+                        self.prunable.insert(hash, prunable);
+                    }
+                    self.transactions.insert(hash, tx.prune());
+                }
+                Op::PrunedTransaction(hash, tx) => {
+                    self.transactions.insert(hash, tx);
+                }
+                Op::Output(output) => self.outputs.push(output),
+                Op::KeyImage(key_image, height) => {
+                    self.key_images.insert(key_image, height);
+                }
+                Op::PopBlocks(height) => self.pop_blocks(height),
+            }
+        }
+    }
+
+    /// Outputs created below `height`; they come in height order
+    fn outputs_below(&self, height: u64) -> u64 {
+        self.outputs.partition_point(|o| o.height < height) as u64
+    }
+
+    fn pop_blocks(&mut self, height: u64) {
+        for entry in self.blocks.drain(height as usize..) {
+            self.heights.remove(&entry.id);
+            let miner_tx = entry.block.miner_tx.hash();
+            for hash in entry.block.tx_hashes.iter().chain([&miner_tx]) {
+                self.transactions.remove(hash);
+                self.prunable.remove(hash);
+            }
+        }
+        self.outputs.truncate(self.outputs_below(height) as usize);
+        self.key_images.retain(|_, h| *h < height);
+    }
+}
+
+impl ChainStore for MemoryStore {
+    fn write(&mut self, batch: WriteBatch) -> Result<(), StoreError> {
+        self.check(&batch)?;
+        self.apply(batch);
+        Ok(())
+    }
+
+    fn height(&self) -> u64 {
+        self.blocks.len() as u64
+    }
+
+    fn block(&self, height: u64) -> Option<ChainEntry> {
+        self.blocks.get(usize::try_from(height).ok()?).cloned()
+    }
+
+    fn block_height(&self, id: &Hash256) -> Option<u64> {
+        self.heights.get(id).copied()
+    }
+
+    fn transaction(&self, hash: &Hash256) -> Option<Transaction> {
+        let prunable = self.prunable.get(hash)?;
+        self.transactions.get(hash)?.restore(prunable).ok()
+    }
+
+    fn pruned_transaction(&self, hash: &Hash256) -> Option<PrunedTransaction> {
+        self.transactions.get(hash).cloned()
+    }
+
+    fn is_pruned(&self) -> bool {
+        self.pruned
+    }
+
+    fn num_outputs(&self) -> u64 {
+        self.outputs.len() as u64
+    }
+
+    fn output(&self, global_index: u64) -> Option<CandidateOutput> {
+        self.outputs
+            .get(usize::try_from(global_index).ok()?)
+            .copied()
+    }
+
+    fn is_spent(&self, key_image: &KeyImage) -> bool {
+        self.key_images.contains_key(key_image)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use curve25519_dalek::{EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT as G};
+
+    use super::*;
+    use crate::chain::blockchain::tests::chain_of;
+    use crate::tx::prefix::tests::RCT_TX_V2;
+    use crate::tx::rct::RingMember;
+
+    pub(crate) fn output(global_index: u64) -> CandidateOutput {
+        let key: EdwardsPoint = G * Scalar::from(global_index + 1);
+        CandidateOutput {
+            member: RingMember {
+                global_index,
+                key,
+                commitment: key + G,
+            },
+            height: global_index / 2,
+            unlock_time: 0,
+            coinbase: global_index.is_multiple_of(2),
+        }
+    }
+
+    /// A batch per block of a short chain, each with its miner transaction,
+    /// two outputs and a key image
+    pub(crate) fn batches() -> Vec<WriteBatch> {
+        let chain = chain_of(3);
+        (0..chain.height())
+            .map(|h| {
+                let entry = chain.entry(h).unwrap().clone();
+                WriteBatch::new()
+                    .put_transaction(entry.block.miner_tx.clone())
+                    .put_block(entry)
+                    .put_output(output(2 * h))
+                    .put_output(output(2 * h + 1))
+                    .put_key_image(KeyImage([h as u8; 32]), h)
+            })
+            .collect()
+    }
+
+    pub(crate) fn check_contents(store: &impl ChainStore) {
+        let chain = chain_of(3);
+        assert_eq!(store.height(), 4);
+        assert_eq!(store.num_outputs(), 8);
+        let top = chain.top();
+        assert_eq!(store.block(3).as_ref(), Some(top));
+        assert_eq!(store.block_height(&top.id), Some(3));
+        let miner_tx = &top.block.miner_tx;
+        assert_eq!(store.transaction(&miner_tx.hash()).as_ref(), Some(miner_tx));
+        assert_eq!(store.output(5), Some(output(5)));
+        assert_eq!(store.output(8), None);
+        assert!(store.is_spent(&KeyImage([2; 32])));
+        assert!(!store.is_spent(&KeyImage([9; 32])));
+        let heights: Vec<_> = store.blocks_from(1).map(|e| e.height).collect();
+        assert_eq!(heights, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_memory_store() {
+        let mut store = MemoryStore::new();
+        for batch in batches() {
+            store.write(batch).unwrap();
+        }
+        check_contents(&store);
+
+        // a batch with a gap is rejected whole
+        let batch = WriteBatch::new()
+            .put_key_image(KeyImage([9; 32]), 4)
+            .put_output(output(9));
+        assert_eq!(
+            store.write(batch),
+            Err(StoreError::OutOfOrder {
+                expected: 8,
+                found: 9
+            })
+        );
+        assert!(!store.is_spent(&KeyImage([9; 32])));
+    }
+
+    #[test]
+    fn test_pruned() {
+        let tx = Transaction::from_hex(RCT_TX_V2).unwrap();
+        let hash = tx.hash();
+        for mut store in [MemoryStore::new(), MemoryStore::pruned()] {
+            for batch in batches() {
+                store.write(batch).unwrap();
+            }
+            store
+                .write(WriteBatch::new().put_transaction(tx.clone()))
+                .unwrap();
+            // coinbase transactions have nothing to prune
+            check_contents(&store);
+            assert_eq!(store.pruned_transaction(&hash), Some(tx.prune()));
+            match store.is_pruned() {
+                true => assert_eq!(store.transaction(&hash), None),
+                false => assert_eq!(store.transaction(&hash), Some(tx.clone())),
+            }
+        }
+
+        let mut store = MemoryStore::new();
+        let batch = WriteBatch::new().put_pruned_transaction(hash, tx.prune());
+        store.write(batch).unwrap();
+        assert_eq!(store.transaction(&hash), None);
+        assert_eq!(store.pruned_transaction(&hash), Some(tx.prune()));
+    }
+
+    /// `store` holding [`batches`] rolls back to block 1 and takes a new
+    /// block 2
+    pub(crate) fn check_pop(store: &mut impl ChainStore) {
+        let popped_tx = store.block(3).unwrap().block.miner_tx;
+        let mut entry = store.block(2).unwrap();
+        entry.block.header.nonce ^= 1;
+        entry.id = entry.block.hash();
+        let batch = WriteBatch::new()
+            .pop_blocks(2)
+            .put_block(entry.clone())
+            .put_output(output(4))
+            .put_key_image(KeyImage([7; 32]), 2);
+        store.write(batch).unwrap();
+
+        assert_eq!(store.height(), 3);
+        assert_eq!(store.block(2), Some(entry));
+        assert_eq!(store.block(3), None);
+        assert_eq!(store.num_outputs(), 5);
+        assert!(store.is_spent(&KeyImage([1; 32])));
+        assert!(!store.is_spent(&KeyImage([2; 32])));
+        assert!(store.is_spent(&KeyImage([7; 32])));
+        assert_eq!(store.transaction(&popped_tx.hash()), None);
+
+        let too_high = WriteBatch::new().pop_blocks(4);
+        assert_eq!(
+            store.write(too_high),
+            Err(StoreError::OutOfOrder {
+                expected: 3,
+                found: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_pop_blocks() {
+        let mut store = MemoryStore::new();
+        for batch in batches() {
+            store.write(batch).unwrap();
+        }
+        check_pop(&mut store);
+    }
+}
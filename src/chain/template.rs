@@ -0,0 +1,321 @@
+// src/chain/template.rs
+//! Candidate blocks for miners (`get_block_template`).
+//!
+//! A template extends the top of the chain with the best-paying pool
+//! transactions that fit without a reward penalty, and a miner transaction
+//! paying the reward and their fees to one address. The caller's extra
+//! nonce ends the miner transaction's extra, so a pool can rewrite it in
+//! place at [`BlockTemplate::reserved_offset`] without moving anything
+//! else.
+use std::collections::HashSet;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use curve25519_dalek::{Scalar, constants::ED25519_BASEPOINT_POINT};
+use rand::rngs::OsRng;
+
+use super::Blockchain;
+use crate::address::{Address, AddressKind};
+use crate::block::{Block, BlockHeader};
+use crate::consensus::hardfork::{RCT_VERSION, VIEW_TAGS_VERSION, check_transaction};
+use crate::consensus::reward::base_reward;
+use crate::consensus::weight::COINBASE_BLOB_RESERVED_SIZE;
+use crate::crypto::hash::Hash256;
+use crate::crypto::keys::{derive_public_key, derive_view_tag, generate_key_derivation};
+use crate::crypto::pow::randomx_seed_height;
+use crate::network::Network;
+use crate::tx::unlock::COINBASE_MATURITY;
+use crate::tx::{
+    Extra, ExtraField, ExtraNonce, RctSig, Transaction, TransactionPrefix, TxIn, TxInGen, TxOut,
+};
+
+/// Longest extra nonce a transaction may carry
+pub const MAX_EXTRA_NONCE_SIZE: usize = 255;
+
+/// Why a template couldn't be made
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateError {
+    /// Miners can't be paid at subaddresses
+    Subaddress,
+    /// Address for another network than the chain's
+    WrongNetwork(Network),
+    /// Extra nonce longer than [`MAX_EXTRA_NONCE_SIZE`]
+    ExtraNonceTooLong(usize),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Subaddress => write!(f, "cannot mine to a subaddress"),
+            TemplateError::WrongNetwork(n) => write!(f, "address is for {n:?}"),
+            TemplateError::ExtraNonceTooLong(len) => write!(f, "extra nonce of {len} bytes"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// A block to mine, with what a pool needs to hand it out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockTemplate {
+    pub block: Block,
+    pub height: u64,
+    pub difficulty: u128,
+    /// Amount of the miner transaction: reward plus fees
+    pub expected_reward: u64,
+    /// Offset of the extra nonce in [`Self::blob`]
+    pub reserved_offset: usize,
+    /// Height and id of the block keying RandomX
+    pub seed_height: u64,
+    pub seed_hash: Hash256,
+}
+
+impl BlockTemplate {
+    /// The block blob, as `get_block_template` returns it
+    pub fn blob(&self) -> Vec<u8> {
+        self.block.to_bytes()
+    }
+
+    /// What miners hash, nonce included
+    pub fn hashing_blob(&self) -> Vec<u8> {
+        self.block.hashing_blob()
+    }
+}
+
+/// A block on top of `chain` paying `address`, with transactions from
+/// `pool` and `extra_nonce` in the miner transaction. Pool transactions
+/// are taken as valid; of two spending the same key image only the better
+/// paying is included.
+pub fn create_block_template(
+    chain: &Blockchain,
+    pool: &[Transaction],
+    address: &Address,
+    extra_nonce: &[u8],
+) -> Result<BlockTemplate, TemplateError> {
+    if address.kind() == AddressKind::Subaddress {
+        return Err(TemplateError::Subaddress);
+    }
+    if address.network() != chain.network() {
+        return Err(TemplateError::WrongNetwork(address.network()));
+    }
+    if extra_nonce.len() > MAX_EXTRA_NONCE_SIZE {
+        return Err(TemplateError::ExtraNonceTooLong(extra_nonce.len()));
+    }
+
+    let top = chain.top();
+    let height = chain.height();
+    // the minor version votes for the latest version this chain knows
+    let (major_version, minor_version) = (chain.next_version(), chain.hard_forks().latest());
+    let median = chain.next_median_weight(major_version);
+    let (tx_hashes, fees) = select_transactions(pool, major_version, median);
+    let expected_reward = base_reward(top.generated_coins, major_version).saturating_add(fees);
+    let miner_tx = miner_transaction(address, height, major_version, expected_reward, extra_nonce);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let header = BlockHeader {
+        major_version,
+        minor_version,
+        timestamp: now.max(chain.median_timestamp().unwrap_or(0)),
+        prev_id: top.id,
+        nonce: 0,
+    };
+    let reserved_offset =
+        header.to_bytes().len() + miner_tx.prefix.to_bytes().len() - extra_nonce.len();
+    let seed_height = randomx_seed_height(height);
+    Ok(BlockTemplate {
+        block: Block {
+            header,
+            miner_tx,
+            tx_hashes,
+        },
+        height,
+        difficulty: chain.next_difficulty(major_version),
+        expected_reward,
+        reserved_offset,
+        seed_height,
+        seed_hash: chain.entry(seed_height).expect("seed is below the top").id,
+    })
+}
+
+/// Hashes of the pool transactions to include, best fee per weight first,
+/// and their fees. They follow the rules of `major_version` and stay under
+/// `median_weight` with the miner transaction, so the reward isn't
+/// penalised.
+fn select_transactions(
+    pool: &[Transaction],
+    major_version: u8,
+    median_weight: u64,
+) -> (Vec<Hash256>, u64) {
+    let mut candidates: Vec<_> = pool
+        .iter()
+        .filter(|tx| !tx.prefix.is_coinbase())
+        .filter(|tx| check_transaction(tx, major_version).is_ok())
+        .filter_map(|tx| Some((tx, tx.fee()?, tx.weight() as u64)))
+        .collect();
+    candidates.sort_by(|(_, fee_a, weight_a), (_, fee_b, weight_b)| {
+        (*fee_b as u128 * *weight_a as u128).cmp(&(*fee_a as u128 * *weight_b as u128))
+    });
+
+    let limit = median_weight - COINBASE_BLOB_RESERVED_SIZE;
+    let (mut hashes, mut fees, mut weight) = (Vec::new(), 0u64, 0);
+    let mut spent = HashSet::new();
+    for (tx, fee, tx_weight) in candidates {
+        if weight + tx_weight > limit {
+            continue;
+        }
+        let key_images: HashSet<_> = tx.prefix.key_images().collect();
+        if !key_images.is_disjoint(&spent) {
+            continue;
+        }
+        spent.extend(key_images);
+        weight += tx_weight;
+        fees = fees.saturating_add(fee);
+        hashes.push(tx.hash());
+    }
+    (hashes, fees)
+}
+
+/// Coinbase paying `amount` to `address`, locked for
+/// [`COINBASE_MATURITY`] blocks
+pub(crate) fn miner_transaction(
+    address: &Address,
+    height: u64,
+    major_version: u8,
+    amount: u64,
+    extra_nonce: &[u8],
+) -> Transaction {
+    let tx_key = Scalar::random(&mut OsRng);
+    let derivation = generate_key_derivation(address.view_public(), &tx_key);
+    let key = derive_public_key(&derivation, 0, address.spend_public()).compress();
+    let output = match major_version >= VIEW_TAGS_VERSION {
+        true => TxOut::to_tagged_key(amount, key, derive_view_tag(&derivation, 0)),
+        false => TxOut::to_key(amount, key),
+    };
+    let mut extra = Extra::new().with(ExtraField::PublicKey(
+        (tx_key * ED25519_BASEPOINT_POINT).compress(),
+    ));
+    if !extra_nonce.is_empty() {
+        extra.push(ExtraField::Nonce(ExtraNonce::Other(extra_nonce.to_vec())));
+    }
+    Transaction {
+        prefix: TransactionPrefix {
+            version: if major_version >= RCT_VERSION { 2 } else { 1 },
+            unlock_time: height + COINBASE_MATURITY,
+            vin: vec![TxIn::Gen(TxInGen { height })],
+            vout: vec![output],
+            extra: extra.to_bytes().expect("extra nonce fits"),
+        },
+        signatures: Vec::new(),
+        rct_signatures: RctSig::null(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::ChainEvent;
+    use crate::chain::blockchain::tests::{AnyWork, chain_of};
+    use crate::crypto::cn::KeyImage;
+    use crate::crypto::keys::WalletKeys;
+    use crate::tx::{RctType, TxInToKey};
+
+    fn pool_tx(key_image: u8, fee: u64, extra_len: usize) -> Transaction {
+        let mut rct_signatures = RctSig::null();
+        rct_signatures.base.rct_type = RctType::BulletproofPlus;
+        rct_signatures.base.fee = fee;
+        Transaction {
+            prefix: TransactionPrefix {
+                version: 2,
+                unlock_time: 0,
+                vin: vec![TxIn::ToKey(TxInToKey {
+                    amount: 0,
+                    key_offsets: vec![1; 16],
+                    key_image: KeyImage([key_image; 32]),
+                })],
+                vout: Vec::new(),
+                extra: vec![0; extra_len],
+            },
+            signatures: Vec::new(),
+            rct_signatures,
+        }
+    }
+
+    #[test]
+    fn test_template() {
+        let mut chain = chain_of(3);
+        let keys = WalletKeys::generate();
+        let address = Address::from_keys(&keys, Network::Stagenet);
+        let template = create_block_template(&chain, &[], &address, &[0; 8]).unwrap();
+        assert_eq!(template.height, 4);
+        assert_eq!(template.seed_height, 0);
+        assert_eq!(
+            template.expected_reward,
+            base_reward(chain.top().generated_coins, 16)
+        );
+
+        // the reserved bytes are the extra nonce, at the end of the extra
+        let mut blob = template.blob();
+        let offset = template.reserved_offset;
+        assert_eq!(blob[offset..offset + 8], [0; 8]);
+        blob[offset..offset + 8].copy_from_slice(b"poolnonc");
+        let block = Block::from_bytes(&blob).unwrap();
+        let extra = block.miner_tx.prefix.parse_extra();
+        assert_eq!(
+            extra.nonce(),
+            Some(&ExtraNonce::Other(b"poolnonc".to_vec()))
+        );
+
+        let miner_tx = &template.block.miner_tx;
+        assert_eq!(miner_tx.prefix.unlock_time, 64);
+        let output = &miner_tx.prefix.vout[0];
+        assert_eq!(output.amount, template.expected_reward);
+        assert!(output.target.view_tag().is_some());
+        let tx_pub = extra.tx_public_key().unwrap().decompress().unwrap();
+        let key = output.target.key().decompress().unwrap();
+        assert!(keys.owns_output(&tx_pub, 0, &key));
+
+        let event = chain
+            .add_block_at(template.block, &[], &mut AnyWork, u64::MAX)
+            .unwrap();
+        assert!(matches!(event, ChainEvent::Extended(_)));
+
+        let subaddress = Address::subaddress(&keys, Network::Stagenet, 0, 1);
+        assert_eq!(
+            create_block_template(&chain, &[], &subaddress, &[]),
+            Err(TemplateError::Subaddress)
+        );
+        let mainnet = Address::from_keys(&keys, Network::Mainnet);
+        assert_eq!(
+            create_block_template(&chain, &[], &mainnet, &[]),
+            Err(TemplateError::WrongNetwork(Network::Mainnet))
+        );
+        assert_eq!(
+            create_block_template(&chain, &[], &address, &[0; 256]),
+            Err(TemplateError::ExtraNonceTooLong(256))
+        );
+    }
+
+    #[test]
+    fn test_selection() {
+        let chain = chain_of(3);
+        let address = Address::from_keys(&WalletKeys::generate(), Network::Stagenet);
+        let small = pool_tx(1, 10, 50);
+        let big = pool_tx(2, 5000, 150_000);
+        let too_big = pool_tx(3, 1000, 160_000);
+        let double_spend = pool_tx(2, 3000, 120_000);
+        // pays best, but with a ring of 11 it's invalid at v16
+        let mut old_ring = pool_tx(4, 100_000, 50);
+        old_ring.prefix.vin = vec![TxIn::ToKey(TxInToKey {
+            amount: 0,
+            key_offsets: vec![1; 11],
+            key_image: KeyImage([4; 32]),
+        })];
+        let pool = [too_big, small.clone(), double_spend, big.clone(), old_ring];
+        let template = create_block_template(&chain, &pool, &address, &[]).unwrap();
+        assert_eq!(template.block.tx_hashes, [small.hash(), big.hash()]);
+        let reward = base_reward(chain.top().generated_coins, 16);
+        assert_eq!(template.expected_reward, reward + 5010);
+    }
+}
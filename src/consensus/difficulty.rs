@@ -0,0 +1,135 @@
+// src/consensus/difficulty.rs
+//! Difficulty adjustment (`next_difficulty`).
+//!
+//! The next block's difficulty is the work done over the window divided by
+//! the time it took, scaled to the target block time. The window is the
+//! [`DIFFICULTY_WINDOW`] blocks ending [`DIFFICULTY_LAG`] blocks back; its
+//! timestamps are sorted and the [`DIFFICULTY_CUT`] outliers at each end
+//! dropped, so a few lying miners can't swing it.
+
+/// Blocks the difficulty is computed over
+pub const DIFFICULTY_WINDOW: usize = 720;
+/// Most recent blocks left out of the window
+pub const DIFFICULTY_LAG: usize = 15;
+/// Timestamps dropped at each end of the sorted window
+pub const DIFFICULTY_CUT: usize = 60;
+/// Blocks to pass to [`next_difficulty`]
+pub const DIFFICULTY_BLOCKS_COUNT: usize = DIFFICULTY_WINDOW + DIFFICULTY_LAG;
+
+/// Seconds per block before v2
+pub const DIFFICULTY_TARGET_V1: u64 = 60;
+/// Seconds per block since v2
+pub const DIFFICULTY_TARGET: u64 = 120;
+
+/// Target block time under hard fork `major_version`
+pub fn target_seconds(major_version: u8) -> u64 {
+    match major_version {
+        0 | 1 => DIFFICULTY_TARGET_V1,
+        _ => DIFFICULTY_TARGET,
+    }
+}
+
+/// Difficulty of the next block, from the timestamps and cumulative
+/// difficulties of the last [`DIFFICULTY_BLOCKS_COUNT`] blocks, oldest
+/// first. 0 if it doesn't fit in 128 bits, which monerod treats as invalid.
+pub fn next_difficulty(
+    timestamps: &[u64],
+    cumulative_difficulties: &[u128],
+    target_seconds: u64,
+) -> u128 {
+    assert_eq!(timestamps.len(), cumulative_difficulties.len());
+    let length = timestamps.len().min(DIFFICULTY_WINDOW);
+    if length <= 1 {
+        return 1;
+    }
+    let mut timestamps = timestamps[..length].to_vec();
+    timestamps.sort_unstable();
+
+    let kept = DIFFICULTY_WINDOW - 2 * DIFFICULTY_CUT;
+    let (cut_begin, cut_end) = match length <= kept {
+        true => (0, length),
+        false => {
+            let begin = (length - kept).div_ceil(2);
+            (begin, begin + kept)
+        }
+    };
+    let time_span = (timestamps[cut_end - 1] - timestamps[cut_begin]).max(1);
+    let total_work =
+        cumulative_difficulties[cut_end - 1].saturating_sub(cumulative_difficulties[cut_begin]);
+
+    // ceil(total_work * target / time_span) without a 256-bit product
+    let (span, target) = (time_span as u128, target_seconds as u128);
+    let quotient = total_work / span;
+    let rest = (total_work % span * target).div_ceil(span);
+    quotient
+        .checked_mul(target)
+        .and_then(|d| d.checked_add(rest))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` blocks `spacing` seconds apart, each of difficulty `difficulty`
+    fn steady(count: usize, spacing: u64, difficulty: u128) -> (Vec<u64>, Vec<u128>) {
+        let timestamps = (0..count as u64).map(|i| 1_600_000_000 + i * spacing);
+        let cumulative = (1..=count as u128).map(|i| i * difficulty);
+        (timestamps.collect(), cumulative.collect())
+    }
+
+    #[test]
+    fn test_short_chain() {
+        assert_eq!(next_difficulty(&[], &[], DIFFICULTY_TARGET), 1);
+        assert_eq!(next_difficulty(&[5], &[1], DIFFICULTY_TARGET), 1);
+        // the two genesis-era blocks of a fresh chain
+        assert_eq!(next_difficulty(&[0, 60], &[1, 2], DIFFICULTY_TARGET_V1), 1);
+        let (t, c) = steady(10, 120, 1000);
+        assert_eq!(next_difficulty(&t, &c, DIFFICULTY_TARGET), 1000);
+    }
+
+    #[test]
+    fn test_adjustment() {
+        let (t, c) = steady(DIFFICULTY_BLOCKS_COUNT, 120, 300_000_000_000);
+        assert_eq!(next_difficulty(&t, &c, DIFFICULTY_TARGET), 300_000_000_000);
+        // blocks twice as fast double it, twice as slow halve it
+        let (t, c) = steady(DIFFICULTY_BLOCKS_COUNT, 60, 1000);
+        assert_eq!(next_difficulty(&t, &c, DIFFICULTY_TARGET), 2000);
+        let (t, c) = steady(DIFFICULTY_BLOCKS_COUNT, 240, 1000);
+        assert_eq!(next_difficulty(&t, &c, DIFFICULTY_TARGET), 500);
+        // rounds up
+        let (t, c) = steady(3, 7, 1);
+        assert_eq!(next_difficulty(&t, &c, 10), 2);
+    }
+
+    #[test]
+    fn test_cut_and_lag() {
+        let (mut t, c) = steady(DIFFICULTY_BLOCKS_COUNT, 120, 1000);
+        // outlying timestamps fall in the cut; sorting shifts the kept ones
+        // against their cumulative difficulties, as in monerod
+        t[100] = 0;
+        t[200] = u64::MAX / 2;
+        assert_eq!(next_difficulty(&t, &c, DIFFICULTY_TARGET), 997);
+        // the lagging blocks are ignored however they look
+        let (mut t, mut c) = steady(DIFFICULTY_BLOCKS_COUNT, 120, 1000);
+        for i in DIFFICULTY_WINDOW..DIFFICULTY_BLOCKS_COUNT {
+            t[i] = t[DIFFICULTY_WINDOW - 1] + 1;
+            c[i] = c[i - 1] + 1_000_000;
+        }
+        assert_eq!(next_difficulty(&t, &c, DIFFICULTY_TARGET), 1000);
+        // equal timestamps count as one second
+        let c = [0, 1000];
+        assert_eq!(next_difficulty(&[7, 7], &c, DIFFICULTY_TARGET), 120_000);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let c = [0, u128::MAX / 2];
+        assert_eq!(next_difficulty(&[0, 1], &c, DIFFICULTY_TARGET), 0);
+        let c = [0, u128::MAX / 120];
+        assert_eq!(
+            next_difficulty(&[0, 1], &c, DIFFICULTY_TARGET),
+            u128::MAX / 120 * 120
+        );
+    }
+}
@@ -0,0 +1,370 @@
+// src/consensus/hardfork.rs
+//! Hard fork schedule (`hardforks.cpp`) and the transaction rules it
+//! switches.
+//!
+//! Each network moves to a new protocol version at a fixed height; a
+//! block's major version must be the one in force at its height. Most
+//! transaction format changes come with a grace version: the fork that
+//! introduces a format allows the old one too, the next one requires it.
+use std::fmt;
+
+use crate::network::Network;
+use crate::tx::{RctType, Transaction, TxIn};
+
+/// First version with RingCT transactions
+pub const RCT_VERSION: u8 = 4;
+/// First version rejecting non-RingCT spends
+pub const ENFORCE_RCT_VERSION: u8 = 6;
+/// First version with bulletproofs; borromean range proofs end a version
+/// later
+pub const BULLETPROOF_VERSION: u8 = 8;
+/// First version with ring sizes fixed to the minimum
+pub const FIXED_RING_SIZE_VERSION: u8 = 8;
+/// First version with the smaller bulletproof encoding
+pub const SMALLER_BP_VERSION: u8 = 10;
+/// First version requiring v2 miner transactions
+pub const MIN_V2_COINBASE_VERSION: u8 = 12;
+/// First version with CLSAG ring signatures
+pub const CLSAG_VERSION: u8 = 13;
+/// First version with Bulletproof+ range proofs
+pub const BULLETPROOF_PLUS_VERSION: u8 = 15;
+/// First version with view tags on outputs
+pub const VIEW_TAGS_VERSION: u8 = 15;
+
+/// `(version, first height)` on mainnet
+pub const MAINNET_HARD_FORKS: [(u8, u64); 16] = [
+    (1, 0),
+    (2, 1_009_827),
+    (3, 1_141_317),
+    (4, 1_220_516),
+    (5, 1_288_616),
+    (6, 1_400_000),
+    (7, 1_546_000),
+    (8, 1_685_555),
+    (9, 1_686_275),
+    (10, 1_788_000),
+    (11, 1_788_720),
+    (12, 1_978_433),
+    (13, 2_210_000),
+    (14, 2_210_720),
+    (15, 2_688_888),
+    (16, 2_689_608),
+];
+/// ... on testnet
+pub const TESTNET_HARD_FORKS: [(u8, u64); 16] = [
+    (1, 0),
+    (2, 624_634),
+    (3, 800_500),
+    (4, 801_219),
+    (5, 802_660),
+    (6, 971_400),
+    (7, 1_057_027),
+    (8, 1_057_058),
+    (9, 1_057_778),
+    (10, 1_154_318),
+    (11, 1_155_038),
+    (12, 1_308_737),
+    (13, 1_543_939),
+    (14, 1_544_659),
+    (15, 1_982_800),
+    (16, 1_983_520),
+];
+/// ... on stagenet
+pub const STAGENET_HARD_FORKS: [(u8, u64); 16] = [
+    (1, 0),
+    (2, 32_000),
+    (3, 33_000),
+    (4, 34_000),
+    (5, 35_000),
+    (6, 36_000),
+    (7, 37_000),
+    (8, 176_456),
+    (9, 177_176),
+    (10, 269_000),
+    (11, 269_720),
+    (12, 454_721),
+    (13, 675_405),
+    (14, 676_125),
+    (15, 1_151_000),
+    (16, 1_151_720),
+];
+
+/// Why a transaction breaks the rules of a version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleError {
+    /// Transaction version not allowed
+    TxVersion(u64),
+    /// Input `input` with a ring of `size` members
+    RingSize { input: usize, size: usize },
+    /// RingCT type not allowed
+    RctType(RctType),
+    /// Output `n` with a view tag before they exist, or without one after
+    /// they're required
+    ViewTag(usize),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::TxVersion(v) => write!(f, "transaction version {v} not allowed"),
+            RuleError::RingSize { input, size } => {
+                write!(f, "input {input} has a ring of {size}")
+            }
+            RuleError::RctType(t) => write!(f, "RingCT type {t:?} not allowed"),
+            RuleError::ViewTag(n) => write!(f, "output {n} has the wrong target type"),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+/// Heights each protocol version starts at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HardFork {
+    /// `(version, first height)`, both increasing
+    forks: Vec<(u8, u64)>,
+}
+
+impl HardFork {
+    /// A schedule of `(version, first height)`. None unless it starts at
+    /// height 0 and both versions and heights increase.
+    pub fn new(forks: &[(u8, u64)]) -> Option<Self> {
+        let increasing = forks.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1);
+        match forks.first() {
+            Some((_, 0)) if increasing => Some(Self {
+                forks: forks.to_vec(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The schedule of `network`
+    pub fn for_network(network: Network) -> Self {
+        let forks = match network {
+            Network::Mainnet => &MAINNET_HARD_FORKS,
+            Network::Testnet => &TESTNET_HARD_FORKS,
+            Network::Stagenet => &STAGENET_HARD_FORKS,
+        };
+        Self {
+            forks: forks.to_vec(),
+        }
+    }
+
+    /// Version in force at `height`
+    pub fn version_at(&self, height: u64) -> u8 {
+        let next = self.forks.partition_point(|&(_, start)| start <= height);
+        self.forks[next - 1].0
+    }
+
+    /// First height of `version`, if it is scheduled
+    pub fn height_of(&self, version: u8) -> Option<u64> {
+        self.forks
+            .iter()
+            .find(|&&(v, _)| v == version)
+            .map(|&(_, height)| height)
+    }
+
+    /// Last version scheduled
+    pub fn latest(&self) -> u8 {
+        self.forks.last().expect("schedule starts at height 0").0
+    }
+}
+
+/// Smallest ring allowed under `version`. Since
+/// [`FIXED_RING_SIZE_VERSION`] rings must be exactly this size.
+pub fn min_ring_size(version: u8) -> usize {
+    match version {
+        0 | 1 => 1,
+        2..=5 => 3,
+        6 => 5,
+        7 => 7,
+        8..=14 => 11,
+        _ => 16,
+    }
+}
+
+/// Whether v2 transactions spending with `rct_type` may be mined under
+/// `version`
+pub fn rct_type_allowed(rct_type: RctType, version: u8) -> bool {
+    match rct_type {
+        RctType::Null => false,
+        RctType::Full | RctType::Simple => (RCT_VERSION..=BULLETPROOF_VERSION).contains(&version),
+        RctType::Bulletproof => (BULLETPROOF_VERSION..=SMALLER_BP_VERSION).contains(&version),
+        RctType::Bulletproof2 => (SMALLER_BP_VERSION..=CLSAG_VERSION).contains(&version),
+        RctType::Clsag => (CLSAG_VERSION..=BULLETPROOF_PLUS_VERSION).contains(&version),
+        RctType::BulletproofPlus => version >= BULLETPROOF_PLUS_VERSION,
+    }
+}
+
+/// Check `tx` against the format rules of `version`: its version, ring
+/// sizes, RingCT type and output targets. Signatures and amounts are left
+/// to [`crate::tx::verify_rct`].
+pub fn check_transaction(tx: &Transaction, version: u8) -> Result<(), RuleError> {
+    let prefix = &tx.prefix;
+    let coinbase = prefix.is_coinbase();
+    let min_tx_version = match coinbase {
+        true if version >= MIN_V2_COINBASE_VERSION => 2,
+        false if version >= ENFORCE_RCT_VERSION => 2,
+        _ => 1,
+    };
+    let max_tx_version = if version >= RCT_VERSION { 2 } else { 1 };
+    if !(min_tx_version..=max_tx_version).contains(&prefix.version) {
+        return Err(RuleError::TxVersion(prefix.version));
+    }
+
+    for (n, output) in prefix.vout.iter().enumerate() {
+        let tagged = output.view_tag().is_some();
+        if (tagged && version < VIEW_TAGS_VERSION) || (!tagged && version > VIEW_TAGS_VERSION) {
+            return Err(RuleError::ViewTag(n));
+        }
+    }
+    if coinbase {
+        return Ok(());
+    }
+
+    let rct_type = tx.rct_signatures.base.rct_type;
+    if prefix.version >= 2 && !rct_type_allowed(rct_type, version) {
+        return Err(RuleError::RctType(rct_type));
+    }
+    let min = min_ring_size(version);
+    for (input, vin) in prefix.vin.iter().enumerate() {
+        let TxIn::ToKey(vin) = vin else { continue };
+        let size = vin.key_offsets.len();
+        if size < min || (version >= FIXED_RING_SIZE_VERSION && size != min) {
+            return Err(RuleError::RingSize { input, size });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::cn::KeyImage;
+    use crate::tx::{RctSig, TransactionPrefix, TxInGen, TxInToKey, TxOut};
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    fn tx(version: u64, ring_size: usize, rct_type: RctType, tagged: bool) -> Transaction {
+        let mut rct_signatures = RctSig::null();
+        rct_signatures.base.rct_type = rct_type;
+        let key = CompressedEdwardsY([0; 32]);
+        Transaction {
+            prefix: TransactionPrefix {
+                version,
+                unlock_time: 0,
+                vin: vec![TxIn::ToKey(TxInToKey {
+                    amount: 0,
+                    key_offsets: vec![1; ring_size],
+                    key_image: KeyImage([0; 32]),
+                })],
+                vout: vec![match tagged {
+                    true => TxOut::to_tagged_key(0, key, 0),
+                    false => TxOut::to_key(0, key),
+                }],
+                extra: Vec::new(),
+            },
+            signatures: Vec::new(),
+            rct_signatures,
+        }
+    }
+
+    #[test]
+    fn test_schedule() {
+        let forks = HardFork::for_network(Network::Mainnet);
+        assert_eq!(forks.version_at(0), 1);
+        assert_eq!(forks.version_at(1_009_826), 1);
+        assert_eq!(forks.version_at(1_009_827), 2);
+        assert_eq!(forks.version_at(2_688_887), 14);
+        assert_eq!(forks.version_at(u64::MAX), 16);
+        assert_eq!(forks.height_of(15), Some(2_688_888));
+        assert_eq!(forks.height_of(17), None);
+        assert_eq!(forks.latest(), 16);
+        for network in Network::ALL {
+            let forks = HardFork::for_network(network);
+            assert_eq!(HardFork::new(&forks.forks), Some(forks));
+        }
+
+        let custom = HardFork::new(&[(1, 0), (16, 1)]).unwrap();
+        assert_eq!(custom.version_at(1), 16);
+        assert_eq!(HardFork::new(&[(1, 5)]), None);
+        assert_eq!(HardFork::new(&[(1, 0), (2, 0)]), None);
+        assert_eq!(HardFork::new(&[(2, 0), (1, 5)]), None);
+        assert_eq!(HardFork::new(&[]), None);
+    }
+
+    #[test]
+    fn test_rules() {
+        let current = tx(2, 16, RctType::BulletproofPlus, true);
+        assert_eq!(check_transaction(&current, 16), Ok(()));
+        assert_eq!(check_transaction(&current, 14), Err(RuleError::ViewTag(0)));
+        // v15 takes both formats, v16 only the new one
+        let clsag = tx(2, 16, RctType::Clsag, false);
+        assert_eq!(check_transaction(&clsag, 15), Ok(()));
+        assert_eq!(check_transaction(&clsag, 16), Err(RuleError::ViewTag(0)));
+        let clsag = tx(2, 16, RctType::Clsag, true);
+        assert_eq!(
+            check_transaction(&clsag, 16),
+            Err(RuleError::RctType(RctType::Clsag))
+        );
+
+        let small_ring = tx(2, 11, RctType::BulletproofPlus, true);
+        assert_eq!(
+            check_transaction(&small_ring, 16),
+            Err(RuleError::RingSize { input: 0, size: 11 })
+        );
+        assert_eq!(
+            check_transaction(&tx(2, 11, RctType::Clsag, false), 14),
+            Ok(())
+        );
+        assert_eq!(
+            check_transaction(&tx(2, 12, RctType::Clsag, false), 14),
+            Err(RuleError::RingSize { input: 0, size: 12 })
+        );
+        // rings only had a minimum before v8
+        assert_eq!(
+            check_transaction(&tx(2, 9, RctType::Simple, false), 7),
+            Ok(())
+        );
+
+        assert_eq!(
+            check_transaction(&tx(1, 3, RctType::Null, false), 5),
+            Ok(())
+        );
+        assert_eq!(
+            check_transaction(&tx(1, 2, RctType::Null, false), 5),
+            Err(RuleError::RingSize { input: 0, size: 2 })
+        );
+        assert_eq!(
+            check_transaction(&tx(2, 3, RctType::Null, false), 5),
+            Err(RuleError::RctType(RctType::Null))
+        );
+        assert_eq!(
+            check_transaction(&tx(1, 5, RctType::Null, false), 6),
+            Err(RuleError::TxVersion(1))
+        );
+        assert_eq!(
+            check_transaction(&tx(2, 3, RctType::Simple, false), 3),
+            Err(RuleError::TxVersion(2))
+        );
+
+        let mut coinbase = tx(1, 0, RctType::Null, false);
+        coinbase.prefix.vin = vec![TxIn::Gen(TxInGen { height: 1 })];
+        assert_eq!(check_transaction(&coinbase, 11), Ok(()));
+        assert_eq!(
+            check_transaction(&coinbase, 12),
+            Err(RuleError::TxVersion(1))
+        );
+    }
+
+    #[test]
+    fn test_rct_types() {
+        assert!(!rct_type_allowed(RctType::Null, 5));
+        assert!(rct_type_allowed(RctType::Full, 4));
+        assert!(rct_type_allowed(RctType::Simple, 8));
+        assert!(!rct_type_allowed(RctType::Simple, 9));
+        assert!(!rct_type_allowed(RctType::Bulletproof2, 9));
+        assert!(rct_type_allowed(RctType::Bulletproof2, 13));
+        assert!(!rct_type_allowed(RctType::Bulletproof2, 14));
+        assert!(!rct_type_allowed(RctType::BulletproofPlus, 14));
+    }
+}
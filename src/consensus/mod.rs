@@ -0,0 +1,13 @@
+// src/consensus/mod.rs
+//! Consensus rules: what monerod requires of blocks before it accepts them.
+pub mod difficulty;
+pub mod hardfork;
+pub mod reward;
+pub mod verify;
+pub mod weight;
+
+pub use difficulty::{next_difficulty, target_seconds};
+pub use hardfork::{HardFork, RuleError, check_transaction};
+pub use reward::{base_reward, reward};
+pub use verify::{TxContext, VerifyError, verify_transaction};
+pub use weight::{effective_median, long_term_weight, max_tx_weight};
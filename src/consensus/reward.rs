@@ -0,0 +1,97 @@
+// src/consensus/reward.rs
+//! Block reward (`get_block_reward`).
+//!
+//! The base reward is what's left of the money supply shifted down by the
+//! emission speed factor, never less than the tail emission. A block
+//! heavier than the median weight of recent blocks loses
+//! `base * ((weight - median) / median)^2`, and one over twice the median
+//! is invalid.
+use super::difficulty::target_seconds;
+
+/// All the atomic units there will ever be, before tail emission
+pub const MONEY_SUPPLY: u64 = u64::MAX;
+/// `log2` of the remaining supply divided by a one-minute block's reward
+pub const EMISSION_SPEED_FACTOR_PER_MINUTE: u32 = 20;
+/// Tail emission per minute of target block time: 0.3 XMR
+pub const FINAL_SUBSIDY_PER_MINUTE: u64 = 300_000_000_000;
+
+/// Blocks up to this weight are never penalised, before v2
+pub const FULL_REWARD_ZONE_V1: u64 = 20_000;
+/// ... from v2 to v4
+pub const FULL_REWARD_ZONE_V2: u64 = 60_000;
+/// ... since v5
+pub const FULL_REWARD_ZONE_V5: u64 = 300_000;
+
+/// Weight below which blocks are never penalised under `version`
+pub fn full_reward_zone(version: u8) -> u64 {
+    match version {
+        0 | 1 => FULL_REWARD_ZONE_V1,
+        2..=4 => FULL_REWARD_ZONE_V2,
+        _ => FULL_REWARD_ZONE_V5,
+    }
+}
+
+/// Reward of a block no heavier than the median, once `already_generated`
+/// atomic units have been mined
+pub fn base_reward(already_generated: u64, version: u8) -> u64 {
+    let target_minutes = target_seconds(version) / 60;
+    let speed_factor = EMISSION_SPEED_FACTOR_PER_MINUTE - (target_minutes as u32 - 1);
+    let base = (MONEY_SUPPLY - already_generated) >> speed_factor;
+    base.max(FINAL_SUBSIDY_PER_MINUTE * target_minutes)
+}
+
+/// Reward of a block of `block_weight` under hard fork `version`. None if
+/// the block is more than twice the median weight.
+pub fn reward(
+    already_generated: u64,
+    block_weight: u64,
+    median_weight: u64,
+    version: u8,
+) -> Option<u64> {
+    let base = base_reward(already_generated, version);
+    let median = median_weight.max(full_reward_zone(version));
+    if block_weight <= median {
+        return Some(base);
+    }
+    if block_weight > 2 * median {
+        return None;
+    }
+    // base * (2M - W) * W / M^2; (2M - W) * W <= M^2 fits in 64 bits
+    let multiplicand = (2 * median - block_weight) * block_weight;
+    let reduced = base as u128 * multiplicand as u128 / median as u128 / median as u128;
+    Some(reduced as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emission() {
+        // mainnet's first block reward, and the first at two minutes
+        assert_eq!(base_reward(0, 1), 17_592_186_044_415);
+        assert_eq!(reward(0, 0, 0, 1), Some(17_592_186_044_415));
+        let generated = 8_000_000_000_000_000_000;
+        assert_eq!(base_reward(generated, 2), (u64::MAX - generated) >> 19);
+        // tail emission
+        assert_eq!(base_reward(u64::MAX - 1_000, 16), 600_000_000_000);
+        assert_eq!(base_reward(u64::MAX - 1_000, 1), 300_000_000_000);
+    }
+
+    #[test]
+    fn test_penalty() {
+        let base = base_reward(0, 16);
+        // up to the median, or the full reward zone, there is no penalty
+        assert_eq!(reward(0, 300_000, 0, 16), Some(base));
+        assert_eq!(reward(0, 400_000, 400_000, 16), Some(base));
+        let penalised = base_reward(0, 4) as u128 * 3_599_999_999 / 3_600_000_000;
+        assert_eq!(reward(0, 60_001, 0, 4), Some(penalised as u64));
+        // 1.5 times the median loses a quarter
+        assert_eq!(
+            reward(0, 450_000, 300_000, 16),
+            Some((base as u128 * 3 / 4) as u64)
+        );
+        assert_eq!(reward(0, 600_000, 300_000, 16), Some(0));
+        assert_eq!(reward(0, 600_001, 300_000, 16), None);
+    }
+}
@@ -0,0 +1,491 @@
+// src/consensus/verify.rs
+//! Full transaction verification (`check_tx_semantic`, `check_tx_inputs`).
+//!
+//! A transaction is checked on its own first: the rules of the hard fork,
+//! output counts and amounts, key image order and validity, weight and
+//! fee. Then against the chain: its key images must be unspent and every
+//! ring member must exist and be unlocked at the height the transaction
+//! would be mined. Last come the proofs, the expensive part: range proofs,
+//! balance and ring signatures.
+//!
+//! The store indexes RingCT outputs only. Pre-RingCT outputs are indexed
+//! per amount, so inputs spending them, and with them every v1
+//! transaction, are rejected rather than checked against the wrong
+//! outputs.
+use std::collections::HashSet;
+use std::fmt;
+
+use super::hardfork::{BULLETPROOF_VERSION, RuleError, check_transaction};
+use super::weight::max_tx_weight;
+use crate::chain::ChainStore;
+use crate::tx::builder::MAX_OUTPUTS;
+use crate::tx::rct::{RctError, RctType, RingMember, verify_rct};
+use crate::tx::{Transaction, TxIn};
+
+/// First version requiring two outputs or more
+const MIN_2_OUTPUTS_VERSION: u8 = 12;
+/// First version requiring inputs sorted by key image
+const SORTED_INPUTS_VERSION: u8 = 7;
+
+/// Why a transaction isn't valid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// Miner transactions are checked with their block
+    Coinbase,
+    NoInputs,
+    /// Fewer than two outputs since v12, or more than a bulletproof covers
+    OutputCount(usize),
+    /// RingCT output `n` with a cleartext amount
+    NonZeroAmount(usize),
+    Rules(RuleError),
+    /// Key image of input `n` not in the prime-order subgroup
+    InvalidKeyImage(usize),
+    /// Input `n` spends the same key image as an earlier one
+    DuplicateKeyImage(usize),
+    /// Inputs not in descending key image order
+    UnsortedInputs,
+    /// Input `n` spends a key image already spent on chain
+    SpentKeyImage(usize),
+    /// Ring member with this global index isn't on chain
+    UnknownOutput(u64),
+    /// Ring member with this global index is still locked
+    LockedOutput(u64),
+    /// Heavier than the transaction weight limit
+    TooHeavy {
+        weight: u64,
+        limit: u64,
+    },
+    /// v1 outputs worth more than its inputs
+    Overspend,
+    /// Fee below the minimum for the weight
+    LowFee {
+        fee: u64,
+        required: u64,
+    },
+    /// Input `n` spends pre-RingCT outputs, indexed by amount
+    PreRctInput(usize),
+    Rct(RctError),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Coinbase => write!(f, "miner transaction outside a block"),
+            VerifyError::NoInputs => write!(f, "no inputs"),
+            VerifyError::OutputCount(n) => write!(f, "{n} outputs not allowed"),
+            VerifyError::NonZeroAmount(n) => write!(f, "output {n} has a cleartext amount"),
+            VerifyError::Rules(e) => write!(f, "{e}"),
+            VerifyError::InvalidKeyImage(n) => write!(f, "invalid key image in input {n}"),
+            VerifyError::DuplicateKeyImage(n) => write!(f, "input {n} repeats a key image"),
+            VerifyError::UnsortedInputs => write!(f, "inputs not sorted by key image"),
+            VerifyError::SpentKeyImage(n) => write!(f, "input {n} is already spent"),
+            VerifyError::UnknownOutput(i) => write!(f, "unknown output {i}"),
+            VerifyError::LockedOutput(i) => write!(f, "output {i} is still locked"),
+            VerifyError::TooHeavy { weight, limit } => {
+                write!(f, "weight {weight} over the limit of {limit}")
+            }
+            VerifyError::Overspend => write!(f, "outputs worth more than the inputs"),
+            VerifyError::LowFee { fee, required } => {
+                write!(f, "fee {fee} below the required {required}")
+            }
+            VerifyError::PreRctInput(n) => write!(f, "input {n} spends pre-RingCT outputs"),
+            VerifyError::Rct(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<RuleError> for VerifyError {
+    fn from(e: RuleError) -> Self {
+        VerifyError::Rules(e)
+    }
+}
+
+impl From<RctError> for VerifyError {
+    fn from(e: RctError) -> Self {
+        VerifyError::Rct(e)
+    }
+}
+
+/// The chain a transaction is verified against, as of the block it would
+/// go in
+pub struct TxContext<'a, S: ?Sized> {
+    /// Outputs and spent key images
+    pub store: &'a S,
+    /// Hard fork version of the block
+    pub version: u8,
+    /// Height of the block
+    pub height: u64,
+    /// About when the block is mined, for timestamp unlock times
+    pub timestamp: u64,
+    /// Lowest base fee per byte of weight accepted
+    pub fee_per_byte: u64,
+}
+
+/// Check everything about `tx` a node checks before accepting it into a
+/// block under `context`
+pub fn verify_transaction<S: ChainStore + ?Sized>(
+    tx: &Transaction,
+    context: &TxContext<'_, S>,
+) -> Result<(), VerifyError> {
+    let prefix = &tx.prefix;
+    let version = context.version;
+    if prefix.is_coinbase() || prefix.vin.iter().any(|i| matches!(i, TxIn::Gen(_))) {
+        return Err(VerifyError::Coinbase);
+    }
+    if prefix.vin.is_empty() {
+        return Err(VerifyError::NoInputs);
+    }
+    check_transaction(tx, version)?;
+
+    let rct_type = tx.rct_signatures.base.rct_type;
+    let n_outputs = prefix.vout.len();
+    let max_outputs = match version >= BULLETPROOF_VERSION {
+        true => MAX_OUTPUTS,
+        false => usize::MAX,
+    };
+    let min_outputs = match version >= MIN_2_OUTPUTS_VERSION {
+        true => 2,
+        false => 1,
+    };
+    if !(min_outputs..=max_outputs).contains(&n_outputs) {
+        return Err(VerifyError::OutputCount(n_outputs));
+    }
+    if rct_type != RctType::Null
+        && let Some(n) = prefix.vout.iter().position(|o| o.amount != 0)
+    {
+        return Err(VerifyError::NonZeroAmount(n));
+    }
+
+    let mut seen = HashSet::new();
+    let mut previous: Option<&[u8; 32]> = None;
+    for (n, key_image) in prefix.key_images().enumerate() {
+        if !key_image.is_valid() {
+            return Err(VerifyError::InvalidKeyImage(n));
+        }
+        if !seen.insert(key_image) {
+            return Err(VerifyError::DuplicateKeyImage(n));
+        }
+        if version >= SORTED_INPUTS_VERSION && previous.is_some_and(|p| p <= key_image.as_bytes()) {
+            return Err(VerifyError::UnsortedInputs);
+        }
+        previous = Some(key_image.as_bytes());
+    }
+
+    let weight = tx.weight() as u64;
+    let limit = max_tx_weight(version);
+    if weight > limit {
+        return Err(VerifyError::TooHeavy { weight, limit });
+    }
+    let fee = tx.fee().ok_or(VerifyError::Overspend)?;
+    let needed = weight.saturating_mul(context.fee_per_byte);
+    // monerod lets fees fall 2% short, for estimates made before signing
+    let required = needed - needed / 50;
+    if fee < required {
+        return Err(VerifyError::LowFee { fee, required });
+    }
+
+    let pre_rct = prefix.vin.iter().position(|input| match input {
+        TxIn::ToKey(input) => prefix.version == 1 || input.amount != 0,
+        TxIn::Gen(_) => false,
+    });
+    if let Some(n) = pre_rct {
+        return Err(VerifyError::PreRctInput(n));
+    }
+    for (n, key_image) in prefix.key_images().enumerate() {
+        if context.store.is_spent(key_image) {
+            return Err(VerifyError::SpentKeyImage(n));
+        }
+    }
+    let rings = rings(tx, context)?;
+    verify_rct(tx, &rings)?;
+    Ok(())
+}
+
+/// The ring of every input, each member on chain and unlocked
+fn rings<S: ChainStore + ?Sized>(
+    tx: &Transaction,
+    context: &TxContext<'_, S>,
+) -> Result<Vec<Vec<RingMember>>, VerifyError> {
+    let mut rings = Vec::with_capacity(tx.prefix.vin.len());
+    for input in &tx.prefix.vin {
+        let TxIn::ToKey(input) = input else {
+            return Err(VerifyError::Coinbase);
+        };
+        let indices = input
+            .absolute_offsets()
+            .ok_or(VerifyError::UnknownOutput(u64::MAX))?;
+        let mut ring = Vec::with_capacity(indices.len());
+        for index in indices {
+            let output = context
+                .store
+                .output(index)
+                .ok_or(VerifyError::UnknownOutput(index))?;
+            if !output.is_unlocked(context.height, context.timestamp) {
+                return Err(VerifyError::LockedOutput(index));
+            }
+            ring.push(output.member);
+        }
+        rings.push(ring);
+    }
+    Ok(rings)
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::{Scalar, constants::ED25519_BASEPOINT_POINT};
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::address::Address;
+    use crate::chain::{MemoryStore, WriteBatch};
+    use crate::crypto::cn::{KeyImage, generate_key_image};
+    use crate::crypto::keys::WalletKeys;
+    use crate::crypto::ring::cryptonote;
+    use crate::network::Network;
+    use crate::tx::builder::tests::with_chain;
+    use crate::tx::decoys::CandidateOutput;
+    use crate::tx::rct::RctSig;
+    use crate::tx::{TransactionBuilder, TransactionPrefix, TxInToKey, TxOut};
+
+    /// A signed transaction and a store holding its rings, every output
+    /// created at height 0
+    fn signed() -> (Transaction, MemoryStore) {
+        let sender = WalletKeys::generate();
+        let to = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let (mut chain, outputs) = with_chain(&sender, &[3_000_000_000, 2_000_000_000]);
+        let mut builder =
+            TransactionBuilder::new(&sender, Network::Mainnet).destination(to, 4_000_000_000);
+        for output in outputs {
+            builder = builder.input(output);
+        }
+        let tx = builder.build(&mut chain).unwrap().tx;
+
+        let top = chain.outputs.keys().max().copied().unwrap();
+        let mut batch = WriteBatch::new();
+        for global_index in 0..=top {
+            let random = || Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT;
+            let member = chain.outputs.get(&global_index).copied();
+            batch = batch.put_output(CandidateOutput {
+                member: member.unwrap_or(RingMember {
+                    global_index,
+                    key: random(),
+                    commitment: random(),
+                }),
+                height: 0,
+                unlock_time: 0,
+                coinbase: false,
+            });
+        }
+        let mut store = MemoryStore::new();
+        store.write(batch).unwrap();
+        (tx, store)
+    }
+
+    fn context(store: &MemoryStore) -> TxContext<'_, MemoryStore> {
+        TxContext {
+            store,
+            version: 16,
+            height: 100,
+            timestamp: 0,
+            fee_per_byte: 20_000,
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let (tx, store) = signed();
+        let context = context(&store);
+        assert_eq!(verify_transaction(&tx, &context), Ok(()));
+
+        // outputs younger than ten blocks can't be in a ring
+        let young = TxContext {
+            height: 5,
+            ..context
+        };
+        assert!(matches!(
+            verify_transaction(&tx, &young),
+            Err(VerifyError::LockedOutput(_))
+        ));
+        let old_fork = TxContext {
+            version: 14,
+            ..context
+        };
+        assert!(matches!(
+            verify_transaction(&tx, &old_fork),
+            Err(VerifyError::Rules(_))
+        ));
+        let expensive = TxContext {
+            fee_per_byte: 1_000_000,
+            ..context
+        };
+        assert!(matches!(
+            verify_transaction(&tx, &expensive),
+            Err(VerifyError::LowFee { .. })
+        ));
+
+        let mut spent = store.clone();
+        let key_image = *tx.prefix.key_images().nth(1).unwrap();
+        spent
+            .write(WriteBatch::new().put_key_image(key_image, 50))
+            .unwrap();
+        assert_eq!(
+            verify_transaction(
+                &tx,
+                &TxContext {
+                    store: &spent,
+                    ..context
+                }
+            ),
+            Err(VerifyError::SpentKeyImage(1))
+        );
+        let empty = MemoryStore::new();
+        assert!(matches!(
+            verify_transaction(
+                &tx,
+                &TxContext {
+                    store: &empty,
+                    ..context
+                }
+            ),
+            Err(VerifyError::UnknownOutput(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered() {
+        let (tx, store) = signed();
+        let context = context(&store);
+
+        let mut swapped = tx.clone();
+        swapped.prefix.vin.swap(0, 1);
+        assert_eq!(
+            verify_transaction(&swapped, &context),
+            Err(VerifyError::UnsortedInputs)
+        );
+        let mut duplicate = tx.clone();
+        duplicate.prefix.vin[1] = duplicate.prefix.vin[0].clone();
+        assert_eq!(
+            verify_transaction(&duplicate, &context),
+            Err(VerifyError::DuplicateKeyImage(1))
+        );
+        let mut invalid = tx.clone();
+        if let TxIn::ToKey(input) = &mut invalid.prefix.vin[0] {
+            input.key_image = KeyImage([0; 32]);
+        }
+        assert_eq!(
+            verify_transaction(&invalid, &context),
+            Err(VerifyError::InvalidKeyImage(0))
+        );
+        let mut one_output = tx.clone();
+        one_output.prefix.vout.truncate(1);
+        assert_eq!(
+            verify_transaction(&one_output, &context),
+            Err(VerifyError::OutputCount(1))
+        );
+        let mut amount = tx.clone();
+        amount.prefix.vout[0].amount = 1;
+        assert_eq!(
+            verify_transaction(&amount, &context),
+            Err(VerifyError::NonZeroAmount(0))
+        );
+
+        // a forged signature, then a forged proof
+        let mut forged = tx.clone();
+        forged.rct_signatures.prunable.clsags[0].c1 += Scalar::ONE;
+        assert_eq!(
+            verify_transaction(&forged, &context),
+            Err(VerifyError::Rct(RctError::Signature(0)))
+        );
+        let mut inflated = tx.clone();
+        inflated.rct_signatures.base.fee += 1;
+        assert_eq!(
+            verify_transaction(&inflated, &context),
+            Err(VerifyError::Rct(RctError::Unbalanced))
+        );
+    }
+
+    /// A v1 transaction spending `input_amount` from RingCT outputs 0 to 2
+    /// of `store`, output 0 being its own, into `output_amount`. Its ring
+    /// signature is valid.
+    fn v1_spend(store: &mut MemoryStore, input_amount: u64, output_amount: u64) -> Transaction {
+        let secret = Scalar::random(&mut OsRng);
+        let ring: Vec<_> = (0..3)
+            .map(|i| match i {
+                0 => secret * ED25519_BASEPOINT_POINT,
+                _ => Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT,
+            })
+            .collect();
+        let mut batch = WriteBatch::new();
+        for (global_index, &key) in ring.iter().enumerate() {
+            batch = batch.put_output(CandidateOutput {
+                member: RingMember {
+                    global_index: global_index as u64,
+                    key,
+                    commitment: ED25519_BASEPOINT_POINT,
+                },
+                height: 0,
+                unlock_time: 0,
+                coinbase: false,
+            });
+        }
+        store.write(batch).unwrap();
+
+        let key_image = generate_key_image(&secret, &ring[0]);
+        let prefix = TransactionPrefix {
+            version: 1,
+            unlock_time: 0,
+            vin: vec![TxIn::ToKey(TxInToKey::from_absolute(
+                input_amount,
+                &[0, 1, 2],
+                key_image,
+            ))],
+            vout: vec![TxOut::to_key(
+                output_amount,
+                (Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT).compress(),
+            )],
+            extra: Vec::new(),
+        };
+        let prefix_hash = prefix.prefix_hash().to_bytes();
+        let signature = cryptonote::sign(&prefix_hash, &key_image, &ring, &secret, 0).unwrap();
+        let tx = Transaction {
+            prefix,
+            signatures: vec![signature],
+            rct_signatures: RctSig::null(),
+        };
+        assert!(cryptonote::verify(
+            &prefix_hash,
+            &key_image,
+            &ring,
+            &tx.signatures[0]
+        ));
+        tx
+    }
+
+    #[test]
+    fn test_v1_inputs() {
+        let mut store = MemoryStore::new();
+        // v1 is still allowed at v5; the input claims an amount its RingCT
+        // ring members don't hold, which would mint coins
+        let context = |store| TxContext {
+            store,
+            version: 5,
+            height: 100,
+            timestamp: 0,
+            fee_per_byte: 0,
+        };
+        let minted = v1_spend(&mut store, 1_000_000_000_000_000, 999_000_000_000_000);
+        assert_eq!(
+            verify_transaction(&minted, &context(&store)),
+            Err(VerifyError::PreRctInput(0))
+        );
+
+        let mut store = MemoryStore::new();
+        let overspent = v1_spend(&mut store, 1, 1_000_000_000_000);
+        assert_eq!(
+            verify_transaction(&overspent, &context(&store)),
+            Err(VerifyError::Overspend)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// src/consensus/weight.rs
+//! Block weight limits (`update_next_cumulative_weight_limit`).
+//!
+//! The reward penalty is charged against the effective median: the median
+//! weight of the last [`REWARD_BLOCKS_WINDOW`] blocks, at least the full
+//! reward zone. Since v10 that median is also capped at
+//! [`SHORT_TERM_BLOCK_WEIGHT_SURGE_FACTOR`] times the long-term median, the
+//! median over [`LONG_TERM_BLOCK_WEIGHT_WINDOW`] blocks of their long-term
+//! weights: each block's weight clamped around the long-term median before
+//! it. Blocks may weigh up to twice the effective median.
+use super::reward::{FULL_REWARD_ZONE_V5, full_reward_zone};
+
+/// Blocks the short-term median is taken over
+pub const REWARD_BLOCKS_WINDOW: usize = 100;
+/// Blocks the long-term median is taken over
+pub const LONG_TERM_BLOCK_WEIGHT_WINDOW: usize = 100_000;
+/// How far the short-term median may outgrow the long-term one
+pub const SHORT_TERM_BLOCK_WEIGHT_SURGE_FACTOR: u64 = 50;
+/// Weight kept free in a block for the miner transaction
+pub const COINBASE_BLOB_RESERVED_SIZE: u64 = 600;
+
+/// First version with long-term weights
+const LONG_TERM_WEIGHT_VERSION: u8 = 10;
+/// First version clamping long-term weights from below too
+const SCALING_2021_VERSION: u8 = 15;
+/// First version charging fees per byte, with the smaller transaction limit
+const PER_BYTE_FEE_VERSION: u8 = 8;
+
+/// epee's median: the mean of the middle two for an even count, 0 for none
+pub fn median(values: &[u64]) -> u64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => 0,
+        n if n.is_multiple_of(2) => {
+            sorted[mid - 1] / 2 + sorted[mid] / 2 + (sorted[mid - 1] % 2 + sorted[mid] % 2) / 2
+        }
+        _ => sorted[mid],
+    }
+}
+
+/// Long-term median from the long-term weights of the last
+/// [`LONG_TERM_BLOCK_WEIGHT_WINDOW`] blocks, at least the v5 full reward
+/// zone
+pub fn long_term_median(long_term_weights: &[u64]) -> u64 {
+    median(long_term_weights).max(FULL_REWARD_ZONE_V5)
+}
+
+/// Long-term weight of a block of `block_weight`, from the long-term
+/// median of the blocks before it
+pub fn long_term_weight(block_weight: u64, long_term_median: u64, version: u8) -> u64 {
+    if version < LONG_TERM_WEIGHT_VERSION {
+        return block_weight;
+    }
+    if version >= SCALING_2021_VERSION {
+        // within [median / 1.4, median * 1.4]
+        let upper = long_term_median + long_term_median * 2 / 5;
+        return block_weight.max(long_term_median * 10 / 14).min(upper);
+    }
+    block_weight.min(long_term_median + long_term_median * 7 / 10)
+}
+
+/// Median the next block's reward penalty is charged against, from the
+/// weights of the last [`REWARD_BLOCKS_WINDOW`] blocks and the long-term
+/// median
+pub fn effective_median(weights: &[u64], long_term_median: u64, version: u8) -> u64 {
+    if version < LONG_TERM_WEIGHT_VERSION {
+        return median(weights).max(full_reward_zone(version));
+    }
+    let short_term = median(weights).max(FULL_REWARD_ZONE_V5);
+    short_term.min(SHORT_TERM_BLOCK_WEIGHT_SURGE_FACTOR * long_term_median)
+}
+
+/// Heaviest transaction accepted under `version`: the smallest penalty-free
+/// block, halved since per-byte fees, less the coinbase reserve
+pub fn max_tx_weight(version: u8) -> u64 {
+    let zone = full_reward_zone(version);
+    let zone = match version >= PER_BYTE_FEE_VERSION {
+        true => zone / 2,
+        false => zone,
+    };
+    zone - COINBASE_BLOB_RESERVED_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::builder::MAX_TX_WEIGHT;
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[]), 0);
+        assert_eq!(median(&[5, 1, 3]), 3);
+        assert_eq!(median(&[4, 1, 3, 8]), 3);
+        assert_eq!(median(&[u64::MAX, u64::MAX]), u64::MAX);
+        assert_eq!(long_term_median(&[1000]), FULL_REWARD_ZONE_V5);
+        assert_eq!(long_term_median(&[1_000_000, 500_000]), 750_000);
+    }
+
+    #[test]
+    fn test_long_term_weight() {
+        let ltm = 1_000_000;
+        assert_eq!(long_term_weight(5_000_000, ltm, 9), 5_000_000);
+        assert_eq!(long_term_weight(5_000_000, ltm, 10), 1_700_000);
+        assert_eq!(long_term_weight(1_000, ltm, 10), 1_000);
+        assert_eq!(long_term_weight(5_000_000, ltm, 16), 1_400_000);
+        assert_eq!(long_term_weight(1_000, ltm, 16), 714_285);
+        assert_eq!(long_term_weight(900_000, ltm, 16), 900_000);
+    }
+
+    #[test]
+    fn test_effective_median() {
+        // small blocks get the full reward zone
+        assert_eq!(
+            effective_median(&[1_000; 100], FULL_REWARD_ZONE_V5, 4),
+            60_000
+        );
+        assert_eq!(
+            effective_median(&[1_000; 100], FULL_REWARD_ZONE_V5, 16),
+            300_000
+        );
+        assert_eq!(
+            effective_median(&[400_000; 100], FULL_REWARD_ZONE_V5, 16),
+            400_000
+        );
+        // a surge is capped by the long-term median
+        let surge = [100_000_000; 100];
+        assert_eq!(effective_median(&surge, 1_000_000, 9), 100_000_000);
+        assert_eq!(effective_median(&surge, 1_000_000, 16), 50_000_000);
+    }
+
+    #[test]
+    fn test_max_tx_weight() {
+        assert_eq!(max_tx_weight(16), MAX_TX_WEIGHT as u64);
+        assert_eq!(max_tx_weight(7), 299_400);
+        assert_eq!(max_tx_weight(1), 19_400);
+    }
+}
@@ -0,0 +1,276 @@
+// src/crypto/aead.rs
+// ChaCha20-Poly1305 (RFC 8439), the AEAD behind encrypted key exports
+use zeroize::Zeroize;
+
+pub(crate) const KEY_LEN: usize = 32;
+pub(crate) const NONCE_LEN: usize = 12;
+pub(crate) const TAG_LEN: usize = 16;
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+/// One 64-byte ChaCha20 keystream block
+fn chacha20_block(key: &[u8; KEY_LEN], counter: u32, nonce: &[u8; NONCE_LEN]) -> [u8; 64] {
+    let word = |b: &[u8]| u32::from_le_bytes(b.try_into().unwrap());
+    let mut init = [0u32; 16];
+    init[..4].copy_from_slice(&[0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574]);
+    for i in 0..8 {
+        init[4 + i] = word(&key[4 * i..4 * i + 4]);
+    }
+    init[12] = counter;
+    for i in 0..3 {
+        init[13 + i] = word(&nonce[4 * i..4 * i + 4]);
+    }
+
+    let mut s = init;
+    for _ in 0..10 {
+        quarter_round(&mut s, 0, 4, 8, 12);
+        quarter_round(&mut s, 1, 5, 9, 13);
+        quarter_round(&mut s, 2, 6, 10, 14);
+        quarter_round(&mut s, 3, 7, 11, 15);
+        quarter_round(&mut s, 0, 5, 10, 15);
+        quarter_round(&mut s, 1, 6, 11, 12);
+        quarter_round(&mut s, 2, 7, 8, 13);
+        quarter_round(&mut s, 3, 4, 9, 14);
+    }
+    let mut out = [0u8; 64];
+    for i in 0..16 {
+        out[4 * i..4 * i + 4].copy_from_slice(&s[i].wrapping_add(init[i]).to_le_bytes());
+    }
+    s.zeroize();
+    init.zeroize();
+    out
+}
+
+/// XOR `data` with the keystream starting at block `counter`
+fn chacha20_xor(key: &[u8; KEY_LEN], counter: u32, nonce: &[u8; NONCE_LEN], data: &mut [u8]) {
+    for (i, chunk) in data.chunks_mut(64).enumerate() {
+        let mut block = chacha20_block(key, counter.wrapping_add(i as u32), nonce);
+        for (b, k) in chunk.iter_mut().zip(block) {
+            *b ^= k;
+        }
+        block.zeroize();
+    }
+}
+
+/// Poly1305 one-time authenticator over 26-bit limbs
+struct Poly1305 {
+    r: [u32; 5],
+    h: [u32; 5],
+    pad: [u32; 4],
+}
+
+impl Poly1305 {
+    fn new(key: &[u8; 32]) -> Self {
+        let word = |i: usize| u32::from_le_bytes(key[i..i + 4].try_into().unwrap());
+        Self {
+            r: [
+                word(0) & 0x03ff_ffff,
+                (word(3) >> 2) & 0x03ff_ff03,
+                (word(6) >> 4) & 0x03ff_c0ff,
+                (word(9) >> 6) & 0x03f0_3fff,
+                (word(12) >> 8) & 0x000f_ffff,
+            ],
+            h: [0; 5],
+            pad: [word(16), word(20), word(24), word(28)],
+        }
+    }
+
+    /// Absorb one block of up to 16 bytes, padded with the 2^(8·len) bit
+    fn block(&mut self, data: &[u8]) {
+        let mut buf = [0u8; 17];
+        buf[..data.len()].copy_from_slice(data);
+        buf[data.len()] = 1;
+        let word = |i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+        let h = &mut self.h;
+        h[0] += word(0) & 0x03ff_ffff;
+        h[1] += (word(3) >> 2) & 0x03ff_ffff;
+        h[2] += (word(6) >> 4) & 0x03ff_ffff;
+        h[3] += (word(9) >> 6) & 0x03ff_ffff;
+        h[4] += (word(12) >> 8) | ((buf[16] as u32) << 24);
+
+        let r = self.r.map(u64::from);
+        let s = [r[1] * 5, r[2] * 5, r[3] * 5, r[4] * 5];
+        let h64 = h.map(u64::from);
+        let d = [
+            h64[0] * r[0] + h64[1] * s[3] + h64[2] * s[2] + h64[3] * s[1] + h64[4] * s[0],
+            h64[0] * r[1] + h64[1] * r[0] + h64[2] * s[3] + h64[3] * s[2] + h64[4] * s[1],
+            h64[0] * r[2] + h64[1] * r[1] + h64[2] * r[0] + h64[3] * s[3] + h64[4] * s[2],
+            h64[0] * r[3] + h64[1] * r[2] + h64[2] * r[1] + h64[3] * r[0] + h64[4] * s[3],
+            h64[0] * r[4] + h64[1] * r[3] + h64[2] * r[2] + h64[3] * r[1] + h64[4] * r[0],
+        ];
+
+        let mut carry = 0u64;
+        for i in 0..5 {
+            let v = d[i] + carry;
+            h[i] = (v & 0x03ff_ffff) as u32;
+            carry = v >> 26;
+        }
+        h[0] += (carry * 5) as u32;
+        h[1] += h[0] >> 26;
+        h[0] &= 0x03ff_ffff;
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(16) {
+            self.block(chunk);
+        }
+    }
+
+    /// Zero-pad to a 16-byte boundary, as the AEAD construction requires
+    fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(16) {
+            let mut block = [0u8; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.block(&block);
+        }
+    }
+
+    fn finalize(mut self) -> [u8; TAG_LEN] {
+        let h = &mut self.h;
+        // full carry, then reduce mod 2^130 - 5
+        for i in 1..5 {
+            h[i] += h[i - 1] >> 26;
+            h[i - 1] &= 0x03ff_ffff;
+        }
+        h[0] += (h[4] >> 26) * 5;
+        h[4] &= 0x03ff_ffff;
+        h[1] += h[0] >> 26;
+        h[0] &= 0x03ff_ffff;
+
+        let mut g = [0u32; 5];
+        let mut carry = 5u32;
+        for i in 0..5 {
+            let v = h[i] + carry;
+            g[i] = v & 0x03ff_ffff;
+            carry = v >> 26;
+        }
+        g[4] = g[4].wrapping_sub(1 << 26);
+        // use g if h + 5 overflowed 2^130, i.e. h >= p
+        let mask = (g[4] >> 31).wrapping_sub(1);
+        for i in 0..5 {
+            h[i] = (h[i] & !mask) | (g[i] & mask);
+        }
+
+        let words = [
+            h[0] | (h[1] << 26),
+            (h[1] >> 6) | (h[2] << 20),
+            (h[2] >> 12) | (h[3] << 14),
+            (h[3] >> 18) | (h[4] << 8),
+        ];
+        let mut out = [0u8; TAG_LEN];
+        let mut carry = 0u64;
+        for i in 0..4 {
+            let v = words[i] as u64 + self.pad[i] as u64 + carry;
+            out[4 * i..4 * i + 4].copy_from_slice(&(v as u32).to_le_bytes());
+            carry = v >> 32;
+        }
+        self.r.zeroize();
+        self.h.zeroize();
+        self.pad.zeroize();
+        out
+    }
+}
+
+fn tag(key: &[u8; KEY_LEN], nonce: &[u8; NONCE_LEN], aad: &[u8], ciphertext: &[u8]) -> [u8; 16] {
+    let mut block = chacha20_block(key, 0, nonce);
+    let mut mac = Poly1305::new(block[..32].try_into().unwrap());
+    block.zeroize();
+    mac.update_padded(aad);
+    mac.update_padded(ciphertext);
+    let mut lens = [0u8; 16];
+    lens[..8].copy_from_slice(&(aad.len() as u64).to_le_bytes());
+    lens[8..].copy_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+    mac.update(&lens);
+    mac.finalize()
+}
+
+/// Encrypt and authenticate: `ciphertext || tag`
+pub(crate) fn seal(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8; NONCE_LEN],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Vec<u8> {
+    let mut out = plaintext.to_vec();
+    chacha20_xor(key, 1, nonce, &mut out);
+    let tag = tag(key, nonce, aad, &out);
+    out.extend_from_slice(&tag);
+    out
+}
+
+/// Check the tag and decrypt; None if anything was tampered with
+pub(crate) fn open(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8; NONCE_LEN],
+    aad: &[u8],
+    sealed: &[u8],
+) -> Option<Vec<u8>> {
+    let split = sealed.len().checked_sub(TAG_LEN)?;
+    let (ciphertext, received) = sealed.split_at(split);
+    let expected = tag(key, nonce, aad, ciphertext);
+    if !bool::from(subtle::ConstantTimeEq::ct_eq(&expected[..], received)) {
+        return None;
+    }
+    let mut out = ciphertext.to_vec();
+    chacha20_xor(key, 1, nonce, &mut out);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc8439_aead_vector() {
+        // RFC 8439 section 2.8.2
+        let key: [u8; 32] = core::array::from_fn(|i| 0x80 + i as u8);
+        let nonce: [u8; 12] = hex::decode("070000004041424344454647")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let aad = hex::decode("50515253c0c1c2c3c4c5c6c7").unwrap();
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you \
+only one tip for the future, sunscreen would be it.";
+        let sealed = seal(&key, &nonce, &aad, plaintext);
+        assert_eq!(
+            hex::encode(&sealed),
+            "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+             3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+             92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+             3ff4def08e4b7a9de576d26586cec64b6116\
+             1ae10b594f09e26a7e902ecbd0600691"
+        );
+        assert_eq!(open(&key, &nonce, &aad, &sealed).unwrap(), plaintext);
+
+        let mut tampered = sealed.clone();
+        tampered[0] ^= 1;
+        assert!(open(&key, &nonce, &aad, &tampered).is_none());
+        assert!(open(&key, &nonce, b"other aad", &sealed).is_none());
+        assert!(open(&key, &nonce, &aad, &sealed[..10]).is_none());
+    }
+
+    #[test]
+    fn test_poly1305_rfc8439_vector() {
+        // RFC 8439 section 2.5.2
+        let key: [u8; 32] =
+            hex::decode("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let mut mac = Poly1305::new(&key);
+        mac.update(b"Cryptographic Forum Research Group");
+        assert_eq!(
+            hex::encode(mac.finalize()),
+            "a8061dc1305136c6c22b8baf0c0127a9"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+// src/crypto/aes.rs
+// Bare AES round function and AES-256 key schedule, as CryptoNight uses them
+// (no full AES encryption: CryptoNight only ever needs individual rounds).
+
+/// Multiply in GF(2^8) modulo the AES polynomial x^8 + x^4 + x^3 + x + 1
+pub(crate) const fn gmul(mut a: u8, mut b: u8) -> u8 {
+    let mut p = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            p ^= a;
+        }
+        let hi = a & 0x80;
+        a <<= 1;
+        if hi != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    p
+}
+
+const fn build_sbox() -> [u8; 256] {
+    let mut sbox = [0u8; 256];
+    let mut x = 0usize;
+    while x < 256 {
+        // Multiplicative inverse as x^254 (0 maps to 0)
+        let mut inv = 1u8;
+        let mut i = 0;
+        while i < 254 {
+            inv = gmul(inv, x as u8);
+            i += 1;
+        }
+        if x == 0 {
+            inv = 0;
+        }
+        let b = inv;
+        sbox[x] =
+            b ^ b.rotate_left(1) ^ b.rotate_left(2) ^ b.rotate_left(3) ^ b.rotate_left(4) ^ 0x63;
+        x += 1;
+    }
+    sbox
+}
+
+/// The AES S-box (also used by Groestl)
+pub(crate) const SBOX: [u8; 256] = build_sbox();
+
+/// One AES encryption round: SubBytes, ShiftRows, MixColumns, AddRoundKey
+pub(crate) fn round(block: &mut [u8; 16], key: &[u8; 16]) {
+    let mut s = [0u8; 16];
+    for c in 0..4 {
+        for r in 0..4 {
+            s[r + 4 * c] = SBOX[block[r + 4 * ((c + r) % 4)] as usize];
+        }
+    }
+    for c in 0..4 {
+        let a = [s[4 * c], s[4 * c + 1], s[4 * c + 2], s[4 * c + 3]];
+        block[4 * c] = gmul(a[0], 2) ^ gmul(a[1], 3) ^ a[2] ^ a[3] ^ key[4 * c];
+        block[4 * c + 1] = a[0] ^ gmul(a[1], 2) ^ gmul(a[2], 3) ^ a[3] ^ key[4 * c + 1];
+        block[4 * c + 2] = a[0] ^ a[1] ^ gmul(a[2], 2) ^ gmul(a[3], 3) ^ key[4 * c + 2];
+        block[4 * c + 3] = gmul(a[0], 3) ^ a[1] ^ a[2] ^ gmul(a[3], 2) ^ key[4 * c + 3];
+    }
+}
+
+/// First 10 round keys of the AES-256 schedule for `key`
+pub(crate) fn expand_key(key: &[u8; 32]) -> [[u8; 16]; 10] {
+    const RCON: [u8; 7] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40];
+    let mut w = [[0u8; 4]; 40];
+    for (i, word) in w.iter_mut().take(8).enumerate() {
+        word.copy_from_slice(&key[4 * i..4 * i + 4]);
+    }
+    for i in 8..40 {
+        let mut t = w[i - 1];
+        if i % 8 == 0 {
+            t = [
+                SBOX[t[1] as usize] ^ RCON[i / 8 - 1],
+                SBOX[t[2] as usize],
+                SBOX[t[3] as usize],
+                SBOX[t[0] as usize],
+            ];
+        } else if i % 8 == 4 {
+            t = t.map(|b| SBOX[b as usize]);
+        }
+        for j in 0..4 {
+            w[i][j] = w[i - 8][j] ^ t[j];
+        }
+    }
+    let mut keys = [[0u8; 16]; 10];
+    for (k, round_key) in keys.iter_mut().enumerate() {
+        for j in 0..4 {
+            round_key[4 * j..4 * j + 4].copy_from_slice(&w[4 * k + j]);
+        }
+    }
+    keys
+}
+
+/// CryptoNight's "pseudo round": 10 full AES rounds with no initial key add
+pub(crate) fn pseudo_round(block: &mut [u8; 16], keys: &[[u8; 16]; 10]) {
+    for key in keys {
+        round(block, key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sbox_and_schedule() {
+        assert_eq!(SBOX[0x00], 0x63);
+        assert_eq!(SBOX[0x53], 0xed);
+
+        // FIPS-197 C.3: round[2].k_sch for key 00..1f
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let keys = expand_key(&key);
+        assert_eq!(hex::encode(keys[2]), "a573c29fa176c498a97fce93a572c09c");
+    }
+}
@@ -0,0 +1,252 @@
+// src/crypto/cn.rs
+// CryptoNote primitives: Hs (hash to scalar) and Hp (hash to point)
+use std::{fmt, sync::OnceLock};
+
+use curve25519_dalek::{EdwardsPoint, Scalar, edwards::CompressedEdwardsY, traits::IsIdentity};
+use subtle::{Choice, ConstantTimeEq};
+
+use super::field::{Fe, exponent, sqrt_m1};
+use super::hash::keccak256;
+
+/// Montgomery curve constant A
+const A: u64 = 486662;
+
+/// Square roots used by `ge_fromfe_frombytes_vartime` (fe_fffb1..4 in monero)
+struct FromFeConsts {
+    sqrtm1: Fe,
+    fffb1: Fe,
+    fffb2: Fe,
+    fffb3: Fe,
+    fffb4: Fe,
+}
+
+fn consts() -> &'static FromFeConsts {
+    static CONSTS: OnceLock<FromFeConsts> = OnceLock::new();
+    CONSTS.get_or_init(|| {
+        let sqrtm1 = sqrt_m1();
+        let a_ap2 = Fe::from_u64(A).mul(&Fe::from_u64(A + 2));
+        let two_a_ap2 = Fe::from_u64(2).mul(&a_ap2);
+        let root = |c: Fe| c.sqrt().expect("constant is a square");
+        FromFeConsts {
+            sqrtm1,
+            fffb1: root(two_a_ap2.neg()), // sqrt(-2 * A * (A + 2))
+            fffb2: root(two_a_ap2),       // sqrt(2 * A * (A + 2))
+            fffb3: root(sqrtm1.mul(&a_ap2).neg()), // sqrt(-sqrt(-1) * A * (A + 2))
+            fffb4: root(sqrtm1.mul(&a_ap2)), // sqrt(sqrt(-1) * A * (A + 2))
+        }
+    })
+}
+
+/// Hs(data): Keccak-256 reduced mod l (Monero `hash_to_scalar`)
+pub fn hash_to_scalar(data: &[u8]) -> Scalar {
+    Scalar::from_bytes_mod_order(keccak256(data).to_bytes())
+}
+
+/// Hp(data): Monero `hash_to_ec`
+///
+/// Keccak-256 of `data`, mapped onto the curve with
+/// `ge_fromfe_frombytes_vartime`, then multiplied by the cofactor 8 so the
+/// result is in the prime-order subgroup.
+pub fn hash_to_point(data: &[u8]) -> EdwardsPoint {
+    let h = keccak256(data).to_bytes();
+    ge_fromfe_frombytes_vartime(&h).mul_by_cofactor()
+}
+
+/// Key image `I = x * Hp(P)`: the linkable tag that marks an output as spent
+///
+/// Compared in constant time like the other key types.
+#[derive(Debug, Clone, Copy, Eq)]
+pub struct KeyImage(pub [u8; 32]);
+
+impl ConstantTimeEq for KeyImage {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for KeyImage {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl std::hash::Hash for KeyImage {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl KeyImage {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Decode the point, rejecting anything outside the prime-order subgroup
+    ///
+    /// Monero requires `l * I == 0`; without it an attacker could add a
+    /// small-order component and spend the same output twice.
+    pub fn point(&self) -> Option<EdwardsPoint> {
+        let p = CompressedEdwardsY(self.0).decompress()?;
+        (p.is_torsion_free() && !p.is_identity()).then_some(p)
+    }
+
+    /// True if the key image is a valid prime-order point
+    pub fn is_valid(&self) -> bool {
+        self.point().is_some()
+    }
+}
+
+impl fmt::Display for KeyImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+/// Key image for the one-time output key `pubkey` owned by `secret`
+pub fn generate_key_image(secret: &Scalar, pubkey: &EdwardsPoint) -> KeyImage {
+    let hp = hash_to_point(pubkey.compress().as_bytes());
+    KeyImage((secret * hp).compress().to_bytes())
+}
+
+/// Port of monero's `ge_fromfe_frombytes_vartime` (crypto-ops.c)
+///
+/// Variable time: only ever call this on public data.
+fn ge_fromfe_frombytes_vartime(s: &[u8; 32]) -> EdwardsPoint {
+    let c = consts();
+    let a = Fe::from_u64(A);
+    let ma = a.neg();
+    let ma2 = a.square().neg();
+    let two = Fe::from_u64(2);
+
+    let u = Fe::from_bytes_unmasked(s);
+    let v = two.mul(&u.square()); // 2 * u^2
+    let w = v.add(&Fe::ONE); // w = 2 * u^2 + 1
+    let mut x = w.square().add(&ma2.mul(&v)); // x = w^2 - 2 * A^2 * u^2
+
+    // (w / x)^((p + 3) / 8), via w * x^3 * (w * x^7)^((p - 5) / 8)
+    let x3 = x.square().mul(&x);
+    let x7 = x3.square().mul(&x);
+    let mut rx = w.mul(&x3).mul(&w.mul(&x7).pow(&exponent(0xfd, 0x0f)));
+
+    x = rx.square().mul(&x);
+    let mut z = ma;
+    let sign;
+    if w.sub(&x).is_zero() || w.add(&x).is_zero() {
+        let k = if w.sub(&x).is_zero() {
+            c.fffb2
+        } else {
+            c.fffb1
+        };
+        rx = rx.mul(&k).mul(&u); // u * sqrt(2 * A * (A + 2) * w / x)
+        z = z.mul(&v); // -2 * A * u^2
+        sign = false;
+    } else {
+        x = x.mul(&c.sqrtm1);
+        if w.sub(&x).is_zero() {
+            rx = rx.mul(&c.fffb4);
+        } else {
+            debug_assert!(w.add(&x).is_zero());
+            rx = rx.mul(&c.fffb3);
+        }
+        // rx = sqrt(A * (A + 2) * w / x), z = -A
+        sign = true;
+    }
+    if rx.is_negative() != sign {
+        rx = rx.neg();
+    }
+
+    // Projective (X : Y : Z) -> compressed affine y with the sign of x
+    let pz = z.add(&w);
+    let py = z.sub(&w);
+    let px = rx.mul(&pz);
+    let recip = pz.invert();
+    let mut bytes = py.mul(&recip).to_bytes();
+    bytes[31] ^= (px.mul(&recip).is_negative() as u8) << 7;
+    CompressedEdwardsY(bytes)
+        .decompress()
+        .expect("elligator output is always on the curve")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_hash_to_scalar_is_reduced_keccak() {
+        let s = hash_to_scalar(b"monero");
+        assert_eq!(
+            s,
+            Scalar::from_bytes_mod_order(keccak256(b"monero").to_bytes())
+        );
+        assert!(bool::from(
+            Scalar::from_canonical_bytes(s.to_bytes()).is_some()
+        ));
+    }
+
+    #[test]
+    fn test_hash_to_point_in_prime_subgroup() {
+        for i in 0u32..64 {
+            let p = hash_to_point(&i.to_le_bytes());
+            assert!(p.is_torsion_free());
+            assert!(!p.is_identity());
+        }
+        assert_eq!(hash_to_point(b"abc"), hash_to_point(b"abc"));
+        assert_ne!(hash_to_point(b"abc"), hash_to_point(b"abd"));
+    }
+
+    #[test]
+    fn test_hash_to_point_vectors() {
+        // the second input's Keccak has bit 255 set, which monero keeps
+        for (input, expected) in [
+            (
+                "e8e77626586f73b955364c7b4bbf0bb7f7685ebd40e852b164633a4acbd3244c",
+                "5539d4f604a306d32fadd6947533837c7714e6b90454b5619d777f1e56504253",
+            ),
+            (
+                "2b97a4b75a93aa1ac8581fac0f7d4ab42406569409a737bdf9de584903b372c5",
+                "d6489dc86288dbb4058442f1ddecbd092e5ce9e4e04d847af3cd84179253a75c",
+            ),
+        ] {
+            let point = hash_to_point(&hex::decode(input).unwrap());
+            assert_eq!(hex::encode(point.compress().as_bytes()), expected);
+        }
+    }
+
+    #[test]
+    fn test_key_image_generation() {
+        let x = Scalar::random(&mut OsRng);
+        let p = x * ED25519_BASEPOINT_POINT;
+
+        let ki = generate_key_image(&x, &p);
+        assert!(ki.is_valid());
+        assert_eq!(ki, generate_key_image(&x, &p));
+        assert_eq!(
+            ki.point().unwrap(),
+            x * hash_to_point(p.compress().as_bytes())
+        );
+
+        // Different output -> different key image
+        let y = Scalar::random(&mut OsRng);
+        assert_ne!(ki, generate_key_image(&y, &(y * ED25519_BASEPOINT_POINT)));
+    }
+
+    #[test]
+    fn test_key_image_rejects_torsion() {
+        let x = Scalar::random(&mut OsRng);
+        let p = x * ED25519_BASEPOINT_POINT;
+        let ki = generate_key_image(&x, &p).point().unwrap();
+
+        // Point of order 2: (0, -1)
+        let mut neg_one = [0u8; 32];
+        neg_one[0] = 0xec;
+        neg_one.iter_mut().skip(1).for_each(|b| *b = 0xff);
+        neg_one[31] = 0x7f;
+        let torsion = CompressedEdwardsY(neg_one).decompress().unwrap();
+
+        let tainted = KeyImage((ki + torsion).compress().to_bytes());
+        assert!(!tainted.is_valid());
+        assert!(!KeyImage(EdwardsPoint::default().compress().to_bytes()).is_valid());
+    }
+}
@@ -0,0 +1,136 @@
+// src/crypto/ecdh.rs
+//! X25519 key agreement (RFC 7748), including for our Ed25519 keys.
+//!
+//! This is the Montgomery-form Diffie-Hellman used by transport encryption
+//! and encrypted payment IDs, not CryptoNote's Edwards `8·r·A` output
+//! derivation (see `crypto::keys`).
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use ed25519_dalek::VerifyingKey;
+use rand::{RngCore, rngs::OsRng};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use super::signature::Ed25519Keypair;
+
+/// 32-byte X25519 output, wiped on drop
+#[derive(Clone)]
+pub struct SharedSecret([u8; 32]);
+
+impl SharedSecret {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl ConstantTimeEq for SharedSecret {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for SharedSecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for SharedSecret {}
+
+impl Drop for SharedSecret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// X25519 public key for a (clamped) secret
+pub fn x25519_public(secret: &[u8; 32]) -> [u8; 32] {
+    MontgomeryPoint::mul_base_clamped(*secret).to_bytes()
+}
+
+/// X25519(secret, their_public)
+///
+/// None if the result is all zeros, i.e. `their_public` has small order and
+/// the "shared" secret would be known to anyone.
+pub fn x25519(secret: &[u8; 32], their_public: &[u8; 32]) -> Option<SharedSecret> {
+    let shared = MontgomeryPoint(*their_public)
+        .mul_clamped(*secret)
+        .to_bytes();
+    if shared == [0u8; 32] {
+        return None;
+    }
+    Some(SharedSecret(shared))
+}
+
+/// Fresh ephemeral X25519 secret and its public key
+pub fn ephemeral() -> ([u8; 32], [u8; 32]) {
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    let public = x25519_public(&secret);
+    (secret, public)
+}
+
+/// Montgomery form of an Ed25519 public key (the birational map u = (1+y)/(1-y))
+pub fn ed25519_public_to_x25519(key: &VerifyingKey) -> [u8; 32] {
+    key.to_montgomery().to_bytes()
+}
+
+/// X25519 secret matching `ed25519_public_to_x25519(&keypair.public)`
+pub fn ed25519_secret_to_x25519(keypair: &Ed25519Keypair) -> [u8; 32] {
+    keypair.signing_key.to_scalar_bytes()
+}
+
+/// X25519 between two Ed25519 identities
+pub fn shared_secret(ours: &Ed25519Keypair, theirs: &VerifyingKey) -> Option<SharedSecret> {
+    let mut secret = ed25519_secret_to_x25519(ours);
+    let shared = x25519(&secret, &ed25519_public_to_x25519(theirs));
+    secret.zeroize();
+    shared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn h(s: &str) -> [u8; 32] {
+        hex::decode(s).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn test_x25519_rfc7748() {
+        // RFC 7748 section 6.1
+        let alice = h("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob = h("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        let alice_pub = x25519_public(&alice);
+        let bob_pub = x25519_public(&bob);
+        assert_eq!(
+            alice_pub,
+            h("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+        );
+        assert_eq!(
+            bob_pub,
+            h("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+        );
+        let k = h("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+        assert_eq!(x25519(&alice, &bob_pub).unwrap().as_bytes(), &k);
+        assert_eq!(x25519(&bob, &alice_pub).unwrap().as_bytes(), &k);
+    }
+
+    #[test]
+    fn test_ed25519_conversion_agrees() {
+        let a = Ed25519Keypair::generate();
+        let b = Ed25519Keypair::generate();
+        assert_eq!(
+            x25519_public(&ed25519_secret_to_x25519(&a)),
+            ed25519_public_to_x25519(&a.public)
+        );
+        let ab = shared_secret(&a, &b.public).unwrap();
+        let ba = shared_secret(&b, &a.public).unwrap();
+        assert_eq!(ab.as_bytes(), ba.as_bytes());
+    }
+
+    #[test]
+    fn test_rejects_low_order_point() {
+        let (secret, _) = ephemeral();
+        assert!(x25519(&secret, &[0u8; 32]).is_none());
+    }
+}
@@ -0,0 +1,51 @@
+// src/crypto/error.rs
+// Crate-wide error for the crypto module
+use ed25519_dalek::SignatureError;
+use thiserror::Error;
+
+use super::{hd::HdError, kdf::KdfError, ring::RingError, signature::KeypairError};
+
+/// Any failure from the crypto module
+///
+/// The byte-level APIs return it directly so callers can tell malformed
+/// input from a signature that simply doesn't verify; the per-module errors
+/// convert into it with `?`. Being a `std::error::Error`, it still boxes
+/// into `Box<dyn Error>` for code written against the old signatures.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CryptoError {
+    #[error("expected {expected} bytes, got {got}")]
+    InvalidLength { expected: usize, got: usize },
+    #[error("public key is not a valid curve point")]
+    InvalidPublicKey,
+    #[error("public key has a small-order component")]
+    WeakPublicKey,
+    #[error("signature verification failed")]
+    InvalidSignature,
+    #[error("signature scalar is not canonically encoded")]
+    NonCanonicalSignature,
+    #[error("invalid hex: {0}")]
+    InvalidHex(hex::FromHexError),
+    #[error(transparent)]
+    Keypair(#[from] KeypairError),
+    #[error(transparent)]
+    Kdf(#[from] KdfError),
+    #[error(transparent)]
+    Ring(#[from] RingError),
+    #[error(transparent)]
+    Hd(#[from] HdError),
+}
+
+/// dalek's error is opaque; everything it reports is a failed check
+impl From<SignatureError> for CryptoError {
+    fn from(_: SignatureError) -> Self {
+        CryptoError::InvalidSignature
+    }
+}
+
+/// Borrow `bytes` as an `N`-byte array or report both lengths
+pub(crate) fn fixed<const N: usize>(bytes: &[u8]) -> Result<&[u8; N], CryptoError> {
+    bytes.try_into().map_err(|_| CryptoError::InvalidLength {
+        expected: N,
+        got: bytes.len(),
+    })
+}
@@ -0,0 +1,239 @@
+// src/crypto/field.rs
+//! Minimal arithmetic mod p = 2^255 - 19.
+//!
+//! curve25519-dalek keeps its field element private, but Monero's
+//! `hash_to_point` needs raw field math (ref10's `ge_fromfe_frombytes_vartime`),
+//! so this is a small 5x51-bit limb port for that one job. Not constant time.
+
+use subtle::{Choice, ConstantTimeEq};
+
+const MASK: u64 = (1 << 51) - 1;
+
+/// Equality compares canonical encodings, in constant time
+#[derive(Clone, Copy, Debug, Eq)]
+pub(crate) struct Fe([u64; 5]);
+
+impl ConstantTimeEq for Fe {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.to_bytes().ct_eq(&other.to_bytes())
+    }
+}
+
+impl PartialEq for Fe {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Fe {
+    pub const ZERO: Fe = Fe([0; 5]);
+    pub const ONE: Fe = Fe([1, 0, 0, 0, 0]);
+
+    pub fn from_u64(v: u64) -> Fe {
+        Fe([v & MASK, v >> 51, 0, 0, 0])
+    }
+
+    /// Load 32 little-endian bytes, ignoring the top bit (like ref10 `fe_frombytes`)
+    pub fn from_bytes(b: &[u8; 32]) -> Fe {
+        let w = |i: usize| u64::from_le_bytes(b[i * 8..i * 8 + 8].try_into().unwrap());
+        let (w0, w1, w2, w3) = (w(0), w(1), w(2), w(3));
+        Fe([
+            w0 & MASK,
+            ((w0 >> 51) | (w1 << 13)) & MASK,
+            ((w1 >> 38) | (w2 << 26)) & MASK,
+            ((w2 >> 25) | (w3 << 39)) & MASK,
+            (w3 >> 12) & MASK,
+        ])
+    }
+
+    /// Like [`Fe::from_bytes`] but keeping bit 255, as monero's
+    /// `ge_fromfe_frombytes_vartime` loads its input: `2^255 = 19 (mod p)`
+    pub fn from_bytes_unmasked(b: &[u8; 32]) -> Fe {
+        let mut fe = Fe::from_bytes(b);
+        if b[31] & 0x80 != 0 {
+            fe = fe.add(&Fe::from_u64(19));
+        }
+        fe
+    }
+
+    /// Canonical (fully reduced) little-endian encoding
+    pub fn to_bytes(self) -> [u8; 32] {
+        let mut l = self.carry().0;
+        // Add 19 and see whether it overflows 2^255, i.e. whether l >= p
+        let mut q = (l[0] + 19) >> 51;
+        for limb in &l[1..] {
+            q = (limb + q) >> 51;
+        }
+        l[0] += 19 * q;
+        for i in 0..4 {
+            l[i + 1] += l[i] >> 51;
+            l[i] &= MASK;
+        }
+        l[4] &= MASK;
+
+        let mut out = [0u8; 32];
+        let mut acc: u128 = 0;
+        let mut bits = 0;
+        let mut pos = 0;
+        for limb in l {
+            acc |= (limb as u128) << bits;
+            bits += 51;
+            while bits >= 8 && pos < 32 {
+                out[pos] = acc as u8;
+                acc >>= 8;
+                bits -= 8;
+                pos += 1;
+            }
+        }
+        if pos < 32 {
+            out[pos] = acc as u8;
+        }
+        out
+    }
+
+    fn carry(self) -> Fe {
+        let mut l = self.0;
+        for i in 0..4 {
+            l[i + 1] += l[i] >> 51;
+            l[i] &= MASK;
+        }
+        l[0] += 19 * (l[4] >> 51);
+        l[4] &= MASK;
+        Fe(l)
+    }
+
+    pub fn add(&self, o: &Fe) -> Fe {
+        let a = self.0;
+        let b = o.0;
+        Fe([
+            a[0] + b[0],
+            a[1] + b[1],
+            a[2] + b[2],
+            a[3] + b[3],
+            a[4] + b[4],
+        ])
+        .carry()
+    }
+
+    pub fn sub(&self, o: &Fe) -> Fe {
+        // Add 16p first so limbs never underflow
+        let a = self.carry().0;
+        let b = o.carry().0;
+        Fe([
+            (a[0] + 36028797018963664) - b[0],
+            (a[1] + 36028797018963952) - b[1],
+            (a[2] + 36028797018963952) - b[2],
+            (a[3] + 36028797018963952) - b[3],
+            (a[4] + 36028797018963952) - b[4],
+        ])
+        .carry()
+    }
+
+    pub fn neg(&self) -> Fe {
+        Fe::ZERO.sub(self)
+    }
+
+    pub fn mul(&self, o: &Fe) -> Fe {
+        let a = self.0.map(|x| x as u128);
+        let b = o.0.map(|x| x as u128);
+        let r = [
+            a[0] * b[0] + 19 * (a[1] * b[4] + a[2] * b[3] + a[3] * b[2] + a[4] * b[1]),
+            a[0] * b[1] + a[1] * b[0] + 19 * (a[2] * b[4] + a[3] * b[3] + a[4] * b[2]),
+            a[0] * b[2] + a[1] * b[1] + a[2] * b[0] + 19 * (a[3] * b[4] + a[4] * b[3]),
+            a[0] * b[3] + a[1] * b[2] + a[2] * b[1] + a[3] * b[0] + 19 * (a[4] * b[4]),
+            a[0] * b[4] + a[1] * b[3] + a[2] * b[2] + a[3] * b[1] + a[4] * b[0],
+        ];
+        let mut out = [0u64; 5];
+        let mut c: u128 = 0;
+        for i in 0..5 {
+            let v = r[i] + c;
+            out[i] = (v as u64) & MASK;
+            c = v >> 51;
+        }
+        out[0] += (c as u64) * 19;
+        Fe(out).carry()
+    }
+
+    pub fn square(&self) -> Fe {
+        self.mul(self)
+    }
+
+    /// `self^e` with `e` given as 32 little-endian bytes
+    pub fn pow(&self, e: &[u8; 32]) -> Fe {
+        let mut r = Fe::ONE;
+        for byte in e.iter().rev() {
+            for bit in (0..8).rev() {
+                r = r.square();
+                if (byte >> bit) & 1 == 1 {
+                    r = r.mul(self);
+                }
+            }
+        }
+        r
+    }
+
+    pub fn invert(&self) -> Fe {
+        // p - 2
+        self.pow(&exponent(0xeb, 0x7f))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.to_bytes() == [0u8; 32]
+    }
+
+    /// ref10 `fe_isnegative`: low bit of the canonical encoding
+    pub fn is_negative(&self) -> bool {
+        self.to_bytes()[0] & 1 == 1
+    }
+
+    /// Some square root of `self`, if one exists (p = 5 mod 8)
+    pub fn sqrt(&self) -> Option<Fe> {
+        // (p + 3) / 8
+        let cand = self.pow(&exponent(0xfe, 0x0f));
+        if cand.square() == *self {
+            Some(cand)
+        } else if cand.square() == self.neg() {
+            Some(cand.mul(&sqrt_m1()))
+        } else {
+            None
+        }
+    }
+}
+
+/// 32-byte LE exponent of the form `top || ff..ff || low`
+pub(crate) fn exponent(low: u8, top: u8) -> [u8; 32] {
+    let mut e = [0xffu8; 32];
+    e[0] = low;
+    e[31] = top;
+    e
+}
+
+/// sqrt(-1) = 2^((p - 1) / 4)
+pub(crate) fn sqrt_m1() -> Fe {
+    Fe::from_u64(2).pow(&exponent(0xfb, 0x1f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_basics() {
+        let a = Fe::from_u64(123456789);
+        let b = Fe::from_u64(987654321);
+
+        assert_eq!(a.mul(&a.invert()), Fe::ONE);
+        assert_eq!(a.add(&b).sub(&b), a);
+        assert!(a.add(&a.neg()).is_zero());
+        assert_eq!(sqrt_m1().square(), Fe::ONE.neg());
+
+        let sq = a.square();
+        let r = sq.sqrt().unwrap();
+        assert_eq!(r.square(), sq);
+
+        // p itself encodes to zero, and equals it
+        let p = Fe::from_bytes(&exponent(0xed, 0x7f));
+        assert!(p.is_zero());
+        assert_eq!(p, Fe::ZERO);
+    }
+}
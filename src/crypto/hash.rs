@@ -1,26 +1,367 @@
-use std::{fmt};
+use std::{
+    array::TryFromSliceError,
+    fmt,
+    io::{self, Read},
+    str::FromStr,
+};
 
-use blake2::Blake2b512;
-use sha3::Digest;
+pub(crate) mod argon2;
+mod blake256;
+mod groestl;
+mod jh;
+mod skein;
 
+use blake2::{
+    Blake2b512, Blake2bMac512,
+    digest::{InvalidLength, Mac},
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use sha2::{Sha256, Sha512};
+use sha3::{Digest, Keccak256, Sha3_256, Sha3_512};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
 
+/// Fixed-size hash digest of `N` bytes
+///
+/// Equality is constant-time, since digests double as MACs and checksums.
+#[derive(Debug, Clone, Copy, Eq)]
+pub struct Hash<const N: usize>(pub [u8; N]);
 
+/// 32-byte digest (Keccak-256, tx/block ids)
+pub type Hash256 = Hash<32>;
+/// 64-byte digest (Blake2b-512)
+pub type Hash512 = Hash<64>;
 
+impl<const N: usize> Hash<N> {
+    /// Raw digest bytes
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    /// Consume the hash and return its bytes
+    pub fn to_bytes(self) -> [u8; N] {
+        self.0
+    }
+}
+
+impl<const N: usize> ConstantTimeEq for Hash<N> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
 
-#[derive(Debug ,Clone , PartialEq , Eq ,Hash)]
-pub struct Hash(pub Vec<u8>);
+impl<const N: usize> PartialEq for Hash<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
 
-impl fmt::Display for Hash {
+impl<const N: usize> std::hash::Hash for Hash<N> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<const N: usize> fmt::Display for Hash<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", hex::encode(&self.0))
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for Hash<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Hash<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Hash(bytes)
+    }
+}
+
+impl<const N: usize> From<Hash<N>> for [u8; N] {
+    fn from(hash: Hash<N>) -> Self {
+        hash.0
+    }
+}
+
+impl<const N: usize> TryFrom<&[u8]> for Hash<N> {
+    type Error = TryFromSliceError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Hash(bytes.try_into()?))
+    }
+}
+
+/// Parse from hex (must be exactly `2 * N` characters)
+impl<const N: usize> FromStr for Hash<N> {
+    type Err = hex::FromHexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bytes = [0u8; N];
+        hex::decode_to_slice(s, &mut bytes)?;
+        Ok(Hash(bytes))
     }
 }
 
 /// Blake2b-512 (Monero's primary hash)
-pub fn blake2b(data: &[u8]) -> Hash {
+pub fn blake2b(data: &[u8]) -> Hash512 {
     let mut hasher = Blake2b512::new();
     hasher.update(data);
-    Hash(hasher.finalize().to_vec())
+    Hash(hasher.finalize().into())
+}
+
+/// Keccak-256 (Monero's `cn_fast_hash`, used for tx and block hashing)
+///
+/// This is the original Keccak padding, *not* the finalized SHA3-256.
+pub fn keccak256(data: &[u8]) -> Hash256 {
+    let mut hasher = Keccak256::new();
+    hasher.update(data);
+    Hash(hasher.finalize().into())
+}
+
+/// SHA3-256 (FIPS 202), for interop outside Monero
+pub fn sha3_256(data: &[u8]) -> Hash256 {
+    Hash(Sha3_256::digest(data).into())
+}
+
+/// SHA3-512 (FIPS 202), for interop outside Monero
+pub fn sha3_512(data: &[u8]) -> Hash512 {
+    Hash(Sha3_512::digest(data).into())
+}
+
+/// BLAKE-256 (the SHA-3 finalist, not Blake2)
+pub fn blake256(data: &[u8]) -> Hash256 {
+    Hash(blake256::blake256(data))
+}
+
+/// Groestl-256
+pub fn groestl256(data: &[u8]) -> Hash256 {
+    Hash(groestl::groestl256(data))
+}
+
+/// JH-256
+pub fn jh256(data: &[u8]) -> Hash256 {
+    Hash(jh::jh256(data))
+}
+
+/// Skein-512-256 (Skein-512 state, 256-bit output, as CryptoNight uses it)
+pub fn skein512_256(data: &[u8]) -> Hash256 {
+    Hash(skein::skein512_256(data))
+}
+
+/// Keccak-256 of two concatenated 32-byte hashes (one Merkle tree node)
+fn hash_pair(left: &Hash256, right: &Hash256) -> Hash256 {
+    let mut buf = [0u8; 64];
+    buf[..32].copy_from_slice(&left.0);
+    buf[32..].copy_from_slice(&right.0);
+    keccak256(&buf)
+}
+
+/// Monero `tree_hash`: Merkle root of the tx hashes in a block
+///
+/// Mirrors monerod exactly: when the count is not a power of two, the
+/// first `2 * cnt - count` hashes are carried up unchanged and only the
+/// rest are paired, so the tree is *not* padded by duplicating leaves.
+///
+/// # Panics
+/// If `hashes` is empty (a block always has at least the miner tx).
+pub fn tree_hash(hashes: &[Hash256]) -> Hash256 {
+    let count = hashes.len();
+    assert!(count > 0, "tree_hash of zero hashes");
+    match count {
+        1 => return hashes[0],
+        2 => return hash_pair(&hashes[0], &hashes[1]),
+        _ => {}
+    }
+
+    // Largest power of two strictly below `count`
+    let mut cnt = count.next_power_of_two() >> 1;
+    if cnt == count {
+        cnt >>= 1;
+    }
+
+    let carried = 2 * cnt - count;
+    let mut ints: Vec<Hash256> = hashes[..carried].to_vec();
+    ints.extend(hashes[carried..].chunks(2).map(|p| hash_pair(&p[0], &p[1])));
+
+    while cnt > 2 {
+        cnt >>= 1;
+        for j in 0..cnt {
+            ints[j] = hash_pair(&ints[2 * j], &ints[2 * j + 1]);
+        }
+    }
+    hash_pair(&ints[0], &ints[1])
+}
+
+/// Keyed Blake2b-512 MAC (native Blake2 keying, key up to 64 bytes)
+pub fn blake2b_keyed(key: &[u8], data: &[u8]) -> Result<Hash512, InvalidLength> {
+    let mut mac = <Blake2bMac512 as Mac>::new_from_slice(key)?;
+    Mac::update(&mut mac, data);
+    Ok(Hash(mac.finalize().into_bytes().into()))
+}
+
+/// Check a `blake2b_keyed` tag in constant time
+pub fn verify_blake2b_keyed(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    let Ok(mut mac) = <Blake2bMac512 as Mac>::new_from_slice(key) else {
+        return false;
+    };
+    Mac::update(&mut mac, data);
+    mac.verify_slice(tag).is_ok()
+}
+
+/// HMAC (RFC 2104) over a hash with a `BLOCK`-byte input block
+///
+/// The padded key blocks and the inner digest are wiped before returning.
+fn hmac<D: Digest, const BLOCK: usize, const N: usize>(key: &[u8], data: &[u8]) -> Hash<N> {
+    let mut key_block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        let mut hashed = D::digest(key);
+        key_block[..hashed.len()].copy_from_slice(&hashed);
+        hashed.as_mut_slice().zeroize();
+    } else {
+        key_block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut pad = [0u8; BLOCK];
+    for (p, k) in pad.iter_mut().zip(&key_block) {
+        *p = k ^ 0x36;
+    }
+    let mut inner = D::new();
+    Digest::update(&mut inner, pad);
+    Digest::update(&mut inner, data);
+    let mut inner_hash = inner.finalize();
+
+    for (p, k) in pad.iter_mut().zip(&key_block) {
+        *p = k ^ 0x5c;
+    }
+    let mut outer = D::new();
+    Digest::update(&mut outer, pad);
+    Digest::update(&mut outer, &inner_hash);
+    let tag = Hash(outer.finalize().as_slice().try_into().unwrap());
+
+    key_block.zeroize();
+    pad.zeroize();
+    inner_hash.as_mut_slice().zeroize();
+    tag
+}
+
+/// HMAC-SHA512 (RFC 2104), e.g. for SLIP-0010 style key derivation
+pub fn hmac_sha512(key: &[u8], data: &[u8]) -> Hash512 {
+    hmac::<Sha512, 128, 64>(key, data)
+}
+
+/// HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Hash256 {
+    hmac::<Sha256, 64, 32>(key, data)
+}
+
+/// PBKDF2-HMAC-SHA512 (RFC 8018) producing one 64-byte block, as BIP39 uses
+pub fn pbkdf2_hmac_sha512(password: &[u8], salt: &[u8], rounds: u32) -> Hash512 {
+    let mut block = salt.to_vec();
+    block.extend_from_slice(&1u32.to_be_bytes());
+    let mut u = hmac_sha512(password, &block);
+    let mut out = u;
+    for _ in 1..rounds {
+        u = hmac_sha512(password, u.as_bytes());
+        for (o, b) in out.0.iter_mut().zip(u.as_bytes()) {
+            *o ^= b;
+        }
+    }
+    u.0.zeroize();
+    out
+}
+
+/// PBKDF2-HMAC-SHA256 (RFC 8018) producing one 32-byte block
+pub fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], rounds: u32) -> Hash256 {
+    let mut block = salt.to_vec();
+    block.extend_from_slice(&1u32.to_be_bytes());
+    let mut u = hmac_sha256(password, &block);
+    let mut out = u;
+    for _ in 1..rounds {
+        u = hmac_sha256(password, u.as_bytes());
+        for (o, b) in out.0.iter_mut().zip(u.as_bytes()) {
+            *o ^= b;
+        }
+    }
+    u.0.zeroize();
+    out
+}
+
+/// Incremental Blake2b-512 hasher for data that doesn't fit in memory
+#[derive(Clone, Default)]
+pub struct Hasher {
+    inner: Blake2b512,
+}
+
+impl Hasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed more data into the hash
+    pub fn update(&mut self, data: &[u8]) -> &mut Self {
+        Digest::update(&mut self.inner, data);
+        self
+    }
+
+    /// Finish hashing; same result as `blake2b()` over all the updates
+    pub fn finalize(self) -> Hash512 {
+        Hash(self.inner.finalize().into())
+    }
+}
+
+/// Blake2b-512 of everything read from `reader` (files, sockets, ...)
+pub fn hash_reader(mut reader: impl Read) -> io::Result<Hash512> {
+    let mut hasher = Hasher::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                hasher.update(&buf[..n]);
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(hasher.finalize())
+}
+
+/// Chunked Blake2b-512 over a 2-level tree, leaves hashed on rayon's pool
+///
+/// Construction (so other implementations can reproduce it):
+/// - leaf_i = Blake2b-512(0x00 || chunk_i), chunks of `chunk_size` bytes
+///   (the last may be shorter; empty input has one empty chunk)
+/// - root = Blake2b-512(0x01 || chunk_size as u64 LE || leaf_0 || leaf_1 || ...)
+///
+/// The result depends only on `data` and `chunk_size`, never on thread count.
+/// Not interchangeable with `blake2b()`.
+#[cfg(feature = "parallel")]
+pub fn hash_parallel(data: &[u8], chunk_size: usize) -> Hash512 {
+    assert!(chunk_size > 0, "chunk_size must be non-zero");
+    let chunks: Vec<&[u8]> = if data.is_empty() {
+        vec![data]
+    } else {
+        data.chunks(chunk_size).collect()
+    };
+
+    let leaf = |chunk: &[u8]| {
+        let mut h = Hasher::new();
+        h.update(&[0x00]).update(chunk);
+        h.finalize()
+    };
+
+    let leaves: Vec<Hash512> = chunks.par_iter().map(|c| leaf(c)).collect();
+
+    let mut root = Hasher::new();
+    root.update(&[0x01]).update(&(chunk_size as u64).to_le_bytes());
+    for l in &leaves {
+        root.update(l.as_ref());
+    }
+    root.finalize()
 }
 
 #[test]
@@ -30,7 +371,7 @@ fn test_blake2b_known_value() {
    
     // Actually better: don't hardcode unless verified
     println!("Hash of 'Hello Monero!': {}", hash);
-    assert_eq!(hash.0.len(), 64);
+    assert_eq!(hash.as_ref().len(), 64);
 }
 
 #[test]
@@ -40,7 +381,261 @@ fn test_hash_determinism() {
     assert_eq!(h1, h2);
 }
 
+#[test]
+fn test_keccak256_vectors() {
+    // Same digests monerod's cn_fast_hash produces
+    let vectors: [(&[u8], &str); 3] = [
+        (b"", "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"),
+        (b"abc", "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"),
+        (
+            b"The quick brown fox jumps over the lazy dog",
+            "4d741b6f1eb29cb2a9b9911c82f56fa8d73b04959d3d9d222895df6c0b28aa15",
+        ),
+    ];
+    for (input, expected) in vectors {
+        let hash = keccak256(input);
+        assert_eq!(hash.to_string(), expected);
+    }
+}
+
+#[test]
+fn test_hash_hex_roundtrip() {
+    let hash = keccak256(b"abc");
+    let parsed: Hash256 = hash.to_string().parse().unwrap();
+    assert_eq!(parsed, hash);
 
+    // Wrong length or non-hex input is rejected
+    assert!("abcd".parse::<Hash256>().is_err());
+    assert!("zz".repeat(32).parse::<Hash256>().is_err());
+    assert!(Hash256::try_from(&[0u8; 31][..]).is_err());
+}
 
+#[test]
+fn test_hash_constant_time_eq() {
+    let a = keccak256(b"abc");
+    let mut b = a;
+    assert!(bool::from(a.ct_eq(&b)));
+    b.0[31] ^= 1;
+    assert!(!bool::from(a.ct_eq(&b)));
+    assert_ne!(a, b);
 
+    let set: std::collections::HashSet<Hash256> = [a, b, a].into_iter().collect();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_streaming_matches_one_shot() {
+    let data = vec![0xabu8; 200_000];
+
+    let mut hasher = Hasher::new();
+    for chunk in data.chunks(7_777) {
+        hasher.update(chunk);
+    }
+    assert_eq!(hasher.finalize(), blake2b(&data));
+
+    let from_reader = hash_reader(io::Cursor::new(&data)).unwrap();
+    assert_eq!(from_reader, blake2b(&data));
+}
+
+#[test]
+fn test_blake2b_keyed_mac() {
+    let key = b"wallet cache key";
+    let tag = blake2b_keyed(key, b"cache contents").unwrap();
+
+    assert_ne!(tag, blake2b(b"cache contents"));
+    assert!(verify_blake2b_keyed(key, b"cache contents", tag.as_ref()));
+    assert!(!verify_blake2b_keyed(key, b"cache c0ntents", tag.as_ref()));
+    assert!(!verify_blake2b_keyed(b"other key", b"cache contents", tag.as_ref()));
+    assert!(!verify_blake2b_keyed(key, b"cache contents", &tag.as_ref()[..32]));
+
+    // Blake2b keys are limited to 64 bytes
+    assert!(blake2b_keyed(&[0u8; 65], b"x").is_err());
+}
 
+#[test]
+fn test_blake2b_keyed_kat() {
+    // First keyed entry of the reference blake2b-kat.txt (key = 00..3f, empty input)
+    let key: Vec<u8> = (0u8..64).collect();
+    assert_eq!(
+        blake2b_keyed(&key, b"").unwrap().to_string(),
+        "10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568"
+    );
+}
+
+#[test]
+fn test_tree_hash_shapes() {
+    let h: Vec<Hash256> = (0u8..5).map(|i| keccak256(&[i])).collect();
+
+    assert_eq!(tree_hash(&h[..1]), h[0]);
+    assert_eq!(tree_hash(&h[..2]), hash_pair(&h[0], &h[1]));
+
+    // 3 leaves: h0 carried up, (h1,h2) paired
+    assert_eq!(
+        tree_hash(&h[..3]),
+        hash_pair(&h[0], &hash_pair(&h[1], &h[2]))
+    );
+
+    // 4 leaves: perfect binary tree
+    assert_eq!(
+        tree_hash(&h[..4]),
+        hash_pair(&hash_pair(&h[0], &h[1]), &hash_pair(&h[2], &h[3]))
+    );
+
+    // 5 leaves: h0..h2 carried, (h3,h4) paired, then a 4-wide tree
+    assert_eq!(
+        tree_hash(&h[..5]),
+        hash_pair(
+            &hash_pair(&h[0], &h[1]),
+            &hash_pair(&h[2], &hash_pair(&h[3], &h[4]))
+        )
+    );
+}
+
+/// Block 202612's tx hashes, the miner transaction's first. All 514 hash to
+/// the block's correct id; the first 512 alone to the id the chain accepted
+/// when `tree_hash` dropped the last two.
+#[test]
+fn test_tree_hash_block_202612() {
+    use crate::block::BlockHeader;
+    use crate::encoding::varint;
+
+    let hashes: Vec<Hash256> = include_str!("testdata/block_202612_tx_hashes.txt")
+        .lines()
+        .map(|line| line.parse().unwrap())
+        .collect();
+    assert_eq!(hashes.len(), 514);
+    let header = BlockHeader {
+        major_version: 1,
+        minor_version: 0,
+        timestamp: 1_409_804_570,
+        prev_id: "5da0a3d004c352a90cc86b00fab676695d76a4d1de16036c41ba4dd188c4d76f"
+            .parse()
+            .unwrap(),
+        nonce: 1_073_744_198,
+    };
+    let id = |root: Hash256| {
+        let mut blob = header.to_bytes();
+        blob.extend_from_slice(root.as_bytes());
+        varint::write(&mut blob, hashes.len() as u64);
+        let mut data = Vec::new();
+        varint::write(&mut data, blob.len() as u64);
+        data.extend_from_slice(&blob);
+        keccak256(&data).to_string()
+    };
+    assert_eq!(
+        id(tree_hash(&hashes)),
+        "426d16cff04c71f8b16340b722dc4010a2dd3831c22041431f772547ba6e331a"
+    );
+    assert_eq!(
+        id(tree_hash(&hashes[..512])),
+        "bbd604d2ba11ba27935e006ed39c9bfdd99b76bf4a50654bc1e1e61217962698"
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_tree_hash_empty() {
+    tree_hash(&[]);
+}
+
+#[test]
+fn test_sha3_vectors() {
+    assert_eq!(
+        sha3_256(b"").to_string(),
+        "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+    );
+    assert_eq!(
+        sha3_256(b"abc").to_string(),
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+    );
+    assert_eq!(
+        sha3_512(b"").to_string(),
+        "a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a615b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26"
+    );
+    // SHA3 and Keccak differ only in padding
+    assert_ne!(sha3_256(b"abc"), keccak256(b"abc"));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_hash_parallel_tree() {
+    let data: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+    let root = hash_parallel(&data, 4096);
+
+    // Same construction done by hand, single threaded
+    let mut expected = Hasher::new();
+    expected.update(&[0x01]).update(&4096u64.to_le_bytes());
+    for chunk in data.chunks(4096) {
+        let mut leaf = Hasher::new();
+        leaf.update(&[0x00]).update(chunk);
+        expected.update(leaf.finalize().as_ref());
+    }
+    assert_eq!(root, expected.finalize());
+
+    assert_ne!(root, hash_parallel(&data, 8192));
+    assert_eq!(hash_parallel(b"", 1024), hash_parallel(b"", 1024));
+}
+
+#[test]
+fn test_hmac_sha512_rfc4231() {
+    // Test case 2
+    assert_eq!(
+        hmac_sha512(b"Jefe", b"what do ya want for nothing?").to_string(),
+        "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+    );
+    // Test case 6: key longer than the block size
+    assert_eq!(
+        hmac_sha512(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First"
+        )
+        .to_string(),
+        "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"
+    );
+}
+
+#[test]
+fn test_pbkdf2_hmac_sha512() {
+    assert_eq!(
+        pbkdf2_hmac_sha512(b"password", b"salt", 1).to_string(),
+        "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252c02d470a285a0501bad999bfe943c08f050235d7d68b1da55e63f73b60a57fce"
+    );
+    assert_eq!(
+        pbkdf2_hmac_sha512(b"password", b"salt", 4096).to_string(),
+        "d197b1b33db0143e018b12f3d1d1479e6cdebdcc97c5c0f87f6902e072f457b5143f30602641b3d55cd335988cb36b84376060ecd532e039b742a239434af2d5"
+    );
+}
+
+#[test]
+fn test_hmac_and_pbkdf2_sha256() {
+    assert_eq!(
+        hmac_sha256(b"Jefe", b"what do ya want for nothing?").to_string(),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    assert_eq!(
+        pbkdf2_hmac_sha256(b"password", b"salt", 2).to_string(),
+        "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43"
+    );
+}
+
+#[test]
+fn test_argon2id_rfc9106() {
+    // RFC 9106 section 5.3
+    let mut tag = [0u8; 32];
+    argon2::argon2id(
+        &[0x01; 32],
+        &[0x02; 16],
+        &[0x03; 8],
+        &[0x04; 12],
+        argon2::Params {
+            t_cost: 3,
+            m_cost: 32,
+            lanes: 4,
+        },
+        &mut tag,
+    );
+    assert_eq!(
+        hex::encode(tag),
+        "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"
+    );
+}
@@ -0,0 +1,241 @@
+// src/crypto/hash/argon2.rs
+// Argon2id v1.3 (RFC 9106), the memory-hard password hash behind our KDFs
+
+use blake2::{
+    Blake2b512, Blake2bVar,
+    digest::{Digest, Update, VariableOutput},
+};
+use zeroize::Zeroize;
+
+const BLOCK_WORDS: usize = 128;
+const SYNC_POINTS: u32 = 4;
+const VERSION: u32 = 0x13;
+const ARGON2ID: u32 = 2;
+
+/// Cost parameters: passes, memory in KiB, and lane count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Params {
+    pub t_cost: u32,
+    pub m_cost: u32,
+    pub lanes: u32,
+}
+
+type Block = [u64; BLOCK_WORDS];
+
+/// H' from RFC 9106 section 3.3: Blake2b stretched to any output length
+fn hash_long(input: &[&[u8]], out: &mut [u8]) {
+    let len = (out.len() as u32).to_le_bytes();
+    if out.len() <= 64 {
+        let mut h = Blake2bVar::new(out.len()).unwrap();
+        h.update(&len);
+        for part in input {
+            h.update(part);
+        }
+        h.finalize_variable(out).unwrap();
+        return;
+    }
+
+    let mut h = Blake2b512::new();
+    Digest::update(&mut h, len);
+    for part in input {
+        Digest::update(&mut h, part);
+    }
+    let mut v: [u8; 64] = h.finalize().into();
+    let mut pos = 0;
+    while out.len() - pos > 64 {
+        out[pos..pos + 32].copy_from_slice(&v[..32]);
+        pos += 32;
+        if out.len() - pos > 64 {
+            v = Blake2b512::digest(v).into();
+        }
+    }
+    let mut h = Blake2bVar::new(out.len() - pos).unwrap();
+    h.update(&v);
+    h.finalize_variable(&mut out[pos..]).unwrap();
+}
+
+#[inline(always)]
+fn gb(v: &mut Block, a: usize, b: usize, c: usize, d: usize) {
+    let fblamka = |x: u64, y: u64| {
+        x.wrapping_add(y)
+            .wrapping_add(2u64.wrapping_mul(x & 0xffff_ffff).wrapping_mul(y & 0xffff_ffff))
+    };
+    v[a] = fblamka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = fblamka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = fblamka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = fblamka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Permutation P over the 16 words at `idx`
+fn permute(v: &mut Block, idx: [usize; 16]) {
+    gb(v, idx[0], idx[4], idx[8], idx[12]);
+    gb(v, idx[1], idx[5], idx[9], idx[13]);
+    gb(v, idx[2], idx[6], idx[10], idx[14]);
+    gb(v, idx[3], idx[7], idx[11], idx[15]);
+    gb(v, idx[0], idx[5], idx[10], idx[15]);
+    gb(v, idx[1], idx[6], idx[11], idx[12]);
+    gb(v, idx[2], idx[7], idx[8], idx[13]);
+    gb(v, idx[3], idx[4], idx[9], idx[14]);
+}
+
+/// Compression function G(x, y)
+fn compress(x: &Block, y: &Block) -> Block {
+    let r: Block = core::array::from_fn(|i| x[i] ^ y[i]);
+    let mut z = r;
+    for row in 0..8 {
+        permute(&mut z, core::array::from_fn(|i| 16 * row + i));
+    }
+    for col in 0..8 {
+        permute(&mut z, core::array::from_fn(|i| 2 * col + (i / 2) * 16 + (i % 2)));
+    }
+    core::array::from_fn(|i| z[i] ^ r[i])
+}
+
+fn block_from_bytes(bytes: &[u8; 1024]) -> Block {
+    core::array::from_fn(|i| u64::from_le_bytes(bytes[8 * i..8 * i + 8].try_into().unwrap()))
+}
+
+/// Argon2id tag of `out.len()` bytes (4 or more)
+///
+/// `m_cost` is rounded down to a multiple of `4 * lanes` and must be at
+/// least `8 * lanes`; callers validate parameters before getting here.
+pub(crate) fn argon2id(
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    ad: &[u8],
+    params: Params,
+    out: &mut [u8],
+) {
+    let Params {
+        t_cost,
+        m_cost,
+        lanes,
+    } = params;
+    assert!(t_cost >= 1 && lanes >= 1 && m_cost >= 8 * lanes && out.len() >= 4);
+
+    let mut h = Blake2b512::new();
+    for word in [lanes, out.len() as u32, m_cost, t_cost, VERSION, ARGON2ID] {
+        Digest::update(&mut h, word.to_le_bytes());
+    }
+    for field in [password, salt, secret, ad] {
+        Digest::update(&mut h, (field.len() as u32).to_le_bytes());
+        Digest::update(&mut h, field);
+    }
+    let h0: [u8; 64] = h.finalize().into();
+
+    let segment_len = m_cost / (SYNC_POINTS * lanes);
+    let lane_len = segment_len * SYNC_POINTS;
+    let blocks = (lane_len * lanes) as usize;
+    let mut memory: Vec<Block> = vec![[0; BLOCK_WORDS]; blocks];
+    let at = |lane: u32, col: u32| (lane * lane_len + col) as usize;
+
+    let mut bytes = [0u8; 1024];
+    for lane in 0..lanes {
+        for col in 0..2u32 {
+            hash_long(&[&h0, &col.to_le_bytes(), &lane.to_le_bytes()], &mut bytes);
+            memory[at(lane, col)] = block_from_bytes(&bytes);
+        }
+    }
+
+    let zero: Block = [0; BLOCK_WORDS];
+    for pass in 0..t_cost {
+        for slice in 0..SYNC_POINTS {
+            for lane in 0..lanes {
+                // Argon2i-style addressing for the first half of the first pass
+                let independent = pass == 0 && slice < 2;
+                let mut input = zero;
+                let mut addresses = zero;
+                if independent {
+                    input[..6].copy_from_slice(&[
+                        pass as u64,
+                        lane as u64,
+                        slice as u64,
+                        blocks as u64,
+                        t_cost as u64,
+                        ARGON2ID as u64,
+                    ]);
+                }
+                let start = if pass == 0 && slice == 0 {
+                    if independent {
+                        input[6] += 1;
+                        addresses = compress(&zero, &compress(&zero, &input));
+                    }
+                    2
+                } else {
+                    0
+                };
+
+                for i in start..segment_len {
+                    let col = slice * segment_len + i;
+                    let prev = if col == 0 { lane_len - 1 } else { col - 1 };
+                    let rand = if independent {
+                        if i % BLOCK_WORDS as u32 == 0 {
+                            input[6] += 1;
+                            addresses = compress(&zero, &compress(&zero, &input));
+                        }
+                        addresses[(i % BLOCK_WORDS as u32) as usize]
+                    } else {
+                        memory[at(lane, prev)][0]
+                    };
+                    let j1 = rand & 0xffff_ffff;
+                    let j2 = rand >> 32;
+
+                    let ref_lane = if pass == 0 && slice == 0 {
+                        lane
+                    } else {
+                        (j2 % lanes as u64) as u32
+                    };
+                    let finished = if pass == 0 {
+                        slice * segment_len
+                    } else {
+                        lane_len - segment_len
+                    };
+                    let area = if ref_lane == lane {
+                        finished + i - 1
+                    } else if i == 0 {
+                        finished - 1
+                    } else {
+                        finished
+                    } as u64;
+                    let x = (j1 * j1) >> 32;
+                    let rel = area - 1 - ((area * x) >> 32);
+                    let origin = if pass == 0 || slice == SYNC_POINTS - 1 {
+                        0
+                    } else {
+                        (slice + 1) * segment_len
+                    } as u64;
+                    let ref_col = ((origin + rel) % lane_len as u64) as u32;
+
+                    let next = compress(&memory[at(lane, prev)], &memory[at(ref_lane, ref_col)]);
+                    let cur = &mut memory[at(lane, col)];
+                    if pass == 0 {
+                        *cur = next;
+                    } else {
+                        for (w, n) in cur.iter_mut().zip(next) {
+                            *w ^= n;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut last = memory[at(0, lane_len - 1)];
+    for lane in 1..lanes {
+        for (w, b) in last.iter_mut().zip(memory[at(lane, lane_len - 1)]) {
+            *w ^= b;
+        }
+    }
+    for (chunk, w) in bytes.chunks_exact_mut(8).zip(last) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+    hash_long(&[&bytes], out);
+    bytes.zeroize();
+    last.zeroize();
+    memory.zeroize();
+}
@@ -0,0 +1,89 @@
+// src/crypto/hash/groestl.rs
+// Groestl-256 (final-round tweaked version), one of CryptoNight's final hashes
+use crate::crypto::aes::{SBOX, gmul};
+
+const ROUNDS: u8 = 10;
+const MIX: [u8; 8] = [2, 2, 3, 4, 5, 3, 5, 7];
+
+/// State is 8x8 bytes; byte `i` sits at row `i % 8`, column `i / 8`
+type State = [u8; 64];
+
+fn permute(state: &mut State, q: bool) {
+    let shifts: [usize; 8] = if q {
+        [1, 3, 5, 7, 0, 2, 4, 6]
+    } else {
+        [0, 1, 2, 3, 4, 5, 6, 7]
+    };
+    for r in 0..ROUNDS {
+        // AddRoundConstant
+        for col in 0..8 {
+            let c = ((col as u8) << 4) ^ r;
+            if q {
+                for row in 0..8 {
+                    state[col * 8 + row] ^= 0xff;
+                }
+                state[col * 8 + 7] ^= c;
+            } else {
+                state[col * 8] ^= c;
+            }
+        }
+        // SubBytes + ShiftBytes
+        let mut t = [0u8; 64];
+        for row in 0..8 {
+            for col in 0..8 {
+                t[col * 8 + row] = SBOX[state[((col + shifts[row]) % 8) * 8 + row] as usize];
+            }
+        }
+        // MixBytes
+        for col in 0..8 {
+            for row in 0..8 {
+                let mut acc = 0u8;
+                for k in 0..8 {
+                    acc ^= gmul(MIX[(k + 8 - row) % 8], t[col * 8 + k]);
+                }
+                state[col * 8 + row] = acc;
+            }
+        }
+    }
+}
+
+/// Groestl-256 digest of `data`
+pub(crate) fn groestl256(data: &[u8]) -> [u8; 32] {
+    let mut h: State = [0; 64];
+    h[62] = 0x01; // output length 256, big-endian
+
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    let blocks = (msg.len() / 64 + 1) as u64;
+    msg.extend_from_slice(&blocks.to_be_bytes());
+
+    for block in msg.chunks_exact(64) {
+        let mut p: State = core::array::from_fn(|i| h[i] ^ block[i]);
+        let mut q: State = block.try_into().unwrap();
+        permute(&mut p, false);
+        permute(&mut q, true);
+        for i in 0..64 {
+            h[i] ^= p[i] ^ q[i];
+        }
+    }
+
+    let mut p = h;
+    permute(&mut p, false);
+    core::array::from_fn(|i| p[32 + i] ^ h[32 + i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groestl256_vector() {
+        assert_eq!(
+            hex::encode(groestl256(b"The quick brown fox jumps over the lazy dog")),
+            "8c7ad62eb26a21297bc39c2d7293b4bd4d3399fa8afab29e970471739e28b301"
+        );
+    }
+}
@@ -0,0 +1,144 @@
+// src/crypto/hash/jh.rs
+// JH-256, one of CryptoNight's final hashes.
+// Straight port of the 4-bit reference implementation (jh_ref.h): slow, but
+// fine for small inputs like CryptoNight's 200-byte state.
+
+const S: [[u8; 16]; 2] = [
+    [9, 0, 4, 11, 13, 12, 3, 15, 1, 10, 2, 6, 7, 5, 8, 14],
+    [3, 12, 6, 13, 5, 7, 1, 9, 15, 2, 0, 4, 11, 10, 14, 8],
+];
+
+/// Fractional part of sqrt(2), one nibble per element
+const ROUND_CONSTANT_ZERO: [u8; 64] = [
+    0x6, 0xa, 0x0, 0x9, 0xe, 0x6, 0x6, 0x7, 0xf, 0x3, 0xb, 0xc, 0xc, 0x9, 0x0, 0x8, 0xb, 0x2, 0xf,
+    0xb, 0x1, 0x3, 0x6, 0x6, 0xe, 0xa, 0x9, 0x5, 0x7, 0xd, 0x3, 0xe, 0x3, 0xa, 0xd, 0xe, 0xc, 0x1,
+    0x7, 0x5, 0x1, 0x2, 0x7, 0x7, 0x5, 0x0, 0x9, 0x9, 0xd, 0xa, 0x2, 0xf, 0x5, 0x9, 0x0, 0xb, 0x0,
+    0x6, 0x6, 0x7, 0x3, 0x2, 0x2, 0xa,
+];
+
+/// Linear transformation L on two 4-bit elements
+fn l(a: &mut u8, b: &mut u8) {
+    *b ^= ((*a << 1) ^ (*a >> 3) ^ ((*a >> 2) & 2)) & 0xf;
+    *a ^= ((*b << 1) ^ (*b >> 3) ^ ((*b >> 2) & 2)) & 0xf;
+}
+
+/// Permutation layer shared by R8 (n = 256) and R6 (n = 64)
+fn permute(a: &mut [u8]) {
+    let n = a.len();
+    // Pi: swap elements 2 and 3 of every group of 4
+    for i in (0..n).step_by(4) {
+        a.swap(i + 2, i + 3);
+    }
+    // P': even elements to the front half, odd to the back
+    let tmp = a.to_vec();
+    for i in 0..n / 2 {
+        a[i] = tmp[2 * i];
+        a[i + n / 2] = tmp[2 * i + 1];
+    }
+    // Phi: swap adjacent pairs in the back half
+    for i in (n / 2..n).step_by(2) {
+        a.swap(i, i + 1);
+    }
+}
+
+fn r8(a: &mut [u8; 256], rc: &[u8; 64]) {
+    for (i, x) in a.iter_mut().enumerate() {
+        let bit = (rc[i >> 2] >> (3 - (i & 3))) & 1;
+        *x = S[bit as usize][*x as usize];
+    }
+    for pair in a.chunks_exact_mut(2) {
+        let (x, y) = pair.split_at_mut(1);
+        l(&mut x[0], &mut y[0]);
+    }
+    permute(a);
+}
+
+fn update_round_constant(rc: &mut [u8; 64]) {
+    for x in rc.iter_mut() {
+        *x = S[0][*x as usize];
+    }
+    for pair in rc.chunks_exact_mut(2) {
+        let (x, y) = pair.split_at_mut(1);
+        l(&mut x[0], &mut y[0]);
+    }
+    permute(rc);
+}
+
+fn e8(h: &mut [u8; 128]) {
+    let bit = |h: &[u8; 128], i: usize| (h[i >> 3] >> (7 - (i & 7))) & 1;
+
+    // Group the 1024 bits into 256 nibbles
+    let mut tem = [0u8; 256];
+    for (i, t) in tem.iter_mut().enumerate() {
+        *t = (bit(h, i) << 3) | (bit(h, i + 256) << 2) | (bit(h, i + 512) << 1) | bit(h, i + 768);
+    }
+    let mut a = [0u8; 256];
+    for i in 0..128 {
+        a[i << 1] = tem[i];
+        a[(i << 1) + 1] = tem[i + 128];
+    }
+
+    let mut rc = ROUND_CONSTANT_ZERO;
+    for _ in 0..42 {
+        r8(&mut a, &rc);
+        update_round_constant(&mut rc);
+    }
+
+    // De-group back into bytes
+    for i in 0..128 {
+        tem[i] = a[i << 1];
+        tem[i + 128] = a[(i << 1) + 1];
+    }
+    h.fill(0);
+    for (i, &t) in tem.iter().enumerate() {
+        let shift = 7 - (i & 7);
+        h[i >> 3] |= ((t >> 3) & 1) << shift;
+        h[(i + 256) >> 3] |= ((t >> 2) & 1) << shift;
+        h[(i + 512) >> 3] |= ((t >> 1) & 1) << shift;
+        h[(i + 768) >> 3] |= (t & 1) << shift;
+    }
+}
+
+fn f8(h: &mut [u8; 128], block: &[u8]) {
+    for i in 0..64 {
+        h[i] ^= block[i];
+    }
+    e8(h);
+    for i in 0..64 {
+        h[i + 64] ^= block[i];
+    }
+}
+
+/// JH-256 digest of `data`
+pub(crate) fn jh256(data: &[u8]) -> [u8; 32] {
+    let mut h = [0u8; 128];
+    h[0] = 0x01; // hash bit length 256, big-endian
+    h[1] = 0x00;
+    f8(&mut h, &[0u8; 64]);
+
+    // Padding: 0x80, zeros, 128-bit big-endian bit length. Unlike MD-style
+    // padding it is always 512 + (-len mod 512) bits, never squeezed in.
+    let padded_len = data.len() + 64 + (64 - data.len() % 64) % 64;
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    msg.resize(padded_len - 16, 0);
+    msg.extend_from_slice(&((data.len() as u128) * 8).to_be_bytes());
+
+    for block in msg.chunks_exact(64) {
+        f8(&mut h, block);
+    }
+    h[96..].try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jh256_empty() {
+        assert_eq!(
+            hex::encode(jh256(b"")),
+            "46e64619c18bb0a92a5e87185a47eef83ca747b8fcc8e1412921357e326df434"
+        );
+    }
+}
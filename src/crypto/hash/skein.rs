@@ -0,0 +1,119 @@
+// src/crypto/hash/skein.rs
+// Skein-512-256 (v1.3), one of CryptoNight's final hashes
+
+const ROT: [[u32; 4]; 8] = [
+    [46, 36, 19, 37],
+    [33, 27, 14, 42],
+    [17, 49, 36, 39],
+    [44, 9, 54, 56],
+    [39, 30, 34, 24],
+    [13, 50, 10, 17],
+    [25, 29, 39, 43],
+    [8, 35, 56, 22],
+];
+const PERM: [usize; 8] = [2, 1, 4, 7, 6, 5, 0, 3];
+const KEY_PARITY: u64 = 0x1bd11bdaa9fc1a22;
+
+const TYPE_CFG: u64 = 4;
+const TYPE_MSG: u64 = 48;
+const TYPE_OUT: u64 = 63;
+const FIRST: u64 = 1 << 62;
+const FINAL: u64 = 1 << 63;
+
+fn threefish512(key: &[u64; 8], tweak: [u64; 2], block: &[u64; 8]) -> [u64; 8] {
+    let mut k = [0u64; 9];
+    k[..8].copy_from_slice(key);
+    k[8] = key.iter().fold(KEY_PARITY, |acc, w| acc ^ w);
+    let t = [tweak[0], tweak[1], tweak[0] ^ tweak[1]];
+
+    let subkey = |s: usize| -> [u64; 8] {
+        let mut sk: [u64; 8] = core::array::from_fn(|i| k[(s + i) % 9]);
+        sk[5] = sk[5].wrapping_add(t[s % 3]);
+        sk[6] = sk[6].wrapping_add(t[(s + 1) % 3]);
+        sk[7] = sk[7].wrapping_add(s as u64);
+        sk
+    };
+
+    let mut v = *block;
+    for d in 0..72 {
+        if d % 4 == 0 {
+            let sk = subkey(d / 4);
+            for i in 0..8 {
+                v[i] = v[i].wrapping_add(sk[i]);
+            }
+        }
+        let mut f = [0u64; 8];
+        for j in 0..4 {
+            let (x0, x1) = (v[2 * j], v[2 * j + 1]);
+            f[2 * j] = x0.wrapping_add(x1);
+            f[2 * j + 1] = x1.rotate_left(ROT[d % 8][j]) ^ f[2 * j];
+        }
+        v = core::array::from_fn(|i| f[PERM[i]]);
+    }
+    let sk = subkey(18);
+    core::array::from_fn(|i| v[i].wrapping_add(sk[i]))
+}
+
+fn words(bytes: &[u8]) -> [u64; 8] {
+    core::array::from_fn(|i| u64::from_le_bytes(bytes[8 * i..8 * i + 8].try_into().unwrap()))
+}
+
+/// Unique Block Iteration over `msg` (zero-padded to whole blocks)
+fn ubi(g: [u64; 8], msg: &[u8], ty: u64) -> [u64; 8] {
+    let mut g = g;
+    let nblocks = msg.len().div_ceil(64).max(1);
+    for i in 0..nblocks {
+        let mut block = [0u8; 64];
+        let chunk = &msg[(i * 64).min(msg.len())..((i + 1) * 64).min(msg.len())];
+        block[..chunk.len()].copy_from_slice(chunk);
+
+        let position = (i * 64 + chunk.len()) as u64;
+        let mut t1 = ty << 56;
+        if i == 0 {
+            t1 |= FIRST;
+        }
+        if i == nblocks - 1 {
+            t1 |= FINAL;
+        }
+        let m = words(&block);
+        let e = threefish512(&g, [position, t1], &m);
+        g = core::array::from_fn(|j| e[j] ^ m[j]);
+    }
+    g
+}
+
+/// Skein-512-256 digest of `data`
+pub(crate) fn skein512_256(data: &[u8]) -> [u8; 32] {
+    // Config: schema "SHA3", version 1, output bits, no tree
+    let mut cfg = [0u8; 32];
+    cfg[..4].copy_from_slice(b"SHA3");
+    cfg[4] = 1;
+    cfg[8..16].copy_from_slice(&256u64.to_le_bytes());
+    let g = ubi([0; 8], &cfg, TYPE_CFG);
+
+    let g = ubi(g, data, TYPE_MSG);
+    let out = ubi(g, &0u64.to_le_bytes(), TYPE_OUT);
+
+    let mut hash = [0u8; 32];
+    for (i, w) in out.iter().take(4).enumerate() {
+        hash[8 * i..8 * i + 8].copy_from_slice(&w.to_le_bytes());
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skein512_256_vectors() {
+        assert_eq!(
+            hex::encode(skein512_256(b"")),
+            "39ccc4554a8b31853b9de7a1fe638a24cce6b35a55f2431009e18780335d2621"
+        );
+        assert_eq!(
+            hex::encode(skein512_256(b"The quick brown fox jumps over the lazy dog")),
+            "b3250457e05d3060b1a4bbc1428bc75a3f525ca389aeab96cfa34638d96e492a"
+        );
+    }
+}
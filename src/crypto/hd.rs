@@ -0,0 +1,225 @@
+// src/crypto/hd.rs
+//! SLIP-0010 hierarchical derivation of Ed25519 keys.
+//!
+//! Ed25519 only supports hardened children, so every path segment must be
+//! hardened (`44'`, `44h` or `44H`). One master seed then yields any number
+//! of independent `Ed25519Keypair`s, e.g. one per service at
+//! `m/44'/128'/0'/<n>'`.
+use std::{fmt, str::FromStr};
+
+use zeroize::Zeroize;
+
+use super::hash::hmac_sha512;
+use super::signature::Ed25519Keypair;
+
+/// Offset marking an index as hardened
+pub const HARDENED: u32 = 0x8000_0000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HdError {
+    /// Path isn't of the form `m/a'/b'/...`
+    InvalidPath(String),
+    /// Ed25519 can't derive non-hardened children
+    NotHardened(u32),
+}
+
+impl fmt::Display for HdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HdError::InvalidPath(p) => write!(f, "invalid derivation path: {p}"),
+            HdError::NotHardened(i) => write!(f, "index {i} must be hardened for ed25519"),
+        }
+    }
+}
+
+impl std::error::Error for HdError {}
+
+/// Parsed path such as `m/44'/128'/0'`; indices include the hardened offset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = HdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || HdError::InvalidPath(s.to_string());
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err(invalid());
+        }
+        let mut indices = Vec::new();
+        for part in parts {
+            let Some(number) = part.strip_suffix(['\'', 'h', 'H']) else {
+                let index = part.parse::<u32>().map_err(|_| invalid())?;
+                return Err(HdError::NotHardened(index));
+            };
+            let index = number.parse::<u32>().map_err(|_| invalid())?;
+            if index >= HARDENED {
+                return Err(invalid());
+            }
+            indices.push(index | HARDENED);
+        }
+        Ok(Self(indices))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{}'", index & !HARDENED)?;
+        }
+        Ok(())
+    }
+}
+
+/// Private key plus chain code at some depth of the tree
+#[derive(Clone)]
+pub struct ExtendedKey {
+    key: [u8; 32],
+    chain_code: [u8; 32],
+    depth: u8,
+}
+
+impl ExtendedKey {
+    fn from_hmac(mut i: [u8; 64], depth: u8) -> Self {
+        let key = i[..32].try_into().unwrap();
+        let chain_code = i[32..].try_into().unwrap();
+        i.zeroize();
+        Self {
+            key,
+            chain_code,
+            depth,
+        }
+    }
+
+    /// Root of the tree for a 16-64 byte seed
+    pub fn master(seed: &[u8]) -> Self {
+        Self::from_hmac(hmac_sha512(b"ed25519 seed", seed).0, 0)
+    }
+
+    /// Hardened child `index` (with or without the hardened offset)
+    pub fn child(&self, index: u32) -> Self {
+        let mut data = [0u8; 37];
+        data[1..33].copy_from_slice(&self.key);
+        data[33..].copy_from_slice(&(index | HARDENED).to_be_bytes());
+        let child = Self::from_hmac(hmac_sha512(&self.chain_code, &data).0, self.depth + 1);
+        data.zeroize();
+        child
+    }
+
+    /// Walk every segment of `path` from this key
+    pub fn derive(&self, path: &DerivationPath) -> Self {
+        path.0
+            .iter()
+            .fold(self.clone(), |key, &index| key.child(index))
+    }
+
+    /// Ed25519 keypair whose seed is this node's private key
+    pub fn keypair(&self) -> Ed25519Keypair {
+        Ed25519Keypair::from_seed(self.key)
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+}
+
+impl fmt::Debug for ExtendedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtendedKey")
+            .field("depth", &self.depth)
+            .field("key", &format_args!("REDACTED"))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for ExtendedKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.chain_code.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slip10_vector_1() {
+        // SLIP-0010 test vector 1 for ed25519
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::master(&seed);
+        assert_eq!(
+            hex::encode(master.key),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex::encode(master.chain_code()),
+            "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb"
+        );
+        assert_eq!(
+            hex::encode(master.keypair().public_bytes()),
+            "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed"
+        );
+
+        let child = master.derive(&"m/0'".parse().unwrap());
+        assert_eq!(child.depth(), 1);
+        assert_eq!(
+            hex::encode(child.key),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+        assert_eq!(
+            hex::encode(child.chain_code()),
+            "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69"
+        );
+    }
+
+    #[test]
+    fn test_path_parsing() {
+        let path: DerivationPath = "m/44'/128h/0H".parse().unwrap();
+        assert_eq!(path.indices(), [44 | HARDENED, 128 | HARDENED, HARDENED]);
+        assert_eq!(path.to_string(), "m/44'/128'/0'");
+        assert_eq!("m".parse::<DerivationPath>().unwrap().indices(), []);
+
+        assert_eq!(
+            "m/44'/0".parse::<DerivationPath>(),
+            Err(HdError::NotHardened(0))
+        );
+        assert!(matches!(
+            "44'/0'".parse::<DerivationPath>(),
+            Err(HdError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            "m/2147483648'".parse::<DerivationPath>(),
+            Err(HdError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn test_children_are_independent() {
+        let master = ExtendedKey::master(&[7u8; 32]);
+        let a = master.derive(&"m/44'/128'/0'".parse().unwrap());
+        let b = master.derive(&"m/44'/128'/1'".parse().unwrap());
+        assert_ne!(a.keypair().public_bytes(), b.keypair().public_bytes());
+        assert_eq!(
+            a.keypair().public_bytes(),
+            master
+                .child(44)
+                .child(128)
+                .child(0)
+                .keypair()
+                .public_bytes()
+        );
+    }
+}
@@ -0,0 +1,203 @@
+// src/crypto/kdf.rs
+//! Password-based key derivation with Argon2id.
+//!
+//! Everything that turns a password into key material (passphrase keypairs,
+//! encrypted exports, wallet files) goes through [`derive_key`] so the cost
+//! settings live in one place. Parameters travel with the data they protect
+//! via [`KdfParams::to_bytes`], prefixed with a version byte so the scheme
+//! can change without breaking old files.
+use std::fmt;
+
+use super::{hash::argon2, secret::SecretBytes};
+
+/// Version byte of the current encoding: Argon2id v1.3
+pub const VERSION: u8 = 1;
+/// Length of [`KdfParams::to_bytes`]
+pub const ENCODED_LEN: usize = 13;
+/// Shortest salt accepted
+pub const MIN_SALT_LEN: usize = 8;
+
+/// Ceilings for parameters read from untrusted data (4 GiB, 64 passes)
+const MAX_M_COST: u32 = 4 * 1024 * 1024;
+const MAX_T_COST: u32 = 64;
+const MAX_LANES: u32 = 64;
+
+/// Why key derivation failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdfError {
+    /// Costs out of range, or too little memory for the lane count
+    InvalidParams,
+    /// Encoded parameters use a version this build doesn't know
+    UnsupportedVersion(u8),
+    /// Encoded parameters have the wrong length
+    Malformed,
+    /// Salt is shorter than [`MIN_SALT_LEN`]
+    SaltTooShort(usize),
+}
+
+impl fmt::Display for KdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdfError::InvalidParams => write!(f, "invalid Argon2id parameters"),
+            KdfError::UnsupportedVersion(v) => write!(f, "unsupported KDF version {v}"),
+            KdfError::Malformed => write!(f, "malformed KDF parameters"),
+            KdfError::SaltTooShort(n) => {
+                write!(f, "salt must be at least {MIN_SALT_LEN} bytes, got {n}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KdfError {}
+
+/// Argon2id cost: passes, memory in KiB, and parallel lanes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub t_cost: u32,
+    pub m_cost: u32,
+    pub lanes: u32,
+}
+
+impl KdfParams {
+    /// t=3, 64 MiB, p=4: RFC 9106's second recommended setting, the default
+    pub const MODERATE: Self = Self {
+        t_cost: 3,
+        m_cost: 64 * 1024,
+        lanes: 4,
+    };
+
+    /// t=2, 19 MiB, p=1: the OWASP minimum, for frequent interactive unlocks
+    pub const INTERACTIVE: Self = Self {
+        t_cost: 2,
+        m_cost: 19 * 1024,
+        lanes: 1,
+    };
+
+    /// Check the costs are usable and within the ceilings for untrusted input
+    pub fn validate(&self) -> Result<(), KdfError> {
+        let ok = (1..=MAX_T_COST).contains(&self.t_cost)
+            && (1..=MAX_LANES).contains(&self.lanes)
+            && self.m_cost >= 8 * self.lanes
+            && self.m_cost <= MAX_M_COST;
+        if ok {
+            Ok(())
+        } else {
+            Err(KdfError::InvalidParams)
+        }
+    }
+
+    /// `version || t_cost || m_cost || lanes`, integers little-endian
+    pub fn to_bytes(&self) -> [u8; ENCODED_LEN] {
+        let mut out = [0u8; ENCODED_LEN];
+        out[0] = VERSION;
+        out[1..5].copy_from_slice(&self.t_cost.to_le_bytes());
+        out[5..9].copy_from_slice(&self.m_cost.to_le_bytes());
+        out[9..13].copy_from_slice(&self.lanes.to_le_bytes());
+        out
+    }
+
+    /// Parse and validate [`KdfParams::to_bytes`] output
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, KdfError> {
+        let bytes: &[u8; ENCODED_LEN] = bytes.try_into().map_err(|_| KdfError::Malformed)?;
+        if bytes[0] != VERSION {
+            return Err(KdfError::UnsupportedVersion(bytes[0]));
+        }
+        let word = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let params = Self {
+            t_cost: word(1),
+            m_cost: word(5),
+            lanes: word(9),
+        };
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self::MODERATE
+    }
+}
+
+/// 32-byte key from `password`
+///
+/// `context` is bound in as Argon2's associated data, so keys derived for
+/// different purposes from the same password and salt are unrelated.
+pub fn derive_key(
+    password: &[u8],
+    salt: &[u8],
+    context: &[u8],
+    params: &KdfParams,
+) -> Result<SecretBytes<32>, KdfError> {
+    if salt.len() < MIN_SALT_LEN {
+        return Err(KdfError::SaltTooShort(salt.len()));
+    }
+    params.validate()?;
+    let mut key = SecretBytes::new([0u8; 32]);
+    argon2::argon2id(
+        password,
+        salt,
+        &[],
+        context,
+        argon2::Params {
+            t_cost: params.t_cost,
+            m_cost: params.m_cost,
+            lanes: params.lanes,
+        },
+        key.as_mut_bytes(),
+    );
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHEAP: KdfParams = KdfParams {
+        t_cost: 1,
+        m_cost: 64,
+        lanes: 2,
+    };
+
+    #[test]
+    fn test_params_encoding() {
+        let bytes = KdfParams::MODERATE.to_bytes();
+        assert_eq!(hex::encode(bytes), "01030000000000010004000000");
+        assert_eq!(KdfParams::from_bytes(&bytes), Ok(KdfParams::MODERATE));
+        assert_eq!(KdfParams::default(), KdfParams::MODERATE);
+
+        let mut future = bytes;
+        future[0] = 2;
+        assert_eq!(
+            KdfParams::from_bytes(&future),
+            Err(KdfError::UnsupportedVersion(2))
+        );
+        assert_eq!(KdfParams::from_bytes(&bytes[1..]), Err(KdfError::Malformed));
+
+        let greedy = KdfParams {
+            m_cost: u32::MAX,
+            ..KdfParams::MODERATE
+        };
+        assert_eq!(
+            KdfParams::from_bytes(&greedy.to_bytes()),
+            Err(KdfError::InvalidParams)
+        );
+    }
+
+    #[test]
+    fn test_derive_key() {
+        let a = derive_key(b"pw", b"saltsalt", b"ctx", &CHEAP).unwrap();
+        assert_eq!(a, derive_key(b"pw", b"saltsalt", b"ctx", &CHEAP).unwrap());
+        assert_ne!(a, derive_key(b"pw", b"saltsalT", b"ctx", &CHEAP).unwrap());
+        assert_ne!(a, derive_key(b"pw", b"saltsalt", b"other", &CHEAP).unwrap());
+        assert_eq!(
+            derive_key(b"pw", b"salt", b"ctx", &CHEAP).err(),
+            Some(KdfError::SaltTooShort(4))
+        );
+        let too_small = KdfParams { m_cost: 8, ..CHEAP };
+        assert_eq!(
+            derive_key(b"pw", b"saltsalt", b"ctx", &too_small).err(),
+            Some(KdfError::InvalidParams)
+        );
+    }
+}
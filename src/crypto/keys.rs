@@ -0,0 +1,417 @@
+// src/crypto/keys.rs
+// CryptoNote wallet keys: a spend keypair plus a view keypair
+use std::{collections::HashMap, fmt};
+
+use curve25519_dalek::{
+    EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT, edwards::CompressedEdwardsY,
+};
+use rand::{CryptoRng, RngCore, rngs::OsRng};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use super::cn::hash_to_scalar;
+use super::hash::keccak256;
+use crate::encoding::varint;
+
+/// Shared secret `8·r·A` (sender) or `8·a·R` (receiver), compressed
+///
+/// Equality is constant-time.
+#[derive(Debug, Clone, Copy, Eq)]
+pub struct KeyDerivation(pub [u8; 32]);
+
+impl ConstantTimeEq for KeyDerivation {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for KeyDerivation {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl std::hash::Hash for KeyDerivation {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl KeyDerivation {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// Monero `generate_key_derivation`: `8·secret·public`
+pub fn generate_key_derivation(public: &EdwardsPoint, secret: &Scalar) -> KeyDerivation {
+    KeyDerivation((secret * public).mul_by_cofactor().compress().to_bytes())
+}
+
+/// Monero `derivation_to_scalar`: `Hs(derivation || varint(output_index))`
+pub fn derivation_to_scalar(derivation: &KeyDerivation, output_index: u64) -> Scalar {
+    let mut buf = derivation.0.to_vec();
+    varint::write(&mut buf, output_index);
+    hash_to_scalar(&buf)
+}
+
+/// Monero `derive_public_key`: `Hs(derivation || i)·G + base`
+pub fn derive_public_key(
+    derivation: &KeyDerivation,
+    output_index: u64,
+    base: &EdwardsPoint,
+) -> EdwardsPoint {
+    derivation_to_scalar(derivation, output_index) * ED25519_BASEPOINT_POINT + base
+}
+
+/// Monero `derive_secret_key`: `Hs(derivation || i) + base`
+pub fn derive_secret_key(derivation: &KeyDerivation, output_index: u64, base: &Scalar) -> Scalar {
+    derivation_to_scalar(derivation, output_index) + base
+}
+
+/// Monero `derive_view_tag`: first byte of
+/// `Keccak256("view_tag" || derivation || varint(output_index))`
+///
+/// Lets a scanner discard 255/256 of foreign outputs after one hash instead
+/// of a point operation.
+pub fn derive_view_tag(derivation: &KeyDerivation, output_index: u64) -> u8 {
+    let mut buf = b"view_tag".to_vec();
+    buf.extend_from_slice(&derivation.0);
+    varint::write(&mut buf, output_index);
+    keccak256(&buf).as_bytes()[0]
+}
+
+/// Monero `derive_subaddress_public_key`: `P - Hs(derivation || i)·G`
+///
+/// Recovers the spend key an output was sent to, for lookup in
+/// [`WalletKeys::subaddress_table`].
+pub fn derive_subaddress_public_key(
+    output_key: &EdwardsPoint,
+    derivation: &KeyDerivation,
+    output_index: u64,
+) -> EdwardsPoint {
+    output_key - derivation_to_scalar(derivation, output_index) * ED25519_BASEPOINT_POINT
+}
+
+/// Sender side: one-time key `P = Hs(8·r·A || i)·G + B` for output `index`
+///
+/// Only the holder of the view secret `a` (for which `A = a·G`) can recompute
+/// it from the transaction public key `R = r·G`.
+pub fn derive_one_time_key(
+    tx_secret: &Scalar,
+    recipient_view_pub: &EdwardsPoint,
+    recipient_spend_pub: &EdwardsPoint,
+    output_index: u64,
+) -> EdwardsPoint {
+    let derivation = generate_key_derivation(recipient_view_pub, tx_secret);
+    derive_public_key(&derivation, output_index, recipient_spend_pub)
+}
+
+/// Spend and view keys of a standard Monero wallet
+///
+/// The spend secret authorises spending; the view secret only lets its
+/// holder recognise incoming outputs. Deterministic wallets derive the view
+/// secret as `Hs(spend_secret)`, which is what every constructor here does.
+/// Both secrets are wiped on drop.
+#[derive(Clone)]
+pub struct WalletKeys {
+    spend_secret: Scalar,
+    view_secret: Scalar,
+    spend_public: EdwardsPoint,
+    view_public: EdwardsPoint,
+}
+
+impl WalletKeys {
+    /// Fresh wallet with a random spend key
+    pub fn generate() -> Self {
+        Self::generate_with_rng(&mut OsRng)
+    }
+
+    /// Fresh wallet drawing the spend key from `rng`
+    pub fn generate_with_rng(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        Self::from_spend_secret(Scalar::random(rng))
+    }
+
+    /// Restore from a spend secret; the view key follows from it
+    pub fn from_spend_secret(spend_secret: Scalar) -> Self {
+        let view_secret = hash_to_scalar(spend_secret.as_bytes());
+        Self {
+            spend_secret,
+            view_secret,
+            spend_public: spend_secret * ED25519_BASEPOINT_POINT,
+            view_public: view_secret * ED25519_BASEPOINT_POINT,
+        }
+    }
+
+    /// Restore from 32 seed bytes, reduced mod l like `sc_reduce32`
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self::from_spend_secret(Scalar::from_bytes_mod_order(seed))
+    }
+
+    pub fn spend_secret(&self) -> &Scalar {
+        &self.spend_secret
+    }
+
+    pub fn view_secret(&self) -> &Scalar {
+        &self.view_secret
+    }
+
+    pub fn spend_public(&self) -> &EdwardsPoint {
+        &self.spend_public
+    }
+
+    pub fn view_public(&self) -> &EdwardsPoint {
+        &self.view_public
+    }
+
+    /// True if output `index` of the transaction with public key `tx_pub`
+    /// pays `output_key` to this wallet's main address
+    pub fn owns_output(
+        &self,
+        tx_pub: &EdwardsPoint,
+        index: u64,
+        output_key: &EdwardsPoint,
+    ) -> bool {
+        let derivation = generate_key_derivation(tx_pub, &self.view_secret);
+        derive_subaddress_public_key(output_key, &derivation, index) == self.spend_public
+    }
+
+    /// Secret key `Hs(8·a·R || i) + b` that spends an owned output
+    pub fn one_time_secret(&self, tx_pub: &EdwardsPoint, index: u64) -> Scalar {
+        let derivation = generate_key_derivation(tx_pub, &self.view_secret);
+        derive_secret_key(&derivation, index, &self.spend_secret)
+    }
+
+    /// `m = Hs("SubAddr\0" || a || major || minor)`; zero for the main address
+    fn subaddress_offset(&self, major: u32, minor: u32) -> Scalar {
+        if major == 0 && minor == 0 {
+            return Scalar::ZERO;
+        }
+        let mut buf = Vec::with_capacity(8 + 32 + 8);
+        buf.extend_from_slice(b"SubAddr\0");
+        buf.extend_from_slice(self.view_secret.as_bytes());
+        buf.extend_from_slice(&major.to_le_bytes());
+        buf.extend_from_slice(&minor.to_le_bytes());
+        hash_to_scalar(&buf)
+    }
+
+    /// Subaddress spend key `D = B + m·G` (`B` itself for index 0/0)
+    pub fn subaddress_spend_public(&self, major: u32, minor: u32) -> EdwardsPoint {
+        self.spend_public + self.subaddress_offset(major, minor) * ED25519_BASEPOINT_POINT
+    }
+
+    /// Subaddress view key `C = a·D` (`A` itself for index 0/0)
+    pub fn subaddress_view_public(&self, major: u32, minor: u32) -> EdwardsPoint {
+        if major == 0 && minor == 0 {
+            return self.view_public;
+        }
+        self.view_secret * self.subaddress_spend_public(major, minor)
+    }
+
+    /// Secret key `b + m` spending outputs sent to the subaddress
+    pub fn subaddress_spend_secret(&self, major: u32, minor: u32) -> Scalar {
+        self.spend_secret + self.subaddress_offset(major, minor)
+    }
+
+    /// Map from subaddress spend key to `(major, minor)` for output scanning
+    ///
+    /// Covers accounts `0..majors` and indices `0..minors` in each, like the
+    /// wallet's lookahead table.
+    pub fn subaddress_table(
+        &self,
+        majors: u32,
+        minors: u32,
+    ) -> HashMap<CompressedEdwardsY, (u32, u32)> {
+        let mut table = HashMap::with_capacity((majors as usize) * (minors as usize));
+        for major in 0..majors {
+            for minor in 0..minors {
+                let d = self.subaddress_spend_public(major, minor).compress();
+                table.insert(d, (major, minor));
+            }
+        }
+        table
+    }
+}
+
+impl fmt::Debug for WalletKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalletKeys")
+            .field("spend_public", &hex::encode(self.spend_public.compress().as_bytes()))
+            .field("view_public", &hex::encode(self.view_public.compress().as_bytes()))
+            .field("secrets", &format_args!("REDACTED"))
+            .finish()
+    }
+}
+
+impl Drop for WalletKeys {
+    fn drop(&mut self) {
+        self.spend_secret.zeroize();
+        self.view_secret.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_key_derived_from_spend_key() {
+        let keys = WalletKeys::generate();
+        let restored = WalletKeys::from_spend_secret(*keys.spend_secret());
+        assert_eq!(restored.view_secret(), keys.view_secret());
+        assert_eq!(restored.view_public(), keys.view_public());
+        assert_eq!(
+            *keys.view_secret(),
+            hash_to_scalar(keys.spend_secret().as_bytes())
+        );
+        assert_eq!(
+            *keys.spend_public(),
+            keys.spend_secret() * ED25519_BASEPOINT_POINT
+        );
+    }
+
+    #[test]
+    fn test_from_seed_reduces() {
+        // l + 1 reduces to 1
+        let mut seed =
+            hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+                .unwrap();
+        seed[0] += 1;
+        let keys = WalletKeys::from_seed(seed.try_into().unwrap());
+        assert_eq!(*keys.spend_secret(), Scalar::ONE);
+    }
+
+    #[test]
+    fn test_subaddress_keys() {
+        let keys = WalletKeys::generate();
+        assert_eq!(keys.subaddress_spend_public(0, 0), *keys.spend_public());
+        assert_eq!(keys.subaddress_view_public(0, 0), *keys.view_public());
+
+        let d = keys.subaddress_spend_public(1, 2);
+        assert_ne!(d, *keys.spend_public());
+        assert_eq!(
+            keys.subaddress_spend_secret(1, 2) * ED25519_BASEPOINT_POINT,
+            d
+        );
+        assert_eq!(keys.subaddress_view_public(1, 2), keys.view_secret() * d);
+        assert_ne!(d, keys.subaddress_spend_public(2, 1));
+    }
+
+    #[test]
+    fn test_subaddress_table_lookup() {
+        let keys = WalletKeys::generate();
+        let table = keys.subaddress_table(2, 50);
+        assert_eq!(table.len(), 100);
+        assert_eq!(table[&keys.spend_public().compress()], (0, 0));
+        let d = keys.subaddress_spend_public(1, 37).compress();
+        assert_eq!(table[&d], (1, 37));
+        assert!(!table.contains_key(&keys.subaddress_spend_public(2, 0).compress()));
+    }
+
+    #[test]
+    fn test_one_time_key_roundtrip() {
+        let alice = WalletKeys::generate();
+        let bob = WalletKeys::generate();
+        let r = Scalar::random(&mut OsRng);
+        let tx_pub = r * ED25519_BASEPOINT_POINT;
+
+        for index in [0u64, 1, 200] {
+            let p = derive_one_time_key(&r, alice.view_public(), alice.spend_public(), index);
+            assert!(alice.owns_output(&tx_pub, index, &p));
+            assert!(!bob.owns_output(&tx_pub, index, &p));
+            assert!(!alice.owns_output(&tx_pub, index + 1, &p));
+            assert_eq!(
+                alice.one_time_secret(&tx_pub, index) * ED25519_BASEPOINT_POINT,
+                p
+            );
+        }
+    }
+
+    #[test]
+    fn test_derivation_is_symmetric() {
+        let r = Scalar::random(&mut OsRng);
+        let a = Scalar::random(&mut OsRng);
+        let big_r = r * ED25519_BASEPOINT_POINT;
+        let big_a = a * ED25519_BASEPOINT_POINT;
+        let sender = generate_key_derivation(&big_a, &r);
+        let receiver = generate_key_derivation(&big_r, &a);
+        assert_eq!(sender, receiver);
+        assert_eq!(
+            sender.0,
+            (Scalar::from(8u8) * r * a * ED25519_BASEPOINT_POINT)
+                .compress()
+                .to_bytes()
+        );
+
+        let b = Scalar::random(&mut OsRng);
+        let big_b = b * ED25519_BASEPOINT_POINT;
+        let p = derive_public_key(&sender, 3, &big_b);
+        assert_eq!(
+            derive_secret_key(&receiver, 3, &b) * ED25519_BASEPOINT_POINT,
+            p
+        );
+        assert_eq!(derive_subaddress_public_key(&p, &receiver, 3), big_b);
+        assert_ne!(
+            derivation_to_scalar(&sender, 3),
+            derivation_to_scalar(&sender, 4)
+        );
+    }
+
+    #[test]
+    fn test_view_tag() {
+        let mut d = [0u8; 32];
+        hex::decode_to_slice(
+            "0fc47054f355ced4d67de73bfa12e4c78ff19089548fffa7d07a674741860f97",
+            &mut d,
+        )
+        .unwrap();
+        let tags: Vec<u8> = (0..3).map(|i| derive_view_tag(&KeyDerivation(d), i)).collect();
+        assert_eq!(tags, [0x76, 0xd6, 0x87]);
+    }
+
+    /// Wallet keys, tx public keys and the one-time keys they pay, from
+    /// monero-rs's one-time key tests
+    #[test]
+    fn test_derivation_vectors() {
+        let scalar = |h: &str| {
+            Scalar::from_canonical_bytes(hex::decode(h).unwrap().try_into().unwrap()).unwrap()
+        };
+        let point = |h: &str| {
+            CompressedEdwardsY(hex::decode(h).unwrap().try_into().unwrap())
+                .decompress()
+                .unwrap()
+        };
+        let a = scalar("bcfdda53205318e1c14fa0ddca1a45df363bb427972981d0249d0f4652a7df07");
+        let b = scalar("e5f4301d32f3bdaef814a835a18aaaa24b13cc76cf01a832a7852faf9322e907");
+        let big_b = b * ED25519_BASEPOINT_POINT;
+
+        // output 1 to the main address
+        let r = point("5d1402db663eda8cef4f6782b66321e4a990f746aca249c973e098ba2c0837c1");
+        let derivation = generate_key_derivation(&r, &a);
+        let p = derive_public_key(&derivation, 1, &big_b);
+        assert_eq!(
+            hex::encode(p.compress().as_bytes()),
+            "e3e77faca64b5997ac1f75763e87713d03d9e2896edec65843ffd2970ef1dde6"
+        );
+        let x = scalar("afaebe00bcb29e233c2717e4574c7c8b114890571430bd1427d835ed7339050e");
+        assert_eq!(derive_secret_key(&derivation, 1, &b), x);
+        assert_eq!(derivation_to_scalar(&derivation, 1), x - b);
+        assert_ne!(derive_public_key(&derivation, 0, &big_b), p);
+        assert_ne!(derive_public_key(&derivation, 2, &big_b), p);
+
+        // output 1 to subaddress (0, 1)
+        let keys = WalletKeys::from_spend_secret(b);
+        assert_eq!(keys.view_secret(), &a);
+        let r = point("d6c75cf8c76ac458123f2a498512eb65bb3cecba346c8fcfc516dc0c88518bb9");
+        let derivation = generate_key_derivation(&r, &a);
+        let p = point("b6a2e2f35a93d637ff7d25e20da326cee8e92005d3b18b3c425dabe833656899");
+        assert_eq!(
+            derive_subaddress_public_key(&p, &derivation, 1),
+            keys.subaddress_spend_public(0, 1)
+        );
+        assert_eq!(
+            derive_secret_key(&derivation, 1, &keys.subaddress_spend_secret(0, 1)),
+            scalar("9650bef0bff89132c91f2244d909e0d65acd13415a46efcb933e6c10b7af4c01")
+        );
+    }
+}
@@ -1,2 +1,21 @@
+#[cfg_attr(not(feature = "cryptonight"), allow(dead_code))]
+pub(crate) mod aes;
+pub(crate) mod aead;
+pub mod cn;
+pub mod commitment;
+pub mod ecdh;
+mod error;
+pub(crate) mod field;
 pub  mod  hash;
+pub mod hd;
+pub mod kdf;
+pub mod keys;
+pub mod pow;
+pub mod rct;
+pub mod ring;
+pub mod secret;
+#[cfg(feature = "serde")]
+pub mod serde_hex;
 pub mod  signature; 
+
+pub use error::CryptoError;
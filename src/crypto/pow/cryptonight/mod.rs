@@ -0,0 +1,354 @@
+// src/crypto/pow/cryptonight/mod.rs
+//! CryptoNight (`cn_slow_hash`), Monero's PoW before RandomX (v12).
+//!
+//! Variant 0 was used up to v6, variant 1 for v7 and variant 2 for v8-v9.
+//! v10 and v11 used CryptoNight-R, which isn't implemented here, and
+//! RandomX replaced CryptoNight from v12.
+//! This is the portable code path from monero's `slow-hash.c`: it needs a
+//! 2 MiB scratchpad per call and is slow, which is fine for checking
+//! historical blocks but nothing else.
+use crate::crypto::aes;
+use crate::crypto::hash::{Hash256, blake256, groestl256, jh256, skein512_256};
+
+const MEMORY: usize = 1 << 21;
+const ITER: usize = 1 << 20;
+const INIT_SIZE: usize = 128;
+/// Low 4 bytes of a block select a 16-byte aligned scratchpad slot
+const ADDR_MASK: u32 = ((MEMORY / 16 - 1) << 4) as u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    V0,
+    V1,
+    V2,
+}
+
+impl Variant {
+    /// Variant in force for a given block major version, None from v10
+    /// on: CryptoNight-R, then RandomX
+    pub fn for_major_version(major: u8) -> Option<Self> {
+        match major {
+            0..=6 => Some(Variant::V0),
+            7 => Some(Variant::V1),
+            8 | 9 => Some(Variant::V2),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptoNightError {
+    /// Variant 1 reads the nonce at offset 35, so the blob needs >= 43 bytes
+    InputTooShort,
+    /// Block major version hashed with something other than variants 0-2
+    UnsupportedVersion(u8),
+}
+
+impl std::fmt::Display for CryptoNightError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CryptoNightError::InputTooShort => {
+                write!(f, "cryptonight variant 1 needs at least 43 bytes")
+            }
+            CryptoNightError::UnsupportedVersion(v) => {
+                write!(f, "no cryptonight variant for block version {v}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CryptoNightError {}
+
+/// Keccak-1600 with rate 136 returning the whole 200-byte state
+fn keccak1600(data: &[u8]) -> [u64; 25] {
+    let mut st = [0u64; 25];
+    let absorb = |st: &mut [u64; 25], block: &[u8]| {
+        for (i, w) in block.chunks_exact(8).enumerate() {
+            st[i] ^= u64::from_le_bytes(w.try_into().unwrap());
+        }
+        keccak::f1600(st);
+    };
+    let mut chunks = data.chunks_exact(136);
+    for block in &mut chunks {
+        absorb(&mut st, block);
+    }
+    let rest = chunks.remainder();
+    let mut last = [0u8; 136];
+    last[..rest.len()].copy_from_slice(rest);
+    last[rest.len()] = 0x01;
+    last[135] |= 0x80;
+    absorb(&mut st, &last);
+    st
+}
+
+fn state_bytes(st: &[u64; 25]) -> [u8; 200] {
+    let mut out = [0u8; 200];
+    for (i, w) in st.iter().enumerate() {
+        out[8 * i..8 * i + 8].copy_from_slice(&w.to_le_bytes());
+    }
+    out
+}
+
+fn u64_at(b: &[u8], i: usize) -> u64 {
+    u64::from_le_bytes(b[8 * i..8 * i + 8].try_into().unwrap())
+}
+
+fn block_at(pad: &[u8], at: usize) -> [u8; 16] {
+    pad[at..at + 16].try_into().unwrap()
+}
+
+fn xor16(a: &mut [u8; 16], b: &[u8; 16]) {
+    for i in 0..16 {
+        a[i] ^= b[i];
+    }
+}
+
+/// 16-byte block as two little-endian u64 halves
+fn halves(b: &[u8; 16]) -> [u64; 2] {
+    [u64_at(b, 0), u64_at(b, 1)]
+}
+
+fn from_halves(h: [u64; 2]) -> [u8; 16] {
+    let mut b = [0u8; 16];
+    b[..8].copy_from_slice(&h[0].to_le_bytes());
+    b[8..].copy_from_slice(&h[1].to_le_bytes());
+    b
+}
+
+fn slot(b: &[u8; 16]) -> usize {
+    (u32::from_le_bytes(b[..4].try_into().unwrap()) & ADDR_MASK) as usize
+}
+
+/// Variant 2 shuffle: mix the three neighbouring 16-byte chunks of `j`
+fn v2_shuffle_add(pad: &mut [u8], j: usize, a: &[u8; 16], b: &[u8; 32]) {
+    let c1 = halves(&block_at(pad, j ^ 0x10));
+    let c2 = halves(&block_at(pad, j ^ 0x20));
+    let c3 = halves(&block_at(pad, j ^ 0x30));
+    let b0 = halves(b[..16].try_into().unwrap());
+    let b1 = halves(b[16..].try_into().unwrap());
+    let a = halves(a);
+
+    let add = |x: [u64; 2], y: [u64; 2]| [x[0].wrapping_add(y[0]), x[1].wrapping_add(y[1])];
+    pad[(j ^ 0x10)..(j ^ 0x10) + 16].copy_from_slice(&from_halves(add(c3, b1)));
+    pad[(j ^ 0x30)..(j ^ 0x30) + 16].copy_from_slice(&from_halves(add(c2, a)));
+    pad[(j ^ 0x20)..(j ^ 0x20) + 16].copy_from_slice(&from_halves(add(c1, b0)));
+}
+
+/// Variant 2 division + square-root step, mutates `c` and the running results
+fn v2_integer_math(
+    c: &mut [u8; 16],
+    c1: &[u8; 16],
+    division_result: &mut u64,
+    sqrt_result: &mut u64,
+) {
+    let mut ch = halves(c);
+    ch[0] ^= *division_result ^ (*sqrt_result << 32);
+    *c = from_halves(ch);
+
+    let c1h = halves(c1);
+    let dividend = c1h[1];
+    let divisor = (c1h[0].wrapping_add((*sqrt_result << 1) & 0xffff_ffff) as u32) | 0x8000_0001;
+    let divisor = divisor as u64;
+    *division_result = ((dividend / divisor) & 0xffff_ffff) + ((dividend % divisor) << 32);
+    let sqrt_input = c1h[0].wrapping_add(*division_result);
+
+    let mut r = ((sqrt_input as f64 + 18446744073709551616.0).sqrt() * 2.0 - 8589934592.0) as u64;
+    // Fix up the floating point estimate to the exact integer result
+    let s = r >> 1;
+    let bit = r & 1;
+    let r2 = s.wrapping_mul(s + bit).wrapping_add(r << 32);
+    if r2.wrapping_add(bit) > sqrt_input {
+        r = r.wrapping_sub(1);
+    }
+    if r2.wrapping_add(1 << 32) < sqrt_input.wrapping_sub(s) {
+        r = r.wrapping_add(1);
+    }
+    *sqrt_result = r;
+}
+
+/// CryptoNight hash of `data` for the given variant
+pub fn cn_slow_hash(data: &[u8], variant: Variant) -> Result<Hash256, CryptoNightError> {
+    if variant == Variant::V1 && data.len() < 43 {
+        return Err(CryptoNightError::InputTooShort);
+    }
+
+    let mut st = keccak1600(data);
+    let hs = state_bytes(&st);
+
+    let tweak1_2 = match variant {
+        Variant::V1 => st[24] ^ u64::from_le_bytes(data[35..43].try_into().unwrap()),
+        _ => 0,
+    };
+
+    // Fill the scratchpad by repeatedly AES-ing the 128 bytes after the keys
+    let mut pad = vec![0u8; MEMORY];
+    let keys = aes::expand_key(hs[..32].try_into().unwrap());
+    let mut text: [u8; INIT_SIZE] = hs[64..192].try_into().unwrap();
+    for chunk in pad.chunks_exact_mut(INIT_SIZE) {
+        for block in text.chunks_exact_mut(16) {
+            aes::pseudo_round(block.try_into().unwrap(), &keys);
+        }
+        chunk.copy_from_slice(&text);
+    }
+
+    let mut a = [0u8; 16];
+    let mut b = [0u8; 32];
+    for i in 0..16 {
+        a[i] = hs[i] ^ hs[32 + i];
+        b[i] = hs[16 + i] ^ hs[48 + i];
+    }
+    let mut division_result = 0u64;
+    let mut sqrt_result = 0u64;
+    if variant == Variant::V2 {
+        b[16..24].copy_from_slice(&(st[8] ^ st[10]).to_le_bytes());
+        b[24..32].copy_from_slice(&(st[9] ^ st[11]).to_le_bytes());
+        division_result = st[12];
+        sqrt_result = st[13];
+    }
+
+    for _ in 0..ITER / 2 {
+        // Step 1: one AES round keyed by `a`
+        let j = slot(&a);
+        let mut c1 = block_at(&pad, j);
+        aes::round(&mut c1, &a);
+        if variant == Variant::V2 {
+            v2_shuffle_add(&mut pad, j, &a, &b);
+        }
+        let mut p = c1;
+        xor16(&mut p, b[..16].try_into().unwrap());
+        if variant == Variant::V1 {
+            let tmp = p[11];
+            let index = (((tmp >> 3) & 6) | (tmp & 1)) << 1;
+            p[11] = tmp ^ ((0x75310u32 >> index) as u8 & 0x30);
+        }
+        pad[j..j + 16].copy_from_slice(&p);
+
+        // Step 2: 64x64 multiply and add
+        let j = slot(&c1);
+        let mut c = block_at(&pad, j);
+        if variant == Variant::V2 {
+            v2_integer_math(&mut c, &c1, &mut division_result, &mut sqrt_result);
+        }
+        let product = (u64_at(&c1, 0) as u128) * (u64_at(&c, 0) as u128);
+        let mut d = from_halves([(product >> 64) as u64, product as u64]);
+        if variant == Variant::V2 {
+            let mut n1 = block_at(&pad, j ^ 0x10);
+            xor16(&mut n1, &d);
+            pad[(j ^ 0x10)..(j ^ 0x10) + 16].copy_from_slice(&n1);
+            xor16(&mut d, &block_at(&pad, j ^ 0x20));
+            v2_shuffle_add(&mut pad, j, &a, &b);
+        }
+        let ah = halves(&a);
+        let dh = halves(&d);
+        let sum = [ah[0].wrapping_add(dh[0]), ah[1].wrapping_add(dh[1])];
+
+        let mut stored = from_halves(sum);
+        a = stored;
+        xor16(&mut a, &c);
+        if variant == Variant::V1 {
+            stored = from_halves([sum[0], sum[1] ^ tweak1_2]);
+        }
+        pad[j..j + 16].copy_from_slice(&stored);
+
+        if variant == Variant::V2 {
+            b.copy_within(..16, 16);
+        }
+        b[..16].copy_from_slice(&c1);
+    }
+
+    // Fold the scratchpad back into the state with the second key
+    let keys = aes::expand_key(hs[32..64].try_into().unwrap());
+    let mut text: [u8; INIT_SIZE] = hs[64..192].try_into().unwrap();
+    for chunk in pad.chunks_exact(INIT_SIZE) {
+        for (block, src) in text.chunks_exact_mut(16).zip(chunk.chunks_exact(16)) {
+            let block: &mut [u8; 16] = block.try_into().unwrap();
+            xor16(block, src.try_into().unwrap());
+            aes::pseudo_round(block, &keys);
+        }
+    }
+    for (i, w) in text.chunks_exact(8).enumerate() {
+        st[8 + i] = u64::from_le_bytes(w.try_into().unwrap());
+    }
+    keccak::f1600(&mut st);
+
+    let state = state_bytes(&st);
+    Ok(match state[0] & 3 {
+        0 => blake256(&state),
+        1 => groestl256(&state),
+        2 => jh256(&state),
+        _ => skein512_256(&state),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(variant: Variant, input: &[u8], expected: &str) {
+        assert_eq!(cn_slow_hash(input, variant).unwrap().to_string(), expected);
+    }
+
+    #[test]
+    fn test_cryptonight_v0() {
+        // monero/tests/hash/tests-slow.txt
+        check(
+            Variant::V0,
+            b"de omnibus dubitandum",
+            "2f8e3df40bd11f9ac90c743ca8e32bb391da4fb98612aa3b6cdc639ee00b31f5",
+        );
+        check(
+            Variant::V0,
+            b"abundans cautela non nocet",
+            "722fa8ccd594d40e4a41f3822734304c8d5eff7e1b528408e2229da38ba553c4",
+        );
+        check(
+            Variant::V0,
+            b"caveat emptor",
+            "bbec2cacf69866a8e740380fe7b818fc78f8571221742d729d9d02d7f8989b87",
+        );
+        check(
+            Variant::V0,
+            b"ex nihilo nihil fit",
+            "b1257de4efc5ce28c6b40ceb1c6c8f812a64634eb3e81c5220bee9b2b76a6f05",
+        );
+    }
+
+    #[test]
+    fn test_cryptonight_v1() {
+        // monero/tests/hash/tests-slow-1.txt
+        check(
+            Variant::V1,
+            &[0u8; 43],
+            "b5a7f63abb94d07d1a6445c36c07c7e8327fe61b1647e391b4c7edae5de57a3d",
+        );
+    }
+
+    #[test]
+    fn test_cryptonight_v2() {
+        // monero/tests/hash/tests-slow-2.txt
+        check(
+            Variant::V2,
+            b"This is a test This is a test This is a test",
+            "353fdc068fd47b03c04b9431e005e00b68c2168a3cc7335c8b9b308156591a4f",
+        );
+    }
+
+    #[test]
+    fn test_variant_for_major_version() {
+        assert_eq!(Variant::for_major_version(1), Some(Variant::V0));
+        assert_eq!(Variant::for_major_version(6), Some(Variant::V0));
+        assert_eq!(Variant::for_major_version(7), Some(Variant::V1));
+        assert_eq!(Variant::for_major_version(9), Some(Variant::V2));
+        // CryptoNight-R, then RandomX
+        assert_eq!(Variant::for_major_version(10), None);
+        assert_eq!(Variant::for_major_version(12), None);
+    }
+
+    #[test]
+    fn test_cryptonight_v1_needs_nonce() {
+        assert_eq!(
+            cn_slow_hash(b"too short", Variant::V1),
+            Err(CryptoNightError::InputTooShort)
+        );
+    }
+}
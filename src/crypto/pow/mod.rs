@@ -0,0 +1,84 @@
+// src/crypto/pow/mod.rs
+// Proof-of-work: difficulty check plus the hash functions behind it
+use super::hash::Hash256;
+
+#[cfg(feature = "cryptonight")]
+pub mod cryptonight;
+#[cfg(feature = "randomx")]
+pub mod randomx;
+
+/// Blocks between RandomX key changes
+pub const SEEDHASH_EPOCH_BLOCKS: u64 = 2048;
+/// How far behind the block the key's seed block is, so miners can switch
+/// keys ahead of time
+pub const SEEDHASH_EPOCH_LAG: u64 = 64;
+
+/// Monero `rx_seedheight`: height of the block whose id keys the RandomX
+/// VM for the block at `height`
+pub fn randomx_seed_height(height: u64) -> u64 {
+    if height <= SEEDHASH_EPOCH_BLOCKS + SEEDHASH_EPOCH_LAG {
+        return 0;
+    }
+    (height - SEEDHASH_EPOCH_LAG - 1) & !(SEEDHASH_EPOCH_BLOCKS - 1)
+}
+
+/// Monero `check_hash`: does `hash` meet `difficulty`?
+///
+/// The hash is read as a little-endian 256-bit integer and is valid when
+/// `hash * difficulty` still fits in 256 bits.
+pub fn check_hash(hash: &Hash256, difficulty: u128) -> bool {
+    if difficulty == 0 {
+        return false;
+    }
+    let limbs: Vec<u64> = hash
+        .as_bytes()
+        .chunks(8)
+        .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    let diff = [difficulty as u64, (difficulty >> 64) as u64];
+
+    // Schoolbook 256 x 128 multiply; only the limbs above 2^256 matter
+    let mut product = [0u64; 6];
+    for (i, &d) in diff.iter().enumerate() {
+        let mut carry: u128 = 0;
+        for (j, &h) in limbs.iter().enumerate() {
+            let cur = product[i + j] as u128 + (h as u128) * (d as u128) + carry;
+            product[i + j] = cur as u64;
+            carry = cur >> 64;
+        }
+        product[i + 4] = carry as u64;
+    }
+    product[4] == 0 && product[5] == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::hash::Hash;
+
+    #[test]
+    fn test_check_hash() {
+        let max = Hash([0xff; 32]);
+        assert!(check_hash(&max, 1));
+        assert!(!check_hash(&max, 2));
+        assert!(!check_hash(&max, 0));
+
+        // Top 8 bytes zero -> good for any difficulty below 2^64
+        let mut bytes = [0xff; 32];
+        bytes[24..].fill(0);
+        let easy = Hash(bytes);
+        assert!(check_hash(&easy, u64::MAX as u128));
+        assert!(!check_hash(&easy, (u64::MAX as u128) + 2));
+
+        assert!(check_hash(&Hash([0; 32]), u128::MAX));
+    }
+
+    #[test]
+    fn test_seed_height() {
+        assert_eq!(randomx_seed_height(0), 0);
+        assert_eq!(randomx_seed_height(2048 + 64), 0);
+        assert_eq!(randomx_seed_height(2048 + 65), 2048);
+        assert_eq!(randomx_seed_height(4096 + 64), 2048);
+        assert_eq!(randomx_seed_height(4096 + 65), 4096);
+    }
+}
@@ -0,0 +1,159 @@
+// src/crypto/pow/randomx.rs
+//! RandomX proof-of-work (Monero since v12), via the reference `librandomx`.
+//!
+//! Enabled with the `randomx` feature; the system must provide the
+//! `randomx` C library (https://github.com/tevador/RandomX) at link time.
+//! Only light mode (256 MiB cache, no dataset) is used: slower per hash but
+//! fine for verifying blocks.
+use std::ffi::{c_int, c_void};
+
+use crate::crypto::hash::{Hash, Hash256};
+
+#[repr(C)]
+struct RandomxCache {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct RandomxVm {
+    _private: [u8; 0],
+}
+
+#[link(name = "randomx")]
+unsafe extern "C" {
+    fn randomx_get_flags() -> c_int;
+    fn randomx_alloc_cache(flags: c_int) -> *mut RandomxCache;
+    fn randomx_init_cache(cache: *mut RandomxCache, key: *const c_void, key_size: usize);
+    fn randomx_release_cache(cache: *mut RandomxCache);
+    fn randomx_create_vm(
+        flags: c_int,
+        cache: *mut RandomxCache,
+        dataset: *mut c_void,
+    ) -> *mut RandomxVm;
+    fn randomx_destroy_vm(vm: *mut RandomxVm);
+    fn randomx_calculate_hash(
+        vm: *mut RandomxVm,
+        input: *const c_void,
+        input_size: usize,
+        output: *mut c_void,
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomXError {
+    /// librandomx could not allocate the cache
+    CacheAlloc,
+    /// librandomx could not create a VM
+    VmCreate,
+}
+
+impl std::fmt::Display for RandomXError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RandomXError::CacheAlloc => write!(f, "failed to allocate RandomX cache"),
+            RandomXError::VmCreate => write!(f, "failed to create RandomX VM"),
+        }
+    }
+}
+
+impl std::error::Error for RandomXError {}
+
+/// A light-mode RandomX VM keyed by one seed hash
+///
+/// Building one is expensive (cache init takes ~1s), so reuse it for every
+/// block sharing the same seed (seeds change every 2048 blocks).
+pub struct RandomX {
+    seed: Hash256,
+    cache: *mut RandomxCache,
+    vm: *mut RandomxVm,
+}
+
+impl RandomX {
+    pub fn new(seed: &Hash256) -> Result<Self, RandomXError> {
+        let mut vm = Self::with_key(seed.as_bytes())?;
+        vm.seed = *seed;
+        Ok(vm)
+    }
+
+    /// VM keyed by arbitrary bytes (Monero always uses a 32-byte seed hash)
+    pub fn with_key(key: &[u8]) -> Result<Self, RandomXError> {
+        // SAFETY: pointers come straight from librandomx and are checked for
+        // null before use; they're released exactly once in Drop.
+        unsafe {
+            let flags = randomx_get_flags();
+            let cache = randomx_alloc_cache(flags);
+            if cache.is_null() {
+                return Err(RandomXError::CacheAlloc);
+            }
+            randomx_init_cache(cache, key.as_ptr().cast(), key.len());
+            let vm = randomx_create_vm(flags, cache, std::ptr::null_mut());
+            if vm.is_null() {
+                randomx_release_cache(cache);
+                return Err(RandomXError::VmCreate);
+            }
+            Ok(Self {
+                seed: Hash([0; 32]),
+                cache,
+                vm,
+            })
+        }
+    }
+
+    /// Seed hash this VM was initialised with (zero if built `with_key`)
+    pub fn seed(&self) -> &Hash256 {
+        &self.seed
+    }
+
+    /// RandomX hash of a block hashing blob
+    pub fn hash(&mut self, blob: &[u8]) -> Hash256 {
+        let mut out = [0u8; 32];
+        // SAFETY: vm is valid for the lifetime of self; out is 32 bytes
+        unsafe {
+            randomx_calculate_hash(
+                self.vm,
+                blob.as_ptr().cast(),
+                blob.len(),
+                out.as_mut_ptr().cast(),
+            );
+        }
+        Hash(out)
+    }
+
+    /// True if `blob` hashes to `expected` under this seed
+    pub fn verify(&mut self, blob: &[u8], expected: &Hash256) -> bool {
+        self.hash(blob) == *expected
+    }
+}
+
+impl Drop for RandomX {
+    fn drop(&mut self) {
+        // SAFETY: both pointers were created in new() and are not shared
+        unsafe {
+            randomx_destroy_vm(self.vm);
+            randomx_release_cache(self.cache);
+        }
+    }
+}
+
+// The VM is only touched through &mut self
+unsafe impl Send for RandomX {}
+
+/// One-shot check of a block's PoW hash for the given seed
+pub fn verify(seed: &Hash256, blob: &[u8], expected: &Hash256) -> Result<bool, RandomXError> {
+    Ok(RandomX::new(seed)?.verify(blob, expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_randomx_reference_vector() {
+        // tests/tests.cpp in tevador/RandomX: key "test key 000"
+        let expected: Hash256 = "639183aae1bf4c9a35884cb46b09cad9175f04efd7684e7262a0ac1c2f0b4e3f"
+            .parse()
+            .unwrap();
+        let mut vm = RandomX::with_key(b"test key 000").unwrap();
+        assert!(vm.verify(b"This is a test", &expected));
+    }
+}
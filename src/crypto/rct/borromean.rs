@@ -0,0 +1,182 @@
+// src/crypto/rct/borromean.rs
+//! Borromean range signatures (RingCT v1/v2 era, replaced by Bulletproofs in v8).
+//!
+//! A range proof splits an amount commitment `C` into 64 bit commitments
+//! `C_i` (each committing to 0 or 2^i) and proves with a 2-key ring per bit
+//! that every `C_i` opens to one of the two. Port of `rctSigs.cpp`.
+use std::sync::OnceLock;
+
+use curve25519_dalek::{
+    EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT, edwards::CompressedEdwardsY,
+};
+use rand::rngs::OsRng;
+
+use crate::crypto::cn::hash_to_scalar;
+use crate::crypto::commitment::h_generator;
+
+pub const ATOMS: usize = 64;
+
+/// Borromean ring signature over 64 two-member rings (`boroSig`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorromeanSignature {
+    pub s0: [Scalar; ATOMS],
+    pub s1: [Scalar; ATOMS],
+    pub ee: Scalar,
+}
+
+/// Range proof for one output amount (`rangeSig`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeSig {
+    pub asig: BorromeanSignature,
+    pub ci: [CompressedEdwardsY; ATOMS],
+}
+
+/// 2^i * H for every bit position
+fn h2() -> &'static [EdwardsPoint; ATOMS] {
+    static H2: OnceLock<[EdwardsPoint; ATOMS]> = OnceLock::new();
+    H2.get_or_init(|| {
+        let mut out = [h_generator(); ATOMS];
+        for i in 1..ATOMS {
+            out[i] = out[i - 1] + out[i - 1];
+        }
+        out
+    })
+}
+
+fn hs_point(p: &EdwardsPoint) -> Scalar {
+    hash_to_scalar(p.compress().as_bytes())
+}
+
+/// Hs over 64 concatenated points (`hash_to_scalar(key64)`)
+fn hs_points(points: &[EdwardsPoint; ATOMS]) -> Scalar {
+    let mut buf = Vec::with_capacity(32 * ATOMS);
+    for p in points {
+        buf.extend_from_slice(p.compress().as_bytes());
+    }
+    hash_to_scalar(&buf)
+}
+
+/// `verifyBorromean`: check the ring signature over key pairs (P1[i], P2[i])
+pub fn verify_borromean(
+    sig: &BorromeanSignature,
+    p1: &[EdwardsPoint; ATOMS],
+    p2: &[EdwardsPoint; ATOMS],
+) -> bool {
+    let mut lv1 = [EdwardsPoint::default(); ATOMS];
+    for i in 0..ATOMS {
+        let ll = EdwardsPoint::vartime_double_scalar_mul_basepoint(&sig.ee, &p1[i], &sig.s0[i]);
+        let chash = hs_point(&ll);
+        lv1[i] = EdwardsPoint::vartime_double_scalar_mul_basepoint(&chash, &p2[i], &sig.s1[i]);
+    }
+    hs_points(&lv1) == sig.ee
+}
+
+/// `verRange`: does `sig` prove that commitment `c` holds a 64-bit amount?
+pub fn verify_range(c: &EdwardsPoint, sig: &RangeSig) -> bool {
+    let mut ci = [EdwardsPoint::default(); ATOMS];
+    for (dst, src) in ci.iter_mut().zip(&sig.ci) {
+        match src.decompress() {
+            Some(p) => *dst = p,
+            None => return false,
+        }
+    }
+    if ci.iter().sum::<EdwardsPoint>() != *c {
+        return false;
+    }
+    let h2 = h2();
+    let cih: [EdwardsPoint; ATOMS] = core::array::from_fn(|i| ci[i] - h2[i]);
+    verify_borromean(&sig.asig, &ci, &cih)
+}
+
+/// `generateBorromean`: `x[i]` is the secret for `P1[i]` if bit i is 0, else for `P2[i]`
+fn generate_borromean(
+    x: &[Scalar; ATOMS],
+    p1: &[EdwardsPoint; ATOMS],
+    p2: &[EdwardsPoint; ATOMS],
+    bits: &[bool; ATOMS],
+) -> BorromeanSignature {
+    let mut l = [[EdwardsPoint::default(); ATOMS]; 2];
+    let mut alpha = [Scalar::ZERO; ATOMS];
+    let mut s0 = [Scalar::ZERO; ATOMS];
+    let mut s1 = [Scalar::ZERO; ATOMS];
+
+    for i in 0..ATOMS {
+        let naught = bits[i] as usize;
+        alpha[i] = Scalar::random(&mut OsRng);
+        l[naught][i] = alpha[i] * ED25519_BASEPOINT_POINT;
+        if naught == 0 {
+            s1[i] = Scalar::random(&mut OsRng);
+            let c = hs_point(&l[0][i]);
+            l[1][i] = EdwardsPoint::vartime_double_scalar_mul_basepoint(&c, &p2[i], &s1[i]);
+        }
+    }
+    let ee = hs_points(&l[1]);
+    for i in 0..ATOMS {
+        if !bits[i] {
+            s0[i] = alpha[i] - x[i] * ee;
+        } else {
+            s0[i] = Scalar::random(&mut OsRng);
+            let ll = EdwardsPoint::vartime_double_scalar_mul_basepoint(&ee, &p1[i], &s0[i]);
+            let cc = hs_point(&ll);
+            s1[i] = alpha[i] - x[i] * cc;
+        }
+    }
+    BorromeanSignature { s0, s1, ee }
+}
+
+/// `proveRange`: commitment to `amount`, its mask and the range proof
+///
+/// Only useful for tests and tooling: consensus has required Bulletproofs
+/// for new transactions since v8.
+pub fn prove_range(amount: u64) -> (EdwardsPoint, Scalar, RangeSig) {
+    let h2 = h2();
+    let bits: [bool; ATOMS] = core::array::from_fn(|i| (amount >> i) & 1 == 1);
+    let ai: [Scalar; ATOMS] = core::array::from_fn(|_| Scalar::random(&mut OsRng));
+
+    let ci: [EdwardsPoint; ATOMS] = core::array::from_fn(|i| {
+        let base = ai[i] * ED25519_BASEPOINT_POINT;
+        if bits[i] { base + h2[i] } else { base }
+    });
+    let cih: [EdwardsPoint; ATOMS] = core::array::from_fn(|i| ci[i] - h2[i]);
+
+    let mask = ai.iter().sum();
+    let c = ci.iter().sum();
+    let asig = generate_borromean(&ai, &ci, &cih, &bits);
+    let sig = RangeSig {
+        asig,
+        ci: ci.map(|p| p.compress()),
+    };
+    (c, mask, sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::commitment::commit;
+
+    #[test]
+    fn test_range_proof_roundtrip() {
+        for amount in [0u64, 1, 123_456_789_000, u64::MAX] {
+            let (c, mask, sig) = prove_range(amount);
+            assert_eq!(c, commit(amount, mask));
+            assert!(verify_range(&c, &sig));
+        }
+    }
+
+    #[test]
+    fn test_range_proof_rejects_tampering() {
+        let (c, _, sig) = prove_range(1_000_000);
+
+        let mut bad = sig.clone();
+        bad.asig.s0[5] += Scalar::ONE;
+        assert!(!verify_range(&c, &bad));
+
+        let mut bad = sig.clone();
+        bad.asig.ee += Scalar::ONE;
+        assert!(!verify_range(&c, &bad));
+
+        // Proof for a different commitment
+        let (other, _, _) = prove_range(1_000_000);
+        assert!(!verify_range(&other, &sig));
+    }
+}
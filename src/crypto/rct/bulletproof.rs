@@ -0,0 +1,236 @@
+// src/crypto/rct/bulletproof.rs
+//! Original Bulletproofs (RingCT types 3 to 5, v8 to v14), verification
+//! only: nothing may create them any more, but historic transactions still
+//! have to check out.
+//!
+//! Same aggregate statement as [`super::bulletproof_plus`], proven with the
+//! original inner-product argument. Port of `bulletproof_VERIFY` from
+//! `bulletproofs.cc`, one proof at a time.
+use std::sync::OnceLock;
+
+use curve25519_dalek::{
+    EdwardsPoint, Scalar,
+    constants::ED25519_BASEPOINT_POINT,
+    edwards::CompressedEdwardsY,
+    traits::{IsIdentity, VartimeMultiscalarMul},
+};
+
+use crate::crypto::cn::{hash_to_point, hash_to_scalar};
+use crate::crypto::commitment::{H_BYTES, h_generator};
+use crate::crypto::hash::keccak256;
+use crate::crypto::ring::clsag::inv_eight;
+use crate::encoding::varint;
+
+/// Most outputs one proof can cover
+pub const MAX_OUTPUTS: usize = 16;
+const BITS: usize = 64;
+
+/// A Bulletproof as serialised in a transaction (without the commitments,
+/// which are the outputs' `outPk`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bulletproof {
+    pub a: CompressedEdwardsY,
+    pub s: CompressedEdwardsY,
+    pub t1: CompressedEdwardsY,
+    pub t2: CompressedEdwardsY,
+    pub taux: Scalar,
+    pub mu: Scalar,
+    pub l: Vec<CompressedEdwardsY>,
+    pub r: Vec<CompressedEdwardsY>,
+    /// Final inner-product scalars, `a` and `b` in monero
+    pub ip_a: Scalar,
+    pub ip_b: Scalar,
+    /// Claimed `<l, r>`
+    pub t: Scalar,
+}
+
+/// `Gi` and `Hi`, [`MAX_OUTPUTS`] * 64 of each
+pub(super) struct Generators {
+    pub(super) g: Vec<EdwardsPoint>,
+    pub(super) h: Vec<EdwardsPoint>,
+}
+
+impl Generators {
+    /// `Hp(Keccak(H || salt || varint(i)))`, even `i` for `Hi` and odd for
+    /// `Gi`
+    pub(super) fn new(salt: &[u8]) -> Self {
+        let exponent = |i: u64| {
+            let mut data = H_BYTES.to_vec();
+            data.extend_from_slice(salt);
+            varint::write(&mut data, i);
+            hash_to_point(keccak256(&data).as_bytes())
+        };
+        let n = (MAX_OUTPUTS * BITS) as u64;
+        Generators {
+            h: (0..n).map(|i| exponent(2 * i)).collect(),
+            g: (0..n).map(|i| exponent(2 * i + 1)).collect(),
+        }
+    }
+}
+
+fn generators() -> &'static Generators {
+    static GENS: OnceLock<Generators> = OnceLock::new();
+    GENS.get_or_init(|| Generators::new(b"bulletproof"))
+}
+
+pub(super) fn decompress8(p: &CompressedEdwardsY) -> Option<EdwardsPoint> {
+    p.decompress().map(|p| p.mul_by_cofactor())
+}
+
+/// Product of `e_k` or `1/e_k` per round, by the bits of the index: the
+/// coefficient each original generator ends up with after folding
+pub(super) fn challenge_products(challenges: &[(Scalar, Scalar)]) -> Vec<Scalar> {
+    let rounds = challenges.len();
+    (0..1usize << rounds)
+        .map(|i| {
+            challenges
+                .iter()
+                .enumerate()
+                .map(|(k, (e, e_inv))| {
+                    if (i >> (rounds - 1 - k)) & 1 == 1 {
+                        *e
+                    } else {
+                        *e_inv
+                    }
+                })
+                .product()
+        })
+        .collect()
+}
+
+/// `Hs(items...)`
+fn hash_items(items: &[&[u8; 32]]) -> Scalar {
+    let mut data = Vec::with_capacity(32 * items.len());
+    for item in items {
+        data.extend_from_slice(*item);
+    }
+    hash_to_scalar(&data)
+}
+
+/// `x^0 .. x^(n-1)`
+fn powers(x: Scalar, n: usize) -> Vec<Scalar> {
+    let mut out = Vec::with_capacity(n);
+    let mut acc = Scalar::ONE;
+    for _ in 0..n {
+        out.push(acc);
+        acc *= x;
+    }
+    out
+}
+
+/// Check `proof` against the full output commitments it covers
+pub fn verify(proof: &Bulletproof, commitments: &[EdwardsPoint]) -> bool {
+    if commitments.is_empty() || commitments.len() > MAX_OUTPUTS {
+        return false;
+    }
+    let m = commitments.len().next_power_of_two();
+    let mn = m * BITS;
+    let rounds = mn.trailing_zeros() as usize;
+    if proof.l.len() != rounds || proof.r.len() != rounds {
+        return false;
+    }
+
+    // The proof is over V = C/8, so torsion in C is cleared like monerod
+    // does when it rebuilds V from outPk
+    let v: Vec<EdwardsPoint> = commitments.iter().map(|c| c * inv_eight()).collect();
+    let v_bytes: Vec<[u8; 32]> = v.iter().map(|v| v.compress().to_bytes()).collect();
+    let transcript = hash_to_scalar(&v_bytes.concat());
+    let y = hash_items(&[transcript.as_bytes(), proof.a.as_bytes(), proof.s.as_bytes()]);
+    let z = hash_to_scalar(y.as_bytes());
+    let x = hash_items(&[
+        z.as_bytes(),
+        z.as_bytes(),
+        proof.t1.as_bytes(),
+        proof.t2.as_bytes(),
+    ]);
+    let x_ip = hash_items(&[
+        x.as_bytes(),
+        x.as_bytes(),
+        proof.taux.as_bytes(),
+        proof.mu.as_bytes(),
+        proof.t.as_bytes(),
+    ]);
+    if y == Scalar::ZERO || z == Scalar::ZERO || x == Scalar::ZERO || x_ip == Scalar::ZERO {
+        return false;
+    }
+
+    let (Some(a), Some(s), Some(t1), Some(t2)) = (
+        decompress8(&proof.a),
+        decompress8(&proof.s),
+        decompress8(&proof.t1),
+        decompress8(&proof.t2),
+    ) else {
+        return false;
+    };
+    let z_powers = powers(z, m + 3);
+    let y_powers = powers(y, mn);
+    let y_sum: Scalar = y_powers.iter().sum();
+
+    // t(x) commits to the right polynomial:
+    // t*H + taux*G == sum(z^(j+2) C_j) + delta(y, z)*H + x*T1 + x^2*T2
+    let mut delta = (z - z_powers[2]) * y_sum;
+    for z_j in &z_powers[3..] {
+        delta -= z_j * Scalar::from(u64::MAX);
+    }
+    let mut scalars = vec![proof.t - delta, proof.taux, -x, -(x * x)];
+    let mut points = vec![h_generator(), ED25519_BASEPOINT_POINT, t1, t2];
+    for (v, z_j) in v.iter().zip(&z_powers[2..]) {
+        scalars.push(-z_j);
+        points.push(v.mul_by_cofactor());
+    }
+    if !EdwardsPoint::vartime_multiscalar_mul(scalars, points).is_identity() {
+        return false;
+    }
+
+    // The inner-product argument for <l, r> == t, folded into one
+    // multiexp that must vanish
+    let mut w = x_ip;
+    let mut challenges = Vec::with_capacity(rounds);
+    let mut lr = Vec::with_capacity(rounds);
+    for (li, ri) in proof.l.iter().zip(&proof.r) {
+        w = hash_items(&[w.as_bytes(), li.as_bytes(), ri.as_bytes()]);
+        if w == Scalar::ZERO {
+            return false;
+        }
+        challenges.push((w, w.invert()));
+        let (Some(li), Some(ri)) = (decompress8(li), decompress8(ri)) else {
+            return false;
+        };
+        lr.push((li, ri));
+    }
+    let gens = generators();
+    let products = challenge_products(&challenges);
+    let y_inv = y.invert();
+    let two = Scalar::from(2u8);
+
+    let mut scalars = Vec::with_capacity(2 * mn + 2 * rounds + 4);
+    let mut points = Vec::with_capacity(2 * mn + 2 * rounds + 4);
+    let mut y_inv_i = Scalar::ONE;
+    let mut two_i = Scalar::ONE;
+    for i in 0..mn {
+        if i % BITS == 0 {
+            two_i = Scalar::ONE;
+        }
+        scalars.push(-z - products[i] * proof.ip_a);
+        points.push(gens.g[i]);
+        let h_offset = z + y_inv_i * z_powers[2 + i / BITS] * two_i;
+        scalars.push(h_offset - products[mn - 1 - i] * proof.ip_b * y_inv_i);
+        points.push(gens.h[i]);
+        y_inv_i *= y_inv;
+        two_i *= two;
+    }
+    for ((w, w_inv), (li, ri)) in challenges.iter().zip(lr) {
+        scalars.push(w * w);
+        points.push(li);
+        scalars.push(w_inv * w_inv);
+        points.push(ri);
+    }
+    scalars.extend([
+        Scalar::ONE,
+        x,
+        -proof.mu,
+        x_ip * (proof.t - proof.ip_a * proof.ip_b),
+    ]);
+    points.extend([a, s, ED25519_BASEPOINT_POINT, h_generator()]);
+    EdwardsPoint::vartime_multiscalar_mul(scalars, points).is_identity()
+}
@@ -0,0 +1,578 @@
+// src/crypto/rct/bulletproof_plus.rs
+//! Bulletproofs+ aggregate range proofs (RingCT type 6, since v15).
+//!
+//! One proof covers up to 16 output commitments `C_j = mask_j*G + v_j*H` and
+//! shows every `v_j` fits in 64 bits. The proof is a weighted inner-product
+//! argument over `2 * 64 * m` generators, `m` the output count rounded up to
+//! a power of two, so it grows only logarithmically. Port of
+//! `bulletproofs_plus.cc`, including its transcript and the habit of
+//! transmitting every point multiplied by `1/8`.
+use std::{fmt, sync::OnceLock};
+
+use curve25519_dalek::{
+    EdwardsPoint, Scalar,
+    constants::ED25519_BASEPOINT_POINT,
+    edwards::CompressedEdwardsY,
+    traits::{IsIdentity, MultiscalarMul, VartimeMultiscalarMul},
+};
+use rand::rngs::OsRng;
+
+use super::bulletproof::{Generators, challenge_products, decompress8};
+use crate::crypto::cn::{hash_to_point, hash_to_scalar};
+use crate::crypto::commitment::h_generator;
+use crate::crypto::hash::keccak256;
+use crate::crypto::ring::clsag::inv_eight;
+
+/// Most outputs one proof can cover
+pub const MAX_OUTPUTS: usize = 16;
+const BITS: usize = 64;
+
+/// Why a proof could not be produced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulletproofError {
+    /// Nothing to prove
+    Empty,
+    /// More than [`MAX_OUTPUTS`] amounts
+    TooManyOutputs(usize),
+    /// Amounts and masks differ in length
+    LengthMismatch,
+}
+
+impl fmt::Display for BulletproofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BulletproofError::Empty => write!(f, "no amounts to prove"),
+            BulletproofError::TooManyOutputs(n) => {
+                write!(f, "{n} outputs, at most {MAX_OUTPUTS} per proof")
+            }
+            BulletproofError::LengthMismatch => write!(f, "amount and mask counts differ"),
+        }
+    }
+}
+
+impl std::error::Error for BulletproofError {}
+
+/// A Bulletproof+ as serialised in a transaction
+///
+/// The commitments themselves aren't part of it; they are the outputs'
+/// `outPk`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulletproofPlus {
+    pub a: CompressedEdwardsY,
+    pub a1: CompressedEdwardsY,
+    pub b: CompressedEdwardsY,
+    pub r1: Scalar,
+    pub s1: Scalar,
+    pub d1: Scalar,
+    pub l: Vec<CompressedEdwardsY>,
+    pub r: Vec<CompressedEdwardsY>,
+}
+
+fn generators() -> &'static Generators {
+    static GENS: OnceLock<Generators> = OnceLock::new();
+    GENS.get_or_init(|| Generators::new(b"bulletproof_plus"))
+}
+
+/// Fixed transcript seed, `Hp(Keccak("bulletproof_plus_transcript"))`
+fn initial_transcript() -> [u8; 32] {
+    static SEED: OnceLock<[u8; 32]> = OnceLock::new();
+    *SEED.get_or_init(|| {
+        hash_to_point(keccak256(b"bulletproof_plus_transcript").as_bytes())
+            .compress()
+            .to_bytes()
+    })
+}
+
+/// `Hs(transcript || items...)`, which also becomes the new transcript
+fn challenge(transcript: &mut Scalar, items: &[&[u8; 32]]) -> Scalar {
+    let mut data = transcript.to_bytes().to_vec();
+    for item in items {
+        data.extend_from_slice(*item);
+    }
+    *transcript = hash_to_scalar(&data);
+    *transcript
+}
+
+/// `x^1 ..= x^n`
+fn powers(x: Scalar, n: usize) -> Vec<Scalar> {
+    let mut out = Vec::with_capacity(n);
+    let mut acc = x;
+    for _ in 0..n {
+        out.push(acc);
+        acc *= x;
+    }
+    out
+}
+
+/// `sum(a_i * b_i * y^(i+1))`
+fn weighted_inner_product(a: &[Scalar], b: &[Scalar], y_powers: &[Scalar]) -> Scalar {
+    a.iter()
+        .zip(b)
+        .zip(y_powers)
+        .map(|((a, b), y)| a * b * y)
+        .sum()
+}
+
+fn compress_inv8(p: EdwardsPoint) -> CompressedEdwardsY {
+    (p * inv_eight()).compress()
+}
+
+/// The first round, shared by prover and verifier
+struct Statement {
+    /// Commitment count rounded up to a power of two
+    m: usize,
+    transcript: Scalar,
+    y: Scalar,
+    z: Scalar,
+    /// `z^2, z^4, ..`, one per padded commitment
+    z_even: Vec<Scalar>,
+    /// `d_i * y^(mn - i) + z`, the offsets added to `aR`
+    d_y_z: Vec<Scalar>,
+    /// `y^(mn + 1)`
+    y_mn_1: Scalar,
+    /// `8A` with the public parts of the statement folded in
+    a_hat: EdwardsPoint,
+}
+
+impl Statement {
+    /// `commitments` are the outputs' full `C_j`; `a` is the proof's `A`
+    fn new(commitments: &[EdwardsPoint], a: &CompressedEdwardsY) -> Option<Self> {
+        // the transcript and A-hat use C/8, as monero computes them from
+        // the proof's V vector
+        let scaled: Vec<EdwardsPoint> = commitments.iter().map(|c| c * inv_eight()).collect();
+        let mut buf = Vec::with_capacity(32 * scaled.len());
+        for c in &scaled {
+            buf.extend_from_slice(c.compress().as_bytes());
+        }
+        let mut seed = initial_transcript().to_vec();
+        seed.extend_from_slice(hash_to_scalar(&buf).as_bytes());
+        let mut transcript = hash_to_scalar(&seed);
+
+        let m = commitments.len().next_power_of_two();
+        let mn = m * BITS;
+        let y = challenge(&mut transcript, &[a.as_bytes()]);
+        let z = hash_to_scalar(y.as_bytes());
+        transcript = z;
+        if y == Scalar::ZERO || z == Scalar::ZERO {
+            return None;
+        }
+
+        let z2 = z * z;
+        let z_even = powers(z2, m);
+        let y_powers = powers(y, mn);
+        let mut d_y_z = Vec::with_capacity(mn);
+        for (j, zj) in z_even.iter().enumerate() {
+            let mut two = Scalar::ONE;
+            for bit in 0..BITS {
+                let i = j * BITS + bit;
+                d_y_z.push(zj * two * y_powers[mn - 1 - i] + z);
+                two += two;
+            }
+        }
+        let y_mn_1 = y_powers[mn - 1] * y;
+        let y_sum: Scalar = y_powers.iter().sum();
+        // every d_j sums to 2^64 - 1
+        let d_sum = z_even.iter().sum::<Scalar>() * Scalar::from(u64::MAX);
+
+        let gens = generators();
+        let mut scalars = Vec::with_capacity(2 * mn + m + 1);
+        let mut points = Vec::with_capacity(2 * mn + m + 1);
+        for ((g, h), d) in gens.g.iter().zip(&gens.h).zip(&d_y_z) {
+            scalars.push(-z);
+            points.push(*g);
+            scalars.push(*d);
+            points.push(*h);
+        }
+        for (c, zj) in scaled.iter().zip(&z_even) {
+            scalars.push(zj * y_mn_1);
+            points.push(c.mul_by_cofactor());
+        }
+        scalars.push(y_sum * z - d_sum * y_mn_1 * z - y_sum * z2);
+        points.push(h_generator());
+        let a_hat = decompress8(a)? + EdwardsPoint::vartime_multiscalar_mul(scalars, points);
+
+        Some(Self {
+            m,
+            transcript,
+            y,
+            z,
+            z_even,
+            d_y_z,
+            y_mn_1,
+            a_hat,
+        })
+    }
+}
+
+/// Prove that every `amounts[j]` is committed to by `commit(amounts[j], masks[j])`
+pub fn prove(amounts: &[u64], masks: &[Scalar]) -> Result<BulletproofPlus, BulletproofError> {
+    if amounts.is_empty() {
+        return Err(BulletproofError::Empty);
+    }
+    if amounts.len() > MAX_OUTPUTS {
+        return Err(BulletproofError::TooManyOutputs(amounts.len()));
+    }
+    if amounts.len() != masks.len() {
+        return Err(BulletproofError::LengthMismatch);
+    }
+    let commitments: Vec<EdwardsPoint> = amounts
+        .iter()
+        .zip(masks)
+        .map(|(&v, &mask)| crate::crypto::commitment::commit(v, mask))
+        .collect();
+
+    let m = amounts.len().next_power_of_two();
+    let mn = m * BITS;
+    let gens = generators();
+
+    // aL holds the bits of each amount (padding amounts are zero), aR = aL - 1
+    let a_l: Vec<Scalar> = (0..mn)
+        .map(|i| {
+            let v = amounts.get(i / BITS).copied().unwrap_or(0);
+            Scalar::from((v >> (i % BITS)) & 1)
+        })
+        .collect();
+    let a_r: Vec<Scalar> = a_l.iter().map(|b| b - Scalar::ONE).collect();
+
+    let alpha = Scalar::random(&mut OsRng);
+    let a = compress_inv8(EdwardsPoint::multiscalar_mul(
+        a_l.iter().chain(&a_r).chain([&alpha]),
+        gens.g[..mn]
+            .iter()
+            .chain(&gens.h[..mn])
+            .chain([&ED25519_BASEPOINT_POINT]),
+    ));
+
+    let st = Statement::new(&commitments, &a).expect("A is a valid point");
+    let mut a_vec: Vec<Scalar> = a_l.iter().map(|x| x - st.z).collect();
+    let mut b_vec: Vec<Scalar> = a_r.iter().zip(&st.d_y_z).map(|(x, d)| x + d).collect();
+    let mut alpha = alpha
+        + masks
+            .iter()
+            .zip(&st.z_even)
+            .map(|(mask, zj)| zj * mask * st.y_mn_1)
+            .sum::<Scalar>();
+
+    // weighted inner-product argument, halving the vectors each round
+    let mut transcript = st.transcript;
+    let mut y_powers = powers(st.y, mn);
+    let mut g_bold = gens.g[..mn].to_vec();
+    let mut h_bold = gens.h[..mn].to_vec();
+    let (mut l, mut r) = (Vec::new(), Vec::new());
+    let h = h_generator();
+    while g_bold.len() > 1 {
+        let n = g_bold.len() / 2;
+        let (a1, a2) = a_vec.split_at(n);
+        let (b1, b2) = b_vec.split_at(n);
+        let (g1, g2) = g_bold.split_at(n);
+        let (h1, h2) = h_bold.split_at(n);
+        let y_n = y_powers[n - 1];
+        let y_n_inv = y_n.invert();
+        y_powers.truncate(n);
+
+        let d_l = Scalar::random(&mut OsRng);
+        let d_r = Scalar::random(&mut OsRng);
+        let a2_y: Vec<Scalar> = a2.iter().map(|x| x * y_n).collect();
+        let c_l = weighted_inner_product(a1, b2, &y_powers);
+        let c_r = weighted_inner_product(&a2_y, b1, &y_powers);
+
+        let li = compress_inv8(EdwardsPoint::multiscalar_mul(
+            a1.iter()
+                .map(|x| x * y_n_inv)
+                .chain(b2.iter().copied())
+                .chain([c_l, d_l]),
+            g2.iter().chain(h1).chain([&h, &ED25519_BASEPOINT_POINT]),
+        ));
+        let ri = compress_inv8(EdwardsPoint::multiscalar_mul(
+            a2_y.iter().chain(b1).chain([&c_r, &d_r]),
+            g1.iter().chain(h2).chain([&h, &ED25519_BASEPOINT_POINT]),
+        ));
+        let e = challenge(&mut transcript, &[li.as_bytes(), ri.as_bytes()]);
+        let e_inv = e.invert();
+        l.push(li);
+        r.push(ri);
+
+        let e_y_inv = e * y_n_inv;
+        g_bold = g1
+            .iter()
+            .zip(g2)
+            .map(|(p, q)| EdwardsPoint::vartime_multiscalar_mul([e_inv, e_y_inv], [p, q]))
+            .collect();
+        h_bold = h1
+            .iter()
+            .zip(h2)
+            .map(|(p, q)| EdwardsPoint::vartime_multiscalar_mul([e, e_inv], [p, q]))
+            .collect();
+        let a_inv = y_n * e_inv;
+        a_vec = a1.iter().zip(a2).map(|(x, w)| x * e + w * a_inv).collect();
+        b_vec = b1.iter().zip(b2).map(|(x, w)| x * e_inv + w * e).collect();
+        alpha += d_l * e * e + d_r * e_inv * e_inv;
+    }
+
+    let (r0, s0, delta, eta) = (
+        Scalar::random(&mut OsRng),
+        Scalar::random(&mut OsRng),
+        Scalar::random(&mut OsRng),
+        Scalar::random(&mut OsRng),
+    );
+    let y = st.y;
+    let a1 = compress_inv8(EdwardsPoint::multiscalar_mul(
+        [r0, s0, r0 * y * b_vec[0] + s0 * y * a_vec[0], delta],
+        [g_bold[0], h_bold[0], h, ED25519_BASEPOINT_POINT],
+    ));
+    let b = compress_inv8(EdwardsPoint::multiscalar_mul(
+        [r0 * y * s0, eta],
+        [h, ED25519_BASEPOINT_POINT],
+    ));
+    let e = challenge(&mut transcript, &[a1.as_bytes(), b.as_bytes()]);
+
+    Ok(BulletproofPlus {
+        a,
+        a1,
+        b,
+        r1: r0 + a_vec[0] * e,
+        s1: s0 + b_vec[0] * e,
+        d1: eta + delta * e + alpha * e * e,
+        l,
+        r,
+    })
+}
+
+/// Check `proof` against the full output commitments it covers
+pub fn verify(proof: &BulletproofPlus, commitments: &[EdwardsPoint]) -> bool {
+    if commitments.is_empty() || commitments.len() > MAX_OUTPUTS {
+        return false;
+    }
+    let Some(st) = Statement::new(commitments, &proof.a) else {
+        return false;
+    };
+    let mn = st.m * BITS;
+    let rounds = mn.trailing_zeros() as usize;
+    if proof.l.len() != rounds || proof.r.len() != rounds {
+        return false;
+    }
+
+    let mut transcript = st.transcript;
+    let mut challenges = Vec::with_capacity(rounds);
+    let mut lr = Vec::with_capacity(2 * rounds);
+    for (li, ri) in proof.l.iter().zip(&proof.r) {
+        let e = challenge(&mut transcript, &[li.as_bytes(), ri.as_bytes()]);
+        if e == Scalar::ZERO {
+            return false;
+        }
+        challenges.push((e, e.invert()));
+        let (Some(li), Some(ri)) = (decompress8(li), decompress8(ri)) else {
+            return false;
+        };
+        lr.push((li, ri));
+    }
+    let e = challenge(&mut transcript, &[proof.a1.as_bytes(), proof.b.as_bytes()]);
+    let (Some(a1), Some(b)) = (decompress8(&proof.a1), decompress8(&proof.b)) else {
+        return false;
+    };
+
+    // The folded relation, checked as one multiexp that must vanish:
+    // e^2 (A-hat + sum(e_k^2 L_k + e_k^-2 R_k)) + e A1 + B ==
+    //   r1 e G'-fold + s1 e H'-fold + r1 y s1 H + d1 G
+    let gens = generators();
+    let products = challenge_products(&challenges);
+    let y_inv = st.y.invert();
+    let neg_e2 = -(e * e);
+    let mut scalars = Vec::with_capacity(2 * mn + 2 * rounds + 5);
+    let mut points = Vec::with_capacity(2 * mn + 2 * rounds + 5);
+    let mut y_inv_i = Scalar::ONE;
+    for i in 0..mn {
+        scalars.push(proof.r1 * e * products[i] * y_inv_i);
+        points.push(gens.g[i]);
+        scalars.push(proof.s1 * e * products[mn - 1 - i]);
+        points.push(gens.h[i]);
+        y_inv_i *= y_inv;
+    }
+    for ((ek, ek_inv), (li, ri)) in challenges.iter().zip(lr) {
+        scalars.push(neg_e2 * ek * ek);
+        points.push(li);
+        scalars.push(neg_e2 * ek_inv * ek_inv);
+        points.push(ri);
+    }
+    scalars.extend([
+        neg_e2,
+        -e,
+        -Scalar::ONE,
+        proof.r1 * st.y * proof.s1,
+        proof.d1,
+    ]);
+    points.extend([st.a_hat, a1, b, h_generator(), ED25519_BASEPOINT_POINT]);
+    EdwardsPoint::vartime_multiscalar_mul(scalars, points).is_identity()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::commitment::commit;
+    use crate::tx::{RctType, Transaction};
+
+    /// Mainnet transaction
+    /// 2f650db5bafd37ce8982f37ee443f2ecf0a8f08f639591583aecb6cd74d5a80c,
+    /// one CLSAG input and four outputs under one Bulletproof+
+    const MAINNET_TX: &str = "020001020010d6f68721ea820c88d539a68f0b84af09d19401c08a02f0ee\
+        048250c219958401a49f02b33fa321a527dd227f94e759b07b2c025ce22a57db0cb062bfd1f50f6086b14ca374\
+        2730c7fa9e5d040003fcdf91296bb4560335835fda30804a7d8d200acfabe4e98a0c425d38556dac06870003d6\
+        6821247fe13266bad423e445ddd6a1b51a86198e38049e2c8039ab6d5dc8b485000393ae131b8c649288a9fb61\
+        ebffa8ecb0fababa8f5159286f895f5bed10bad6388600038abfdfa2d445934fe750607f9654e02389c0566444\
+        53c942d1841bbf418d94e22021014004716b1c1ffb8447e0c1d27f147a4691ed393fdf2eadb225ebfd54ffdf87\
+        2f0680d5f814756596945ca3852476b456ac3c9942c978d0a3bcd9e6c236efadcdf54e6ed0db9c4bc6ac562b68\
+        59a40ad8f3bc85ca35c98badb4b4c5d43832f330d6fedb08e8f9e2acd339c648bf03957cb02aa69b8ab15326e3\
+        bbe1ce35df677306edabd89e5635f226a743068500e25028fbdbf1ea19d0921a27c8baec842b753080f407ee4b\
+        9a87f2c525e9bfb61fb4d14187c0577e799bf20e53a86359cb75f40ee4d291017c2b59e7278c94b6296dee9ac6\
+        5ed5ccf61a77ba4f1b3edfb13c5d02748763b23a6bac2f6a891b474d55b625030b35f9b7b564e747afd4cb8e1c\
+        e830a9bc59fd6e146443965494f94a8433de054080fcb71f8d48803598cc91db3c7b3fd190ea8ff5f67980a63d\
+        e4cb9cd06568a9b27aa994992bc33d70990225acb09faf68066aa27c1118c685cb8f3516c3b664450fabdced38\
+        4de01650d6455287bc0f210aaa5c173c491844155736a64d7cbdda79f0c8a5ccc07d187ca112664a0e6eb50008\
+        7178983179f1ba2ffb030d577638001b58f5e621b4723e5b0bd0853fb430113d03efd026660a18a23c7582e978\
+        8f770212b604759aa242b35b3ca4a835bb18881c8593ac4247ba51ea95946cf079721588bac494f563a687fe10\
+        10818caa94583969b0f4a4a40eeee395cbb7881a53d98cad51b1e5d12c7071a7424b4c534e32c53a31b11e6151\
+        edd0a13ef9695021bff9bd4c62df9a62d9e0fbd01e750d0b6abc56cc96d55ef06f6428b42fc63f6610633ecf02\
+        3211e64a1ff89dcabfeeb4b938e64312dcc849929e8d4a290eff601e06dc65141665d7b312ac1f0f859a00fd6d\
+        6ccf7dc695e7ae3cf44bed1d9c8659ee3451dd3498f462912ba881a473c9bc0866e4fb33114b2ef7c25869f9cc\
+        3c40a06fc2407e2c678126ff1c38a35c5c949bc219fb33ba15730510c41554c727d5adfce33a518148234e0aa5\
+        411cb20c115e749792ad47ee19e9f1544dba61593d95cb98d4720a8ae6e60146416d673e5707c3de31d9104342\
+        2ab848d4676a6845ced6e7075c5a09bc8b4e0ad706c8c07bda527a7325771438e04f37517f3ca5262ef2ddfc9e\
+        13db988a90c50be5422a83ad75b93f4faae980d6e6a3abfd0e96387121101afaf55f425dc876d9a8735c1e29d8\
+        23e19fee5e502c18d16ec9225f232cfbc3dcd143aaa1904f42e880b612beeea3e5a745a7f32e6b2135a75f7111\
+        7e2947c99647f14702417a9a76f6130b5d62fd149a606061709a86253c3c2a30c8ccc0e2b5ee636bda81973b01\
+        1fa8b96e0f9149e7d02d903e982b025e0944029423ba9318637387d6f0a8a75f1fa957950ce6661368738251a4\
+        18968ae390143e596a77bef7de4008ca66ed28b82e044d0ab293f792e8b1e9c1bc24b14ee53539f535b05f2f33\
+        6c1b7698ca3cb1dc8a3a09568c6841724a19d412d4313760e3560616df7f5b2250b1a52bf32922b3964309b0be\
+        db645579ee09d87959f4e997e4792ac9fa26858ef1aa1dbf7b10da08e7092cb200369d75f3d2b81ad2c237954c\
+        dfea1d173f84122ce4cf82a9ebaa04650a69f3675f2155bbb7ce508fdd6a328492b8788e37809f2accf082387b\
+        97a7660d427cac9eb93ceacda0cdb9db95a2d6c6fa9ca86276acce2cb8e432b14efb4d0e8a1f3cbc8534c5dfb9\
+        a42f7b0d5c212928115cb2c5b905c650b5325e2a849109c60329dcc20f1c1f10d9f6a87d17359938c520e00dd3\
+        f5e1857b5af502cc590cad89abca61f4a94513d8e42db9e7223b5d97afd80f490155bf49b79c7ea5c10d6cb74b\
+        a10211d6ec75458436a08794164d16bcb4d092274061449418d9fc3d0a9947a8854a399c7e77a49568676ff8df\
+        07c3aa21ca90a611dcdfe0c6bd44690a43a3263237f1def6658ba936e2f17c3853fdcd2c0e24cc0b26c59abb47\
+        031e00992ca59657da958b48d21d12ae0a93a68596b72c6cc826fd8e079de67b0539026a24c5dcea4875f16cd0\
+        722352424493647f7ad3b3148bcdf6c8504c25bbbb07a8b01a6352cb602a1964c02e7e10601644cee41c2bdbb3\
+        9a9687fdd78dca919726312d076b9e7a4e5b0324e305b99bb1c3ea40bd2296de41f2fc43f668e1a9fb";
+
+    fn random_outputs(n: usize) -> (Vec<u64>, Vec<Scalar>, Vec<EdwardsPoint>) {
+        let amounts: Vec<u64> = (0..n as u64).map(|i| i * 1_000_000_007 + 3).collect();
+        let masks: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut OsRng)).collect();
+        let commitments = amounts
+            .iter()
+            .zip(&masks)
+            .map(|(&v, &m)| commit(v, m))
+            .collect();
+        (amounts, masks, commitments)
+    }
+
+    #[test]
+    fn test_prove_verify() {
+        for n in [1, 2, 3, 16] {
+            let (amounts, masks, commitments) = random_outputs(n);
+            let proof = prove(&amounts, &masks).unwrap();
+            assert_eq!(
+                proof.l.len(),
+                6 + n.next_power_of_two().trailing_zeros() as usize
+            );
+            assert!(verify(&proof, &commitments), "{n} outputs");
+        }
+        let proof = prove(&[0, u64::MAX], &[Scalar::ONE, Scalar::ONE]).unwrap();
+        assert!(verify(
+            &proof,
+            &[commit(0, Scalar::ONE), commit(u64::MAX, Scalar::ONE)]
+        ));
+    }
+
+    #[test]
+    fn test_rejects_tampering() {
+        let (amounts, masks, commitments) = random_outputs(2);
+        let proof = prove(&amounts, &masks).unwrap();
+
+        let mut bad = proof.clone();
+        bad.r1 += Scalar::ONE;
+        assert!(!verify(&bad, &commitments));
+
+        let mut bad = proof.clone();
+        bad.l.swap(0, 1);
+        assert!(!verify(&bad, &commitments));
+
+        let mut other = commitments.clone();
+        other[1] = commit(amounts[1] + 1, masks[1]);
+        assert!(!verify(&proof, &other));
+        assert!(!verify(&proof, &commitments[..1]));
+    }
+
+    #[test]
+    fn test_mainnet_proof() {
+        let tx = Transaction::from_hex(MAINNET_TX).unwrap();
+        assert_eq!(
+            hex::encode(tx.hash().as_bytes()),
+            "2f650db5bafd37ce8982f37ee443f2ecf0a8f08f639591583aecb6cd74d5a80c"
+        );
+        let rct = &tx.rct_signatures;
+        assert_eq!(rct.rct_type(), RctType::BulletproofPlus);
+        let commitments: Vec<EdwardsPoint> = rct
+            .base
+            .out_pk
+            .iter()
+            .map(|c| c.decompress().unwrap())
+            .collect();
+        let proof = &rct.prunable.bulletproofs_plus[0];
+        assert_eq!(commitments.len(), 4);
+        assert_eq!(proof.l.len(), 8);
+        assert!(verify(proof, &commitments));
+
+        let mut bad = proof.clone();
+        bad.d1 += Scalar::ONE;
+        assert!(!verify(&bad, &commitments));
+        let mut swapped = commitments.clone();
+        swapped.swap(0, 3);
+        assert!(!verify(proof, &swapped));
+    }
+
+    #[test]
+    fn test_generators() {
+        // pinned from the values the mainnet proof above verifies under
+        assert_eq!(
+            hex::encode(initial_transcript()),
+            "4a677c90eb73051e790da45591107f6ee105904d9187c5d35471096c445a2275"
+        );
+        let gens = generators();
+        assert_eq!(gens.g.len(), MAX_OUTPUTS * BITS);
+        for (point, expected) in [
+            (
+                gens.g[0],
+                "38c5d4db53aeb86f5a80def9be4953f2288ed5a44c66af723f463d0170829010",
+            ),
+            (
+                gens.h[0],
+                "48628df380a5016d25451aaa501731a11b72bf66dc41d81f719abd35ce92b0ed",
+            ),
+            (
+                gens.g[255],
+                "f7d31b7fe251507bfa6cc0f3f26fc0752212c67fc7e88cb8b4547be53733ab23",
+            ),
+            (
+                gens.h[1023],
+                "8d9afbc61e2be1b105b9c6135a0d91e9b91330bd34e33d1fee74b535ce541066",
+            ),
+        ] {
+            assert_eq!(hex::encode(point.compress().as_bytes()), expected);
+        }
+        // salted apart from the original Bulletproofs' generators
+        assert_ne!(gens.g[0], Generators::new(b"bulletproof").g[0]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(prove(&[], &[]), Err(BulletproofError::Empty));
+        assert_eq!(
+            prove(&[1; 17], &[Scalar::ONE; 17]),
+            Err(BulletproofError::TooManyOutputs(17))
+        );
+        assert_eq!(
+            prove(&[1, 2], &[Scalar::ONE]),
+            Err(BulletproofError::LengthMismatch)
+        );
+    }
+}
@@ -0,0 +1,93 @@
+// src/crypto/rct/ecdh.rs
+//! Output amount encryption (`ecdhEncode` / `ecdhDecode`).
+//!
+//! Sender and recipient share `s = Hs(8rA || i)` for output `i`. Before v10
+//! the mask and amount were hidden by adding `Hs(s)` and `Hs(Hs(s))`;
+//! since, the mask is derived from `s` and only the amount is sent, XORed
+//! with 8 bytes of `Keccak256("amount" || s)`.
+use curve25519_dalek::Scalar;
+
+use crate::crypto::{
+    cn::hash_to_scalar,
+    hash::keccak256,
+    keys::{KeyDerivation, derivation_to_scalar},
+};
+
+/// Shared secret of output `output_index`, from the transaction's key
+/// derivation
+pub fn shared_secret(derivation: &KeyDerivation, output_index: u64) -> Scalar {
+    derivation_to_scalar(derivation, output_index)
+}
+
+/// `Hs("commitment_mask" || s)`: the commitment mask of outputs with
+/// short amounts
+pub fn commitment_mask(shared: &Scalar) -> Scalar {
+    let mut data = b"commitment_mask".to_vec();
+    data.extend_from_slice(shared.as_bytes());
+    hash_to_scalar(&data)
+}
+
+fn amount_keystream(shared: &Scalar) -> [u8; 8] {
+    let mut data = b"amount".to_vec();
+    data.extend_from_slice(shared.as_bytes());
+    keccak256(&data).as_bytes()[..8].try_into().unwrap()
+}
+
+/// Short form: `amount` XORed with the keystream
+pub fn encode_amount(amount: u64, shared: &Scalar) -> [u8; 8] {
+    let keystream = amount_keystream(shared);
+    std::array::from_fn(|i| amount.to_le_bytes()[i] ^ keystream[i])
+}
+
+pub fn decode_amount(encrypted: &[u8; 8], shared: &Scalar) -> u64 {
+    let keystream = amount_keystream(shared);
+    u64::from_le_bytes(std::array::from_fn(|i| encrypted[i] ^ keystream[i]))
+}
+
+/// Long form: `(mask + Hs(s), amount + Hs(Hs(s)))`
+pub fn encode_full(amount: u64, mask: &Scalar, shared: &Scalar) -> ([u8; 32], [u8; 32]) {
+    let mask_factor = hash_to_scalar(shared.as_bytes());
+    let amount_factor = hash_to_scalar(mask_factor.as_bytes());
+    (
+        (mask + mask_factor).to_bytes(),
+        (Scalar::from(amount) + amount_factor).to_bytes(),
+    )
+}
+
+/// Amount and mask from the long form; None unless both are canonical
+/// scalars and the amount fits in 64 bits
+pub fn decode_full(mask: &[u8; 32], amount: &[u8; 32], shared: &Scalar) -> Option<(u64, Scalar)> {
+    let mask = Option::<Scalar>::from(Scalar::from_canonical_bytes(*mask))?;
+    let amount = Option::<Scalar>::from(Scalar::from_canonical_bytes(*amount))?;
+    let mask_factor = hash_to_scalar(shared.as_bytes());
+    let amount_factor = hash_to_scalar(mask_factor.as_bytes());
+    let amount = (amount - amount_factor).to_bytes();
+    if amount[8..] != [0; 24] {
+        return None;
+    }
+    let amount = u64::from_le_bytes(amount[..8].try_into().unwrap());
+    Some((amount, mask - mask_factor))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let shared = Scalar::random(&mut OsRng);
+        let encrypted = encode_amount(123_456_789, &shared);
+        assert_eq!(decode_amount(&encrypted, &shared), 123_456_789);
+        let other = Scalar::random(&mut OsRng);
+        assert_ne!(decode_amount(&encrypted, &other), 123_456_789);
+
+        let mask = Scalar::random(&mut OsRng);
+        let (m, a) = encode_full(u64::MAX, &mask, &shared);
+        assert_eq!(decode_full(&m, &a, &shared), Some((u64::MAX, mask)));
+        // the wrong secret leaves an amount far beyond 64 bits
+        assert_eq!(decode_full(&m, &a, &other), None);
+        assert_eq!(decode_full(&[0xff; 32], &a, &shared), None);
+    }
+}
@@ -0,0 +1,6 @@
+// src/crypto/rct/mod.rs
+// RingCT building blocks (range proofs, RingCT signature pieces)
+pub mod borromean;
+pub mod bulletproof;
+pub mod bulletproof_plus;
+pub mod ecdh;
@@ -0,0 +1,214 @@
+// src/crypto/ring/batch.rs
+//! Batch verification for CLSAG and MLSAG signatures.
+//!
+//! Unlike Ed25519, these can't be folded into one multiscalar
+//! multiplication: every round's L/R points are hashed to produce the next
+//! challenge, so a verifier has to materialise each of them. What batching
+//! buys here is spreading the queue over all cores and reporting exactly
+//! which signatures failed.
+use curve25519_dalek::EdwardsPoint;
+
+use super::clsag::{self, ClsagSignature};
+use super::mlsag::{self, MlsagSignature};
+
+enum Item {
+    Clsag {
+        message: [u8; 32],
+        keys: Vec<EdwardsPoint>,
+        commitments: Vec<EdwardsPoint>,
+        pseudo_out: EdwardsPoint,
+        sig: Box<ClsagSignature>,
+    },
+    Mlsag {
+        message: [u8; 32],
+        pk: Vec<Vec<EdwardsPoint>>,
+        sig: MlsagSignature,
+        ds_rows: usize,
+    },
+}
+
+impl Item {
+    fn verify(&self) -> bool {
+        match self {
+            Item::Clsag {
+                message,
+                keys,
+                commitments,
+                pseudo_out,
+                sig,
+            } => clsag::verify(message, keys, commitments, pseudo_out, sig),
+            Item::Mlsag {
+                message,
+                pk,
+                sig,
+                ds_rows,
+            } => mlsag::verify(message, pk, sig, *ds_rows),
+        }
+    }
+}
+
+/// Queue of ring signatures to check in one go
+#[derive(Default)]
+pub struct BatchVerifier {
+    items: Vec<Item>,
+}
+
+impl BatchVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Queue a CLSAG; returns its index in the batch
+    pub fn queue_clsag(
+        &mut self,
+        message: [u8; 32],
+        keys: Vec<EdwardsPoint>,
+        commitments: Vec<EdwardsPoint>,
+        pseudo_out: EdwardsPoint,
+        sig: ClsagSignature,
+    ) -> usize {
+        self.items.push(Item::Clsag {
+            message,
+            keys,
+            commitments,
+            pseudo_out,
+            sig: Box::new(sig),
+        });
+        self.items.len() - 1
+    }
+
+    /// Queue an MLSAG; returns its index in the batch
+    pub fn queue_mlsag(
+        &mut self,
+        message: [u8; 32],
+        pk: Vec<Vec<EdwardsPoint>>,
+        sig: MlsagSignature,
+        ds_rows: usize,
+    ) -> usize {
+        self.items.push(Item::Mlsag {
+            message,
+            pk,
+            sig,
+            ds_rows,
+        });
+        self.items.len() - 1
+    }
+
+    /// Verify everything queued; on failure, the sorted indices of bad signatures
+    pub fn verify(self) -> Result<(), Vec<usize>> {
+        if self.items.is_empty() {
+            return Ok(());
+        }
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let per_thread = self.items.len().div_ceil(threads);
+
+        let mut failed: Vec<usize> = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .items
+                .chunks(per_thread)
+                .enumerate()
+                .map(|(t, group)| {
+                    scope.spawn(move || {
+                        group
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, item)| !item.verify())
+                            .map(|(i, _)| t * per_thread + i)
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().expect("verifier thread panicked"))
+                .collect()
+        });
+        failed.sort_unstable();
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(failed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::commitment::commit;
+    use curve25519_dalek::{Scalar, constants::ED25519_BASEPOINT_POINT};
+    use rand::rngs::OsRng;
+
+    fn signed_clsag(
+        message: [u8; 32],
+    ) -> (
+        Vec<EdwardsPoint>,
+        Vec<EdwardsPoint>,
+        EdwardsPoint,
+        ClsagSignature,
+    ) {
+        let p = Scalar::random(&mut OsRng);
+        let mask = Scalar::random(&mut OsRng);
+        let pseudo_mask = Scalar::random(&mut OsRng);
+        let mut keys: Vec<_> = (0..4)
+            .map(|_| Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT)
+            .collect();
+        let mut commitments = keys.clone();
+        keys[1] = p * ED25519_BASEPOINT_POINT;
+        commitments[1] = commit(10, mask);
+        let pseudo_out = commit(10, pseudo_mask);
+        let sig = clsag::sign(
+            &message,
+            &keys,
+            &commitments,
+            &pseudo_out,
+            &p,
+            &(mask - pseudo_mask),
+            1,
+        )
+        .unwrap();
+        (keys, commitments, pseudo_out, sig)
+    }
+
+    #[test]
+    fn test_batch_reports_failures() {
+        let mut batch = BatchVerifier::new();
+        for i in 0..6u8 {
+            let (keys, commitments, pseudo_out, sig) = signed_clsag([i; 32]);
+            // Item 4 is queued against the wrong message
+            let msg = if i == 4 { [99; 32] } else { [i; 32] };
+            batch.queue_clsag(msg, keys, commitments, pseudo_out, sig);
+        }
+
+        let x = Scalar::random(&mut OsRng);
+        let pk = vec![
+            vec![x * ED25519_BASEPOINT_POINT],
+            vec![ED25519_BASEPOINT_POINT],
+        ];
+        let sig = mlsag::sign(&[1; 32], &pk, &[x], 0, 1).unwrap();
+        batch.queue_mlsag([1; 32], pk, sig, 1);
+
+        assert_eq!(batch.len(), 7);
+        assert_eq!(batch.verify(), Err(vec![4]));
+    }
+
+    #[test]
+    fn test_batch_all_valid() {
+        let mut batch = BatchVerifier::new();
+        assert!(batch.is_empty());
+        for i in 0..3u8 {
+            let (keys, commitments, pseudo_out, sig) = signed_clsag([i; 32]);
+            batch.queue_clsag([i; 32], keys, commitments, pseudo_out, sig);
+        }
+        assert_eq!(batch.verify(), Ok(()));
+    }
+}
@@ -0,0 +1,561 @@
+// src/crypto/ring/clsag.rs
+//! CLSAG (concise linkable spontaneous anonymous group) signatures, the
+//! RingCT input signature since v13. Port of `CLSAG_Gen` /
+//! `verRctCLSAGSimple` including the domain-separated aggregation hashes.
+//!
+//! Each ring member is a pair (P_i, C_i): one-time key and amount
+//! commitment. The signer proves knowledge of `p` with `P_l = p*G` and of
+//! `z` with `C_l - C_offset = z*G`, where `C_offset` is the pseudo-output.
+use std::sync::OnceLock;
+
+use curve25519_dalek::{
+    EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT, traits::IsIdentity,
+    traits::VartimeMultiscalarMul,
+};
+use rand::rngs::OsRng;
+
+use super::RingError;
+use crate::crypto::cn::{KeyImage, hash_to_point, hash_to_scalar};
+
+const HASH_KEY_CLSAG_ROUND: &[u8] = b"CLSAG_round";
+const HASH_KEY_CLSAG_AGG_0: &[u8] = b"CLSAG_agg_0";
+const HASH_KEY_CLSAG_AGG_1: &[u8] = b"CLSAG_agg_1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClsagSignature {
+    /// One response per ring member
+    pub s: Vec<Scalar>,
+    /// Challenge at ring index 0
+    pub c1: Scalar,
+    /// Commitment key image `z * Hp(P_l) / 8`
+    pub d: EdwardsPoint,
+    /// Spend key image `p * Hp(P_l)` (carried by the tx input, not serialized)
+    pub key_image: KeyImage,
+}
+
+pub(crate) fn inv_eight() -> Scalar {
+    static INV8: OnceLock<Scalar> = OnceLock::new();
+    *INV8.get_or_init(|| Scalar::from(8u8).invert())
+}
+
+/// 32-byte zero-padded domain tag, as monero copies it into a `key`
+fn domain(tag: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[..tag.len()].copy_from_slice(tag);
+    out
+}
+
+/// Hashes shared by signer and verifier
+struct Context {
+    mu_p: Scalar,
+    mu_c: Scalar,
+    /// domain || P || C || C_offset || message, L and R get appended per round
+    round_prefix: Vec<u8>,
+}
+
+impl Context {
+    fn new(
+        message: &[u8; 32],
+        keys: &[EdwardsPoint],
+        commitments: &[EdwardsPoint],
+        c_offset: &EdwardsPoint,
+        image: &EdwardsPoint,
+        d_div8: &EdwardsPoint,
+    ) -> Self {
+        let mut ring = Vec::with_capacity(64 * keys.len() + 96);
+        for p in keys.iter().chain(commitments) {
+            ring.extend_from_slice(p.compress().as_bytes());
+        }
+
+        let agg = |tag: &[u8]| {
+            let mut buf = domain(tag).to_vec();
+            buf.extend_from_slice(&ring);
+            buf.extend_from_slice(image.compress().as_bytes());
+            buf.extend_from_slice(d_div8.compress().as_bytes());
+            buf.extend_from_slice(c_offset.compress().as_bytes());
+            hash_to_scalar(&buf)
+        };
+
+        let mut round_prefix = domain(HASH_KEY_CLSAG_ROUND).to_vec();
+        round_prefix.extend_from_slice(&ring);
+        round_prefix.extend_from_slice(c_offset.compress().as_bytes());
+        round_prefix.extend_from_slice(message);
+
+        Context {
+            mu_p: agg(HASH_KEY_CLSAG_AGG_0),
+            mu_c: agg(HASH_KEY_CLSAG_AGG_1),
+            round_prefix,
+        }
+    }
+
+    fn round_hash(&self, l: &EdwardsPoint, r: &EdwardsPoint) -> Scalar {
+        let mut buf = self.round_prefix.clone();
+        buf.extend_from_slice(l.compress().as_bytes());
+        buf.extend_from_slice(r.compress().as_bytes());
+        hash_to_scalar(&buf)
+    }
+
+    /// L = s*G + c*mu_P*P + c*mu_C*C,  R = s*Hp(P) + c*mu_P*I + c*mu_C*D
+    #[allow(clippy::too_many_arguments)]
+    fn next(
+        &self,
+        c: &Scalar,
+        s: &Scalar,
+        p: &EdwardsPoint,
+        c_adj: &EdwardsPoint,
+        image: &EdwardsPoint,
+        d: &EdwardsPoint,
+    ) -> Scalar {
+        let c_p = self.mu_p * c;
+        let c_c = self.mu_c * c;
+        let hp = hash_to_point(p.compress().as_bytes());
+        let l = EdwardsPoint::vartime_multiscalar_mul(
+            [*s, c_p, c_c],
+            [ED25519_BASEPOINT_POINT, *p, *c_adj],
+        );
+        let r = EdwardsPoint::vartime_multiscalar_mul([*s, c_p, c_c], [hp, *image, *d]);
+        self.round_hash(&l, &r)
+    }
+}
+
+/// A CLSAG with every response filled in except the signer's
+///
+/// Built from the signer's nonce points `a·G` and `a·Hp(P_l)` rather than
+/// `a` itself, so several parties holding shares of `a`, `p` and `z` can
+/// agree on it and each contribute to `s[index]`, as multisig does.
+pub(crate) struct Skeleton {
+    pub sig: ClsagSignature,
+    /// Challenge at the signer's index
+    pub c: Scalar,
+    pub mu_p: Scalar,
+    pub mu_c: Scalar,
+}
+
+impl Skeleton {
+    /// Fill in `s[index] = a - c·(mu_P·p + mu_C·z)`
+    pub fn finish(mut self, index: usize, response: Scalar) -> ClsagSignature {
+        self.sig.s[index] = response;
+        self.sig
+    }
+}
+
+/// Lay out a CLSAG around a known key image and commitment image
+///
+/// `image = p·Hp(P_l)` and `d = z·Hp(P_l)`; `decoys[i]` becomes the response
+/// of every ring member but `index`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn skeleton(
+    message: &[u8; 32],
+    keys: &[EdwardsPoint],
+    commitments: &[EdwardsPoint],
+    c_offset: &EdwardsPoint,
+    image: &EdwardsPoint,
+    d: &EdwardsPoint,
+    nonce: (&EdwardsPoint, &EdwardsPoint),
+    decoys: Vec<Scalar>,
+    index: usize,
+) -> Result<Skeleton, RingError> {
+    let n = keys.len();
+    if n < 2 {
+        return Err(RingError::RingTooSmall);
+    }
+    if commitments.len() != n || decoys.len() != n {
+        return Err(RingError::DimensionMismatch);
+    }
+    if index >= n {
+        return Err(RingError::IndexOutOfRange);
+    }
+
+    let d_div8 = d * inv_eight();
+    let ctx = Context::new(message, keys, commitments, c_offset, image, &d_div8);
+    let mut s = decoys;
+    s[index] = Scalar::ZERO;
+
+    let mut c = ctx.round_hash(nonce.0, nonce.1);
+    let mut c1 = Scalar::ZERO;
+    let mut i = (index + 1) % n;
+    if i == 0 {
+        c1 = c;
+    }
+    while i != index {
+        c = ctx.next(&c, &s[i], &keys[i], &(commitments[i] - c_offset), image, d);
+        i = (i + 1) % n;
+        if i == 0 {
+            c1 = c;
+        }
+    }
+
+    Ok(Skeleton {
+        sig: ClsagSignature {
+            s,
+            c1,
+            d: d_div8,
+            key_image: KeyImage(image.compress().to_bytes()),
+        },
+        c,
+        mu_p: ctx.mu_p,
+        mu_c: ctx.mu_c,
+    })
+}
+
+/// Sign `message` as ring member `index`
+///
+/// `p` is the one-time secret key of `keys[index]`, `z` the mask difference
+/// `input mask - pseudo-out mask`, `c_offset` the pseudo-output commitment.
+pub fn sign(
+    message: &[u8; 32],
+    keys: &[EdwardsPoint],
+    commitments: &[EdwardsPoint],
+    c_offset: &EdwardsPoint,
+    p: &Scalar,
+    z: &Scalar,
+    index: usize,
+) -> Result<ClsagSignature, RingError> {
+    let n = keys.len();
+    if n < 2 {
+        return Err(RingError::RingTooSmall);
+    }
+    if commitments.len() != n {
+        return Err(RingError::DimensionMismatch);
+    }
+    if index >= n {
+        return Err(RingError::IndexOutOfRange);
+    }
+    if p * ED25519_BASEPOINT_POINT != keys[index]
+        || z * ED25519_BASEPOINT_POINT != commitments[index] - c_offset
+    {
+        return Err(RingError::KeyMismatch);
+    }
+
+    let h = hash_to_point(keys[index].compress().as_bytes());
+    let a = Scalar::random(&mut OsRng);
+    let decoys = (0..n).map(|_| Scalar::random(&mut OsRng)).collect();
+    let sk = skeleton(
+        message,
+        keys,
+        commitments,
+        c_offset,
+        &(p * h),
+        &(z * h),
+        (&(a * ED25519_BASEPOINT_POINT), &(a * h)),
+        decoys,
+        index,
+    )?;
+    let response = a - sk.c * (sk.mu_p * p + sk.mu_c * z);
+    Ok(sk.finish(index, response))
+}
+
+/// Verify a CLSAG over ring (keys, commitments) with pseudo-output `c_offset`
+pub fn verify(
+    message: &[u8; 32],
+    keys: &[EdwardsPoint],
+    commitments: &[EdwardsPoint],
+    c_offset: &EdwardsPoint,
+    sig: &ClsagSignature,
+) -> bool {
+    let n = keys.len();
+    if n < 2 || commitments.len() != n || sig.s.len() != n {
+        return false;
+    }
+    let Some(image) = sig.key_image.point() else {
+        return false;
+    };
+    let d = sig.d.mul_by_cofactor();
+    if d.is_identity() {
+        return false;
+    }
+
+    let ctx = Context::new(message, keys, commitments, c_offset, &image, &sig.d);
+    let mut c = sig.c1;
+    for i in 0..n {
+        c = ctx.next(
+            &c,
+            &sig.s[i],
+            &keys[i],
+            &(commitments[i] - c_offset),
+            &image,
+            &d,
+        );
+        if c == Scalar::ZERO {
+            return false;
+        }
+    }
+    c == sig.c1
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    use super::*;
+    use crate::crypto::commitment::commit;
+    use crate::tx::Transaction;
+
+    /// A two-input CLSAG transaction monero-wallet-rpc built on a test
+    /// chain, efd109f6cec3530a98c5d87d5058ed87fd616d8afdcf6655a11ac8a6b56ab27e,
+    /// and the keys and commitments of its rings as the daemon returned
+    /// them, from monero-oxide's test vectors
+    const WALLET_TX: &str = "020002020010020102010101010302010c0201060103d8c6f077bb201ffd\
+        c16407df206cb5962ec635a4a4c9cd7551b88698d1bef497020010000402040801010303030101020104018267\
+        c18a435f4a5dea50ad0f10755a4fd7783340beb3a3903a67fa14938edf420200039716cdbae38def9a74e7df54\
+        02c108270a1d5fc87c7e5ebaaaed68aae77701e3cf0003082e27ca8af2b9e3004156c152aa98503b548b1591fd\
+        cd839ab550612ae6c9dc7e2c01a57c93fb0ca77ab96b7dfd7380c4842d1e58c055430e0d425cd1c76c578cca39\
+        0209019519f8c1ce5e20300680e5a0da09acd081c0dd2c7178a341382720ada87588a96ac5cff1623fd2e4aaf5\
+        6ed395a325393fbd950428a3ff7e6dc6c559669c8d5e8fb80d5e979c8a81c89754201d4bd094c37c143759260e\
+        282555dfed3100013256ca0156c1c34dc569565039c27f784b45ec50ba816f69b54ae3df98d841070f51aec2a8\
+        afd4991d5bbf50b785d0bdc2a6491c5ab45795d7ce3b08d63282907c52f9951e711cb6a2cba1aba1f7849a6693\
+        45711263cc736e2d4e1c7308c5e7cb97e948ed647f89fc9869fb9c9a5a742e5e7be419cce7a5e99a5b21cb491f\
+        00003ec1da7e8cec39b709d46fab65f59f5f6147c1e4429d18d8bf6e3e62639102a300ce6006a20403ef021a19\
+        7b6c632ac280e674c7aad08290424271dec4de010710ee7895389150dd15017cfd5f47ea9dddd11e2182514339\
+        06f62aff6b8cb2b5f8cca25add297da40d7cddbea718703ff9ad3795fcdc172a34c73179326c16f5274de69073\
+        281f3276d800fe7fbd01a07d14a42ce367c32727a9f0bc8c8d6ab4b3b17dd981bdc522595fc1bfe83ad3976876\
+        fb3bb2e4bd4392ac1a94ac22cbcc326ede82d1af2f1ec9d4ac596b22d035c7f1ac11d8ace7c5a70b30e39596de\
+        d794077ae55144e3f4b0c17cbc4f5a960129eb5321077bb7e2b9e4621e17fbcf2960abae1e1a9f89af21cc2fcc\
+        e410a839186b8da92966415d6dd3ad772d652cbe075af46b97ae7062ccbaa328e371a351492f6860832c5bfdd7\
+        b77e8611b7441ecfa0967e66c13cb9ab348bf78a15bbd2d9bec6b8ec5cdd5f84a91580758247da84afed22ec2c\
+        f89d632e406fdc927e48ebfaacd0a0b715a968c9cfc74fff611f4cda4b6cb9eb1e044a71c58a832c5ae7551833\
+        c0ba2ab6f9d1e466e5757c230157cd3099686bf89e8f9eb822ea702e13e38f669603dd3c7c8be90daf192de689\
+        ab2078d16cf489f3782e70469fbe01f918297e0db6cef3bf48e0293b6856d348fda3a2d76bf899432acef74aa4\
+        2961be28635d1899509b9d368bc42a18e08d2b94b055da149139c347f7c0b2a381dfaa12aaaabe076f38fe1237\
+        2d1ba17cd0d808ed5b4b911f8cee2e45841a4c879f40968e455ba5a796b27c968be0f7e88daf0b766fcf2c5986\
+        fbe14b2e0433cecb04af100ec81d03e2875d25483d0a9dc9dc0a42150a64e894af1655e9ab99f629826f63c01e\
+        44b366c5fe2959c7396450360a3156ad081764b5904a7654fe82a2b1d52db46361c0b08dfeee383165641e6e0e\
+        5733e5fb99fc8c75ba5cf230518b1e384d4441251840e810aed950eb27899809711d42c54f8fc0647537e249e5\
+        10738412c399b915ff923e9209cdd12820720b8b07086f3361d6b95934f994a8ac4fb6a9598f11d54bbbcfc33e\
+        71b9f73570012b3520914dfab3f3fe15abad981d8ed71dab71ac8f45f187f62ad440a83d000e08fc039ece25e7\
+        eadd0ce169ccda8182321cd73eba6f6d0e4f482a061eb4190fe4051e6988a47165cb2cf39973b1a555cc92d662\
+        f4e856a91c0cd51a486b960cfc850c4fc854f9a4aade4336942cb50cb50ae3bc31d3da50b719196d5fd40f02b1\
+        addad16de443e825bf7177beaac79adc6b198115f408a391a94a8517b7e50fd57663df52309c0a00b0b61373f8\
+        95206771be8b185c54da6f805b561264aa019ef3bd1dcded26fc45a6a0e39cbb7bc6a7025ab858bc8e54a99da3\
+        aedce68f00bacc83a7eb3553ac626881188329b6ba86a53aaaaed9bd9efb0528f08c649c093f005dd0fa9620b0\
+        a40fc3f248c1d0edb8f70ff05c7254de0f8faab8315443021b3d279f5a4218c3126dee5d6eceae1c49eabdd04d\
+        8a0cdb6814c422b3ea69b3be3794f42081e65dc47b1d2fc2f5705cef816596416c373bd60abc4ff06b3f02ef34\
+        dc290f987607bdb16c1650307ea3bc0fc7a62ce86e7129293d7530c3cf09dc731e22c18daec3c639575421b079\
+        fa57be56693278125b2aa50c299ac4f8020714c6ac666b7fb7471c63adda93f1fa6733729f7b6e326ac04744f9\
+        c3223d0456ee515d0bfe27101f907cca958dddb90717bed5229c1a02928fab9e7be4e4012c96d3acda0ebca72e\
+        63f41efdad5c9baa19bffd1216e4c3e2e5564e823b57054a3a2cf2c3318f214d23f24304655e73d50015186337\
+        57f6cbe6711f2a5f2601df20a753caaa87a32fe627b6ce7573ce77957c7b6401959824fd49bc7063670fb18fcc\
+        1f2de113affd868eb76c7fbe12997024dc493b6a26563a80574a52760a7b384fd2f9d23d8dfe4d226b15086751\
+        d4f383d4bca7cf080fd471b8a218b709b539f4e5417677f43627ef06b70c24edacce80bdd10ca2ac9af8aa3f64\
+        53cc08da75ee99409447225843c143fca551167a4aa5fd2354a5420c35c0006731950d6c356218d8cf365e084d\
+        9bb52c793322aa2d8d05c4164d9ffe81ce09e4f17802efa7461d375a5cff4c17ab0cdc5767a8f7d34091921fd4\
+        620660470ea9305f00dd9e6ee5ca4054ac0b36d4e2b58006224559cc19a3a4e48f66aa596295541007f2524b21\
+        98f3c0c688fbbc38590f59674b25e528ac2115a0f7da805d9c5810065f95c7c7ece23d2de922e55a77f967baab\
+        6d9db543e49734a8c4bc23c5ae640edb904851b4856c5a1ce4729957f4d000e70cb88c56d80bf6e693a5c67d56\
+        61911374d7aa7f6e6f4a5b340a9954d9cf8bd5d2f4b4a37f946e15bca800978ae745eec2096b3def10f9703a6e\
+        2040df0d8a89bf1562bb29d3a13df2f9a77c3e064e";
+
+    const RING_KEYS: [[&str; 16]; 2] = [
+        [
+            "a1abc026eb4a18ca197ca7dbd32f7a4e66cda075a7c07ee6cbe68639a4b4ee46",
+            "a374121e22ed620248c970e7f32ea7598b054f73c1edec33c4e1b18a73c35c14",
+            "e2ac4d36f9567092563a09c7a19c5e21c39598f5d9d9dd8733b61cebb3ea8662",
+            "68c08bbbfdb3ad736dfed5854264a3b410de40d8f3d02b22f5cf75f69f6e2e1f",
+            "7b8b580f7a2288040a0755810c5708c5a8277d139762545082785260275678e4",
+            "348d9be3f2b42686c2a919ba1515c5a540c5ffb4c1762e4a371b42643ff69b3b",
+            "91a59666453bcc55d2a02480dfe2029082e24548cdfd7d614be31657fdd75357",
+            "9868cb5201d4b00e5a3552a7f485662dfb3ca74b79f6bd069ee0a4650597abbc",
+            "56d05fced0eb9dda981a26fdd4170f46de2b0a35c70f02ceae23ad9f2ed8a5b0",
+            "9e82f65349da1e0dacf5d96a9c0f80c0c5fd0fc2437cafbcc38b2f20e721abc5",
+            "2590a255607ab619fcd62142f4b002818f2d55dbb5b8665500854203b83e5c86",
+            "c0e22332d897f0637440ad151089652e59dcbf27dc84b11c2efbe686a9e7afb5",
+            "360e4efb484e8d419bdda5f581703de716671e3516d1c9deb97204f9b4c9c0d4",
+            "5bb515d131f03bbb3be4e710b83589f62f07f185b9ad344095df47092f41b8e0",
+            "5ce647c3017ec3c36a2385e2b11fb9a452a5766987d80531bec75952924ed896",
+            "dbc787f7ca41996a981a0ebb498a8d565dfa62a3b3b169c4c3018fff2233a757",
+        ],
+        [
+            "d10621b38fbc5237061b2d3503866f0be46aaa0694c9f9d747f7ed19acebe8ef",
+            "a9afb71ae2db057049131df856d246f7088a656cc85297ce7e1ef339bd6e0c96",
+            "68c08bbbfdb3ad736dfed5854264a3b410de40d8f3d02b22f5cf75f69f6e2e1f",
+            "74193737897162c8b2c380ff34674e3bfbfb2ac7e1c7aacbb13f2a3a8fb2b043",
+            "96e002055aafbfdd1136cc587543e5c0e51da0d9682879c107abab3cdcdb9479",
+            "2a72f3b2cb3e10727fbfc09d2c726763000a92f77f2f000c63dee714a6c7424d",
+            "797f5f3a30ce8d4b19305ca9d8193033d649f0a74705203da9f3f106ad60dfb4",
+            "b0c42947607815eba320f97e7c9ecd092fe187fb67d7263540015e6308f6dc1a",
+            "59015cfd533a742857454dce9d82846fce08ab7d96c5583640cf6e38ecf0445e",
+            "c0e22332d897f0637440ad151089652e59dcbf27dc84b11c2efbe686a9e7afb5",
+            "360e4efb484e8d419bdda5f581703de716671e3516d1c9deb97204f9b4c9c0d4",
+            "92619df80e988c0b2dfb63dd6324ff2979ca319bf8200260b28944753dda4ac1",
+            "0ac7e630a04be92b1f3c821c50ec80a2813f7bee4c1ab117967bc26263d4fd84",
+            "b97300cdb6ef63a6990686521138b5c7c80cf6c9a8844518352f3ef1130d413d",
+            "1a62237b77e28713e5a47129f1ba18be27a5139d6f1e6d6d38c78705143b3ea5",
+            "ffca492152d8206bb7f215d2408669856203edffd424f4fc6a0304def2195717",
+        ],
+    ];
+    const RING_COMMITMENTS: [[&str; 16]; 2] = [
+        [
+            "48d7f0b8796720c7edef5e3797135b3e5ad2ae23db1d934bcf6d6bc396b8ed47",
+            "15beeeedc9b33615097e0fac0acc6a0984e139fa2b4196896877a8cc3ebc3590",
+            "3d9105f85f9edd3f7f72b62385bb9a42d549331d3babea6cf73bbbcde8e4f53c",
+            "36c39958ddcad401d85d63883da510505650321ad7a26859e8b1b6c28204d274",
+            "498105ec1dc7559becfb833140c5049382b846eff812616a2414494d7a46930d",
+            "eeca9ed04ba72a89dbd85564cf3084daad577634db09d048895524f1ded26b19",
+            "ae7f14cbb31d24b727d8680fbd03bcc177fc67b982edeca54e6b2b47d6b8d012",
+            "570e3b126e429022177d22fd09d73c6950676c82a4872addb3afa950646c5f1d",
+            "a0e20ecd8526bd2a640c4df42c187fcf75d05660ba61262c93b19384b8fad49b",
+            "e83344061c0632631eec627bb2103898cfc230b35e0177681e48f0ee4b6d37c8",
+            "e9c103485b3f4dadab560e8efc67c594ba11f16513685f0faff78c6fdf4de061",
+            "363d5dcbc765854e830dc52762e24f71d7c85f6095227551f3ef6ada6aa25964",
+            "29ef141fa24ef86af35af48094928392543a9e7e7726ae92a9da322178e680ad",
+            "94fd6083b669533eebfa49a1cb47b94555e8be7d5f84573354b0201229d07bed",
+            "8f61d7be3b4f2252810fbade3bbac970ccff55c453e34405836545f3e49be6f5",
+            "9bb749be705747d9c28168c0446d589b3ac18949fa0087e230805aaff5a9982f",
+        ],
+        [
+            "a1a7a42155f0abff0353a6008eda2a9b16d9ffcf7584a38933cce3e3976987cd",
+            "96e9dc7a96a19c9ebaeb33ab94e7e9d86d88df1c1b11006b297b74f529f37f5a",
+            "36c39958ddcad401d85d63883da510505650321ad7a26859e8b1b6c28204d274",
+            "8157e47f9998f4afdce72a328eb9e897a57a5819b838ed1b517ea2c938e0c94f",
+            "f76929f6dba6d75bec713a02677aa7ad39dd4319077bfa7189fe65fe86b2ee9a",
+            "db459ca84da12ebab294b31961838c43cee1868f0690d143c93da1f2f825d07f",
+            "39339ac52a1194790b1bb5db0b119d403a1d5dcc4db4f8819fca4d425d5b2614",
+            "6b92c8c269319192298307feb26a7b64fb78d877ac2e49a594650227f26e64bc",
+            "cf375f037e253ab6f52699fbba73f796ee2140e546710a1faa3c9f09b4f570ac",
+            "363d5dcbc765854e830dc52762e24f71d7c85f6095227551f3ef6ada6aa25964",
+            "29ef141fa24ef86af35af48094928392543a9e7e7726ae92a9da322178e680ad",
+            "0a574b0aca86da38dd7aeb58d92550dc558c680deaa63c69e31e9a78e88a3559",
+            "ed0bd4d707ab3deaf18437ae9d945da2d3f2c6e758068ce57972d676da2a24bf",
+            "690c312586bbdf123d9e34ad7955e1c2ae5259cd3effd0b08b19cb556d65ec25",
+            "39f6ba6d816695f20212042b1048301cd637161f685d7c2b61379b907b7b4c59",
+            "cd7684b7c32531b363784d86bee71731c113c545c67103ec1265c362de7e5555",
+        ],
+    ];
+
+    struct Ring {
+        keys: Vec<EdwardsPoint>,
+        commitments: Vec<EdwardsPoint>,
+        pseudo_out: EdwardsPoint,
+        p: Scalar,
+        z: Scalar,
+    }
+
+    fn ring(n: usize, index: usize) -> Ring {
+        let rand_point = || Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT;
+        let p = Scalar::random(&mut OsRng);
+        let in_mask = Scalar::random(&mut OsRng);
+        let pseudo_mask = Scalar::random(&mut OsRng);
+
+        let mut keys: Vec<_> = (0..n).map(|_| rand_point()).collect();
+        let mut commitments: Vec<_> = (0..n).map(|_| rand_point()).collect();
+        keys[index] = p * ED25519_BASEPOINT_POINT;
+        commitments[index] = commit(5_000, in_mask);
+        Ring {
+            keys,
+            commitments,
+            pseudo_out: commit(5_000, pseudo_mask),
+            p,
+            z: in_mask - pseudo_mask,
+        }
+    }
+
+    #[test]
+    fn test_clsag_sign_verify() {
+        for (n, index) in [(2, 1), (16, 0), (16, 9), (16, 15)] {
+            let r = ring(n, index);
+            let msg = [3u8; 32];
+            let sig = sign(
+                &msg,
+                &r.keys,
+                &r.commitments,
+                &r.pseudo_out,
+                &r.p,
+                &r.z,
+                index,
+            )
+            .unwrap();
+            assert!(verify(&msg, &r.keys, &r.commitments, &r.pseudo_out, &sig));
+            assert!(!verify(
+                &[4u8; 32],
+                &r.keys,
+                &r.commitments,
+                &r.pseudo_out,
+                &sig
+            ));
+            assert_eq!(
+                sig.key_image,
+                crate::crypto::cn::generate_key_image(&r.p, &r.keys[index])
+            );
+        }
+    }
+
+    #[test]
+    fn test_clsag_rejects_tampering() {
+        let r = ring(11, 3);
+        let msg = [0u8; 32];
+        let sig = sign(&msg, &r.keys, &r.commitments, &r.pseudo_out, &r.p, &r.z, 3).unwrap();
+
+        let mut bad = sig.clone();
+        bad.s[7] += Scalar::ONE;
+        assert!(!verify(&msg, &r.keys, &r.commitments, &r.pseudo_out, &bad));
+
+        let mut bad = sig.clone();
+        bad.d = EdwardsPoint::default();
+        assert!(!verify(&msg, &r.keys, &r.commitments, &r.pseudo_out, &bad));
+
+        let mut keys = r.keys.clone();
+        keys.swap(0, 1);
+        assert!(!verify(&msg, &keys, &r.commitments, &r.pseudo_out, &sig));
+
+        // Pseudo-out committing to a different amount
+        let wrong = commit(5_001, Scalar::ONE);
+        assert!(!verify(&msg, &r.keys, &r.commitments, &wrong, &sig));
+        assert_eq!(
+            sign(&msg, &r.keys, &r.commitments, &wrong, &r.p, &r.z, 3),
+            Err(RingError::KeyMismatch)
+        );
+
+        let r = ring(1, 0);
+        assert_eq!(
+            sign(&msg, &r.keys, &r.commitments, &r.pseudo_out, &r.p, &r.z, 0),
+            Err(RingError::RingTooSmall)
+        );
+        let mut single = sig.clone();
+        single.s.truncate(1);
+        assert!(!verify(
+            &msg,
+            &r.keys,
+            &r.commitments,
+            &r.pseudo_out,
+            &single
+        ));
+    }
+
+    #[test]
+    fn test_wallet_tx() {
+        let tx = Transaction::from_hex(WALLET_TX).unwrap();
+        assert_eq!(
+            hex::encode(tx.hash().as_bytes()),
+            "efd109f6cec3530a98c5d87d5058ed87fd616d8afdcf6655a11ac8a6b56ab27e"
+        );
+        let rct = &tx.rct_signatures;
+        let message = rct.signature_message(&tx.prefix.prefix_hash());
+        let point = |h: &str| {
+            CompressedEdwardsY(hex::decode(h).unwrap().try_into().unwrap())
+                .decompress()
+                .unwrap()
+        };
+        let keys: Vec<Vec<_>> = RING_KEYS
+            .iter()
+            .map(|ring| ring.iter().map(|k| point(k)).collect())
+            .collect();
+        assert_eq!(rct.prunable.clsags.len(), 2);
+        for (i, sig) in rct.prunable.clsags.iter().enumerate() {
+            let commitments: Vec<_> = RING_COMMITMENTS[i].iter().map(|c| point(c)).collect();
+            let pseudo_out = rct.pseudo_outs()[i].decompress().unwrap();
+            assert!(verify(&message, &keys[i], &commitments, &pseudo_out, sig));
+
+            let mut bad = sig.clone();
+            bad.c1 += Scalar::ONE;
+            assert!(!verify(&message, &keys[i], &commitments, &pseudo_out, &bad));
+            // the other input's ring
+            assert!(!verify(
+                &message,
+                &keys[1 - i],
+                &commitments,
+                &pseudo_out,
+                sig
+            ));
+        }
+    }
+}
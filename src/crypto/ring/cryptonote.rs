@@ -0,0 +1,148 @@
+// src/crypto/ring/cryptonote.rs
+//! The original CryptoNote ring signature, `generate_ring_signature` /
+//! `check_ring_signature`.
+//!
+//! Pre-RingCT inputs were signed with it, and wallets still use it to
+//! prove key images and spends. Every member gets a challenge and a
+//! response `(c_i, r_i)`, and the challenges must sum to
+//! `Hs(prefix_hash || L_0 || R_0 || ...)`. A ring of one is allowed.
+use curve25519_dalek::{EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT};
+use rand::rngs::OsRng;
+
+use super::RingError;
+use crate::crypto::cn::{KeyImage, hash_to_point, hash_to_scalar};
+
+/// One `(c, r)` pair per ring member
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RingSignature(pub Vec<(Scalar, Scalar)>);
+
+impl RingSignature {
+    /// `c || r` per member, as monerod stores it
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(64 * self.0.len());
+        for (c, r) in &self.0 {
+            out.extend_from_slice(c.as_bytes());
+            out.extend_from_slice(r.as_bytes());
+        }
+        out
+    }
+
+    /// None unless `data` holds whole pairs of canonical scalars
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if !data.len().is_multiple_of(64) {
+            return None;
+        }
+        let scalar = |b: &[u8]| Option::from(Scalar::from_canonical_bytes(b.try_into().unwrap()));
+        data.chunks(64)
+            .map(|pair| Some((scalar(&pair[..32])?, scalar(&pair[32..])?)))
+            .collect::<Option<_>>()
+            .map(Self)
+    }
+}
+
+/// Sign `prefix_hash` with `secret`, whose key sits at `ring[index]` and
+/// whose key image is `key_image`
+pub fn sign(
+    prefix_hash: &[u8; 32],
+    key_image: &KeyImage,
+    ring: &[EdwardsPoint],
+    secret: &Scalar,
+    index: usize,
+) -> Result<RingSignature, RingError> {
+    if index >= ring.len() {
+        return Err(RingError::IndexOutOfRange);
+    }
+    if secret * ED25519_BASEPOINT_POINT != ring[index] {
+        return Err(RingError::KeyMismatch);
+    }
+    let image = key_image.point().ok_or(RingError::KeyMismatch)?;
+
+    let mut buf = prefix_hash.to_vec();
+    let mut pairs = vec![(Scalar::ZERO, Scalar::ZERO); ring.len()];
+    let mut sum = Scalar::ZERO;
+    let k = Scalar::random(&mut OsRng);
+    for (i, key) in ring.iter().enumerate() {
+        let hp = hash_to_point(key.compress().as_bytes());
+        let (l, r) = if i == index {
+            (k * ED25519_BASEPOINT_POINT, k * hp)
+        } else {
+            let c = Scalar::random(&mut OsRng);
+            let r = Scalar::random(&mut OsRng);
+            pairs[i] = (c, r);
+            sum += c;
+            (
+                EdwardsPoint::vartime_double_scalar_mul_basepoint(&c, key, &r),
+                r * hp + c * image,
+            )
+        };
+        buf.extend_from_slice(l.compress().as_bytes());
+        buf.extend_from_slice(r.compress().as_bytes());
+    }
+    let c = hash_to_scalar(&buf) - sum;
+    pairs[index] = (c, k - c * secret);
+    Ok(RingSignature(pairs))
+}
+
+/// Check that a member of `ring` with key image `key_image` signed
+/// `prefix_hash`
+pub fn verify(
+    prefix_hash: &[u8; 32],
+    key_image: &KeyImage,
+    ring: &[EdwardsPoint],
+    signature: &RingSignature,
+) -> bool {
+    if ring.is_empty() || signature.0.len() != ring.len() {
+        return false;
+    }
+    let Some(image) = key_image.point() else {
+        return false;
+    };
+    let mut buf = prefix_hash.to_vec();
+    let mut sum = Scalar::ZERO;
+    for (key, (c, r)) in ring.iter().zip(&signature.0) {
+        let hp = hash_to_point(key.compress().as_bytes());
+        let l = EdwardsPoint::vartime_double_scalar_mul_basepoint(c, key, r);
+        let r = r * hp + c * image;
+        buf.extend_from_slice(l.compress().as_bytes());
+        buf.extend_from_slice(r.compress().as_bytes());
+        sum += c;
+    }
+    hash_to_scalar(&buf) == sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::cn::generate_key_image;
+
+    #[test]
+    fn test_ring_signature() {
+        let x = Scalar::random(&mut OsRng);
+        let mut ring: Vec<EdwardsPoint> = (0..7)
+            .map(|_| Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT)
+            .collect();
+        ring[3] = x * ED25519_BASEPOINT_POINT;
+        let image = generate_key_image(&x, &ring[3]);
+        let hash = [9u8; 32];
+
+        let sig = sign(&hash, &image, &ring, &x, 3).unwrap();
+        assert!(verify(&hash, &image, &ring, &sig));
+        assert!(!verify(&[8u8; 32], &image, &ring, &sig));
+        let other = generate_key_image(&x, &ring[2]);
+        assert!(!verify(&hash, &other, &ring, &sig));
+        assert_eq!(RingSignature::from_bytes(&sig.to_bytes()), Some(sig));
+        assert_eq!(RingSignature::from_bytes(&[0u8; 63]), None);
+
+        // a ring of one proves a key image
+        let single = sign(&hash, &image, &ring[3..4], &x, 0).unwrap();
+        assert!(verify(&hash, &image, &ring[3..4], &single));
+        assert_eq!(
+            sign(&hash, &image, &ring, &x, 2),
+            Err(RingError::KeyMismatch)
+        );
+        assert_eq!(
+            sign(&hash, &image, &[], &x, 0),
+            Err(RingError::IndexOutOfRange)
+        );
+    }
+}
@@ -0,0 +1,156 @@
+// src/crypto/ring/lsag.rs
+//! Plain LSAG ring signatures over arbitrary messages.
+//!
+//! Not a consensus format: a single-layer linkable ring signature for
+//! experimenting with rings of ordinary `Ed25519Keypair` keys. Two
+//! signatures by the same key share a key image and can be linked,
+//! whichever ring or message they were made over.
+use curve25519_dalek::{EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT};
+use ed25519_dalek::VerifyingKey;
+use rand::rngs::OsRng;
+
+use super::RingError;
+use crate::crypto::cn::{KeyImage, hash_to_point, hash_to_scalar};
+use crate::crypto::signature::Ed25519Keypair;
+
+const DOMAIN: &[u8] = b"monero_rust LSAG";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LsagSignature {
+    pub c0: Scalar,
+    pub s: Vec<Scalar>,
+    pub key_image: KeyImage,
+}
+
+impl LsagSignature {
+    /// True if both signatures were made with the same secret key
+    pub fn is_linked(&self, other: &LsagSignature) -> bool {
+        self.key_image == other.key_image
+    }
+}
+
+/// c = Hs(domain || ring || message || L || R)
+fn challenge(prefix: &[u8], l: &EdwardsPoint, r: &EdwardsPoint) -> Scalar {
+    let mut buf = prefix.to_vec();
+    buf.extend_from_slice(l.compress().as_bytes());
+    buf.extend_from_slice(r.compress().as_bytes());
+    hash_to_scalar(&buf)
+}
+
+fn prefix(ring: &[VerifyingKey], message: &[u8]) -> Vec<u8> {
+    let mut buf = DOMAIN.to_vec();
+    buf.extend_from_slice(&(ring.len() as u64).to_le_bytes());
+    for key in ring {
+        buf.extend_from_slice(key.as_bytes());
+    }
+    buf.extend_from_slice(message);
+    buf
+}
+
+/// Sign `message` as `signer`, who must sit at `ring[index]`
+pub fn sign(
+    message: &[u8],
+    ring: &[VerifyingKey],
+    signer: &Ed25519Keypair,
+    index: usize,
+) -> Result<LsagSignature, RingError> {
+    let n = ring.len();
+    if n < 2 {
+        return Err(RingError::RingTooSmall);
+    }
+    if index >= n {
+        return Err(RingError::IndexOutOfRange);
+    }
+    if ring[index] != signer.public {
+        return Err(RingError::KeyMismatch);
+    }
+
+    let x = signer.signing_key.to_scalar();
+    let points: Vec<EdwardsPoint> = ring.iter().map(|k| k.to_edwards()).collect();
+    let hp: Vec<EdwardsPoint> = ring.iter().map(|k| hash_to_point(k.as_bytes())).collect();
+    let image = x * hp[index];
+    let prefix = prefix(ring, message);
+
+    let alpha = Scalar::random(&mut OsRng);
+    let mut c = vec![Scalar::ZERO; n];
+    let mut s = vec![Scalar::ZERO; n];
+    c[(index + 1) % n] = challenge(
+        &prefix,
+        &(alpha * ED25519_BASEPOINT_POINT),
+        &(alpha * hp[index]),
+    );
+
+    let mut i = (index + 1) % n;
+    while i != index {
+        s[i] = Scalar::random(&mut OsRng);
+        let l = EdwardsPoint::vartime_double_scalar_mul_basepoint(&c[i], &points[i], &s[i]);
+        let r = s[i] * hp[i] + c[i] * image;
+        c[(i + 1) % n] = challenge(&prefix, &l, &r);
+        i = (i + 1) % n;
+    }
+    s[index] = alpha - c[index] * x;
+
+    Ok(LsagSignature {
+        c0: c[0],
+        s,
+        key_image: KeyImage(image.compress().to_bytes()),
+    })
+}
+
+/// Verify that one of `ring` signed `message`
+pub fn verify(message: &[u8], ring: &[VerifyingKey], sig: &LsagSignature) -> bool {
+    let n = ring.len();
+    if n < 2 || sig.s.len() != n {
+        return false;
+    }
+    let Some(image) = sig.key_image.point() else {
+        return false;
+    };
+    let prefix = prefix(ring, message);
+
+    let mut c = sig.c0;
+    for (key, s) in ring.iter().zip(&sig.s) {
+        let l = EdwardsPoint::vartime_double_scalar_mul_basepoint(&c, &key.to_edwards(), s);
+        let r = s * hash_to_point(key.as_bytes()) + c * image;
+        c = challenge(&prefix, &l, &r);
+    }
+    c == sig.c0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lsag_sign_verify_and_link() {
+        let members: Vec<Ed25519Keypair> = (0..5).map(|_| Ed25519Keypair::generate()).collect();
+        let ring: Vec<VerifyingKey> = members.iter().map(|k| k.public).collect();
+
+        let sig = sign(b"vote: yes", &ring, &members[2], 2).unwrap();
+        assert!(verify(b"vote: yes", &ring, &sig));
+        assert!(!verify(b"vote: no", &ring, &sig));
+
+        // Same signer, different message and ring order -> linkable
+        let mut shuffled = ring.clone();
+        shuffled.rotate_left(1);
+        let again = sign(b"vote: no", &shuffled, &members[2], 1).unwrap();
+        assert!(verify(b"vote: no", &shuffled, &again));
+        assert!(sig.is_linked(&again));
+
+        let other = sign(b"vote: yes", &ring, &members[0], 0).unwrap();
+        assert!(!sig.is_linked(&other));
+    }
+
+    #[test]
+    fn test_lsag_wrong_signer() {
+        let members: Vec<Ed25519Keypair> = (0..3).map(|_| Ed25519Keypair::generate()).collect();
+        let ring: Vec<VerifyingKey> = members.iter().map(|k| k.public).collect();
+        let outsider = Ed25519Keypair::generate();
+
+        assert_eq!(sign(b"m", &ring, &outsider, 0), Err(RingError::KeyMismatch));
+        assert_eq!(
+            sign(b"m", &ring[..1], &members[0], 0),
+            Err(RingError::RingTooSmall)
+        );
+    }
+}
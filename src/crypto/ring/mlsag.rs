@@ -0,0 +1,269 @@
+// src/crypto/ring/mlsag.rs
+//! MLSAG (multilayered linkable spontaneous anonymous group) signatures,
+//! used by RingCT before CLSAG (v10). Port of `MLSAG_Gen` / `MLSAG_Ver`.
+//!
+//! The ring is a matrix `pk[column][row]`: every column is one ring member,
+//! every row one key the signer proves knowledge of. The first `ds_rows`
+//! rows are "double-spend" rows and get a key image; the rest (e.g. the
+//! commitment row) are proven without one.
+use curve25519_dalek::{
+    EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT, traits::IsIdentity,
+};
+use rand::rngs::OsRng;
+
+use super::RingError;
+use crate::crypto::cn::{KeyImage, hash_to_point, hash_to_scalar};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MlsagSignature {
+    /// Responses, `ss[column][row]`
+    pub ss: Vec<Vec<Scalar>>,
+    /// Challenge at column 0
+    pub cc: Scalar,
+    /// One key image per double-spend row
+    pub key_images: Vec<KeyImage>,
+}
+
+/// Hash state for one column: message || per-row (P, L[, R])
+struct Transcript {
+    buf: Vec<u8>,
+}
+
+impl Transcript {
+    fn new(message: &[u8; 32]) -> Self {
+        Transcript {
+            buf: message.to_vec(),
+        }
+    }
+
+    fn push(&mut self, p: &EdwardsPoint) {
+        self.buf.extend_from_slice(p.compress().as_bytes());
+    }
+
+    fn challenge(self) -> Scalar {
+        hash_to_scalar(&self.buf)
+    }
+}
+
+fn check_matrix(pk: &[Vec<EdwardsPoint>], ds_rows: usize) -> Result<usize, RingError> {
+    if pk.len() < 2 {
+        return Err(RingError::RingTooSmall);
+    }
+    let rows = pk[0].len();
+    if rows == 0 || ds_rows > rows || pk.iter().any(|col| col.len() != rows) {
+        return Err(RingError::DimensionMismatch);
+    }
+    Ok(rows)
+}
+
+/// Sign `message` with secrets `xx` (one per row) for column `index` of `pk`
+pub fn sign(
+    message: &[u8; 32],
+    pk: &[Vec<EdwardsPoint>],
+    xx: &[Scalar],
+    index: usize,
+    ds_rows: usize,
+) -> Result<MlsagSignature, RingError> {
+    let rows = check_matrix(pk, ds_rows)?;
+    let cols = pk.len();
+    if index >= cols {
+        return Err(RingError::IndexOutOfRange);
+    }
+    if xx.len() != rows {
+        return Err(RingError::DimensionMismatch);
+    }
+    if (0..rows).any(|j| xx[j] * ED25519_BASEPOINT_POINT != pk[index][j]) {
+        return Err(RingError::KeyMismatch);
+    }
+
+    let hp: Vec<EdwardsPoint> = (0..ds_rows)
+        .map(|j| hash_to_point(pk[index][j].compress().as_bytes()))
+        .collect();
+    let images: Vec<EdwardsPoint> = (0..ds_rows).map(|j| xx[j] * hp[j]).collect();
+    let alpha: Vec<Scalar> = (0..rows).map(|_| Scalar::random(&mut OsRng)).collect();
+
+    let mut t = Transcript::new(message);
+    for j in 0..rows {
+        t.push(&pk[index][j]);
+        t.push(&(alpha[j] * ED25519_BASEPOINT_POINT));
+        if j < ds_rows {
+            t.push(&(alpha[j] * hp[j]));
+        }
+    }
+    let mut c_old = t.challenge();
+
+    let mut ss = vec![vec![Scalar::ZERO; rows]; cols];
+    let mut cc = Scalar::ZERO;
+    let mut i = (index + 1) % cols;
+    if i == 0 {
+        cc = c_old;
+    }
+    while i != index {
+        ss[i] = (0..rows).map(|_| Scalar::random(&mut OsRng)).collect();
+        c_old = column_challenge(message, &pk[i], &ss[i], &c_old, &images, ds_rows);
+        i = (i + 1) % cols;
+        if i == 0 {
+            cc = c_old;
+        }
+    }
+    for j in 0..rows {
+        ss[index][j] = alpha[j] - c_old * xx[j];
+    }
+
+    Ok(MlsagSignature {
+        ss,
+        cc,
+        key_images: images
+            .iter()
+            .map(|p| KeyImage(p.compress().to_bytes()))
+            .collect(),
+    })
+}
+
+/// Recompute L (and R for double-spend rows) for one column and hash them
+fn column_challenge(
+    message: &[u8; 32],
+    column: &[EdwardsPoint],
+    ss: &[Scalar],
+    c: &Scalar,
+    images: &[EdwardsPoint],
+    ds_rows: usize,
+) -> Scalar {
+    let mut t = Transcript::new(message);
+    for (j, p) in column.iter().enumerate() {
+        t.push(p);
+        t.push(&EdwardsPoint::vartime_double_scalar_mul_basepoint(
+            c, p, &ss[j],
+        ));
+        if j < ds_rows {
+            let hi = hash_to_point(p.compress().as_bytes());
+            t.push(&(ss[j] * hi + c * images[j]));
+        }
+    }
+    t.challenge()
+}
+
+/// Verify an MLSAG over the ring matrix `pk`
+pub fn verify(
+    message: &[u8; 32],
+    pk: &[Vec<EdwardsPoint>],
+    sig: &MlsagSignature,
+    ds_rows: usize,
+) -> bool {
+    let Ok(rows) = check_matrix(pk, ds_rows) else {
+        return false;
+    };
+    if sig.key_images.len() != ds_rows
+        || sig.ss.len() != pk.len()
+        || sig.ss.iter().any(|col| col.len() != rows)
+    {
+        return false;
+    }
+    let mut images = Vec::with_capacity(ds_rows);
+    for ki in &sig.key_images {
+        match ki.point() {
+            Some(p) if !p.is_identity() => images.push(p),
+            _ => return false,
+        }
+    }
+
+    let mut c = sig.cc;
+    for (column, ss) in pk.iter().zip(&sig.ss) {
+        c = column_challenge(message, column, ss, &c, &images, ds_rows);
+        if c == Scalar::ZERO {
+            return false;
+        }
+    }
+    c == sig.cc
+}
+
+/// Build the RingCT "mixed" matrix: row 0 the one-time keys, row 1
+/// `C_i - pseudo_out` so the signer also proves the amounts balance.
+pub fn ring_ct_matrix(
+    keys: &[EdwardsPoint],
+    commitments: &[EdwardsPoint],
+    pseudo_out: &EdwardsPoint,
+) -> Result<Vec<Vec<EdwardsPoint>>, RingError> {
+    if keys.len() != commitments.len() {
+        return Err(RingError::DimensionMismatch);
+    }
+    Ok(keys
+        .iter()
+        .zip(commitments)
+        .map(|(p, c)| vec![*p, c - pseudo_out])
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::commitment::commit;
+
+    fn random_point() -> EdwardsPoint {
+        Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT
+    }
+
+    #[test]
+    fn test_mlsag_single_row() {
+        let x = Scalar::random(&mut OsRng);
+        let mut pk: Vec<Vec<EdwardsPoint>> = (0..11).map(|_| vec![random_point()]).collect();
+        pk[4][0] = x * ED25519_BASEPOINT_POINT;
+        let msg = [7u8; 32];
+
+        let sig = sign(&msg, &pk, &[x], 4, 1).unwrap();
+        assert!(verify(&msg, &pk, &sig, 1));
+        assert!(!verify(&[8u8; 32], &pk, &sig, 1));
+
+        // Key image is the usual x * Hp(P)
+        let expected = crate::crypto::cn::generate_key_image(&x, &pk[4][0]);
+        assert_eq!(sig.key_images[0], expected);
+    }
+
+    #[test]
+    fn test_mlsag_ringct_commitment_row() {
+        let ring_size = 5;
+        let index = 0;
+        let x = Scalar::random(&mut OsRng);
+        let in_mask = Scalar::random(&mut OsRng);
+        let pseudo_mask = Scalar::random(&mut OsRng);
+
+        let mut keys: Vec<EdwardsPoint> = (0..ring_size).map(|_| random_point()).collect();
+        let mut commitments: Vec<EdwardsPoint> = (0..ring_size).map(|_| random_point()).collect();
+        keys[index] = x * ED25519_BASEPOINT_POINT;
+        commitments[index] = commit(1000, in_mask);
+        let pseudo_out = commit(1000, pseudo_mask);
+
+        let pk = ring_ct_matrix(&keys, &commitments, &pseudo_out).unwrap();
+        let msg = [1u8; 32];
+        let sig = sign(&msg, &pk, &[x, in_mask - pseudo_mask], index, 1).unwrap();
+        assert!(verify(&msg, &pk, &sig, 1));
+
+        // A pseudo-out with a different amount can't be signed for
+        let wrong = ring_ct_matrix(&keys, &commitments, &commit(999, pseudo_mask)).unwrap();
+        assert_eq!(
+            sign(&msg, &wrong, &[x, in_mask - pseudo_mask], index, 1),
+            Err(RingError::KeyMismatch)
+        );
+        assert!(!verify(&msg, &wrong, &sig, 1));
+    }
+
+    #[test]
+    fn test_mlsag_rejects_bad_shapes() {
+        let x = Scalar::random(&mut OsRng);
+        let pk = vec![vec![x * ED25519_BASEPOINT_POINT]];
+        assert_eq!(
+            sign(&[0; 32], &pk, &[x], 0, 1),
+            Err(RingError::RingTooSmall)
+        );
+
+        let pk = vec![vec![x * ED25519_BASEPOINT_POINT], vec![random_point()]];
+        assert_eq!(
+            sign(&[0; 32], &pk, &[x], 2, 1),
+            Err(RingError::IndexOutOfRange)
+        );
+        assert_eq!(
+            sign(&[0; 32], &pk, &[x], 0, 2),
+            Err(RingError::DimensionMismatch)
+        );
+    }
+}
@@ -0,0 +1,35 @@
+// src/crypto/ring/mod.rs
+// Linkable ring signatures (CryptoNote, LSAG, MLSAG, CLSAG)
+use std::fmt;
+
+pub mod batch;
+pub mod clsag;
+pub mod cryptonote;
+pub mod lsag;
+pub mod mlsag;
+
+/// Why a ring signature could not be produced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RingError {
+    /// Need at least two ring members
+    RingTooSmall,
+    /// Signer index is outside the ring
+    IndexOutOfRange,
+    /// Ring rows, secrets or key images have inconsistent sizes
+    DimensionMismatch,
+    /// The secret keys don't match the public keys at the signer index
+    KeyMismatch,
+}
+
+impl fmt::Display for RingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RingError::RingTooSmall => write!(f, "ring needs at least two members"),
+            RingError::IndexOutOfRange => write!(f, "signer index out of range"),
+            RingError::DimensionMismatch => write!(f, "ring dimensions don't match"),
+            RingError::KeyMismatch => write!(f, "secret key doesn't match the ring"),
+        }
+    }
+}
+
+impl std::error::Error for RingError {}
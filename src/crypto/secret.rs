@@ -0,0 +1,238 @@
+// src/crypto/secret.rs
+// Secret buffers that wipe themselves and stay out of swap
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use rand::{RngCore, rngs::OsRng};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+
+/// `N` secret bytes, zeroized on drop and redacted in `Debug`
+///
+/// Returned wherever this crate hands out raw key material (seeds, mnemonic
+/// keys) so the copy the caller holds is wiped too. Read it with
+/// [`SecretBytes::as_bytes`]; anything copied out of it is the caller's to
+/// clean up.
+#[derive(Clone)]
+pub struct SecretBytes<const N: usize>([u8; N]);
+
+impl<const N: usize> SecretBytes<N> {
+    pub fn new(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+
+    /// Fresh bytes from the OS RNG
+    pub fn random() -> Self {
+        let mut bytes = [0u8; N];
+        OsRng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    pub fn as_mut_bytes(&mut self) -> &mut [u8; N] {
+        &mut self.0
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for SecretBytes<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for SecretBytes<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> ConstantTimeEq for SecretBytes<N> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl<const N: usize> PartialEq for SecretBytes<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const N: usize> Eq for SecretBytes<N> {}
+
+impl<const N: usize> fmt::Debug for SecretBytes<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes<{N}>(..)")
+    }
+}
+
+impl<const N: usize> Zeroize for SecretBytes<N> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<const N: usize> Drop for SecretBytes<N> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Heap cell whose memory is pinned in RAM (`mlock`/`VirtualLock`)
+///
+/// Keeps key material out of swap and hibernation files, and overwrites it
+/// with zeros after dropping the value. Locking is best effort: it fails
+/// when the process exceeds its locked-memory limit (`RLIMIT_MEMLOCK`), in
+/// which case the buffer still works and [`SecureBuffer::is_locked`] says
+/// so. The value passed to [`SecureBuffer::new`] is moved into place, so
+/// any copy the caller had on the stack is not covered.
+pub struct SecureBuffer<T> {
+    ptr: NonNull<T>,
+    locked: bool,
+}
+
+// Owns its T exclusively, like a Box
+unsafe impl<T: Send> Send for SecureBuffer<T> {}
+unsafe impl<T: Sync> Sync for SecureBuffer<T> {}
+
+impl<T> SecureBuffer<T> {
+    pub fn new(value: T) -> Self {
+        let ptr = NonNull::from(Box::leak(Box::new(value)));
+        let size = std::mem::size_of::<T>();
+        // SAFETY: ptr points at a live allocation of `size` bytes
+        let locked = size > 0 && unsafe { sys::lock(ptr.as_ptr().cast(), size) };
+        Self { ptr, locked }
+    }
+
+    /// True if the OS agreed to keep the memory resident
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl<T: Clone> Clone for SecureBuffer<T> {
+    fn clone(&self) -> Self {
+        Self::new((**self).clone())
+    }
+}
+
+impl<T> Deref for SecureBuffer<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: ptr is valid and uniquely owned until Drop
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> DerefMut for SecureBuffer<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: as above, and &mut self guarantees exclusivity
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T> fmt::Debug for SecureBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecureBuffer(..)")
+    }
+}
+
+impl<T> Drop for SecureBuffer<T> {
+    fn drop(&mut self) {
+        let size = std::mem::size_of::<T>();
+        let raw = self.ptr.as_ptr();
+        // SAFETY: the value is dropped exactly once, then its bytes are
+        // overwritten and the allocation is freed without dropping again.
+        unsafe {
+            std::ptr::drop_in_place(raw);
+            let bytes = std::slice::from_raw_parts_mut(raw.cast::<u8>(), size);
+            bytes.zeroize();
+            if self.locked {
+                sys::unlock(raw.cast(), size);
+            }
+            drop(Box::from_raw(raw.cast::<std::mem::ManuallyDrop<T>>()));
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    pub unsafe fn lock(addr: *mut u8, len: usize) -> bool {
+        unsafe { libc::mlock(addr.cast(), len) == 0 }
+    }
+
+    pub unsafe fn unlock(addr: *mut u8, len: usize) {
+        unsafe {
+            libc::munlock(addr.cast(), len);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::c_void;
+
+    unsafe extern "system" {
+        fn VirtualLock(addr: *mut c_void, len: usize) -> i32;
+        fn VirtualUnlock(addr: *mut c_void, len: usize) -> i32;
+    }
+
+    pub unsafe fn lock(addr: *mut u8, len: usize) -> bool {
+        unsafe { VirtualLock(addr.cast(), len) != 0 }
+    }
+
+    pub unsafe fn unlock(addr: *mut u8, len: usize) {
+        unsafe {
+            VirtualUnlock(addr.cast(), len);
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    pub unsafe fn lock(_addr: *mut u8, _len: usize) -> bool {
+        false
+    }
+
+    pub unsafe fn unlock(_addr: *mut u8, _len: usize) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_bytes() {
+        let mut secret = SecretBytes::new([0xabu8; 32]);
+        assert_eq!(format!("{secret:?}"), "SecretBytes<32>(..)");
+        assert_eq!(secret.as_bytes(), &[0xab; 32]);
+        secret.zeroize();
+        assert_eq!(secret.as_bytes(), &[0; 32]);
+        assert_ne!(SecretBytes::<32>::random().as_bytes(), &[0; 32]);
+        assert_eq!(secret, SecretBytes::new([0; 32]));
+        assert!(!bool::from(secret.ct_eq(&SecretBytes::new([1; 32]))));
+    }
+
+    #[test]
+    fn test_secure_buffer() {
+        let mut buf = SecureBuffer::new([7u8; 64]);
+        buf[0] = 1;
+        assert_eq!(buf[..2], [1, 7]);
+        let copy = buf.clone();
+        assert_eq!(*copy, *buf);
+        assert_eq!(format!("{buf:?}"), "SecureBuffer(..)");
+
+        // drops run once, zero-sized values are fine
+        let counted = std::rc::Rc::new(());
+        drop(SecureBuffer::new(counted.clone()));
+        assert_eq!(std::rc::Rc::strong_count(&counted), 1);
+        let _ = SecureBuffer::new(());
+    }
+}
@@ -0,0 +1,247 @@
+// src/crypto/serde_hex.rs
+//! Serde support for fixed-size byte types (`serde` feature).
+//!
+//! Human-readable formats (JSON, TOML, ...) get lowercase hex strings, binary
+//! formats get raw bytes. [`Hash`](super::hash::Hash), [`KeyImage`],
+//! [`Signature`] and [`SchnorrSignature`] implement the traits directly; the
+//! dalek `VerifyingKey` behind
+//! [`Ed25519Keypair`](super::signature::Ed25519Keypair) is foreign, so it
+//! goes through the [`verifying_key`] module instead:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Signed {
+//!     #[serde(with = "monero_rust::crypto::serde_hex::verifying_key")]
+//!     key: VerifyingKey,
+//!     sig: Signature,
+//! }
+//! ```
+use std::fmt;
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, SeqAccess, Visitor},
+};
+
+use super::{
+    cn::KeyImage,
+    hash::Hash,
+    signature::{Signature, schnorr::SchnorrSignature},
+};
+
+pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&hex::encode(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(ArrayVisitor::<N>)
+    } else {
+        deserializer.deserialize_bytes(ArrayVisitor::<N>)
+    }
+}
+
+struct ArrayVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for ArrayVisitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{N} bytes or {} hex characters", 2 * N)
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        let mut out = [0u8; N];
+        hex::decode_to_slice(s, &mut out).map_err(E::custom)?;
+        Ok(out)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        bytes
+            .try_into()
+            .map_err(|_| E::invalid_length(bytes.len(), &self))
+    }
+
+    // formats without a native byte string send a sequence of u8
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut out = [0u8; N];
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(de::Error::invalid_length(N + 1, &self));
+        }
+        Ok(out)
+    }
+}
+
+impl<const N: usize> Serialize for Hash<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for Hash<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Hash)
+    }
+}
+
+impl Serialize for KeyImage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyImage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(KeyImage)
+    }
+}
+
+impl Serialize for SchnorrSignature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.to_bytes(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SchnorrSignature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize(deserializer)?;
+        SchnorrSignature::from_bytes(&bytes)
+            .ok_or_else(|| de::Error::custom("signature scalar is not canonical"))
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.to_bytes(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize(deserializer)?;
+        Signature::from_bytes(&bytes).map_err(de::Error::custom)
+    }
+}
+
+/// `#[serde(with = ...)]` for `ed25519_dalek::VerifyingKey`
+pub mod verifying_key {
+    use ed25519_dalek::VerifyingKey;
+    use serde::{Deserializer, Serializer, de};
+
+    pub fn serialize<S: Serializer>(key: &VerifyingKey, serializer: S) -> Result<S::Ok, S::Error> {
+        super::serialize(key.as_bytes(), serializer)
+    }
+
+    /// Rejects encodings that are not a curve point
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<VerifyingKey, D::Error> {
+        let bytes = super::deserialize(deserializer)?;
+        VerifyingKey::from_bytes(&bytes).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{hash::keccak256, signature::Ed25519Keypair};
+    use curve25519_dalek::Scalar;
+    use serde_test::{Configure, Token, assert_de_tokens_error, assert_tokens};
+
+    #[test]
+    fn test_hash_serde() {
+        let hash = keccak256(b"");
+        let hex = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+        assert_tokens(&hash.readable(), &[Token::Str(hex)]);
+        assert_tokens(&Hash([0xab; 32]).compact(), &[Token::Bytes(&[0xab; 32])]);
+        assert_tokens(&KeyImage([1; 32]).compact(), &[Token::Bytes(&[1; 32])]);
+
+        assert_de_tokens_error::<serde_test::Readable<Hash<32>>>(
+            &[Token::Str("abcd")],
+            "Invalid string length",
+        );
+        assert_de_tokens_error::<serde_test::Compact<Hash<32>>>(
+            &[Token::Bytes(&[0; 31])],
+            "invalid length 31, expected 32 bytes or 64 hex characters",
+        );
+    }
+
+    #[test]
+    fn test_signature_serde() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Signed {
+            #[serde(with = "verifying_key")]
+            key: ed25519_dalek::VerifyingKey,
+            sig: Signature,
+        }
+
+        // RFC 8032 section 7.1, test 1
+        let keypair = Ed25519Keypair::from_secret_hex(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        )
+        .unwrap();
+        let signed = Signed {
+            key: keypair.public,
+            sig: keypair.sign(b""),
+        };
+        assert_tokens(
+            &signed.readable(),
+            &[
+                Token::Struct {
+                    name: "Signed",
+                    len: 2,
+                },
+                Token::Str("key"),
+                Token::Str("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"),
+                Token::Str("sig"),
+                Token::Str(
+                    "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555\
+                     fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+                ),
+                Token::StructEnd,
+            ],
+        );
+        assert_de_tokens_error::<serde_test::Readable<Signed>>(
+            &[
+                Token::Struct {
+                    name: "Signed",
+                    len: 2,
+                },
+                Token::Str("key"),
+                Token::Str("0200000000000000000000000000000000000000000000000000000000000000"),
+            ],
+            "signature error: Cannot decompress Edwards point",
+        );
+
+        let schnorr = SchnorrSignature {
+            c: Scalar::from(3u8),
+            r: Scalar::from(3u8),
+        };
+        const BYTES: [u8; 64] = {
+            let mut b = [0u8; 64];
+            b[0] = 3;
+            b[32] = 3;
+            b
+        };
+        assert_tokens(&schnorr.compact(), &[Token::Bytes(&BYTES)]);
+        assert_de_tokens_error::<serde_test::Compact<SchnorrSignature>>(
+            &[Token::Bytes(&[0xff; 64])],
+            "signature scalar is not canonical",
+        );
+        assert_de_tokens_error::<serde_test::Compact<Signature>>(
+            &[Token::Bytes(&[0xff; 64])],
+            "signature scalar is not canonically encoded",
+        );
+    }
+}
@@ -0,0 +1,467 @@
+// src/crypto/signature/frost.rs
+//! FROST threshold Ed25519 signatures (RFC 9591, FROST(Ed25519, SHA-512)).
+//!
+//! A key is split into `max` Shamir shares of which any `min` can sign
+//! together. Signing takes two rounds: every participant publishes a
+//! [`SigningCommitments`], then answers the resulting [`SigningPackage`]
+//! with a [`SignatureShare`]. [`aggregate`] checks each share and outputs
+//! an ordinary Ed25519 signature that [`super::verify_signature`] accepts.
+//!
+//! Only trusted-dealer key generation is provided: whoever calls
+//! [`split`] or [`generate_with_dealer`] sees the whole secret once.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use curve25519_dalek::{EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT};
+use ed25519_dalek::VerifyingKey;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+use super::{Ed25519Keypair, Signature};
+
+const CONTEXT: &[u8] = b"FROST-ED25519-SHA512-v1";
+
+/// Participant identifier, 1..=max
+pub type Identifier = u16;
+
+/// Why a FROST operation failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrostError {
+    /// Need `2 <= min <= max`
+    InvalidParameters { min: u16, max: u16 },
+    /// Fewer commitments than the signing threshold
+    NotEnoughSigners,
+    /// Identifier is zero, repeated, or not part of the key
+    UnknownParticipant(Identifier),
+    /// Our own commitment is missing from the signing package
+    MissingCommitment,
+    /// This participant's signature share doesn't verify
+    InvalidShare(Identifier),
+}
+
+impl fmt::Display for FrostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrostError::InvalidParameters { min, max } => {
+                write!(f, "invalid {min}-of-{max} threshold")
+            }
+            FrostError::NotEnoughSigners => write!(f, "not enough signers"),
+            FrostError::UnknownParticipant(id) => write!(f, "unknown participant {id}"),
+            FrostError::MissingCommitment => write!(f, "own commitment not in signing package"),
+            FrostError::InvalidShare(id) => write!(f, "invalid signature share from {id}"),
+        }
+    }
+}
+
+impl std::error::Error for FrostError {}
+
+fn hash(parts: &[&[u8]]) -> Scalar {
+    let mut h = Sha512::new();
+    for part in parts {
+        h.update(part);
+    }
+    Scalar::from_bytes_mod_order_wide(&h.finalize().into())
+}
+
+/// H1..H5 from RFC 9591 section 6.5 prefix the context string and a tag
+fn hash_tagged(tag: &[u8], parts: &[&[u8]]) -> Scalar {
+    let mut all: Vec<&[u8]> = vec![CONTEXT, tag];
+    all.extend_from_slice(parts);
+    hash(&all)
+}
+
+fn to_scalar(id: Identifier) -> Scalar {
+    Scalar::from(id)
+}
+
+/// Lagrange coefficient of `id` at zero over `ids`
+fn lagrange(id: Identifier, ids: impl Iterator<Item = Identifier>) -> Scalar {
+    let x = to_scalar(id);
+    let (mut num, mut den) = (Scalar::ONE, Scalar::ONE);
+    for other in ids.filter(|&j| j != id) {
+        let xj = to_scalar(other);
+        num *= xj;
+        den *= xj - x;
+    }
+    num * den.invert()
+}
+
+/// One participant's secret share, wiped on drop
+#[derive(Clone)]
+pub struct KeyShare {
+    identifier: Identifier,
+    secret: Scalar,
+    min_signers: u16,
+    group_public: VerifyingKey,
+}
+
+impl KeyShare {
+    pub fn identifier(&self) -> Identifier {
+        self.identifier
+    }
+
+    pub fn min_signers(&self) -> u16 {
+        self.min_signers
+    }
+
+    pub fn group_public(&self) -> &VerifyingKey {
+        &self.group_public
+    }
+
+    /// `secret·G`, as listed in the [`PublicKeyPackage`]
+    pub fn verifying_share(&self) -> EdwardsPoint {
+        self.secret * ED25519_BASEPOINT_POINT
+    }
+}
+
+impl fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("identifier", &self.identifier)
+            .field("min_signers", &self.min_signers)
+            .field("secret", &format_args!("REDACTED"))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for KeyShare {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+/// Public data every signer and the aggregator need
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKeyPackage {
+    pub group_public: VerifyingKey,
+    pub verifying_shares: BTreeMap<Identifier, EdwardsPoint>,
+    pub min_signers: u16,
+}
+
+/// Split an existing keypair's signing scalar into `max` shares
+pub fn split(
+    keypair: &Ed25519Keypair,
+    min: u16,
+    max: u16,
+) -> Result<(Vec<KeyShare>, PublicKeyPackage), FrostError> {
+    let mut secret = keypair.signing_key.to_scalar();
+    let out = deal(&secret, keypair.public, min, max);
+    secret.zeroize();
+    out
+}
+
+/// Split a fresh random key into `max` shares
+pub fn generate_with_dealer(
+    min: u16,
+    max: u16,
+) -> Result<(Vec<KeyShare>, PublicKeyPackage), FrostError> {
+    let mut secret = Scalar::random(&mut OsRng);
+    let public = VerifyingKey::from(secret * ED25519_BASEPOINT_POINT);
+    let out = deal(&secret, public, min, max);
+    secret.zeroize();
+    out
+}
+
+fn deal(
+    secret: &Scalar,
+    group_public: VerifyingKey,
+    min: u16,
+    max: u16,
+) -> Result<(Vec<KeyShare>, PublicKeyPackage), FrostError> {
+    if min < 2 || min > max {
+        return Err(FrostError::InvalidParameters { min, max });
+    }
+    let mut coeffs: Vec<Scalar> = std::iter::once(*secret)
+        .chain((1..min).map(|_| Scalar::random(&mut OsRng)))
+        .collect();
+    let shares: Vec<KeyShare> = (1..=max)
+        .map(|id| {
+            let x = to_scalar(id);
+            let secret = coeffs.iter().rev().fold(Scalar::ZERO, |acc, c| acc * x + c);
+            KeyShare {
+                identifier: id,
+                secret,
+                min_signers: min,
+                group_public,
+            }
+        })
+        .collect();
+    coeffs.zeroize();
+    let package = PublicKeyPackage {
+        group_public,
+        verifying_shares: shares
+            .iter()
+            .map(|s| (s.identifier, s.verifying_share()))
+            .collect(),
+        min_signers: min,
+    };
+    Ok((shares, package))
+}
+
+/// Round-1 secret nonces; use for exactly one signature
+pub struct SigningNonces {
+    hiding: Scalar,
+    binding: Scalar,
+}
+
+impl Drop for SigningNonces {
+    fn drop(&mut self) {
+        self.hiding.zeroize();
+        self.binding.zeroize();
+    }
+}
+
+/// Round-1 broadcast: `(d·G, e·G)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigningCommitments {
+    pub hiding: EdwardsPoint,
+    pub binding: EdwardsPoint,
+}
+
+/// The message plus every participant's commitments, sent to all signers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningPackage {
+    pub message: Vec<u8>,
+    pub commitments: BTreeMap<Identifier, SigningCommitments>,
+}
+
+/// Round-2 reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureShare {
+    pub identifier: Identifier,
+    pub share: Scalar,
+}
+
+/// RFC 9591 `nonce_generate`: fresh randomness hashed with the share
+fn nonce(secret: &Scalar) -> Scalar {
+    let mut random = [0u8; 32];
+    OsRng.fill_bytes(&mut random);
+    let k = hash_tagged(b"nonce", &[&random, secret.as_bytes()]);
+    random.zeroize();
+    k
+}
+
+/// Round 1: draw nonces and the commitments to publish
+pub fn commit(share: &KeyShare) -> (SigningNonces, SigningCommitments) {
+    let nonces = SigningNonces {
+        hiding: nonce(&share.secret),
+        binding: nonce(&share.secret),
+    };
+    let commitments = SigningCommitments {
+        hiding: nonces.hiding * ED25519_BASEPOINT_POINT,
+        binding: nonces.binding * ED25519_BASEPOINT_POINT,
+    };
+    (nonces, commitments)
+}
+
+/// Binding factors, group commitment `R` and challenge for a package
+struct Session {
+    rho: BTreeMap<Identifier, Scalar>,
+    r: EdwardsPoint,
+    challenge: Scalar,
+}
+
+impl Session {
+    fn new(
+        group_public: &VerifyingKey,
+        min_signers: u16,
+        package: &SigningPackage,
+    ) -> Result<Self, FrostError> {
+        if package.commitments.len() < min_signers as usize {
+            return Err(FrostError::NotEnoughSigners);
+        }
+        if package.commitments.contains_key(&0) {
+            return Err(FrostError::UnknownParticipant(0));
+        }
+
+        let mut encoded = Vec::with_capacity(96 * package.commitments.len());
+        for (id, c) in &package.commitments {
+            encoded.extend_from_slice(to_scalar(*id).as_bytes());
+            encoded.extend_from_slice(c.hiding.compress().as_bytes());
+            encoded.extend_from_slice(c.binding.compress().as_bytes());
+        }
+        let mut prefix = group_public.as_bytes().to_vec();
+        prefix.extend_from_slice(hash_tagged(b"msg", &[&package.message]).as_bytes());
+        prefix.extend_from_slice(hash_tagged(b"com", &[&encoded]).as_bytes());
+
+        let mut rho = BTreeMap::new();
+        let mut r = EdwardsPoint::default();
+        for (id, c) in &package.commitments {
+            let factor = hash_tagged(b"rho", &[&prefix, to_scalar(*id).as_bytes()]);
+            r += c.hiding + factor * c.binding;
+            rho.insert(*id, factor);
+        }
+        let challenge = hash(&[
+            r.compress().as_bytes(),
+            group_public.as_bytes(),
+            &package.message,
+        ]);
+        Ok(Self { rho, r, challenge })
+    }
+
+    fn lambda(&self, id: Identifier) -> Scalar {
+        lagrange(id, self.rho.keys().copied())
+    }
+}
+
+/// Round 2: our share of the signature over `package`
+///
+/// Takes the nonces by value so they can't be used twice.
+pub fn sign(
+    share: &KeyShare,
+    nonces: SigningNonces,
+    package: &SigningPackage,
+) -> Result<SignatureShare, FrostError> {
+    let ours = package
+        .commitments
+        .get(&share.identifier)
+        .ok_or(FrostError::MissingCommitment)?;
+    if ours.hiding != nonces.hiding * ED25519_BASEPOINT_POINT
+        || ours.binding != nonces.binding * ED25519_BASEPOINT_POINT
+    {
+        return Err(FrostError::MissingCommitment);
+    }
+    let session = Session::new(&share.group_public, share.min_signers, package)?;
+    let id = share.identifier;
+    Ok(SignatureShare {
+        identifier: id,
+        share: nonces.hiding
+            + nonces.binding * session.rho[&id]
+            + session.lambda(id) * share.secret * session.challenge,
+    })
+}
+
+/// Check every share and combine them into an Ed25519 signature
+pub fn aggregate(
+    package: &SigningPackage,
+    shares: &[SignatureShare],
+    public: &PublicKeyPackage,
+) -> Result<Signature, FrostError> {
+    let session = Session::new(&public.group_public, public.min_signers, package)?;
+    if shares.len() != package.commitments.len() {
+        return Err(FrostError::NotEnoughSigners);
+    }
+
+    let mut z = Scalar::ZERO;
+    for (id, c) in &package.commitments {
+        let share = shares
+            .iter()
+            .find(|s| s.identifier == *id)
+            .ok_or(FrostError::InvalidShare(*id))?;
+        let verifying = public
+            .verifying_shares
+            .get(id)
+            .ok_or(FrostError::UnknownParticipant(*id))?;
+        let expected = c.hiding
+            + session.rho[id] * c.binding
+            + (session.challenge * session.lambda(*id)) * verifying;
+        if share.share * ED25519_BASEPOINT_POINT != expected {
+            return Err(FrostError::InvalidShare(*id));
+        }
+        z += share.share;
+    }
+    Ok(Signature::from_parts(&session.r, &z))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::signature::verify_signature;
+
+    fn run(
+        shares: &[KeyShare],
+        public: &PublicKeyPackage,
+        who: &[usize],
+        message: &[u8],
+    ) -> Result<Signature, FrostError> {
+        let mut nonces = Vec::new();
+        let mut commitments = BTreeMap::new();
+        for &i in who {
+            let (n, c) = commit(&shares[i]);
+            nonces.push(n);
+            commitments.insert(shares[i].identifier(), c);
+        }
+        let package = SigningPackage {
+            message: message.to_vec(),
+            commitments,
+        };
+        let sig_shares = who
+            .iter()
+            .zip(nonces)
+            .map(|(&i, n)| sign(&shares[i], n, &package))
+            .collect::<Result<Vec<_>, _>>()?;
+        aggregate(&package, &sig_shares, public)
+    }
+
+    #[test]
+    fn test_threshold_signature_verifies() {
+        let (shares, public) = generate_with_dealer(2, 3).unwrap();
+        let message = b"frost over monero";
+        for who in [[0, 1], [1, 2], [2, 0]] {
+            let sig = run(&shares, &public, &who, message).unwrap();
+            assert!(
+                verify_signature(public.group_public.as_bytes(), message, &sig.to_bytes()).is_ok()
+            );
+        }
+        let sig = run(&shares, &public, &[0, 1, 2], message).unwrap();
+        assert!(verify_signature(public.group_public.as_bytes(), message, &sig.to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_split_existing_keypair() {
+        let keypair = Ed25519Keypair::generate();
+        let (shares, public) = split(&keypair, 3, 5).unwrap();
+        assert_eq!(public.group_public, keypair.public);
+        let sig = run(&shares, &public, &[4, 0, 2], b"msg").unwrap();
+        assert!(verify_signature(&keypair.public_bytes(), b"msg", &sig.to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert_eq!(
+            generate_with_dealer(1, 3).err(),
+            Some(FrostError::InvalidParameters { min: 1, max: 3 })
+        );
+        assert_eq!(
+            generate_with_dealer(4, 3).err(),
+            Some(FrostError::InvalidParameters { min: 4, max: 3 })
+        );
+
+        let (shares, public) = generate_with_dealer(3, 4).unwrap();
+        assert_eq!(
+            run(&shares, &public, &[0, 1], b"m").err(),
+            Some(FrostError::NotEnoughSigners)
+        );
+
+        let who = [0, 1, 3];
+        let mut nonces = Vec::new();
+        let mut commitments = BTreeMap::new();
+        for &i in &who {
+            let (n, c) = commit(&shares[i]);
+            nonces.push(n);
+            commitments.insert(shares[i].identifier(), c);
+        }
+        let package = SigningPackage {
+            message: b"m".to_vec(),
+            commitments,
+        };
+        let mut sig_shares: Vec<SignatureShare> = who
+            .iter()
+            .zip(nonces)
+            .map(|(&i, n)| sign(&shares[i], n, &package).unwrap())
+            .collect();
+        sig_shares[1].share += Scalar::ONE;
+        assert_eq!(
+            aggregate(&package, &sig_shares, &public),
+            Err(FrostError::InvalidShare(2))
+        );
+
+        let (n, _) = commit(&shares[2]);
+        assert_eq!(
+            sign(&shares[2], n, &package).err(),
+            Some(FrostError::MissingCommitment)
+        );
+    }
+}
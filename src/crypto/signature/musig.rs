@@ -0,0 +1,353 @@
+// src/crypto/signature/musig.rs
+//! MuSig2 n-of-n key and signature aggregation for [`Ed25519Keypair`]s.
+//!
+//! The signers' keys combine into one [`KeyAggContext`] whose public key
+//! looks like any other Ed25519 key, weighted as
+//! `X = Σ aᵢ·Xᵢ` with `aᵢ = H("MuSig/agg" || L || Xᵢ)` over the sorted key
+//! list `L` so nobody can cancel out the others. Signing takes two rounds:
+//! exchange [`PublicNonce`]s, then [`PartialSignature`]s, which
+//! [`KeyAggContext::aggregate`] checks and sums into a standard Ed25519
+//! signature over the message.
+//!
+//! Each signer sends two nonces and the effective one is `R₁ + b·R₂` with
+//! `b` bound to all nonces and the message, which is what lets the first
+//! round run before the message or the other nonces are known (MuSig2).
+use std::fmt;
+
+use curve25519_dalek::{EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT};
+use ed25519_dalek::VerifyingKey;
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+use super::{Ed25519Keypair, Signature};
+
+/// Why a MuSig2 operation failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MusigError {
+    /// Need at least two signers
+    TooFewSigners,
+    /// The same key appears twice
+    DuplicateKey,
+    /// A key has small order
+    WeakKey,
+    /// Key isn't part of the aggregate, or a signer's nonce is missing
+    UnknownSigner,
+    /// This signer's nonces were already used
+    NonceReused,
+    /// The partial signature at this position doesn't verify
+    InvalidPartial(usize),
+}
+
+impl fmt::Display for MusigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MusigError::TooFewSigners => write!(f, "need at least two signers"),
+            MusigError::DuplicateKey => write!(f, "duplicate signer key"),
+            MusigError::WeakKey => write!(f, "signer key has small order"),
+            MusigError::UnknownSigner => write!(f, "signer set doesn't match the aggregate key"),
+            MusigError::NonceReused => write!(f, "signing nonces already used"),
+            MusigError::InvalidPartial(i) => write!(f, "invalid partial signature from signer {i}"),
+        }
+    }
+}
+
+impl std::error::Error for MusigError {}
+
+fn hash(parts: &[&[u8]]) -> Scalar {
+    let mut h = Sha512::new();
+    for part in parts {
+        h.update(part);
+    }
+    Scalar::from_bytes_mod_order_wide(&h.finalize().into())
+}
+
+/// The signer set and its aggregate public key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyAggContext {
+    /// Signer keys, sorted by encoding
+    keys: Vec<VerifyingKey>,
+    coefficients: Vec<Scalar>,
+    public: VerifyingKey,
+}
+
+impl KeyAggContext {
+    /// Aggregate the keys of every co-signer (order doesn't matter)
+    pub fn new(keys: &[VerifyingKey]) -> Result<Self, MusigError> {
+        if keys.len() < 2 {
+            return Err(MusigError::TooFewSigners);
+        }
+        if keys.iter().any(|k| k.is_weak()) {
+            return Err(MusigError::WeakKey);
+        }
+        let mut keys = keys.to_vec();
+        keys.sort_by_key(|k| k.to_bytes());
+        if keys.windows(2).any(|w| w[0] == w[1]) {
+            return Err(MusigError::DuplicateKey);
+        }
+
+        let list: Vec<u8> = keys.iter().flat_map(|k| k.to_bytes()).collect();
+        let coefficients: Vec<Scalar> = keys
+            .iter()
+            .map(|k| hash(&[b"MuSig/agg", &list, k.as_bytes()]))
+            .collect();
+        let point: EdwardsPoint = keys
+            .iter()
+            .zip(&coefficients)
+            .map(|(k, a)| a * k.to_edwards())
+            .sum();
+        Ok(Self {
+            keys,
+            coefficients,
+            public: VerifyingKey::from(point),
+        })
+    }
+
+    /// The aggregate key signatures verify under
+    pub fn public(&self) -> &VerifyingKey {
+        &self.public
+    }
+
+    /// Signer keys, sorted
+    pub fn keys(&self) -> &[VerifyingKey] {
+        &self.keys
+    }
+
+    fn position(&self, key: &VerifyingKey) -> Result<usize, MusigError> {
+        self.keys
+            .iter()
+            .position(|k| k == key)
+            .ok_or(MusigError::UnknownSigner)
+    }
+
+    /// `(R, b, c)` for the given nonces, ordered like [`Self::keys`]
+    fn challenge<'n>(
+        &self,
+        nonces: &'n [PublicNonce],
+        message: &[u8],
+    ) -> Result<(Vec<&'n PublicNonce>, EdwardsPoint, Scalar, Scalar), MusigError> {
+        if nonces.len() != self.keys.len() {
+            return Err(MusigError::UnknownSigner);
+        }
+        let mut ordered = Vec::with_capacity(nonces.len());
+        for key in &self.keys {
+            ordered.push(
+                nonces
+                    .iter()
+                    .find(|n| n.signer == *key)
+                    .ok_or(MusigError::UnknownSigner)?,
+            );
+        }
+        let r1: EdwardsPoint = ordered.iter().map(|n| n.r1).sum();
+        let r2: EdwardsPoint = ordered.iter().map(|n| n.r2).sum();
+        let b = hash(&[
+            b"MuSig/noncecoef",
+            r1.compress().as_bytes(),
+            r2.compress().as_bytes(),
+            self.public.as_bytes(),
+            message,
+        ]);
+        let r = r1 + b * r2;
+        // the plain Ed25519 challenge, so the result verifies anywhere
+        let c = hash(&[r.compress().as_bytes(), self.public.as_bytes(), message]);
+        Ok((ordered, r, b, c))
+    }
+
+    /// Check every partial signature and combine them
+    pub fn aggregate(
+        &self,
+        nonces: &[PublicNonce],
+        message: &[u8],
+        partials: &[PartialSignature],
+    ) -> Result<Signature, MusigError> {
+        let (ordered, r, b, c) = self.challenge(nonces, message)?;
+        if partials.len() != self.keys.len() {
+            return Err(MusigError::UnknownSigner);
+        }
+        let mut s = Scalar::ZERO;
+        for (i, nonce) in ordered.iter().enumerate() {
+            let partial = partials
+                .iter()
+                .find(|p| p.signer == self.keys[i])
+                .ok_or(MusigError::UnknownSigner)?;
+            let expected =
+                nonce.r1 + b * nonce.r2 + (c * self.coefficients[i]) * self.keys[i].to_edwards();
+            if partial.s * ED25519_BASEPOINT_POINT != expected {
+                return Err(MusigError::InvalidPartial(i));
+            }
+            s += partial.s;
+        }
+
+        Ok(Signature::from_parts(&r, &s))
+    }
+}
+
+/// Round-1 broadcast: `(r₁·G, r₂·G)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicNonce {
+    pub signer: VerifyingKey,
+    pub r1: EdwardsPoint,
+    pub r2: EdwardsPoint,
+}
+
+/// Round-2 broadcast
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialSignature {
+    pub signer: VerifyingKey,
+    pub s: Scalar,
+}
+
+/// One co-signer's state for one signature
+pub struct SigningSession<'a> {
+    keypair: &'a Ed25519Keypair,
+    context: &'a KeyAggContext,
+    nonces: Option<[Scalar; 2]>,
+    public: PublicNonce,
+}
+
+impl<'a> SigningSession<'a> {
+    /// Round 1: draw fresh nonces for `keypair`, a member of `context`
+    pub fn new(
+        keypair: &'a Ed25519Keypair,
+        context: &'a KeyAggContext,
+    ) -> Result<Self, MusigError> {
+        context.position(&keypair.public)?;
+        let nonces = [Scalar::random(&mut OsRng), Scalar::random(&mut OsRng)];
+        let public = PublicNonce {
+            signer: keypair.public,
+            r1: nonces[0] * ED25519_BASEPOINT_POINT,
+            r2: nonces[1] * ED25519_BASEPOINT_POINT,
+        };
+        Ok(Self {
+            keypair,
+            context,
+            nonces: Some(nonces),
+            public,
+        })
+    }
+
+    pub fn public_nonce(&self) -> &PublicNonce {
+        &self.public
+    }
+
+    /// Round 2: sign `message` given every signer's nonce, ours included
+    ///
+    /// The nonces are used up; a second call fails.
+    pub fn sign(
+        &mut self,
+        nonces: &[PublicNonce],
+        message: &[u8],
+    ) -> Result<PartialSignature, MusigError> {
+        let ctx = self.context;
+        if self.nonces.is_none() {
+            return Err(MusigError::NonceReused);
+        }
+        if !nonces.contains(&self.public) {
+            return Err(MusigError::UnknownSigner);
+        }
+        let (_, _, b, c) = ctx.challenge(nonces, message)?;
+        let i = ctx.position(&self.keypair.public)?;
+        let mut k = self.nonces.take().expect("checked above");
+        let mut x = self.keypair.signing_key.to_scalar();
+        let s = k[0] + b * k[1] + c * ctx.coefficients[i] * x;
+        k.zeroize();
+        x.zeroize();
+        Ok(PartialSignature {
+            signer: self.keypair.public,
+            s,
+        })
+    }
+}
+
+impl Drop for SigningSession<'_> {
+    fn drop(&mut self) {
+        if let Some(k) = &mut self.nonces {
+            k.zeroize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::signature::verify_signature;
+
+    fn cosign(keypairs: &[Ed25519Keypair], message: &[u8]) -> (KeyAggContext, Signature) {
+        let keys: Vec<VerifyingKey> = keypairs.iter().map(|k| k.public).collect();
+        let ctx = KeyAggContext::new(&keys).unwrap();
+        let mut sessions: Vec<SigningSession> = keypairs
+            .iter()
+            .map(|k| SigningSession::new(k, &ctx).unwrap())
+            .collect();
+        let nonces: Vec<PublicNonce> = sessions.iter().map(|s| *s.public_nonce()).collect();
+        let partials: Vec<PartialSignature> = sessions
+            .iter_mut()
+            .map(|s| s.sign(&nonces, message).unwrap())
+            .collect();
+        assert_eq!(
+            sessions[0].sign(&nonces, message),
+            Err(MusigError::NonceReused)
+        );
+        let sig = ctx.aggregate(&nonces, message, &partials).unwrap();
+        drop(sessions);
+        (ctx, sig)
+    }
+
+    #[test]
+    fn test_aggregate_signature_verifies() {
+        for n in [2, 3, 7] {
+            let keypairs: Vec<Ed25519Keypair> =
+                (0..n).map(|_| Ed25519Keypair::generate()).collect();
+            let (ctx, sig) = cosign(&keypairs, b"shared admin key");
+            assert!(
+                verify_signature(
+                    ctx.public().as_bytes(),
+                    b"shared admin key",
+                    &sig.to_bytes()
+                )
+                .is_ok()
+            );
+            assert!(verify_signature(ctx.public().as_bytes(), b"other", &sig.to_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_key_order_irrelevant() {
+        let a = Ed25519Keypair::generate().public;
+        let b = Ed25519Keypair::generate().public;
+        assert_eq!(
+            KeyAggContext::new(&[a, b]).unwrap().public(),
+            KeyAggContext::new(&[b, a]).unwrap().public()
+        );
+        assert_eq!(KeyAggContext::new(&[a]), Err(MusigError::TooFewSigners));
+        assert_eq!(KeyAggContext::new(&[a, a]), Err(MusigError::DuplicateKey));
+    }
+
+    #[test]
+    fn test_bad_partial_is_identified() {
+        let keypairs: Vec<Ed25519Keypair> = (0..3).map(|_| Ed25519Keypair::generate()).collect();
+        let keys: Vec<VerifyingKey> = keypairs.iter().map(|k| k.public).collect();
+        let ctx = KeyAggContext::new(&keys).unwrap();
+        let mut sessions: Vec<SigningSession> = keypairs
+            .iter()
+            .map(|k| SigningSession::new(k, &ctx).unwrap())
+            .collect();
+        let nonces: Vec<PublicNonce> = sessions.iter().map(|s| *s.public_nonce()).collect();
+        let mut partials: Vec<PartialSignature> = sessions
+            .iter_mut()
+            .map(|s| s.sign(&nonces, b"m").unwrap())
+            .collect();
+        partials[0].s += Scalar::ONE;
+        let bad = ctx.position(&partials[0].signer).unwrap();
+        assert_eq!(
+            ctx.aggregate(&nonces, b"m", &partials),
+            Err(MusigError::InvalidPartial(bad))
+        );
+
+        let outsider = Ed25519Keypair::generate();
+        assert!(matches!(
+            SigningSession::new(&outsider, &ctx),
+            Err(MusigError::UnknownSigner)
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+// src/encoding/base58.rs
+//! Monero's block-based base58.
+//!
+//! Unlike Bitcoin's base58, which treats the whole input as one big number,
+//! Monero splits it into 8-byte blocks and encodes each into exactly 11
+//! characters (a shorter final block into a fixed shorter width). Lengths
+//! are therefore predictable and leading zero bytes need no special case.
+use std::fmt;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const FULL_BLOCK_SIZE: usize = 8;
+const FULL_ENCODED_BLOCK_SIZE: usize = 11;
+/// Characters needed for a block of `i` bytes
+const ENCODED_BLOCK_SIZES: [usize; FULL_BLOCK_SIZE + 1] = [0, 2, 3, 5, 6, 7, 9, 10, 11];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base58Error {
+    /// Character outside the base58 alphabet
+    InvalidCharacter(char),
+    /// Final block has a length no byte count encodes to
+    InvalidLength(usize),
+    /// A block's value doesn't fit in its byte count
+    Overflow,
+}
+
+impl fmt::Display for Base58Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Base58Error::InvalidCharacter(c) => write!(f, "invalid base58 character {c:?}"),
+            Base58Error::InvalidLength(n) => write!(f, "invalid base58 block length {n}"),
+            Base58Error::Overflow => write!(f, "base58 block overflows its size"),
+        }
+    }
+}
+
+impl std::error::Error for Base58Error {}
+
+fn encode_block(block: &[u8], out: &mut String) {
+    let mut num = block.iter().fold(0u64, |n, &b| (n << 8) | b as u64);
+    let mut chars = [ALPHABET[0]; FULL_ENCODED_BLOCK_SIZE];
+    let size = ENCODED_BLOCK_SIZES[block.len()];
+    for c in chars[..size].iter_mut().rev() {
+        *c = ALPHABET[(num % 58) as usize];
+        num /= 58;
+    }
+    out.extend(chars[..size].iter().map(|&c| c as char));
+}
+
+fn decode_block(block: &[u8], out: &mut Vec<u8>) -> Result<(), Base58Error> {
+    let size = ENCODED_BLOCK_SIZES
+        .iter()
+        .position(|&n| n == block.len())
+        .ok_or(Base58Error::InvalidLength(block.len()))?;
+    let mut num = 0u64;
+    for &c in block {
+        let digit = ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or(Base58Error::InvalidCharacter(c as char))?;
+        num = num
+            .checked_mul(58)
+            .and_then(|n| n.checked_add(digit as u64))
+            .ok_or(Base58Error::Overflow)?;
+    }
+    if size < FULL_BLOCK_SIZE && num >> (8 * size) != 0 {
+        return Err(Base58Error::Overflow);
+    }
+    out.extend_from_slice(&num.to_be_bytes()[FULL_BLOCK_SIZE - size..]);
+    Ok(())
+}
+
+/// Encode `data`; the output length depends only on the input length
+pub fn encode(data: &[u8]) -> String {
+    let full = data.len() / FULL_BLOCK_SIZE;
+    let mut out = String::with_capacity(
+        full * FULL_ENCODED_BLOCK_SIZE + ENCODED_BLOCK_SIZES[data.len() % FULL_BLOCK_SIZE],
+    );
+    for block in data.chunks(FULL_BLOCK_SIZE) {
+        encode_block(block, &mut out);
+    }
+    out
+}
+
+/// Decode a string produced by [`encode`]
+pub fn decode(text: &str) -> Result<Vec<u8>, Base58Error> {
+    if let Some(c) = text.chars().find(|c| !c.is_ascii()) {
+        return Err(Base58Error::InvalidCharacter(c));
+    }
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len() / FULL_ENCODED_BLOCK_SIZE * FULL_BLOCK_SIZE + 8);
+    for block in bytes.chunks(FULL_ENCODED_BLOCK_SIZE) {
+        decode_block(block, &mut out)?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // block-boundary cases, as in Monero's tests/unit_tests/base58.cpp
+    const VECTORS: &[(&str, &str)] = &[
+        ("", ""),
+        ("00", "11"),
+        ("39", "1z"),
+        ("ff", "5Q"),
+        ("0000", "111"),
+        ("0039", "11z"),
+        ("0100", "15R"),
+        ("ffff", "LUv"),
+        ("000000", "11111"),
+        ("000039", "1111z"),
+        ("010000", "11LUw"),
+        ("ffffff", "2UzHL"),
+        ("00000039", "11111z"),
+        ("ffffffff", "7YXq9G"),
+        ("0000000039", "111111z"),
+        ("ffffffffff", "VtB5VXc"),
+        ("000000000039", "11111111z"),
+        ("ffffffffffff", "3CUsUpv9t"),
+        ("00000000000039", "111111111z"),
+        ("ffffffffffffff", "Ahg1opVcGW"),
+        ("0000000000000039", "1111111111z"),
+        ("ffffffffffffffff", "jpXCZedGfVQ"),
+        ("0000000000000000", "11111111111"),
+        ("0000000000000001", "11111111112"),
+        ("0000000000000008", "11111111119"),
+        ("0000000000000009", "1111111111A"),
+        ("000000000000003a", "11111111121"),
+        ("00ffffffffffffff", "1Ahg1opVcGW"),
+        ("06156013762879f7", "22222222222"),
+        ("05e022ba374b2a00", "1z111111111"),
+        ("0000000000", "1111111"),
+        ("000000000000", "111111111"),
+        ("00000000000000", "1111111111"),
+        ("06156013762879f7ffffffffff", "22222222222VtB5VXc"),
+    ];
+
+    #[test]
+    fn test_vectors() {
+        for &(hex_in, text) in VECTORS {
+            let data = hex::decode(hex_in).unwrap();
+            assert_eq!(encode(&data), text, "encode {hex_in}");
+            assert_eq!(decode(text).unwrap(), data, "decode {text}");
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(decode("1"), Err(Base58Error::InvalidLength(1)));
+        assert_eq!(decode("1111"), Err(Base58Error::InvalidLength(4)));
+        assert_eq!(decode("111111111111"), Err(Base58Error::InvalidLength(1)));
+        assert_eq!(decode("5R"), Err(Base58Error::Overflow));
+        assert_eq!(decode("zzzzzzzzzzz"), Err(Base58Error::Overflow));
+        assert_eq!(decode("LUw"), Err(Base58Error::Overflow));
+        assert_eq!(decode("1O"), Err(Base58Error::InvalidCharacter('O')));
+        assert_eq!(decode("10"), Err(Base58Error::InvalidCharacter('0')));
+        assert_eq!(decode("1é"), Err(Base58Error::InvalidCharacter('é')));
+    }
+
+    #[test]
+    fn test_address_roundtrip() {
+        let addr = "42ey1afDFnn4886T7196doS9GPMzexD9gXpsZJDwVjeRVdFCSoHnv7KPbBeGpzJBzHRC\
+                    As9UxqeoyFQMYbqSWYTfJJQAWDm";
+        let data = decode(addr).unwrap();
+        // network byte, spend key, view key, checksum
+        assert_eq!(data.len(), 1 + 32 + 32 + 4);
+        assert_eq!(data[0], 0x12);
+        assert_eq!(encode(&data), addr);
+    }
+}
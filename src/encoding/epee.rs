@@ -0,0 +1,709 @@
+// src/encoding/epee.rs
+//! Epee portable storage, the binary format of monerod's `.bin` RPC
+//! endpoints and P2P messages.
+//!
+//! A document is a 9-byte header followed by one [`Section`]: a count, then
+//! entries of `name_len u8 || name || type u8 || value`. Numbers are
+//! little-endian, strings are length-prefixed bytes (hashes and other POD
+//! blobs travel as strings), and arrays set bit 0x80 on the element type and
+//! share that one type byte. Counts and lengths use epee's own varint, which
+//! stores its byte width in the low two bits; it is unrelated to
+//! [`super::varint`].
+//!
+//! ```ignore
+//! let request = Section::new()
+//!     .with("start_height", 100u64)
+//!     .with("prune", true);
+//! let bytes = epee::to_bytes(&request)?;
+//! ```
+use std::fmt;
+
+/// `PORTABLE_STORAGE_SIGNATUREA/B` as little-endian u32s, then the version
+const HEADER: [u8; 9] = [0x01, 0x11, 0x01, 0x01, 0x01, 0x01, 0x02, 0x01, 0x01];
+const VERSION: u8 = 1;
+/// monerod refuses deeper nesting too
+pub const MAX_DEPTH: usize = 100;
+const ARRAY_FLAG: u8 = 0x80;
+/// Largest value the varint can hold
+const MAX_VARINT: u64 = (1 << 62) - 1;
+
+const TYPE_I64: u8 = 1;
+const TYPE_I32: u8 = 2;
+const TYPE_I16: u8 = 3;
+const TYPE_I8: u8 = 4;
+const TYPE_U64: u8 = 5;
+const TYPE_U32: u8 = 6;
+const TYPE_U16: u8 = 7;
+const TYPE_U8: u8 = 8;
+const TYPE_F64: u8 = 9;
+const TYPE_STRING: u8 = 10;
+const TYPE_BOOL: u8 = 11;
+const TYPE_OBJECT: u8 = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpeeError {
+    /// Missing or wrong storage signature
+    InvalidHeader,
+    UnsupportedVersion(u8),
+    /// Input ended inside a value
+    Truncated,
+    /// Type byte not in the format, or nested arrays
+    UnknownType(u8),
+    /// Entry name empty, over 255 bytes, or not UTF-8
+    InvalidName,
+    /// Count or length larger than the varint holds or the input could contain
+    TooLarge(u64),
+    /// Objects nested more than [`MAX_DEPTH`] deep
+    TooDeep,
+    /// Bytes left over after the root section
+    TrailingBytes(usize),
+    /// Bool byte other than 0 or 1
+    InvalidBool(u8),
+}
+
+impl fmt::Display for EpeeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpeeError::InvalidHeader => write!(f, "not an epee portable storage document"),
+            EpeeError::UnsupportedVersion(v) => write!(f, "unsupported epee version {v}"),
+            EpeeError::Truncated => write!(f, "epee document truncated"),
+            EpeeError::UnknownType(t) => write!(f, "unknown epee type {t:#04x}"),
+            EpeeError::InvalidName => write!(f, "invalid epee entry name"),
+            EpeeError::TooLarge(n) => write!(f, "epee length {n} too large"),
+            EpeeError::TooDeep => write!(f, "epee objects nested deeper than {MAX_DEPTH}"),
+            EpeeError::TrailingBytes(n) => write!(f, "{n} trailing bytes after epee document"),
+            EpeeError::InvalidBool(b) => write!(f, "invalid epee bool {b:#04x}"),
+        }
+    }
+}
+
+impl std::error::Error for EpeeError {}
+
+/// One entry value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    I64(i64),
+    I32(i32),
+    I16(i16),
+    I8(i8),
+    U64(u64),
+    U32(u32),
+    U16(u16),
+    U8(u8),
+    F64(f64),
+    /// Arbitrary bytes; text, hashes and packed POD arrays alike
+    String(Vec<u8>),
+    Bool(bool),
+    Object(Section),
+    Array(Array),
+}
+
+/// Homogeneous array; the element type is stored once, so even an empty
+/// array has one
+#[derive(Debug, Clone, PartialEq)]
+pub enum Array {
+    I64(Vec<i64>),
+    I32(Vec<i32>),
+    I16(Vec<i16>),
+    I8(Vec<i8>),
+    U64(Vec<u64>),
+    U32(Vec<u32>),
+    U16(Vec<u16>),
+    U8(Vec<u8>),
+    F64(Vec<f64>),
+    String(Vec<Vec<u8>>),
+    Bool(Vec<bool>),
+    Object(Vec<Section>),
+}
+
+impl Value {
+    /// Any integer type holding a non-negative value
+    ///
+    /// monerod isn't consistent about integer widths across versions, so
+    /// readers should accept whatever arrives.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::U64(n) => Some(n),
+            Value::U32(n) => Some(n.into()),
+            Value::U16(n) => Some(n.into()),
+            Value::U8(n) => Some(n.into()),
+            Value::I64(n) => n.try_into().ok(),
+            Value::I32(n) => n.try_into().ok(),
+            Value::I16(n) => n.try_into().ok(),
+            Value::I8(n) => n.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Any integer type that fits
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::I64(n) => Some(n),
+            Value::I32(n) => Some(n.into()),
+            Value::I16(n) => Some(n.into()),
+            Value::I8(n) => Some(n.into()),
+            Value::U64(n) => n.try_into().ok(),
+            Value::U32(n) => Some(n.into()),
+            Value::U16(n) => Some(n.into()),
+            Value::U8(n) => Some(n.into()),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::F64(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// String value that is valid UTF-8
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(self.as_bytes()?).ok()
+    }
+
+    pub fn as_object(&self) -> Option<&Section> {
+        match self {
+            Value::Object(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Array> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($ty:ty => $variant:ident),* $(,)?) => {$(
+        impl From<$ty> for Value {
+            fn from(v: $ty) -> Self {
+                Value::$variant(v)
+            }
+        }
+
+        impl From<Vec<$ty>> for Value {
+            fn from(v: Vec<$ty>) -> Self {
+                Value::Array(Array::$variant(v))
+            }
+        }
+    )*};
+}
+
+impl_from!(
+    i64 => I64, i32 => I32, i16 => I16, i8 => I8,
+    u64 => U64, u32 => U32, u16 => U16,
+    f64 => F64, bool => Bool, Section => Object,
+);
+
+/// `u8` is the only byte type; a `Vec<u8>` is a string, not an array
+impl From<u8> for Value {
+    fn from(v: u8) -> Self {
+        Value::U8(v)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(v: Vec<u8>) -> Self {
+        Value::String(v)
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(v: &[u8]) -> Self {
+        Value::String(v.to_vec())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::String(v.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::String(v.into_bytes())
+    }
+}
+
+impl From<Array> for Value {
+    fn from(v: Array) -> Self {
+        Value::Array(v)
+    }
+}
+
+impl Array {
+    pub fn len(&self) -> usize {
+        match self {
+            Array::I64(v) => v.len(),
+            Array::I32(v) => v.len(),
+            Array::I16(v) => v.len(),
+            Array::I8(v) => v.len(),
+            Array::U64(v) => v.len(),
+            Array::U32(v) => v.len(),
+            Array::U16(v) => v.len(),
+            Array::U8(v) => v.len(),
+            Array::F64(v) => v.len(),
+            Array::String(v) => v.len(),
+            Array::Bool(v) => v.len(),
+            Array::Object(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An object: named entries in insertion order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Section {
+    entries: Vec<(String, Value)>,
+}
+
+impl Section {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder form of [`Section::insert`]
+    pub fn with(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    /// Set `name`, replacing any previous value
+    pub fn insert(&mut self, name: &str, value: impl Into<Value>) {
+        let value = value.into();
+        match self.entries.iter_mut().find(|(n, _)| n == name) {
+            Some((_, old)) => *old = value,
+            None => self.entries.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.entries.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.entries.iter().map(|(n, v)| (n.as_str(), v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Serialise `root` as a complete document
+pub fn to_bytes(root: &Section) -> Result<Vec<u8>, EpeeError> {
+    let mut out = HEADER.to_vec();
+    write_section(&mut out, root)?;
+    Ok(out)
+}
+
+/// Parse a complete document
+pub fn from_bytes(data: &[u8]) -> Result<Section, EpeeError> {
+    let body = data
+        .strip_prefix(&HEADER[..8])
+        .ok_or(EpeeError::InvalidHeader)?;
+    let mut reader = Reader(body);
+    match reader.u8()? {
+        VERSION => {}
+        v => return Err(EpeeError::UnsupportedVersion(v)),
+    }
+    let root = reader.section(0)?;
+    if !reader.0.is_empty() {
+        return Err(EpeeError::TrailingBytes(reader.0.len()));
+    }
+    Ok(root)
+}
+
+fn write_varint(out: &mut Vec<u8>, n: u64) -> Result<(), EpeeError> {
+    match n {
+        0..=0x3f => out.push((n as u8) << 2),
+        0x40..=0x3fff => out.extend_from_slice(&((n as u16) << 2 | 1).to_le_bytes()),
+        0x4000..=0x3fff_ffff => out.extend_from_slice(&((n as u32) << 2 | 2).to_le_bytes()),
+        0x4000_0000..=MAX_VARINT => out.extend_from_slice(&(n << 2 | 3).to_le_bytes()),
+        _ => return Err(EpeeError::TooLarge(n)),
+    }
+    Ok(())
+}
+
+fn write_string(out: &mut Vec<u8>, s: &[u8]) -> Result<(), EpeeError> {
+    write_varint(out, s.len() as u64)?;
+    out.extend_from_slice(s);
+    Ok(())
+}
+
+fn write_section(out: &mut Vec<u8>, section: &Section) -> Result<(), EpeeError> {
+    write_varint(out, section.entries.len() as u64)?;
+    for (name, value) in &section.entries {
+        if name.is_empty() || name.len() > u8::MAX as usize {
+            return Err(EpeeError::InvalidName);
+        }
+        out.push(name.len() as u8);
+        out.extend_from_slice(name.as_bytes());
+        write_value(out, value)?;
+    }
+    Ok(())
+}
+
+/// Type byte then payload
+fn write_value(out: &mut Vec<u8>, value: &Value) -> Result<(), EpeeError> {
+    match value {
+        Value::I64(n) => write_scalar(out, TYPE_I64, &n.to_le_bytes()),
+        Value::I32(n) => write_scalar(out, TYPE_I32, &n.to_le_bytes()),
+        Value::I16(n) => write_scalar(out, TYPE_I16, &n.to_le_bytes()),
+        Value::I8(n) => write_scalar(out, TYPE_I8, &n.to_le_bytes()),
+        Value::U64(n) => write_scalar(out, TYPE_U64, &n.to_le_bytes()),
+        Value::U32(n) => write_scalar(out, TYPE_U32, &n.to_le_bytes()),
+        Value::U16(n) => write_scalar(out, TYPE_U16, &n.to_le_bytes()),
+        Value::U8(n) => write_scalar(out, TYPE_U8, &[*n]),
+        Value::F64(n) => write_scalar(out, TYPE_F64, &n.to_le_bytes()),
+        Value::Bool(b) => write_scalar(out, TYPE_BOOL, &[*b as u8]),
+        Value::String(s) => {
+            out.push(TYPE_STRING);
+            write_string(out, s)?;
+        }
+        Value::Object(s) => {
+            out.push(TYPE_OBJECT);
+            write_section(out, s)?;
+        }
+        Value::Array(a) => write_array(out, a)?,
+    }
+    Ok(())
+}
+
+fn write_scalar(out: &mut Vec<u8>, ty: u8, bytes: &[u8]) {
+    out.push(ty);
+    out.extend_from_slice(bytes);
+}
+
+fn write_array(out: &mut Vec<u8>, array: &Array) -> Result<(), EpeeError> {
+    macro_rules! numbers {
+        ($ty:expr, $v:expr) => {{
+            out.push($ty | ARRAY_FLAG);
+            write_varint(out, $v.len() as u64)?;
+            for n in $v {
+                out.extend_from_slice(&n.to_le_bytes());
+            }
+        }};
+    }
+    match array {
+        Array::I64(v) => numbers!(TYPE_I64, v),
+        Array::I32(v) => numbers!(TYPE_I32, v),
+        Array::I16(v) => numbers!(TYPE_I16, v),
+        Array::I8(v) => numbers!(TYPE_I8, v),
+        Array::U64(v) => numbers!(TYPE_U64, v),
+        Array::U32(v) => numbers!(TYPE_U32, v),
+        Array::U16(v) => numbers!(TYPE_U16, v),
+        Array::U8(v) => numbers!(TYPE_U8, v),
+        Array::F64(v) => numbers!(TYPE_F64, v),
+        Array::Bool(v) => numbers!(TYPE_BOOL, v.iter().map(|&b| b as u8).collect::<Vec<_>>()),
+        Array::String(v) => {
+            out.push(TYPE_STRING | ARRAY_FLAG);
+            write_varint(out, v.len() as u64)?;
+            for s in v {
+                write_string(out, s)?;
+            }
+        }
+        Array::Object(v) => {
+            out.push(TYPE_OBJECT | ARRAY_FLAG);
+            write_varint(out, v.len() as u64)?;
+            for s in v {
+                write_section(out, s)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], EpeeError> {
+        if self.0.len() < n {
+            return Err(EpeeError::Truncated);
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], EpeeError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, EpeeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, EpeeError> {
+        let width = 1 << (self.0.first().ok_or(EpeeError::Truncated)? & 3);
+        let mut buf = [0u8; 8];
+        buf[..width].copy_from_slice(self.take(width)?);
+        Ok(u64::from_le_bytes(buf) >> 2)
+    }
+
+    /// A count of items each at least `min_size` bytes, checked against the
+    /// remaining input so a forged count can't force a huge allocation
+    fn count(&mut self, min_size: usize) -> Result<usize, EpeeError> {
+        let n = self.varint()?;
+        if n.saturating_mul(min_size as u64) > self.0.len() as u64 {
+            return Err(EpeeError::TooLarge(n));
+        }
+        Ok(n as usize)
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>, EpeeError> {
+        let len = self.count(1)?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn bool(&mut self) -> Result<bool, EpeeError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(EpeeError::InvalidBool(b)),
+        }
+    }
+
+    fn section(&mut self, depth: usize) -> Result<Section, EpeeError> {
+        if depth > MAX_DEPTH {
+            return Err(EpeeError::TooDeep);
+        }
+        // shortest entry: 1-byte name length, 1-byte name, type, u8 value
+        let count = self.count(4)?;
+        let mut section = Section {
+            entries: Vec::with_capacity(count),
+        };
+        for _ in 0..count {
+            let len = self.u8()? as usize;
+            let name = std::str::from_utf8(self.take(len)?).map_err(|_| EpeeError::InvalidName)?;
+            if name.is_empty() {
+                return Err(EpeeError::InvalidName);
+            }
+            let ty = self.u8()?;
+            let value = self.value(ty, depth)?;
+            section.insert(name, value);
+        }
+        Ok(section)
+    }
+
+    fn value(&mut self, ty: u8, depth: usize) -> Result<Value, EpeeError> {
+        Ok(match ty {
+            TYPE_I64 => Value::I64(i64::from_le_bytes(self.array()?)),
+            TYPE_I32 => Value::I32(i32::from_le_bytes(self.array()?)),
+            TYPE_I16 => Value::I16(i16::from_le_bytes(self.array()?)),
+            TYPE_I8 => Value::I8(i8::from_le_bytes(self.array()?)),
+            TYPE_U64 => Value::U64(u64::from_le_bytes(self.array()?)),
+            TYPE_U32 => Value::U32(u32::from_le_bytes(self.array()?)),
+            TYPE_U16 => Value::U16(u16::from_le_bytes(self.array()?)),
+            TYPE_U8 => Value::U8(self.u8()?),
+            TYPE_F64 => Value::F64(f64::from_le_bytes(self.array()?)),
+            TYPE_STRING => Value::String(self.string()?),
+            TYPE_BOOL => Value::Bool(self.bool()?),
+            TYPE_OBJECT => Value::Object(self.section(depth + 1)?),
+            t if t & ARRAY_FLAG != 0 => Value::Array(self.array_value(t & !ARRAY_FLAG, depth)?),
+            t => return Err(EpeeError::UnknownType(t)),
+        })
+    }
+
+    fn array_value(&mut self, ty: u8, depth: usize) -> Result<Array, EpeeError> {
+        macro_rules! numbers {
+            ($variant:ident, $ty:ty) => {{
+                let n = self.count(size_of::<$ty>())?;
+                Array::$variant(
+                    (0..n)
+                        .map(|_| self.array().map(<$ty>::from_le_bytes))
+                        .collect::<Result<_, _>>()?,
+                )
+            }};
+        }
+        Ok(match ty {
+            TYPE_I64 => numbers!(I64, i64),
+            TYPE_I32 => numbers!(I32, i32),
+            TYPE_I16 => numbers!(I16, i16),
+            TYPE_I8 => numbers!(I8, i8),
+            TYPE_U64 => numbers!(U64, u64),
+            TYPE_U32 => numbers!(U32, u32),
+            TYPE_U16 => numbers!(U16, u16),
+            TYPE_U8 => numbers!(U8, u8),
+            TYPE_F64 => numbers!(F64, f64),
+            TYPE_BOOL => {
+                let n = self.count(1)?;
+                Array::Bool((0..n).map(|_| self.bool()).collect::<Result<_, _>>()?)
+            }
+            TYPE_STRING => {
+                let n = self.count(1)?;
+                Array::String((0..n).map(|_| self.string()).collect::<Result<_, _>>()?)
+            }
+            TYPE_OBJECT => {
+                let n = self.count(1)?;
+                Array::Object(
+                    (0..n)
+                        .map(|_| self.section(depth + 1))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            t => return Err(EpeeError::UnknownType(t | ARRAY_FLAG)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_bytes() {
+        let doc = Section::new()
+            .with("a", 1u8)
+            .with("s", "hi")
+            .with("v", vec![1u32, 2]);
+        let bytes = to_bytes(&doc).unwrap();
+        assert_eq!(
+            hex::encode(&bytes),
+            concat!(
+                "011101010101020101", // header
+                "0c",                 // 3 entries
+                "01610801",           // a: u8 1
+                "01730a086869",       // s: string "hi"
+                "0176860801000000",   // v: u32 array, 2 elements
+                "02000000",
+            )
+        );
+        assert_eq!(from_bytes(&bytes).unwrap(), doc);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let block = Section::new()
+            .with("block", vec![0xabu8; 80])
+            .with("txs", Array::String(vec![vec![1; 3], vec![]]));
+        let doc = Section::new()
+            .with("i64", -5i64)
+            .with("i32", i32::MIN)
+            .with("i16", -2i16)
+            .with("i8", -1i8)
+            .with("u64", u64::MAX)
+            .with("u16", 513u16)
+            .with("f64", 1.5f64)
+            .with("untrusted", false)
+            .with("blocks", Array::Object(vec![block.clone(), Section::new()]))
+            .with("heights", Vec::<u64>::new())
+            .with("flags", vec![true, false])
+            .with("nested", Section::new().with("inner", block))
+            .with("long", "x".repeat(20000));
+        let bytes = to_bytes(&doc).unwrap();
+        let parsed = from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, doc);
+
+        assert_eq!(parsed.get("u16").unwrap().as_u64(), Some(513));
+        assert_eq!(parsed.get("i8").unwrap().as_u64(), None);
+        assert_eq!(parsed.get("i8").unwrap().as_i64(), Some(-1));
+        assert_eq!(parsed.get("u64").unwrap().as_i64(), None);
+        assert_eq!(parsed.get("untrusted").unwrap().as_bool(), Some(false));
+        assert_eq!(parsed.get("long").unwrap().as_str().unwrap().len(), 20000);
+        let blocks = parsed.get("blocks").unwrap().as_array().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert!(
+            parsed
+                .get("heights")
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .is_empty()
+        );
+        assert!(parsed.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_varint_widths() {
+        for (n, width) in [
+            (0, 1),
+            (63, 1),
+            (64, 2),
+            (16383, 2),
+            (16384, 4),
+            (1 << 30, 8),
+        ] {
+            let mut out = Vec::new();
+            write_varint(&mut out, n).unwrap();
+            assert_eq!(out.len(), width, "{n}");
+            assert_eq!(Reader(&out).varint().unwrap(), n);
+        }
+        assert_eq!(
+            write_varint(&mut Vec::new(), 1 << 62),
+            Err(EpeeError::TooLarge(1 << 62))
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let good = to_bytes(&Section::new().with("a", 1u8)).unwrap();
+        assert_eq!(from_bytes(&good[1..]), Err(EpeeError::InvalidHeader));
+        let mut v2 = good.clone();
+        v2[8] = 2;
+        assert_eq!(from_bytes(&v2), Err(EpeeError::UnsupportedVersion(2)));
+        assert_eq!(
+            from_bytes(&good[..good.len() - 1]),
+            Err(EpeeError::TooLarge(1))
+        );
+        let wide = to_bytes(&Section::new().with("a", 1u64)).unwrap();
+        assert_eq!(
+            from_bytes(&wide[..wide.len() - 1]),
+            Err(EpeeError::Truncated)
+        );
+        let mut trailing = good.clone();
+        trailing.push(0);
+        assert_eq!(from_bytes(&trailing), Err(EpeeError::TrailingBytes(1)));
+        let mut bad_type = good.clone();
+        bad_type[12] = 0x0e;
+        assert_eq!(from_bytes(&bad_type), Err(EpeeError::UnknownType(0x0e)));
+        let mut nested_array = good.clone();
+        nested_array[12] = 0x8d;
+        assert_eq!(from_bytes(&nested_array), Err(EpeeError::UnknownType(0x8d)));
+
+        // claims 2^30 entries in a few bytes
+        let mut huge = HEADER.to_vec();
+        huge.extend_from_slice(&[0x02, 0x00, 0x00, 0x01]);
+        assert_eq!(from_bytes(&huge), Err(EpeeError::TooLarge(1 << 22)));
+
+        let mut deep = Section::new();
+        for _ in 0..=MAX_DEPTH {
+            deep = Section::new().with("o", deep);
+        }
+        assert_eq!(
+            from_bytes(&to_bytes(&deep).unwrap()),
+            Err(EpeeError::TooDeep)
+        );
+
+        assert_eq!(
+            to_bytes(&Section::new().with(&"n".repeat(256), 1u8)),
+            Err(EpeeError::InvalidName)
+        );
+    }
+}
@@ -0,0 +1,5 @@
+// src/encoding/mod.rs
+// Byte and text encodings shared by keys, addresses and transactions
+pub mod base58;
+pub mod epee;
+pub mod varint;
@@ -0,0 +1,167 @@
+// src/encoding/varint.rs
+//! CryptoNote varints: little-endian base-128, high bit set on every byte
+//! but the last.
+//!
+//! Used for every length, amount and index in transactions and blocks. A
+//! `u64` takes at most 10 bytes. As in Monero's `tools::read_varint`,
+//! decoding rejects encodings with a redundant trailing zero group, so each
+//! value has exactly one valid encoding and re-serialising a parsed
+//! transaction reproduces its hash.
+use std::{fmt, io};
+
+/// Longest encoding of a `u64`
+pub const MAX_LEN: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarintError {
+    /// Input ended with the continuation bit still set
+    Truncated,
+    /// Value doesn't fit in 64 bits
+    Overflow,
+    /// Ends in a zero group, e.g. `0x80 0x00` for 0
+    NonCanonical,
+}
+
+impl fmt::Display for VarintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VarintError::Truncated => write!(f, "varint truncated"),
+            VarintError::Overflow => write!(f, "varint overflows 64 bits"),
+            VarintError::NonCanonical => write!(f, "varint not minimally encoded"),
+        }
+    }
+}
+
+impl std::error::Error for VarintError {}
+
+impl From<VarintError> for io::Error {
+    fn from(e: VarintError) -> Self {
+        let kind = match e {
+            VarintError::Truncated => io::ErrorKind::UnexpectedEof,
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, e)
+    }
+}
+
+/// Bytes `n` encodes to
+pub fn encoded_len(n: u64) -> usize {
+    (64 - n.leading_zeros() as usize).div_ceil(7).max(1)
+}
+
+/// Append the encoding of `n`
+pub fn write(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push((n as u8 & 0x7f) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+/// Value at the start of `data` and the bytes it used
+pub fn read(data: &[u8]) -> Result<(u64, usize), VarintError> {
+    let mut n = 0u64;
+    for (i, &b) in data.iter().enumerate() {
+        accumulate(&mut n, i, b)?;
+        if b & 0x80 == 0 {
+            return Ok((n, i + 1));
+        }
+    }
+    Err(VarintError::Truncated)
+}
+
+/// Write `n` to `writer`, returning the bytes written
+pub fn write_to<W: io::Write + ?Sized>(writer: &mut W, n: u64) -> io::Result<usize> {
+    let mut buf = Vec::with_capacity(MAX_LEN);
+    write(&mut buf, n);
+    writer.write_all(&buf)?;
+    Ok(buf.len())
+}
+
+/// Read one varint from `reader`, consuming only its bytes
+pub fn read_from<R: io::Read + ?Sized>(reader: &mut R) -> io::Result<u64> {
+    let mut n = 0u64;
+    for i in 0.. {
+        let mut b = [0u8];
+        reader.read_exact(&mut b)?;
+        accumulate(&mut n, i, b[0])?;
+        if b[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    Ok(n)
+}
+
+/// Fold byte `i` into `n`
+fn accumulate(n: &mut u64, i: usize, b: u8) -> Result<(), VarintError> {
+    let group = (b & 0x7f) as u64;
+    let shift = 7 * i as u32;
+    if shift >= 64 || (shift > 0 && group >> (64 - shift) != 0) {
+        return Err(VarintError::Overflow);
+    }
+    if b == 0 && i > 0 {
+        return Err(VarintError::NonCanonical);
+    }
+    *n |= group << shift;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint() {
+        let mut buf = Vec::new();
+        write(&mut buf, 0);
+        write(&mut buf, 127);
+        write(&mut buf, 128);
+        write(&mut buf, 300);
+        assert_eq!(buf, [0x00, 0x7f, 0x80, 0x01, 0xac, 0x02]);
+
+        for n in [
+            0,
+            1,
+            127,
+            128,
+            16383,
+            16384,
+            u32::MAX as u64,
+            u64::MAX - 1,
+            u64::MAX,
+        ] {
+            let mut buf = Vec::new();
+            write(&mut buf, n);
+            assert_eq!(buf.len(), encoded_len(n));
+            assert_eq!(read(&buf), Ok((n, buf.len())));
+
+            let mut io_buf = Vec::new();
+            assert_eq!(write_to(&mut io_buf, n).unwrap(), buf.len());
+            io_buf.push(0xee);
+            let mut reader = io_buf.as_slice();
+            assert_eq!(read_from(&mut reader).unwrap(), n);
+            assert_eq!(reader, [0xee]);
+        }
+        assert_eq!(encoded_len(u64::MAX), MAX_LEN);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(read(&[]), Err(VarintError::Truncated));
+        assert_eq!(read(&[0x80, 0x80]), Err(VarintError::Truncated));
+        assert_eq!(read(&[0x80, 0x00]), Err(VarintError::NonCanonical));
+        assert_eq!(read(&[0xff, 0x80, 0x00]), Err(VarintError::NonCanonical));
+        // u64::MAX is nine 0xff then 0x01; 0x02 in the last group overflows
+        let mut max = [0xff; 10];
+        max[9] = 0x01;
+        assert_eq!(read(&max), Ok((u64::MAX, 10)));
+        max[9] = 0x02;
+        assert_eq!(read(&max), Err(VarintError::Overflow));
+        assert_eq!(read(&[0x80; 11]), Err(VarintError::Overflow));
+
+        let err = read_from(&mut &[0x80u8][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let err = read_from(&mut &[0x80u8, 0x00][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
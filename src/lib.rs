@@ -1,3 +1,19 @@
- pub  mod tests;
- 
- pub mod  crypto; 
+#[cfg(test)]
+pub mod tests;
+
+pub mod address;
+pub mod block;
+pub mod chain;
+pub mod consensus;
+pub mod crypto;
+pub mod encoding;
+pub mod mnemonic;
+pub mod multisig;
+pub mod network;
+pub mod openalias;
+#[cfg(feature = "qr")]
+pub mod qr;
+pub mod tx;
+pub mod uri;
+pub mod vanity;
+pub mod wallet;
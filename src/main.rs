@@ -1,5 +1,4 @@
 // src/main.rs
-mod crypto;
 
 use monero_rust::crypto::signature::Ed25519Keypair;
 
@@ -8,7 +7,7 @@ fn main() {
     
     println!("New Monero-style Wallet Created!");
     println!("Public Key (Address base): {}", hex::encode(wallet.public_bytes()));
-    println!("Secret Key (NEVER SHARE): {}", hex::encode(wallet.signing_key.to_bytes()));
+    println!("Keypair: {wallet:?}");
     
     let tx = b"Send 10 XMR to Alice";
     let signature = wallet.sign(tx);
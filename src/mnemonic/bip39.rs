@@ -0,0 +1,215 @@
+// src/mnemonic/bip39.rs
+//! BIP39 phrases, as an alternative to Monero's native 25-word seed.
+//!
+//! BIP39 has no standard mapping to Monero keys, so this crate defines one:
+//! the 64-byte BIP39 seed (PBKDF2 of phrase and passphrase) becomes the
+//! spend key as `Hs("monero_rust/bip39/v1" || seed)`, and the view key
+//! follows as usual. Wallets using other mappings (e.g. BIP44 paths over
+//! secp256k1) will produce different keys from the same phrase.
+//!
+//! Only the English wordlist is bundled. Passphrases must be ASCII because
+//! NFKD normalisation isn't implemented.
+use std::{fmt, sync::OnceLock};
+
+use rand::{RngCore, rngs::OsRng};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::crypto::{
+    cn::hash_to_scalar, hash::pbkdf2_hmac_sha512, keys::WalletKeys, secret::SecretBytes,
+};
+
+const DOMAIN: &[u8] = b"monero_rust/bip39/v1";
+
+/// English BIP39 wordlist (shared with Polyseed)
+pub(crate) fn wordlist() -> &'static [&'static str] {
+    static ENGLISH: OnceLock<Vec<&'static str>> = OnceLock::new();
+    ENGLISH.get_or_init(|| {
+        include_str!("wordlists/bip39_english.txt")
+            .lines()
+            .collect()
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bip39Error {
+    /// Phrase must have 12, 15, 18, 21 or 24 words
+    WordCount(usize),
+    /// Entropy must be 16, 20, 24, 28 or 32 bytes
+    EntropyLength(usize),
+    /// Word isn't in the wordlist
+    UnknownWord(String),
+    /// Checksum bits don't match the entropy
+    Checksum,
+    /// Passphrase contains non-ASCII characters
+    NonAsciiPassphrase,
+}
+
+impl fmt::Display for Bip39Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bip39Error::WordCount(n) => write!(f, "invalid BIP39 word count {n}"),
+            Bip39Error::EntropyLength(n) => write!(f, "invalid BIP39 entropy length {n}"),
+            Bip39Error::UnknownWord(w) => write!(f, "unknown BIP39 word: {w}"),
+            Bip39Error::Checksum => write!(f, "BIP39 checksum mismatch"),
+            Bip39Error::NonAsciiPassphrase => write!(f, "passphrase must be ASCII"),
+        }
+    }
+}
+
+impl std::error::Error for Bip39Error {}
+
+/// Phrase encoding `entropy` (16 to 32 bytes, in steps of 4)
+pub fn from_entropy(entropy: &[u8]) -> Result<String, Bip39Error> {
+    if !(16..=32).contains(&entropy.len()) || !entropy.len().is_multiple_of(4) {
+        return Err(Bip39Error::EntropyLength(entropy.len()));
+    }
+    let checksum = Sha256::digest(entropy)[0];
+    let mut bits: Vec<bool> = entropy
+        .iter()
+        .flat_map(|b| (0..8).rev().map(move |i| b >> i & 1 == 1))
+        .collect();
+    bits.extend((0..entropy.len() / 4).map(|i| checksum >> (7 - i) & 1 == 1));
+
+    let list = wordlist();
+    let words: Vec<&str> = bits
+        .chunks(11)
+        .map(|c| list[c.iter().fold(0usize, |acc, &b| acc << 1 | b as usize)])
+        .collect();
+    Ok(words.join(" "))
+}
+
+/// Fresh phrase of `words` words
+pub fn generate(words: usize) -> Result<String, Bip39Error> {
+    if !matches!(words, 12 | 15 | 18 | 21 | 24) {
+        return Err(Bip39Error::WordCount(words));
+    }
+    let mut entropy = vec![0u8; words * 4 / 3];
+    OsRng.fill_bytes(&mut entropy);
+    let phrase = from_entropy(&entropy);
+    entropy.zeroize();
+    phrase
+}
+
+/// Entropy behind a phrase, checking its checksum
+pub fn to_entropy(phrase: &str) -> Result<Zeroizing<Vec<u8>>, Bip39Error> {
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    if !matches!(words.len(), 12 | 15 | 18 | 21 | 24) {
+        return Err(Bip39Error::WordCount(words.len()));
+    }
+    let list = wordlist();
+    let mut bits = Vec::with_capacity(words.len() * 11);
+    for word in &words {
+        let word = word.to_lowercase();
+        let index = list
+            .binary_search(&word.as_str())
+            .map_err(|_| Bip39Error::UnknownWord(word.clone()))?;
+        bits.extend((0..11).rev().map(|i| index >> i & 1 == 1));
+    }
+
+    let checksum_bits = words.len() / 3;
+    let (data, checksum) = bits.split_at(bits.len() - checksum_bits);
+    let entropy: Zeroizing<Vec<u8>> = Zeroizing::new(
+        data.chunks(8)
+            .map(|c| c.iter().fold(0u8, |acc, &b| acc << 1 | b as u8))
+            .collect(),
+    );
+    let expected = Sha256::digest(&entropy)[0] >> (8 - checksum_bits);
+    let actual = checksum.iter().fold(0u8, |acc, &b| acc << 1 | b as u8);
+    bits.zeroize();
+    if expected != actual {
+        return Err(Bip39Error::Checksum);
+    }
+    Ok(entropy)
+}
+
+/// 64-byte BIP39 seed: PBKDF2-HMAC-SHA512(phrase, "mnemonic" || passphrase, 2048)
+pub fn to_seed(phrase: &str, passphrase: &str) -> Result<SecretBytes<64>, Bip39Error> {
+    to_entropy(phrase)?;
+    if !passphrase.is_ascii() {
+        return Err(Bip39Error::NonAsciiPassphrase);
+    }
+    let mut normalized = phrase
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut salt = format!("mnemonic{passphrase}");
+    let seed = SecretBytes::new(pbkdf2_hmac_sha512(normalized.as_bytes(), salt.as_bytes(), 2048).0);
+    normalized.zeroize();
+    salt.zeroize();
+    Ok(seed)
+}
+
+/// Monero wallet keys for a BIP39 phrase, using this crate's mapping
+pub fn to_wallet_keys(phrase: &str, passphrase: &str) -> Result<WalletKeys, Bip39Error> {
+    let seed = to_seed(phrase, passphrase)?;
+    let mut buf = DOMAIN.to_vec();
+    buf.extend_from_slice(seed.as_bytes());
+    let keys = WalletKeys::from_spend_secret(hash_to_scalar(&buf));
+    buf.zeroize();
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trezor_vectors() {
+        // trezor/python-mnemonic vectors.json, passphrase "TREZOR"
+        let phrase = from_entropy(&[0u8; 16]).unwrap();
+        assert_eq!(phrase, format!("{}about", "abandon ".repeat(11)));
+        assert_eq!(
+            hex::encode(to_seed(&phrase, "TREZOR").unwrap()),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+
+        let phrase = from_entropy(&[0x7f; 16]).unwrap();
+        assert_eq!(
+            phrase,
+            "legal winner thank year wave sausage worth useful legal winner thank yellow"
+        );
+        assert_eq!(
+            hex::encode(to_seed(&phrase, "TREZOR").unwrap()),
+            "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607"
+        );
+
+        assert_eq!(
+            from_entropy(&[0xff; 32]).unwrap(),
+            format!("{}vote", "zoo ".repeat(23))
+        );
+    }
+
+    #[test]
+    fn test_entropy_roundtrip_and_errors() {
+        for words in [12, 15, 18, 21, 24] {
+            let phrase = generate(words).unwrap();
+            let entropy = to_entropy(&phrase).unwrap();
+            assert_eq!(from_entropy(&entropy).unwrap(), phrase);
+        }
+        let bad = format!("{}abandon", "abandon ".repeat(11));
+        assert_eq!(to_entropy(&bad), Err(Bip39Error::Checksum));
+        assert_eq!(to_entropy("abandon about"), Err(Bip39Error::WordCount(2)));
+        assert_eq!(from_entropy(&[0; 17]), Err(Bip39Error::EntropyLength(17)));
+        assert!(matches!(
+            to_entropy(&format!("{}monero", "abandon ".repeat(11))),
+            Err(Bip39Error::UnknownWord(_))
+        ));
+        let phrase = generate(12).unwrap();
+        assert_eq!(
+            to_seed(&phrase, "pässword").err(),
+            Some(Bip39Error::NonAsciiPassphrase)
+        );
+    }
+
+    #[test]
+    fn test_wallet_keys_depend_on_passphrase() {
+        let phrase = generate(24).unwrap();
+        let a = to_wallet_keys(&phrase, "").unwrap();
+        let b = to_wallet_keys(&phrase, "").unwrap();
+        let c = to_wallet_keys(&phrase, "extra").unwrap();
+        assert_eq!(a.spend_public(), b.spend_public());
+        assert_ne!(a.spend_public(), c.spend_public());
+    }
+}
@@ -0,0 +1,5 @@
+// src/mnemonic/mod.rs
+// Human-readable seed phrases
+pub mod bip39;
+pub mod monero;
+pub mod polyseed;
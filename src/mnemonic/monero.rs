@@ -0,0 +1,238 @@
+// src/mnemonic/monero.rs
+//! Monero's native 25-word seed (the Electrum-style scheme).
+//!
+//! The 32-byte spend key is split into eight little-endian `u32`s, each
+//! written as three words; a 25th word repeats one of the 24 chosen by a
+//! CRC-32 of their unique prefixes.
+use std::{fmt, sync::OnceLock};
+
+use crate::crypto::{keys::WalletKeys, secret::SecretBytes};
+
+/// Wordlists shipped with the crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+}
+
+impl Language {
+    fn words(self) -> &'static [&'static str] {
+        static ENGLISH: OnceLock<Vec<&'static str>> = OnceLock::new();
+        match self {
+            Language::English => ENGLISH.get_or_init(|| {
+                include_str!("wordlists/monero_english.txt")
+                    .lines()
+                    .collect()
+            }),
+        }
+    }
+
+    /// Leading characters that identify a word uniquely
+    fn prefix_len(self) -> usize {
+        match self {
+            Language::English => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MnemonicError {
+    /// Phrase must have 24 or 25 words
+    WordCount(usize),
+    /// Word isn't in the wordlist
+    UnknownWord(String),
+    /// A word triple doesn't encode a 32-bit value
+    InvalidEncoding,
+    /// The 25th word doesn't match
+    Checksum,
+}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MnemonicError::WordCount(n) => write!(f, "expected 24 or 25 words, got {n}"),
+            MnemonicError::UnknownWord(w) => write!(f, "unknown seed word: {w}"),
+            MnemonicError::InvalidEncoding => write!(f, "seed words don't encode a key"),
+            MnemonicError::Checksum => write!(f, "seed checksum word mismatch"),
+        }
+    }
+}
+
+impl std::error::Error for MnemonicError {}
+
+fn prefix(word: &str, len: usize) -> &str {
+    match word.char_indices().nth(len) {
+        Some((i, _)) => &word[..i],
+        None => word,
+    }
+}
+
+/// Index of the word repeated as the checksum
+fn checksum_index(words: &[&str], language: Language) -> usize {
+    let mut joined = String::new();
+    for word in words {
+        joined.push_str(prefix(word, language.prefix_len()));
+    }
+    crc32(joined.as_bytes()) as usize % words.len()
+}
+
+/// CRC-32 (IEEE, reflected), as Boost's `crc_32_type`
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// 25-word phrase for a spend key
+pub fn encode(key: &[u8; 32], language: Language) -> String {
+    let list = language.words();
+    let n = list.len() as u32;
+    let mut words = Vec::with_capacity(25);
+    for chunk in key.chunks_exact(4) {
+        let val = u32::from_le_bytes(chunk.try_into().unwrap());
+        let w1 = val % n;
+        let w2 = (val / n + w1) % n;
+        let w3 = (val / n / n + w2) % n;
+        words.extend([list[w1 as usize], list[w2 as usize], list[w3 as usize]]);
+    }
+    words.push(words[checksum_index(&words, language)]);
+    words.join(" ")
+}
+
+/// Spend key from a 24- or 25-word phrase (the checksum is optional)
+///
+/// Words are matched on their unique prefix, so truncated words restore too.
+pub fn decode(phrase: &str, language: Language) -> Result<SecretBytes<32>, MnemonicError> {
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    if words.len() != 24 && words.len() != 25 {
+        return Err(MnemonicError::WordCount(words.len()));
+    }
+
+    let list = language.words();
+    let plen = language.prefix_len();
+    let index_of = |word: &str| {
+        let word = word.to_lowercase();
+        list.iter()
+            .position(|w| prefix(w, plen) == prefix(&word, plen))
+            .ok_or(MnemonicError::UnknownWord(word))
+    };
+
+    let n = list.len() as u64;
+    let mut key = SecretBytes::new([0u8; 32]);
+    for (chunk, triple) in key
+        .as_mut_bytes()
+        .chunks_exact_mut(4)
+        .zip(words[..24].chunks_exact(3))
+    {
+        let w1 = index_of(triple[0])? as u64;
+        let w2 = index_of(triple[1])? as u64;
+        let w3 = index_of(triple[2])? as u64;
+        let val = w1 + n * ((n - w1 + w2) % n) + n * n * ((n - w2 + w3) % n);
+        let val = u32::try_from(val).map_err(|_| MnemonicError::InvalidEncoding)?;
+        chunk.copy_from_slice(&val.to_le_bytes());
+    }
+
+    if words.len() == 25 {
+        let expected = words[checksum_index(&words[..24], language)];
+        if prefix(&expected.to_lowercase(), plen) != prefix(&words[24].to_lowercase(), plen) {
+            return Err(MnemonicError::Checksum);
+        }
+    }
+    Ok(key)
+}
+
+/// Restore full wallet keys from a seed phrase
+pub fn to_wallet_keys(phrase: &str, language: Language) -> Result<WalletKeys, MnemonicError> {
+    Ok(WalletKeys::from_seed(*decode(phrase, language)?.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Wallet used by Monero's tests/functional_tests/transfer.py
+    const SEED: &str = "velvet lymph giddy number token physics poetry unquoted nibs useful \
+                        sabotage limits benches lifestyle eden nitrogen anvil fewest avoid \
+                        batch vials washing fences goat unquoted";
+
+    #[test]
+    fn test_wordlist_shape() {
+        let words = Language::English.words();
+        assert_eq!(words.len(), 1626);
+        assert!(words.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let key = decode(SEED, Language::English).unwrap();
+        assert_eq!(
+            encode(key.as_bytes(), Language::English),
+            SEED.split_whitespace().collect::<Vec<_>>().join(" ")
+        );
+
+        let random = [0xa5u8; 32];
+        let phrase = encode(&random, Language::English);
+        assert_eq!(phrase.split(' ').count(), 25);
+        assert_eq!(
+            decode(&phrase, Language::English).unwrap().as_bytes(),
+            &random
+        );
+    }
+
+    #[test]
+    fn test_prefixes_and_errors() {
+        let key = decode(SEED, Language::English).unwrap();
+        let short: Vec<&str> = SEED.split_whitespace().map(|w| &w[..3]).collect();
+        assert_eq!(
+            decode(&short.join(" "), Language::English)
+                .unwrap()
+                .as_bytes(),
+            key.as_bytes()
+        );
+
+        let without_checksum: Vec<&str> = SEED.split_whitespace().take(24).collect();
+        assert_eq!(
+            decode(&without_checksum.join(" "), Language::English)
+                .unwrap()
+                .as_bytes(),
+            key.as_bytes()
+        );
+
+        let bad_checksum = SEED.replace("goat unquoted", "goat velvet");
+        assert_eq!(
+            decode(&bad_checksum, Language::English).err(),
+            Some(MnemonicError::Checksum)
+        );
+        assert_eq!(
+            decode("velvet lymph", Language::English).err(),
+            Some(MnemonicError::WordCount(2))
+        );
+        assert_eq!(
+            decode(&SEED.replace("velvet", "quartz"), Language::English).err(),
+            Some(MnemonicError::UnknownWord("quartz".into()))
+        );
+    }
+
+    #[test]
+    fn test_restores_reference_wallet() {
+        // public keys of 42ey1afDFnn4886T7196doS9GPMzexD9gXpsZJDwVjeRVdFCSoHnv7KPbBe...
+        let keys = to_wallet_keys(SEED, Language::English).unwrap();
+        assert_eq!(
+            hex::encode(keys.spend_public().compress().as_bytes()),
+            "1b3bd040020d3712ab84992b773d0a965134eb2df0392fb84af95de8a17be2ab"
+        );
+        assert_eq!(
+            hex::encode(keys.view_public().compress().as_bytes()),
+            "231c9bf8341c6a870d92e3fb98063a90a355fb8dbf74a8561b9d7f9273247e99"
+        );
+    }
+}
@@ -0,0 +1,291 @@
+// src/mnemonic/polyseed.rs
+//! Polyseed: 16-word seeds with an embedded wallet birthday.
+//!
+//! Each of the 16 BIP39-list words is a coefficient of a polynomial over
+//! GF(2048). Word 0 is the checksum (the polynomial evaluates to zero at
+//! x = 2), and the other 15 each carry 10 secret bits plus one bit of the
+//! 5 feature bits and 10 birthday bits. See tevador/polyseed.
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rand::{RngCore, rngs::OsRng};
+use zeroize::Zeroize;
+
+use super::bip39::wordlist;
+use crate::crypto::{hash::pbkdf2_hmac_sha256, keys::WalletKeys, secret::SecretBytes};
+
+const WORDS: usize = 16;
+const DATA_WORDS: usize = 15;
+const SECRET_BITS: usize = 150;
+const SECRET_SIZE: usize = 19;
+const DATE_BITS: u32 = 10;
+const FEATURE_BITS: u32 = 5;
+/// 1 November 2021, the earliest representable birthday
+const EPOCH: u64 = 1_635_768_000;
+/// One twelfth of a year
+const TIME_STEP: u64 = 2_629_746;
+const KDF_ROUNDS: u32 = 10_000;
+/// Feature bit marking a passphrase-encrypted seed
+const ENCRYPTED: u8 = 16;
+/// Feature bits reserved by the spec and never set
+const RESERVED: u8 = 8;
+/// Coin id mixed into the first data word and the key salt
+const COIN_MONERO: u16 = 0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolyseedError {
+    /// Phrase must have exactly 16 words
+    WordCount(usize),
+    /// Word isn't in the wordlist
+    UnknownWord(String),
+    /// Polynomial check failed (typo or swapped words)
+    Checksum,
+    /// Encrypted seeds or reserved feature bits aren't supported
+    UnsupportedFeatures(u8),
+}
+
+impl fmt::Display for PolyseedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolyseedError::WordCount(n) => write!(f, "polyseed needs 16 words, got {n}"),
+            PolyseedError::UnknownWord(w) => write!(f, "unknown polyseed word: {w}"),
+            PolyseedError::Checksum => write!(f, "polyseed checksum mismatch"),
+            PolyseedError::UnsupportedFeatures(bits) => {
+                write!(f, "unsupported polyseed features {bits:#07b}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolyseedError {}
+
+/// x·2 in GF(2^11) modulo x^11 + x^2 + 1
+fn gf_mul2(x: u16) -> u16 {
+    if x & 0x400 != 0 {
+        ((x << 1) ^ 0x805) & 0x7ff
+    } else {
+        x << 1
+    }
+}
+
+/// Evaluate the polynomial at x = 2 (Horner's rule)
+fn poly_eval(coeff: &[u16; WORDS]) -> u16 {
+    let mut result = coeff[WORDS - 1];
+    for &c in coeff[..WORDS - 1].iter().rev() {
+        result = gf_mul2(result) ^ c;
+    }
+    result
+}
+
+/// A decoded Polyseed: 150-bit secret, birthday and feature bits
+#[derive(Clone, PartialEq, Eq)]
+pub struct Polyseed {
+    secret: [u8; SECRET_SIZE],
+    birthday: u16,
+    features: u8,
+}
+
+impl Polyseed {
+    /// Fresh seed dated now, with user feature bits `features` (0..=7)
+    pub fn generate(features: u8) -> Result<Self, PolyseedError> {
+        if features > 7 {
+            return Err(PolyseedError::UnsupportedFeatures(features));
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(EPOCH);
+        let mut secret = [0u8; SECRET_SIZE];
+        OsRng.fill_bytes(&mut secret);
+        secret[SECRET_SIZE - 1] &= 0x3f;
+        Ok(Self {
+            secret,
+            birthday: (now.saturating_sub(EPOCH) / TIME_STEP) as u16 & 0x3ff,
+            features,
+        })
+    }
+
+    /// Unix time the wallet was created, rounded down to a month
+    pub fn birthday(&self) -> u64 {
+        EPOCH + self.birthday as u64 * TIME_STEP
+    }
+
+    pub fn features(&self) -> u8 {
+        self.features
+    }
+
+    fn to_poly(&self) -> [u16; WORDS] {
+        let extra = (self.features as u16) << DATE_BITS | self.birthday;
+        let bit = |i: usize| {
+            // the last byte only carries its low 6 bits
+            let (byte, shift) = if i < 144 {
+                (i / 8, 7 - i % 8)
+            } else {
+                (SECRET_SIZE - 1, SECRET_BITS - 1 - i)
+            };
+            (self.secret[byte] >> shift & 1) as u16
+        };
+        let mut coeff = [0u16; WORDS];
+        for (w, c) in coeff[1..].iter_mut().enumerate() {
+            let secret_bits = (0..10).fold(0, |acc, j| acc << 1 | bit(10 * w + j));
+            let extra_bit = extra >> (DATE_BITS + FEATURE_BITS - 1 - w as u32) & 1;
+            *c = secret_bits << 1 | extra_bit;
+        }
+        coeff[0] = poly_eval(&coeff);
+        coeff[1] ^= COIN_MONERO;
+        coeff
+    }
+
+    /// The 16-word phrase
+    pub fn encode(&self) -> String {
+        let list = wordlist();
+        let words: Vec<&str> = self.to_poly().iter().map(|&c| list[c as usize]).collect();
+        words.join(" ")
+    }
+
+    /// Parse a phrase; words may be shortened to their first four letters
+    pub fn decode(phrase: &str) -> Result<Self, PolyseedError> {
+        let words: Vec<&str> = phrase.split_whitespace().collect();
+        if words.len() != WORDS {
+            return Err(PolyseedError::WordCount(words.len()));
+        }
+        let list = wordlist();
+        let mut coeff = [0u16; WORDS];
+        for (c, word) in coeff.iter_mut().zip(&words) {
+            let word = word.to_lowercase();
+            let short = word.chars().take(4).collect::<String>();
+            let index = list
+                .binary_search(&word.as_str())
+                .ok()
+                .or_else(|| {
+                    list.iter()
+                        .position(|w| w.chars().take(4).eq(short.chars()))
+                })
+                .ok_or_else(|| PolyseedError::UnknownWord(word.clone()))?;
+            *c = index as u16;
+        }
+
+        coeff[1] ^= COIN_MONERO;
+        if poly_eval(&coeff) != 0 {
+            return Err(PolyseedError::Checksum);
+        }
+
+        let mut extra = 0u16;
+        let mut bits = Vec::with_capacity(SECRET_BITS);
+        for &c in &coeff[1..] {
+            extra = extra << 1 | (c & 1);
+            bits.extend((0..10).rev().map(|j| (c >> (j + 1) & 1) as u8));
+        }
+        debug_assert_eq!(bits.len(), DATA_WORDS * 10);
+        let mut secret = [0u8; SECRET_SIZE];
+        for (byte, chunk) in secret.iter_mut().zip(bits.chunks(8)) {
+            *byte = chunk.iter().fold(0, |acc, b| acc << 1 | b);
+        }
+        bits.zeroize();
+
+        let features = (extra >> DATE_BITS) as u8;
+        if features & (ENCRYPTED | RESERVED) != 0 {
+            secret.zeroize();
+            return Err(PolyseedError::UnsupportedFeatures(features));
+        }
+        Ok(Self {
+            secret,
+            birthday: extra & 0x3ff,
+            features,
+        })
+    }
+
+    /// 32-byte key: PBKDF2-HMAC-SHA256 of the secret, salted with the
+    /// coin, birthday and features
+    pub fn key(&self) -> SecretBytes<32> {
+        let mut salt = [0u8; 32];
+        salt[..12].copy_from_slice(b"POLYSEED key");
+        salt[13..16].fill(0xff);
+        salt[16..20].copy_from_slice(&(COIN_MONERO as u32).to_le_bytes());
+        salt[20..24].copy_from_slice(&(self.birthday as u32).to_le_bytes());
+        salt[24..28].copy_from_slice(&(self.features as u32).to_le_bytes());
+        let mut password = [0u8; 32];
+        password[..SECRET_SIZE].copy_from_slice(&self.secret);
+        let key = SecretBytes::new(pbkdf2_hmac_sha256(&password, &salt, KDF_ROUNDS).0);
+        password.zeroize();
+        key
+    }
+
+    /// Wallet keys, with the key reduced into the spend secret
+    pub fn to_wallet_keys(&self) -> WalletKeys {
+        WalletKeys::from_seed(*self.key().as_bytes())
+    }
+}
+
+impl fmt::Debug for Polyseed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Polyseed")
+            .field("birthday", &self.birthday())
+            .field("features", &self.features)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Polyseed {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example phrase from the tevador/polyseed README
+    const PHRASE: &str = "raven tail swear infant grief assist regular lamp \
+                          duck valid someone little harsh puppy airport language";
+
+    #[test]
+    fn test_reference_phrase() {
+        let seed = Polyseed::decode(PHRASE).unwrap();
+        assert_eq!(seed.features(), 0);
+        assert_eq!(seed.birthday(), EPOCH + TIME_STEP);
+        let canonical: Vec<&str> = PHRASE.split_whitespace().collect();
+        assert_eq!(seed.encode(), canonical.join(" "));
+
+        let short: Vec<String> = canonical
+            .iter()
+            .map(|w| w.chars().take(4).collect())
+            .collect();
+        assert_eq!(Polyseed::decode(&short.join(" ")).unwrap(), seed);
+    }
+
+    #[test]
+    fn test_roundtrip_and_keys() {
+        let seed = Polyseed::generate(3).unwrap();
+        let decoded = Polyseed::decode(&seed.encode()).unwrap();
+        assert_eq!(decoded, seed);
+        assert_eq!(decoded.features(), 3);
+        assert_eq!(decoded.key().as_bytes(), seed.key().as_bytes());
+        assert_eq!(
+            decoded.to_wallet_keys().spend_public(),
+            seed.to_wallet_keys().spend_public()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut words: Vec<&str> = PHRASE.split_whitespace().collect();
+        words.swap(3, 4);
+        assert_eq!(
+            Polyseed::decode(&words.join(" ")),
+            Err(PolyseedError::Checksum)
+        );
+        assert_eq!(
+            Polyseed::decode("raven tail"),
+            Err(PolyseedError::WordCount(2))
+        );
+        assert!(matches!(
+            Polyseed::decode(&PHRASE.replace("raven", "qqqq")),
+            Err(PolyseedError::UnknownWord(_))
+        ));
+        assert!(Polyseed::generate(8).is_err());
+    }
+}
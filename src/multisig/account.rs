@@ -0,0 +1,561 @@
+// src/multisig/account.rs
+//! Multisig key exchange (kex), after Monero's `multisig_account`.
+//!
+//! Every signer blinds its wallet keys as `k = Hs(b || "Multisig")` and
+//! publishes `K = k·G` together with its share of the common view key in a
+//! signed round-1 message. For `M`-of-`N` there are `N - M + 1` such key
+//! rounds: each one extends every key shared by a set of signers to one more
+//! signer through a Diffie-Hellman exchange, until every key is shared by
+//! `N - M + 1` signers and so known to any `M` of them. The group spend key
+//! is `Σ c_X·X` over those final keys, with MuSig-style coefficients
+//! `c_X = Hs("Multisig_aggregation_key" || sorted keys || X)` so no signer
+//! can choose its key to cancel the others'. A final round has everyone
+//! publish the key they computed, so a disagreement surfaces before funds
+//! are sent to it.
+//!
+//! Messages carry private view key material: send them over a private,
+//! authenticated channel.
+use curve25519_dalek::{
+    EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT, edwards::CompressedEdwardsY,
+    traits::IsIdentity,
+};
+
+use zeroize::Zeroize;
+
+use super::MultisigError;
+use crate::crypto::{
+    cn::hash_to_scalar,
+    signature::schnorr::{self, SchnorrSignature},
+};
+
+const BLIND_DOMAIN: &[u8] = b"Multisig";
+const AGG_DOMAIN: &[u8] = b"Multisig_aggregation_key";
+const MSG_DOMAIN: &[u8] = b"multisig_kex_msg";
+const KEX_DOMAIN: &[u8] = b"multisig_kex_derivation";
+
+/// `Hs(key || "Multisig")`, used for both spend and view shares
+fn blind(secret: &Scalar) -> Scalar {
+    let mut buf = secret.as_bytes().to_vec();
+    buf.extend_from_slice(BLIND_DOMAIN);
+    hash_to_scalar(&buf)
+}
+
+/// One signer's message for one kex round
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KexMessage {
+    pub round: u32,
+    /// Sender's base public key, which also signs the message
+    pub signer: EdwardsPoint,
+    /// Round 1: the base key; later key rounds: derived keys; final round:
+    /// the group spend key
+    pub keys: Vec<EdwardsPoint>,
+    /// Sender's common view key share (round 1 only)
+    pub view_share: Option<Scalar>,
+    pub signature: SchnorrSignature,
+}
+
+impl KexMessage {
+    fn signing_hash(
+        round: u32,
+        signer: &EdwardsPoint,
+        keys: &[EdwardsPoint],
+        view_share: Option<&Scalar>,
+    ) -> [u8; 32] {
+        let mut buf = MSG_DOMAIN.to_vec();
+        buf.extend_from_slice(&round.to_le_bytes());
+        buf.extend_from_slice(signer.compress().as_bytes());
+        for key in keys {
+            buf.extend_from_slice(key.compress().as_bytes());
+        }
+        if let Some(share) = view_share {
+            buf.extend_from_slice(share.as_bytes());
+        }
+        hash_to_scalar(&buf).to_bytes()
+    }
+
+    fn new(round: u32, base: &Scalar, keys: Vec<EdwardsPoint>, view_share: Option<Scalar>) -> Self {
+        let signer = base * ED25519_BASEPOINT_POINT;
+        let hash = Self::signing_hash(round, &signer, &keys, view_share.as_ref());
+        Self {
+            round,
+            signer,
+            keys,
+            view_share,
+            signature: schnorr::sign(&hash, base),
+        }
+    }
+
+    fn verify(&self) -> bool {
+        let hash = Self::signing_hash(
+            self.round,
+            &self.signer,
+            &self.keys,
+            self.view_share.as_ref(),
+        );
+        schnorr::verify(&hash, &self.signer, &self.signature)
+    }
+
+    /// `round || signer || n || keys || has_share [|| share] || signature`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(4 + 32 + 4 + 32 * self.keys.len() + 33 + 64);
+        out.extend_from_slice(&self.round.to_le_bytes());
+        out.extend_from_slice(self.signer.compress().as_bytes());
+        out.extend_from_slice(&(self.keys.len() as u32).to_le_bytes());
+        for key in &self.keys {
+            out.extend_from_slice(key.compress().as_bytes());
+        }
+        match &self.view_share {
+            Some(share) => {
+                out.push(1);
+                out.extend_from_slice(share.as_bytes());
+            }
+            None => out.push(0),
+        }
+        out.extend_from_slice(&self.signature.to_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MultisigError> {
+        let mut reader = Reader(bytes);
+        let round = u32::from_le_bytes(reader.take()?);
+        let signer = reader.point()?;
+        let n = u32::from_le_bytes(reader.take()?) as usize;
+        if n > reader.0.len() / 32 {
+            return Err(MultisigError::Malformed);
+        }
+        let keys = (0..n).map(|_| reader.point()).collect::<Result<_, _>>()?;
+        let view_share = match reader.take::<1>()? {
+            [0] => None,
+            [1] => Some(reader.scalar()?),
+            _ => return Err(MultisigError::Malformed),
+        };
+        let signature =
+            SchnorrSignature::from_bytes(&reader.take()?).ok_or(MultisigError::Malformed)?;
+        if !reader.0.is_empty() {
+            return Err(MultisigError::Malformed);
+        }
+        Ok(Self {
+            round,
+            signer,
+            keys,
+            view_share,
+            signature,
+        })
+    }
+}
+
+/// Cursor over a message buffer
+pub(super) struct Reader<'a>(pub(super) &'a [u8]);
+
+impl Reader<'_> {
+    pub(super) fn take<const N: usize>(&mut self) -> Result<[u8; N], MultisigError> {
+        if self.0.len() < N {
+            return Err(MultisigError::Malformed);
+        }
+        let (head, rest) = self.0.split_at(N);
+        self.0 = rest;
+        Ok(head.try_into().unwrap())
+    }
+
+    pub(super) fn point(&mut self) -> Result<EdwardsPoint, MultisigError> {
+        CompressedEdwardsY(self.take()?)
+            .decompress()
+            .ok_or(MultisigError::Malformed)
+    }
+
+    pub(super) fn scalar(&mut self) -> Result<Scalar, MultisigError> {
+        Option::from(Scalar::from_canonical_bytes(self.take()?)).ok_or(MultisigError::Malformed)
+    }
+}
+
+/// One signer's view of an M-of-N multisig wallet under construction
+pub struct Account {
+    threshold: usize,
+    signers: usize,
+    base_spend: Scalar,
+    base_view: Scalar,
+    /// Round whose messages we're waiting for; `None` once complete
+    round: Option<u32>,
+    /// Base public keys of all signers, sorted by encoding
+    signer_keys: Vec<EdwardsPoint>,
+    /// Secrets we publish in the current round, with their public keys
+    round_keys: Vec<(EdwardsPoint, Scalar)>,
+    /// Component keys of the group key, sorted, with the signers holding each
+    group_keys: Vec<(EdwardsPoint, Vec<EdwardsPoint>)>,
+    /// Aggregation coefficient `c_X` of each group key component
+    coefficients: Vec<Scalar>,
+    /// Our weighted component secrets `c_X·x_X`, keyed by `X`
+    shares: Vec<(EdwardsPoint, Scalar)>,
+    view_secret: Scalar,
+    spend_public: Option<EdwardsPoint>,
+}
+
+impl Account {
+    /// Start a kex from this signer's wallet spend and view secrets
+    pub fn new(
+        threshold: usize,
+        signers: usize,
+        spend_secret: &Scalar,
+        view_secret: &Scalar,
+    ) -> Result<Self, MultisigError> {
+        if signers < 2 || threshold == 0 || threshold > signers {
+            return Err(MultisigError::InvalidThreshold { threshold, signers });
+        }
+        let base_spend = blind(spend_secret);
+        Ok(Self {
+            threshold,
+            signers,
+            base_spend,
+            base_view: blind(view_secret),
+            round: Some(1),
+            signer_keys: Vec::new(),
+            round_keys: vec![(base_spend * ED25519_BASEPOINT_POINT, base_spend)],
+            group_keys: Vec::new(),
+            coefficients: Vec::new(),
+            shares: Vec::new(),
+            view_secret: Scalar::ZERO,
+            spend_public: None,
+        })
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn signers(&self) -> usize {
+        self.signers
+    }
+
+    /// Rounds that exchange keys; one verification round follows them
+    pub fn key_rounds(&self) -> u32 {
+        (self.signers - self.threshold + 1) as u32
+    }
+
+    /// This signer's base public key, its identity during kex
+    pub fn base_public(&self) -> EdwardsPoint {
+        self.base_spend * ED25519_BASEPOINT_POINT
+    }
+
+    /// True once every round has completed
+    pub fn is_ready(&self) -> bool {
+        self.round.is_none()
+    }
+
+    /// Number of the round the next `update` expects
+    pub fn round(&self) -> Option<u32> {
+        self.round
+    }
+
+    /// Group spend public key, once computed
+    pub fn spend_public(&self) -> Option<&EdwardsPoint> {
+        self.spend_public.as_ref()
+    }
+
+    /// Shared view secret `Σ Hs(aᵢ || "Multisig")`, once known
+    pub fn view_secret(&self) -> Option<&Scalar> {
+        self.spend_public.as_ref().map(|_| &self.view_secret)
+    }
+
+    /// Sum of this signer's weighted component secrets
+    ///
+    /// In an N-of-N wallet this is `cᵢ·kᵢ` and the shares of all signers add
+    /// up to the group spend secret. With `M < N` components are held by
+    /// several signers, so signing assigns each to one of them instead.
+    pub fn spend_share(&self) -> Option<Scalar> {
+        self.spend_public
+            .as_ref()
+            .map(|_| self.shares.iter().map(|(_, x)| x).sum())
+    }
+
+    /// Base public keys of every signer, sorted
+    pub fn signer_keys(&self) -> &[EdwardsPoint] {
+        &self.signer_keys
+    }
+
+    /// Components `X` of the group key `Σ c_X·X`, each with its holders
+    pub(super) fn group_keys(&self) -> &[(EdwardsPoint, Vec<EdwardsPoint>)] {
+        &self.group_keys
+    }
+
+    /// `c_X` for each entry of `group_keys`
+    pub(super) fn coefficients(&self) -> &[Scalar] {
+        &self.coefficients
+    }
+
+    /// Our weighted secrets `c_X·x_X` for the components we hold
+    pub(super) fn shares(&self) -> &[(EdwardsPoint, Scalar)] {
+        &self.shares
+    }
+
+    /// Message to broadcast for the current round
+    pub fn kex_message(&self) -> Result<KexMessage, MultisigError> {
+        match self.round {
+            None => Err(MultisigError::AlreadyComplete),
+            Some(1) => Ok(KexMessage::new(
+                1,
+                &self.base_spend,
+                vec![self.base_public()],
+                Some(self.base_view),
+            )),
+            Some(round) if round <= self.key_rounds() => Ok(KexMessage::new(
+                round,
+                &self.base_spend,
+                self.round_keys.iter().map(|(k, _)| *k).collect(),
+                None,
+            )),
+            Some(round) => Ok(KexMessage::new(
+                round,
+                &self.base_spend,
+                vec![self.spend_public.expect("set after key rounds")],
+                None,
+            )),
+        }
+    }
+
+    /// Feed the other signers' messages for the current round
+    ///
+    /// Our own message may be included; it's recognised and skipped.
+    pub fn update(&mut self, messages: &[KexMessage]) -> Result<(), MultisigError> {
+        let round = self.round.ok_or(MultisigError::AlreadyComplete)?;
+        let me = self.base_public();
+        let mut seen = vec![me];
+        let mut others = Vec::with_capacity(self.signers - 1);
+        for msg in messages {
+            if msg.round != round {
+                return Err(MultisigError::WrongRound {
+                    expected: round,
+                    got: msg.round,
+                });
+            }
+            if msg.signer == me {
+                continue;
+            }
+            let outsider = round > 1 && !self.signer_keys.contains(&msg.signer);
+            if outsider || seen.contains(&msg.signer) {
+                return Err(MultisigError::SignerMismatch);
+            }
+            if !msg.verify() {
+                return Err(MultisigError::BadSignature);
+            }
+            seen.push(msg.signer);
+            others.push(msg);
+        }
+        if others.len() != self.signers - 1 {
+            return Err(MultisigError::SignerMismatch);
+        }
+
+        if round > self.key_rounds() {
+            let spend = self.spend_public.expect("set after key rounds");
+            if others.iter().any(|m| m.keys != [spend]) {
+                return Err(MultisigError::KeyMismatch);
+            }
+            self.round = None;
+            return Ok(());
+        }
+
+        if round == 1 {
+            let mut view = self.base_view;
+            for msg in &others {
+                if msg.keys != [msg.signer] {
+                    return Err(MultisigError::Malformed);
+                }
+                view += msg.view_share.ok_or(MultisigError::Malformed)?;
+            }
+            seen.sort_by_key(|p| p.compress().to_bytes());
+            self.signer_keys = seen;
+            self.view_secret = view;
+        }
+
+        // every key of round r is shared by exactly r signers
+        let mut published: Vec<(EdwardsPoint, Vec<EdwardsPoint>)> = self
+            .round_keys
+            .iter()
+            .map(|(k, _)| (*k, vec![me]))
+            .collect();
+        for msg in &others {
+            for key in &msg.keys {
+                if key.is_identity() || !key.is_torsion_free() {
+                    return Err(MultisigError::Malformed);
+                }
+                match published.iter_mut().find(|(k, _)| k == key) {
+                    Some((_, holders)) if !holders.contains(&msg.signer) => {
+                        holders.push(msg.signer)
+                    }
+                    Some(_) => return Err(MultisigError::Malformed),
+                    None => published.push((*key, vec![msg.signer])),
+                }
+            }
+        }
+        if published.iter().any(|(_, h)| h.len() != round as usize) {
+            return Err(MultisigError::KeyMismatch);
+        }
+
+        if round < self.key_rounds() {
+            self.round_keys = self.next_round_keys(&published);
+        } else {
+            published.sort_by_key(|(k, _)| k.compress().to_bytes());
+            for (_, holders) in &mut published {
+                holders.sort_by_key(|p| p.compress().to_bytes());
+            }
+            let components: Vec<EdwardsPoint> = published.iter().map(|(k, _)| *k).collect();
+            let coeff = coefficients(&components);
+            self.shares = self
+                .round_keys
+                .iter()
+                .map(|(k, x)| {
+                    let i = components.iter().position(|c| c == k).unwrap();
+                    (*k, coeff[i] * x)
+                })
+                .collect();
+            self.spend_public = Some(components.iter().zip(&coeff).map(|(k, c)| c * k).sum());
+            self.group_keys = published;
+            self.coefficients = coeff;
+            self.round_keys.clear();
+        }
+        self.round = Some(round + 1);
+        Ok(())
+    }
+
+    /// Secrets for the next round: one per (key we hold, signer who doesn't)
+    ///
+    /// Extending a key `X` shared by set `S` to signer `j ∉ S` gives
+    /// `Hs(x·K_j) = Hs(k_j·X)`, which members of `S` and `j` both compute.
+    fn next_round_keys(
+        &self,
+        published: &[(EdwardsPoint, Vec<EdwardsPoint>)],
+    ) -> Vec<(EdwardsPoint, Scalar)> {
+        let me = self.base_public();
+        let mut secrets: Vec<Scalar> = Vec::new();
+        for (key, holders) in published {
+            let dhs: Vec<EdwardsPoint> = match self.round_keys.iter().find(|(k, _)| k == key) {
+                Some((_, x)) => self
+                    .signer_keys
+                    .iter()
+                    .filter(|k| !holders.contains(k))
+                    .map(|k| x * k)
+                    .collect(),
+                None if !holders.contains(&me) => vec![self.base_spend * key],
+                None => Vec::new(),
+            };
+            for dh in dhs {
+                let mut buf = KEX_DOMAIN.to_vec();
+                buf.extend_from_slice(dh.compress().as_bytes());
+                let secret = hash_to_scalar(&buf);
+                if !secrets.contains(&secret) {
+                    secrets.push(secret);
+                }
+            }
+        }
+        secrets
+            .into_iter()
+            .map(|x| (x * ED25519_BASEPOINT_POINT, x))
+            .collect()
+    }
+}
+
+impl Drop for Account {
+    fn drop(&mut self) {
+        self.base_spend.zeroize();
+        self.base_view.zeroize();
+        self.view_secret.zeroize();
+        for (_, x) in self.round_keys.iter_mut().chain(&mut self.shares) {
+            x.zeroize();
+        }
+    }
+}
+
+/// Aggregation coefficients `c_X` for sorted component keys
+fn coefficients(sorted: &[EdwardsPoint]) -> Vec<Scalar> {
+    let mut prefix = AGG_DOMAIN.to_vec();
+    for key in sorted {
+        prefix.extend_from_slice(key.compress().as_bytes());
+    }
+    sorted
+        .iter()
+        .map(|key| {
+            let mut buf = prefix.clone();
+            buf.extend_from_slice(key.compress().as_bytes());
+            hash_to_scalar(&buf)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    fn run_kex(n: usize) -> Vec<Account> {
+        let mut accounts: Vec<Account> = (0..n)
+            .map(|_| {
+                Account::new(
+                    n,
+                    n,
+                    &Scalar::random(&mut OsRng),
+                    &Scalar::random(&mut OsRng),
+                )
+                .unwrap()
+            })
+            .collect();
+        while !accounts[0].is_ready() {
+            let msgs: Vec<KexMessage> = accounts
+                .iter()
+                .map(|a| KexMessage::from_bytes(&a.kex_message().unwrap().to_bytes()).unwrap())
+                .collect();
+            for account in &mut accounts {
+                account.update(&msgs).unwrap();
+            }
+        }
+        accounts
+    }
+
+    #[test]
+    fn test_n_of_n_kex() {
+        for n in [2, 3, 5] {
+            let accounts = run_kex(n);
+            let spend = *accounts[0].spend_public().unwrap();
+            let mut share_sum = Scalar::ZERO;
+            for a in &accounts {
+                assert!(a.is_ready());
+                assert_eq!(a.spend_public(), Some(&spend));
+                assert_eq!(a.view_secret(), accounts[0].view_secret());
+                share_sum += a.spend_share().unwrap();
+            }
+            // the shares add up to the group spend secret
+            assert_eq!(share_sum * ED25519_BASEPOINT_POINT, spend);
+        }
+    }
+
+    #[test]
+    fn test_kex_rejects_bad_input() {
+        let x = || Scalar::random(&mut OsRng);
+        assert!(Account::new(1, 1, &x(), &x()).is_err());
+
+        let mut a = Account::new(2, 2, &x(), &x()).unwrap();
+        let b = Account::new(2, 2, &x(), &x()).unwrap();
+        let c = Account::new(2, 2, &x(), &x()).unwrap();
+
+        let mut forged = b.kex_message().unwrap();
+        forged.view_share = Some(x());
+        assert_eq!(a.update(&[forged]), Err(MultisigError::BadSignature));
+
+        let msgs = [b.kex_message().unwrap(), c.kex_message().unwrap()];
+        assert_eq!(a.update(&msgs), Err(MultisigError::SignerMismatch));
+        assert_eq!(a.update(&[]), Err(MultisigError::SignerMismatch));
+
+        a.update(&msgs[..1]).unwrap();
+        assert_eq!(
+            a.update(&msgs[..1]),
+            Err(MultisigError::WrongRound {
+                expected: 2,
+                got: 1
+            })
+        );
+        let mut bytes = a.kex_message().unwrap().to_bytes();
+        bytes.pop();
+        assert_eq!(
+            KexMessage::from_bytes(&bytes),
+            Err(MultisigError::Malformed)
+        );
+    }
+}
@@ -0,0 +1,63 @@
+// src/multisig/mod.rs
+// Monero-style multisig wallets: key exchange and cooperative signing
+use std::fmt;
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+
+mod account;
+mod sign;
+
+pub use account::{Account, KexMessage};
+pub use sign::{NonceMessage, PartialSignature, SignRequest, Signer};
+
+/// Why a multisig operation failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultisigError {
+    /// Threshold must be in 1..=signers, with at least two signers
+    InvalidThreshold { threshold: usize, signers: usize },
+    /// Message is for a different round than the account is in
+    WrongRound { expected: u32, got: u32 },
+    /// Message signature doesn't verify
+    BadSignature,
+    /// Wrong number of distinct signers, or an unknown/duplicate signer
+    SignerMismatch,
+    /// Message bytes couldn't be parsed
+    Malformed,
+    /// Signers computed different group keys
+    KeyMismatch,
+    /// Key exchange already finished
+    AlreadyComplete,
+    /// Key exchange hasn't finished, so the wallet can't sign yet
+    NotReady,
+    /// This signer's nonces were already used for a signature
+    NonceReused,
+    /// The partial signature from this signer doesn't verify
+    InvalidPartial(CompressedEdwardsY),
+}
+
+impl fmt::Display for MultisigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultisigError::InvalidThreshold { threshold, signers } => {
+                write!(f, "invalid {threshold}-of-{signers} multisig")
+            }
+            MultisigError::WrongRound { expected, got } => {
+                write!(f, "expected round {expected} message, got round {got}")
+            }
+            MultisigError::BadSignature => write!(f, "kex message signature is invalid"),
+            MultisigError::SignerMismatch => write!(f, "unexpected set of signers"),
+            MultisigError::Malformed => write!(f, "malformed multisig message"),
+            MultisigError::KeyMismatch => write!(f, "signers disagree on the group key"),
+            MultisigError::AlreadyComplete => write!(f, "key exchange already complete"),
+            MultisigError::NotReady => write!(f, "key exchange not finished"),
+            MultisigError::NonceReused => write!(f, "signing nonces already used"),
+            MultisigError::InvalidPartial(signer) => write!(
+                f,
+                "invalid partial signature from {}",
+                hex::encode(signer.as_bytes())
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MultisigError {}
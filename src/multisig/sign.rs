@@ -0,0 +1,545 @@
+// src/multisig/sign.rs
+//! Cooperative CLSAG signing by `M` signers of a multisig wallet.
+//!
+//! All participants agree on a [`SignRequest`] for the input being spent,
+//! then run two rounds:
+//!
+//! 1. Each creates a [`Signer`] and broadcasts its [`NonceMessage`]: two
+//!    nonce pairs `(αⱼ·G, αⱼ·Hp(P))` plus partial key images `w_X·Hp(P)` for
+//!    every group key component `X` it holds.
+//! 2. With all nonce messages in hand each signer derives the same CLSAG
+//!    skeleton and returns a [`PartialSignature`]. Any participant then calls
+//!    [`Signer::aggregate`] to check every partial and assemble the final
+//!    signature.
+//!
+//! The effective nonce is `α₁ + b·α₂` with the binding factor
+//! `b = Hs(message || all nonces)`, as in MuSig2, so nonces may be sent
+//! before the others are known. Each group key component is charged to the
+//! first participant (by sorted base key) holding it; that same first
+//! participant also covers the publicly known one-time key offset and
+//! commitment mask. The decoy responses are derived from `b`, so nobody has
+//! to distribute them.
+use curve25519_dalek::{EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT};
+use rand::rngs::OsRng;
+use zeroize::Zeroize;
+
+use super::{Account, MultisigError, account::Reader};
+use crate::crypto::{
+    cn::{hash_to_point, hash_to_scalar},
+    ring::clsag::{self, ClsagSignature, Skeleton},
+};
+
+const BINDING_DOMAIN: &[u8] = b"multisig_clsag_binding";
+const DECOY_DOMAIN: &[u8] = b"multisig_clsag_decoy";
+
+/// The input every participant signs; all must hold identical copies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignRequest {
+    /// CLSAG message (the transaction's pre-MLSAG hash)
+    pub message: [u8; 32],
+    pub ring: Vec<EdwardsPoint>,
+    pub commitments: Vec<EdwardsPoint>,
+    pub pseudo_out: EdwardsPoint,
+    /// Position of the real output in the ring
+    pub index: usize,
+    /// `Hs(8·a·R || i)` (plus any subaddress offset): the real output's key
+    /// is `key_offset·G + B` for the group spend key `B`
+    pub key_offset: Scalar,
+    /// Input mask minus pseudo-output mask
+    pub mask_diff: Scalar,
+}
+
+/// Round-1 broadcast: nonces and partial key images
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceMessage {
+    /// Sender's kex base public key
+    pub signer: EdwardsPoint,
+    /// `α₁·G, α₁·Hp(P), α₂·G, α₂·Hp(P)`
+    pub nonces: [EdwardsPoint; 4],
+    /// `(X, w_X·Hp(P))` for each group key component the sender holds
+    pub key_images: Vec<(EdwardsPoint, EdwardsPoint)>,
+}
+
+impl NonceMessage {
+    /// `signer || nonces || n || (X || image)*n`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(32 * 5 + 4 + 64 * self.key_images.len());
+        out.extend_from_slice(self.signer.compress().as_bytes());
+        for nonce in &self.nonces {
+            out.extend_from_slice(nonce.compress().as_bytes());
+        }
+        out.extend_from_slice(&(self.key_images.len() as u32).to_le_bytes());
+        for (key, image) in &self.key_images {
+            out.extend_from_slice(key.compress().as_bytes());
+            out.extend_from_slice(image.compress().as_bytes());
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MultisigError> {
+        let mut reader = Reader(bytes);
+        let signer = reader.point()?;
+        let nonces = [
+            reader.point()?,
+            reader.point()?,
+            reader.point()?,
+            reader.point()?,
+        ];
+        let n = u32::from_le_bytes(reader.take()?) as usize;
+        if n > reader.0.len() / 64 {
+            return Err(MultisigError::Malformed);
+        }
+        let key_images = (0..n)
+            .map(|_| Ok((reader.point()?, reader.point()?)))
+            .collect::<Result<_, MultisigError>>()?;
+        if !reader.0.is_empty() {
+            return Err(MultisigError::Malformed);
+        }
+        Ok(Self {
+            signer,
+            nonces,
+            key_images,
+        })
+    }
+}
+
+/// Round-2 reply: one signer's share of the real response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialSignature {
+    pub signer: EdwardsPoint,
+    pub response: Scalar,
+}
+
+impl PartialSignature {
+    /// `signer || response`
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        out[..32].copy_from_slice(self.signer.compress().as_bytes());
+        out[32..].copy_from_slice(self.response.as_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MultisigError> {
+        let mut reader = Reader(bytes);
+        let signer = reader.point()?;
+        let response = reader.scalar()?;
+        if !reader.0.is_empty() {
+            return Err(MultisigError::Malformed);
+        }
+        Ok(Self { signer, response })
+    }
+}
+
+/// Everything the participants derive identically from the nonce messages
+struct Session<'a> {
+    /// Nonce messages sorted by signer
+    participants: Vec<&'a NonceMessage>,
+    binding: Scalar,
+    skeleton: Skeleton,
+    /// For each group key component, the index of the participant charged
+    assigned: Vec<usize>,
+}
+
+/// One participant's signing state for one input; unused nonces are wiped
+pub struct Signer<'a> {
+    account: &'a Account,
+    request: SignRequest,
+    hp: EdwardsPoint,
+    nonces: Option<[Scalar; 2]>,
+    message: NonceMessage,
+}
+
+impl<'a> Signer<'a> {
+    /// Check the request against the wallet and draw fresh nonces
+    pub fn new(account: &'a Account, request: SignRequest) -> Result<Self, MultisigError> {
+        let spend = account.spend_public().ok_or(MultisigError::NotReady)?;
+        let real = request
+            .ring
+            .get(request.index)
+            .ok_or(MultisigError::Malformed)?;
+        if request.commitments.len() != request.ring.len() {
+            return Err(MultisigError::Malformed);
+        }
+        if request.key_offset * ED25519_BASEPOINT_POINT + spend != *real {
+            return Err(MultisigError::KeyMismatch);
+        }
+
+        let hp = hash_to_point(real.compress().as_bytes());
+        let alpha = [Scalar::random(&mut OsRng), Scalar::random(&mut OsRng)];
+        let message = NonceMessage {
+            signer: account.base_public(),
+            nonces: [
+                alpha[0] * ED25519_BASEPOINT_POINT,
+                alpha[0] * hp,
+                alpha[1] * ED25519_BASEPOINT_POINT,
+                alpha[1] * hp,
+            ],
+            key_images: account.shares().iter().map(|(k, w)| (*k, w * hp)).collect(),
+        };
+        Ok(Self {
+            account,
+            request,
+            hp,
+            nonces: Some(alpha),
+            message,
+        })
+    }
+
+    /// Our round-1 broadcast
+    pub fn nonce_message(&self) -> &NonceMessage {
+        &self.message
+    }
+
+    /// Produce our partial signature from every participant's nonce message
+    ///
+    /// Our own message must be among them. The nonces are consumed: a second
+    /// call fails rather than risk leaking the key share.
+    pub fn sign(&mut self, messages: &[NonceMessage]) -> Result<PartialSignature, MultisigError> {
+        if self.nonces.is_none() {
+            return Err(MultisigError::NonceReused);
+        }
+        let session = self.session(messages)?;
+        let mut alpha = self.nonces.take().expect("checked above");
+        let me = session
+            .participants
+            .iter()
+            .position(|m| m.signer == self.message.signer)
+            .expect("session includes us");
+
+        let mut secret = Scalar::ZERO;
+        for ((key, _), owner) in self.account.group_keys().iter().zip(&session.assigned) {
+            if *owner == me {
+                let (_, w) = self
+                    .account
+                    .shares()
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .unwrap();
+                secret += w;
+            }
+        }
+        let sk = &session.skeleton;
+        let mut response = alpha[0] + session.binding * alpha[1] - sk.c * sk.mu_p * secret;
+        if me == 0 {
+            response -= sk.c * self.public_offset(sk);
+        }
+        alpha.zeroize();
+        secret.zeroize();
+        Ok(PartialSignature {
+            signer: self.message.signer,
+            response,
+        })
+    }
+
+    /// Check each partial signature and assemble the CLSAG
+    pub fn aggregate(
+        &self,
+        messages: &[NonceMessage],
+        partials: &[PartialSignature],
+    ) -> Result<ClsagSignature, MultisigError> {
+        let session = self.session(messages)?;
+        if partials.len() != session.participants.len() {
+            return Err(MultisigError::SignerMismatch);
+        }
+        let sk = &session.skeleton;
+        let b = session.binding;
+        let mut response = Scalar::ZERO;
+        for (i, msg) in session.participants.iter().enumerate() {
+            let partial = partials
+                .iter()
+                .find(|p| p.signer == msg.signer)
+                .ok_or(MultisigError::SignerMismatch)?;
+
+            // s_i·G = A₁ + b·A₂ - c·mu_P·Σ w_X·G, and likewise over Hp(P)
+            let mut owed_g = EdwardsPoint::default();
+            let mut owed_hp = EdwardsPoint::default();
+            for (((key, _), owner), coeff) in self
+                .account
+                .group_keys()
+                .iter()
+                .zip(&session.assigned)
+                .zip(self.account.coefficients())
+            {
+                if *owner == i {
+                    owed_g += coeff * key;
+                    owed_hp += msg.key_images.iter().find(|(k, _)| k == key).unwrap().1;
+                }
+            }
+            let mut expect_g = msg.nonces[0] + b * msg.nonces[2] - sk.c * sk.mu_p * owed_g;
+            let mut expect_hp = msg.nonces[1] + b * msg.nonces[3] - sk.c * sk.mu_p * owed_hp;
+            if i == 0 {
+                let offset = sk.c * self.public_offset(sk);
+                expect_g -= offset * ED25519_BASEPOINT_POINT;
+                expect_hp -= offset * self.hp;
+            }
+            if partial.response * ED25519_BASEPOINT_POINT != expect_g
+                || partial.response * self.hp != expect_hp
+            {
+                return Err(MultisigError::InvalidPartial(msg.signer.compress()));
+            }
+            response += partial.response;
+        }
+
+        let req = &self.request;
+        let Session { skeleton, .. } = session;
+        let sig = skeleton.finish(req.index, response);
+        if !clsag::verify(
+            &req.message,
+            &req.ring,
+            &req.commitments,
+            &req.pseudo_out,
+            &sig,
+        ) {
+            return Err(MultisigError::KeyMismatch);
+        }
+        Ok(sig)
+    }
+
+    /// `mu_P·key_offset + mu_C·z`, the part of the response everyone knows
+    fn public_offset(&self, sk: &Skeleton) -> Scalar {
+        sk.mu_p * self.request.key_offset + sk.mu_c * self.request.mask_diff
+    }
+
+    fn session<'m>(&self, messages: &'m [NonceMessage]) -> Result<Session<'m>, MultisigError> {
+        let account = self.account;
+        let mut participants: Vec<&NonceMessage> = messages.iter().collect();
+        participants.sort_by_key(|m| m.signer.compress().to_bytes());
+        if participants.windows(2).any(|w| w[0].signer == w[1].signer)
+            || participants
+                .iter()
+                .any(|m| !account.signer_keys().contains(&m.signer))
+            || participants.len() < account.threshold()
+            || !participants.iter().any(|m| **m == self.message)
+        {
+            return Err(MultisigError::SignerMismatch);
+        }
+
+        // every component must be covered, and its holders must agree on
+        // its partial key image
+        let mut image = self.request.key_offset * self.hp;
+        let mut assigned = Vec::with_capacity(account.group_keys().len());
+        for (key, holders) in account.group_keys() {
+            let mut owner = None;
+            let mut part = None;
+            for (i, msg) in participants.iter().enumerate() {
+                let listed = msg.key_images.iter().find(|(k, _)| k == key);
+                match (holders.contains(&msg.signer), listed) {
+                    (false, None) => {}
+                    (true, Some((_, img))) if part.is_none_or(|p| p == *img) => {
+                        owner.get_or_insert(i);
+                        part = Some(*img);
+                    }
+                    (true, Some(_)) => return Err(MultisigError::KeyMismatch),
+                    _ => return Err(MultisigError::Malformed),
+                }
+            }
+            image += part.ok_or(MultisigError::SignerMismatch)?;
+            assigned.push(owner.expect("set with part"));
+        }
+        if participants.iter().any(|m| {
+            m.key_images
+                .iter()
+                .any(|(k, _)| !account.group_keys().iter().any(|(g, _)| g == k))
+        }) {
+            return Err(MultisigError::Malformed);
+        }
+
+        let req = &self.request;
+        let mut buf = BINDING_DOMAIN.to_vec();
+        buf.extend_from_slice(&req.message);
+        for msg in &participants {
+            buf.extend_from_slice(msg.signer.compress().as_bytes());
+            for nonce in &msg.nonces {
+                buf.extend_from_slice(nonce.compress().as_bytes());
+            }
+        }
+        let binding = hash_to_scalar(&buf);
+
+        let (l, r) = participants.iter().fold(
+            Default::default(),
+            |(l, r): (EdwardsPoint, EdwardsPoint), m| {
+                (
+                    l + m.nonces[0] + binding * m.nonces[2],
+                    r + m.nonces[1] + binding * m.nonces[3],
+                )
+            },
+        );
+        let decoys = (0..req.ring.len() as u32)
+            .map(|j| {
+                let mut buf = DECOY_DOMAIN.to_vec();
+                buf.extend_from_slice(binding.as_bytes());
+                buf.extend_from_slice(&j.to_le_bytes());
+                hash_to_scalar(&buf)
+            })
+            .collect();
+        let skeleton = clsag::skeleton(
+            &req.message,
+            &req.ring,
+            &req.commitments,
+            &req.pseudo_out,
+            &image,
+            &(req.mask_diff * self.hp),
+            (&l, &r),
+            decoys,
+            req.index,
+        )
+        .map_err(|_| MultisigError::Malformed)?;
+
+        Ok(Session {
+            participants,
+            binding,
+            skeleton,
+            assigned,
+        })
+    }
+}
+
+impl Drop for Signer<'_> {
+    fn drop(&mut self) {
+        if let Some(alpha) = &mut self.nonces {
+            alpha.zeroize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::multisig::KexMessage;
+
+    fn run_kex(m: usize, n: usize) -> Vec<Account> {
+        let mut accounts: Vec<Account> = (0..n)
+            .map(|_| {
+                Account::new(
+                    m,
+                    n,
+                    &Scalar::random(&mut OsRng),
+                    &Scalar::random(&mut OsRng),
+                )
+                .unwrap()
+            })
+            .collect();
+        while !accounts[0].is_ready() {
+            let msgs: Vec<KexMessage> = accounts.iter().map(|a| a.kex_message().unwrap()).collect();
+            for account in &mut accounts {
+                account.update(&msgs).unwrap();
+            }
+        }
+        accounts
+    }
+
+    fn request(spend: &EdwardsPoint) -> SignRequest {
+        let g = ED25519_BASEPOINT_POINT;
+        let random_point = || Scalar::random(&mut OsRng) * g;
+        let index = 3;
+        let key_offset = Scalar::random(&mut OsRng);
+        let mut ring: Vec<EdwardsPoint> = (0..11).map(|_| random_point()).collect();
+        ring[index] = key_offset * g + spend;
+        let commitments: Vec<EdwardsPoint> = (0..11).map(|_| random_point()).collect();
+        let mask_diff = Scalar::random(&mut OsRng);
+        SignRequest {
+            message: [7u8; 32],
+            pseudo_out: commitments[index] - mask_diff * g,
+            ring,
+            commitments,
+            index,
+            key_offset,
+            mask_diff,
+        }
+    }
+
+    /// Sign with the accounts at `who`, passing every message through bytes
+    fn sign_with(accounts: &[Account], who: &[usize], req: &SignRequest) -> ClsagSignature {
+        let mut signers: Vec<Signer> = who
+            .iter()
+            .map(|&i| Signer::new(&accounts[i], req.clone()).unwrap())
+            .collect();
+        let nonces: Vec<NonceMessage> = signers
+            .iter()
+            .map(|s| NonceMessage::from_bytes(&s.nonce_message().to_bytes()).unwrap())
+            .collect();
+        let partials: Vec<PartialSignature> = signers
+            .iter_mut()
+            .map(|s| {
+                let p = s.sign(&nonces).unwrap();
+                PartialSignature::from_bytes(&p.to_bytes()).unwrap()
+            })
+            .collect();
+        assert_eq!(signers[0].sign(&nonces), Err(MultisigError::NonceReused));
+        signers[0].aggregate(&nonces, &partials).unwrap()
+    }
+
+    #[test]
+    fn test_threshold_signing() {
+        for (m, n, who) in [
+            (2, 2, vec![0, 1]),
+            (2, 3, vec![0, 2]),
+            (2, 3, vec![2, 1]),
+            (3, 3, vec![0, 1, 2]),
+            (3, 4, vec![3, 0, 1]),
+        ] {
+            let accounts = run_kex(m, n);
+            let spend = *accounts[0].spend_public().unwrap();
+            for a in &accounts {
+                assert_eq!(a.spend_public(), Some(&spend));
+            }
+            let req = request(&spend);
+            let sig = sign_with(&accounts, &who, &req);
+            assert!(clsag::verify(
+                &req.message,
+                &req.ring,
+                &req.commitments,
+                &req.pseudo_out,
+                &sig
+            ));
+        }
+    }
+
+    #[test]
+    fn test_key_image_matches_group_secret() {
+        // 2-of-2 shares add up to the spend secret, giving the expected image
+        let accounts = run_kex(2, 2);
+        let spend = *accounts[0].spend_public().unwrap();
+        let req = request(&spend);
+        let sig = sign_with(&accounts, &[0, 1], &req);
+        let secret = req.key_offset
+            + accounts[0].spend_share().unwrap()
+            + accounts[1].spend_share().unwrap();
+        let hp = hash_to_point(req.ring[req.index].compress().as_bytes());
+        assert_eq!(sig.key_image.point(), Some(secret * hp));
+    }
+
+    #[test]
+    fn test_signing_rejects_bad_input() {
+        let accounts = run_kex(2, 3);
+        let spend = *accounts[0].spend_public().unwrap();
+        let req = request(&spend);
+
+        let mut wrong = req.clone();
+        wrong.key_offset += Scalar::ONE;
+        assert!(matches!(
+            Signer::new(&accounts[0], wrong),
+            Err(MultisigError::KeyMismatch)
+        ));
+
+        let mut a = Signer::new(&accounts[0], req.clone()).unwrap();
+        let mut b = Signer::new(&accounts[1], req.clone()).unwrap();
+        let nonces = vec![a.nonce_message().clone(), b.nonce_message().clone()];
+        assert_eq!(a.sign(&nonces[..1]), Err(MultisigError::SignerMismatch));
+
+        let pa = a.sign(&nonces).unwrap();
+        let mut pb = b.sign(&nonces).unwrap();
+        pb.response += Scalar::ONE;
+        assert_eq!(
+            a.aggregate(&nonces, &[pa, pb]),
+            Err(MultisigError::InvalidPartial(pb.signer.compress()))
+        );
+
+        let mut bytes = nonces[0].to_bytes();
+        bytes.push(0);
+        assert_eq!(
+            NonceMessage::from_bytes(&bytes),
+            Err(MultisigError::Malformed)
+        );
+    }
+}
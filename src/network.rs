@@ -0,0 +1,138 @@
+// src/network.rs
+//! Which Monero network the crate is talking to.
+//!
+//! Mainnet, testnet and stagenet share every rule except a handful of
+//! constants: the address prefixes, the genesis block and the default
+//! ports. Everything network-dependent takes a [`Network`] instead of
+//! hard-coding the mainnet values.
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Network {
+    #[default]
+    Mainnet,
+    /// Public test network with worthless coins; forks early
+    Testnet,
+    /// Mirrors mainnet's hard-fork schedule, for staging releases
+    Stagenet,
+}
+
+impl Network {
+    pub const ALL: [Network; 3] = [Network::Mainnet, Network::Testnet, Network::Stagenet];
+
+    /// Address prefix of standard addresses (`4`, `9`/`A`, `5` on mainnet,
+    /// testnet, stagenet)
+    pub fn address_prefix(self) -> u64 {
+        match self {
+            Network::Mainnet => 18,
+            Network::Testnet => 53,
+            Network::Stagenet => 24,
+        }
+    }
+
+    /// Address prefix of integrated addresses
+    pub fn integrated_prefix(self) -> u64 {
+        match self {
+            Network::Mainnet => 19,
+            Network::Testnet => 54,
+            Network::Stagenet => 25,
+        }
+    }
+
+    /// Address prefix of subaddresses
+    pub fn subaddress_prefix(self) -> u64 {
+        match self {
+            Network::Mainnet => 42,
+            Network::Testnet => 63,
+            Network::Stagenet => 36,
+        }
+    }
+
+    /// Port `monerod` serves its RPC interface on by default
+    pub fn default_rpc_port(self) -> u16 {
+        match self {
+            Network::Mainnet => 18081,
+            Network::Testnet => 28081,
+            Network::Stagenet => 38081,
+        }
+    }
+
+    /// Port `monerod` listens for peers on by default
+    pub fn default_p2p_port(self) -> u16 {
+        match self {
+            Network::Mainnet => 18080,
+            Network::Testnet => 28080,
+            Network::Stagenet => 38080,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Stagenet => "stagenet",
+        })
+    }
+}
+
+/// Unrecognised network name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownNetwork(pub String);
+
+impl fmt::Display for UnknownNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown network {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownNetwork {}
+
+/// Case-insensitive, as `monerod --testnet`/`--stagenet` users type it
+impl FromStr for Network {
+    type Err = UnknownNetwork;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Network::ALL
+            .into_iter()
+            .find(|n| n.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| UnknownNetwork(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        for network in Network::ALL {
+            assert_eq!(network.to_string().parse::<Network>(), Ok(network));
+        }
+        assert_eq!("Stagenet".parse::<Network>(), Ok(Network::Stagenet));
+        assert_eq!(
+            "regtest".parse::<Network>(),
+            Err(UnknownNetwork("regtest".into()))
+        );
+        assert_eq!(Network::default(), Network::Mainnet);
+    }
+
+    #[test]
+    fn test_prefixes_distinct() {
+        let mut prefixes: Vec<u64> = Network::ALL
+            .iter()
+            .flat_map(|n| {
+                [
+                    n.address_prefix(),
+                    n.integrated_prefix(),
+                    n.subaddress_prefix(),
+                ]
+            })
+            .collect();
+        prefixes.sort();
+        prefixes.dedup();
+        assert_eq!(prefixes.len(), 9);
+        assert_eq!(Network::Testnet.default_rpc_port(), 28081);
+    }
+}
@@ -0,0 +1,400 @@
+// src/openalias.rs
+//! OpenAlias: Monero addresses published in DNS.
+//!
+//! A name like `donate.getmonero.org` carries a TXT record such as
+//! `oa1:xmr recipient_address=4...; recipient_name=Monero Development;`.
+//! Anyone able to tamper with DNS can swap the address, so lookups report
+//! whether the answer was DNSSEC-validated and [`resolve_verified`] refuses
+//! anything that wasn't.
+//!
+//! Resolution is generic over [`TxtResolver`] so any async DNS client can be
+//! plugged in; the `openalias` feature provides [`DnsResolver`], built on
+//! hickory and tokio.
+use std::{fmt, future::Future};
+
+use crate::address::{Address, AddressError};
+use crate::network::Network;
+
+/// Record prefix for Monero entries
+pub const XMR_PREFIX: &str = "oa1:xmr";
+
+/// Why a name didn't resolve to an address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenAliasError {
+    /// Not a DNS name (OpenAlias names always contain a dot)
+    InvalidName,
+    /// No `oa1:xmr` record for the name
+    NotFound,
+    /// Several `oa1:xmr` records; the user has to choose
+    Ambiguous(usize),
+    /// Record has no `recipient_address`
+    MissingAddress,
+    Address(AddressError),
+    /// Address belongs to another network
+    WrongNetwork(Network),
+    /// The answer wasn't DNSSEC-validated
+    Unverified,
+    /// Lookup itself failed
+    Dns(String),
+}
+
+impl fmt::Display for OpenAliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenAliasError::InvalidName => write!(f, "not an OpenAlias name"),
+            OpenAliasError::NotFound => write!(f, "no {XMR_PREFIX} record found"),
+            OpenAliasError::Ambiguous(n) => write!(f, "{n} {XMR_PREFIX} records found"),
+            OpenAliasError::MissingAddress => write!(f, "record has no recipient_address"),
+            OpenAliasError::Address(e) => write!(f, "invalid recipient address: {e}"),
+            OpenAliasError::WrongNetwork(n) => write!(f, "record holds a {n} address"),
+            OpenAliasError::Unverified => write!(f, "DNS answer not validated by DNSSEC"),
+            OpenAliasError::Dns(e) => write!(f, "DNS lookup failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for OpenAliasError {}
+
+impl From<AddressError> for OpenAliasError {
+    fn from(e: AddressError) -> Self {
+        OpenAliasError::Address(e)
+    }
+}
+
+/// TXT strings for a name and whether DNSSEC vouched for them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxtAnswer {
+    pub records: Vec<String>,
+    pub dnssec_validated: bool,
+}
+
+/// Async source of TXT records
+///
+/// A missing name should come back as an empty answer rather than an error.
+pub trait TxtResolver {
+    fn lookup_txt(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<TxtAnswer, OpenAliasError>> + Send;
+}
+
+/// A resolved alias
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenAlias {
+    pub address: Address,
+    pub recipient_name: Option<String>,
+    pub description: Option<String>,
+    pub dnssec_validated: bool,
+}
+
+/// DNS name for an alias; `user@example.com` is looked up as
+/// `user.example.com`, as the reference wallet does
+pub fn alias_to_name(alias: &str) -> Result<String, OpenAliasError> {
+    let name = alias.trim().trim_end_matches('.').replacen('@', ".", 1);
+    if !name.contains('.') || name.starts_with('.') || name.contains("..") {
+        return Err(OpenAliasError::InvalidName);
+    }
+    Ok(name)
+}
+
+/// Fields of one `oa1:xmr` record; None for records of other currencies
+pub fn parse_record(record: &str) -> Option<Result<OpenAlias, OpenAliasError>> {
+    let body = record.trim().strip_prefix(XMR_PREFIX)?;
+    if !body.is_empty() && !body.starts_with(char::is_whitespace) {
+        // e.g. "oa1:xmrx"
+        return None;
+    }
+
+    let mut address = None;
+    let mut recipient_name = None;
+    let mut description = None;
+    for field in body.split(';') {
+        let Some((key, value)) = field.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value)
+            .to_string();
+        match key.trim() {
+            "recipient_address" => address = Some(value),
+            "recipient_name" => recipient_name = Some(value),
+            "tx_description" => description = Some(value),
+            _ => {}
+        }
+    }
+
+    let Some(address) = address else {
+        return Some(Err(OpenAliasError::MissingAddress));
+    };
+    Some(
+        address
+            .parse()
+            .map_err(Into::into)
+            .map(|address| OpenAlias {
+                address,
+                recipient_name,
+                description,
+                dnssec_validated: false,
+            }),
+    )
+}
+
+/// Look up `alias` and return its single Monero address for `network`
+///
+/// The result carries the DNSSEC flag but isn't required to be validated;
+/// see [`resolve_verified`].
+pub async fn resolve<R: TxtResolver>(
+    resolver: &R,
+    alias: &str,
+    network: Network,
+) -> Result<OpenAlias, OpenAliasError> {
+    let answer = resolver.lookup_txt(&alias_to_name(alias)?).await?;
+    let mut found: Vec<_> = answer
+        .records
+        .iter()
+        .filter_map(|r| parse_record(r))
+        .collect();
+    let mut alias = match found.len() {
+        0 => return Err(OpenAliasError::NotFound),
+        1 => found.pop().unwrap()?,
+        n => return Err(OpenAliasError::Ambiguous(n)),
+    };
+    if alias.address.network() != network {
+        return Err(OpenAliasError::WrongNetwork(alias.address.network()));
+    }
+    alias.dnssec_validated = answer.dnssec_validated;
+    Ok(alias)
+}
+
+/// As [`resolve`], but only trust DNSSEC-validated answers
+pub async fn resolve_verified<R: TxtResolver>(
+    resolver: &R,
+    alias: &str,
+    network: Network,
+) -> Result<Address, OpenAliasError> {
+    let alias = resolve(resolver, alias, network).await?;
+    if !alias.dnssec_validated {
+        return Err(OpenAliasError::Unverified);
+    }
+    Ok(alias.address)
+}
+
+#[cfg(feature = "openalias")]
+pub use dns::DnsResolver;
+
+#[cfg(feature = "openalias")]
+mod dns {
+    use hickory_resolver::{
+        TokioAsyncResolver,
+        config::{ResolverConfig, ResolverOpts},
+        error::{ResolveError, ResolveErrorKind},
+        system_conf::read_system_conf,
+    };
+
+    use super::{OpenAliasError, TxtAnswer, TxtResolver};
+
+    /// Hickory-backed resolver; needs a tokio runtime
+    ///
+    /// Every name is first queried with DNSSEC validation on. Unsigned zones
+    /// fail that, so they're retried without and flagged as unvalidated.
+    pub struct DnsResolver {
+        validating: TokioAsyncResolver,
+        plain: TokioAsyncResolver,
+    }
+
+    impl DnsResolver {
+        /// Use the system's configured nameservers
+        pub fn from_system_conf() -> Result<Self, OpenAliasError> {
+            let (config, opts) = read_system_conf().map_err(dns_error)?;
+            Ok(Self::new(config, opts))
+        }
+
+        pub fn new(config: ResolverConfig, opts: ResolverOpts) -> Self {
+            let mut validating_opts = opts.clone();
+            validating_opts.validate = true;
+            let mut plain_opts = opts;
+            plain_opts.validate = false;
+            Self {
+                validating: TokioAsyncResolver::tokio(config.clone(), validating_opts),
+                plain: TokioAsyncResolver::tokio(config, plain_opts),
+            }
+        }
+    }
+
+    impl Default for DnsResolver {
+        /// Cloudflare's public resolvers
+        fn default() -> Self {
+            Self::new(ResolverConfig::cloudflare(), ResolverOpts::default())
+        }
+    }
+
+    fn dns_error(e: ResolveError) -> OpenAliasError {
+        OpenAliasError::Dns(e.to_string())
+    }
+
+    async fn lookup(
+        resolver: &TokioAsyncResolver,
+        name: &str,
+    ) -> Result<Vec<String>, ResolveError> {
+        let lookup = match resolver.txt_lookup(name).await {
+            Ok(lookup) => lookup,
+            Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                return Ok(Vec::new());
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(lookup
+            .iter()
+            .map(|txt| {
+                // a record longer than 255 bytes is split into several strings
+                let bytes: Vec<u8> = txt
+                    .txt_data()
+                    .iter()
+                    .flat_map(|s| s.iter().copied())
+                    .collect();
+                String::from_utf8_lossy(&bytes).into_owned()
+            })
+            .collect())
+    }
+
+    impl TxtResolver for DnsResolver {
+        async fn lookup_txt(&self, name: &str) -> Result<TxtAnswer, OpenAliasError> {
+            if let Ok(records) = lookup(&self.validating, name).await
+                && !records.is_empty()
+            {
+                return Ok(TxtAnswer {
+                    records,
+                    dnssec_validated: true,
+                });
+            }
+            let records = lookup(&self.plain, name).await.map_err(dns_error)?;
+            Ok(TxtAnswer {
+                records,
+                dnssec_validated: false,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    const ADDRESS: &str = "42ey1afDFnn4886T7196doS9GPMzexD9gXpsZJDwVjeRVdFCSoHnv7KPbBeGpzJBzHRCAs9\
+                           UxqeoyFQMYbqSWYTfJJQAWDm";
+
+    struct Fixed(TxtAnswer);
+
+    impl TxtResolver for Fixed {
+        async fn lookup_txt(&self, name: &str) -> Result<TxtAnswer, OpenAliasError> {
+            assert_eq!(name, "donate.example.org");
+            Ok(self.0.clone())
+        }
+    }
+
+    /// The mock never suspends, so one poll finishes it
+    fn block_on<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(out) => out,
+            Poll::Pending => panic!("mock resolver suspended"),
+        }
+    }
+
+    fn answer(records: &[&str], dnssec_validated: bool) -> Fixed {
+        Fixed(TxtAnswer {
+            records: records.iter().map(|r| r.to_string()).collect(),
+            dnssec_validated,
+        })
+    }
+
+    #[test]
+    fn test_parse_record() {
+        let record = format!(
+            "oa1:xmr recipient_address={ADDRESS}; recipient_name=\"Donations\"; \
+             tx_description=Thanks!;"
+        );
+        let alias = parse_record(&record).unwrap().unwrap();
+        assert_eq!(alias.address.to_string(), ADDRESS);
+        assert_eq!(alias.recipient_name.as_deref(), Some("Donations"));
+        assert_eq!(alias.description.as_deref(), Some("Thanks!"));
+
+        assert!(parse_record("oa1:btc recipient_address=1abc;").is_none());
+        assert!(parse_record("oa1:xmrx recipient_address=1abc;").is_none());
+        assert!(parse_record("v=spf1 -all").is_none());
+        assert_eq!(
+            parse_record("oa1:xmr recipient_name=x;"),
+            Some(Err(OpenAliasError::MissingAddress))
+        );
+        assert!(matches!(
+            parse_record("oa1:xmr recipient_address=4abc;"),
+            Some(Err(OpenAliasError::Address(_)))
+        ));
+    }
+
+    #[test]
+    fn test_alias_to_name() {
+        assert_eq!(
+            alias_to_name("donate@example.org").unwrap(),
+            "donate.example.org"
+        );
+        assert_eq!(
+            alias_to_name("donate.example.org.").unwrap(),
+            "donate.example.org"
+        );
+        assert_eq!(alias_to_name("localhost"), Err(OpenAliasError::InvalidName));
+        assert_eq!(alias_to_name("a..b"), Err(OpenAliasError::InvalidName));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let record = format!("oa1:xmr recipient_address={ADDRESS};");
+        let signed = answer(&["v=spf1 -all", &record], true);
+        let alias = block_on(resolve(&signed, "donate@example.org", Network::Mainnet)).unwrap();
+        assert!(alias.dnssec_validated);
+        assert_eq!(
+            block_on(resolve_verified(
+                &signed,
+                "donate.example.org",
+                Network::Mainnet
+            )),
+            Ok(alias.address)
+        );
+        assert_eq!(
+            block_on(resolve(&signed, "donate.example.org", Network::Testnet)),
+            Err(OpenAliasError::WrongNetwork(Network::Mainnet))
+        );
+
+        let unsigned = answer(&[&record], false);
+        assert!(
+            !block_on(resolve(&unsigned, "donate.example.org", Network::Mainnet))
+                .unwrap()
+                .dnssec_validated
+        );
+        assert_eq!(
+            block_on(resolve_verified(
+                &unsigned,
+                "donate.example.org",
+                Network::Mainnet
+            )),
+            Err(OpenAliasError::Unverified)
+        );
+
+        let none = answer(&["v=spf1 -all"], true);
+        assert_eq!(
+            block_on(resolve(&none, "donate.example.org", Network::Mainnet)),
+            Err(OpenAliasError::NotFound)
+        );
+        let two = answer(&[&record, &record], true);
+        assert_eq!(
+            block_on(resolve(&two, "donate.example.org", Network::Mainnet)),
+            Err(OpenAliasError::Ambiguous(2))
+        );
+    }
+}
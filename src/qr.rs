@@ -0,0 +1,166 @@
+// src/qr.rs
+//! QR codes for addresses and payment requests (`qr` feature).
+//!
+//! Scanning wallets accept either a bare address or a `monero:` URI, so
+//! [`Qr`] is built from either and rendered as SVG for apps, PNG for
+//! anything that wants a bitmap, or half-block Unicode for a terminal.
+use std::fmt;
+
+use qrcode::{Color, EcLevel, QrCode, render::svg, render::unicode::Dense1x2, types::QrError};
+
+use crate::{address::Address, uri::MoneroUri};
+
+/// Light modules around the code; scanners need at least four
+const QUIET_ZONE: usize = 4;
+
+#[derive(Debug)]
+pub enum QrCodeError {
+    /// Payload doesn't fit any QR version
+    Data(QrError),
+    Png(png::EncodingError),
+}
+
+impl fmt::Display for QrCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QrCodeError::Data(e) => write!(f, "cannot encode QR code: {e}"),
+            QrCodeError::Png(e) => write!(f, "PNG encoding failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for QrCodeError {}
+
+impl From<QrError> for QrCodeError {
+    fn from(e: QrError) -> Self {
+        QrCodeError::Data(e)
+    }
+}
+
+impl From<png::EncodingError> for QrCodeError {
+    fn from(e: png::EncodingError) -> Self {
+        QrCodeError::Png(e)
+    }
+}
+
+/// An encoded QR symbol, ready to render
+pub struct Qr(QrCode);
+
+impl Qr {
+    /// Encode arbitrary text at error-correction level M
+    pub fn new(text: &str) -> Result<Self, QrCodeError> {
+        Ok(Self(QrCode::with_error_correction_level(text, EcLevel::M)?))
+    }
+
+    /// Bare address, as the reference wallet's receive tab shows it
+    pub fn address(address: &Address) -> Result<Self, QrCodeError> {
+        Self::new(&address.to_string())
+    }
+
+    /// Payment request including amount and description
+    pub fn uri(uri: &MoneroUri) -> Result<Self, QrCodeError> {
+        Self::new(&uri.to_string())
+    }
+
+    /// Modules per side, excluding the quiet zone
+    pub fn width(&self) -> usize {
+        self.0.width()
+    }
+
+    /// Standalone SVG document, at least `min_size` pixels square
+    pub fn to_svg(&self, min_size: u32) -> String {
+        self.0
+            .render::<svg::Color<'_>>()
+            .min_dimensions(min_size, min_size)
+            .build()
+    }
+
+    /// 8-bit greyscale PNG with `module_px` pixels per module
+    pub fn to_png(&self, module_px: u32) -> Result<Vec<u8>, QrCodeError> {
+        let modules = self.0.to_colors();
+        let width = self.width();
+        let scale = module_px.max(1) as usize;
+        let side = (width + 2 * QUIET_ZONE) * scale;
+
+        let mut pixels = vec![0xffu8; side * side];
+        for (i, _) in modules
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c == Color::Dark)
+        {
+            let (x, y) = (i % width + QUIET_ZONE, i / width + QUIET_ZONE);
+            for row in y * scale..(y + 1) * scale {
+                pixels[row * side + x * scale..row * side + (x + 1) * scale].fill(0);
+            }
+        }
+
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, side as u32, side as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&pixels)?;
+        Ok(out)
+    }
+
+    /// Two modules per character using half blocks
+    ///
+    /// Dark modules are drawn as blocks, which suits light-background
+    /// terminals; `inverted` swaps them for dark backgrounds, where the
+    /// text colour is what looks light.
+    pub fn to_unicode(&self, inverted: bool) -> String {
+        let (dark, light) = if inverted {
+            (Dense1x2::Light, Dense1x2::Dark)
+        } else {
+            (Dense1x2::Dark, Dense1x2::Light)
+        };
+        self.0
+            .render::<Dense1x2>()
+            .dark_color(dark)
+            .light_color(light)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "42ey1afDFnn4886T7196doS9GPMzexD9gXpsZJDwVjeRVdFCSoHnv7KPbBeGpzJBzHRCAs9\
+                           UxqeoyFQMYbqSWYTfJJQAWDm";
+
+    #[test]
+    fn test_render() {
+        let address: Address = ADDRESS.parse().unwrap();
+        let qr = Qr::address(&address).unwrap();
+        // 95 bytes at level M needs version 6
+        assert_eq!(qr.width(), 41);
+
+        let svg = qr.to_svg(200);
+        assert!(svg.starts_with("<?xml"));
+        assert!(svg.contains("<svg"));
+
+        let png = qr.to_png(3).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        // IHDR width and height
+        let side = ((41 + 2 * QUIET_ZONE) * 3) as u32;
+        assert_eq!(png[16..20], side.to_be_bytes());
+        assert_eq!(png[20..24], side.to_be_bytes());
+
+        let text = qr.to_unicode(false);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), (41 + 2 * QUIET_ZONE).div_ceil(2));
+        assert!(
+            lines
+                .iter()
+                .all(|l| l.chars().count() == 41 + 2 * QUIET_ZONE)
+        );
+        assert_ne!(qr.to_unicode(true), text);
+
+        let uri = MoneroUri::new(address).amount(1).description("invoice 7");
+        assert!(Qr::uri(&uri).unwrap().width() > qr.width());
+        assert!(matches!(
+            Qr::new(&"x".repeat(4000)),
+            Err(QrCodeError::Data(QrError::DataTooLong))
+        ));
+    }
+}
@@ -31,6 +31,7 @@ fn borrowing_example() {
 
 // ---------- Slices ----------
 #[test]
+#[allow(clippy::useless_vec)]
 fn slice_borrowing() {
     let v = vec![1, 2, 3, 4, 5];
     let slice = &v[1..3]; // Borrow part of vector
@@ -0,0 +1,1594 @@
+// src/tx/builder.rs
+//! Building and signing RingCT transactions.
+//!
+//! [`TransactionBuilder`] follows wallet2's `construct_tx`: a fresh tx key
+//! (plus one key per output when paying subaddresses), view-tagged
+//! outputs, an encrypted payment ID, one Bulletproof+ over all outputs and
+//! one CLSAG per input, with inputs ordered by key image. Every size in a
+//! type 6 transaction is known before signing, so the fee is computed from
+//! the exact weight up front and each input is signed once.
+use std::{collections::VecDeque, fmt};
+
+use curve25519_dalek::{EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT};
+use rand::{rngs::OsRng, seq::SliceRandom};
+
+use super::{
+    Extra, ExtraError, ExtraField, ExtraNonce, TransactionPrefix, TxIn, TxInToKey, TxOut,
+    fees::{FeePriority, FeeRate, TYPICAL_EXTRA_LEN, estimate_weight, fee_for_weight},
+    rct::{EcdhInfo, RctSig, RctSigBase, RctSigPrunable, RctType, RingMember},
+    selection::OutputSelector,
+    transaction::{self, Transaction, TxShape},
+};
+use crate::address::{Address, AddressKind, PaymentId};
+use crate::consensus::hardfork::VIEW_TAGS_VERSION;
+use crate::crypto::{
+    cn::{KeyImage, generate_key_image},
+    commitment::commit,
+    hash::Hash256,
+    keys::{
+        WalletKeys, derive_public_key, derive_secret_key, derive_view_tag, generate_key_derivation,
+    },
+    rct::{
+        bulletproof_plus::{self, BulletproofError},
+        ecdh::{commitment_mask, encode_amount, shared_secret},
+    },
+    ring::{RingError, clsag},
+};
+use crate::network::Network;
+
+/// Ring size enforced since v15
+pub const DEFAULT_RING_SIZE: usize = 16;
+/// Most outputs in one transaction, change included
+pub const MAX_OUTPUTS: usize = bulletproof_plus::MAX_OUTPUTS;
+/// Heaviest transaction nodes accept since v8: half the smallest
+/// penalty-free block weight, less the space reserved for the coinbase
+pub const MAX_TX_WEIGHT: usize = 149_400;
+
+/// Why a transaction could not be built
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    NoInputs,
+    NoDestinations,
+    /// A destination amount of zero
+    ZeroAmount,
+    /// More than [`MAX_OUTPUTS`] outputs with change
+    TooManyOutputs(usize),
+    /// Transaction heavier than the builder's maximum weight
+    TxTooLarge {
+        weight: usize,
+        limit: usize,
+    },
+    /// A destination address belongs to another network
+    WrongNetwork(Network),
+    /// Integrated addresses with different payment IDs
+    MultiplePaymentIds,
+    /// Ring size below 2
+    RingSize(usize),
+    /// Input `n` isn't spendable with the builder's keys
+    NotOwned(usize),
+    /// The decoy source had no ring for input `n`
+    Decoys(usize),
+    /// The decoy source returned the wrong count, a duplicate or the real
+    /// output itself for input `n`
+    InvalidDecoys(usize),
+    InsufficientFunds {
+        available: u64,
+        required: u64,
+    },
+    /// The output selector picked candidate `n` twice or out of range
+    InvalidSelection(usize),
+    /// No input or candidate has the key image to sweep
+    UnknownKeyImage,
+    /// Amounts or fee overflow 64 bits
+    Overflow,
+    Extra(ExtraError),
+    Bulletproof(BulletproofError),
+    Ring(RingError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::NoInputs => write!(f, "no inputs to spend"),
+            BuildError::NoDestinations => write!(f, "no destinations"),
+            BuildError::ZeroAmount => write!(f, "destination amount is zero"),
+            BuildError::TooManyOutputs(n) => {
+                write!(f, "{n} outputs, at most {MAX_OUTPUTS} allowed")
+            }
+            BuildError::TxTooLarge { weight, limit } => {
+                write!(f, "transaction weight {weight} over the limit of {limit}")
+            }
+            BuildError::WrongNetwork(n) => write!(f, "destination is a {n} address"),
+            BuildError::MultiplePaymentIds => write!(f, "more than one payment ID"),
+            BuildError::RingSize(n) => write!(f, "ring size {n} is below 2"),
+            BuildError::NotOwned(i) => write!(f, "input {i} is not owned by this wallet"),
+            BuildError::Decoys(i) => write!(f, "no decoys for input {i}"),
+            BuildError::InvalidDecoys(i) => write!(f, "invalid decoys for input {i}"),
+            BuildError::InsufficientFunds {
+                available,
+                required,
+            } => write!(
+                f,
+                "insufficient funds: {available} available, {required} required"
+            ),
+            BuildError::InvalidSelection(i) => write!(f, "invalid selection of candidate {i}"),
+            BuildError::UnknownKeyImage => write!(f, "no output with that key image"),
+            BuildError::Overflow => write!(f, "amount overflow"),
+            BuildError::Extra(e) => write!(f, "tx_extra: {e}"),
+            BuildError::Bulletproof(e) => write!(f, "range proof: {e}"),
+            BuildError::Ring(e) => write!(f, "ring signature: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl From<ExtraError> for BuildError {
+    fn from(e: ExtraError) -> Self {
+        BuildError::Extra(e)
+    }
+}
+
+impl From<BulletproofError> for BuildError {
+    fn from(e: BulletproofError) -> Self {
+        BuildError::Bulletproof(e)
+    }
+}
+
+impl From<RingError> for BuildError {
+    fn from(e: RingError) -> Self {
+        BuildError::Ring(e)
+    }
+}
+
+/// An output the wallet received and may spend
+#[derive(Debug, Clone)]
+pub struct OwnedOutput {
+    /// Global output index, by which rings reference it
+    pub global_index: u64,
+    /// One-time key `P`
+    pub key: EdwardsPoint,
+    pub amount: u64,
+    /// Commitment mask: the on-chain commitment is `mask*G + amount*H`
+    pub mask: Scalar,
+    /// Key the output was derived from: the tx public key `R` or the
+    /// output's additional key
+    pub tx_public_key: EdwardsPoint,
+    /// Position in the transaction that created it
+    pub output_index: u64,
+    /// Subaddress `(major, minor)` it was sent to, `(0, 0)` for the main
+    /// address
+    pub subaddress: (u32, u32),
+}
+
+/// Where decoys come from, typically a daemon's `get_outs`
+pub trait DecoySource {
+    /// `count` outputs with distinct global indices, none of them
+    /// `real_index`, or None if they can't be had
+    fn decoys(&mut self, real_index: u64, count: usize) -> Option<Vec<RingMember>>;
+}
+
+/// A signed transaction with the secrets needed to prove it later
+#[derive(Clone)]
+pub struct PendingTransaction {
+    pub tx: Transaction,
+    /// `r` of the tx public key
+    pub tx_key: Scalar,
+    /// One per output when paying subaddresses, else empty
+    pub additional_tx_keys: Vec<Scalar>,
+    pub fee: u64,
+    /// Output paying the change back, if there was any
+    pub change_index: Option<usize>,
+}
+
+impl fmt::Debug for PendingTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingTransaction")
+            .field("tx", &self.tx)
+            .field("tx_key", &format_args!("REDACTED"))
+            .field("additional_tx_keys", &format_args!("REDACTED"))
+            .field("fee", &self.fee)
+            .field("change_index", &self.change_index)
+            .finish()
+    }
+}
+
+impl PendingTransaction {
+    /// Blob for `send_raw_transaction`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.tx.to_bytes()
+    }
+
+    pub fn hash(&self) -> Hash256 {
+        self.tx.hash()
+    }
+}
+
+/// One-time secret key of `output`, if it's ours
+pub(crate) fn output_secret(keys: &WalletKeys, output: &OwnedOutput) -> Scalar {
+    let derivation = generate_key_derivation(&output.tx_public_key, keys.view_secret());
+    let (major, minor) = output.subaddress;
+    derive_secret_key(
+        &derivation,
+        output.output_index,
+        &keys.subaddress_spend_secret(major, minor),
+    )
+}
+
+/// An input ready to sign
+struct PreparedInput {
+    secret: Scalar,
+    key_image: KeyImage,
+    ring: Vec<RingMember>,
+    /// Position of the real output in `ring`
+    index: usize,
+    amount: u64,
+    mask: Scalar,
+}
+
+/// What an output is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputRole {
+    /// Pays destination `n`
+    Destination(usize),
+    Change,
+    /// Zero amount to a random address
+    Dummy,
+}
+
+/// Inputs and destinations of one transaction of a split
+type SplitPart = (Vec<OwnedOutput>, Vec<(Address, u64)>);
+
+/// Spend everything, or one output, to a single address
+#[derive(Debug, Clone, Copy)]
+enum Sweep {
+    All(Address),
+    Output(KeyImage, Address),
+}
+
+/// Keys and prefix of a transaction, before amounts and fee
+struct Layout {
+    tx_key: Scalar,
+    additional_tx_keys: Vec<Scalar>,
+    planned: Vec<PlannedOutput>,
+    prefix: TransactionPrefix,
+}
+
+/// An output before its amount is known
+struct PlannedOutput {
+    key: EdwardsPoint,
+    view_tag: u8,
+    /// `Hs(derivation || i)`
+    shared: Scalar,
+}
+
+/// Builds one transaction from owned outputs to a list of destinations,
+/// sending the change to the wallet's main address
+pub struct TransactionBuilder<'a> {
+    keys: &'a WalletKeys,
+    network: Network,
+    inputs: Vec<OwnedOutput>,
+    /// Outputs the selector may add to `inputs`
+    candidates: Vec<OwnedOutput>,
+    selector: Option<Box<dyn OutputSelector + 'a>>,
+    sweep: Option<Sweep>,
+    destinations: Vec<(Address, u64)>,
+    priority: FeePriority,
+    fee_rate: FeeRate,
+    ring_size: usize,
+    max_weight: usize,
+    /// Tag outputs, as every hard fork since v15 does
+    view_tags: bool,
+}
+
+impl<'a> TransactionBuilder<'a> {
+    pub fn new(keys: &'a WalletKeys, network: Network) -> Self {
+        Self {
+            keys,
+            network,
+            inputs: Vec::new(),
+            candidates: Vec::new(),
+            selector: None,
+            sweep: None,
+            destinations: Vec::new(),
+            priority: FeePriority::default(),
+            fee_rate: FeeRate::default(),
+            ring_size: DEFAULT_RING_SIZE,
+            max_weight: MAX_TX_WEIGHT,
+            view_tags: true,
+        }
+    }
+
+    /// Spend `output`
+    pub fn input(mut self, output: OwnedOutput) -> Self {
+        self.inputs.push(output);
+        self
+    }
+
+    /// Also spend whichever of `outputs` `selector` picks to cover the
+    /// destinations and fee
+    pub fn select_from(
+        mut self,
+        outputs: Vec<OwnedOutput>,
+        selector: impl OutputSelector + 'a,
+    ) -> Self {
+        self.candidates = outputs;
+        self.selector = Some(Box::new(selector));
+        self
+    }
+
+    /// Send every input and candidate to `destination`, less the fee.
+    /// Replaces any other destinations.
+    pub fn sweep_all(mut self, destination: Address) -> Self {
+        self.sweep = Some(Sweep::All(destination));
+        self
+    }
+
+    /// Send the input or candidate with `key_image` to `destination`, less
+    /// the fee, and nothing else. Replaces any other destinations.
+    pub fn sweep_output(mut self, key_image: KeyImage, destination: Address) -> Self {
+        self.sweep = Some(Sweep::Output(key_image, destination));
+        self
+    }
+
+    /// Pay `amount` atomic units to `address`
+    pub fn destination(mut self, address: Address, amount: u64) -> Self {
+        self.destinations.push((address, amount));
+        self
+    }
+
+    pub fn priority(mut self, priority: FeePriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Base fee per byte of weight, before the priority multiplier
+    pub fn fee_per_byte(mut self, fee: u64) -> Self {
+        self.fee_rate.fee_per_byte = fee;
+        self
+    }
+
+    pub fn fee_quantization_mask(mut self, mask: u64) -> Self {
+        self.fee_rate.quantization_mask = mask.max(1);
+        self
+    }
+
+    /// Base fee and quantization together, as the daemon reports them
+    pub fn fee_rate(mut self, rate: FeeRate) -> Self {
+        self.fee_rate = rate;
+        self
+    }
+
+    pub fn ring_size(mut self, ring_size: usize) -> Self {
+        self.ring_size = ring_size;
+        self
+    }
+
+    /// Heaviest transaction to build, [`MAX_TX_WEIGHT`] by default; see
+    /// [`crate::consensus::max_tx_weight`] for other hard forks
+    pub fn max_weight(mut self, weight: usize) -> Self {
+        self.max_weight = weight;
+        self
+    }
+
+    /// Hard fork version the transaction is for, the latest by default.
+    /// Outputs carry view tags from [`VIEW_TAGS_VERSION`] on.
+    pub fn hard_fork(mut self, version: u8) -> Self {
+        self.view_tags = version >= VIEW_TAGS_VERSION;
+        self
+    }
+
+    /// Fee for a transaction of `weight`, rounded up to the quantization
+    fn fee_for(&self, weight: usize) -> Option<u64> {
+        fee_for_weight(weight, self.priority, &self.fee_rate)
+    }
+
+    /// Estimated fee of a transaction with `n_inputs` and `n_outputs`
+    fn estimated_fee(&self, n_inputs: usize, n_outputs: usize) -> u64 {
+        let mut extra_len = TYPICAL_EXTRA_LEN;
+        if self
+            .destinations
+            .iter()
+            .any(|(address, _)| matches!(address.kind(), AddressKind::Subaddress))
+        {
+            // tag, count and a key per output
+            extra_len += 2 + 32 * n_outputs;
+        }
+        let weight = estimate_weight(n_inputs, n_outputs, self.ring_size, extra_len);
+        self.fee_for(weight).unwrap_or(u64::MAX)
+    }
+
+    /// Most inputs a transaction with all [`MAX_OUTPUTS`] can spend
+    /// without going over the maximum weight
+    fn max_inputs(&self) -> usize {
+        let weight = |n: usize| estimate_weight(n, MAX_OUTPUTS, self.ring_size, TYPICAL_EXTRA_LEN);
+        (1..)
+            .take_while(|&n| weight(n) + 2 + 32 * MAX_OUTPUTS <= self.max_weight)
+            .last()
+            .unwrap_or(1)
+    }
+
+    /// The candidates `selector` picks to add to the inputs, given the fee
+    /// for each total input count
+    fn select_inputs(
+        &self,
+        mut selector: Box<dyn OutputSelector + 'a>,
+        candidates: &[OwnedOutput],
+        spent: u64,
+        fee: &dyn Fn(usize) -> u64,
+    ) -> Result<Vec<OwnedOutput>, BuildError> {
+        let n_explicit = self.inputs.len();
+        let explicit = self
+            .inputs
+            .iter()
+            .fold(0u64, |sum, o| sum.saturating_add(o.amount));
+        let required = |n: usize| {
+            fee(n_explicit + n)
+                .saturating_add(spent)
+                .saturating_sub(explicit)
+        };
+        if n_explicit > 0 && required(0) == 0 {
+            return Ok(Vec::new());
+        }
+        let Some(picked) = selector.select(candidates, &required) else {
+            return Err(BuildError::InsufficientFunds {
+                available: candidates
+                    .iter()
+                    .fold(explicit, |sum, o| sum.saturating_add(o.amount)),
+                required: required(candidates.len()).saturating_add(explicit),
+            });
+        };
+        let mut selected: Vec<OwnedOutput> = Vec::with_capacity(picked.len());
+        for i in picked {
+            match candidates.get(i) {
+                Some(output)
+                    if !self
+                        .inputs
+                        .iter()
+                        .chain(&selected)
+                        .any(|input| input.global_index == output.global_index) =>
+                {
+                    selected.push(output.clone())
+                }
+                _ => return Err(BuildError::InvalidSelection(i)),
+            }
+        }
+        Ok(selected)
+    }
+
+    /// Turn a sweep into a single zero-amount destination, its amount set
+    /// once the fee is known, spending the outputs it covers
+    fn prepare_sweep(&mut self, sweep: Sweep) -> Result<(), BuildError> {
+        let candidates = std::mem::take(&mut self.candidates);
+        self.selector = None;
+        let destination = match sweep {
+            Sweep::All(destination) => {
+                self.inputs.extend(candidates);
+                destination
+            }
+            Sweep::Output(key_image, destination) => {
+                let output = self
+                    .inputs
+                    .iter()
+                    .chain(&candidates)
+                    .find(|output| {
+                        generate_key_image(&output_secret(self.keys, output), &output.key)
+                            == key_image
+                    })
+                    .ok_or(BuildError::UnknownKeyImage)?;
+                self.inputs = vec![output.clone()];
+                destination
+            }
+        };
+        self.destinations = vec![(destination, 0)];
+        Ok(())
+    }
+
+    /// Select decoys for every input and order the inputs by key image,
+    /// descending, as consensus requires
+    fn prepare_inputs(
+        &self,
+        decoys: &mut impl DecoySource,
+    ) -> Result<Vec<PreparedInput>, BuildError> {
+        let mut prepared = Vec::with_capacity(self.inputs.len());
+        for (i, output) in self.inputs.iter().enumerate() {
+            let secret = output_secret(self.keys, output);
+            if secret * ED25519_BASEPOINT_POINT != output.key {
+                return Err(BuildError::NotOwned(i));
+            }
+
+            let count = self.ring_size - 1;
+            let mut ring = decoys
+                .decoys(output.global_index, count)
+                .ok_or(BuildError::Decoys(i))?;
+            if ring.len() != count {
+                return Err(BuildError::InvalidDecoys(i));
+            }
+            ring.push(RingMember {
+                global_index: output.global_index,
+                key: output.key,
+                commitment: commit(output.amount, output.mask),
+            });
+            ring.sort_by_key(|member| member.global_index);
+            if ring
+                .windows(2)
+                .any(|w| w[0].global_index == w[1].global_index)
+            {
+                return Err(BuildError::InvalidDecoys(i));
+            }
+            let index = ring
+                .iter()
+                .position(|member| member.global_index == output.global_index)
+                .expect("real output is in the ring");
+
+            prepared.push(PreparedInput {
+                secret,
+                key_image: generate_key_image(&secret, &output.key),
+                ring,
+                index,
+                amount: output.amount,
+                mask: output.mask,
+            });
+        }
+        prepared.sort_by(|a, b| b.key_image.as_bytes().cmp(a.key_image.as_bytes()));
+        Ok(prepared)
+    }
+
+    /// Output keys, tx_extra and prefix for outputs with `roles`, in order.
+    /// A lone subaddress gets R = r*D; paying one alongside any other
+    /// address takes a key per output.
+    fn layout(
+        &self,
+        roles: &[OutputRole],
+        payment_id: Option<(PaymentId, EdwardsPoint)>,
+        dummy: &Address,
+        inputs: &[PreparedInput],
+    ) -> Result<Layout, BuildError> {
+        let change = Address::from_keys(self.keys, self.network);
+        let address = |role: &OutputRole| match role {
+            OutputRole::Destination(i) => &self.destinations[*i].0,
+            OutputRole::Change => &change,
+            OutputRole::Dummy => dummy,
+        };
+        let is_subaddress = |address: &Address| matches!(address.kind(), AddressKind::Subaddress);
+        let mut unique: Vec<&Address> = Vec::new();
+        for role in roles.iter().filter(|role| **role != OutputRole::Change) {
+            let address = address(role);
+            if !unique
+                .iter()
+                .any(|u| u.spend_public() == address.spend_public())
+            {
+                unique.push(address);
+            }
+        }
+        let additional = unique.len() > 1 && unique.iter().any(|a| is_subaddress(a));
+
+        let tx_key = Scalar::random(&mut OsRng);
+        let tx_public_key = match unique[..] {
+            [address] if is_subaddress(address) => tx_key * address.spend_public(),
+            _ => tx_key * ED25519_BASEPOINT_POINT,
+        };
+        let mut additional_tx_keys = Vec::new();
+        let mut additional_public_keys = Vec::new();
+        let mut planned = Vec::with_capacity(roles.len());
+        for (i, role) in roles.iter().enumerate() {
+            let address = address(role);
+            let subaddress = is_subaddress(address);
+            let output_key = if additional {
+                let key = Scalar::random(&mut OsRng);
+                additional_public_keys.push(if subaddress {
+                    (key * address.spend_public()).compress()
+                } else {
+                    (key * ED25519_BASEPOINT_POINT).compress()
+                });
+                additional_tx_keys.push(key);
+                key
+            } else {
+                tx_key
+            };
+            let derivation = if *role == OutputRole::Change {
+                generate_key_derivation(&tx_public_key, self.keys.view_secret())
+            } else if subaddress {
+                generate_key_derivation(address.view_public(), &output_key)
+            } else {
+                generate_key_derivation(address.view_public(), &tx_key)
+            };
+            let index = i as u64;
+            planned.push(PlannedOutput {
+                key: derive_public_key(&derivation, index, address.spend_public()),
+                view_tag: derive_view_tag(&derivation, index),
+                shared: shared_secret(&derivation, index),
+            });
+        }
+
+        // wallet2 adds an encrypted zero ID to plain transfers with change
+        // so they look like payments to integrated addresses
+        let payment_id = match payment_id {
+            None if roles.len() == 2 && roles.contains(&OutputRole::Change) => {
+                Some((PaymentId([0; 8]), *self.destinations[0].0.view_public()))
+            }
+            id => id,
+        };
+        let mut extra = Extra::new().with(ExtraField::PublicKey(tx_public_key.compress()));
+        if let Some((id, view)) = payment_id {
+            let derivation = generate_key_derivation(&view, &tx_key);
+            extra.push(ExtraField::Nonce(ExtraNonce::encrypted_payment_id(
+                &id,
+                &derivation,
+            )));
+        }
+        if additional {
+            extra.push(ExtraField::AdditionalPublicKeys(additional_public_keys));
+        }
+
+        let prefix = TransactionPrefix {
+            version: 2,
+            unlock_time: 0,
+            vin: inputs
+                .iter()
+                .map(|input| {
+                    let indices: Vec<u64> = input
+                        .ring
+                        .iter()
+                        .map(|member| member.global_index)
+                        .collect();
+                    TxIn::ToKey(TxInToKey::from_absolute(0, &indices, input.key_image))
+                })
+                .collect(),
+            vout: planned
+                .iter()
+                .map(|output| match self.view_tags {
+                    true => TxOut::to_tagged_key(0, output.key.compress(), output.view_tag),
+                    false => TxOut::to_key(0, output.key.compress()),
+                })
+                .collect(),
+            extra: extra.to_bytes()?,
+        };
+        Ok(Layout {
+            tx_key,
+            additional_tx_keys,
+            planned,
+            prefix,
+        })
+    }
+
+    /// Smallest fee of at least `floor` that covers the signed
+    /// transaction's weight. The fee's varint is the only size that
+    /// depends on the fee.
+    fn fee_at_least(
+        &self,
+        prefix: &TransactionPrefix,
+        n_outputs: usize,
+        floor: u64,
+    ) -> Result<u64, BuildError> {
+        let prefix_len = prefix.to_bytes().len();
+        let mut fee = floor;
+        loop {
+            let weight = self.weight(prefix_len, n_outputs, fee);
+            let needed = self.fee_for(weight).ok_or(BuildError::Overflow)?;
+            if needed <= fee {
+                return Ok(fee);
+            }
+            fee = needed;
+        }
+    }
+
+    /// Exact weight of the signed transaction, given its prefix
+    fn weight(&self, prefix_len: usize, n_outputs: usize, fee: u64) -> usize {
+        transaction::weight(&TxShape {
+            rct_type: RctType::BulletproofPlus,
+            prefix_len,
+            n_inputs: self.inputs.len(),
+            ring_size: self.ring_size,
+            n_outputs,
+            fee,
+        })
+    }
+
+    /// Build and sign the transaction
+    pub fn build(
+        mut self,
+        decoys: &mut impl DecoySource,
+    ) -> Result<PendingTransaction, BuildError> {
+        let sweeping = self.sweep.is_some();
+        if let Some(sweep) = self.sweep.take() {
+            self.prepare_sweep(sweep)?;
+        }
+        if self.inputs.is_empty() && self.candidates.is_empty() {
+            return Err(BuildError::NoInputs);
+        }
+        if self.destinations.is_empty() {
+            return Err(BuildError::NoDestinations);
+        }
+        if self.ring_size < 2 {
+            return Err(BuildError::RingSize(self.ring_size));
+        }
+        let n_outputs = self.destinations.len() + 1;
+        if n_outputs > MAX_OUTPUTS {
+            return Err(BuildError::TooManyOutputs(n_outputs));
+        }
+        let mut payment_id = None;
+        for (address, amount) in &self.destinations {
+            if address.network() != self.network {
+                return Err(BuildError::WrongNetwork(address.network()));
+            }
+            if *amount == 0 && !sweeping {
+                return Err(BuildError::ZeroAmount);
+            }
+            if let AddressKind::Integrated(id) = address.kind() {
+                if payment_id.is_some_and(|(other, _)| other != id) {
+                    return Err(BuildError::MultiplePaymentIds);
+                }
+                payment_id = Some((id, *address.view_public()));
+            }
+        }
+        let spent = self
+            .destinations
+            .iter()
+            .try_fold(0u64, |sum, (_, amount)| sum.checked_add(*amount))
+            .ok_or(BuildError::Overflow)?;
+        if let Some(selector) = self.selector.take() {
+            let candidates = std::mem::take(&mut self.candidates);
+            let fee = |n_inputs: usize| self.estimated_fee(n_inputs, n_outputs);
+            let selected = self.select_inputs(selector, &candidates, spent, &fee)?;
+            self.inputs.extend(selected);
+        }
+        let available = self
+            .inputs
+            .iter()
+            .try_fold(0u64, |sum, output| sum.checked_add(output.amount))
+            .ok_or(BuildError::Overflow)?;
+
+        let inputs = self.prepare_inputs(decoys)?;
+
+        // The destinations and change back to the main address, in random
+        // order. Without change a lone destination still gets a zero-amount
+        // dummy, as transactions need two outputs; a sweep never has change.
+        let mut roles: Vec<OutputRole> = (0..self.destinations.len())
+            .map(OutputRole::Destination)
+            .chain([match sweeping {
+                true => OutputRole::Dummy,
+                false => OutputRole::Change,
+            }])
+            .collect();
+        roles.shuffle(&mut OsRng);
+        let dummy = Address::from_keys(&WalletKeys::generate(), self.network);
+        let mut layout = self.layout(&roles, payment_id, &dummy, &inputs)?;
+        let mut fee = self.fee_at_least(&layout.prefix, roles.len(), 0)?;
+        if !sweeping && spent.checked_add(fee) == Some(available) {
+            if self.destinations.len() == 1 {
+                for role in &mut roles {
+                    if *role == OutputRole::Change {
+                        *role = OutputRole::Dummy;
+                    }
+                }
+            } else {
+                roles.retain(|role| *role != OutputRole::Change);
+            }
+            layout = self.layout(&roles, payment_id, &dummy, &inputs)?;
+            // never below the fee the change was dropped for
+            fee = self.fee_at_least(&layout.prefix, roles.len(), fee)?;
+        }
+        let required = match sweeping {
+            // something has to be left to send
+            true => fee.checked_add(1),
+            false => spent.checked_add(fee),
+        }
+        .ok_or(BuildError::Overflow)?;
+        if available < required {
+            return Err(BuildError::InsufficientFunds {
+                available,
+                required,
+            });
+        }
+        let n_outputs = roles.len();
+        let amounts: Vec<u64> = roles
+            .iter()
+            .map(|role| match role {
+                OutputRole::Destination(_) if sweeping => available - fee,
+                OutputRole::Destination(i) => self.destinations[*i].1,
+                OutputRole::Change => available - required,
+                OutputRole::Dummy => 0,
+            })
+            .collect();
+        let Layout {
+            tx_key,
+            additional_tx_keys,
+            planned,
+            prefix,
+        } = layout;
+        let prefix_len = prefix.to_bytes().len();
+        let weight = self.weight(prefix_len, n_outputs, fee);
+        if weight > self.max_weight {
+            return Err(BuildError::TxTooLarge {
+                weight,
+                limit: self.max_weight,
+            });
+        }
+
+        let masks: Vec<Scalar> = planned.iter().map(|o| commitment_mask(&o.shared)).collect();
+        let proof = bulletproof_plus::prove(&amounts, &masks)?;
+        let mut pseudo_masks: Vec<Scalar> = (1..inputs.len())
+            .map(|_| Scalar::random(&mut OsRng))
+            .collect();
+        pseudo_masks.push(masks.iter().sum::<Scalar>() - pseudo_masks.iter().sum::<Scalar>());
+        let pseudo_outs: Vec<EdwardsPoint> = inputs
+            .iter()
+            .zip(&pseudo_masks)
+            .map(|(input, mask)| commit(input.amount, *mask))
+            .collect();
+
+        let mut rct_signatures = RctSig {
+            base: RctSigBase {
+                rct_type: RctType::BulletproofPlus,
+                fee,
+                pseudo_outs: Vec::new(),
+                ecdh_info: planned
+                    .iter()
+                    .zip(&amounts)
+                    .map(|(output, amount)| {
+                        EcdhInfo::Compact(encode_amount(*amount, &output.shared))
+                    })
+                    .collect(),
+                out_pk: amounts
+                    .iter()
+                    .zip(&masks)
+                    .map(|(amount, mask)| commit(*amount, *mask).compress())
+                    .collect(),
+            },
+            prunable: RctSigPrunable {
+                bulletproofs_plus: vec![proof],
+                pseudo_outs: pseudo_outs.iter().map(EdwardsPoint::compress).collect(),
+                ..Default::default()
+            },
+        };
+
+        let message = rct_signatures.signature_message(&prefix.prefix_hash());
+        for ((input, pseudo_out), pseudo_mask) in inputs.iter().zip(&pseudo_outs).zip(&pseudo_masks)
+        {
+            let keys: Vec<EdwardsPoint> = input.ring.iter().map(|member| member.key).collect();
+            let commitments: Vec<EdwardsPoint> =
+                input.ring.iter().map(|member| member.commitment).collect();
+            let signature = clsag::sign(
+                &message,
+                &keys,
+                &commitments,
+                pseudo_out,
+                &input.secret,
+                &(input.mask - pseudo_mask),
+                input.index,
+            )?;
+            rct_signatures.prunable.clsags.push(signature);
+        }
+        let tx = Transaction {
+            prefix,
+            signatures: Vec::new(),
+            rct_signatures,
+        };
+        debug_assert_eq!(tx.weight(), weight);
+        Ok(PendingTransaction {
+            tx,
+            tx_key,
+            additional_tx_keys,
+            fee,
+            change_index: roles.iter().position(|role| *role == OutputRole::Change),
+        })
+    }
+
+    /// Build as many transactions as it takes to stay within
+    /// [`MAX_OUTPUTS`] and the maximum weight, splitting a destination's
+    /// amount across two transactions where inputs run out mid-way.
+    ///
+    /// Inputs are spent in order, and only as far as needed; a sweep of
+    /// everything spends them all.
+    pub fn build_split(
+        mut self,
+        decoys: &mut impl DecoySource,
+    ) -> Result<Vec<PendingTransaction>, BuildError> {
+        if self.ring_size < 2 {
+            return Err(BuildError::RingSize(self.ring_size));
+        }
+        let max_inputs = self.max_inputs();
+        match self.sweep {
+            Some(Sweep::All(destination)) => {
+                let mut inputs = std::mem::take(&mut self.inputs);
+                inputs.append(&mut self.candidates);
+                if inputs.is_empty() {
+                    return Err(BuildError::NoInputs);
+                }
+                return inputs
+                    .chunks(max_inputs)
+                    .map(|chunk| {
+                        let mut part = self.part();
+                        part.inputs = chunk.to_vec();
+                        part.sweep_all(destination).build(decoys)
+                    })
+                    .collect();
+            }
+            Some(Sweep::Output(..)) => return self.build(decoys).map(|pending| vec![pending]),
+            None => {}
+        }
+        if self.destinations.is_empty() {
+            return Err(BuildError::NoDestinations);
+        }
+        let spent = self
+            .destinations
+            .iter()
+            .try_fold(0u64, |sum, (_, amount)| sum.checked_add(*amount))
+            .ok_or(BuildError::Overflow)?;
+        if let Some(selector) = self.selector.take() {
+            let candidates = std::mem::take(&mut self.candidates);
+            // a full transaction's fee per max_inputs inputs or
+            // MAX_OUTPUTS - 1 destinations, whichever needs more
+            let n_destinations = self.destinations.len();
+            let fee = |n_inputs: usize| {
+                let n_txs = n_inputs
+                    .div_ceil(max_inputs)
+                    .max(n_destinations.div_ceil(MAX_OUTPUTS - 1))
+                    .max(1);
+                let per_tx = self.estimated_fee(0, 2);
+                self.estimated_fee(n_inputs, n_destinations + n_txs)
+                    .saturating_add(per_tx.saturating_mul(n_txs as u64 - 1))
+            };
+            let selected = self.select_inputs(selector, &candidates, spent, &fee)?;
+            self.inputs.extend(selected);
+        }
+        if self.inputs.is_empty() {
+            return Err(BuildError::NoInputs);
+        }
+
+        let plan = self.plan_split(spent, max_inputs)?;
+        plan.into_iter()
+            .map(|(inputs, destinations)| {
+                let mut part = self.part();
+                part.inputs = inputs;
+                part.destinations = destinations;
+                part.build(decoys)
+            })
+            .collect()
+    }
+
+    /// Inputs and destinations of each transaction in a split, spending
+    /// at most `max_inputs` per transaction
+    fn plan_split(&self, spent: u64, max_inputs: usize) -> Result<Vec<SplitPart>, BuildError> {
+        let mut plan = Vec::new();
+        let mut pool = self.inputs.iter();
+        let mut remaining: VecDeque<(Address, u64)> = self.destinations.iter().copied().collect();
+        while !remaining.is_empty() {
+            let mut inputs: Vec<OwnedOutput> = Vec::new();
+            let mut funds = 0u64;
+            let mut destinations: Vec<(Address, u64)> = Vec::new();
+            let mut paid = 0u64;
+            'tx: while let Some(&(address, amount)) = remaining.front() {
+                if destinations.len() == MAX_OUTPUTS - 1 {
+                    break;
+                }
+                loop {
+                    // this destination and change on top of what's planned
+                    let fee = self.estimated_fee(inputs.len(), destinations.len() + 2);
+                    let need = paid.saturating_add(fee);
+                    if funds >= need.saturating_add(amount) {
+                        destinations.push((address, amount));
+                        paid += amount;
+                        remaining.pop_front();
+                        break;
+                    }
+                    if inputs.len() < max_inputs
+                        && let Some(output) = pool.next()
+                    {
+                        funds = funds
+                            .checked_add(output.amount)
+                            .ok_or(BuildError::Overflow)?;
+                        inputs.push(output.clone());
+                        continue;
+                    }
+                    // out of inputs: pay what's left, the rest goes in the
+                    // next transaction
+                    let partial = funds.saturating_sub(need);
+                    if partial > 0 {
+                        destinations.push((address, partial));
+                        remaining[0].1 -= partial;
+                    }
+                    break 'tx;
+                }
+            }
+            if destinations.is_empty() {
+                let available = self
+                    .inputs
+                    .iter()
+                    .fold(0u64, |sum, o| sum.saturating_add(o.amount));
+                let fee = self.estimated_fee(self.inputs.len(), self.destinations.len() + 1);
+                return Err(BuildError::InsufficientFunds {
+                    available,
+                    required: spent.saturating_add(fee),
+                });
+            }
+            plan.push((inputs, destinations));
+        }
+        Ok(plan)
+    }
+
+    /// An empty builder with the same keys and fee settings
+    fn part(&self) -> Self {
+        Self {
+            priority: self.priority,
+            fee_rate: self.fee_rate,
+            ring_size: self.ring_size,
+            max_weight: self.max_weight,
+            view_tags: self.view_tags,
+            ..Self::new(self.keys, self.network)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::crypto::keys::{
+        KeyDerivation, derive_subaddress_public_key, generate_key_derivation,
+    };
+    use crate::tx::{
+        fees::estimate_weight,
+        rct::{RctError, verify_rct},
+        selection::{LargestFirst, OldestFirst, RandomSelection},
+        transaction::bulletproof_plus_clawback,
+    };
+
+    /// Random chain outputs, remembered so rings can be checked
+    #[derive(Default)]
+    pub(crate) struct Chain {
+        pub(crate) outputs: HashMap<u64, RingMember>,
+    }
+
+    impl DecoySource for Chain {
+        fn decoys(&mut self, real_index: u64, count: usize) -> Option<Vec<RingMember>> {
+            let mut decoys = Vec::new();
+            let mut global_index = real_index;
+            while decoys.len() < count {
+                global_index += 7;
+                let member = RingMember {
+                    global_index,
+                    key: Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT,
+                    commitment: commit(1, Scalar::random(&mut OsRng)),
+                };
+                self.outputs.insert(global_index, member);
+                decoys.push(member);
+            }
+            Some(decoys)
+        }
+    }
+
+    /// Output `index` of a transaction paying `amount` to `keys`
+    fn received(keys: &WalletKeys, global_index: u64, amount: u64) -> OwnedOutput {
+        let r = Scalar::random(&mut OsRng);
+        let derivation = generate_key_derivation(keys.view_public(), &r);
+        OwnedOutput {
+            global_index,
+            key: derive_public_key(&derivation, 1, keys.spend_public()),
+            amount,
+            mask: Scalar::random(&mut OsRng),
+            tx_public_key: r * ED25519_BASEPOINT_POINT,
+            output_index: 1,
+            subaddress: (0, 0),
+        }
+    }
+
+    /// The ring of every input of `tx`
+    pub(crate) fn rings(tx: &Transaction, chain: &Chain) -> Vec<Vec<RingMember>> {
+        tx.prefix
+            .vin
+            .iter()
+            .map(|input| {
+                let TxIn::ToKey(input) = input else {
+                    panic!("coinbase input");
+                };
+                input
+                    .absolute_offsets()
+                    .unwrap()
+                    .iter()
+                    .map(|i| chain.outputs[i])
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Check proofs, signatures and balance like a node would
+    pub(crate) fn check(pending: &PendingTransaction, chain: &Chain) {
+        let tx = &pending.tx;
+        let key_images: Vec<&KeyImage> = tx.prefix.key_images().collect();
+        assert!(
+            key_images
+                .windows(2)
+                .all(|w| w[0].as_bytes() > w[1].as_bytes())
+        );
+        let rings = rings(tx, chain);
+        assert_eq!(verify_rct(tx, &rings), Ok(()));
+        assert_eq!(tx.to_bytes(), pending.to_bytes());
+        let estimate = estimate_weight(
+            rings.len(),
+            tx.prefix.vout.len(),
+            rings[0].len(),
+            tx.prefix.extra.len(),
+        );
+        assert!(estimate >= tx.weight());
+
+        let mut tampered = tx.clone();
+        tampered.rct_signatures.base.fee += 1;
+        assert_eq!(verify_rct(&tampered, &rings), Err(RctError::Unbalanced));
+    }
+
+    /// Receiver side: decrypt output `index` with derivation `derivation`
+    fn amount(pending: &PendingTransaction, derivation: &KeyDerivation, index: usize) -> u64 {
+        let shared = shared_secret(derivation, index as u64);
+        let base = &pending.tx.rct_signatures.base;
+        let (amount, _) = base.ecdh_info[index]
+            .decode(&shared, &base.out_pk[index])
+            .unwrap();
+        amount
+    }
+
+    pub(crate) fn with_chain(keys: &WalletKeys, amounts: &[u64]) -> (Chain, Vec<OwnedOutput>) {
+        let mut chain = Chain::default();
+        let outputs: Vec<OwnedOutput> = amounts
+            .iter()
+            .enumerate()
+            .map(|(i, &amount)| received(keys, 1000 * i as u64 + 3, amount))
+            .collect();
+        for output in &outputs {
+            chain.outputs.insert(
+                output.global_index,
+                RingMember {
+                    global_index: output.global_index,
+                    key: output.key,
+                    commitment: commit(output.amount, output.mask),
+                },
+            );
+        }
+        (chain, outputs)
+    }
+
+    #[test]
+    fn test_build_and_verify() {
+        let sender = WalletKeys::generate();
+        let recipient = WalletKeys::generate();
+        let to = Address::from_keys(&recipient, Network::Mainnet);
+        let (mut chain, outputs) = with_chain(&sender, &[3_000_000_000, 2_000_000_000]);
+
+        let mut builder = TransactionBuilder::new(&sender, Network::Mainnet)
+            .destination(to, 4_000_000_000)
+            .priority(FeePriority::Normal);
+        for output in outputs {
+            builder = builder.input(output);
+        }
+        let pending = builder.build(&mut chain).unwrap();
+        check(&pending, &chain);
+
+        let tx = &pending.tx;
+        assert_eq!(tx.prefix.vin.len(), 2);
+        assert_eq!(tx.prefix.vout.len(), 2);
+        assert!(pending.additional_tx_keys.is_empty());
+        let weight = tx.weight();
+        assert_eq!(weight, tx.to_bytes().len());
+        assert_eq!(
+            pending.fee,
+            (weight as u64 * 20_000 * 5).div_ceil(10_000) * 10_000
+        );
+
+        let extra = tx.prefix.parse_extra();
+        let tx_pub = extra.tx_public_key().unwrap().decompress().unwrap();
+        assert_eq!(tx_pub, pending.tx_key * ED25519_BASEPOINT_POINT);
+        let debug = format!("{pending:?}");
+        assert!(debug.contains("tx_key: REDACTED"));
+        assert!(!debug.contains(&hex::encode(pending.tx_key.as_bytes())));
+        let key = |i: usize| tx.prefix.vout[i].key().decompress().unwrap();
+        let change = pending.change_index.unwrap();
+        let paid = 1 - change;
+        assert!(recipient.owns_output(&tx_pub, paid as u64, &key(paid)));
+        assert!(sender.owns_output(&tx_pub, change as u64, &key(change)));
+
+        let derivation = generate_key_derivation(&tx_pub, recipient.view_secret());
+        assert_eq!(
+            tx.prefix.vout[paid].view_tag(),
+            Some(derive_view_tag(&derivation, paid as u64))
+        );
+        assert_eq!(amount(&pending, &derivation, paid), 4_000_000_000);
+        // a plain transfer still carries an (all-zero) encrypted payment ID
+        assert_eq!(extra.payment_id(&derivation), Some(PaymentId([0; 8])));
+        let derivation = generate_key_derivation(&tx_pub, sender.view_secret());
+        assert_eq!(
+            amount(&pending, &derivation, change),
+            1_000_000_000 - pending.fee
+        );
+    }
+
+    #[test]
+    fn test_subaddress_destinations() {
+        let sender = WalletKeys::generate();
+        let recipient = WalletKeys::generate();
+        let sub = Address::subaddress(&recipient, Network::Mainnet, 1, 2);
+
+        // a lone subaddress: R = r*D, no additional keys
+        let (mut chain, outputs) = with_chain(&sender, &[5_000_000_000]);
+        let pending = TransactionBuilder::new(&sender, Network::Mainnet)
+            .input(outputs[0].clone())
+            .destination(sub, 1_000_000_000)
+            .build(&mut chain)
+            .unwrap();
+        check(&pending, &chain);
+        let extra = pending.tx.prefix.parse_extra();
+        let tx_pub = extra.tx_public_key().unwrap().decompress().unwrap();
+        assert_eq!(tx_pub, pending.tx_key * sub.spend_public());
+        assert!(extra.additional_public_keys().is_empty());
+        let derivation = generate_key_derivation(&tx_pub, recipient.view_secret());
+        let paid = 1 - pending.change_index.unwrap();
+        let key = pending.tx.prefix.vout[paid].key().decompress().unwrap();
+        assert_eq!(
+            derive_subaddress_public_key(&key, &derivation, paid as u64),
+            recipient.subaddress_spend_public(1, 2)
+        );
+        assert_eq!(amount(&pending, &derivation, paid), 1_000_000_000);
+
+        // with a second destination every output gets its own key
+        let other = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let (mut chain, outputs) = with_chain(&sender, &[5_000_000_000]);
+        let pending = TransactionBuilder::new(&sender, Network::Mainnet)
+            .input(outputs[0].clone())
+            .destination(other, 1_000_000_000)
+            .destination(sub, 2_000_000_000)
+            .build(&mut chain)
+            .unwrap();
+        check(&pending, &chain);
+        let extra = pending.tx.prefix.parse_extra();
+        let additional = extra.additional_public_keys();
+        assert_eq!(additional.len(), 3);
+        assert_eq!(pending.additional_tx_keys.len(), 3);
+        let paid = (0..3)
+            .find(|&i| {
+                let derivation = generate_key_derivation(
+                    &additional[i].decompress().unwrap(),
+                    recipient.view_secret(),
+                );
+                let key = pending.tx.prefix.vout[i].key().decompress().unwrap();
+                derive_subaddress_public_key(&key, &derivation, i as u64)
+                    == recipient.subaddress_spend_public(1, 2)
+            })
+            .unwrap();
+        let tx_pub = additional[paid].decompress().unwrap();
+        let derivation = generate_key_derivation(&tx_pub, recipient.view_secret());
+        assert_eq!(amount(&pending, &derivation, paid), 2_000_000_000);
+    }
+
+    #[test]
+    fn test_no_change() {
+        let sender = WalletKeys::generate();
+        let recipient = WalletKeys::generate();
+        let to = Address::from_keys(&recipient, Network::Mainnet);
+        let other = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let (mut chain, outputs) = with_chain(&sender, &[5_000_000_000]);
+        let build = |chain: &mut Chain, destinations: &[(Address, u64)]| {
+            let mut builder =
+                TransactionBuilder::new(&sender, Network::Mainnet).input(outputs[0].clone());
+            for (address, amount) in destinations {
+                builder = builder.destination(*address, *amount);
+            }
+            builder.build(chain).unwrap()
+        };
+
+        // spending exactly the input: a zero-amount dummy takes the change's
+        // place, and no payment ID is added for it
+        let fee = build(&mut chain, &[(to, 1)]).fee;
+        let pending = build(&mut chain, &[(to, 5_000_000_000 - fee)]);
+        check(&pending, &chain);
+        assert_eq!(pending.fee, fee);
+        assert_eq!(pending.change_index, None);
+        assert_eq!(pending.tx.prefix.vout.len(), 2);
+        let extra = pending.tx.prefix.parse_extra();
+        let tx_pub = extra.tx_public_key().unwrap().decompress().unwrap();
+        let derivation = generate_key_derivation(&tx_pub, recipient.view_secret());
+        assert_eq!(extra.payment_id(&derivation), None);
+        let paid = (0..2)
+            .find(|&i| {
+                let key = pending.tx.prefix.vout[i].key().decompress().unwrap();
+                recipient.owns_output(&tx_pub, i as u64, &key)
+            })
+            .unwrap();
+        assert_eq!(amount(&pending, &derivation, paid), 5_000_000_000 - fee);
+
+        // with two destinations the change output is just left out
+        let fee = build(&mut chain, &[(to, 1), (other, 1)]).fee;
+        let pending = build(&mut chain, &[(to, 1), (other, 5_000_000_000 - 1 - fee)]);
+        check(&pending, &chain);
+        assert_eq!(pending.change_index, None);
+        assert_eq!(pending.tx.prefix.vout.len(), 2);
+        assert!(pending.fee >= fee);
+    }
+
+    #[test]
+    fn test_sweep() {
+        let sender = WalletKeys::generate();
+        let recipient = WalletKeys::generate();
+        let to = Address::from_keys(&recipient, Network::Mainnet);
+        let (mut chain, outputs) =
+            with_chain(&sender, &[1_000_000_000, 2_000_000_000, 3_000_000_000]);
+        let received = |pending: &PendingTransaction| {
+            let extra = pending.tx.prefix.parse_extra();
+            let tx_pub = extra.tx_public_key().unwrap().decompress().unwrap();
+            let derivation = generate_key_derivation(&tx_pub, recipient.view_secret());
+            let paid = (0..2)
+                .find(|&i| {
+                    let key = pending.tx.prefix.vout[i].key().decompress().unwrap();
+                    recipient.owns_output(&tx_pub, i as u64, &key)
+                })
+                .unwrap();
+            amount(pending, &derivation, paid)
+        };
+
+        let pending = TransactionBuilder::new(&sender, Network::Mainnet)
+            .input(outputs[0].clone())
+            .destination(to, 1)
+            .select_from(outputs[1..].to_vec(), LargestFirst)
+            .sweep_all(to)
+            .build(&mut chain)
+            .unwrap();
+        check(&pending, &chain);
+        assert_eq!(pending.tx.prefix.vin.len(), 3);
+        assert_eq!(pending.tx.prefix.vout.len(), 2);
+        assert_eq!(pending.change_index, None);
+        assert_eq!(received(&pending), 6_000_000_000 - pending.fee);
+
+        let builder = || TransactionBuilder::new(&sender, Network::Mainnet);
+        let key_image = generate_key_image(&output_secret(&sender, &outputs[1]), &outputs[1].key);
+        let pending = builder()
+            .select_from(outputs.clone(), RandomSelection)
+            .sweep_output(key_image, to)
+            .build(&mut chain)
+            .unwrap();
+        check(&pending, &chain);
+        assert_eq!(pending.tx.prefix.vin.len(), 1);
+        assert_eq!(pending.tx.prefix.key_images().next(), Some(&key_image));
+        assert_eq!(received(&pending), 2_000_000_000 - pending.fee);
+
+        let stranger = WalletKeys::generate();
+        let key_image = generate_key_image(stranger.spend_secret(), stranger.spend_public());
+        assert_eq!(
+            builder()
+                .select_from(outputs.clone(), RandomSelection)
+                .sweep_output(key_image, to)
+                .build(&mut chain)
+                .unwrap_err(),
+            BuildError::UnknownKeyImage
+        );
+        let (mut chain, dust) = with_chain(&sender, &[1000]);
+        assert!(matches!(
+            builder()
+                .input(dust[0].clone())
+                .sweep_all(to)
+                .build(&mut chain)
+                .unwrap_err(),
+            BuildError::InsufficientFunds {
+                available: 1000,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_build_split() {
+        let sender = WalletKeys::generate();
+        let recipients: Vec<Address> = (0..20)
+            .map(|_| Address::from_keys(&WalletKeys::generate(), Network::Mainnet))
+            .collect();
+        let amounts = [10_000_000_000; 3];
+        let (mut chain, outputs) = with_chain(&sender, &amounts);
+
+        // 20 destinations need two transactions, and the change of the first
+        // can't pay for the second
+        let mut builder = TransactionBuilder::new(&sender, Network::Mainnet);
+        for address in &recipients {
+            builder = builder.destination(*address, 1_000_000_000);
+        }
+        for output in &outputs {
+            builder = builder.input(output.clone());
+        }
+        let pending = builder.build_split(&mut chain).unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].tx.prefix.vout.len(), MAX_OUTPUTS);
+        assert_eq!(pending[1].tx.prefix.vout.len(), 6);
+        assert_eq!(pending[0].tx.prefix.vin.len(), 2);
+        assert_eq!(pending[1].tx.prefix.vin.len(), 1);
+        for pending in &pending {
+            check(pending, &chain);
+        }
+
+        // sweeping everything goes one transaction per input chunk
+        let pending = TransactionBuilder::new(&sender, Network::Mainnet)
+            .select_from(outputs.clone(), LargestFirst)
+            .sweep_all(recipients[0])
+            .build_split(&mut chain)
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].tx.prefix.vin.len(), 3);
+
+        // a lower weight limit fits fewer inputs per transaction
+        let builder = TransactionBuilder::new(&sender, Network::Mainnet);
+        let max_inputs = builder.max_inputs();
+        assert!(builder.max_weight(MAX_TX_WEIGHT / 2).max_inputs() < max_inputs);
+    }
+
+    #[test]
+    fn test_plan_split() {
+        let sender = WalletKeys::generate();
+        let to = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let other = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let amounts = [4_000_000_000; 5];
+        let (_, outputs) = with_chain(&sender, &amounts);
+        let mut builder = TransactionBuilder::new(&sender, Network::Mainnet)
+            .destination(to, 10_000_000_000)
+            .destination(other, 5_000_000_000);
+        for output in &outputs {
+            builder = builder.input(output.clone());
+        }
+
+        // at most two inputs each: the first destination is split across
+        // two transactions, the second fits with its remainder
+        let plan = builder.plan_split(15_000_000_000, 2).unwrap();
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan[0].1.len(), 1);
+        assert_eq!(plan[1].0.len(), 2);
+        assert!(plan.iter().all(|(inputs, _)| inputs.len() <= 2));
+        let paid = |address: &Address| -> u64 {
+            plan.iter()
+                .flat_map(|(_, destinations)| destinations)
+                .filter(|(a, _)| a == address)
+                .map(|(_, amount)| amount)
+                .sum()
+        };
+        assert_eq!(paid(&to), 10_000_000_000);
+        assert_eq!(paid(&other), 5_000_000_000);
+        for (inputs, destinations) in &plan {
+            let funds: u64 = inputs.iter().map(|o| o.amount).sum();
+            let fee = builder.estimated_fee(inputs.len(), destinations.len() + 1);
+            let sent: u64 = destinations.iter().map(|(_, amount)| amount).sum();
+            assert!(funds >= sent + fee);
+        }
+
+        // the fees take more than the 5 XMR left over
+        let builder = builder.destination(to, 5_000_000_000);
+        let plan = builder.plan_split(20_000_000_000, 1);
+        assert!(matches!(
+            plan,
+            Err(BuildError::InsufficientFunds {
+                available: 20_000_000_000,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_three_outputs_clawback() {
+        let sender = WalletKeys::generate();
+        let (mut chain, outputs) = with_chain(&sender, &[5_000_000_000]);
+        let to = |n: u32| Address::subaddress(&sender, Network::Mainnet, 0, n);
+        let pending = TransactionBuilder::new(&sender, Network::Mainnet)
+            .input(outputs[0].clone())
+            .destination(to(1), 1)
+            .destination(to(2), 2)
+            .build(&mut chain)
+            .unwrap();
+        check(&pending, &chain);
+        let tx = &pending.tx;
+        assert_eq!(
+            tx.weight(),
+            tx.to_bytes().len() + bulletproof_plus_clawback(3)
+        );
+    }
+
+    #[test]
+    fn test_select_from() {
+        let sender = WalletKeys::generate();
+        let to = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let amounts = [1_000_000_000, 5_000_000_000, 2_000_000_000, 3_000_000_000];
+        let (mut chain, outputs) = with_chain(&sender, &amounts);
+
+        let pending = TransactionBuilder::new(&sender, Network::Mainnet)
+            .destination(to, 6_000_000_000)
+            .select_from(outputs.clone(), LargestFirst)
+            .build(&mut chain)
+            .unwrap();
+        check(&pending, &chain);
+        let ring_contains = |pending: &PendingTransaction, global_index: u64| {
+            pending.tx.prefix.vin.iter().any(|input| match input {
+                TxIn::ToKey(input) => input.absolute_offsets().unwrap().contains(&global_index),
+                TxIn::Gen(_) => false,
+            })
+        };
+        assert_eq!(pending.tx.prefix.vin.len(), 2);
+        assert!(ring_contains(&pending, outputs[1].global_index));
+        assert!(ring_contains(&pending, outputs[3].global_index));
+
+        // an explicit input comes first, the selector adds the rest
+        let pending = TransactionBuilder::new(&sender, Network::Mainnet)
+            .input(outputs[0].clone())
+            .destination(to, 2_000_000_000)
+            .select_from(outputs[1..].to_vec(), OldestFirst)
+            .build(&mut chain)
+            .unwrap();
+        check(&pending, &chain);
+        assert_eq!(pending.tx.prefix.vin.len(), 2);
+        assert!(ring_contains(&pending, outputs[0].global_index));
+
+        let err = TransactionBuilder::new(&sender, Network::Mainnet)
+            .destination(to, 11_000_000_000)
+            .select_from(outputs, RandomSelection)
+            .build(&mut chain)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuildError::InsufficientFunds {
+                available: 11_000_000_000,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        let sender = WalletKeys::generate();
+        let to = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let (mut chain, outputs) = with_chain(&sender, &[1_000_000]);
+        let builder = || TransactionBuilder::new(&sender, Network::Mainnet);
+
+        assert_eq!(
+            builder().destination(to, 1).build(&mut chain).unwrap_err(),
+            BuildError::NoInputs
+        );
+        let err = builder()
+            .input(outputs[0].clone())
+            .destination(to, 1)
+            .build(&mut chain)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuildError::InsufficientFunds {
+                available: 1_000_000,
+                ..
+            }
+        ));
+        let stranger = WalletKeys::generate();
+        assert_eq!(
+            TransactionBuilder::new(&stranger, Network::Mainnet)
+                .input(outputs[0].clone())
+                .destination(to, 1)
+                .build(&mut chain)
+                .unwrap_err(),
+            BuildError::NotOwned(0)
+        );
+        let testnet = Address::from_keys(&sender, Network::Testnet);
+        assert_eq!(
+            builder()
+                .input(outputs[0].clone())
+                .destination(testnet, 1)
+                .build(&mut chain)
+                .unwrap_err(),
+            BuildError::WrongNetwork(Network::Testnet)
+        );
+
+        // one input with its ring and proofs is already over 1000
+        let err = builder()
+            .input(outputs[0].clone())
+            .destination(to, 1)
+            .fee_rate(FeeRate {
+                fee_per_byte: 0,
+                quantization_mask: 1,
+            })
+            .max_weight(1000)
+            .build(&mut chain)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuildError::TxTooLarge { weight, limit: 1000 } if weight > 1000
+        ));
+    }
+}
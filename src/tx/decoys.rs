@@ -0,0 +1,342 @@
+// src/tx/decoys.rs
+//! Decoy selection the way wallet2 does it.
+//!
+//! Spends are young: the age of a real input, in seconds, is roughly
+//! log-gamma distributed. Decoys are drawn from the same distribution over
+//! the chain's RingCT outputs, so the real output doesn't stand out by age.
+//! Ages are turned into output indices with the average output rate of the
+//! last year, from the daemon's output distribution.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::{Rng, rngs::OsRng};
+
+pub use super::unlock::{COINBASE_MATURITY, MAX_BLOCK_NUMBER, SPENDABLE_AGE};
+use super::{builder::DecoySource, rct::RingMember, unlock::OutputLock};
+pub use crate::consensus::difficulty::DIFFICULTY_TARGET;
+
+/// Shape and scale of wallet2's log-age distribution, fitted to spends
+const GAMMA_SHAPE: f64 = 19.28;
+const GAMMA_SCALE: f64 = 1.0 / 1.61;
+/// Ages under the lock are redrawn uniformly from this many seconds
+const RECENT_SPEND_WINDOW: u64 = 15 * DIFFICULTY_TARGET;
+/// Rounds of lookups, and draws per decoy in each, before giving up on a
+/// ring
+const MAX_ATTEMPTS: usize = 100;
+
+/// RingCT outputs per block, as `get_output_distribution` reports them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputDistribution {
+    /// Height of the first block covered
+    pub start_height: u64,
+    /// Per block from `start_height`, the number of RingCT outputs up to
+    /// and including it, counted from the first RingCT output
+    pub cumulative: Vec<u64>,
+}
+
+impl OutputDistribution {
+    /// Height the next block will have
+    pub fn height(&self) -> u64 {
+        self.start_height + self.cumulative.len() as u64
+    }
+}
+
+/// Draws global output indices like wallet2's `gamma_picker`
+#[derive(Debug, Clone)]
+pub struct GammaPicker {
+    /// Cumulative counts without the blocks too young to spend from
+    spendable: Vec<u64>,
+    average_output_time: f64,
+}
+
+impl GammaPicker {
+    /// None if no output is old enough to be picked
+    pub fn new(distribution: &OutputDistribution) -> Option<Self> {
+        let offsets = &distribution.cumulative;
+        let end = offsets.len().checked_sub(SPENDABLE_AGE as usize)?;
+        let spendable = offsets[..end].to_vec();
+        let n_outputs = *spendable.last()?;
+        if n_outputs == 0 {
+            return None;
+        }
+        // the output rate over the last year, young outputs included, as
+        // wallet2 counts it
+        let blocks_in_a_year = (86_400 * 365 / DIFFICULTY_TARGET) as usize;
+        let blocks = offsets.len().min(blocks_in_a_year);
+        let before = match blocks < offsets.len() {
+            true => offsets[offsets.len() - blocks - 1],
+            false => 0,
+        };
+        let total = *offsets.last()?;
+        let outputs = total.saturating_sub(before).max(1);
+        Some(Self {
+            spendable,
+            average_output_time: (DIFFICULTY_TARGET * blocks as u64) as f64 / outputs as f64,
+        })
+    }
+
+    /// Outputs old enough to be picked
+    pub fn num_outputs(&self) -> u64 {
+        *self.spendable.last().expect("checked in new")
+    }
+
+    /// One global index, or None if the drawn age is older than the chain
+    pub fn pick(&self, rng: &mut impl Rng) -> Option<u64> {
+        let age = sample_gamma(rng, GAMMA_SHAPE, GAMMA_SCALE).exp();
+        let lock = (SPENDABLE_AGE * DIFFICULTY_TARGET) as f64;
+        let age = match age > lock {
+            true => age - lock,
+            false => rng.gen_range(0..RECENT_SPEND_WINDOW) as f64,
+        };
+        let outputs_back = (age / self.average_output_time) as u64;
+        let n_outputs = self.num_outputs();
+        if outputs_back >= n_outputs {
+            return None;
+        }
+        // the block holding that output, then any output of the block
+        let target = n_outputs - 1 - outputs_back;
+        let block = self.spendable.partition_point(|&count| count < target);
+        let first = match block {
+            0 => 0,
+            b => self.spendable[b - 1],
+        };
+        let in_block = self.spendable[block] - first;
+        if in_block == 0 {
+            return None;
+        }
+        Some(first + rng.gen_range(0..in_block))
+    }
+}
+
+/// Marsaglia and Tsang's method, for `shape >= 1`
+fn sample_gamma(rng: &mut impl Rng, shape: f64, scale: f64) -> f64 {
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        // standard normal by Box-Muller
+        let u1: f64 = 1.0 - rng.r#gen::<f64>();
+        let u2: f64 = rng.r#gen();
+        let x = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u: f64 = 1.0 - rng.r#gen::<f64>();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v * scale;
+        }
+    }
+}
+
+/// A chain output as `get_outs` returns it, with what it takes to tell
+/// whether it can be spent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CandidateOutput {
+    pub member: RingMember,
+    /// Height of the block that created it
+    pub height: u64,
+    /// Its transaction's unlock time
+    pub unlock_time: u64,
+    /// Created by a miner transaction
+    pub coinbase: bool,
+}
+
+impl CandidateOutput {
+    /// Whether it could be spent in the block at `height`, mined around
+    /// `timestamp`
+    pub fn is_unlocked(&self, height: u64, timestamp: u64) -> bool {
+        self.lock().is_unlocked(height, timestamp)
+    }
+
+    pub fn lock(&self) -> OutputLock {
+        OutputLock {
+            height: self.height,
+            unlock_time: self.unlock_time,
+            coinbase: self.coinbase,
+        }
+    }
+}
+
+/// Looks up outputs by global index, typically a daemon's `get_outs`
+pub trait OutputLookup {
+    /// The outputs at `global_indices`, in order, or None if they can't be
+    /// had
+    fn outputs(&mut self, global_indices: &[u64]) -> Option<Vec<CandidateOutput>>;
+}
+
+/// [`DecoySource`] drawing decoys with [`GammaPicker`] and skipping those
+/// still locked
+pub struct GammaDecoys<L> {
+    picker: GammaPicker,
+    lookup: L,
+    height: u64,
+    timestamp: u64,
+}
+
+impl<L: OutputLookup> GammaDecoys<L> {
+    /// None if the chain has no outputs old enough to use
+    pub fn new(distribution: &OutputDistribution, lookup: L) -> Option<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Some(Self {
+            picker: GammaPicker::new(distribution)?,
+            lookup,
+            height: distribution.height(),
+            timestamp,
+        })
+    }
+}
+
+impl<L: OutputLookup> DecoySource for GammaDecoys<L> {
+    fn decoys(&mut self, real_index: u64, count: usize) -> Option<Vec<RingMember>> {
+        if count as u64 >= self.picker.num_outputs() {
+            return None;
+        }
+        let mut seen = vec![real_index];
+        let mut decoys = Vec::with_capacity(count);
+        for _ in 0..MAX_ATTEMPTS {
+            if decoys.len() == count {
+                return Some(decoys);
+            }
+            let mut batch = Vec::new();
+            for _ in 0..MAX_ATTEMPTS * count {
+                if batch.len() == count - decoys.len() {
+                    break;
+                }
+                match self.picker.pick(&mut OsRng) {
+                    Some(index) if !seen.contains(&index) => {
+                        seen.push(index);
+                        batch.push(index);
+                    }
+                    _ => {}
+                }
+            }
+            let outputs = self.lookup.outputs(&batch)?;
+            if outputs.len() != batch.len() {
+                return None;
+            }
+            decoys.extend(
+                outputs
+                    .iter()
+                    .filter(|output| output.is_unlocked(self.height, self.timestamp))
+                    .map(|output| output.member),
+            );
+        }
+        (decoys.len() == count).then_some(decoys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::{Scalar, constants::ED25519_BASEPOINT_POINT};
+
+    use super::*;
+    use crate::crypto::commitment::commit;
+
+    /// 10 outputs in every block of a 10 000 block chain
+    fn distribution() -> OutputDistribution {
+        OutputDistribution {
+            start_height: 0,
+            cumulative: (1..=10_000).map(|b| 10 * b).collect(),
+        }
+    }
+
+    /// Every output from block `height / 10`, odd ones time-locked
+    struct Chain;
+
+    impl OutputLookup for Chain {
+        fn outputs(&mut self, global_indices: &[u64]) -> Option<Vec<CandidateOutput>> {
+            let member = RingMember {
+                global_index: 0,
+                key: ED25519_BASEPOINT_POINT,
+                commitment: commit(0, Scalar::ONE),
+            };
+            Some(
+                global_indices
+                    .iter()
+                    .map(|&i| CandidateOutput {
+                        member: RingMember {
+                            global_index: i,
+                            ..member
+                        },
+                        height: i / 10,
+                        unlock_time: if i % 2 == 1 { 20_000 } else { 0 },
+                        coinbase: false,
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_picker() {
+        let picker = GammaPicker::new(&distribution()).unwrap();
+        assert_eq!(picker.num_outputs(), 99_900);
+        // a block every 120 s, 10 outputs each, the unspendable ones too
+        assert_eq!(picker.average_output_time, 12.0);
+        let picks: Vec<u64> = (0..2000).filter_map(|_| picker.pick(&mut OsRng)).collect();
+        assert!(picks.len() > 1000);
+        assert!(picks.iter().all(|&i| i < 99_900));
+        // most spends are under a few days old: 2 days is 14 400 outputs
+        let recent = picks.iter().filter(|&&i| i >= 99_900 - 14_400).count();
+        assert!(recent > picks.len() / 4, "{recent} of {}", picks.len());
+
+        let young = OutputDistribution {
+            start_height: 0,
+            cumulative: vec![5; 10],
+        };
+        assert!(GammaPicker::new(&young).is_none());
+    }
+
+    #[test]
+    fn test_gamma_mean() {
+        let n = 20_000;
+        let mean: f64 = (0..n)
+            .map(|_| sample_gamma(&mut OsRng, GAMMA_SHAPE, GAMMA_SCALE))
+            .sum::<f64>()
+            / n as f64;
+        assert!((mean - GAMMA_SHAPE * GAMMA_SCALE).abs() < 0.1, "{mean}");
+    }
+
+    #[test]
+    fn test_gamma_decoys() {
+        let mut decoys = GammaDecoys::new(&distribution(), Chain).unwrap();
+        let ring = decoys.decoys(99_000, 15).unwrap();
+        assert_eq!(ring.len(), 15);
+        let mut indices: Vec<u64> = ring.iter().map(|m| m.global_index).collect();
+        indices.sort();
+        indices.dedup();
+        assert_eq!(indices.len(), 15);
+        assert!(!indices.contains(&99_000));
+        assert!(indices.iter().all(|i| i % 2 == 0));
+    }
+
+    #[test]
+    fn test_unlocked() {
+        let output = CandidateOutput {
+            member: RingMember {
+                global_index: 0,
+                key: ED25519_BASEPOINT_POINT,
+                commitment: ED25519_BASEPOINT_POINT,
+            },
+            height: 100,
+            unlock_time: 0,
+            coinbase: false,
+        };
+        assert!(!output.is_unlocked(109, 0));
+        assert!(output.is_unlocked(110, 0));
+        let coinbase = CandidateOutput {
+            coinbase: true,
+            unlock_time: 160,
+            ..output
+        };
+        assert!(!coinbase.is_unlocked(159, 0));
+        assert!(coinbase.is_unlocked(160, 0));
+        let timed = CandidateOutput {
+            unlock_time: 1_700_000_000,
+            ..output
+        };
+        assert!(!timed.is_unlocked(200, 1_600_000_000));
+        assert!(timed.is_unlocked(200, 1_700_000_000));
+    }
+}
@@ -0,0 +1,487 @@
+// src/tx/extra.rs
+//! The tx_extra field.
+//!
+//! A sequence of tagged fields: the tx public key `R = r·G`, per-output
+//! public keys for subaddress payments, a nonce (which carries payment IDs)
+//! and trailing zero padding. Consensus only requires the bytes to exist, so
+//! mainnet holds plenty of oddities: repeated keys, garbage tails, unknown
+//! tags. [`Extra::parse_lenient`] reads such extras as far as it can, the
+//! way monerod does; [`Extra::parse`] and [`Extra::to_bytes`] enforce the
+//! rules a newly built transaction should meet.
+use std::fmt;
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+
+use super::{Reader, TxError};
+use crate::address::PaymentId;
+use crate::crypto::{
+    hash::{Hash, Hash256},
+    keys::KeyDerivation,
+};
+use crate::encoding::varint::{self, VarintError};
+
+const TAG_PADDING: u8 = 0x00;
+const TAG_PUBKEY: u8 = 0x01;
+const TAG_NONCE: u8 = 0x02;
+const TAG_MERGE_MINING: u8 = 0x03;
+const TAG_ADDITIONAL_PUBKEYS: u8 = 0x04;
+const TAG_MINERGATE: u8 = 0xde;
+
+const NONCE_PAYMENT_ID: u8 = 0x00;
+const NONCE_ENCRYPTED_PAYMENT_ID: u8 = 0x01;
+
+/// Longest nonce monerod will relay
+pub const MAX_NONCE_LEN: usize = 255;
+/// Longest run of padding, counting its tag
+pub const MAX_PADDING_LEN: usize = 255;
+
+/// Why a tx_extra is malformed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraError {
+    /// Input ended inside a field
+    Truncated,
+    Varint(VarintError),
+    UnknownTag(u8),
+    /// Length larger than the remaining input could hold
+    TooLarge(u64),
+    /// Padding contains a non-zero byte
+    InvalidPadding,
+    PaddingTooLong(usize),
+    NonceTooLong(usize),
+    /// A field that may appear once appears again, by tag
+    Duplicate(u8),
+    /// A public key that isn't a curve point
+    InvalidKey,
+    /// Bytes left inside a length-delimited field
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for ExtraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtraError::Truncated => write!(f, "tx_extra truncated"),
+            ExtraError::Varint(e) => write!(f, "invalid varint: {e}"),
+            ExtraError::UnknownTag(t) => write!(f, "unknown tx_extra tag {t:#04x}"),
+            ExtraError::TooLarge(n) => write!(f, "length {n} exceeds the remaining data"),
+            ExtraError::InvalidPadding => write!(f, "non-zero byte in padding"),
+            ExtraError::PaddingTooLong(n) => {
+                write!(f, "{n} bytes of padding, at most {MAX_PADDING_LEN} allowed")
+            }
+            ExtraError::NonceTooLong(n) => {
+                write!(f, "{n}-byte nonce, at most {MAX_NONCE_LEN} allowed")
+            }
+            ExtraError::Duplicate(t) => write!(f, "duplicate tx_extra field {t:#04x}"),
+            ExtraError::InvalidKey => write!(f, "public key is not a curve point"),
+            ExtraError::TrailingBytes(n) => write!(f, "{n} trailing bytes in field"),
+        }
+    }
+}
+
+impl std::error::Error for ExtraError {}
+
+impl From<TxError> for ExtraError {
+    fn from(e: TxError) -> Self {
+        match e {
+            TxError::Truncated => ExtraError::Truncated,
+            TxError::Varint(e) => ExtraError::Varint(e),
+            TxError::TooLarge(n) => ExtraError::TooLarge(n),
+            TxError::TrailingBytes(n) => ExtraError::TrailingBytes(n),
+            TxError::UnknownInputType(_)
+            | TxError::UnknownOutputType(_)
+            | TxError::UnknownRctType(_)
+            | TxError::InvalidScalar
+            | TxError::InvalidPoint
+            | TxError::UnknownVersion(_)
+            | TxError::InvalidHex
+            | TxError::PrunableHashMismatch => {
+                unreachable!("not produced by tx_extra parsing")
+            }
+        }
+    }
+}
+
+/// Contents of a nonce field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtraNonce {
+    /// Legacy 32-byte payment ID, in the clear
+    PaymentId([u8; 32]),
+    /// 8-byte payment ID of an integrated address, encrypted
+    EncryptedPaymentId([u8; 8]),
+    /// Anything else, e.g. a pool's extra nonce in a coinbase
+    Other(Vec<u8>),
+}
+
+impl ExtraNonce {
+    /// Nonce carrying `id` encrypted under `derivation` (see
+    /// [`PaymentId::encrypt`])
+    pub fn encrypted_payment_id(id: &PaymentId, derivation: &KeyDerivation) -> Self {
+        ExtraNonce::EncryptedPaymentId(id.encrypt(derivation))
+    }
+
+    fn from_bytes(data: &[u8]) -> Self {
+        match data {
+            [NONCE_PAYMENT_ID, id @ ..] if id.len() == 32 => {
+                ExtraNonce::PaymentId(id.try_into().unwrap())
+            }
+            [NONCE_ENCRYPTED_PAYMENT_ID, id @ ..] if id.len() == 8 => {
+                ExtraNonce::EncryptedPaymentId(id.try_into().unwrap())
+            }
+            _ => ExtraNonce::Other(data.to_vec()),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            ExtraNonce::PaymentId(id) => [&[NONCE_PAYMENT_ID][..], id].concat(),
+            ExtraNonce::EncryptedPaymentId(id) => [&[NONCE_ENCRYPTED_PAYMENT_ID][..], id].concat(),
+            ExtraNonce::Other(data) => data.clone(),
+        }
+    }
+}
+
+/// One tx_extra field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtraField {
+    /// `len` zero bytes, the tag included; only valid at the end
+    Padding(usize),
+    /// Transaction public key `R`
+    PublicKey(CompressedEdwardsY),
+    Nonce(ExtraNonce),
+    /// Merged-mining commitment in a coinbase
+    MergeMining {
+        depth: u64,
+        merkle_root: Hash256,
+    },
+    /// One key per output, for transactions paying subaddresses
+    AdditionalPublicKeys(Vec<CompressedEdwardsY>),
+    /// Opaque field written by the MinerGate pool
+    Minergate(Vec<u8>),
+}
+
+impl ExtraField {
+    fn tag(&self) -> u8 {
+        match self {
+            ExtraField::Padding(_) => TAG_PADDING,
+            ExtraField::PublicKey(_) => TAG_PUBKEY,
+            ExtraField::Nonce(_) => TAG_NONCE,
+            ExtraField::MergeMining { .. } => TAG_MERGE_MINING,
+            ExtraField::AdditionalPublicKeys(_) => TAG_ADDITIONAL_PUBKEYS,
+            ExtraField::Minergate(_) => TAG_MINERGATE,
+        }
+    }
+
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.push(self.tag());
+        match self {
+            ExtraField::Padding(len) => out.resize(out.len() + len.saturating_sub(1), 0),
+            ExtraField::PublicKey(key) => out.extend_from_slice(key.as_bytes()),
+            ExtraField::Nonce(nonce) => write_blob(out, &nonce.to_bytes()),
+            ExtraField::MergeMining { depth, merkle_root } => {
+                let mut blob = Vec::new();
+                varint::write(&mut blob, *depth);
+                blob.extend_from_slice(merkle_root.as_bytes());
+                write_blob(out, &blob);
+            }
+            ExtraField::AdditionalPublicKeys(keys) => {
+                varint::write(out, keys.len() as u64);
+                for key in keys {
+                    out.extend_from_slice(key.as_bytes());
+                }
+            }
+            ExtraField::Minergate(data) => write_blob(out, data),
+        }
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, ExtraError> {
+        Ok(match r.u8()? {
+            TAG_PADDING => {
+                // padding runs to the end of the extra
+                let rest = r.take(r.remaining())?;
+                if rest.iter().any(|&b| b != 0) {
+                    return Err(ExtraError::InvalidPadding);
+                }
+                ExtraField::Padding(rest.len() + 1)
+            }
+            TAG_PUBKEY => ExtraField::PublicKey(CompressedEdwardsY(r.array()?)),
+            TAG_NONCE => ExtraField::Nonce(ExtraNonce::from_bytes(read_blob(r)?)),
+            TAG_MERGE_MINING => {
+                let mut blob = Reader::new(read_blob(r)?);
+                let depth = blob.varint()?;
+                let merkle_root = Hash(blob.array()?);
+                blob.finish()?;
+                ExtraField::MergeMining { depth, merkle_root }
+            }
+            TAG_ADDITIONAL_PUBKEYS => ExtraField::AdditionalPublicKeys(
+                (0..r.count(32)?)
+                    .map(|_| Ok(CompressedEdwardsY(r.array()?)))
+                    .collect::<Result<_, TxError>>()?,
+            ),
+            TAG_MINERGATE => ExtraField::Minergate(read_blob(r)?.to_vec()),
+            t => return Err(ExtraError::UnknownTag(t)),
+        })
+    }
+}
+
+fn write_blob(out: &mut Vec<u8>, data: &[u8]) {
+    varint::write(out, data.len() as u64);
+    out.extend_from_slice(data);
+}
+
+fn read_blob<'a>(r: &mut Reader<'a>) -> Result<&'a [u8], TxError> {
+    let len = r.count(1)?;
+    r.take(len)
+}
+
+/// A parsed or to-be-built tx_extra
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Extra {
+    pub fields: Vec<ExtraField>,
+}
+
+impl Extra {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a field, builder-style
+    pub fn with(mut self, field: ExtraField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn push(&mut self, field: ExtraField) {
+        self.fields.push(field);
+    }
+
+    /// Parse an extra that must be well-formed throughout and satisfy
+    /// [`Extra::validate`]
+    pub fn parse(data: &[u8]) -> Result<Self, ExtraError> {
+        let mut reader = Reader::new(data);
+        let mut extra = Self::new();
+        while reader.remaining() > 0 {
+            extra.fields.push(ExtraField::read(&mut reader)?);
+        }
+        extra.validate()?;
+        Ok(extra)
+    }
+
+    /// Parse fields until the first malformed one and keep those
+    ///
+    /// Never fails; duplicates and invalid keys are kept as found. This is
+    /// what scanning historic transactions needs.
+    pub fn parse_lenient(data: &[u8]) -> Self {
+        let mut reader = Reader::new(data);
+        let mut extra = Self::new();
+        while reader.remaining() > 0 {
+            match ExtraField::read(&mut reader) {
+                Ok(field) => extra.fields.push(field),
+                Err(_) => break,
+            }
+        }
+        extra
+    }
+
+    /// Check the rules for a newly built extra: at most one public key,
+    /// nonce and additional-keys field, padding only last, lengths in
+    /// bounds and every key a curve point
+    pub fn validate(&self) -> Result<(), ExtraError> {
+        let mut seen = Vec::new();
+        for (i, field) in self.fields.iter().enumerate() {
+            let tag = field.tag();
+            if matches!(tag, TAG_PUBKEY | TAG_NONCE | TAG_ADDITIONAL_PUBKEYS) {
+                if seen.contains(&tag) {
+                    return Err(ExtraError::Duplicate(tag));
+                }
+                seen.push(tag);
+            }
+            match field {
+                ExtraField::Padding(len) => {
+                    if i + 1 != self.fields.len() {
+                        return Err(ExtraError::InvalidPadding);
+                    }
+                    if !(1..=MAX_PADDING_LEN).contains(len) {
+                        return Err(ExtraError::PaddingTooLong(*len));
+                    }
+                }
+                ExtraField::Nonce(nonce) => {
+                    let len = nonce.to_bytes().len();
+                    if len > MAX_NONCE_LEN {
+                        return Err(ExtraError::NonceTooLong(len));
+                    }
+                }
+                ExtraField::PublicKey(key) => check_key(key)?,
+                ExtraField::AdditionalPublicKeys(keys) => keys.iter().try_for_each(check_key)?,
+                ExtraField::MergeMining { .. } | ExtraField::Minergate(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialise after checking [`Extra::validate`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ExtraError> {
+        self.validate()?;
+        let mut out = Vec::new();
+        for field in &self.fields {
+            field.serialize(&mut out);
+        }
+        Ok(out)
+    }
+
+    /// First transaction public key
+    pub fn tx_public_key(&self) -> Option<&CompressedEdwardsY> {
+        self.fields.iter().find_map(|f| match f {
+            ExtraField::PublicKey(key) => Some(key),
+            _ => None,
+        })
+    }
+
+    /// Per-output public keys, empty if there are none
+    pub fn additional_public_keys(&self) -> &[CompressedEdwardsY] {
+        self.fields
+            .iter()
+            .find_map(|f| match f {
+                ExtraField::AdditionalPublicKeys(keys) => Some(&keys[..]),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+
+    /// First nonce
+    pub fn nonce(&self) -> Option<&ExtraNonce> {
+        self.fields.iter().find_map(|f| match f {
+            ExtraField::Nonce(nonce) => Some(nonce),
+            _ => None,
+        })
+    }
+
+    /// Decrypted payment ID of the nonce, if it carries an encrypted one
+    pub fn payment_id(&self, derivation: &KeyDerivation) -> Option<PaymentId> {
+        match self.nonce()? {
+            ExtraNonce::EncryptedPaymentId(id) => Some(PaymentId::decrypt(id, derivation)),
+            _ => None,
+        }
+    }
+}
+
+fn check_key(key: &CompressedEdwardsY) -> Result<(), ExtraError> {
+    key.decompress().map(|_| ()).ok_or(ExtraError::InvalidKey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar};
+
+    fn key(n: u8) -> CompressedEdwardsY {
+        (ED25519_BASEPOINT_TABLE * &Scalar::from(n)).compress()
+    }
+
+    #[test]
+    fn test_mainnet_extras() {
+        // v1 coinbase: tx key and an 8-byte pool nonce
+        let data = hex::decode(
+            "0104d46cf1a204ae727c14473d67ea95da3e97b250f3c63e0997198bfc812d7a81020800000000d8111b25",
+        )
+        .unwrap();
+        let extra = Extra::parse(&data).unwrap();
+        assert_eq!(
+            hex::encode(extra.tx_public_key().unwrap().as_bytes()),
+            "04d46cf1a204ae727c14473d67ea95da3e97b250f3c63e0997198bfc812d7a81"
+        );
+        assert_eq!(
+            extra.nonce(),
+            Some(&ExtraNonce::Other(hex::decode("00000000d8111b25").unwrap()))
+        );
+        assert!(extra.additional_public_keys().is_empty());
+        assert_eq!(extra.to_bytes().unwrap(), data);
+
+        // v2 coinbase with a 17-byte nonce
+        let data = hex::decode(
+            "01b39bdcdfd51751a05fe85766973f46f98b286e1033c1afd0267841bf9b8b0104\
+             0211c42504001b25bba3000000000000000000",
+        )
+        .unwrap();
+        let extra = Extra::parse(&data).unwrap();
+        assert_eq!(extra.fields.len(), 2);
+        assert_eq!(extra.to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn test_build() {
+        let extra = Extra::new()
+            .with(ExtraField::PublicKey(key(1)))
+            .with(ExtraField::AdditionalPublicKeys(vec![key(2), key(3)]))
+            .with(ExtraField::Nonce(ExtraNonce::EncryptedPaymentId([9; 8])))
+            .with(ExtraField::Padding(4));
+        let bytes = extra.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 33 + 2 + 64 + 11 + 4);
+        assert_eq!(bytes[bytes.len() - 4..], [0; 4]);
+        assert_eq!(Extra::parse(&bytes).unwrap(), extra);
+        assert_eq!(extra.additional_public_keys(), [key(2), key(3)]);
+
+        let derivation = KeyDerivation(*key(4).as_bytes());
+        let id = PaymentId([0xab; 8]);
+        let extra = Extra::new().with(ExtraField::Nonce(ExtraNonce::encrypted_payment_id(
+            &id,
+            &derivation,
+        )));
+        let parsed = Extra::parse(&extra.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed.payment_id(&derivation), Some(id));
+
+        let merge_mining = ExtraField::MergeMining {
+            depth: 3,
+            merkle_root: Hash([5; 32]),
+        };
+        let extra = Extra::new().with(merge_mining.clone());
+        assert_eq!(
+            Extra::parse(&extra.to_bytes().unwrap()).unwrap().fields,
+            [merge_mining]
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        let twice = Extra::new()
+            .with(ExtraField::PublicKey(key(1)))
+            .with(ExtraField::PublicKey(key(2)));
+        assert_eq!(twice.to_bytes(), Err(ExtraError::Duplicate(TAG_PUBKEY)));
+
+        let not_last = Extra::new()
+            .with(ExtraField::Padding(2))
+            .with(ExtraField::PublicKey(key(1)));
+        assert_eq!(not_last.validate(), Err(ExtraError::InvalidPadding));
+
+        let long = Extra::new().with(ExtraField::Nonce(ExtraNonce::Other(vec![0; 256])));
+        assert_eq!(long.validate(), Err(ExtraError::NonceTooLong(256)));
+
+        // y = 2 has no x on the curve
+        let mut bad = [0; 32];
+        bad[0] = 2;
+        let bad_key = Extra::new().with(ExtraField::PublicKey(CompressedEdwardsY(bad)));
+        assert_eq!(bad_key.validate(), Err(ExtraError::InvalidKey));
+
+        assert_eq!(
+            Extra::parse(&[0x00, 0x00, 0x01]),
+            Err(ExtraError::InvalidPadding)
+        );
+        assert_eq!(Extra::parse(&[0x01, 0x00]), Err(ExtraError::Truncated));
+        assert_eq!(Extra::parse(&[0x07]), Err(ExtraError::UnknownTag(0x07)));
+    }
+
+    #[test]
+    fn test_lenient() {
+        // duplicate keys followed by garbage, as seen on mainnet
+        let mut data = Vec::new();
+        for k in [key(1), key(2)] {
+            data.push(TAG_PUBKEY);
+            data.extend_from_slice(k.as_bytes());
+        }
+        data.extend_from_slice(&[0x02, 0x21, 0x00, 0x01]);
+        assert!(Extra::parse(&data).is_err());
+
+        let extra = Extra::parse_lenient(&data);
+        assert_eq!(extra.fields.len(), 2);
+        assert_eq!(extra.tx_public_key(), Some(&key(1)));
+        assert_eq!(extra.nonce(), None);
+    }
+}
@@ -0,0 +1,165 @@
+// src/tx/fees.rs
+//! Fee estimation, before any inputs are signed.
+//!
+//! Fees are charged per byte of weight: `weight * fee_per_byte *
+//! multiplier`, rounded up to the daemon's quantization mask. The weight is
+//! estimated like wallet2's `estimate_tx_weight` for Bulletproof+/CLSAG
+//! transactions with view tags, which slightly overshoots the real one so
+//! the final fee always covers it.
+use super::transaction::bulletproof_plus_clawback;
+
+/// Per-byte base fee used when no daemon reports one (`get_fee_estimate`)
+pub const DEFAULT_FEE_PER_BYTE: u64 = 20_000;
+/// Granularity fees are rounded up to, as monerod reports it
+pub const DEFAULT_FEE_QUANTIZATION_MASK: u64 = 10_000;
+/// tx_extra of a typical transfer: the tx public key and an encrypted
+/// payment ID nonce
+pub const TYPICAL_EXTRA_LEN: usize = 1 + 32 + 1 + 1 + 1 + 8;
+
+/// How much over the base fee to pay
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeePriority {
+    /// What wallet2 uses while blocks aren't full
+    #[default]
+    Low,
+    Normal,
+    Elevated,
+    Priority,
+}
+
+impl FeePriority {
+    /// wallet2's fee multiplier for this priority
+    pub fn multiplier(self) -> u64 {
+        match self {
+            FeePriority::Low => 1,
+            FeePriority::Normal => 5,
+            FeePriority::Elevated => 25,
+            FeePriority::Priority => 1000,
+        }
+    }
+}
+
+/// The daemon's current base fee, as `get_fee_estimate` reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeRate {
+    pub fee_per_byte: u64,
+    pub quantization_mask: u64,
+}
+
+impl Default for FeeRate {
+    fn default() -> Self {
+        Self {
+            fee_per_byte: DEFAULT_FEE_PER_BYTE,
+            quantization_mask: DEFAULT_FEE_QUANTIZATION_MASK,
+        }
+    }
+}
+
+/// Where fee rates come from, typically a daemon's `get_fee_estimate`
+pub trait FeeSource {
+    /// The current rate, or None if it can't be had
+    fn fee_rate(&mut self) -> Option<FeeRate>;
+}
+
+/// Fee for a transaction of `weight`, or None on overflow
+pub fn fee_for_weight(weight: usize, priority: FeePriority, rate: &FeeRate) -> Option<u64> {
+    let mask = rate.quantization_mask.max(1);
+    let fee = (weight as u64)
+        .checked_mul(rate.fee_per_byte)?
+        .checked_mul(priority.multiplier())?;
+    fee.div_ceil(mask).checked_mul(mask)
+}
+
+/// Upper bound on the weight of a transaction, change output included in
+/// `n_outputs`
+pub fn estimate_weight(
+    n_inputs: usize,
+    n_outputs: usize,
+    ring_size: usize,
+    extra_len: usize,
+) -> usize {
+    let log_padded_outputs = n_outputs.max(1).next_power_of_two().trailing_zeros() as usize;
+    // version and unlock time
+    let prefix = 1 + 6
+        // amount, offsets of up to 2 bytes each and the key image
+        + n_inputs * (1 + 6 + ring_size * 2 + 32)
+        + n_outputs * (6 + 32)
+        + extra_len;
+    // type, proof with its counts, CLSAGs, pseudo-outputs, amounts,
+    // commitments, fee and view tags
+    let rct = 1
+        + (2 * (6 + log_padded_outputs) + 6) * 32
+        + 3
+        + n_inputs * (32 * ring_size + 64)
+        + 32 * n_inputs
+        + 8 * n_outputs
+        + 32 * n_outputs
+        + 4
+        + n_outputs;
+    prefix + rct + bulletproof_plus_clawback(n_outputs)
+}
+
+/// Fee for a typical transfer spending `n_inputs` into `n_outputs`
+pub fn estimate(
+    n_inputs: usize,
+    n_outputs: usize,
+    ring_size: usize,
+    priority: FeePriority,
+    rate: FeeRate,
+) -> u64 {
+    let weight = estimate_weight(n_inputs, n_outputs, ring_size, TYPICAL_EXTRA_LEN);
+    fee_for_weight(weight, priority, &rate).unwrap_or(u64::MAX)
+}
+
+/// [`estimate`] at the rate `source` reports, or the default one if it
+/// can't
+pub fn estimate_from(
+    source: &mut impl FeeSource,
+    n_inputs: usize,
+    n_outputs: usize,
+    ring_size: usize,
+    priority: FeePriority,
+) -> u64 {
+    let rate = source.fee_rate().unwrap_or_default();
+    estimate(n_inputs, n_outputs, ring_size, priority, rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_weight() {
+        // 1 input, 2 outputs, ring 16: the common wallet transfer
+        assert_eq!(estimate_weight(1, 2, 16, TYPICAL_EXTRA_LEN), 1536);
+        assert_eq!(
+            estimate_weight(2, 3, 16, TYPICAL_EXTRA_LEN),
+            1536 + 679 + 79 + 64 + bulletproof_plus_clawback(3)
+        );
+    }
+
+    #[test]
+    fn test_estimate() {
+        let rate = FeeRate::default();
+        assert_eq!(estimate(1, 2, 16, FeePriority::Low, rate), 30_720_000);
+        assert_eq!(estimate(1, 2, 16, FeePriority::Normal, rate), 153_600_000);
+
+        let rate = FeeRate {
+            fee_per_byte: 21_333,
+            quantization_mask: 10_000,
+        };
+        assert_eq!(estimate(1, 2, 16, FeePriority::Low, rate), 32_770_000);
+        assert_eq!(fee_for_weight(usize::MAX, FeePriority::Low, &rate), None);
+
+        struct Offline;
+        impl FeeSource for Offline {
+            fn fee_rate(&mut self) -> Option<FeeRate> {
+                None
+            }
+        }
+        assert_eq!(
+            estimate_from(&mut Offline, 1, 2, 16, FeePriority::Low),
+            30_720_000
+        );
+    }
+}
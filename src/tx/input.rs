@@ -0,0 +1,182 @@
+// src/tx/input.rs
+//! Transaction inputs.
+//!
+//! A coinbase input only names the block height. Every other input spends
+//! one member of a ring: it lists the ring's global output indices and the
+//! key image of the real one. On the wire the indices are deltas, each
+//! relative to the previous, which keeps the varints short.
+use super::{Reader, TxError};
+use crate::crypto::cn::KeyImage;
+use crate::encoding::varint;
+
+const TAG_GEN: u8 = 0xff;
+const TAG_TO_KEY: u8 = 0x02;
+
+/// Coinbase input: mints the reward of block `height`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxInGen {
+    pub height: u64,
+}
+
+/// Ring input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxInToKey {
+    /// Zero for RingCT outputs
+    pub amount: u64,
+    /// Global output indices of the ring as deltas
+    pub key_offsets: Vec<u64>,
+    pub key_image: KeyImage,
+}
+
+impl TxInToKey {
+    /// Input over the ring at `indices`, which must be strictly increasing
+    ///
+    /// Panics otherwise: deltas can't encode an unsorted or repeated ring,
+    /// and consensus would reject it anyway.
+    pub fn from_absolute(amount: u64, indices: &[u64], key_image: KeyImage) -> Self {
+        let mut prev = None;
+        let key_offsets = indices
+            .iter()
+            .map(|&i| {
+                let delta = match prev {
+                    None => i,
+                    Some(p) if i > p => i - p,
+                    Some(_) => panic!("ring indices must be strictly increasing"),
+                };
+                prev = Some(i);
+                delta
+            })
+            .collect();
+        Self {
+            amount,
+            key_offsets,
+            key_image,
+        }
+    }
+
+    /// Global output indices of the ring, or None if they overflow
+    pub fn absolute_offsets(&self) -> Option<Vec<u64>> {
+        let mut sum = 0u64;
+        self.key_offsets
+            .iter()
+            .map(|&d| {
+                sum = sum.checked_add(d)?;
+                Some(sum)
+            })
+            .collect()
+    }
+
+    pub fn ring_size(&self) -> usize {
+        self.key_offsets.len()
+    }
+}
+
+/// A transaction input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxIn {
+    Gen(TxInGen),
+    ToKey(TxInToKey),
+}
+
+impl TxIn {
+    /// Key image of a ring input
+    pub fn key_image(&self) -> Option<&KeyImage> {
+        match self {
+            TxIn::ToKey(input) => Some(&input.key_image),
+            TxIn::Gen(_) => None,
+        }
+    }
+
+    /// Append the consensus encoding to `out`
+    pub fn serialize(&self, out: &mut Vec<u8>) {
+        match self {
+            TxIn::Gen(input) => {
+                out.push(TAG_GEN);
+                varint::write(out, input.height);
+            }
+            TxIn::ToKey(input) => {
+                out.push(TAG_TO_KEY);
+                varint::write(out, input.amount);
+                varint::write(out, input.key_offsets.len() as u64);
+                for offset in &input.key_offsets {
+                    varint::write(out, *offset);
+                }
+                out.extend_from_slice(&input.key_image.0);
+            }
+        }
+    }
+
+    pub(crate) fn read(r: &mut Reader<'_>) -> Result<Self, TxError> {
+        match r.u8()? {
+            TAG_GEN => Ok(TxIn::Gen(TxInGen {
+                height: r.varint()?,
+            })),
+            TAG_TO_KEY => {
+                let amount = r.varint()?;
+                let key_offsets = (0..r.count(1)?)
+                    .map(|_| r.varint())
+                    .collect::<Result<_, _>>()?;
+                Ok(TxIn::ToKey(TxInToKey {
+                    amount,
+                    key_offsets,
+                    key_image: KeyImage(r.array()?),
+                }))
+            }
+            t => Err(TxError::UnknownInputType(t)),
+        }
+    }
+}
+
+impl From<TxInGen> for TxIn {
+    fn from(input: TxInGen) -> Self {
+        TxIn::Gen(input)
+    }
+}
+
+impl From<TxInToKey> for TxIn {
+    fn from(input: TxInToKey) -> Self {
+        TxIn::ToKey(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offsets() {
+        let input = TxInToKey::from_absolute(0, &[5, 9, 100, 101], KeyImage([7; 32]));
+        assert_eq!(input.key_offsets, [5, 4, 91, 1]);
+        assert_eq!(input.absolute_offsets().unwrap(), [5, 9, 100, 101]);
+        assert_eq!(input.ring_size(), 4);
+
+        let overflow = TxInToKey {
+            amount: 0,
+            key_offsets: vec![u64::MAX, 1],
+            key_image: KeyImage([0; 32]),
+        };
+        assert_eq!(overflow.absolute_offsets(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly increasing")]
+    fn test_unsorted_ring() {
+        TxInToKey::from_absolute(0, &[9, 5], KeyImage([0; 32]));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let input = TxIn::from(TxInToKey::from_absolute(0, &[1, 300], KeyImage([0xaa; 32])));
+        let mut bytes = Vec::new();
+        input.serialize(&mut bytes);
+        assert_eq!(bytes[..6], [0x02, 0x00, 0x02, 0x01, 0xab, 0x02]);
+        assert_eq!(TxIn::read(&mut Reader::new(&bytes)).unwrap(), input);
+        assert_eq!(input.key_image(), Some(&KeyImage([0xaa; 32])));
+
+        let coinbase = TxIn::from(TxInGen { height: 1000 });
+        let mut bytes = Vec::new();
+        coinbase.serialize(&mut bytes);
+        assert_eq!(bytes, [0xff, 0xe8, 0x07]);
+        assert_eq!(coinbase.key_image(), None);
+    }
+}
@@ -0,0 +1,164 @@
+// src/tx/key_images.rs
+//! Key image export from a cold wallet to a watch-only one.
+//!
+//! A watch-only wallet sees its incoming outputs but can't compute their key
+//! images, so it can't tell when one is spent. The cold wallet exports
+//! each key image with a ring signature over a ring of one: the output's
+//! own key. That proves the image belongs to the output, so a tampered
+//! file can't make the watch-only wallet believe an output is spent or
+//! unspent. This is wallet2's `export_key_images`. The file is encrypted
+//! like the tx sets in [`offline`](super::offline).
+use curve25519_dalek::{EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT};
+
+use super::{
+    Reader, TxError,
+    builder::{OwnedOutput, output_secret},
+    offline::{OfflineError, open, seal},
+};
+use crate::crypto::{
+    cn::{KeyImage, generate_key_image},
+    keys::WalletKeys,
+    ring::cryptonote::{self, RingSignature},
+};
+
+const MAGIC: &[u8] = b"monero_rust key image export\x01";
+
+/// A key image with the proof that it belongs to an output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedKeyImage {
+    pub key_image: KeyImage,
+    /// Ring signature over the key image, the output key as the only
+    /// member
+    pub signature: RingSignature,
+}
+
+impl SignedKeyImage {
+    /// Key image of `output` and its proof; a zero proof, which won't
+    /// verify, if `keys` don't own it
+    pub fn new(keys: &WalletKeys, output: &OwnedOutput) -> Self {
+        let secret = output_secret(keys, output);
+        let key_image = generate_key_image(&secret, &output.key);
+        let signature =
+            cryptonote::sign(key_image.as_bytes(), &key_image, &[output.key], &secret, 0)
+                .unwrap_or_else(|_| RingSignature(vec![(Scalar::ZERO, Scalar::ZERO)]));
+        Self {
+            key_image,
+            signature,
+        }
+    }
+
+    /// Whether this is the key image of the output with one-time key
+    /// `output_key`
+    pub fn verify(&self, output_key: &EdwardsPoint) -> bool {
+        cryptonote::verify(
+            self.key_image.as_bytes(),
+            &self.key_image,
+            std::slice::from_ref(output_key),
+            &self.signature,
+        )
+    }
+}
+
+/// Encrypted file with the signed key images of `outputs`, in order
+pub fn export_key_images(keys: &WalletKeys, outputs: &[OwnedOutput]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(64 + 96 * outputs.len());
+    out.extend_from_slice(keys.spend_public().compress().as_bytes());
+    out.extend_from_slice(keys.view_public().compress().as_bytes());
+    for output in outputs {
+        let signed = SignedKeyImage::new(keys, output);
+        out.extend_from_slice(signed.key_image.as_bytes());
+        out.extend_from_slice(&signed.signature.to_bytes());
+    }
+    seal(MAGIC, keys.view_secret(), &out)
+}
+
+/// Read an export of `outputs`' key images and check every proof. Needs
+/// only the view secret and spend public key.
+pub fn import_key_images(
+    data: &[u8],
+    view_secret: &Scalar,
+    spend_public: &EdwardsPoint,
+    outputs: &[OwnedOutput],
+) -> Result<Vec<SignedKeyImage>, OfflineError> {
+    let payload = open(MAGIC, view_secret, data)?;
+    let mut r = Reader::new(&payload);
+    let view_public = view_secret * ED25519_BASEPOINT_POINT;
+    if r.array()? != spend_public.compress().to_bytes()
+        || r.array()? != view_public.compress().to_bytes()
+    {
+        return Err(OfflineError::WrongWallet);
+    }
+    if !r.remaining().is_multiple_of(96) {
+        return Err(TxError::TrailingBytes(r.remaining() % 96).into());
+    }
+    let found = r.remaining() / 96;
+    if found != outputs.len() {
+        return Err(OfflineError::KeyImageCount {
+            expected: outputs.len(),
+            found,
+        });
+    }
+    let mut imported = Vec::with_capacity(found);
+    for (i, output) in outputs.iter().enumerate() {
+        let key_image = KeyImage(r.array()?);
+        let signed = RingSignature::from_bytes(r.take(64)?)
+            .map(|signature| SignedKeyImage {
+                key_image,
+                signature,
+            })
+            .filter(|signed| signed.verify(&output.key))
+            .ok_or(OfflineError::InvalidKeyImage(i))?;
+        imported.push(signed);
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::builder::tests::with_chain;
+
+    #[test]
+    fn test_export_import() {
+        let keys = WalletKeys::generate();
+        let (_, outputs) = with_chain(&keys, &[5, 6, 7]);
+        let file = export_key_images(&keys, &outputs);
+
+        let view = keys.view_secret();
+        let imported = import_key_images(&file, view, keys.spend_public(), &outputs).unwrap();
+        assert_eq!(imported.len(), 3);
+        for (signed, output) in imported.iter().zip(&outputs) {
+            let secret = output_secret(&keys, output);
+            assert_eq!(signed.key_image, generate_key_image(&secret, &output.key));
+            assert!(signed.verify(&output.key));
+        }
+
+        // another wallet, or the proofs matched against other outputs
+        let other = WalletKeys::generate();
+        assert_eq!(
+            import_key_images(&file, view, other.spend_public(), &outputs),
+            Err(OfflineError::WrongWallet)
+        );
+        let mut swapped = outputs.clone();
+        swapped.swap(0, 2);
+        assert_eq!(
+            import_key_images(&file, view, keys.spend_public(), &swapped),
+            Err(OfflineError::InvalidKeyImage(0))
+        );
+        assert_eq!(
+            import_key_images(&file, view, keys.spend_public(), &outputs[..2]),
+            Err(OfflineError::KeyImageCount {
+                expected: 2,
+                found: 3
+            })
+        );
+
+        // an output the exporter doesn't own can't be proven
+        let (_, foreign) = with_chain(&other, &[1]);
+        let file = export_key_images(&keys, &foreign);
+        assert_eq!(
+            import_key_images(&file, view, keys.spend_public(), &foreign),
+            Err(OfflineError::InvalidKeyImage(0))
+        );
+    }
+}
@@ -0,0 +1,167 @@
+// src/tx/mod.rs
+//! Transactions in their consensus binary form.
+//!
+//! Everything here serialises byte-for-byte like monerod: a transaction's
+//! hash commits to its exact encoding, so parsing and re-serialising must
+//! reproduce the input.
+use std::fmt;
+
+use crate::encoding::varint::{self, VarintError};
+
+pub mod builder;
+pub mod decoys;
+pub mod extra;
+pub mod fees;
+pub mod input;
+pub mod key_images;
+pub mod offline;
+pub mod output;
+pub mod prefix;
+pub mod proofs;
+pub mod pruned;
+pub mod rct;
+pub mod selection;
+pub mod transaction;
+pub mod unlock;
+
+pub use builder::{BuildError, DecoySource, OwnedOutput, PendingTransaction, TransactionBuilder};
+pub use decoys::{GammaDecoys, GammaPicker, OutputDistribution, OutputLookup};
+pub use extra::{Extra, ExtraError, ExtraField, ExtraNonce};
+pub use fees::{FeePriority, FeeRate, FeeSource};
+pub use input::{TxIn, TxInGen, TxInToKey};
+pub use key_images::{SignedKeyImage, export_key_images, import_key_images};
+pub use offline::{OfflineError, SignedTxSet, UnsignedInput, UnsignedTx, UnsignedTxSet};
+pub use output::{TxOut, TxOutTarget, TxOutToKey, TxOutToTaggedKey};
+pub use prefix::TransactionPrefix;
+pub use proofs::{
+    ProofDirection, ProofError, Reserve, ReserveLookup, ReserveProof, ReserveProofEntry, SpendProof,
+    TxProof,
+};
+pub use pruned::PrunedTransaction;
+pub use rct::{
+    EcdhInfo, RctError, RctSig, RctSigBase, RctSigPrunable, RctType, RingMember, verify_rct,
+};
+pub use selection::{LargestFirst, OldestFirst, OutputSelector, RandomSelection};
+pub use transaction::{Transaction, TxShape, weight};
+pub use unlock::{Balance, LockState, OutputLock, UnlockTime, balance, unlocked_outputs};
+
+/// Why a transaction blob didn't parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxError {
+    /// Input ended inside a field
+    Truncated,
+    Varint(VarintError),
+    /// Input tag other than gen (0xff) or to_key (0x02)
+    UnknownInputType(u8),
+    /// Output tag other than to_key (0x02) or to_tagged_key (0x03)
+    UnknownOutputType(u8),
+    /// RingCT type byte above 6
+    UnknownRctType(u8),
+    /// Signature scalar not reduced modulo the group order
+    InvalidScalar,
+    /// Signature element that isn't a curve point
+    InvalidPoint,
+    /// Count larger than the remaining input could hold
+    TooLarge(u64),
+    /// Bytes left after a complete value
+    TrailingBytes(usize),
+    /// Transaction version other than 1 or 2
+    UnknownVersion(u64),
+    /// Hex string that doesn't decode
+    InvalidHex,
+    /// Prunable data that doesn't hash to the pruned transaction's
+    /// prunable hash
+    PrunableHashMismatch,
+}
+
+impl fmt::Display for TxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxError::Truncated => write!(f, "transaction truncated"),
+            TxError::Varint(e) => write!(f, "invalid varint: {e}"),
+            TxError::UnknownInputType(t) => write!(f, "unknown input type {t:#04x}"),
+            TxError::UnknownOutputType(t) => write!(f, "unknown output type {t:#04x}"),
+            TxError::UnknownRctType(t) => write!(f, "unknown RingCT type {t}"),
+            TxError::InvalidScalar => write!(f, "non-canonical scalar"),
+            TxError::InvalidPoint => write!(f, "invalid curve point"),
+            TxError::TooLarge(n) => write!(f, "count {n} exceeds the remaining data"),
+            TxError::TrailingBytes(n) => write!(f, "{n} trailing bytes"),
+            TxError::UnknownVersion(v) => write!(f, "unknown transaction version {v}"),
+            TxError::InvalidHex => write!(f, "invalid hex"),
+            TxError::PrunableHashMismatch => write!(f, "prunable data doesn't match its hash"),
+        }
+    }
+}
+
+impl std::error::Error for TxError {}
+
+impl From<VarintError> for TxError {
+    fn from(e: VarintError) -> Self {
+        match e {
+            VarintError::Truncated => TxError::Truncated,
+            e => TxError::Varint(e),
+        }
+    }
+}
+
+/// Cursor over a consensus blob
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Bytes consumed so far
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    pub(crate) fn take(&mut self, n: usize) -> Result<&'a [u8], TxError> {
+        if self.remaining() < n {
+            return Err(TxError::Truncated);
+        }
+        let out = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(out)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], TxError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, TxError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn varint(&mut self) -> Result<u64, TxError> {
+        let (n, used) = varint::read(&self.data[self.pos..])?;
+        self.pos += used;
+        Ok(n)
+    }
+
+    /// Element count for items of at least `min_size` bytes each, so a
+    /// forged count can't trigger a huge allocation
+    pub(crate) fn count(&mut self, min_size: usize) -> Result<usize, TxError> {
+        let n = self.varint()?;
+        if n.saturating_mul(min_size as u64) > self.remaining() as u64 {
+            return Err(TxError::TooLarge(n));
+        }
+        Ok(n as usize)
+    }
+
+    /// Fail unless everything was consumed
+    pub(crate) fn finish(&self) -> Result<(), TxError> {
+        match self.remaining() {
+            0 => Ok(()),
+            n => Err(TxError::TrailingBytes(n)),
+        }
+    }
+}
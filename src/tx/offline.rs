@@ -0,0 +1,612 @@
+// src/tx/offline.rs
+//! Cold signing: a watch-only wallet prepares, an offline wallet signs.
+//!
+//! The flow is wallet2's `unsigned_tx_set` / `signed_tx_set`. The online
+//! wallet picks the inputs and fetches their decoys, both of which need a
+//! daemon, and exports an [`UnsignedTxSet`]. The offline wallet holds the
+//! spend key: it replays those rings through a [`TransactionBuilder`] and
+//! exports a [`SignedTxSet`] holding the transactions to relay and the key
+//! images of everything spent.
+//!
+//! As in wallet2, both files are encrypted under a key derived from the
+//! view secret, which both wallets have. The cipher is ChaCha20-Poly1305.
+//! The payload uses this crate's own encoding, not a Boost archive, so
+//! monero-wallet-cli can't load these files.
+use std::fmt;
+
+use curve25519_dalek::{EdwardsPoint, Scalar, edwards::CompressedEdwardsY};
+use rand::{RngCore, rngs::OsRng};
+use zeroize::Zeroizing;
+
+use super::{
+    Reader, TxError,
+    builder::{
+        BuildError, DecoySource, OwnedOutput, PendingTransaction, TransactionBuilder, output_secret,
+    },
+    fees::{FeePriority, FeeRate},
+    rct::RingMember,
+    transaction::Transaction,
+};
+use crate::address::{Address, AddressError};
+use crate::crypto::{
+    aead,
+    cn::{KeyImage, generate_key_image},
+    hash::keccak256,
+    keys::WalletKeys,
+};
+use crate::encoding::varint;
+use crate::network::Network;
+
+const UNSIGNED_MAGIC: &[u8] = b"monero_rust unsigned tx set\x01";
+const SIGNED_MAGIC: &[u8] = b"monero_rust signed tx set\x01";
+const KEY_CONTEXT: &[u8] = b"monero_rust/tx-set";
+
+/// Why a tx set file couldn't be read or signed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfflineError {
+    /// Not a tx set of this kind and version
+    InvalidContainer,
+    /// Wrong view key, or the file was tampered with
+    Decryption,
+    /// The decrypted payload doesn't parse
+    Malformed(TxError),
+    /// An address in the payload is invalid
+    Address(AddressError),
+    /// A network byte other than mainnet, testnet or stagenet
+    UnknownNetwork(u8),
+    /// Exported by a wallet with other keys
+    WrongWallet,
+    /// A different number of key images than outputs
+    KeyImageCount {
+        expected: usize,
+        found: usize,
+    },
+    /// Key image `n` doesn't prove ownership of output `n`
+    InvalidKeyImage(usize),
+    Build(BuildError),
+}
+
+impl fmt::Display for OfflineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OfflineError::InvalidContainer => write!(f, "not a tx set file"),
+            OfflineError::Decryption => write!(f, "wrong view key or corrupted tx set"),
+            OfflineError::Malformed(e) => write!(f, "malformed tx set: {e}"),
+            OfflineError::Address(e) => write!(f, "tx set address: {e}"),
+            OfflineError::UnknownNetwork(n) => write!(f, "unknown network {n}"),
+            OfflineError::WrongWallet => write!(f, "exported by another wallet"),
+            OfflineError::KeyImageCount { expected, found } => {
+                write!(f, "{found} key images for {expected} outputs")
+            }
+            OfflineError::InvalidKeyImage(i) => write!(f, "invalid key image proof for output {i}"),
+            OfflineError::Build(e) => write!(f, "signing failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for OfflineError {}
+
+impl From<TxError> for OfflineError {
+    fn from(e: TxError) -> Self {
+        OfflineError::Malformed(e)
+    }
+}
+
+impl From<AddressError> for OfflineError {
+    fn from(e: AddressError) -> Self {
+        OfflineError::Address(e)
+    }
+}
+
+impl From<BuildError> for OfflineError {
+    fn from(e: BuildError) -> Self {
+        OfflineError::Build(e)
+    }
+}
+
+/// An input with the decoys fetched for it
+#[derive(Debug, Clone)]
+pub struct UnsignedInput {
+    pub output: OwnedOutput,
+    /// `ring_size - 1` other outputs, in any order
+    pub decoys: Vec<RingMember>,
+}
+
+/// One transaction to sign
+#[derive(Debug, Clone)]
+pub struct UnsignedTx {
+    pub inputs: Vec<UnsignedInput>,
+    pub destinations: Vec<(Address, u64)>,
+    /// Send every input, less the fee, to the first destination
+    pub sweep: bool,
+}
+
+impl UnsignedTx {
+    /// Spend `inputs` to `destinations`, with the change going back to the
+    /// main address, fetching `ring_size - 1` decoys per input
+    pub fn new(
+        inputs: Vec<OwnedOutput>,
+        destinations: Vec<(Address, u64)>,
+        ring_size: usize,
+        decoys: &mut impl DecoySource,
+    ) -> Result<Self, BuildError> {
+        Ok(Self {
+            inputs: fetch_decoys(inputs, ring_size, decoys)?,
+            destinations,
+            sweep: false,
+        })
+    }
+
+    /// Send everything in `inputs`, less the fee, to `destination`
+    pub fn sweep(
+        inputs: Vec<OwnedOutput>,
+        destination: Address,
+        ring_size: usize,
+        decoys: &mut impl DecoySource,
+    ) -> Result<Self, BuildError> {
+        Ok(Self {
+            inputs: fetch_decoys(inputs, ring_size, decoys)?,
+            destinations: vec![(destination, 0)],
+            sweep: true,
+        })
+    }
+}
+
+fn fetch_decoys(
+    inputs: Vec<OwnedOutput>,
+    ring_size: usize,
+    decoys: &mut impl DecoySource,
+) -> Result<Vec<UnsignedInput>, BuildError> {
+    if ring_size < 2 {
+        return Err(BuildError::RingSize(ring_size));
+    }
+    inputs
+        .into_iter()
+        .enumerate()
+        .map(|(i, output)| {
+            let ring = decoys
+                .decoys(output.global_index, ring_size - 1)
+                .ok_or(BuildError::Decoys(i))?;
+            Ok(UnsignedInput {
+                output,
+                decoys: ring,
+            })
+        })
+        .collect()
+}
+
+/// Replays the rings an [`UnsignedTx`] was prepared with
+struct Recorded<'a>(&'a [UnsignedInput]);
+
+impl DecoySource for Recorded<'_> {
+    fn decoys(&mut self, real_index: u64, _count: usize) -> Option<Vec<RingMember>> {
+        self.0
+            .iter()
+            .find(|input| input.output.global_index == real_index)
+            .map(|input| input.decoys.clone())
+    }
+}
+
+/// Transactions a watch-only wallet prepared for an offline wallet to
+/// sign
+#[derive(Debug, Clone)]
+pub struct UnsignedTxSet {
+    pub network: Network,
+    pub priority: FeePriority,
+    pub fee_rate: FeeRate,
+    pub ring_size: usize,
+    pub transactions: Vec<UnsignedTx>,
+}
+
+impl UnsignedTxSet {
+    /// An empty set with the default fee settings and ring size
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            priority: FeePriority::default(),
+            fee_rate: FeeRate::default(),
+            ring_size: super::builder::DEFAULT_RING_SIZE,
+            transactions: Vec::new(),
+        }
+    }
+
+    /// Encrypted file for the offline wallet
+    pub fn to_bytes(&self, view_secret: &Scalar) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.push(network_byte(self.network));
+        out.push(self.priority as u8);
+        varint::write(&mut out, self.fee_rate.fee_per_byte);
+        varint::write(&mut out, self.fee_rate.quantization_mask);
+        varint::write(&mut out, self.ring_size as u64);
+        varint::write(&mut out, self.transactions.len() as u64);
+        for tx in &self.transactions {
+            out.push(tx.sweep as u8);
+            varint::write(&mut out, tx.destinations.len() as u64);
+            for (address, amount) in &tx.destinations {
+                let bytes = address.to_bytes();
+                varint::write(&mut out, bytes.len() as u64);
+                out.extend_from_slice(&bytes);
+                varint::write(&mut out, *amount);
+            }
+            varint::write(&mut out, tx.inputs.len() as u64);
+            for input in &tx.inputs {
+                write_output(&mut out, &input.output);
+                varint::write(&mut out, input.decoys.len() as u64);
+                for member in &input.decoys {
+                    write_member(&mut out, member);
+                }
+            }
+        }
+        seal(UNSIGNED_MAGIC, view_secret, &out)
+    }
+
+    /// Read a file from [`UnsignedTxSet::to_bytes`]
+    pub fn from_bytes(data: &[u8], view_secret: &Scalar) -> Result<Self, OfflineError> {
+        let payload = open(UNSIGNED_MAGIC, view_secret, data)?;
+        let mut r = Reader::new(&payload);
+        let network = read_network(&mut r)?;
+        let priority = match r.u8()? {
+            0 => FeePriority::Low,
+            1 => FeePriority::Normal,
+            2 => FeePriority::Elevated,
+            3 => FeePriority::Priority,
+            _ => return Err(OfflineError::InvalidContainer),
+        };
+        let fee_rate = FeeRate {
+            fee_per_byte: r.varint()?,
+            quantization_mask: r.varint()?,
+        };
+        let ring_size = r.varint()? as usize;
+        let n_txs = r.count(3)?;
+        let mut transactions = Vec::with_capacity(n_txs);
+        for _ in 0..n_txs {
+            let sweep = read_bool(&mut r)?;
+            let n_destinations = r.count(2)?;
+            let mut destinations = Vec::with_capacity(n_destinations);
+            for _ in 0..n_destinations {
+                let len = r.count(1)?;
+                let address = Address::from_bytes(r.take(len)?)?;
+                destinations.push((address, r.varint()?));
+            }
+            let n_inputs = r.count(1)?;
+            let mut inputs = Vec::with_capacity(n_inputs);
+            for _ in 0..n_inputs {
+                let output = read_output(&mut r)?;
+                let n_decoys = r.count(65)?;
+                let decoys = (0..n_decoys)
+                    .map(|_| read_member(&mut r))
+                    .collect::<Result<_, _>>()?;
+                inputs.push(UnsignedInput { output, decoys });
+            }
+            transactions.push(UnsignedTx {
+                inputs,
+                destinations,
+                sweep,
+            });
+        }
+        r.finish()?;
+        Ok(Self {
+            network,
+            priority,
+            fee_rate,
+            ring_size,
+            transactions,
+        })
+    }
+
+    /// Sign every transaction with `keys`, using the recorded decoys
+    pub fn sign(&self, keys: &WalletKeys) -> Result<SignedTxSet, OfflineError> {
+        let mut signed = SignedTxSet::default();
+        for tx in &self.transactions {
+            let mut builder = TransactionBuilder::new(keys, self.network)
+                .priority(self.priority)
+                .fee_rate(self.fee_rate)
+                .ring_size(self.ring_size);
+            for input in &tx.inputs {
+                builder = builder.input(input.output.clone());
+            }
+            builder = match tx.sweep {
+                true => {
+                    let (destination, _) =
+                        tx.destinations.first().ok_or(BuildError::NoDestinations)?;
+                    builder.sweep_all(*destination)
+                }
+                false => tx
+                    .destinations
+                    .iter()
+                    .fold(builder, |builder, &(address, amount)| {
+                        builder.destination(address, amount)
+                    }),
+            };
+            signed
+                .transactions
+                .push(builder.build(&mut Recorded(&tx.inputs))?);
+            signed.key_images.extend(tx.inputs.iter().map(|input| {
+                let secret = output_secret(keys, &input.output);
+                (
+                    input.output.global_index,
+                    generate_key_image(&secret, &input.output.key),
+                )
+            }));
+        }
+        Ok(signed)
+    }
+}
+
+/// What the offline wallet sends back: transactions ready to relay
+#[derive(Debug, Clone, Default)]
+pub struct SignedTxSet {
+    pub transactions: Vec<PendingTransaction>,
+    /// Key image of every output spent, by global index, so the watch-only
+    /// wallet can tell which of its outputs are gone
+    pub key_images: Vec<(u64, KeyImage)>,
+}
+
+impl SignedTxSet {
+    /// Encrypted file for the watch-only wallet
+    pub fn to_bytes(&self, view_secret: &Scalar) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint::write(&mut out, self.transactions.len() as u64);
+        for pending in &self.transactions {
+            let blob = pending.to_bytes();
+            varint::write(&mut out, blob.len() as u64);
+            out.extend_from_slice(&blob);
+            out.extend_from_slice(pending.tx_key.as_bytes());
+            varint::write(&mut out, pending.additional_tx_keys.len() as u64);
+            for key in &pending.additional_tx_keys {
+                out.extend_from_slice(key.as_bytes());
+            }
+            varint::write(&mut out, pending.fee);
+            match pending.change_index {
+                None => out.push(0),
+                Some(i) => {
+                    out.push(1);
+                    varint::write(&mut out, i as u64);
+                }
+            }
+        }
+        varint::write(&mut out, self.key_images.len() as u64);
+        for (global_index, key_image) in &self.key_images {
+            varint::write(&mut out, *global_index);
+            out.extend_from_slice(key_image.as_bytes());
+        }
+        let sealed = seal(SIGNED_MAGIC, view_secret, &out);
+        zeroize::Zeroize::zeroize(&mut out);
+        sealed
+    }
+
+    /// Read a file from [`SignedTxSet::to_bytes`]
+    pub fn from_bytes(data: &[u8], view_secret: &Scalar) -> Result<Self, OfflineError> {
+        let payload = open(SIGNED_MAGIC, view_secret, data)?;
+        let mut r = Reader::new(&payload);
+        let n_txs = r.count(34)?;
+        let mut transactions = Vec::with_capacity(n_txs);
+        for _ in 0..n_txs {
+            let len = r.count(1)?;
+            let tx = Transaction::from_blob(r.take(len)?)?;
+            let tx_key = read_scalar(&mut r)?;
+            let n_additional = r.count(32)?;
+            let additional_tx_keys = (0..n_additional)
+                .map(|_| read_scalar(&mut r))
+                .collect::<Result<_, _>>()?;
+            let fee = r.varint()?;
+            let change_index = match read_bool(&mut r)? {
+                true => Some(r.varint()? as usize),
+                false => None,
+            };
+            transactions.push(PendingTransaction {
+                tx,
+                tx_key,
+                additional_tx_keys,
+                fee,
+                change_index,
+            });
+        }
+        let n_key_images = r.count(33)?;
+        let mut key_images = Vec::with_capacity(n_key_images);
+        for _ in 0..n_key_images {
+            key_images.push((r.varint()?, KeyImage(r.array()?)));
+        }
+        r.finish()?;
+        Ok(Self {
+            transactions,
+            key_images,
+        })
+    }
+}
+
+/// File key: `Keccak256(context || view secret)`
+fn file_key(view_secret: &Scalar) -> Zeroizing<[u8; aead::KEY_LEN]> {
+    let mut data = Zeroizing::new(KEY_CONTEXT.to_vec());
+    data.extend_from_slice(view_secret.as_bytes());
+    Zeroizing::new(keccak256(&data).to_bytes())
+}
+
+/// `magic || nonce || ChaCha20-Poly1305(payload) || tag`, the magic
+/// authenticated
+pub(super) fn seal(magic: &[u8], view_secret: &Scalar, payload: &[u8]) -> Vec<u8> {
+    let mut nonce = [0u8; aead::NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let mut out = magic.to_vec();
+    out.extend_from_slice(&nonce);
+    let sealed = aead::seal(&file_key(view_secret), &nonce, magic, payload);
+    out.extend_from_slice(&sealed);
+    out
+}
+
+pub(super) fn open(
+    magic: &[u8],
+    view_secret: &Scalar,
+    data: &[u8],
+) -> Result<Vec<u8>, OfflineError> {
+    let header = magic.len() + aead::NONCE_LEN;
+    if data.len() < header + aead::TAG_LEN || !data.starts_with(magic) {
+        return Err(OfflineError::InvalidContainer);
+    }
+    let nonce: [u8; aead::NONCE_LEN] = data[magic.len()..header].try_into().unwrap();
+    aead::open(&file_key(view_secret), &nonce, magic, &data[header..])
+        .ok_or(OfflineError::Decryption)
+}
+
+fn network_byte(network: Network) -> u8 {
+    Network::ALL.iter().position(|n| *n == network).unwrap() as u8
+}
+
+fn read_network(r: &mut Reader<'_>) -> Result<Network, OfflineError> {
+    let byte = r.u8()?;
+    Network::ALL
+        .get(byte as usize)
+        .copied()
+        .ok_or(OfflineError::UnknownNetwork(byte))
+}
+
+fn read_bool(r: &mut Reader<'_>) -> Result<bool, OfflineError> {
+    match r.u8()? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(OfflineError::InvalidContainer),
+    }
+}
+
+fn read_scalar(r: &mut Reader<'_>) -> Result<Scalar, TxError> {
+    Option::from(Scalar::from_canonical_bytes(r.array()?)).ok_or(TxError::InvalidScalar)
+}
+
+fn read_point(r: &mut Reader<'_>) -> Result<EdwardsPoint, TxError> {
+    CompressedEdwardsY(r.array()?)
+        .decompress()
+        .ok_or(TxError::InvalidPoint)
+}
+
+fn write_output(out: &mut Vec<u8>, output: &OwnedOutput) {
+    varint::write(out, output.global_index);
+    out.extend_from_slice(output.key.compress().as_bytes());
+    varint::write(out, output.amount);
+    out.extend_from_slice(output.mask.as_bytes());
+    out.extend_from_slice(output.tx_public_key.compress().as_bytes());
+    varint::write(out, output.output_index);
+    varint::write(out, output.subaddress.0 as u64);
+    varint::write(out, output.subaddress.1 as u64);
+}
+
+fn read_output(r: &mut Reader<'_>) -> Result<OwnedOutput, TxError> {
+    let global_index = r.varint()?;
+    let key = read_point(r)?;
+    let amount = r.varint()?;
+    let mask = read_scalar(r)?;
+    let tx_public_key = read_point(r)?;
+    let output_index = r.varint()?;
+    let mut index = || {
+        let n = r.varint()?;
+        u32::try_from(n).map_err(|_| TxError::TooLarge(n))
+    };
+    let subaddress = (index()?, index()?);
+    Ok(OwnedOutput {
+        global_index,
+        key,
+        amount,
+        mask,
+        tx_public_key,
+        output_index,
+        subaddress,
+    })
+}
+
+fn write_member(out: &mut Vec<u8>, member: &RingMember) {
+    varint::write(out, member.global_index);
+    out.extend_from_slice(member.key.compress().as_bytes());
+    out.extend_from_slice(member.commitment.compress().as_bytes());
+}
+
+fn read_member(r: &mut Reader<'_>) -> Result<RingMember, TxError> {
+    Ok(RingMember {
+        global_index: r.varint()?,
+        key: read_point(r)?,
+        commitment: read_point(r)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::builder::tests::{check, with_chain};
+
+    #[test]
+    fn test_offline_round_trip() {
+        let keys = WalletKeys::generate();
+        let recipient = Address::from_keys(&WalletKeys::generate(), Network::Testnet);
+        let (mut chain, outputs) = with_chain(&keys, &[3_000_000_000, 2_000_000_000, 700]);
+
+        // online: watch-only, so only the view secret
+        let mut set = UnsignedTxSet::new(Network::Testnet);
+        set.priority = FeePriority::Normal;
+        set.transactions.push(
+            UnsignedTx::new(
+                outputs[..2].to_vec(),
+                vec![(recipient, 4_000_000_000)],
+                set.ring_size,
+                &mut chain,
+            )
+            .unwrap(),
+        );
+        let sweep = UnsignedTx::sweep(outputs[2..].to_vec(), recipient, set.ring_size, &mut chain);
+        set.transactions.push(sweep.unwrap());
+        let file = set.to_bytes(keys.view_secret());
+        assert!(file.starts_with(UNSIGNED_MAGIC));
+
+        // offline
+        let imported = UnsignedTxSet::from_bytes(&file, keys.view_secret()).unwrap();
+        assert_eq!(imported.priority, FeePriority::Normal);
+        assert_eq!(imported.transactions.len(), 2);
+        assert_eq!(imported.transactions[0].inputs[1].decoys.len(), 15);
+        assert!(imported.transactions[1].sweep);
+        assert_eq!(
+            UnsignedTxSet::from_bytes(&file, &Scalar::ONE).unwrap_err(),
+            OfflineError::Decryption
+        );
+        // the dust sweep can't pay its fee
+        assert!(matches!(
+            imported.sign(&keys),
+            Err(OfflineError::Build(BuildError::InsufficientFunds { .. }))
+        ));
+        let mut imported = imported;
+        imported.transactions.pop();
+        let signed = imported.sign(&keys).unwrap();
+        let file = signed.to_bytes(keys.view_secret());
+
+        // online again
+        let returned = SignedTxSet::from_bytes(&file, keys.view_secret()).unwrap();
+        assert_eq!(returned.transactions.len(), 1);
+        let pending = &returned.transactions[0];
+        check(pending, &chain);
+        assert_eq!(pending.hash(), signed.transactions[0].hash());
+        assert_eq!(pending.tx_key, signed.transactions[0].tx_key);
+        assert_eq!(pending.change_index, signed.transactions[0].change_index);
+        assert_eq!(returned.key_images.len(), 2);
+        for (global_index, key_image) in &returned.key_images {
+            assert!(outputs[..2].iter().any(|o| o.global_index == *global_index));
+            assert!(pending.tx.prefix.key_images().any(|k| k == key_image));
+        }
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let view = Scalar::from(7u8);
+        let file = SignedTxSet::default().to_bytes(&view);
+        assert!(SignedTxSet::from_bytes(&file, &view).is_ok());
+        assert_eq!(
+            UnsignedTxSet::from_bytes(&file, &view).unwrap_err(),
+            OfflineError::InvalidContainer
+        );
+        let mut tampered = file.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            SignedTxSet::from_bytes(&tampered, &view).unwrap_err(),
+            OfflineError::Decryption
+        );
+        assert_eq!(
+            SignedTxSet::from_bytes(&file[..20], &view).unwrap_err(),
+            OfflineError::InvalidContainer
+        );
+    }
+}
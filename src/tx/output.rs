@@ -0,0 +1,142 @@
+// src/tx/output.rs
+//! Transaction outputs.
+//!
+//! An output is an amount (zero under RingCT, where the commitment carries
+//! it) and a one-time public key. Since hard fork 15 the key is followed by
+//! a one-byte view tag that lets a scanning wallet skip most outputs after a
+//! single hash.
+use curve25519_dalek::edwards::CompressedEdwardsY;
+
+use super::{Reader, TxError};
+use crate::encoding::varint;
+
+const TAG_TO_KEY: u8 = 0x02;
+const TAG_TO_TAGGED_KEY: u8 = 0x03;
+
+/// Untagged one-time key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxOutToKey {
+    pub key: CompressedEdwardsY,
+}
+
+/// One-time key with a view tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxOutToTaggedKey {
+    pub key: CompressedEdwardsY,
+    pub view_tag: u8,
+}
+
+/// Who can spend an output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxOutTarget {
+    ToKey(TxOutToKey),
+    ToTaggedKey(TxOutToTaggedKey),
+}
+
+impl TxOutTarget {
+    /// One-time public key
+    pub fn key(&self) -> &CompressedEdwardsY {
+        match self {
+            TxOutTarget::ToKey(t) => &t.key,
+            TxOutTarget::ToTaggedKey(t) => &t.key,
+        }
+    }
+
+    pub fn view_tag(&self) -> Option<u8> {
+        match self {
+            TxOutTarget::ToKey(_) => None,
+            TxOutTarget::ToTaggedKey(t) => Some(t.view_tag),
+        }
+    }
+}
+
+/// A transaction output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxOut {
+    pub amount: u64,
+    pub target: TxOutTarget,
+}
+
+impl TxOut {
+    pub fn to_key(amount: u64, key: CompressedEdwardsY) -> Self {
+        Self {
+            amount,
+            target: TxOutTarget::ToKey(TxOutToKey { key }),
+        }
+    }
+
+    pub fn to_tagged_key(amount: u64, key: CompressedEdwardsY, view_tag: u8) -> Self {
+        Self {
+            amount,
+            target: TxOutTarget::ToTaggedKey(TxOutToTaggedKey { key, view_tag }),
+        }
+    }
+
+    pub fn key(&self) -> &CompressedEdwardsY {
+        self.target.key()
+    }
+
+    pub fn view_tag(&self) -> Option<u8> {
+        self.target.view_tag()
+    }
+
+    /// Append the consensus encoding to `out`
+    pub fn serialize(&self, out: &mut Vec<u8>) {
+        varint::write(out, self.amount);
+        match &self.target {
+            TxOutTarget::ToKey(t) => {
+                out.push(TAG_TO_KEY);
+                out.extend_from_slice(t.key.as_bytes());
+            }
+            TxOutTarget::ToTaggedKey(t) => {
+                out.push(TAG_TO_TAGGED_KEY);
+                out.extend_from_slice(t.key.as_bytes());
+                out.push(t.view_tag);
+            }
+        }
+    }
+
+    pub(crate) fn read(r: &mut Reader<'_>) -> Result<Self, TxError> {
+        let amount = r.varint()?;
+        let target = match r.u8()? {
+            TAG_TO_KEY => TxOutTarget::ToKey(TxOutToKey {
+                key: CompressedEdwardsY(r.array()?),
+            }),
+            TAG_TO_TAGGED_KEY => TxOutTarget::ToTaggedKey(TxOutToTaggedKey {
+                key: CompressedEdwardsY(r.array()?),
+                view_tag: r.u8()?,
+            }),
+            t => return Err(TxError::UnknownOutputType(t)),
+        };
+        Ok(Self { amount, target })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        let key = CompressedEdwardsY([0x11; 32]);
+        for (output, tag_len) in [
+            (TxOut::to_key(0, key), 0),
+            (TxOut::to_tagged_key(0, key, 0x5a), 1),
+        ] {
+            let mut bytes = Vec::new();
+            output.serialize(&mut bytes);
+            assert_eq!(bytes.len(), 1 + 1 + 32 + tag_len);
+            assert_eq!(TxOut::read(&mut Reader::new(&bytes)).unwrap(), output);
+            assert_eq!(output.key(), &key);
+        }
+        assert_eq!(TxOut::to_tagged_key(0, key, 0x5a).view_tag(), Some(0x5a));
+        assert_eq!(TxOut::to_key(0, key).view_tag(), None);
+
+        let mut script = vec![0x00, 0x00];
+        script.extend_from_slice(&[0; 32]);
+        assert_eq!(
+            TxOut::read(&mut Reader::new(&script)),
+            Err(TxError::UnknownOutputType(0x00))
+        );
+    }
+}
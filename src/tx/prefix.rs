@@ -0,0 +1,251 @@
+// src/tx/prefix.rs
+//! The transaction prefix: everything but the signatures.
+//!
+//! Layout, every integer a varint:
+//! `version || unlock_time || vin || vout || extra`, where the vectors are
+//! count-prefixed and each input and output starts with a type tag. The
+//! prefix hash, `Keccak256(prefix)`, is what ring signatures sign and the
+//! first component of a v2 transaction hash.
+use super::{Extra, Reader, TxError, TxIn, TxOut};
+use crate::crypto::{
+    cn::KeyImage,
+    hash::{Hash256, keccak256},
+};
+use crate::encoding::varint;
+
+/// Version, unlock time, inputs, outputs and extra
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionPrefix {
+    /// 1 for pre-RingCT transactions, 2 for RingCT
+    pub version: u64,
+    /// Block height (below 500 000 000) or Unix time before which outputs
+    /// are locked
+    pub unlock_time: u64,
+    pub vin: Vec<TxIn>,
+    pub vout: Vec<TxOut>,
+    /// Raw tx_extra bytes: tx public key, payment ID, nonces
+    pub extra: Vec<u8>,
+}
+
+impl TransactionPrefix {
+    /// Append the consensus encoding to `out`
+    pub fn serialize(&self, out: &mut Vec<u8>) {
+        varint::write(out, self.version);
+        varint::write(out, self.unlock_time);
+        varint::write(out, self.vin.len() as u64);
+        for input in &self.vin {
+            input.serialize(out);
+        }
+        varint::write(out, self.vout.len() as u64);
+        for output in &self.vout {
+            output.serialize(out);
+        }
+        varint::write(out, self.extra.len() as u64);
+        out.extend_from_slice(&self.extra);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.serialize(&mut out);
+        out
+    }
+
+    /// Parse a prefix that makes up all of `data`
+    pub fn from_bytes(data: &[u8]) -> Result<Self, TxError> {
+        let mut reader = Reader::new(data);
+        let prefix = Self::read(&mut reader)?;
+        reader.finish()?;
+        Ok(prefix)
+    }
+
+    /// Parse the prefix at the start of a full transaction blob, returning
+    /// it and its length
+    pub fn from_bytes_partial(data: &[u8]) -> Result<(Self, usize), TxError> {
+        let mut reader = Reader::new(data);
+        let prefix = Self::read(&mut reader)?;
+        Ok((prefix, reader.position()))
+    }
+
+    pub(crate) fn read(r: &mut Reader<'_>) -> Result<Self, TxError> {
+        let version = r.varint()?;
+        let unlock_time = r.varint()?;
+
+        // smallest input is a gen tag and a one-byte height
+        let vin = (0..r.count(2)?)
+            .map(|_| TxIn::read(r))
+            .collect::<Result<_, _>>()?;
+        // amount, tag and key
+        let vout = (0..r.count(34)?)
+            .map(|_| TxOut::read(r))
+            .collect::<Result<_, _>>()?;
+
+        let extra_len = r.count(1)?;
+        let extra = r.take(extra_len)?.to_vec();
+        Ok(Self {
+            version,
+            unlock_time,
+            vin,
+            vout,
+            extra,
+        })
+    }
+
+    /// A coinbase has exactly one input, of type gen
+    pub fn is_coinbase(&self) -> bool {
+        matches!(self.vin[..], [TxIn::Gen(_)])
+    }
+
+    /// Key images of all ring inputs, in order
+    pub fn key_images(&self) -> impl Iterator<Item = &KeyImage> {
+        self.vin.iter().filter_map(TxIn::key_image)
+    }
+
+    /// Fields of `extra`, parsed leniently since consensus doesn't check it
+    pub fn parse_extra(&self) -> Extra {
+        Extra::parse_lenient(&self.extra)
+    }
+
+    /// `Keccak256` of the serialised prefix
+    pub fn prefix_hash(&self) -> Hash256 {
+        keccak256(&self.to_bytes())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // mainnet v1 coinbase; with no signatures the whole blob is the prefix
+    pub(crate) const MINER_TX_V1: &str = "01f18d0601ffb58d0605efefead70202eb72f82bd8bdda51e0bdc25f0\
+        4e99ffb90c6214e11b455abca7b116c7857738880e497d01202e87c65a22b78f4b7686ef3a30113674659a4fe7\
+        69a7ded73d60e6f7c556a19858090dfc04a022ee52dca8845438995eb6d7af985ca07186cc34a7eb696937f78f\
+        c0fd9008e2280c0f9decfae0102cec392ffdcae05a370dc3c447465798d3688677f4a5937f1fef9661df99ac2f\
+        b80c0caf384a30202e2b6ce11475c2312d2de5c9f26fbd88b7fcac0dbbb7b31f49abe9bd631ed49e42b0104d46\
+        cf1a204ae727c14473d67ea95da3e97b250f3c63e0997198bfc812d7a81020800000000d8111b25";
+
+    // mainnet RingCT transaction with two inputs
+    pub(crate) const RCT_TX_V2: &str = "02000202000bc6aa98049bf603fcec06bd3ccbad04e807e328b5128f22a\
+        63bfb27b6e287e8d594664d5cddd6c89bc413d1bc607b242203a6eb3180041ff5ae679702000b90e4eb028298a\
+        101879110f5bc0383ad03cbfc03a750e52ace37d112c6064faf7d16e2d07c4cc979dccb858aa9b24e12479e4a2\
+        db8350a906ba7a1aec409020002ab6d783607d8e712bbd5aad54a412aec890fcdcc1b35bf0ca4a705c2159bfc3\
+        2000262f4016d5d81ade9e555807a24d23d452f08b6400683da599abd7134fb75324a2c0209016631a2dee1d0f\
+        51f015fd9bf938cf132790bdc5c528037e347828c539e82da6e5921e3d1e6052cb25804d0b7ba81018a4cd5385\
+        ca23ff4f6d76dc41b5254abf579b1856d3fbd04e81ff97c113e318bf7e158fbb0db7adc6ece9c8d4ab94e91f68\
+        e9607667a858ddf3e6890b2835403db6dcc5a1c179a768bcf74d74ace86430176b0056de37f310884e8eed56ee\
+        86840f23f842f1db52945b2feef98f4b56b3d4407734e4e8d3b117b5fd78f0d94f6059b495f53cf855b3716bbe\
+        8614d51727556c8b2e5c303cffc694257a1e91372de2047c4e12381c1de8df46102cdd84a24692f68ada05d1ff\
+        c5122b655582c6307141e130a6963198085ddb67d304b0ddde87e62402a3cadcd07a315604607ddf1530bd8568\
+        5e910aa879733549bde0d019edc36326d33edd6ccecc800395b7075e4959779bde803dd787c24bc25d40205071\
+        b180152dded8b0be1f48a6d6f8e97c3f934f866b1b697f73f73fc9f38c5d2082c610732c79b2f69f403f7e2d31\
+        2399739dd8d4225a2914a3020bd88c362271df633e8387b5345b50f11c4f148f76c0c24ca5843580a02fe72d18\
+        f47dcf8d601e28bbef2cd6659e620179adad4dfa5a0c7b712d716c4e630fe40bbfc6184f76c401db4b801a7001\
+        f65a9c11053db919099f9a1a4fe575c6d783e041ee08222a46adb8a1f13b863d95da277b71ccdefbb32f713a13\
+        b5ac8d041bbbeed9df4ace5a6b730b871ad2fe14141dbb9c816a21fd7fc48cfba4d2cc3e5e5fba29f581c1507a\
+        6a36285a30344790b74d2212dd26178395cd96a18518ead5c59a410baf6ca0b9217865fff207d757bb465fdb05\
+        3e8c80b2ec1a966ccc01f49096fb991b65cc160a5070532c47318720fb9a90f187ce53661b6ce1e29d6ccd2b13\
+        1324101170bb87ef273f0e73d762e159264f0839c6b3b31f5264499bdeb029c66a7035108a84256aec2760e74c\
+        2e8e788b7747084da8953aa48696b7a46e6320a9534d6c06ddee1b26671f03ae70a30c76b8fbf268da16fbb685\
+        f1d3f602668afce2e3eaf089b8758069f398eaeccd01876cef623201dc46dd75f76dc2141a9a2071b2761eefbf\
+        735ca599ed15266acbd0f54f49de38b5c7b3b378c386767383396645778021b30910e6e06937d65dac82312968\
+        d4f63a0bb28c96b9a09ee1d95c4d3afbb021998a9e290647b51083a80d66e0a8900a89c1236214f2d7c0080e2c\
+        18cdc8c0b4bd66765c3c52a26b90ec549bc8358ff8aaac9ffaecb0f6c915113cc97147b06c007a1a6bcd536bdd\
+        ec7fba330877d80cb878c7c9f3da81f8eb3bfb07ac7a804d4d58faadfabc3421350b14af6500b931209b758137\
+        59509e8642574982d680632106041b2687bdaef1c2be67c63a0ccc427bf02dc1ba58b153f00f8fabc8c00d9993\
+        4e3e835291b8fc5a0bd62a3059c22cbd6fc4ae403df254e17cb15f32b0b65a4ed1f0f5fff37e49417c5fee339c\
+        21bd4f1cdcb1c803df8cb4baa11fa210089b61e28fe42e112824ee705f13167bfd3e6c8d660f2307216f5eea91\
+        a6db505b70f38e4b1b72a8f1fa1dd90bf0a47ed9a71e2e11e4f20240c1dac370f2b18075c6537bb475897fdd90\
+        fae360afb0b6b02210c123a3ca8fae31e320639b5e00c91c821abe873c1aaee2c4ebb87b5ecc670bff65de191e\
+        1d8463ecf1367685c0f0d39efc20269e516f29b775060a0c7a1595e158e94f64407d8e22b16ff25ed00cd50c46\
+        fb95ab5a3ca60a04e222c83d26b11c08a678348c8cdad407a0d841c0a50e91e896cb4ff873e9fc81c35f4a146f\
+        25a64b294c07c6adf4e418a3f590f061a89267deea53d985b4576b70b95170970321e07c1397b6f7e1ed3d4629\
+        e8e0a81019a7c15ae6d252e856c761664862d7fd0620fbbc7020fcd675fc97dc7310420b0d428093b4a80012f7\
+        a46612161ee2eeec8996128876d093f71f954244004aded4185afeec305d104d3905e54ddfda59ce1f5d56cf07\
+        8ff10a76b138db90789184149cab60dfb1491943a793e85c332b4b36f448b63e5f099e7beb11d07005a31ed165\
+        8251e9f880466e44c54357781c9cdaf17d48534b062de482ad94005cbdcf52d6fea2e70c20ecc62a339afbb971\
+        e455e38292b78b21393bce982ec06d3e6f3e27897877007283f5a9d44ae134efc0ff14a5ce2fbe711403b53541\
+        3073c9769ddc0474d64643bd2d60f58e001717f0538e1cc1e6b211c5f06f6ddfa029ec10d7e949673c08cd7171\
+        3728ff042948c5b75ea2b610f4b4db838696fa40e24d750aa75f910948af39de2eba2ff6864daf92004453e4fa\
+        5cdae2f553a460f9bc86073dd7d6d2ea0f31092d28110892d6077dcee3b6293e66867a7ae67c5048df756d253c\
+        768debf3989d7643ff8892b7f6f74bf2b36d01bd0b88760c9b30eb54d02aa5498e7b87e2d027ac2a449318deda\
+        9cabf356fcd07f4561e6370db09fbd0d081093d93a569f6968b291fb01995415293b7cd997d9367c352c75b230\
+        ee70de92efd35302572e9de2809cd8e25eb9f824cb559107a1b87dd8c365de106cc800350e1284d67555b8db90\
+        41dfd44b3d5bec42e2b186b6a72b5bc3df0470476c234277592f742c3bf3babd4759b115196117883434af1d01\
+        4ba8aeca028054fb4e8d9dde6a0e4cca9388a9f4c6283cbf9ae89dd17e4dc2a2f511604b33f0ee6e42e4f9a20b\
+        556ebc66e18b6142bfdcd4bf3792d9659ca5f5ef041f5ba28053e752155263ea79bd65dd94157f9464625e6a2e\
+        1b1e6d8ff40b4af4cb3804606239b8370d69d14f5c9eb463f49b0e796efdf01ddc087cc710bae43968856000fd\
+        c3a6408861363a190edbf2ae3e13d2bc52f87d9f2e31c044698b79f37b75a03199c7585b880df73a362ff40a94\
+        ed1d6173998d247a8f74e28407ad933e8bb0cd91ee82b723b3f22152a53b3226e52b32b93b397b5b2c386d3468\
+        651e602360eba66e52a1844aac9bbbe1a7183e97fed0c8b4b649f1551bcb98248dd62ebec087d4020a042085d4\
+        87c64ae3fcf25e11b443ff03eeb8345b6d45d5304fbba83030bce1bbe499a7f5aefff31fef134b3c2c85b2fd16\
+        e6a26133cdbf05069299f007e627036d5ce0536e10546ec3b0719c373e00792f45fa78ff62d543e204d9a0f54a\
+        2b1c934a1463620a5c789ed600792ace37bc0f79c84399018acd073e86309f16a4ee382baad3e98425be3dcea1\
+        fceb47e56d237a49a125a360f7eb56b0305632f3877c17e62204e5a2c2017a934be9e532c5d7fd14ed71c4a2d3\
+        947621d03373796d7ffd6c77a73a06e3cbb61e1d872fb012c9ea0327fb65c4ffa46f02507d4db98bd434a7e921\
+        130e8846e697da226cc85568aa83f95cdfc4ccbfbff8ab0653000211ee7438364596b53793f2dfc4705f6a4911\
+        90b35960f9aec1ffaad8a";
+
+    #[test]
+    fn test_v1_miner_tx() {
+        let blob = hex::decode(MINER_TX_V1).unwrap();
+        let prefix = TransactionPrefix::from_bytes(&blob).unwrap();
+        assert_eq!(prefix.version, 1);
+        assert!(prefix.is_coinbase());
+        assert_eq!(prefix.key_images().count(), 0);
+        assert_eq!(prefix.to_bytes(), blob);
+        // v1 transaction hash is the hash of the whole blob
+        assert_eq!(
+            hex::encode(prefix.prefix_hash().as_bytes()),
+            "3bc7ff015b227e7313cc2e8668bfbb3f3acbee274a9c201d6211cf681b5f6bb1"
+        );
+    }
+
+    #[test]
+    fn test_v2_prefix() {
+        let blob = hex::decode(RCT_TX_V2).unwrap();
+        assert!(TransactionPrefix::from_bytes(&blob).is_err());
+        let (prefix, len) = TransactionPrefix::from_bytes_partial(&blob).unwrap();
+        assert_eq!(prefix.version, 2);
+        assert_eq!(prefix.key_images().count(), 2);
+        assert!(!prefix.is_coinbase());
+        assert!(prefix.vout.iter().all(|o| o.amount == 0));
+        let TxIn::ToKey(input) = &prefix.vin[0] else {
+            panic!("expected a ring input");
+        };
+        assert_eq!(input.ring_size(), 11);
+        assert_eq!(prefix.to_bytes(), blob[..len]);
+        assert_eq!(
+            hex::encode(prefix.prefix_hash().as_bytes()),
+            "3b50349180b4a60e55187507746eabb7bee0de6b74168eac8720a449da28613b"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let blob = hex::decode(MINER_TX_V1).unwrap();
+        for len in [0, 1, 5, 40, blob.len() - 1] {
+            assert!(
+                TransactionPrefix::from_bytes(&blob[..len]).is_err(),
+                "{len}"
+            );
+        }
+        let mut trailing = blob.clone();
+        trailing.push(0);
+        assert_eq!(
+            TransactionPrefix::from_bytes(&trailing),
+            Err(TxError::TrailingBytes(1))
+        );
+        let mut bad_input = blob.clone();
+        bad_input[5] = 0x01;
+        assert_eq!(
+            TransactionPrefix::from_bytes(&bad_input),
+            Err(TxError::UnknownInputType(0x01))
+        );
+        // version 1, unlock 0, 2^62 inputs
+        assert_eq!(
+            TransactionPrefix::from_bytes(&[
+                0x01, 0x00, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x40
+            ]),
+            Err(TxError::TooLarge(1 << 62))
+        );
+    }
+}
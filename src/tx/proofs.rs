@@ -0,0 +1,1090 @@
+// src/tx/proofs.rs
+//! Payment proofs, compatible with wallet2's `get_tx_proof` and
+//! `check_tx_proof`.
+//!
+//! An outbound proof (`OutProofV2`) comes from the sender, who knows the tx
+//! key `r`. An inbound proof (`InProofV2`) comes from the recipient, who
+//! knows the view key `a`. Both reveal the shared secret `D = r*A = a*R`,
+//! one per tx key, and prove with a Schnorr-style signature that it was
+//! computed correctly. With `D` anyone can derive the outputs paid to the
+//! address and decrypt their amounts.
+//!
+//! A spend proof (`SpendProofV1`) shows who sent a transaction instead. It
+//! signs the message again with every input's ring and key image, which
+//! takes the one-time secrets of the real outputs.
+//!
+//! A reserve proof shows a wallet holds some outputs: per output, the
+//! shared secret with its proof and the key image with its proof, plus a
+//! signature by every spend key that received them. The verifier asks a
+//! daemon whether the key images are still unspent. The signatures are
+//! wallet2's, but wallet2 packs them in a boost archive, which isn't
+//! implemented here: proofs are written in this crate's own encoding under
+//! the prefix `RsReserveProofV2`, so neither side reads the other's.
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use curve25519_dalek::{
+    EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT, edwards::CompressedEdwardsY,
+};
+use rand::rngs::OsRng;
+
+use super::{
+    Reader, Transaction, TransactionPrefix, TxError, TxIn,
+    builder::{OwnedOutput, output_secret},
+    rct::{RctType, RingMember},
+};
+use crate::address::{Address, AddressKind};
+use crate::crypto::{
+    cn::{KeyImage, generate_key_image, hash_to_scalar},
+    hash::{Hash256, keccak256},
+    keys::{
+        KeyDerivation, WalletKeys, derive_public_key, derive_subaddress_public_key,
+        generate_key_derivation,
+    },
+    rct::ecdh::shared_secret,
+    ring::cryptonote::{self, RingSignature},
+    signature::schnorr::{self, SchnorrSignature},
+};
+use crate::encoding::{base58, varint};
+
+/// Domain separator hashed into version 2 proofs
+const HASH_KEY_TXPROOF_V2: &[u8] = b"TXPROOF_V2";
+/// Base58 of a 32-byte key followed by a 64-byte signature
+const ENCODED_SIGNATURE_LEN: usize = 44 + 88;
+/// Base58 of a 64-byte signature
+const ENCODED_RING_SIGNATURE_LEN: usize = 88;
+
+/// Why a proof couldn't be made, parsed or checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofError {
+    /// Not a proof string, or a damaged one
+    Format,
+    /// The transaction's tx_extra has no usable tx public key
+    NoTxPublicKey,
+    /// One signature per tx public key is needed
+    SignatureCount { expected: usize, found: usize },
+    /// No signature in the proof checks out
+    InvalidSignature,
+    /// Input `n` spends none of the given outputs
+    UnknownKeyImage(usize),
+    /// The ring given for input `n` isn't the one it references
+    RingMismatch(usize),
+    /// The transaction of reserve proof entry `n` isn't confirmed
+    UnknownTransaction(usize),
+    /// Reserve proof entry `n` isn't an output to the address
+    NotReceived(usize),
+    /// Reserve proof entry `n` repeats an earlier entry's key image
+    DuplicateEntry(usize),
+    /// The daemon couldn't say whether the key images are spent
+    Lookup,
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::Format => write!(f, "malformed proof"),
+            ProofError::NoTxPublicKey => write!(f, "transaction has no tx public key"),
+            ProofError::SignatureCount { expected, found } => {
+                write!(f, "{found} signatures for {expected} tx keys")
+            }
+            ProofError::InvalidSignature => write!(f, "proof signature is invalid"),
+            ProofError::UnknownKeyImage(i) => write!(f, "input {i} spends an unknown output"),
+            ProofError::RingMismatch(i) => write!(f, "wrong ring for input {i}"),
+            ProofError::UnknownTransaction(i) => {
+                write!(f, "transaction of entry {i} isn't confirmed")
+            }
+            ProofError::NotReceived(i) => write!(f, "entry {i} wasn't received by the address"),
+            ProofError::DuplicateEntry(i) => write!(f, "entry {i} repeats an earlier output"),
+            ProofError::Lookup => write!(f, "key image status unavailable"),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+/// Who made a [`TxProof`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofDirection {
+    /// The sender, with the tx key
+    Out,
+    /// The recipient, with the view key
+    In,
+}
+
+/// A proof that a transaction paid an address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxProof {
+    pub direction: ProofDirection,
+    /// 1 for proofs from before wallet2 added domain separation; only
+    /// checked, never made
+    pub version: u8,
+    /// `D` for the tx public key, then for each additional key
+    pub shared_secrets: Vec<EdwardsPoint>,
+    /// `(c, r)` per shared secret
+    pub signatures: Vec<(Scalar, Scalar)>,
+}
+
+/// `Keccak256(txid || message)`, the message every signature signs
+fn prefix_hash(txid: &Hash256, message: &str) -> [u8; 32] {
+    let mut data = txid.as_bytes().to_vec();
+    data.extend_from_slice(message.as_bytes());
+    keccak256(&data).to_bytes()
+}
+
+/// `Hs(msg || D || X || Y)`, and from version 2 also `|| Hash("TXPROOF_V2")
+/// || R || A || B`, with zeros for a missing `B`
+#[allow(clippy::too_many_arguments)]
+fn challenge(
+    version: u8,
+    prefix_hash: &[u8; 32],
+    r: &EdwardsPoint,
+    a: &EdwardsPoint,
+    b: Option<&EdwardsPoint>,
+    d: &EdwardsPoint,
+    x: &EdwardsPoint,
+    y: &EdwardsPoint,
+) -> Scalar {
+    let mut buf = prefix_hash.to_vec();
+    for point in [d, x, y] {
+        buf.extend_from_slice(point.compress().as_bytes());
+    }
+    if version >= 2 {
+        buf.extend_from_slice(keccak256(HASH_KEY_TXPROOF_V2).as_bytes());
+        buf.extend_from_slice(r.compress().as_bytes());
+        buf.extend_from_slice(a.compress().as_bytes());
+        buf.extend_from_slice(&b.map_or([0; 32], |b| b.compress().to_bytes()));
+    }
+    hash_to_scalar(&buf)
+}
+
+/// Prove `D = s*A` for the secret `s` of `R = s*G`, or of `R = s*B` if
+/// `B` is given: crypto's `generate_tx_proof`
+fn generate_tx_proof(
+    prefix_hash: &[u8; 32],
+    r: &EdwardsPoint,
+    a: &EdwardsPoint,
+    b: Option<&EdwardsPoint>,
+    d: &EdwardsPoint,
+    secret: &Scalar,
+) -> (Scalar, Scalar) {
+    let k = Scalar::random(&mut OsRng);
+    let x = k * b.unwrap_or(&ED25519_BASEPOINT_POINT);
+    let y = k * a;
+    let c = challenge(2, prefix_hash, r, a, b, d, &x, &y);
+    (c, k - c * secret)
+}
+
+/// crypto's `check_tx_proof`
+#[allow(clippy::too_many_arguments)]
+fn check_tx_proof(
+    version: u8,
+    prefix_hash: &[u8; 32],
+    r: &EdwardsPoint,
+    a: &EdwardsPoint,
+    b: Option<&EdwardsPoint>,
+    d: &EdwardsPoint,
+    (c, s): &(Scalar, Scalar),
+) -> bool {
+    let x = c * r + s * b.unwrap_or(&ED25519_BASEPOINT_POINT);
+    let y = c * d + s * a;
+    challenge(version, prefix_hash, r, a, b, d, &x, &y) == *c
+}
+
+/// Subaddresses prove against their spend key, standard addresses
+/// against G
+fn base_of(address: &Address) -> Option<&EdwardsPoint> {
+    matches!(address.kind(), AddressKind::Subaddress).then(|| address.spend_public())
+}
+
+/// Tx public key and additional keys from `prefix`'s tx_extra
+fn tx_public_keys(prefix: &TransactionPrefix) -> Result<Vec<EdwardsPoint>, ProofError> {
+    let extra = prefix.parse_extra();
+    let main = extra.tx_public_key().ok_or(ProofError::NoTxPublicKey)?;
+    std::iter::once(main)
+        .chain(extra.additional_public_keys())
+        .map(|key| key.decompress().ok_or(ProofError::NoTxPublicKey))
+        .collect()
+}
+
+impl TxProof {
+    /// Sender's proof of paying `address` in transaction `txid`, from its
+    /// tx key and additional tx keys
+    pub fn outbound(
+        txid: &Hash256,
+        tx_key: &Scalar,
+        additional_tx_keys: &[Scalar],
+        address: &Address,
+        message: &str,
+    ) -> Self {
+        let hash = prefix_hash(txid, message);
+        let b = base_of(address);
+        let (shared_secrets, signatures) = std::iter::once(tx_key)
+            .chain(additional_tx_keys)
+            .map(|r| {
+                let d = r * address.view_public();
+                let tx_public_key = r * b.unwrap_or(&ED25519_BASEPOINT_POINT);
+                let signature =
+                    generate_tx_proof(&hash, &tx_public_key, address.view_public(), b, &d, r);
+                (d, signature)
+            })
+            .unzip();
+        Self {
+            direction: ProofDirection::Out,
+            version: 2,
+            shared_secrets,
+            signatures,
+        }
+    }
+
+    /// Recipient's proof that `address`, one of the wallet with
+    /// `view_secret`, was paid in `tx`
+    pub fn inbound(
+        tx: &Transaction,
+        view_secret: &Scalar,
+        address: &Address,
+        message: &str,
+    ) -> Result<Self, ProofError> {
+        let hash = prefix_hash(&tx.hash(), message);
+        let b = base_of(address);
+        let (shared_secrets, signatures) = tx_public_keys(&tx.prefix)?
+            .iter()
+            .map(|tx_public_key| {
+                let d = view_secret * tx_public_key;
+                let signature = generate_tx_proof(
+                    &hash,
+                    address.view_public(),
+                    tx_public_key,
+                    b,
+                    &d,
+                    view_secret,
+                );
+                (d, signature)
+            })
+            .unzip();
+        Ok(Self {
+            direction: ProofDirection::In,
+            version: 2,
+            shared_secrets,
+            signatures,
+        })
+    }
+
+    /// Check the proof against `tx` and return how much it paid `address`
+    pub fn verify(
+        &self,
+        tx: &Transaction,
+        address: &Address,
+        message: &str,
+    ) -> Result<u64, ProofError> {
+        let tx_public_keys = tx_public_keys(&tx.prefix)?;
+        if tx_public_keys.len() != self.signatures.len()
+            || self.shared_secrets.len() != self.signatures.len()
+        {
+            return Err(ProofError::SignatureCount {
+                expected: tx_public_keys.len(),
+                found: self.signatures.len(),
+            });
+        }
+        let hash = prefix_hash(&tx.hash(), message);
+        let view = address.view_public();
+        let b = base_of(address);
+        let derivations: Vec<Option<KeyDerivation>> = tx_public_keys
+            .iter()
+            .zip(&self.shared_secrets)
+            .zip(&self.signatures)
+            .map(|((tx_public_key, d), signature)| {
+                let good = match self.direction {
+                    ProofDirection::Out => {
+                        check_tx_proof(self.version, &hash, tx_public_key, view, b, d, signature)
+                    }
+                    ProofDirection::In => {
+                        check_tx_proof(self.version, &hash, view, tx_public_key, b, d, signature)
+                    }
+                };
+                // 8*D, as if derived with the scalar 1
+                good.then(|| generate_key_derivation(d, &Scalar::ONE))
+            })
+            .collect();
+        if derivations.iter().all(Option::is_none) {
+            return Err(ProofError::InvalidSignature);
+        }
+        Ok(received(tx, address, &derivations))
+    }
+}
+
+/// Total paid to `address` by outputs found with the main derivation or
+/// the output's additional one, like wallet2's `check_tx_key_helper`
+fn received(tx: &Transaction, address: &Address, derivations: &[Option<KeyDerivation>]) -> u64 {
+    let mut total = 0u64;
+    for (n, output) in tx.prefix.vout.iter().enumerate() {
+        let index = n as u64;
+        let found = [
+            derivations[0].as_ref(),
+            derivations.get(n + 1).and_then(Option::as_ref),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|derivation| {
+            derive_public_key(derivation, index, address.spend_public()).compress() == *output.key()
+        });
+        if let Some(derivation) = found {
+            total = total.saturating_add(amount(tx, n, &shared_secret(derivation, index)));
+        }
+    }
+    total
+}
+
+/// Decrypted amount of output `n`, or 0 if it doesn't open the commitment
+fn amount(tx: &Transaction, n: usize, shared: &Scalar) -> u64 {
+    let rct = &tx.rct_signatures.base;
+    if rct.rct_type == RctType::Null {
+        return tx.prefix.vout[n].amount;
+    }
+    let (Some(ecdh), Some(commitment)) = (rct.ecdh_info.get(n), rct.out_pk.get(n)) else {
+        return 0;
+    };
+    ecdh.decode(shared, commitment)
+        .map_or(0, |(amount, _)| amount)
+}
+
+impl fmt::Display for TxProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.direction {
+            ProofDirection::Out => "Out",
+            ProofDirection::In => "In",
+        };
+        write!(f, "{direction}ProofV{}", self.version)?;
+        for (d, (c, r)) in self.shared_secrets.iter().zip(&self.signatures) {
+            let mut signature = c.to_bytes().to_vec();
+            signature.extend_from_slice(r.as_bytes());
+            write!(
+                f,
+                "{}{}",
+                base58::encode(d.compress().as_bytes()),
+                base58::encode(&signature)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TxProof {
+    type Err = ProofError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (direction, rest) = match (s.strip_prefix("OutProofV"), s.strip_prefix("InProofV")) {
+            (Some(rest), _) => (ProofDirection::Out, rest),
+            (_, Some(rest)) => (ProofDirection::In, rest),
+            _ => return Err(ProofError::Format),
+        };
+        let version = match rest.as_bytes().first() {
+            Some(b'1') => 1,
+            Some(b'2') => 2,
+            _ => return Err(ProofError::Format),
+        };
+        let body = &rest[1..];
+        if body.is_empty() || !body.len().is_multiple_of(ENCODED_SIGNATURE_LEN) {
+            return Err(ProofError::Format);
+        }
+        let decode = |text: &str| base58::decode(text).map_err(|_| ProofError::Format);
+        let scalar = |bytes: &[u8]| {
+            Option::from(Scalar::from_canonical_bytes(bytes.try_into().unwrap()))
+                .ok_or(ProofError::Format)
+        };
+        let mut shared_secrets = Vec::new();
+        let mut signatures = Vec::new();
+        for chunk in body.as_bytes().chunks(ENCODED_SIGNATURE_LEN) {
+            let chunk = std::str::from_utf8(chunk).map_err(|_| ProofError::Format)?;
+            let (d, signature) = (decode(&chunk[..44])?, decode(&chunk[44..])?);
+            if d.len() != 32 || signature.len() != 64 {
+                return Err(ProofError::Format);
+            }
+            let d = CompressedEdwardsY(d.try_into().unwrap())
+                .decompress()
+                .ok_or(ProofError::Format)?;
+            shared_secrets.push(d);
+            signatures.push((scalar(&signature[..32])?, scalar(&signature[32..])?));
+        }
+        Ok(Self {
+            direction,
+            version,
+            shared_secrets,
+            signatures,
+        })
+    }
+}
+
+/// A proof that whoever made it spent every input of a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendProof {
+    /// `(c, r)` per ring member, for every input in order
+    pub signatures: Vec<(Scalar, Scalar)>,
+}
+
+/// Ring of every input of `tx`, checked against its offsets
+fn ring_keys(
+    tx: &Transaction,
+    rings: &[Vec<RingMember>],
+) -> Result<Vec<Vec<EdwardsPoint>>, ProofError> {
+    if rings.len() != tx.prefix.vin.len() {
+        return Err(ProofError::RingMismatch(
+            rings.len().min(tx.prefix.vin.len()),
+        ));
+    }
+    tx.prefix
+        .vin
+        .iter()
+        .zip(rings)
+        .enumerate()
+        .map(|(i, (input, ring))| {
+            let TxIn::ToKey(input) = input else {
+                return Err(ProofError::UnknownKeyImage(i));
+            };
+            let indices = input.absolute_offsets();
+            if indices != Some(ring.iter().map(|member| member.global_index).collect()) {
+                return Err(ProofError::RingMismatch(i));
+            }
+            Ok(ring.iter().map(|member| member.key).collect())
+        })
+        .collect()
+}
+
+impl SpendProof {
+    /// Prove `tx` was sent by `keys`, given the outputs it spent, in any
+    /// order, and each input's ring as `get_outs` returns it
+    pub fn generate(
+        keys: &WalletKeys,
+        tx: &Transaction,
+        spent: &[OwnedOutput],
+        rings: &[Vec<RingMember>],
+        message: &str,
+    ) -> Result<Self, ProofError> {
+        let hash = prefix_hash(&tx.hash(), message);
+        let secrets: Vec<(Scalar, &OwnedOutput)> = spent
+            .iter()
+            .map(|output| (output_secret(keys, output), output))
+            .collect();
+        let mut signatures = Vec::new();
+        for (i, ring) in ring_keys(tx, rings)?.iter().enumerate() {
+            let key_image = tx.prefix.vin[i].key_image().expect("checked by ring_keys");
+            let (secret, output) = secrets
+                .iter()
+                .find(|(secret, output)| generate_key_image(secret, &output.key) == *key_image)
+                .ok_or(ProofError::UnknownKeyImage(i))?;
+            let index = ring
+                .iter()
+                .position(|key| *key == output.key)
+                .ok_or(ProofError::RingMismatch(i))?;
+            let signature = cryptonote::sign(&hash, key_image, ring, secret, index)
+                .map_err(|_| ProofError::UnknownKeyImage(i))?;
+            signatures.extend(signature.0);
+        }
+        Ok(Self { signatures })
+    }
+
+    /// Check the proof against `tx` and the rings of its inputs
+    pub fn verify(
+        &self,
+        tx: &Transaction,
+        rings: &[Vec<RingMember>],
+        message: &str,
+    ) -> Result<(), ProofError> {
+        let rings = ring_keys(tx, rings)?;
+        let expected = rings.iter().map(Vec::len).sum();
+        if self.signatures.len() != expected {
+            return Err(ProofError::SignatureCount {
+                expected,
+                found: self.signatures.len(),
+            });
+        }
+        let hash = prefix_hash(&tx.hash(), message);
+        let mut signatures = self.signatures.iter();
+        for (input, ring) in tx.prefix.vin.iter().zip(&rings) {
+            let key_image = input.key_image().expect("checked by ring_keys");
+            let signature = RingSignature(signatures.by_ref().take(ring.len()).copied().collect());
+            if !cryptonote::verify(&hash, key_image, ring, &signature) {
+                return Err(ProofError::InvalidSignature);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SpendProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SpendProofV1")?;
+        for pair in &self.signatures {
+            let signature = RingSignature(vec![*pair]).to_bytes();
+            write!(f, "{}", base58::encode(&signature))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SpendProof {
+    type Err = ProofError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let body = s.strip_prefix("SpendProofV1").ok_or(ProofError::Format)?;
+        if body.is_empty() || !body.len().is_multiple_of(ENCODED_RING_SIGNATURE_LEN) {
+            return Err(ProofError::Format);
+        }
+        let mut signatures = Vec::new();
+        for chunk in body.as_bytes().chunks(ENCODED_RING_SIGNATURE_LEN) {
+            let chunk = std::str::from_utf8(chunk).map_err(|_| ProofError::Format)?;
+            let bytes = base58::decode(chunk).map_err(|_| ProofError::Format)?;
+            if bytes.len() != 64 {
+                return Err(ProofError::Format);
+            }
+            let signature = RingSignature::from_bytes(&bytes).ok_or(ProofError::Format)?;
+            signatures.extend(signature.0);
+        }
+        Ok(Self { signatures })
+    }
+}
+
+/// What verifying a reserve proof needs from a daemon
+pub trait ReserveLookup {
+    /// The confirmed transaction `txid`, None if unknown or still in the
+    /// pool
+    fn transaction(&mut self, txid: &Hash256) -> Option<Transaction>;
+    /// Whether each key image is spent, on chain or in the pool, or None if
+    /// that can't be had
+    fn spent(&mut self, key_images: &[KeyImage]) -> Option<Vec<bool>>;
+}
+
+/// Prefix of this crate's reserve proofs, before the version; wallet2's
+/// `ReserveProofV` ones are a different encoding
+const RESERVE_PROOF_PREFIX: &str = "RsReserveProofV";
+
+/// One output in a [`ReserveProof`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReserveProofEntry {
+    /// Transaction that created the output
+    pub txid: Hash256,
+    pub index_in_tx: u64,
+    /// `a*R` for the tx key the output was derived from
+    pub shared_secret: EdwardsPoint,
+    pub key_image: KeyImage,
+    /// Tx proof `(c, r)` of the shared secret
+    pub shared_secret_sig: (Scalar, Scalar),
+    /// Ring signature `(c, r)` of the key image, the output as its ring
+    pub key_image_sig: (Scalar, Scalar),
+}
+
+/// A proof that a wallet holds a set of outputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReserveProof {
+    /// 1 for proofs with version 1 tx proofs; only checked, never made
+    pub version: u8,
+    pub entries: Vec<ReserveProofEntry>,
+    /// Each spend key the outputs were received with, signing the proof
+    pub spend_keys: Vec<(EdwardsPoint, SchnorrSignature)>,
+}
+
+/// Balance shown by a [`ReserveProof`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reserve {
+    /// All outputs in the proof
+    pub total: u64,
+    /// Those whose key image has since been spent
+    pub spent: u64,
+}
+
+impl Reserve {
+    pub fn unspent(&self) -> u64 {
+        self.total - self.spent
+    }
+}
+
+/// `Keccak256(message || spend key || view key || key images)`, with the
+/// main address' keys
+fn reserve_prefix_hash(
+    message: &str,
+    spend_public: &EdwardsPoint,
+    view_public: &EdwardsPoint,
+    entries: &[ReserveProofEntry],
+) -> [u8; 32] {
+    let mut data = message.as_bytes().to_vec();
+    data.extend_from_slice(spend_public.compress().as_bytes());
+    data.extend_from_slice(view_public.compress().as_bytes());
+    for entry in entries {
+        data.extend_from_slice(entry.key_image.as_bytes());
+    }
+    keccak256(&data).to_bytes()
+}
+
+impl ReserveProof {
+    /// Prove `keys` hold `outputs`, each with the id of the transaction
+    /// that created it
+    pub fn generate(keys: &WalletKeys, outputs: &[(Hash256, OwnedOutput)], message: &str) -> Self {
+        let mut entries: Vec<ReserveProofEntry> = Vec::with_capacity(outputs.len());
+        let mut secrets = Vec::with_capacity(outputs.len());
+        for (txid, output) in outputs {
+            let secret = output_secret(keys, output);
+            entries.push(ReserveProofEntry {
+                txid: *txid,
+                index_in_tx: output.output_index,
+                shared_secret: keys.view_secret() * output.tx_public_key,
+                key_image: generate_key_image(&secret, &output.key),
+                shared_secret_sig: (Scalar::ZERO, Scalar::ZERO),
+                key_image_sig: (Scalar::ZERO, Scalar::ZERO),
+            });
+            secrets.push(secret);
+        }
+        let hash = reserve_prefix_hash(message, keys.spend_public(), keys.view_public(), &entries);
+        for ((entry, (_, output)), secret) in entries.iter_mut().zip(outputs).zip(&secrets) {
+            entry.shared_secret_sig = generate_tx_proof(
+                &hash,
+                keys.view_public(),
+                &output.tx_public_key,
+                None,
+                &entry.shared_secret,
+                keys.view_secret(),
+            );
+            let key_image = entry.key_image;
+            if let Ok(signature) =
+                cryptonote::sign(key_image.as_bytes(), &key_image, &[output.key], secret, 0)
+            {
+                entry.key_image_sig = signature.0[0];
+            }
+        }
+
+        let mut spend_keys: Vec<(EdwardsPoint, SchnorrSignature)> = Vec::new();
+        for (_, output) in outputs {
+            let (major, minor) = output.subaddress;
+            let secret = keys.subaddress_spend_secret(major, minor);
+            let public = secret * ED25519_BASEPOINT_POINT;
+            if !spend_keys.iter().any(|(key, _)| *key == public) {
+                spend_keys.push((public, schnorr::sign(&hash, &secret)));
+            }
+        }
+        Self {
+            version: 2,
+            entries,
+            spend_keys,
+        }
+    }
+
+    /// Check the proof for the wallet with main address `address` against
+    /// the chain
+    pub fn verify(
+        &self,
+        address: &Address,
+        message: &str,
+        lookup: &mut impl ReserveLookup,
+    ) -> Result<Reserve, ProofError> {
+        let key_images: Vec<KeyImage> = self.entries.iter().map(|e| e.key_image).collect();
+        // an output listed twice would count twice
+        let mut seen = HashSet::with_capacity(key_images.len());
+        if let Some(i) = key_images.iter().position(|k| !seen.insert(k)) {
+            return Err(ProofError::DuplicateEntry(i));
+        }
+        let spent = lookup.spent(&key_images).ok_or(ProofError::Lookup)?;
+        if spent.len() != key_images.len() {
+            return Err(ProofError::Lookup);
+        }
+        let hash = reserve_prefix_hash(
+            message,
+            address.spend_public(),
+            address.view_public(),
+            &self.entries,
+        );
+        let mut reserve = Reserve { total: 0, spent: 0 };
+        for (i, (entry, spent)) in self.entries.iter().zip(spent).enumerate() {
+            let tx = lookup
+                .transaction(&entry.txid)
+                .ok_or(ProofError::UnknownTransaction(i))?;
+            let n = entry.index_in_tx as usize;
+            let output_key = tx
+                .prefix
+                .vout
+                .get(n)
+                .and_then(|output| output.key().decompress())
+                .ok_or(ProofError::NotReceived(i))?;
+            let tx_public_keys = tx_public_keys(&tx.prefix)?;
+            let check = |tx_public_key: &EdwardsPoint| {
+                check_tx_proof(
+                    self.version,
+                    &hash,
+                    address.view_public(),
+                    tx_public_key,
+                    None,
+                    &entry.shared_secret,
+                    &entry.shared_secret_sig,
+                )
+            };
+            let additional = match tx_public_keys.len() == tx.prefix.vout.len() + 1 {
+                true => tx_public_keys.get(n + 1),
+                false => None,
+            };
+            if !check(&tx_public_keys[0]) && !additional.is_some_and(check) {
+                return Err(ProofError::InvalidSignature);
+            }
+            let key_image_sig = RingSignature(vec![entry.key_image_sig]);
+            if !cryptonote::verify(
+                entry.key_image.as_bytes(),
+                &entry.key_image,
+                &[output_key],
+                &key_image_sig,
+            ) {
+                return Err(ProofError::InvalidSignature);
+            }
+
+            let derivation = generate_key_derivation(&entry.shared_secret, &Scalar::ONE);
+            let spend_key =
+                derive_subaddress_public_key(&output_key, &derivation, entry.index_in_tx);
+            if !self.spend_keys.iter().any(|(key, _)| *key == spend_key) {
+                return Err(ProofError::NotReceived(i));
+            }
+            let amount = amount(&tx, n, &shared_secret(&derivation, entry.index_in_tx));
+            reserve.total = reserve.total.saturating_add(amount);
+            if spent {
+                reserve.spent = reserve.spent.saturating_add(amount);
+            }
+        }
+        for (key, signature) in &self.spend_keys {
+            if !schnorr::verify(&hash, key, signature) {
+                return Err(ProofError::InvalidSignature);
+            }
+        }
+        Ok(reserve)
+    }
+
+    /// The entries, then the spend keys with their signatures. Not
+    /// wallet2's boost archive.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint::write(&mut out, self.entries.len() as u64);
+        for entry in &self.entries {
+            out.extend_from_slice(entry.txid.as_bytes());
+            varint::write(&mut out, entry.index_in_tx);
+            out.extend_from_slice(entry.shared_secret.compress().as_bytes());
+            out.extend_from_slice(entry.key_image.as_bytes());
+            for (c, r) in [entry.shared_secret_sig, entry.key_image_sig] {
+                out.extend_from_slice(c.as_bytes());
+                out.extend_from_slice(r.as_bytes());
+            }
+        }
+        varint::write(&mut out, self.spend_keys.len() as u64);
+        for (key, signature) in &self.spend_keys {
+            out.extend_from_slice(key.compress().as_bytes());
+            out.extend_from_slice(&signature.to_bytes());
+        }
+        out
+    }
+
+    fn from_bytes(data: &[u8], version: u8) -> Result<Self, TxError> {
+        let mut r = Reader::new(data);
+        let point = |r: &mut Reader<'_>| {
+            CompressedEdwardsY(r.array()?)
+                .decompress()
+                .ok_or(TxError::InvalidPoint)
+        };
+        let pair = |r: &mut Reader<'_>| {
+            let signature = RingSignature::from_bytes(r.take(64)?).ok_or(TxError::InvalidScalar)?;
+            Ok::<_, TxError>(signature.0[0])
+        };
+        let n_entries = r.count(32 + 1 + 32 + 32 + 128)?;
+        let mut entries = Vec::with_capacity(n_entries);
+        for _ in 0..n_entries {
+            entries.push(ReserveProofEntry {
+                txid: Hash256::from(r.array()?),
+                index_in_tx: r.varint()?,
+                shared_secret: point(&mut r)?,
+                key_image: KeyImage(r.array()?),
+                shared_secret_sig: pair(&mut r)?,
+                key_image_sig: pair(&mut r)?,
+            });
+        }
+        let n_keys = r.count(96)?;
+        let mut spend_keys = Vec::with_capacity(n_keys);
+        for _ in 0..n_keys {
+            let key = point(&mut r)?;
+            let signature =
+                SchnorrSignature::from_bytes(&r.array()?).ok_or(TxError::InvalidScalar)?;
+            spend_keys.push((key, signature));
+        }
+        r.finish()?;
+        Ok(Self {
+            version,
+            entries,
+            spend_keys,
+        })
+    }
+}
+
+impl fmt::Display for ReserveProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{RESERVE_PROOF_PREFIX}{}{}",
+            self.version,
+            base58::encode(&self.to_bytes())
+        )
+    }
+}
+
+impl FromStr for ReserveProof {
+    type Err = ProofError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s
+            .strip_prefix(RESERVE_PROOF_PREFIX)
+            .ok_or(ProofError::Format)?;
+        let (version, body) = match (rest.strip_prefix('1'), rest.strip_prefix('2')) {
+            (Some(body), _) => (1, body),
+            (_, Some(body)) => (2, body),
+            _ => return Err(ProofError::Format),
+        };
+        let data = base58::decode(body).map_err(|_| ProofError::Format)?;
+        Self::from_bytes(&data, version).map_err(|_| ProofError::Format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{keys::WalletKeys, rct::ecdh::commitment_mask};
+    use crate::network::Network;
+    use crate::tx::builder::{
+        TransactionBuilder,
+        tests::{rings, with_chain},
+    };
+
+    #[test]
+    fn test_tx_proofs() {
+        let sender = WalletKeys::generate();
+        let recipient = WalletKeys::generate();
+        let to = Address::from_keys(&recipient, Network::Mainnet);
+        let sub = Address::subaddress(&recipient, Network::Mainnet, 0, 4);
+        let (mut chain, outputs) = with_chain(&sender, &[9_000_000_000]);
+        let pending = TransactionBuilder::new(&sender, Network::Mainnet)
+            .input(outputs[0].clone())
+            .destination(to, 1_000_000_000)
+            .destination(sub, 2_000_000_000)
+            .build(&mut chain)
+            .unwrap();
+        let tx = &pending.tx;
+        assert_eq!(pending.additional_tx_keys.len(), 3);
+
+        for (address, amount) in [(to, 1_000_000_000), (sub, 2_000_000_000)] {
+            let out = TxProof::outbound(
+                &tx.hash(),
+                &pending.tx_key,
+                &pending.additional_tx_keys,
+                &address,
+                "rent",
+            );
+            let text = out.to_string();
+            assert!(text.starts_with("OutProofV2"));
+            assert_eq!(text.len(), 10 + 4 * ENCODED_SIGNATURE_LEN);
+            let parsed: TxProof = text.parse().unwrap();
+            assert_eq!(parsed, out);
+            assert_eq!(parsed.verify(tx, &address, "rent"), Ok(amount));
+            assert_eq!(
+                parsed.verify(tx, &address, "other"),
+                Err(ProofError::InvalidSignature)
+            );
+
+            let inbound = TxProof::inbound(tx, recipient.view_secret(), &address, "").unwrap();
+            let parsed: TxProof = inbound.to_string().parse().unwrap();
+            assert_eq!(parsed.direction, ProofDirection::In);
+            assert_eq!(parsed.verify(tx, &address, ""), Ok(amount));
+        }
+
+        // the wrong address proves nothing was paid to it
+        let stranger = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let out = TxProof::outbound(
+            &tx.hash(),
+            &pending.tx_key,
+            &pending.additional_tx_keys,
+            &stranger,
+            "",
+        );
+        assert_eq!(out.verify(tx, &stranger, ""), Ok(0));
+        let mut forged = out.clone();
+        forged.shared_secrets[1] = to.view_public() * pending.additional_tx_keys[0];
+        assert_eq!(
+            forged.verify(tx, &to, ""),
+            Err(ProofError::InvalidSignature)
+        );
+
+        assert_eq!("OutProofV3".parse::<TxProof>(), Err(ProofError::Format));
+        assert_eq!("InProofV2".parse::<TxProof>(), Err(ProofError::Format));
+        let mut truncated = out.to_string();
+        truncated.pop();
+        assert_eq!(truncated.parse::<TxProof>(), Err(ProofError::Format));
+    }
+
+    #[test]
+    fn test_spend_proof() {
+        let sender = WalletKeys::generate();
+        let to = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let (mut chain, outputs) = with_chain(&sender, &[2_000_000_000, 3_000_000_000, 1]);
+        let pending = TransactionBuilder::new(&sender, Network::Mainnet)
+            .input(outputs[0].clone())
+            .input(outputs[1].clone())
+            .destination(to, 4_000_000_000)
+            .ring_size(4)
+            .build(&mut chain)
+            .unwrap();
+        let tx = &pending.tx;
+        let rings = rings(tx, &chain);
+
+        let proof = SpendProof::generate(&sender, tx, &outputs, &rings, "it was me").unwrap();
+        let text = proof.to_string();
+        assert_eq!(text.len(), 12 + 8 * ENCODED_RING_SIGNATURE_LEN);
+        let parsed: SpendProof = text.parse().unwrap();
+        assert_eq!(parsed, proof);
+        assert_eq!(parsed.verify(tx, &rings, "it was me"), Ok(()));
+        assert_eq!(
+            parsed.verify(tx, &rings, "it was you"),
+            Err(ProofError::InvalidSignature)
+        );
+        let mut swapped = rings.clone();
+        swapped.swap(0, 1);
+        assert_eq!(
+            parsed.verify(tx, &swapped, "it was me"),
+            Err(ProofError::RingMismatch(0))
+        );
+
+        // someone else can't prove it, nor can the sender without the
+        // outputs spent
+        let other = WalletKeys::generate();
+        assert_eq!(
+            SpendProof::generate(&other, tx, &outputs, &rings, ""),
+            Err(ProofError::UnknownKeyImage(0))
+        );
+        assert!(matches!(
+            SpendProof::generate(&sender, tx, &outputs[2..], &rings, ""),
+            Err(ProofError::UnknownKeyImage(_))
+        ));
+        assert_eq!(
+            "SpendProofV1".parse::<SpendProof>(),
+            Err(ProofError::Format)
+        );
+    }
+
+    /// Confirmed transactions, and key images spent so far
+    #[derive(Default)]
+    struct Daemon {
+        txs: Vec<Transaction>,
+        spent: Vec<KeyImage>,
+    }
+
+    impl ReserveLookup for Daemon {
+        fn transaction(&mut self, txid: &Hash256) -> Option<Transaction> {
+            self.txs.iter().find(|tx| tx.hash() == *txid).cloned()
+        }
+
+        fn spent(&mut self, key_images: &[KeyImage]) -> Option<Vec<bool>> {
+            Some(key_images.iter().map(|k| self.spent.contains(k)).collect())
+        }
+    }
+
+    /// Outputs of `tx` to `keys`' main address or subaddress (0, 4)
+    fn scan(keys: &WalletKeys, tx: &Transaction) -> Vec<(Hash256, OwnedOutput)> {
+        let tx_public_keys = tx_public_keys(&tx.prefix).unwrap();
+        let mut found = Vec::new();
+        for (n, output) in tx.prefix.vout.iter().enumerate() {
+            let index = n as u64;
+            let key = output.key().decompress().unwrap();
+            for tx_public_key in [Some(&tx_public_keys[0]), tx_public_keys.get(n + 1)]
+                .into_iter()
+                .flatten()
+            {
+                let derivation = generate_key_derivation(tx_public_key, keys.view_secret());
+                let spend = derive_subaddress_public_key(&key, &derivation, index);
+                let subaddress = match spend {
+                    s if s == *keys.spend_public() => (0, 0),
+                    s if s == keys.subaddress_spend_public(0, 4) => (0, 4),
+                    _ => continue,
+                };
+                let shared = shared_secret(&derivation, index);
+                let output = OwnedOutput {
+                    global_index: index,
+                    key,
+                    amount: amount(tx, n, &shared),
+                    mask: commitment_mask(&shared),
+                    tx_public_key: *tx_public_key,
+                    output_index: index,
+                    subaddress,
+                };
+                found.push((tx.hash(), output));
+                break;
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn test_reserve_proof() {
+        let sender = WalletKeys::generate();
+        let holder = WalletKeys::generate();
+        let main = Address::from_keys(&holder, Network::Mainnet);
+        let sub = Address::subaddress(&holder, Network::Mainnet, 0, 4);
+        let (mut chain, outputs) = with_chain(&sender, &[9_000_000_000]);
+        let pending = TransactionBuilder::new(&sender, Network::Mainnet)
+            .input(outputs[0].clone())
+            .destination(main, 1_000_000_000)
+            .destination(sub, 2_000_000_000)
+            .build(&mut chain)
+            .unwrap();
+        let held = scan(&holder, &pending.tx);
+        assert_eq!(held.len(), 2);
+        let mut daemon = Daemon {
+            txs: vec![pending.tx.clone()],
+            ..Default::default()
+        };
+
+        let proof = ReserveProof::generate(&holder, &held, "audit");
+        assert_eq!(proof.spend_keys.len(), 2);
+        let text = proof.to_string();
+        assert!(text.starts_with("RsReserveProofV2"));
+        let parsed: ReserveProof = text.parse().unwrap();
+        assert_eq!(parsed, proof);
+        let reserve = parsed.verify(&main, "audit", &mut daemon).unwrap();
+        assert_eq!(reserve.total, 3_000_000_000);
+        assert_eq!(reserve.unspent(), 3_000_000_000);
+
+        daemon.spent.push(proof.entries[0].key_image);
+        let reserve = parsed.verify(&main, "audit", &mut daemon).unwrap();
+        assert_eq!(reserve.unspent(), 3_000_000_000 - held[0].1.amount);
+
+        assert_eq!(
+            parsed.verify(&main, "other", &mut daemon),
+            Err(ProofError::InvalidSignature)
+        );
+        let stranger = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        assert_eq!(
+            parsed.verify(&stranger, "audit", &mut daemon),
+            Err(ProofError::InvalidSignature)
+        );
+        // without the subaddress' signature it didn't receive its output
+        let mut partial = proof.clone();
+        partial
+            .spend_keys
+            .retain(|(key, _)| key == holder.spend_public());
+        assert!(matches!(
+            partial.verify(&main, "audit", &mut daemon),
+            Err(ProofError::NotReceived(_))
+        ));
+        assert_eq!(
+            parsed.verify(&main, "audit", &mut Daemon::default()),
+            Err(ProofError::UnknownTransaction(0))
+        );
+        let repeated = [held[0].clone(), held[1].clone(), held[0].clone()];
+        let padded = ReserveProof::generate(&holder, &repeated, "audit");
+        assert_eq!(
+            padded.verify(&main, "audit", &mut daemon),
+            Err(ProofError::DuplicateEntry(2))
+        );
+        assert_eq!(
+            "RsReserveProofV20".parse::<ReserveProof>(),
+            Err(ProofError::Format)
+        );
+        // wallet2's boost archive isn't read as this encoding
+        let wallet2 = text.replacen("RsReserveProofV2", "ReserveProofV2", 1);
+        assert_eq!(wallet2.parse::<ReserveProof>(), Err(ProofError::Format));
+    }
+}
@@ -0,0 +1,134 @@
+// src/tx/pruned.rs
+//! Transactions with their prunable data stripped, as pruned nodes keep
+//! and serve them.
+//!
+//! A pruned blob is the prefix followed by the RingCT base: the range
+//! proofs, ring signatures and pseudo-outputs are dropped, which is most
+//! of a transaction. The hash of what was dropped is kept, so the
+//! transaction id can still be computed and the prunable data checked if
+//! it comes back. A v1 transaction's id hashes its signatures, so a pruned
+//! one has no id of its own.
+use super::{
+    Reader, Transaction, TransactionPrefix, TxError,
+    rct::{RctSigBase, RctType},
+};
+use crate::crypto::hash::{Hash, Hash256, keccak256};
+
+/// A transaction without its prunable data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunedTransaction {
+    pub prefix: TransactionPrefix,
+    /// RingCT base; null in v1
+    pub rct_base: RctSigBase,
+    /// Hash of the prunable data, all zero when there is none
+    pub prunable_hash: Hash256,
+}
+
+impl PrunedTransaction {
+    /// Parse a pruned blob, as `get_transactions` returns in
+    /// `pruned_as_hex`, with the `prunable_hash` it lists alongside
+    pub fn from_blob(data: &[u8], prunable_hash: Hash256) -> Result<Self, TxError> {
+        let mut r = Reader::new(data);
+        let tx = Self::read(&mut r, prunable_hash)?;
+        r.finish()?;
+        Ok(tx)
+    }
+
+    pub(crate) fn read(r: &mut Reader<'_>, prunable_hash: Hash256) -> Result<Self, TxError> {
+        let prefix = TransactionPrefix::read(r)?;
+        let rct_base = match prefix.version {
+            1 => RctSigBase::null(),
+            2 => RctSigBase::read(r, prefix.key_images().count(), prefix.vout.len())?,
+            v => return Err(TxError::UnknownVersion(v)),
+        };
+        Ok(Self {
+            prefix,
+            rct_base,
+            prunable_hash,
+        })
+    }
+
+    /// Append the pruned encoding to `out`
+    pub fn serialize(&self, out: &mut Vec<u8>) {
+        self.prefix.serialize(out);
+        if self.prefix.version != 1 {
+            self.rct_base.serialize(out);
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.serialize(&mut out);
+        out
+    }
+
+    /// Transaction id, None for v1
+    pub fn hash(&self) -> Option<Hash256> {
+        if self.prefix.version == 1 {
+            return None;
+        }
+        let mut base = Vec::new();
+        self.rct_base.serialize(&mut base);
+        let mut data = self.prefix.prefix_hash().to_bytes().to_vec();
+        data.extend_from_slice(keccak256(&base).as_bytes());
+        data.extend_from_slice(self.prunable_hash.as_bytes());
+        Some(keccak256(&data))
+    }
+
+    /// The whole transaction again, from the `prunable` data it was
+    /// stripped of. For v2 that data must match the prunable hash.
+    pub fn restore(&self, prunable: &[u8]) -> Result<Transaction, TxError> {
+        if self.prefix.version != 1 {
+            let hash = match self.rct_base.rct_type {
+                RctType::Null => Hash([0; 32]),
+                _ => keccak256(prunable),
+            };
+            if hash != self.prunable_hash {
+                return Err(TxError::PrunableHashMismatch);
+            }
+        }
+        let mut blob = self.to_bytes();
+        blob.extend_from_slice(prunable);
+        Transaction::from_blob(&blob)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::prefix::tests::{MINER_TX_V1, RCT_TX_V2};
+
+    #[test]
+    fn test_prune_and_restore() {
+        let blob = hex::decode(RCT_TX_V2).unwrap();
+        let tx = Transaction::from_blob(&blob).unwrap();
+        let pruned = tx.prune();
+        assert_eq!(pruned.hash(), Some(tx.hash()));
+        let pruned_blob = pruned.to_bytes();
+        let prunable = tx.prunable_bytes();
+        assert_eq!(pruned_blob.len() + prunable.len(), blob.len());
+        assert_eq!(
+            PrunedTransaction::from_blob(&pruned_blob, pruned.prunable_hash),
+            Ok(pruned.clone())
+        );
+        assert_eq!(pruned.restore(&prunable), Ok(tx));
+
+        let mut corrupt = prunable.clone();
+        corrupt[0] ^= 1;
+        assert_eq!(pruned.restore(&corrupt), Err(TxError::PrunableHashMismatch));
+        assert_eq!(
+            PrunedTransaction::from_blob(&blob, pruned.prunable_hash),
+            Err(TxError::TrailingBytes(prunable.len()))
+        );
+    }
+
+    #[test]
+    fn test_v1() {
+        let tx = Transaction::from_hex(MINER_TX_V1).unwrap();
+        let pruned = tx.prune();
+        assert_eq!(pruned.hash(), None);
+        assert_eq!(pruned.prunable_hash, Hash([0; 32]));
+        assert_eq!(pruned.to_bytes(), tx.prefix.to_bytes());
+        assert_eq!(pruned.restore(&tx.prunable_bytes()), Ok(tx));
+    }
+}
@@ -0,0 +1,1015 @@
+// src/tx/rct.rs
+//! RingCT signatures, the part of a v2 transaction after the prefix.
+//!
+//! Split like monerod's `rctSig`: the base (type, fee, encrypted amounts,
+//! output commitments) is always kept, the prunable part (range proofs,
+//! ring signatures, pseudo-outputs) may be dropped by pruned nodes. The
+//! transaction hash commits to each part separately. Neither part says how
+//! many inputs, outputs or ring members it covers, so parsing needs the
+//! prefix.
+use std::fmt;
+
+use curve25519_dalek::{EdwardsPoint, Scalar, edwards::CompressedEdwardsY};
+
+use super::{Reader, Transaction, TransactionPrefix, TxError, TxIn};
+use crate::crypto::{
+    cn::KeyImage,
+    commitment::{commit, h_generator, sum_commitments},
+    hash::{Hash, Hash256, keccak256},
+    rct::{
+        borromean::{self, ATOMS, BorromeanSignature, RangeSig},
+        bulletproof::{self, Bulletproof},
+        bulletproof_plus::{self, BulletproofPlus},
+        ecdh,
+    },
+    ring::{
+        clsag::{self, ClsagSignature},
+        mlsag::{self, MlsagSignature},
+    },
+};
+use crate::encoding::varint;
+
+/// RingCT signature layout, by the type byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RctType {
+    /// No signatures: coinbase transactions
+    Null,
+    /// One MLSAG over all inputs, Borromean range proofs (v4)
+    Full,
+    /// An MLSAG per input with pseudo-outputs, Borromean range proofs (v4)
+    Simple,
+    /// Bulletproofs with full ecdh info (v8)
+    Bulletproof,
+    /// Bulletproofs with 8-byte encrypted amounts (v10)
+    Bulletproof2,
+    /// CLSAG instead of MLSAG (v13)
+    Clsag,
+    /// Bulletproofs+ with CLSAG, the only type accepted since v15
+    BulletproofPlus,
+}
+
+impl RctType {
+    pub fn from_u8(t: u8) -> Option<Self> {
+        Some(match t {
+            0 => RctType::Null,
+            1 => RctType::Full,
+            2 => RctType::Simple,
+            3 => RctType::Bulletproof,
+            4 => RctType::Bulletproof2,
+            5 => RctType::Clsag,
+            6 => RctType::BulletproofPlus,
+            _ => return None,
+        })
+    }
+
+    pub fn as_u8(self) -> u8 {
+        match self {
+            RctType::Null => 0,
+            RctType::Full => 1,
+            RctType::Simple => 2,
+            RctType::Bulletproof => 3,
+            RctType::Bulletproof2 => 4,
+            RctType::Clsag => 5,
+            RctType::BulletproofPlus => 6,
+        }
+    }
+
+    /// Amounts as 8 encrypted bytes rather than a 32-byte mask and amount
+    pub fn compact_amounts(self) -> bool {
+        matches!(
+            self,
+            RctType::Bulletproof2 | RctType::Clsag | RctType::BulletproofPlus
+        )
+    }
+
+    /// Range proven by (original) Bulletproofs
+    fn bulletproofs(self) -> bool {
+        matches!(
+            self,
+            RctType::Bulletproof | RctType::Bulletproof2 | RctType::Clsag
+        )
+    }
+
+    /// Signed with CLSAGs rather than MLSAGs
+    fn clsag(self) -> bool {
+        matches!(self, RctType::Clsag | RctType::BulletproofPlus)
+    }
+}
+
+/// An output's encrypted amount
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcdhInfo {
+    /// Up to type 3: mask and amount as scalars, each hidden by adding a
+    /// scalar derived from the shared secret
+    Full { mask: [u8; 32], amount: [u8; 32] },
+    /// From type 4: the amount XORed with 8 bytes of keystream; the mask is
+    /// derived from the shared secret instead
+    Compact([u8; 8]),
+}
+
+impl EcdhInfo {
+    /// `amount` hidden the way `rct_type` hides it. Only the long form
+    /// carries `mask`; the short one's is `commitment_mask(shared)`.
+    pub fn encode(amount: u64, mask: &Scalar, shared: &Scalar, rct_type: RctType) -> Self {
+        if rct_type.compact_amounts() {
+            EcdhInfo::Compact(ecdh::encode_amount(amount, shared))
+        } else {
+            let (mask, amount) = ecdh::encode_full(amount, mask, shared);
+            EcdhInfo::Full { mask, amount }
+        }
+    }
+
+    /// Amount and mask, if they open `commitment`
+    pub fn decode(
+        &self,
+        shared: &Scalar,
+        commitment: &CompressedEdwardsY,
+    ) -> Option<(u64, Scalar)> {
+        let (amount, mask) = match self {
+            EcdhInfo::Compact(encrypted) => (
+                ecdh::decode_amount(encrypted, shared),
+                ecdh::commitment_mask(shared),
+            ),
+            EcdhInfo::Full { mask, amount } => ecdh::decode_full(mask, amount, shared)?,
+        };
+        (commit(amount, mask).compress() == *commitment).then_some((amount, mask))
+    }
+}
+
+/// A ring member as the chain stores it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RingMember {
+    pub global_index: u64,
+    pub key: EdwardsPoint,
+    pub commitment: EdwardsPoint,
+}
+
+/// Fields every node keeps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RctSigBase {
+    pub rct_type: RctType,
+    pub fee: u64,
+    /// Pseudo-outputs of [`RctType::Simple`], which keeps them here rather
+    /// than in the prunable part
+    pub pseudo_outs: Vec<CompressedEdwardsY>,
+    /// Per output, the encrypted amount
+    pub ecdh_info: Vec<EcdhInfo>,
+    /// Per output, the amount commitment `C = mask*G + amount*H`
+    pub out_pk: Vec<CompressedEdwardsY>,
+}
+
+impl RctSigBase {
+    /// Base of a coinbase or v1 transaction
+    pub fn null() -> Self {
+        Self {
+            rct_type: RctType::Null,
+            fee: 0,
+            pseudo_outs: Vec::new(),
+            ecdh_info: Vec::new(),
+            out_pk: Vec::new(),
+        }
+    }
+
+    /// Append the consensus encoding to `out`
+    pub fn serialize(&self, out: &mut Vec<u8>) {
+        out.push(self.rct_type.as_u8());
+        if self.rct_type == RctType::Null {
+            return;
+        }
+        varint::write(out, self.fee);
+        for pseudo_out in &self.pseudo_outs {
+            out.extend_from_slice(pseudo_out.as_bytes());
+        }
+        for info in &self.ecdh_info {
+            match info {
+                EcdhInfo::Full { mask, amount } => {
+                    out.extend_from_slice(mask);
+                    out.extend_from_slice(amount);
+                }
+                EcdhInfo::Compact(amount) => out.extend_from_slice(amount),
+            }
+        }
+        for commitment in &self.out_pk {
+            out.extend_from_slice(commitment.as_bytes());
+        }
+    }
+
+    pub(crate) fn read(
+        r: &mut Reader<'_>,
+        n_inputs: usize,
+        n_outputs: usize,
+    ) -> Result<Self, TxError> {
+        let t = r.u8()?;
+        let rct_type = RctType::from_u8(t).ok_or(TxError::UnknownRctType(t))?;
+        let mut base = RctSigBase {
+            rct_type,
+            fee: 0,
+            pseudo_outs: Vec::new(),
+            ecdh_info: Vec::new(),
+            out_pk: Vec::new(),
+        };
+        if rct_type == RctType::Null {
+            return Ok(base);
+        }
+        base.fee = r.varint()?;
+        if rct_type == RctType::Simple {
+            base.pseudo_outs = read_points(r, n_inputs)?;
+        }
+        for _ in 0..n_outputs {
+            base.ecdh_info.push(if rct_type.compact_amounts() {
+                EcdhInfo::Compact(r.array()?)
+            } else {
+                EcdhInfo::Full {
+                    mask: r.array()?,
+                    amount: r.array()?,
+                }
+            });
+        }
+        base.out_pk = read_points(r, n_outputs)?;
+        Ok(base)
+    }
+}
+
+/// Proofs a pruned node may drop; which fields are used depends on the type
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RctSigPrunable {
+    /// Borromean range proofs, one per output
+    pub range_sigs: Vec<RangeSig>,
+    pub bulletproofs: Vec<Bulletproof>,
+    pub bulletproofs_plus: Vec<BulletproofPlus>,
+    /// One over all inputs for [`RctType::Full`], else one per input
+    pub mlsags: Vec<MlsagSignature>,
+    /// One per input, in input order
+    pub clsags: Vec<ClsagSignature>,
+    /// Per input, a commitment to the same amount under a fresh mask
+    pub pseudo_outs: Vec<CompressedEdwardsY>,
+}
+
+impl RctSigPrunable {
+    /// Append the consensus encoding for `rct_type` to `out`
+    pub fn serialize(&self, rct_type: RctType, out: &mut Vec<u8>) {
+        match rct_type {
+            RctType::Null => return,
+            RctType::Full | RctType::Simple => {}
+            // the first Bulletproof type counted its proofs in a u32
+            RctType::Bulletproof => {
+                out.extend_from_slice(&(self.bulletproofs.len() as u32).to_le_bytes());
+            }
+            RctType::Bulletproof2 | RctType::Clsag => {
+                varint::write(out, self.bulletproofs.len() as u64);
+            }
+            RctType::BulletproofPlus => {
+                varint::write(out, self.bulletproofs_plus.len() as u64);
+            }
+        }
+        for sig in &self.range_sigs {
+            for s in sig.asig.s0.iter().chain(&sig.asig.s1) {
+                out.extend_from_slice(s.as_bytes());
+            }
+            out.extend_from_slice(sig.asig.ee.as_bytes());
+            for ci in &sig.ci {
+                out.extend_from_slice(ci.as_bytes());
+            }
+        }
+        for proof in &self.bulletproofs {
+            write_bulletproof(proof, true, out);
+        }
+        for proof in &self.bulletproofs_plus {
+            write_bulletproof_plus(proof, true, out);
+        }
+        // key images live in the inputs, not here
+        for mlsag in &self.mlsags {
+            for column in &mlsag.ss {
+                for s in column {
+                    out.extend_from_slice(s.as_bytes());
+                }
+            }
+            out.extend_from_slice(mlsag.cc.as_bytes());
+        }
+        for clsag in &self.clsags {
+            for s in &clsag.s {
+                out.extend_from_slice(s.as_bytes());
+            }
+            out.extend_from_slice(clsag.c1.as_bytes());
+            out.extend_from_slice(clsag.d.compress().as_bytes());
+        }
+        for pseudo_out in &self.pseudo_outs {
+            out.extend_from_slice(pseudo_out.as_bytes());
+        }
+    }
+
+    fn read(
+        r: &mut Reader<'_>,
+        rct_type: RctType,
+        prefix: &TransactionPrefix,
+    ) -> Result<Self, TxError> {
+        let mut prunable = RctSigPrunable::default();
+        if rct_type == RctType::Null {
+            return Ok(prunable);
+        }
+        let key_images: Vec<KeyImage> = prefix.key_images().copied().collect();
+        let n_inputs = key_images.len();
+        let n_outputs = prefix.vout.len();
+        // every input's ring has the size of the first one
+        let ring_size = match prefix.vin.first() {
+            Some(TxIn::ToKey(input)) => input.ring_size(),
+            _ => 0,
+        };
+
+        match rct_type {
+            RctType::Full | RctType::Simple => {
+                for _ in 0..n_outputs {
+                    prunable.range_sigs.push(read_range_sig(r)?);
+                }
+            }
+            RctType::Bulletproof | RctType::Bulletproof2 | RctType::Clsag => {
+                let n = if rct_type == RctType::Bulletproof {
+                    let n = u32::from_le_bytes(r.array()?) as u64;
+                    if n.saturating_mul(32) > r.remaining() as u64 {
+                        return Err(TxError::TooLarge(n));
+                    }
+                    n as usize
+                } else {
+                    r.count(32)?
+                };
+                for _ in 0..n {
+                    prunable.bulletproofs.push(read_bulletproof(r)?);
+                }
+            }
+            RctType::BulletproofPlus => {
+                for _ in 0..r.count(32)? {
+                    prunable.bulletproofs_plus.push(read_bulletproof_plus(r)?);
+                }
+            }
+            RctType::Null => unreachable!(),
+        }
+
+        if rct_type.clsag() {
+            for key_image in &key_images {
+                let s = read_scalars(r, ring_size)?;
+                let c1 = read_scalar(r)?;
+                let d = CompressedEdwardsY(r.array()?)
+                    .decompress()
+                    .ok_or(TxError::InvalidPoint)?;
+                prunable.clsags.push(ClsagSignature {
+                    s,
+                    c1,
+                    d,
+                    key_image: *key_image,
+                });
+            }
+        } else {
+            // Full signs all inputs at once, with a commitment row on top
+            let (signatures, rows) = match rct_type {
+                RctType::Full => (vec![key_images], n_inputs + 1),
+                _ => (key_images.into_iter().map(|ki| vec![ki]).collect(), 2),
+            };
+            for key_images in signatures {
+                let mut ss = Vec::with_capacity(ring_size);
+                for _ in 0..ring_size {
+                    ss.push(read_scalars(r, rows)?);
+                }
+                let cc = read_scalar(r)?;
+                prunable.mlsags.push(MlsagSignature { ss, cc, key_images });
+            }
+        }
+
+        if rct_type.bulletproofs() || rct_type == RctType::BulletproofPlus {
+            prunable.pseudo_outs = read_points(r, n_inputs)?;
+        }
+        Ok(prunable)
+    }
+
+    /// Range-proof part of the signed message: every proof element
+    /// concatenated, without the length prefixes
+    fn proof_keys(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for sig in &self.range_sigs {
+            for s in sig.asig.s0.iter().chain(&sig.asig.s1) {
+                out.extend_from_slice(s.as_bytes());
+            }
+            out.extend_from_slice(sig.asig.ee.as_bytes());
+            for ci in &sig.ci {
+                out.extend_from_slice(ci.as_bytes());
+            }
+        }
+        for proof in &self.bulletproofs {
+            write_bulletproof(proof, false, &mut out);
+        }
+        for proof in &self.bulletproofs_plus {
+            write_bulletproof_plus(proof, false, &mut out);
+        }
+        out
+    }
+}
+
+fn write_bulletproof(proof: &Bulletproof, lengths: bool, out: &mut Vec<u8>) {
+    for point in [&proof.a, &proof.s, &proof.t1, &proof.t2] {
+        out.extend_from_slice(point.as_bytes());
+    }
+    out.extend_from_slice(proof.taux.as_bytes());
+    out.extend_from_slice(proof.mu.as_bytes());
+    for points in [&proof.l, &proof.r] {
+        if lengths {
+            varint::write(out, points.len() as u64);
+        }
+        for point in points {
+            out.extend_from_slice(point.as_bytes());
+        }
+    }
+    for scalar in [&proof.ip_a, &proof.ip_b, &proof.t] {
+        out.extend_from_slice(scalar.as_bytes());
+    }
+}
+
+fn write_bulletproof_plus(proof: &BulletproofPlus, lengths: bool, out: &mut Vec<u8>) {
+    for point in [&proof.a, &proof.a1, &proof.b] {
+        out.extend_from_slice(point.as_bytes());
+    }
+    for scalar in [&proof.r1, &proof.s1, &proof.d1] {
+        out.extend_from_slice(scalar.as_bytes());
+    }
+    for points in [&proof.l, &proof.r] {
+        if lengths {
+            varint::write(out, points.len() as u64);
+        }
+        for point in points {
+            out.extend_from_slice(point.as_bytes());
+        }
+    }
+}
+
+fn read_scalar(r: &mut Reader<'_>) -> Result<Scalar, TxError> {
+    Option::from(Scalar::from_canonical_bytes(r.array()?)).ok_or(TxError::InvalidScalar)
+}
+
+fn read_scalars(r: &mut Reader<'_>, n: usize) -> Result<Vec<Scalar>, TxError> {
+    if n.saturating_mul(32) > r.remaining() {
+        return Err(TxError::Truncated);
+    }
+    (0..n).map(|_| read_scalar(r)).collect()
+}
+
+fn read_points(r: &mut Reader<'_>, n: usize) -> Result<Vec<CompressedEdwardsY>, TxError> {
+    if n.saturating_mul(32) > r.remaining() {
+        return Err(TxError::Truncated);
+    }
+    (0..n).map(|_| Ok(CompressedEdwardsY(r.array()?))).collect()
+}
+
+fn read_range_sig(r: &mut Reader<'_>) -> Result<RangeSig, TxError> {
+    let s0 = read_scalars(r, ATOMS)?;
+    let s1 = read_scalars(r, ATOMS)?;
+    let ee = read_scalar(r)?;
+    let ci = read_points(r, ATOMS)?;
+    Ok(RangeSig {
+        asig: BorromeanSignature {
+            s0: s0.try_into().unwrap(),
+            s1: s1.try_into().unwrap(),
+            ee,
+        },
+        ci: ci.try_into().unwrap(),
+    })
+}
+
+fn read_bulletproof(r: &mut Reader<'_>) -> Result<Bulletproof, TxError> {
+    let [a, s, t1, t2] = read_points(r, 4)?.try_into().unwrap();
+    let taux = read_scalar(r)?;
+    let mu = read_scalar(r)?;
+    let n = r.count(32)?;
+    let l = read_points(r, n)?;
+    let n = r.count(32)?;
+    let rr = read_points(r, n)?;
+    Ok(Bulletproof {
+        a,
+        s,
+        t1,
+        t2,
+        taux,
+        mu,
+        l,
+        r: rr,
+        ip_a: read_scalar(r)?,
+        ip_b: read_scalar(r)?,
+        t: read_scalar(r)?,
+    })
+}
+
+fn read_bulletproof_plus(r: &mut Reader<'_>) -> Result<BulletproofPlus, TxError> {
+    let [a, a1, b] = read_points(r, 3)?.try_into().unwrap();
+    let r1 = read_scalar(r)?;
+    let s1 = read_scalar(r)?;
+    let d1 = read_scalar(r)?;
+    let n = r.count(32)?;
+    let l = read_points(r, n)?;
+    let n = r.count(32)?;
+    Ok(BulletproofPlus {
+        a,
+        a1,
+        b,
+        r1,
+        s1,
+        d1,
+        l,
+        r: read_points(r, n)?,
+    })
+}
+
+/// A transaction's complete RingCT data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RctSig {
+    pub base: RctSigBase,
+    /// Empty for [`RctType::Null`]
+    pub prunable: RctSigPrunable,
+}
+
+impl RctSig {
+    /// Signatures of a coinbase transaction
+    pub fn null() -> Self {
+        Self {
+            base: RctSigBase::null(),
+            prunable: RctSigPrunable::default(),
+        }
+    }
+
+    pub fn rct_type(&self) -> RctType {
+        self.base.rct_type
+    }
+
+    /// Append the consensus encoding to `out`
+    pub fn serialize(&self, out: &mut Vec<u8>) {
+        self.base.serialize(out);
+        self.prunable.serialize(self.base.rct_type, out);
+    }
+
+    /// Parse `data`, the rest of the blob after `prefix`
+    pub fn from_bytes(data: &[u8], prefix: &TransactionPrefix) -> Result<Self, TxError> {
+        let mut reader = Reader::new(data);
+        let rct = Self::read(&mut reader, prefix)?;
+        reader.finish()?;
+        Ok(rct)
+    }
+
+    pub(crate) fn read(r: &mut Reader<'_>, prefix: &TransactionPrefix) -> Result<Self, TxError> {
+        let n_inputs = prefix.key_images().count();
+        let base = RctSigBase::read(r, n_inputs, prefix.vout.len())?;
+        let prunable = RctSigPrunable::read(r, base.rct_type, prefix)?;
+        Ok(Self { base, prunable })
+    }
+
+    /// Pseudo-outputs, wherever the type keeps them
+    pub fn pseudo_outs(&self) -> &[CompressedEdwardsY] {
+        match self.base.rct_type {
+            RctType::Simple => &self.base.pseudo_outs,
+            _ => &self.prunable.pseudo_outs,
+        }
+    }
+
+    pub fn base_hash(&self) -> Hash256 {
+        let mut out = Vec::new();
+        self.base.serialize(&mut out);
+        keccak256(&out)
+    }
+
+    /// Hash of the prunable part, all zero when there is none
+    pub fn prunable_hash(&self) -> Hash256 {
+        if self.base.rct_type == RctType::Null {
+            return Hash([0; 32]);
+        }
+        let mut out = Vec::new();
+        self.prunable.serialize(self.base.rct_type, &mut out);
+        keccak256(&out)
+    }
+
+    /// Monero `get_pre_mlsag_hash`: the message every ring signature signs,
+    /// `Keccak256(prefix_hash || Keccak256(base) || Keccak256(proofs))`
+    pub fn signature_message(&self, prefix_hash: &Hash256) -> [u8; 32] {
+        let mut data = prefix_hash.as_bytes().to_vec();
+        data.extend_from_slice(self.base_hash().as_bytes());
+        data.extend_from_slice(keccak256(&self.prunable.proof_keys()).as_bytes());
+        keccak256(&data).to_bytes()
+    }
+}
+
+/// Why a transaction's RingCT data doesn't verify
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RctError {
+    /// Null signatures on a transaction that spends inputs
+    MissingSignatures,
+    /// Counts of amounts, proofs, signatures or ring members don't match
+    /// the prefix
+    Dimensions,
+    /// The ring given for input `n` isn't the one it references
+    RingMismatch(usize),
+    /// A commitment or pseudo-output isn't a curve point
+    InvalidPoint,
+    /// The range proof covering output `n` fails
+    RangeProof(usize),
+    /// Inputs don't add up to outputs plus fee
+    Unbalanced,
+    /// The ring signature of input `n` fails
+    Signature(usize),
+}
+
+impl fmt::Display for RctError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RctError::MissingSignatures => write!(f, "inputs without RingCT signatures"),
+            RctError::Dimensions => write!(f, "RingCT data doesn't match the prefix"),
+            RctError::RingMismatch(i) => write!(f, "wrong ring for input {i}"),
+            RctError::InvalidPoint => write!(f, "commitment is not a curve point"),
+            RctError::RangeProof(i) => write!(f, "range proof for output {i} fails"),
+            RctError::Unbalanced => write!(f, "inputs and outputs don't balance"),
+            RctError::Signature(i) => write!(f, "ring signature of input {i} fails"),
+        }
+    }
+}
+
+impl std::error::Error for RctError {}
+
+fn decompress_all(points: &[CompressedEdwardsY]) -> Result<Vec<EdwardsPoint>, RctError> {
+    points
+        .iter()
+        .map(|p| p.decompress().ok_or(RctError::InvalidPoint))
+        .collect()
+}
+
+/// Verify `tx`'s RingCT data: range proofs, balance and ring signatures
+///
+/// `rings` holds, per input, the outputs its key offsets reference, as
+/// looked up on chain. Port of `verRct` / `verRctSimple`; coinbase
+/// transactions have nothing to check.
+pub fn verify_rct(tx: &Transaction, rings: &[Vec<RingMember>]) -> Result<(), RctError> {
+    let prefix = &tx.prefix;
+    let rct = &tx.rct_signatures;
+    let rct_type = rct.base.rct_type;
+    if rct_type == RctType::Null {
+        return match prefix.vin.iter().all(|input| matches!(input, TxIn::Gen(_))) {
+            true => Ok(()),
+            false => Err(RctError::MissingSignatures),
+        };
+    }
+
+    let n_outputs = prefix.vout.len();
+    if rct.base.ecdh_info.len() != n_outputs
+        || rct.base.out_pk.len() != n_outputs
+        || rings.len() != prefix.vin.len()
+    {
+        return Err(RctError::Dimensions);
+    }
+    let mut key_images = Vec::with_capacity(rings.len());
+    for (i, (input, ring)) in prefix.vin.iter().zip(rings).enumerate() {
+        let TxIn::ToKey(input) = input else {
+            return Err(RctError::Dimensions);
+        };
+        let indices = input.absolute_offsets().ok_or(RctError::RingMismatch(i))?;
+        if !indices.iter().eq(ring.iter().map(|m| &m.global_index)) {
+            return Err(RctError::RingMismatch(i));
+        }
+        key_images.push(input.key_image);
+    }
+    let out_pk = decompress_all(&rct.base.out_pk)?;
+
+    // range proofs
+    let prunable = &rct.prunable;
+    match rct_type {
+        RctType::Full | RctType::Simple => {
+            if prunable.range_sigs.len() != n_outputs {
+                return Err(RctError::Dimensions);
+            }
+            for (i, (c, sig)) in out_pk.iter().zip(&prunable.range_sigs).enumerate() {
+                if !borromean::verify_range(c, sig) {
+                    return Err(RctError::RangeProof(i));
+                }
+            }
+        }
+        RctType::BulletproofPlus => match &prunable.bulletproofs_plus[..] {
+            [proof] if bulletproof_plus::verify(proof, &out_pk) => {}
+            [_] => return Err(RctError::RangeProof(0)),
+            _ => return Err(RctError::Dimensions),
+        },
+        _ => match &prunable.bulletproofs[..] {
+            [proof] if bulletproof::verify(proof, &out_pk) => {}
+            [_] => return Err(RctError::RangeProof(0)),
+            _ => return Err(RctError::Dimensions),
+        },
+    }
+
+    let message = rct.signature_message(&prefix.prefix_hash());
+    let fee = Scalar::from(rct.base.fee) * h_generator();
+    if rct_type == RctType::Full {
+        // the last row, sum(C_in) - sum(C_out) - fee*H, is a commitment to
+        // zero exactly when the amounts balance
+        let [mlsag] = &prunable.mlsags[..] else {
+            return Err(RctError::Dimensions);
+        };
+        let ring_size = rings[0].len();
+        if rings.iter().any(|ring| ring.len() != ring_size) || mlsag.key_images != key_images {
+            return Err(RctError::Dimensions);
+        }
+        let outputs = sum_commitments(&out_pk) + fee;
+        let matrix: Vec<Vec<EdwardsPoint>> = (0..ring_size)
+            .map(|j| {
+                let mut column: Vec<EdwardsPoint> = rings.iter().map(|ring| ring[j].key).collect();
+                column.push(
+                    rings
+                        .iter()
+                        .map(|ring| ring[j].commitment)
+                        .sum::<EdwardsPoint>()
+                        - outputs,
+                );
+                column
+            })
+            .collect();
+        if !mlsag::verify(&message, &matrix, mlsag, rings.len()) {
+            return Err(RctError::Signature(0));
+        }
+        return Ok(());
+    }
+
+    let pseudo_outs = decompress_all(rct.pseudo_outs())?;
+    if pseudo_outs.len() != rings.len() {
+        return Err(RctError::Dimensions);
+    }
+    if sum_commitments(&pseudo_outs) != sum_commitments(&out_pk) + fee {
+        return Err(RctError::Unbalanced);
+    }
+    for (i, ((ring, pseudo_out), key_image)) in
+        rings.iter().zip(&pseudo_outs).zip(&key_images).enumerate()
+    {
+        let keys: Vec<EdwardsPoint> = ring.iter().map(|m| m.key).collect();
+        let commitments: Vec<EdwardsPoint> = ring.iter().map(|m| m.commitment).collect();
+        let valid = if rct_type.clsag() {
+            match prunable.clsags.get(i) {
+                Some(sig) if sig.key_image == *key_image => {
+                    clsag::verify(&message, &keys, &commitments, pseudo_out, sig)
+                }
+                _ => return Err(RctError::Dimensions),
+            }
+        } else {
+            let Ok(matrix) = mlsag::ring_ct_matrix(&keys, &commitments, pseudo_out) else {
+                return Err(RctError::Dimensions);
+            };
+            match prunable.mlsags.get(i) {
+                Some(sig) if sig.key_images == [*key_image] => {
+                    mlsag::verify(&message, &matrix, sig, 1)
+                }
+                _ => return Err(RctError::Dimensions),
+            }
+        };
+        if !valid {
+            return Err(RctError::Signature(i));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::crypto::{cn::generate_key_image, commitment::commit, rct::borromean::prove_range};
+    use crate::tx::{TxInToKey, TxOut, prefix::tests::RCT_TX_V2};
+
+    fn parse(blob: &[u8]) -> Transaction {
+        Transaction::from_blob(blob).unwrap()
+    }
+
+    #[test]
+    fn test_mainnet_bulletproof2() {
+        let blob = hex::decode(RCT_TX_V2).unwrap();
+        let tx = parse(&blob);
+        let rct = &tx.rct_signatures;
+        assert_eq!(rct.rct_type(), RctType::Bulletproof2);
+        assert_eq!(rct.prunable.bulletproofs.len(), 1);
+        assert_eq!(rct.prunable.mlsags.len(), 2);
+        assert_eq!(rct.pseudo_outs().len(), 2);
+        assert_eq!(tx.to_bytes(), blob);
+        assert_eq!(
+            hex::encode(tx.hash().as_bytes()),
+            "5a420317e377d3d95b652fb93e65cfe97ef7d89e04be329a2ca94e73ec57b74e"
+        );
+        assert_eq!(
+            hex::encode(rct.signature_message(&tx.prefix.prefix_hash())),
+            "bb44ae73d38f6ef7db0c6df6c6d8dd4417e9c54b03510314799b825d4773e050"
+        );
+
+        let out_pk: Vec<EdwardsPoint> = rct
+            .base
+            .out_pk
+            .iter()
+            .map(|c| c.decompress().unwrap())
+            .collect();
+        let proof = &rct.prunable.bulletproofs[0];
+        assert!(bulletproof::verify(proof, &out_pk));
+        let mut tampered = proof.clone();
+        tampered.t += Scalar::ONE;
+        assert!(!bulletproof::verify(&tampered, &out_pk));
+        assert!(!bulletproof::verify(proof, &out_pk[..1]));
+
+        assert!(RctSig::from_bytes(&blob[blob.len() - 100..], &tx.prefix).is_err());
+    }
+
+    /// A two-input, two-output transaction of an MLSAG type, with its rings
+    fn mlsag_tx(rct_type: RctType) -> (Transaction, Vec<Vec<RingMember>>) {
+        const RING: usize = 3;
+        let (amounts, fee) = ([7u64, 5], 2u64);
+        let spent = [10u64, 4];
+        let real = 1;
+
+        let mut rings = Vec::new();
+        let mut secrets = Vec::new();
+        let mut vin = Vec::new();
+        for (i, amount) in spent.iter().enumerate() {
+            let x = Scalar::random(&mut OsRng);
+            let mask = Scalar::random(&mut OsRng);
+            let ring: Vec<RingMember> = (0..RING)
+                .map(|j| RingMember {
+                    global_index: (10 * i + j) as u64,
+                    key: Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT,
+                    commitment: commit(1, Scalar::random(&mut OsRng)),
+                })
+                .map(|member| match member.global_index as usize % 10 {
+                    j if j == real => RingMember {
+                        key: x * ED25519_BASEPOINT_POINT,
+                        commitment: commit(*amount, mask),
+                        ..member
+                    },
+                    _ => member,
+                })
+                .collect();
+            let indices: Vec<u64> = ring.iter().map(|m| m.global_index).collect();
+            let key_image = generate_key_image(&x, &ring[real].key);
+            vin.push(TxIn::ToKey(TxInToKey::from_absolute(
+                0, &indices, key_image,
+            )));
+            rings.push(ring);
+            secrets.push((x, mask));
+        }
+        let prefix = TransactionPrefix {
+            version: 2,
+            unlock_time: 0,
+            vin,
+            vout: (0..2)
+                .map(|_| {
+                    TxOut::to_key(
+                        0,
+                        (Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT).compress(),
+                    )
+                })
+                .collect(),
+            extra: Vec::new(),
+        };
+
+        let mut out_masks = Vec::new();
+        let mut range_sigs = Vec::new();
+        let mut out_pk = Vec::new();
+        for amount in amounts {
+            let (c, mask, sig) = prove_range(amount);
+            out_masks.push(mask);
+            range_sigs.push(sig);
+            out_pk.push(c.compress());
+        }
+        let out_mask_sum: Scalar = out_masks.iter().sum();
+        let first_mask = Scalar::random(&mut OsRng);
+        let pseudo_masks = [first_mask, out_mask_sum - first_mask];
+        let pseudo_outs: Vec<CompressedEdwardsY> = spent
+            .iter()
+            .zip(&pseudo_masks)
+            .map(|(amount, mask)| commit(*amount, *mask).compress())
+            .collect();
+
+        let mut rct = RctSig {
+            base: RctSigBase {
+                rct_type,
+                fee,
+                pseudo_outs: match rct_type {
+                    RctType::Simple => pseudo_outs.clone(),
+                    _ => Vec::new(),
+                },
+                ecdh_info: vec![
+                    EcdhInfo::Full {
+                        mask: [1; 32],
+                        amount: [2; 32],
+                    };
+                    2
+                ],
+                out_pk,
+            },
+            prunable: RctSigPrunable {
+                range_sigs,
+                ..Default::default()
+            },
+        };
+        let message = rct.signature_message(&prefix.prefix_hash());
+        let tx = Transaction {
+            prefix,
+            signatures: Vec::new(),
+            rct_signatures: rct.clone(),
+        };
+        let out_pk: Vec<EdwardsPoint> = rct
+            .base
+            .out_pk
+            .iter()
+            .map(|c| c.decompress().unwrap())
+            .collect();
+        if rct_type == RctType::Full {
+            let outputs = sum_commitments(&out_pk) + Scalar::from(fee) * h_generator();
+            let matrix: Vec<Vec<EdwardsPoint>> = (0..RING)
+                .map(|j| {
+                    let mut column: Vec<EdwardsPoint> =
+                        rings.iter().map(|ring| ring[j].key).collect();
+                    column.push(
+                        rings
+                            .iter()
+                            .map(|ring| ring[j].commitment)
+                            .sum::<EdwardsPoint>()
+                            - outputs,
+                    );
+                    column
+                })
+                .collect();
+            let mut xx: Vec<Scalar> = secrets.iter().map(|(x, _)| *x).collect();
+            xx.push(secrets.iter().map(|(_, mask)| mask).sum::<Scalar>() - out_mask_sum);
+            rct.prunable.mlsags = vec![mlsag::sign(&message, &matrix, &xx, real, 2).unwrap()];
+        } else {
+            for ((ring, (x, mask)), pseudo_mask) in rings.iter().zip(&secrets).zip(&pseudo_masks) {
+                let keys: Vec<EdwardsPoint> = ring.iter().map(|m| m.key).collect();
+                let commitments: Vec<EdwardsPoint> = ring.iter().map(|m| m.commitment).collect();
+                let pseudo_out =
+                    ring[real].commitment + (pseudo_mask - mask) * ED25519_BASEPOINT_POINT;
+                let matrix = mlsag::ring_ct_matrix(&keys, &commitments, &pseudo_out).unwrap();
+                let sig =
+                    mlsag::sign(&message, &matrix, &[*x, mask - pseudo_mask], real, 1).unwrap();
+                rct.prunable.mlsags.push(sig);
+            }
+        }
+        (
+            Transaction {
+                rct_signatures: rct,
+                ..tx
+            },
+            rings,
+        )
+    }
+
+    #[test]
+    fn test_mlsag_types() {
+        for rct_type in [RctType::Full, RctType::Simple] {
+            let (tx, rings) = mlsag_tx(rct_type);
+            assert_eq!(verify_rct(&tx, &rings), Ok(()));
+
+            // survives a round trip through the wire format
+            let blob = tx.to_bytes();
+            assert_eq!(parse(&blob), tx);
+
+            let mut wrong_fee = tx.clone();
+            wrong_fee.rct_signatures.base.fee += 1;
+            assert!(verify_rct(&wrong_fee, &rings).is_err());
+
+            let mut swapped = rings.clone();
+            swapped[0][0].key = ED25519_BASEPOINT_POINT;
+            assert_eq!(verify_rct(&tx, &swapped), Err(RctError::Signature(0)));
+
+            let mut moved = rings.clone();
+            moved[1][2].global_index += 1;
+            assert_eq!(verify_rct(&tx, &moved), Err(RctError::RingMismatch(1)));
+
+            let mut bad_range = tx.clone();
+            bad_range.rct_signatures.prunable.range_sigs.swap(0, 1);
+            assert_eq!(verify_rct(&bad_range, &rings), Err(RctError::RangeProof(0)));
+        }
+    }
+
+    #[test]
+    fn test_null() {
+        let rct = RctSig::null();
+        let mut out = Vec::new();
+        rct.serialize(&mut out);
+        assert_eq!(out, [0]);
+        assert_eq!(rct.prunable_hash(), Hash([0; 32]));
+        assert!(RctType::from_u8(7).is_none());
+    }
+
+    #[test]
+    fn test_ecdh_info() {
+        let shared = Scalar::random(&mut OsRng);
+        let other = Scalar::random(&mut OsRng);
+        let mask = Scalar::random(&mut OsRng);
+        let full = EcdhInfo::encode(42, &mask, &shared, RctType::Simple);
+        assert!(matches!(full, EcdhInfo::Full { .. }));
+        let commitment = commit(42, mask).compress();
+        assert_eq!(full.decode(&shared, &commitment), Some((42, mask)));
+        assert_eq!(full.decode(&other, &commitment), None);
+
+        // the short form's mask comes from the shared secret
+        let short_mask = ecdh::commitment_mask(&shared);
+        let short = EcdhInfo::encode(42, &mask, &shared, RctType::BulletproofPlus);
+        assert!(matches!(short, EcdhInfo::Compact(_)));
+        let commitment = commit(42, short_mask).compress();
+        assert_eq!(short.decode(&shared, &commitment), Some((42, short_mask)));
+        assert_eq!(short.decode(&other, &commitment), None);
+    }
+}
@@ -0,0 +1,131 @@
+// src/tx/selection.rs
+//! Which owned outputs a transaction spends.
+//!
+//! A [`TransactionBuilder`](super::TransactionBuilder) given candidates
+//! rather than inputs asks an [`OutputSelector`] for a set that covers the
+//! destinations and the fee, which grows with every input picked.
+use rand::{rngs::OsRng, seq::SliceRandom};
+
+use super::builder::OwnedOutput;
+
+/// Picks outputs to spend
+pub trait OutputSelector {
+    /// Indices into `available` to spend, or None if no choice covers the
+    /// payment. `required(n)` is what `n` picked outputs must add up to:
+    /// the amount sent plus the fee of a transaction with `n` inputs.
+    fn select(
+        &mut self,
+        available: &[OwnedOutput],
+        required: &dyn Fn(usize) -> u64,
+    ) -> Option<Vec<usize>>;
+}
+
+/// Take outputs in `order` until they cover what's required
+fn take_in_order(
+    order: impl IntoIterator<Item = usize>,
+    available: &[OwnedOutput],
+    required: &dyn Fn(usize) -> u64,
+) -> Option<Vec<usize>> {
+    let mut picked = Vec::new();
+    let mut sum = 0u64;
+    for i in order {
+        picked.push(i);
+        sum = sum.saturating_add(available[i].amount);
+        if sum >= required(picked.len()) {
+            return Some(picked);
+        }
+    }
+    None
+}
+
+/// Fewest inputs: the largest outputs first. Keeps fees low but spends
+/// big outputs on small payments, which links them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LargestFirst;
+
+impl OutputSelector for LargestFirst {
+    fn select(
+        &mut self,
+        available: &[OwnedOutput],
+        required: &dyn Fn(usize) -> u64,
+    ) -> Option<Vec<usize>> {
+        let mut order: Vec<usize> = (0..available.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(available[i].amount));
+        take_in_order(order, available, required)
+    }
+}
+
+/// The oldest outputs, by global index, first: old outputs are the ones
+/// decoy selection rarely picks, so spending them early hides them best
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OldestFirst;
+
+impl OutputSelector for OldestFirst {
+    fn select(
+        &mut self,
+        available: &[OwnedOutput],
+        required: &dyn Fn(usize) -> u64,
+    ) -> Option<Vec<usize>> {
+        let mut order: Vec<usize> = (0..available.len()).collect();
+        order.sort_by_key(|&i| available[i].global_index);
+        take_in_order(order, available, required)
+    }
+}
+
+/// Outputs in random order, so the choice says nothing about the wallet
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomSelection;
+
+impl OutputSelector for RandomSelection {
+    fn select(
+        &mut self,
+        available: &[OwnedOutput],
+        required: &dyn Fn(usize) -> u64,
+    ) -> Option<Vec<usize>> {
+        let mut order: Vec<usize> = (0..available.len()).collect();
+        order.shuffle(&mut OsRng);
+        take_in_order(order, available, required)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::{Scalar, constants::ED25519_BASEPOINT_POINT};
+
+    use super::*;
+
+    fn outputs(amounts: &[(u64, u64)]) -> Vec<OwnedOutput> {
+        amounts
+            .iter()
+            .map(|&(global_index, amount)| OwnedOutput {
+                global_index,
+                key: ED25519_BASEPOINT_POINT,
+                amount,
+                mask: Scalar::ONE,
+                tx_public_key: ED25519_BASEPOINT_POINT,
+                output_index: 0,
+                subaddress: (0, 0),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_strategies() {
+        let available = outputs(&[(50, 10), (7, 40), (90, 30), (3, 20)]);
+        // 45 plus 1 per input
+        let required = |n: usize| 45 + n as u64;
+
+        assert_eq!(LargestFirst.select(&available, &required), Some(vec![1, 2]));
+        assert_eq!(OldestFirst.select(&available, &required), Some(vec![3, 1]));
+        let mut picked = RandomSelection.select(&available, &required).unwrap();
+        let sum: u64 = picked.iter().map(|&i| available[i].amount).sum();
+        assert!(sum >= required(picked.len()));
+        picked.sort();
+        picked.dedup();
+        assert!(picked.iter().all(|&i| i < available.len()));
+
+        let too_much = |n: usize| 100 + n as u64;
+        assert_eq!(LargestFirst.select(&available, &too_much), None);
+        assert_eq!(RandomSelection.select(&[], &too_much), None);
+    }
+}
@@ -0,0 +1,387 @@
+// src/tx/transaction.rs
+//! A complete v2 (RingCT) transaction: prefix plus RingCT signatures.
+//!
+//! The transaction id is `Keccak256(prefix_hash || base_hash ||
+//! prunable_hash)`, so it can be computed from a pruned transaction given
+//! the prunable hash alone. Version 1 transactions, signed with a
+//! CryptoNote ring signature per input, are parsed too; their id is the
+//! hash of the whole blob.
+use super::{
+    PrunedTransaction, Reader, TransactionPrefix, TxError, TxIn,
+    rct::{RctSig, RctType},
+};
+use crate::{
+    crypto::{
+        hash::{Hash256, keccak256},
+        ring::cryptonote::RingSignature,
+    },
+    encoding::varint,
+};
+
+/// A Borromean range proof: 64 `s0`, 64 `s1`, `ee` and 64 `Ci`
+const BORROMEAN_SIZE: usize = 32 * (64 * 3 + 1);
+
+/// Size of a Bulletproof, or a Bulletproof+ if `plus`, over `padded`
+/// outputs, log-sized vectors included
+fn proof_size(plus: bool, padded: usize) -> usize {
+    let rounds = padded.trailing_zeros() as usize + 6;
+    32 * (if plus { 6 } else { 9 } + 2 * rounds)
+}
+
+/// Weight added to transactions with more than two outputs, since a
+/// bulletproof costs less to store than it does to verify
+fn clawback(plus: bool, n_outputs: usize) -> usize {
+    if n_outputs <= 2 {
+        return 0;
+    }
+    let padded = n_outputs.next_power_of_two();
+    // a two-output proof, per output
+    let base = proof_size(plus, 2) / 2;
+    (base * padded - proof_size(plus, padded)) * 4 / 5
+}
+
+pub(crate) fn bulletproof_plus_clawback(n_outputs: usize) -> usize {
+    clawback(true, n_outputs)
+}
+
+/// What a v2 transaction's size depends on beyond its prefix. Rings all
+/// have one size and outputs share one aggregated proof, as wallets build
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxShape {
+    pub rct_type: RctType,
+    /// Length of the serialised prefix
+    pub prefix_len: usize,
+    pub n_inputs: usize,
+    pub ring_size: usize,
+    pub n_outputs: usize,
+    pub fee: u64,
+}
+
+impl TxShape {
+    /// Shape of `tx`; only the prefix gets serialised
+    pub fn of(tx: &Transaction) -> Self {
+        let ring_size = match tx.prefix.vin.first() {
+            Some(TxIn::ToKey(input)) => input.ring_size(),
+            _ => 0,
+        };
+        Self {
+            rct_type: tx.rct_signatures.base.rct_type,
+            prefix_len: tx.prefix.to_bytes().len(),
+            n_inputs: tx.prefix.key_images().count(),
+            ring_size,
+            n_outputs: tx.prefix.vout.len(),
+            fee: tx.rct_signatures.base.fee,
+        }
+    }
+
+    /// Blob size of the transaction
+    pub fn size(&self) -> usize {
+        let (n_in, n_out, ring) = (self.n_inputs, self.n_outputs, self.ring_size);
+        let rct = match self.rct_type {
+            RctType::Null => return self.prefix_len + 1,
+            rct_type => rct_type,
+        };
+        // type and fee, then amounts and commitments
+        let amount = if rct.compact_amounts() { 8 } else { 64 };
+        let mut size = self.prefix_len + 1 + varint::encoded_len(self.fee) + n_out * (amount + 32);
+        let padded = n_out.next_power_of_two();
+        // a proof's L and R carry varint lengths of one byte
+        let bulletproof = |plus| 1 + proof_size(plus, padded) + 2;
+        size += match rct {
+            RctType::Null => unreachable!(),
+            // one MLSAG with a column per input plus the commitments
+            RctType::Full => n_out * BORROMEAN_SIZE + ring * (n_in + 1) * 32 + 32,
+            // MLSAGs of two columns, and pseudo-outputs
+            RctType::Simple => n_out * BORROMEAN_SIZE + n_in * (ring * 64 + 32 + 32),
+            // this one counted its proofs in a u32
+            RctType::Bulletproof => 3 + bulletproof(false) + n_in * (ring * 64 + 32 + 32),
+            RctType::Bulletproof2 => bulletproof(false) + n_in * (ring * 64 + 32 + 32),
+            // s per member, c1 and D, and pseudo-outputs
+            RctType::Clsag => bulletproof(false) + n_in * 32 * (ring + 3),
+            RctType::BulletproofPlus => bulletproof(true) + n_in * 32 * (ring + 3),
+        };
+        size
+    }
+}
+
+/// Weight of a transaction of `shape`: its size plus the bulletproof
+/// clawback, without building or serialising it
+pub fn weight(shape: &TxShape) -> usize {
+    shape.size() + rct_clawback(shape.rct_type, shape.n_outputs)
+}
+
+/// Clawback for `n_outputs` under `rct_type`, zero for Borromean proofs
+fn rct_clawback(rct_type: RctType, n_outputs: usize) -> usize {
+    match rct_type {
+        RctType::Bulletproof | RctType::Bulletproof2 | RctType::Clsag => clawback(false, n_outputs),
+        RctType::BulletproofPlus => clawback(true, n_outputs),
+        RctType::Null | RctType::Full | RctType::Simple => 0,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub prefix: TransactionPrefix,
+    /// Per ring input, its CryptoNote ring signature; v1 only
+    pub signatures: Vec<RingSignature>,
+    /// RingCT signatures; null in v1
+    pub rct_signatures: RctSig,
+}
+
+impl Transaction {
+    /// Parse a whole transaction blob, v1 or v2
+    pub fn from_blob(data: &[u8]) -> Result<Self, TxError> {
+        let mut r = Reader::new(data);
+        let tx = Self::read(&mut r)?;
+        r.finish()?;
+        Ok(tx)
+    }
+
+    pub(crate) fn read(r: &mut Reader<'_>) -> Result<Self, TxError> {
+        let prefix = TransactionPrefix::read(r)?;
+        let mut signatures = Vec::new();
+        let mut rct_signatures = RctSig::null();
+        match prefix.version {
+            1 => {
+                for input in &prefix.vin {
+                    if let TxIn::ToKey(input) = input {
+                        let bytes = r.take(64 * input.ring_size())?;
+                        let signature =
+                            RingSignature::from_bytes(bytes).ok_or(TxError::InvalidScalar)?;
+                        signatures.push(signature);
+                    }
+                }
+            }
+            2 => rct_signatures = RctSig::read(r, &prefix)?,
+            v => return Err(TxError::UnknownVersion(v)),
+        }
+        Ok(Self {
+            prefix,
+            signatures,
+            rct_signatures,
+        })
+    }
+
+    /// [`from_blob`](Self::from_blob) on a hex string, as daemons return
+    /// transactions
+    pub fn from_hex(s: &str) -> Result<Self, TxError> {
+        let blob = hex::decode(s.trim()).map_err(|_| TxError::InvalidHex)?;
+        Self::from_blob(&blob)
+    }
+
+    /// Append the consensus encoding to `out`
+    pub fn serialize(&self, out: &mut Vec<u8>) {
+        self.prefix.serialize(out);
+        if self.prefix.version == 1 {
+            for signature in &self.signatures {
+                out.extend_from_slice(&signature.to_bytes());
+            }
+        } else {
+            self.rct_signatures.serialize(out);
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.serialize(&mut out);
+        out
+    }
+
+    /// Transaction id: the hash of the blob for v1
+    pub fn hash(&self) -> Hash256 {
+        if self.prefix.version == 1 {
+            return keccak256(&self.to_bytes());
+        }
+        let mut data = self.prefix.prefix_hash().to_bytes().to_vec();
+        data.extend_from_slice(self.rct_signatures.base_hash().as_bytes());
+        data.extend_from_slice(self.rct_signatures.prunable_hash().as_bytes());
+        keccak256(&data)
+    }
+
+    /// The transaction without its prunable data
+    pub fn prune(&self) -> PrunedTransaction {
+        PrunedTransaction {
+            prefix: self.prefix.clone(),
+            rct_base: self.rct_signatures.base.clone(),
+            prunable_hash: self.rct_signatures.prunable_hash(),
+        }
+    }
+
+    /// What [`Self::prune`] drops, as `get_transactions` serves it in
+    /// `prunable_as_hex`: the ring signatures of v1, the proofs and
+    /// signatures of v2
+    pub fn prunable_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if self.prefix.version == 1 {
+            for signature in &self.signatures {
+                out.extend_from_slice(&signature.to_bytes());
+            }
+        } else {
+            let rct = &self.rct_signatures;
+            rct.prunable.serialize(rct.base.rct_type, &mut out);
+        }
+        out
+    }
+
+    /// Weight the fee is charged on: the blob size, plus the bulletproof
+    /// clawback for transactions with more than two outputs
+    pub fn weight(&self) -> usize {
+        let n_outputs = self.prefix.vout.len();
+        self.to_bytes().len() + rct_clawback(self.rct_signatures.base.rct_type, n_outputs)
+    }
+
+    /// Fee paid: the RingCT fee, what a v1 transaction's inputs hold beyond
+    /// its outputs, or nothing for a coinbase. None if the v1 amounts
+    /// don't add up.
+    pub fn fee(&self) -> Option<u64> {
+        if self.prefix.is_coinbase() {
+            return Some(0);
+        }
+        if self.prefix.version != 1 {
+            return Some(self.rct_signatures.base.fee);
+        }
+        let mut inputs = self.prefix.vin.iter().map(|input| match input {
+            TxIn::ToKey(input) => input.amount,
+            TxIn::Gen(_) => 0,
+        });
+        let mut outputs = self.prefix.vout.iter().map(|output| output.amount);
+        let inputs = inputs.try_fold(0u64, u64::checked_add)?;
+        inputs.checked_sub(outputs.try_fold(0u64, u64::checked_add)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::{EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT};
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::address::Address;
+    use crate::crypto::{cn::generate_key_image, keys::WalletKeys, ring::cryptonote};
+    use crate::network::Network;
+    use crate::tx::{
+        TransactionBuilder, TxInToKey, TxOut,
+        builder::tests::{check, with_chain},
+        prefix::tests::{MINER_TX_V1, RCT_TX_V2},
+    };
+
+    #[test]
+    fn test_clawback() {
+        assert_eq!(bulletproof_plus_clawback(2), 0);
+        // 3 outputs prove like 4: a 4-output proof has 8 L/R rounds
+        assert_eq!(bulletproof_plus_clawback(3), (320 * 4 - 32 * 22) * 4 / 5);
+        assert_eq!(bulletproof_plus_clawback(16), (320 * 16 - 32 * 26) * 4 / 5);
+    }
+
+    #[test]
+    fn test_weight_from_shape() {
+        let blob = hex::decode(RCT_TX_V2).unwrap();
+        let tx = Transaction::from_blob(&blob).unwrap();
+        let shape = TxShape::of(&tx);
+        assert_eq!(shape.rct_type, RctType::Bulletproof2);
+        assert_eq!(shape.size(), blob.len());
+        assert_eq!(weight(&shape), tx.weight());
+
+        // bulletproof types before Bulletproof+ claw back too
+        let three = TxShape {
+            n_outputs: 3,
+            ..shape
+        };
+        assert_eq!(
+            weight(&three),
+            three.size() + (368 * 4 - 32 * (9 + 16)) * 4 / 5
+        );
+
+        let keys = WalletKeys::generate();
+        let (mut chain, outputs) = with_chain(&keys, &[5_000_000_000, 7]);
+        for n in 1..4 {
+            let mut builder = TransactionBuilder::new(&keys, Network::Mainnet)
+                .input(outputs[0].clone())
+                .input(outputs[1].clone());
+            for i in 0..n {
+                builder =
+                    builder.destination(Address::subaddress(&keys, Network::Mainnet, 0, i), 1);
+            }
+            let pending = builder.build(&mut chain).unwrap();
+            check(&pending, &chain);
+            let shape = TxShape::of(&pending.tx);
+            assert_eq!(shape.size(), pending.tx.to_bytes().len());
+            assert_eq!(weight(&shape), pending.tx.weight());
+        }
+
+        let coinbase = TxShape {
+            rct_type: RctType::Null,
+            ..shape
+        };
+        assert_eq!(coinbase.size(), shape.prefix_len + 1);
+    }
+
+    #[test]
+    fn test_from_blob() {
+        let tx = Transaction::from_hex(RCT_TX_V2).unwrap();
+        assert_eq!(tx.rct_signatures.rct_type(), RctType::Bulletproof2);
+        assert!(tx.signatures.is_empty());
+        assert_eq!(hex::encode(tx.to_bytes()), RCT_TX_V2);
+        assert_eq!(
+            hex::encode(tx.hash().as_bytes()),
+            "5a420317e377d3d95b652fb93e65cfe97ef7d89e04be329a2ca94e73ec57b74e"
+        );
+
+        // a v1 coinbase is all prefix
+        let blob = hex::decode(MINER_TX_V1).unwrap();
+        let miner = Transaction::from_blob(&blob).unwrap();
+        assert!(miner.prefix.is_coinbase());
+        assert!(miner.signatures.is_empty());
+        assert_eq!(miner.to_bytes(), blob);
+        assert_eq!(miner.hash(), keccak256(&blob));
+
+        // a v1 spend: a ring signature per input after the prefix
+        let x = Scalar::random(&mut OsRng);
+        let mut ring: Vec<EdwardsPoint> = (0..3)
+            .map(|_| Scalar::random(&mut OsRng) * ED25519_BASEPOINT_POINT)
+            .collect();
+        ring[1] = x * ED25519_BASEPOINT_POINT;
+        let key_image = generate_key_image(&x, &ring[1]);
+        let prefix = TransactionPrefix {
+            version: 1,
+            unlock_time: 0,
+            vin: vec![TxIn::ToKey(TxInToKey::from_absolute(
+                1_000_000,
+                &[4, 9, 20],
+                key_image,
+            ))],
+            vout: vec![TxOut::to_key(900_000, ring[0].compress())],
+            extra: vec![1; 33],
+        };
+        let hash = prefix.prefix_hash().to_bytes();
+        let signature = cryptonote::sign(&hash, &key_image, &ring, &x, 1).unwrap();
+        let spend = Transaction {
+            prefix,
+            signatures: vec![signature],
+            rct_signatures: RctSig::null(),
+        };
+        let blob = spend.to_bytes();
+        let parsed = Transaction::from_blob(&blob).unwrap();
+        assert_eq!(parsed, spend);
+        assert!(cryptonote::verify(
+            &hash,
+            &key_image,
+            &ring,
+            &parsed.signatures[0]
+        ));
+        assert_eq!(parsed.weight(), blob.len());
+        assert_eq!(parsed.fee(), Some(100_000));
+        assert_eq!(miner.fee(), Some(0));
+        assert_eq!(tx.fee(), Some(tx.rct_signatures.base.fee));
+
+        assert_eq!(
+            Transaction::from_blob(&blob[..blob.len() - 1]),
+            Err(TxError::Truncated)
+        );
+        let mut v3 = blob.clone();
+        v3[0] = 3;
+        assert_eq!(Transaction::from_blob(&v3), Err(TxError::UnknownVersion(3)));
+        assert_eq!(Transaction::from_hex("02zz"), Err(TxError::InvalidHex));
+    }
+}
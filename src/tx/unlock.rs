@@ -0,0 +1,218 @@
+// src/tx/unlock.rs
+//! When outputs become spendable.
+//!
+//! Every output waits [`SPENDABLE_AGE`] blocks, a coinbase output
+//! [`COINBASE_MATURITY`], and both wait for their transaction's unlock
+//! time, a height or a Unix timestamp. Heights and timestamps here are
+//! those of the block the spend would go in: the chain height, and about
+//! now.
+use super::builder::OwnedOutput;
+
+/// Blocks an output waits before it may be spent
+pub const SPENDABLE_AGE: u64 = 10;
+/// Blocks a coinbase output waits before it may be spent
+pub const COINBASE_MATURITY: u64 = 60;
+/// Unlock times below this are block heights, above it Unix timestamps
+pub const MAX_BLOCK_NUMBER: u64 = 500_000_000;
+
+/// A transaction's unlock time, decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnlockTime {
+    None,
+    /// Spendable from the block at this height
+    Height(u64),
+    /// Spendable from blocks mined at this Unix time
+    Timestamp(u64),
+}
+
+impl UnlockTime {
+    pub fn from_raw(unlock_time: u64) -> Self {
+        match unlock_time {
+            0 => UnlockTime::None,
+            t if t < MAX_BLOCK_NUMBER => UnlockTime::Height(t),
+            t => UnlockTime::Timestamp(t),
+        }
+    }
+
+    /// Whether it has passed for the block at `height`, mined around
+    /// `timestamp`
+    pub fn has_passed(self, height: u64, timestamp: u64) -> bool {
+        match self {
+            UnlockTime::None => true,
+            UnlockTime::Height(h) => h <= height,
+            UnlockTime::Timestamp(t) => t <= timestamp,
+        }
+    }
+}
+
+/// Whether an output can be spent yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockState {
+    Unlocked,
+    /// Too young: spendable from the block at this height
+    Maturing(u64),
+    /// Held by its transaction's unlock time
+    Locked(UnlockTime),
+}
+
+/// What decides when an output unlocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputLock {
+    /// Height of the block that created it
+    pub height: u64,
+    /// Its transaction's unlock time
+    pub unlock_time: u64,
+    /// Created by a miner transaction
+    pub coinbase: bool,
+}
+
+impl OutputLock {
+    /// State for a spend in the block at `height`, mined around
+    /// `timestamp`
+    pub fn state(&self, height: u64, timestamp: u64) -> LockState {
+        let maturity = match self.coinbase {
+            true => COINBASE_MATURITY,
+            false => SPENDABLE_AGE,
+        };
+        let mature = self.height.saturating_add(maturity);
+        if mature > height {
+            return LockState::Maturing(mature);
+        }
+        match UnlockTime::from_raw(self.unlock_time) {
+            t if t.has_passed(height, timestamp) => LockState::Unlocked,
+            t => LockState::Locked(t),
+        }
+    }
+
+    pub fn is_unlocked(&self, height: u64, timestamp: u64) -> bool {
+        self.state(height, timestamp) == LockState::Unlocked
+    }
+}
+
+/// Amounts owned, and how much of them can be spent now
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Balance {
+    pub total: u64,
+    pub unlocked: u64,
+}
+
+impl Balance {
+    pub fn locked(&self) -> u64 {
+        self.total - self.unlocked
+    }
+
+    /// Add `amount`, and to the unlocked part if `unlocked`
+    pub fn credit(&mut self, amount: u64, unlocked: bool) {
+        self.total = self.total.saturating_add(amount);
+        if unlocked {
+            self.unlocked = self.unlocked.saturating_add(amount);
+        }
+    }
+}
+
+/// Balance of `outputs` for a spend in the block at `height`, mined around
+/// `timestamp`
+pub fn balance(outputs: &[(OwnedOutput, OutputLock)], height: u64, timestamp: u64) -> Balance {
+    let mut balance = Balance::default();
+    for (output, lock) in outputs {
+        balance.credit(output.amount, lock.is_unlocked(height, timestamp));
+    }
+    balance
+}
+
+/// The outputs coin selection may pick from: those unlocked for a spend
+/// in the block at `height`, mined around `timestamp`
+pub fn unlocked_outputs(
+    outputs: &[(OwnedOutput, OutputLock)],
+    height: u64,
+    timestamp: u64,
+) -> Vec<OwnedOutput> {
+    outputs
+        .iter()
+        .filter(|(_, lock)| lock.is_unlocked(height, timestamp))
+        .map(|(output, _)| output.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::keys::WalletKeys;
+    use crate::tx::builder::tests::with_chain;
+
+    #[test]
+    fn test_lock_state() {
+        let lock = OutputLock {
+            height: 100,
+            unlock_time: 0,
+            coinbase: false,
+        };
+        assert_eq!(lock.state(109, 0), LockState::Maturing(110));
+        assert_eq!(lock.state(110, 0), LockState::Unlocked);
+
+        let coinbase = OutputLock {
+            coinbase: true,
+            unlock_time: 160,
+            ..lock
+        };
+        assert_eq!(coinbase.state(150, 0), LockState::Maturing(160));
+        assert!(coinbase.is_unlocked(160, 0));
+
+        let by_height = OutputLock {
+            unlock_time: 500,
+            ..lock
+        };
+        assert_eq!(
+            by_height.state(499, 0),
+            LockState::Locked(UnlockTime::Height(500))
+        );
+        assert!(by_height.is_unlocked(500, 0));
+
+        let by_time = OutputLock {
+            unlock_time: 1_700_000_000,
+            ..lock
+        };
+        assert_eq!(
+            by_time.state(200, 1_600_000_000),
+            LockState::Locked(UnlockTime::Timestamp(1_700_000_000))
+        );
+        assert!(by_time.is_unlocked(200, 1_700_000_000));
+    }
+
+    #[test]
+    fn test_balance() {
+        let keys = WalletKeys::generate();
+        let (_, outputs) = with_chain(&keys, &[5, 6, 7]);
+        let locks = [(90, false), (95, false), (90, true)];
+        let owned: Vec<_> = outputs
+            .into_iter()
+            .zip(locks)
+            .map(|(output, (height, coinbase))| {
+                let lock = OutputLock {
+                    height,
+                    unlock_time: 0,
+                    coinbase,
+                };
+                (output, lock)
+            })
+            .collect();
+
+        let now = balance(&owned, 100, 0);
+        assert_eq!(
+            now,
+            Balance {
+                total: 18,
+                unlocked: 5
+            }
+        );
+        assert_eq!(now.locked(), 13);
+        assert_eq!(balance(&owned, 105, 0).unlocked, 11);
+        assert_eq!(balance(&owned, 150, 0).unlocked, 18);
+
+        let spendable = unlocked_outputs(&owned, 105, 0);
+        assert_eq!(
+            spendable.iter().map(|o| o.amount).collect::<Vec<_>>(),
+            [5, 6]
+        );
+    }
+}
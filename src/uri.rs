@@ -0,0 +1,294 @@
+// src/uri.rs
+//! `monero:` payment request URIs.
+//!
+//! The de-facto format wallets exchange (usually as a QR code) is
+//! `monero:<address>?tx_amount=<XMR>&tx_description=<text>&recipient_name=<text>`.
+//! The amount is a decimal XMR value, text is percent-encoded, and unknown
+//! parameters are ignored so newer wallets can add their own.
+use std::{fmt, str::FromStr};
+
+use crate::address::{Address, AddressError};
+
+pub const SCHEME: &str = "monero";
+
+/// Piconero (atomic units) per XMR
+const ATOMIC_PER_XMR: u64 = 1_000_000_000_000;
+const DECIMALS: usize = 12;
+
+/// Why a URI was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UriError {
+    /// Doesn't start with `monero:`
+    MissingScheme,
+    Address(AddressError),
+    /// `tx_amount` isn't a decimal XMR value in range
+    InvalidAmount(String),
+    /// Bad `%XX` escape or escapes that don't decode to UTF-8
+    InvalidEncoding,
+    /// The same parameter given twice
+    DuplicateParameter(String),
+}
+
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UriError::MissingScheme => write!(f, "not a {SCHEME}: URI"),
+            UriError::Address(e) => write!(f, "invalid address: {e}"),
+            UriError::InvalidAmount(a) => write!(f, "invalid amount {a:?}"),
+            UriError::InvalidEncoding => write!(f, "invalid percent-encoding"),
+            UriError::DuplicateParameter(p) => write!(f, "parameter {p} given twice"),
+        }
+    }
+}
+
+impl std::error::Error for UriError {}
+
+impl From<AddressError> for UriError {
+    fn from(e: AddressError) -> Self {
+        UriError::Address(e)
+    }
+}
+
+/// A payment request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoneroUri {
+    pub address: Address,
+    /// Requested amount in atomic units
+    pub amount: Option<u64>,
+    pub description: Option<String>,
+    pub recipient_name: Option<String>,
+}
+
+impl MoneroUri {
+    /// Request for an unspecified amount to `address`
+    pub fn new(address: Address) -> Self {
+        Self {
+            address,
+            amount: None,
+            description: None,
+            recipient_name: None,
+        }
+    }
+
+    pub fn amount(mut self, atomic: u64) -> Self {
+        self.amount = Some(atomic);
+        self
+    }
+
+    pub fn description(mut self, text: impl Into<String>) -> Self {
+        self.description = Some(text.into());
+        self
+    }
+
+    pub fn recipient_name(mut self, name: impl Into<String>) -> Self {
+        self.recipient_name = Some(name.into());
+        self
+    }
+}
+
+impl fmt::Display for MoneroUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{SCHEME}:{}", self.address)?;
+        let mut sep = '?';
+        let mut param = |f: &mut fmt::Formatter<'_>, key: &str, value: &str| {
+            let r = write!(f, "{sep}{key}={value}");
+            sep = '&';
+            r
+        };
+        if let Some(amount) = self.amount {
+            param(f, "tx_amount", &format_amount(amount))?;
+        }
+        if let Some(text) = &self.description {
+            param(f, "tx_description", &percent_encode(text))?;
+        }
+        if let Some(name) = &self.recipient_name {
+            param(f, "recipient_name", &percent_encode(name))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MoneroUri {
+    type Err = UriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s
+            .get(..SCHEME.len() + 1)
+            .filter(|p| p.eq_ignore_ascii_case("monero:"))
+            .map(|_| &s[SCHEME.len() + 1..])
+            .ok_or(UriError::MissingScheme)?;
+        let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut uri = MoneroUri::new(address.parse()?);
+
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let slot = match key {
+                "tx_amount" => {
+                    if uri.amount.is_some() {
+                        return Err(UriError::DuplicateParameter(key.into()));
+                    }
+                    uri.amount = Some(parse_amount(value)?);
+                    continue;
+                }
+                "tx_description" => &mut uri.description,
+                "recipient_name" => &mut uri.recipient_name,
+                _ => continue,
+            };
+            if slot.is_some() {
+                return Err(UriError::DuplicateParameter(key.into()));
+            }
+            *slot = Some(percent_decode(value)?);
+        }
+        Ok(uri)
+    }
+}
+
+/// Atomic units as a decimal XMR string without trailing zeros
+pub fn format_amount(atomic: u64) -> String {
+    let whole = atomic / ATOMIC_PER_XMR;
+    let frac = atomic % ATOMIC_PER_XMR;
+    if frac == 0 {
+        return whole.to_string();
+    }
+    let digits = format!("{frac:0DECIMALS$}");
+    format!("{whole}.{}", digits.trim_end_matches('0'))
+}
+
+/// Decimal XMR string to atomic units; at most 12 decimals
+pub fn parse_amount(text: &str) -> Result<u64, UriError> {
+    let invalid = || UriError::InvalidAmount(text.to_string());
+    let (whole, frac) = text.split_once('.').unwrap_or((text, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && frac.is_empty())
+        || frac.len() > DECIMALS
+        || !all_digits(whole)
+        || !all_digits(frac)
+    {
+        return Err(invalid());
+    }
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| invalid())?
+    };
+    let frac: u64 = format!("{frac:0<DECIMALS$}").parse().unwrap();
+    whole
+        .checked_mul(ATOMIC_PER_XMR)
+        .and_then(|n| n.checked_add(frac))
+        .ok_or_else(invalid)
+}
+
+/// Escape everything but RFC 3986 unreserved characters
+fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+fn percent_decode(text: &str) -> Result<String, UriError> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+                .ok_or(UriError::InvalidEncoding)?;
+            // two hex digits are ASCII and fit a byte
+            out.push(u8::from_str_radix(std::str::from_utf8(hex).unwrap(), 16).unwrap());
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|_| UriError::InvalidEncoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "42ey1afDFnn4886T7196doS9GPMzexD9gXpsZJDwVjeRVdFCSoHnv7KPbBeGpzJBzHRCAs9\
+                           UxqeoyFQMYbqSWYTfJJQAWDm";
+
+    #[test]
+    fn test_roundtrip() {
+        let address: Address = ADDRESS.parse().unwrap();
+        let uri = MoneroUri::new(address)
+            .amount(1_500_000_000_000)
+            .description("Coffee & cake")
+            .recipient_name("Café Ünï");
+        let text = uri.to_string();
+        assert_eq!(
+            text,
+            format!(
+                "monero:{ADDRESS}?tx_amount=1.5&tx_description=Coffee%20%26%20cake\
+                 &recipient_name=Caf%C3%A9%20%C3%9Cn%C3%AF"
+            )
+        );
+        assert_eq!(text.parse::<MoneroUri>().unwrap(), uri);
+
+        let bare = MoneroUri::new(address);
+        assert_eq!(bare.to_string(), format!("monero:{ADDRESS}"));
+        assert_eq!(bare.to_string().parse::<MoneroUri>().unwrap(), bare);
+    }
+
+    #[test]
+    fn test_parse() {
+        let uri: MoneroUri = format!("MONERO:{ADDRESS}?tx_payment_id=00&tx_amount=.25&foo")
+            .parse()
+            .unwrap();
+        assert_eq!(uri.amount, Some(250_000_000_000));
+        assert_eq!(uri.description, None);
+
+        assert_eq!(ADDRESS.parse::<MoneroUri>(), Err(UriError::MissingScheme));
+        assert!(matches!(
+            "monero:4abc".parse::<MoneroUri>(),
+            Err(UriError::Address(_))
+        ));
+        assert_eq!(
+            format!("monero:{ADDRESS}?tx_description=a&tx_description=b").parse::<MoneroUri>(),
+            Err(UriError::DuplicateParameter("tx_description".into()))
+        );
+        assert_eq!(
+            format!("monero:{ADDRESS}?recipient_name=%E9").parse::<MoneroUri>(),
+            Err(UriError::InvalidEncoding)
+        );
+        assert_eq!(
+            format!("monero:{ADDRESS}?recipient_name=%+f").parse::<MoneroUri>(),
+            Err(UriError::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn test_amounts() {
+        for (text, atomic) in [
+            ("0", 0),
+            ("1", ATOMIC_PER_XMR),
+            ("0.000000000001", 1),
+            ("18446744.073709551615", u64::MAX),
+        ] {
+            assert_eq!(parse_amount(text), Ok(atomic));
+            assert_eq!(format_amount(atomic), text);
+        }
+        assert_eq!(parse_amount("2."), Ok(2 * ATOMIC_PER_XMR));
+        for bad in [
+            "",
+            ".",
+            "1.0000000000001",
+            "-1",
+            "1e3",
+            "18446744.073709551616",
+        ] {
+            assert_eq!(parse_amount(bad), Err(UriError::InvalidAmount(bad.into())));
+        }
+    }
+}
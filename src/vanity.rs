@@ -0,0 +1,321 @@
+// src/vanity.rs
+//! Vanity addresses: grind wallets until the address starts with a pattern.
+//!
+//! Only the spend key shows up in the first 44 characters of an address, so
+//! workers step the spend secret by one and the public key by `G` instead of
+//! doing a full scalar multiplication per attempt, and only derive the view
+//! key for the winner. The result is an ordinary deterministic wallet; its
+//! seed restores it like any other.
+//!
+//! Each extra character costs about 58 times more attempts.
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
+};
+
+use curve25519_dalek::{EdwardsPoint, Scalar, constants::ED25519_BASEPOINT_POINT};
+use rand::rngs::OsRng;
+
+use crate::address::Address;
+use crate::crypto::keys::WalletKeys;
+use crate::encoding::{base58, varint};
+use crate::network::Network;
+
+/// Characters fixed by the network prefix and spend key alone
+pub const MAX_PATTERN_LEN: usize = 44;
+/// Attempts between checks of the stop flag
+const BATCH: u64 = 256;
+/// How often the searching thread looks at the cancel flag
+const POLL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VanityError {
+    /// Not in the base58 alphabet
+    InvalidCharacter(char),
+    /// Longer than [`MAX_PATTERN_LEN`]
+    TooLong(usize),
+    /// No address on the network can start this way (e.g. mainnet is `4[0-9AB]`)
+    Unreachable(String),
+}
+
+impl fmt::Display for VanityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VanityError::InvalidCharacter(c) => write!(f, "{c:?} is not a base58 character"),
+            VanityError::TooLong(n) => {
+                write!(f, "pattern of {n} characters exceeds {MAX_PATTERN_LEN}")
+            }
+            VanityError::Unreachable(p) => write!(f, "no address can start with {p:?}"),
+        }
+    }
+}
+
+impl std::error::Error for VanityError {}
+
+/// Snapshot handed to the progress callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub attempts: u64,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    pub fn per_second(&self) -> f64 {
+        self.attempts as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+type ProgressFn<'a> = Box<dyn FnMut(Progress) + 'a>;
+
+/// A configured search; [`search`] covers the common case
+pub struct VanitySearch<'a> {
+    pattern: String,
+    network: Network,
+    threads: usize,
+    interval: Duration,
+    progress: Option<ProgressFn<'a>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl<'a> VanitySearch<'a> {
+    /// Search for addresses starting with `pattern`, including the network's
+    /// leading character
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            network: Network::Mainnet,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            interval: Duration::from_secs(1),
+            progress: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Worker threads; defaults to one per core
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Called on the searching thread roughly every `interval`
+    pub fn on_progress(mut self, interval: Duration, callback: impl FnMut(Progress) + 'a) -> Self {
+        self.interval = interval;
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Flag another thread can set to stop the search
+    pub fn cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel = flag;
+        self
+    }
+
+    /// Check the pattern can match anything on the chosen network
+    pub fn validate(&self) -> Result<(), VanityError> {
+        if let Some(c) = self.pattern.chars().find(|&c| !is_base58(c)) {
+            return Err(VanityError::InvalidCharacter(c));
+        }
+        if self.pattern.len() > MAX_PATTERN_LEN {
+            return Err(VanityError::TooLong(self.pattern.len()));
+        }
+        // the first 11 characters encode the prefix byte and 7 key bytes;
+        // base58 preserves order, so they must fall between these bounds
+        let mut lowest = prefix_bytes(self.network);
+        let mut highest = lowest.clone();
+        lowest.resize(8, 0x00);
+        highest.resize(8, 0xff);
+        let (lowest, highest) = (base58::encode(&lowest), base58::encode(&highest));
+        let n = self.pattern.len().min(lowest.len());
+        let head = &self.pattern[..n];
+        if head < &lowest[..n] || head > &highest[..n] {
+            return Err(VanityError::Unreachable(self.pattern.clone()));
+        }
+        Ok(())
+    }
+
+    /// Grind until a match, or None if cancelled
+    pub fn run(mut self) -> Result<Option<WalletKeys>, VanityError> {
+        self.validate()?;
+        let start = Instant::now();
+        let attempts = AtomicU64::new(0);
+        let stop = AtomicBool::new(false);
+        let (found_tx, found_rx) = mpsc::channel();
+        let prefix = prefix_bytes(self.network);
+
+        let spend = std::thread::scope(|scope| {
+            for _ in 0..self.threads {
+                let found_tx = found_tx.clone();
+                let (pattern, prefix, stop, attempts) = (&self.pattern, &prefix, &stop, &attempts);
+                scope.spawn(move || {
+                    if let Some(secret) = grind(pattern, prefix, stop, attempts) {
+                        let _ = found_tx.send(secret);
+                    }
+                });
+            }
+            drop(found_tx);
+
+            let mut next_report = start + self.interval;
+            let result = loop {
+                match found_rx.recv_timeout(POLL) {
+                    Ok(secret) => break Some(secret),
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break None,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                }
+                if self.cancel.load(Ordering::Relaxed) {
+                    break None;
+                }
+                if let Some(progress) = self.progress.as_mut()
+                    && Instant::now() >= next_report
+                {
+                    next_report += self.interval;
+                    progress(Progress {
+                        attempts: attempts.load(Ordering::Relaxed),
+                        elapsed: start.elapsed(),
+                    });
+                }
+            };
+            stop.store(true, Ordering::Relaxed);
+            result
+        });
+
+        Ok(spend.map(|secret| {
+            let keys = WalletKeys::from_spend_secret(secret);
+            debug_assert!(
+                Address::from_keys(&keys, self.network)
+                    .to_string()
+                    .starts_with(&self.pattern)
+            );
+            keys
+        }))
+    }
+}
+
+/// Wallet whose mainnet address starts with `pattern`, using `threads` workers
+pub fn search(pattern: &str, threads: usize) -> Result<Option<WalletKeys>, VanityError> {
+    VanitySearch::new(pattern).threads(threads).run()
+}
+
+fn is_base58(c: char) -> bool {
+    c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l')
+}
+
+fn prefix_bytes(network: Network) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(varint::MAX_LEN);
+    varint::write(&mut bytes, network.address_prefix());
+    bytes
+}
+
+/// One worker: walk `s, s+1, ...` from a random start
+fn grind(pattern: &str, prefix: &[u8], stop: &AtomicBool, attempts: &AtomicU64) -> Option<Scalar> {
+    let mut secret = Scalar::random(&mut OsRng);
+    let mut public: EdwardsPoint = secret * ED25519_BASEPOINT_POINT;
+    // the first 32 bytes always encode to exactly 44 characters
+    let mut buf = [0u8; 32];
+    buf[..prefix.len()].copy_from_slice(prefix);
+    let key_len = 32 - prefix.len();
+
+    loop {
+        for _ in 0..BATCH {
+            buf[prefix.len()..].copy_from_slice(&public.compress().as_bytes()[..key_len]);
+            if base58::encode(&buf).starts_with(pattern) {
+                attempts.fetch_add(BATCH, Ordering::Relaxed);
+                return Some(secret);
+            }
+            secret += Scalar::ONE;
+            public += ED25519_BASEPOINT_POINT;
+        }
+        attempts.fetch_add(BATCH, Ordering::Relaxed);
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let keys = search("44", 2).unwrap().unwrap();
+        assert!(
+            Address::from_keys(&keys, Network::Mainnet)
+                .to_string()
+                .starts_with("44")
+        );
+
+        let keys = VanitySearch::new("5A")
+            .network(Network::Stagenet)
+            .threads(1)
+            .run()
+            .unwrap()
+            .unwrap();
+        assert!(
+            Address::from_keys(&keys, Network::Stagenet)
+                .to_string()
+                .starts_with("5A")
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(
+            search("40", 1).err(),
+            Some(VanityError::InvalidCharacter('0'))
+        );
+        assert_eq!(
+            search("4l", 1).err(),
+            Some(VanityError::InvalidCharacter('l'))
+        );
+        assert_eq!(
+            search("5", 1).err(),
+            Some(VanityError::Unreachable("5".into()))
+        );
+        assert_eq!(
+            search("4C", 1).err(),
+            Some(VanityError::Unreachable("4C".into()))
+        );
+        assert_eq!(
+            search(&"4".repeat(45), 1).err(),
+            Some(VanityError::TooLong(45))
+        );
+        assert!(VanitySearch::new("4B").validate().is_ok());
+        assert!(
+            VanitySearch::new("9")
+                .network(Network::Testnet)
+                .validate()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_cancel_and_progress() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut reports = Vec::new();
+        let flag = cancel.clone();
+        let found = VanitySearch::new("4AAAAAAAAAAAAAAAAAAAAAAAAAAAA")
+            .threads(2)
+            .cancel_flag(cancel)
+            .on_progress(Duration::from_millis(5), |p| {
+                reports.push(p);
+                if reports.len() == 3 {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            })
+            .run()
+            .unwrap();
+        assert!(found.is_none());
+        assert!(reports.len() >= 3);
+        assert!(reports.windows(2).all(|w| w[0].attempts <= w[1].attempts));
+    }
+}
@@ -0,0 +1,651 @@
+// src/wallet/mod.rs
+//! A wallet following the chain.
+//!
+//! [`Wallet`] is fed the chain's blocks in order, each with its
+//! transactions and their outputs' global indices as `get_blocks.bin`
+//! returns them. It picks out the outputs paid to its main address or a
+//! subaddress in the lookahead table, computes each one's key image, and
+//! marks an output spent when a block spends its key image. Blocks a
+//! reorg removes are undone with [`Wallet::detach`]. Two outputs to the
+//! same one-time key share a key image and only one can ever be spent, so
+//! as in wallet2 the wallet keeps the larger.
+//!
+//! The balance counts the unspent outputs, and of those the ones a
+//! transaction in the next block could spend: past the 10-block lock, or
+//! 60 for miner outputs, and their transaction's unlock time.
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+
+use crate::address::Address;
+use crate::block::Block;
+use crate::crypto::{
+    cn::{KeyImage, generate_key_image},
+    hash::Hash256,
+    keys::WalletKeys,
+};
+use crate::network::Network;
+use crate::tx::{Balance, OutputLock, OwnedOutput, Transaction, TxIn, builder::output_secret};
+
+pub mod scan;
+
+pub use scan::scan_transaction;
+
+/// Accounts covered by the subaddress table, as wallet2
+pub const SUBADDRESS_LOOKAHEAD_MAJOR: u32 = 50;
+/// Subaddresses per account covered by the table, as wallet2
+pub const SUBADDRESS_LOOKAHEAD_MINOR: u32 = 200;
+
+/// Why a block couldn't be processed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletError {
+    /// Block whose miner transaction is for another height than the next
+    /// one to scan
+    WrongHeight { height: u64, expected: u64 },
+    /// Transactions given that aren't the block's, in its order
+    TransactionMismatch,
+    /// Global indices that don't match the outputs of transaction `n`,
+    /// the miner transaction being 0
+    OutputIndices(usize),
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletError::WrongHeight { height, expected } => {
+                write!(f, "block at height {height}, expected {expected}")
+            }
+            WalletError::TransactionMismatch => write!(f, "transactions aren't the block's"),
+            WalletError::OutputIndices(n) => {
+                write!(f, "wrong output indices for transaction {n}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WalletError {}
+
+/// An output the wallet received, and whether it's been spent
+#[derive(Debug, Clone)]
+pub struct TransferDetails {
+    pub output: OwnedOutput,
+    pub key_image: KeyImage,
+    /// Transaction that created it
+    pub tx_hash: Hash256,
+    /// Height, unlock time and whether it's a miner output
+    pub lock: OutputLock,
+    /// Height of the block that spent it, and the spending transaction
+    pub spent: Option<(u64, Hash256)>,
+}
+
+impl TransferDetails {
+    pub fn is_spent(&self) -> bool {
+        self.spent.is_some()
+    }
+}
+
+/// Balance of the whole wallet and of each account and subaddress
+/// holding unspent outputs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalletBalance {
+    pub total: u64,
+    pub unlocked: u64,
+    /// By account index
+    pub accounts: BTreeMap<u32, Balance>,
+    /// By `(account, index)`
+    pub subaddresses: BTreeMap<(u32, u32), Balance>,
+}
+
+impl WalletBalance {
+    pub fn locked(&self) -> u64 {
+        self.total - self.unlocked
+    }
+
+    /// Balance of account `major`, zero if it holds nothing
+    pub fn account(&self, major: u32) -> Balance {
+        self.accounts.get(&major).copied().unwrap_or_default()
+    }
+
+    /// Balance of subaddress `(major, minor)`, zero if it holds nothing
+    pub fn subaddress(&self, major: u32, minor: u32) -> Balance {
+        self.subaddresses
+            .get(&(major, minor))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// What processing a block changed, as indices into
+/// [`Wallet::transfers`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockScan {
+    pub received: Vec<usize>,
+    pub spent: Vec<usize>,
+}
+
+/// Keys, the outputs they received and which of those are spent
+pub struct Wallet {
+    keys: WalletKeys,
+    network: Network,
+    /// Subaddress spend key to `(major, minor)`
+    subaddresses: HashMap<CompressedEdwardsY, (u32, u32)>,
+    /// In the order received
+    transfers: Vec<TransferDetails>,
+    /// Index into `transfers` by key image
+    key_images: HashMap<KeyImage, usize>,
+    /// Transfers replaced by a larger output with their key image, with
+    /// their index in `transfers`, in the order replaced
+    superseded: Vec<(usize, TransferDetails)>,
+    /// Next block to process
+    height: u64,
+}
+
+impl Wallet {
+    /// Wallet scanning from the genesis block with wallet2's lookahead
+    pub fn new(keys: WalletKeys, network: Network) -> Self {
+        Self::with_lookahead(
+            keys,
+            network,
+            SUBADDRESS_LOOKAHEAD_MAJOR,
+            SUBADDRESS_LOOKAHEAD_MINOR,
+        )
+    }
+
+    /// Wallet recognising subaddresses `(0..majors, 0..minors)`
+    pub fn with_lookahead(keys: WalletKeys, network: Network, majors: u32, minors: u32) -> Self {
+        let subaddresses = keys.subaddress_table(majors.max(1), minors.max(1));
+        Self {
+            keys,
+            network,
+            subaddresses,
+            transfers: Vec::new(),
+            key_images: HashMap::new(),
+            superseded: Vec::new(),
+            height: 0,
+        }
+    }
+
+    /// Start scanning at `height`, for a wallet restored with no outputs
+    /// below it
+    pub fn with_refresh_height(mut self, height: u64) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn keys(&self) -> &WalletKeys {
+        &self.keys
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn address(&self) -> Address {
+        Address::from_keys(&self.keys, self.network)
+    }
+
+    /// Height of the next block to process
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Every output received, in order
+    pub fn transfers(&self) -> &[TransferDetails] {
+        &self.transfers
+    }
+
+    /// Outputs received and not yet spent
+    pub fn unspent(&self) -> impl Iterator<Item = &TransferDetails> {
+        self.transfers.iter().filter(|t| !t.is_spent())
+    }
+
+    /// Balance for a spend in the next block, mined about now
+    pub fn balance(&self) -> WalletBalance {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.balance_at(now)
+    }
+
+    /// [`Self::balance`] with the next block mined around `timestamp`
+    pub fn balance_at(&self, timestamp: u64) -> WalletBalance {
+        let mut wallet = Balance::default();
+        let mut balance = WalletBalance::default();
+        for transfer in self.unspent() {
+            let (major, minor) = transfer.output.subaddress;
+            let amount = transfer.output.amount;
+            let unlocked = transfer.lock.is_unlocked(self.height, timestamp);
+            wallet.credit(amount, unlocked);
+            balance
+                .accounts
+                .entry(major)
+                .or_default()
+                .credit(amount, unlocked);
+            balance
+                .subaddresses
+                .entry((major, minor))
+                .or_default()
+                .credit(amount, unlocked);
+        }
+        balance.total = wallet.total;
+        balance.unlocked = wallet.unlocked;
+        balance
+    }
+
+    /// The received output with `key_image`
+    pub fn transfer_by_key_image(&self, key_image: &KeyImage) -> Option<&TransferDetails> {
+        self.key_images.get(key_image).map(|&i| &self.transfers[i])
+    }
+
+    /// Process the block at [`Wallet::height`] with its transactions, in
+    /// order, and the global indices of each transaction's outputs, the
+    /// miner transaction's first
+    pub fn process_block(
+        &mut self,
+        block: &Block,
+        txs: &[Transaction],
+        output_indices: &[Vec<u64>],
+    ) -> Result<BlockScan, WalletError> {
+        let height = self.height;
+        if let [TxIn::Gen(input)] = &block.miner_tx.prefix.vin[..]
+            && input.height != height
+        {
+            return Err(WalletError::WrongHeight {
+                height: input.height,
+                expected: height,
+            });
+        }
+        if txs.len() != block.tx_hashes.len()
+            || txs
+                .iter()
+                .zip(&block.tx_hashes)
+                .any(|(tx, h)| tx.hash() != *h)
+        {
+            return Err(WalletError::TransactionMismatch);
+        }
+        let all = std::iter::once(&block.miner_tx).chain(txs);
+        if output_indices.len() != txs.len() + 1 {
+            return Err(WalletError::OutputIndices(
+                output_indices.len().min(txs.len() + 1),
+            ));
+        }
+        if let Some(n) = all
+            .clone()
+            .zip(output_indices)
+            .position(|(tx, indices)| indices.len() != tx.prefix.vout.len())
+        {
+            return Err(WalletError::OutputIndices(n));
+        }
+
+        let mut scan = BlockScan::default();
+        for (tx, indices) in all.zip(output_indices) {
+            self.process_transaction(tx, height, indices, &mut scan);
+        }
+        self.height = height + 1;
+        Ok(scan)
+    }
+
+    fn process_transaction(
+        &mut self,
+        tx: &Transaction,
+        height: u64,
+        global_indices: &[u64],
+        scan: &mut BlockScan,
+    ) {
+        let tx_hash = tx.hash();
+        for key_image in tx.prefix.key_images() {
+            if let Some(&i) = self.key_images.get(key_image)
+                && self.transfers[i].spent.is_none()
+            {
+                self.transfers[i].spent = Some((height, tx_hash));
+                scan.spent.push(i);
+            }
+        }
+        let lock = OutputLock {
+            height,
+            unlock_time: tx.prefix.unlock_time,
+            coinbase: tx.prefix.is_coinbase(),
+        };
+        for output in scan_transaction(&self.keys, &self.subaddresses, tx, global_indices) {
+            let key_image = generate_key_image(&output_secret(&self.keys, &output), &output.key);
+            let transfer = TransferDetails {
+                output,
+                key_image,
+                tx_hash,
+                lock,
+                spent: None,
+            };
+            // a second output with a known key image can only be spent
+            // once (the burning bug): keep whichever is worth more, as
+            // long as the first isn't spent already
+            if let Some(&i) = self.key_images.get(&key_image) {
+                let known = &mut self.transfers[i];
+                if known.is_spent() || known.output.amount >= transfer.output.amount {
+                    continue;
+                }
+                let known = std::mem::replace(known, transfer);
+                self.superseded.push((i, known));
+                scan.received.push(i);
+                continue;
+            }
+            self.key_images.insert(key_image, self.transfers.len());
+            scan.received.push(self.transfers.len());
+            self.transfers.push(transfer);
+        }
+    }
+
+    /// Undo every block from `height` up, as after a reorg to a chain
+    /// forking there
+    pub fn detach(&mut self, height: u64) {
+        if height >= self.height {
+            return;
+        }
+        // bring back what the undone blocks' outputs replaced, latest
+        // first, so every transfer left below `height` is at the front
+        while let Some((i, _)) = self.superseded.last()
+            && self.transfers[*i].lock.height >= height
+        {
+            let (i, transfer) = self.superseded.pop().unwrap();
+            self.transfers[i] = transfer;
+        }
+        let kept = self.transfers.partition_point(|t| t.lock.height < height);
+        self.transfers.truncate(kept);
+        self.key_images = self
+            .transfers
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.key_image, i))
+            .collect();
+        for transfer in &mut self.transfers {
+            if transfer.spent.is_some_and(|(h, _)| h >= height) {
+                transfer.spent = None;
+            }
+        }
+        self.height = height;
+    }
+}
+
+impl fmt::Debug for Wallet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Wallet")
+            .field("keys", &self.keys)
+            .field("network", &self.network)
+            .field("transfers", &self.transfers.len())
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+
+    use super::*;
+    use crate::block::BlockHeader;
+    use crate::chain::template::miner_transaction;
+    use crate::crypto::hash::Hash;
+    use crate::tx::builder::tests::with_chain;
+    use crate::tx::{TransactionBuilder, TxInGen};
+
+    const MONERO: u64 = 1_000_000_000_000;
+
+    fn wallet() -> Wallet {
+        Wallet::with_lookahead(WalletKeys::generate(), Network::Mainnet, 2, 3)
+    }
+
+    /// Block at `height` mining `reward` to `to`, with `txs`
+    fn block(to: &Address, height: u64, reward: u64, txs: &[Transaction]) -> Block {
+        Block {
+            header: BlockHeader {
+                major_version: 16,
+                minor_version: 16,
+                timestamp: 1_700_000_000 + 120 * height,
+                prev_id: Hash([0; 32]),
+                nonce: 0,
+            },
+            miner_tx: miner_transaction(to, height, 16, reward, &[]),
+            tx_hashes: txs.iter().map(Transaction::hash).collect(),
+        }
+    }
+
+    /// Consecutive global indices for the outputs of the block's
+    /// transactions, from `first`
+    fn indices(block: &Block, txs: &[Transaction], first: u64) -> Vec<Vec<u64>> {
+        let mut next = first;
+        std::iter::once(&block.miner_tx)
+            .chain(txs)
+            .map(|tx| {
+                let indices = (next..next + tx.prefix.vout.len() as u64).collect();
+                next += tx.prefix.vout.len() as u64;
+                indices
+            })
+            .collect()
+    }
+
+    /// A transaction from a stranger paying `amount` to `to`
+    fn payment(to: Address, amount: u64) -> Transaction {
+        let sender = WalletKeys::generate();
+        let (mut chain, outputs) = with_chain(&sender, &[10 * MONERO]);
+        TransactionBuilder::new(&sender, Network::Mainnet)
+            .input(outputs[0].clone())
+            .destination(to, amount)
+            .build(&mut chain)
+            .unwrap()
+            .tx
+    }
+
+    #[test]
+    fn test_receive() {
+        let mut wallet = wallet();
+        let stranger = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let subaddress = Address::subaddress(wallet.keys(), Network::Mainnet, 1, 2);
+        let txs = [
+            payment(wallet.address(), 3 * MONERO),
+            payment(stranger, MONERO),
+            payment(subaddress, 2 * MONERO),
+        ];
+        let block = block(&wallet.address(), 0, 5 * MONERO, &txs);
+        let scan = wallet
+            .process_block(&block, &txs, &indices(&block, &txs, 100))
+            .unwrap();
+        assert_eq!(scan.received.len(), 3);
+        assert_eq!(wallet.height(), 1);
+
+        let transfers = wallet.transfers();
+        let coinbase = &transfers[0];
+        assert_eq!(coinbase.output.amount, 5 * MONERO);
+        assert_eq!(coinbase.output.global_index, 100);
+        assert!(coinbase.lock.coinbase);
+        assert_eq!(transfers[1].output.amount, 3 * MONERO);
+        assert_eq!(transfers[1].tx_hash, txs[0].hash());
+        assert_eq!(transfers[2].output.amount, 2 * MONERO);
+        assert_eq!(transfers[2].output.subaddress, (1, 2));
+        assert!(!transfers[2].lock.coinbase);
+        for transfer in transfers {
+            let secret = output_secret(wallet.keys(), &transfer.output);
+            assert_eq!(secret * ED25519_BASEPOINT_POINT, transfer.output.key);
+        }
+
+        // another block for the same height, then one skipping ahead
+        assert_eq!(
+            wallet.process_block(&block, &txs, &indices(&block, &txs, 100)),
+            Err(WalletError::WrongHeight {
+                height: 0,
+                expected: 1
+            })
+        );
+        let empty = self::block(&stranger, 1, MONERO, &[]);
+        assert_eq!(
+            wallet.process_block(&empty, &txs, &indices(&empty, &txs, 0)),
+            Err(WalletError::TransactionMismatch)
+        );
+        assert_eq!(
+            wallet.process_block(&empty, &[], &[vec![]]),
+            Err(WalletError::OutputIndices(0))
+        );
+        assert_eq!(wallet.height(), 1);
+    }
+
+    #[test]
+    fn test_spend_and_detach() {
+        let mut wallet = wallet();
+        for height in 0..3 {
+            let block = block(&wallet.address(), height, MONERO, &[]);
+            let indices = indices(&block, &[], height);
+            wallet.process_block(&block, &[], &indices).unwrap();
+        }
+        assert_eq!(wallet.unspent().count(), 3);
+
+        // spend the first output: only its key image matters to the wallet
+        let mut tx = payment(wallet.address(), MONERO);
+        let key_image = wallet.transfers()[0].key_image;
+        if let TxIn::ToKey(input) = &mut tx.prefix.vin[0] {
+            input.key_image = key_image;
+        }
+        let txs = [tx];
+        let block = block(
+            &Address::from_keys(&WalletKeys::generate(), Network::Mainnet),
+            3,
+            MONERO,
+            &txs,
+        );
+        let scan = wallet
+            .process_block(&block, &txs, &indices(&block, &txs, 3))
+            .unwrap();
+        assert_eq!(scan.spent, [0]);
+        assert_eq!(scan.received, [3]);
+        assert_eq!(wallet.transfers()[0].spent, Some((3, txs[0].hash())));
+        assert_eq!(
+            wallet
+                .transfer_by_key_image(&key_image)
+                .unwrap()
+                .output
+                .global_index,
+            0
+        );
+        assert_eq!(wallet.unspent().count(), 3);
+
+        wallet.detach(2);
+        assert_eq!(wallet.height(), 2);
+        assert_eq!(wallet.transfers().len(), 2);
+        assert!(!wallet.transfers()[0].is_spent());
+        assert_eq!(wallet.unspent().count(), 2);
+        assert_eq!(wallet.key_images.len(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_key_image() {
+        let mut wallet = wallet();
+        let first = block(&wallet.address(), 0, MONERO, &[]);
+        wallet
+            .process_block(&first, &[], &indices(&first, &[], 0))
+            .unwrap();
+        let key_image = wallet.transfers()[0].key_image;
+
+        // a miner tx reusing the tx key and output key pays to the same
+        // one-time key, so its output has the same key image
+        let address = wallet.address();
+        let reused = |height: u64, amount: u64| {
+            let mut block = block(&address, height, MONERO, &[]);
+            block.miner_tx = first.miner_tx.clone();
+            block.miner_tx.prefix.vin = vec![TxIn::Gen(TxInGen { height })];
+            block.miner_tx.prefix.vout[0].amount = amount;
+            block
+        };
+
+        // a smaller one is ignored
+        let smaller = reused(1, MONERO / 2);
+        let scan = wallet
+            .process_block(&smaller, &[], &indices(&smaller, &[], 1))
+            .unwrap();
+        assert!(scan.received.is_empty());
+        assert_eq!(wallet.transfers()[0].output.amount, MONERO);
+
+        // a larger one replaces it
+        let larger = reused(2, 3 * MONERO);
+        let scan = wallet
+            .process_block(&larger, &[], &indices(&larger, &[], 2))
+            .unwrap();
+        assert_eq!(scan.received, [0]);
+        assert_eq!(wallet.transfers().len(), 1);
+        let transfer = wallet.transfer_by_key_image(&key_image).unwrap();
+        assert_eq!(transfer.output.amount, 3 * MONERO);
+        assert_eq!(transfer.output.global_index, 2);
+        assert_eq!(transfer.lock.height, 2);
+
+        // undoing the block that paid it brings back the one it replaced
+        wallet.detach(2);
+        assert_eq!(wallet.transfers().len(), 1);
+        let transfer = wallet.transfer_by_key_image(&key_image).unwrap();
+        assert_eq!(transfer.output.amount, MONERO);
+        assert_eq!(transfer.lock.height, 0);
+        wallet.detach(0);
+        assert!(wallet.transfers().is_empty());
+    }
+
+    #[test]
+    fn test_balance() {
+        let mut wallet = wallet();
+        let stranger = Address::from_keys(&WalletKeys::generate(), Network::Mainnet);
+        let advance = |wallet: &mut Wallet, to: u64| {
+            while wallet.height() < to {
+                let block = block(&stranger, wallet.height(), MONERO, &[]);
+                let indices = indices(&block, &[], 1000 + wallet.height());
+                wallet.process_block(&block, &[], &indices).unwrap();
+            }
+        };
+        let now = 1_700_000_000;
+        let later = 1_800_000_000;
+        let mut txs = [
+            payment(wallet.address(), 3 * MONERO),
+            payment(
+                Address::subaddress(wallet.keys(), Network::Mainnet, 1, 2),
+                2 * MONERO,
+            ),
+            payment(
+                Address::subaddress(wallet.keys(), Network::Mainnet, 1, 0),
+                MONERO,
+            ),
+        ];
+        txs[1].prefix.unlock_time = 20;
+        txs[2].prefix.unlock_time = later;
+        let block = block(&wallet.address(), 0, 5 * MONERO, &txs);
+        wallet
+            .process_block(&block, &txs, &indices(&block, &txs, 0))
+            .unwrap();
+        let balance = wallet.balance_at(now);
+        assert_eq!((balance.total, balance.unlocked), (11 * MONERO, 0));
+        assert_eq!(balance.locked(), 11 * MONERO);
+
+        // the plain payment unlocks after ten blocks
+        advance(&mut wallet, 10);
+        assert_eq!(wallet.balance_at(now).unlocked, 3 * MONERO);
+
+        // then the one locked to a height
+        advance(&mut wallet, 20);
+        let balance = wallet.balance_at(now);
+        assert_eq!(balance.unlocked, 5 * MONERO);
+        let of = |total, unlocked| Balance { total, unlocked };
+        assert_eq!(balance.account(0), of(8 * MONERO, 3 * MONERO));
+        assert_eq!(balance.account(1), of(3 * MONERO, 2 * MONERO));
+        assert_eq!(balance.subaddress(1, 2), of(2 * MONERO, 2 * MONERO));
+        assert_eq!(balance.subaddress(1, 0), of(MONERO, 0));
+        assert_eq!(balance.subaddress(0, 1), Balance::default());
+        assert_eq!(balance.subaddresses.len(), 3);
+
+        // the one locked to a time, and last the coinbase at 60 blocks
+        assert_eq!(wallet.balance_at(later).unlocked, 6 * MONERO);
+        advance(&mut wallet, 59);
+        assert_eq!(wallet.balance_at(later).unlocked, 6 * MONERO);
+        advance(&mut wallet, 60);
+        assert_eq!(wallet.balance_at(later).unlocked, 11 * MONERO);
+
+        // spent outputs no longer count
+        wallet.transfers[0].spent = Some((60, Hash([0; 32])));
+        let balance = wallet.balance_at(later);
+        assert_eq!((balance.total, balance.unlocked), (6 * MONERO, 6 * MONERO));
+        assert_eq!(balance.account(0), of(3 * MONERO, 3 * MONERO));
+    }
+}